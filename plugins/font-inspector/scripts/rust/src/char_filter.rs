@@ -0,0 +1,135 @@
+use crate::char_names::{Unihan, UnihanEntry};
+use anyhow::{Result, bail};
+
+/// Unihan property a filter condition tests
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    /// `kTotalStrokes`
+    Strokes,
+    /// Kangxi radical number from `kRSUnicode`
+    Radical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    Ge,
+    Gt,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Condition {
+    field: Field,
+    op: Op,
+    value: u32,
+}
+
+/// Codepoint selection by Unihan metadata, e.g. `strokes<=8,radical=85`
+///
+/// Conditions separated by commas must all hold. Characters without the
+/// tested Unihan data never match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CharFilter {
+    conditions: Vec<Condition>,
+}
+
+impl CharFilter {
+    /// Parse a filter expression such as `strokes<=8` or `radical=85,strokes<12`
+    ///
+    /// # Errors
+    /// Returns error on unknown fields, operators or non-numeric values
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut conditions = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let split = part
+                .find(['<', '>', '=', '!'])
+                .ok_or_else(|| anyhow::anyhow!("Invalid filter condition: {}", part))?;
+            let (name, rest) = part.split_at(split);
+            let (op, value) = [("<=", Op::Le), (">=", Op::Ge), ("==", Op::Eq), ("!=", Op::Ne), ("<", Op::Lt), (">", Op::Gt), ("=", Op::Eq)]
+                .into_iter()
+                .find_map(|(token, op)| rest.strip_prefix(token).map(|v| (op, v)))
+                .ok_or_else(|| anyhow::anyhow!("Invalid operator in filter condition: {}", part))?;
+
+            let field = match name.trim().to_ascii_lowercase().as_str() {
+                "strokes" => Field::Strokes,
+                "radical" => Field::Radical,
+                other => bail!("Unknown filter field: {}. Valid fields: strokes, radical", other),
+            };
+            let value = value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Filter value must be a number: {}", part))?;
+
+            conditions.push(Condition { field, op, value });
+        }
+
+        if conditions.is_empty() {
+            bail!("Empty filter expression");
+        }
+        Ok(Self { conditions })
+    }
+
+    /// Whether a character with this Unihan entry passes every condition
+    pub fn matches(&self, entry: Option<&UnihanEntry>) -> bool {
+        let Some(entry) = entry else {
+            return false;
+        };
+
+        self.conditions.iter().all(|cond| {
+            let actual = match cond.field {
+                Field::Strokes => entry.total_strokes,
+                Field::Radical => entry.radical,
+            };
+            let Some(actual) = actual.map(u32::from) else {
+                return false;
+            };
+            match cond.op {
+                Op::Lt => actual < cond.value,
+                Op::Le => actual <= cond.value,
+                Op::Eq => actual == cond.value,
+                Op::Ne => actual != cond.value,
+                Op::Ge => actual >= cond.value,
+                Op::Gt => actual > cond.value,
+            }
+        })
+    }
+
+    /// Keep only the codepoints that pass the filter
+    pub fn retain(&self, codepoints: &mut Vec<u32>, unihan: &Unihan) {
+        codepoints.retain(|&cp| self.matches(unihan.get(cp)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(strokes: u8, radical: u8) -> UnihanEntry {
+        UnihanEntry {
+            total_strokes: Some(strokes),
+            radical: Some(radical),
+            mandarin: None,
+        }
+    }
+
+    #[test]
+    fn filter_should_apply_all_conditions() {
+        let filter = CharFilter::parse("strokes<=8, radical=9").unwrap();
+
+        assert!(filter.matches(Some(&entry(7, 9))));
+        assert!(!filter.matches(Some(&entry(9, 9))));
+        assert!(!filter.matches(Some(&entry(7, 85))));
+        assert!(!filter.matches(None));
+    }
+
+    #[test]
+    fn filter_should_reject_invalid_expressions() {
+        assert!(CharFilter::parse("").is_err());
+        assert!(CharFilter::parse("weight<3").is_err());
+        assert!(CharFilter::parse("strokes~3").is_err());
+        assert!(CharFilter::parse("strokes<=few").is_err());
+    }
+}
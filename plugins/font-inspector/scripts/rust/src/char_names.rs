@@ -0,0 +1,165 @@
+use crate::types::CharAnnotation;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Unihan properties kept for report annotation
+#[derive(Debug, Clone, Default)]
+pub struct UnihanEntry {
+    pub total_strokes: Option<u8>,
+    /// Kangxi radical number (1–214)
+    pub radical: Option<u8>,
+    pub mandarin: Option<String>,
+}
+
+/// Subset of the Unihan database, keyed by codepoint
+#[derive(Debug, Default)]
+pub struct Unihan {
+    entries: HashMap<u32, UnihanEntry>,
+}
+
+impl Unihan {
+    /// Load Unihan data from a `Unihan_*.txt` file or a directory of them
+    ///
+    /// Only `kTotalStrokes`, `kRSUnicode` and `kMandarin` are kept; all
+    /// other fields are skipped while parsing.
+    ///
+    /// # Errors
+    /// Returns error if the path cannot be read
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut unihan = Self::default();
+        if path.is_dir() {
+            let mut files: Vec<_> = fs::read_dir(path)
+                .with_context(|| format!("Failed to read Unihan directory {:?}", path))?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "txt"))
+                .collect();
+            files.sort();
+            for file in files {
+                unihan.parse_file(&file)?;
+            }
+        } else {
+            unihan.parse_file(path)?;
+        }
+        Ok(unihan)
+    }
+
+    fn parse_file(&mut self, path: &Path) -> Result<()> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read Unihan file {:?}", path))?;
+        for line in text.lines() {
+            self.parse_line(line);
+        }
+        Ok(())
+    }
+
+    /// Parse one `U+4E00<TAB>kField<TAB>value` line
+    fn parse_line(&mut self, line: &str) {
+        let mut fields = line.splitn(3, '\t');
+        let (Some(cp), Some(field), Some(value)) = (fields.next(), fields.next(), fields.next()) else {
+            return;
+        };
+        let Some(cp) = cp.strip_prefix("U+").and_then(|hex| u32::from_str_radix(hex, 16).ok()) else {
+            return;
+        };
+
+        match field {
+            "kTotalStrokes" => {
+                // Multiple values list the preferred (zh-Hans) count first
+                let strokes = value.split_whitespace().next().and_then(|v| v.parse().ok());
+                self.entries.entry(cp).or_default().total_strokes = strokes;
+            }
+            "kRSUnicode" => {
+                // `85.5`, or `85'.5` for simplified-form radicals; first value wins
+                let radical = value
+                    .split_whitespace()
+                    .next()
+                    .and_then(|rs| rs.split('.').next())
+                    .and_then(|r| r.trim_end_matches('\'').parse().ok());
+                self.entries.entry(cp).or_default().radical = radical;
+            }
+            "kMandarin" => {
+                self.entries.entry(cp).or_default().mandarin = Some(value.trim().to_string());
+            }
+            _ => {}
+        }
+    }
+
+    pub fn get(&self, codepoint: u32) -> Option<&UnihanEntry> {
+        self.entries.get(&codepoint)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Unicode character name (e.g. `LATIN CAPITAL LETTER A`)
+pub fn char_name(c: char) -> Option<String> {
+    unicode_names2::name(c).map(|name| name.to_string())
+}
+
+/// Build the report annotation for a character
+///
+/// # Returns
+/// `None` if neither a name nor any Unihan data is known
+pub fn annotate(c: char, unihan: Option<&Unihan>) -> Option<CharAnnotation> {
+    let entry = unihan.and_then(|db| db.get(c as u32));
+    let annotation = CharAnnotation {
+        name: char_name(c),
+        total_strokes: entry.and_then(|e| e.total_strokes),
+        radical: entry.and_then(|e| e.radical),
+        mandarin: entry.and_then(|e| e.mandarin.clone()),
+    };
+
+    if annotation.name.is_none()
+        && annotation.total_strokes.is_none()
+        && annotation.radical.is_none()
+        && annotation.mandarin.is_none()
+    {
+        None
+    } else {
+        Some(annotation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unihan_should_parse_strokes_and_readings() {
+        let mut unihan = Unihan::default();
+        unihan.parse_line("# comment");
+        unihan.parse_line("U+4F60\tkTotalStrokes\t7");
+        unihan.parse_line("U+4F60\tkMandarin\tnǐ");
+        unihan.parse_line("U+4F60\tkDefinition\tyou, second person pronoun");
+        unihan.parse_line("U+5F7C\tkTotalStrokes\t8 9");
+        unihan.parse_line("U+4F60\tkRSUnicode\t9.5");
+        unihan.parse_line("U+8BF4\tkRSUnicode\t149'.7");
+
+        assert_eq!(unihan.len(), 3);
+        assert_eq!(unihan.get(0x8BF4).unwrap().radical, Some(149));
+        let entry = unihan.get(0x4F60).unwrap();
+        assert_eq!(entry.total_strokes, Some(7));
+        assert_eq!(entry.mandarin.as_deref(), Some("nǐ"));
+        assert_eq!(entry.radical, Some(9));
+        assert_eq!(unihan.get(0x5F7C).unwrap().total_strokes, Some(8));
+    }
+
+    #[test]
+    fn annotate_should_merge_name_and_unihan() {
+        let mut unihan = Unihan::default();
+        unihan.parse_line("U+4F60\tkTotalStrokes\t7");
+
+        let annotation = annotate('你', Some(&unihan)).unwrap();
+        assert_eq!(annotation.name.as_deref(), Some("CJK UNIFIED IDEOGRAPH-4F60"));
+        assert_eq!(annotation.total_strokes, Some(7));
+        assert!(annotation.mandarin.is_none());
+    }
+}
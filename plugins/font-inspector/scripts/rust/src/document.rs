@@ -0,0 +1,523 @@
+use anyhow::{Context, Result, bail};
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Encoding, Object};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use ttf_parser::Face;
+use zip::ZipArchive;
+
+/// Family used for EPUB text without any `font-family` rule
+pub const DEFAULT_FAMILY: &str = "(default)";
+
+/// Characters a document sets in one declared font
+#[derive(Debug, Default)]
+pub struct FontUsage {
+    pub chars: BTreeSet<char>,
+    /// Embedded font file (EPUB `@font-face` source, PDF `FontFile*` stream)
+    pub embedded: Option<String>,
+    /// Text runs whose bytes could not be mapped to Unicode
+    pub undecodable_runs: usize,
+}
+
+/// Document format detected from the file signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentKind {
+    Pdf,
+    Epub,
+}
+
+/// Coverage of one declared font's characters by the candidate font
+#[derive(Debug, Serialize)]
+pub struct FontCoverage {
+    pub font: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedded: Option<String>,
+    pub characters: usize,
+    pub covered: usize,
+    /// `U+XXXX c` for each character the candidate lacks
+    pub missing: Vec<String>,
+    pub undecodable_runs: usize,
+    pub can_replace: bool,
+}
+
+/// Answer to "can this font replace the fonts used in this document"
+#[derive(Debug, Serialize)]
+pub struct DocumentCoverage {
+    pub document: String,
+    pub kind: DocumentKind,
+    pub candidate: String,
+    pub fonts: Vec<FontCoverage>,
+    pub can_replace_all: bool,
+}
+
+/// Collect the characters used per declared font in a PDF or EPUB
+///
+/// # Arguments
+/// * `path` - PDF or EPUB file, detected by signature
+///
+/// # Returns
+/// The document kind and a map from font name (PDF `BaseFont` without the
+/// subset prefix, EPUB CSS `font-family`) to its usage
+///
+/// # Errors
+/// Returns error if the file is neither a PDF nor an EPUB or cannot be parsed
+pub fn extract_usage(path: &Path) -> Result<(DocumentKind, BTreeMap<String, FontUsage>)> {
+    let data = fs::read(path).with_context(|| format!("Failed to read document: {}", path.display()))?;
+    if data.starts_with(b"%PDF") {
+        Ok((DocumentKind::Pdf, pdf_usage(&data)?))
+    } else if data.starts_with(b"PK") {
+        Ok((DocumentKind::Epub, epub_usage(&data)?))
+    } else {
+        bail!("{} is neither a PDF nor an EPUB", path.display())
+    }
+}
+
+/// Check every declared font's characters against the candidate's cmap
+pub fn check_coverage(usage: &BTreeMap<String, FontUsage>, candidate: &Face) -> Vec<FontCoverage> {
+    usage
+        .iter()
+        .map(|(font, usage)| {
+            let missing: Vec<String> = usage
+                .chars
+                .iter()
+                .filter(|&&c| candidate.glyph_index(c).is_none())
+                .map(|&c| format!("U+{:04X} {}", c as u32, c))
+                .collect();
+            FontCoverage {
+                font: font.clone(),
+                embedded: usage.embedded.clone(),
+                characters: usage.chars.len(),
+                covered: usage.chars.len() - missing.len(),
+                // Unmapped runs may hide uncovered characters, so they block the verdict
+                can_replace: missing.is_empty() && usage.undecodable_runs == 0,
+                missing,
+                undecodable_runs: usage.undecodable_runs,
+            }
+        })
+        .collect()
+}
+
+fn add_text(usage: &mut FontUsage, text: &str) {
+    usage.chars.extend(text.chars().filter(|c| !c.is_control() && *c != '\u{FFFD}'));
+}
+
+fn pdf_usage(data: &[u8]) -> Result<BTreeMap<String, FontUsage>> {
+    let doc = Document::load_mem(data).context("Failed to parse PDF")?;
+    let mut usage: BTreeMap<String, FontUsage> = BTreeMap::new();
+
+    for page_id in doc.get_pages().into_values() {
+        let fonts = doc.get_page_fonts(page_id).context("Failed to read page fonts")?;
+        let mut decoders: HashMap<Vec<u8>, (String, Option<Encoding>, bool)> = HashMap::new();
+        for (resource, font) in fonts {
+            let name = pdf_font_name(font);
+            let entry = usage.entry(name.clone()).or_default();
+            if entry.embedded.is_none() {
+                entry.embedded = pdf_embedded_file(&doc, font);
+            }
+            let composite = font.get(b"Subtype").and_then(Object::as_name_str).is_ok_and(|s| s == "Type0");
+            decoders.insert(resource, (name, font.get_font_encoding(&doc).ok(), composite));
+        }
+
+        let content = doc
+            .get_page_content(page_id)
+            .ok()
+            .and_then(|raw| Content::decode(&raw).ok());
+        let Some(content) = content else {
+            continue;
+        };
+
+        let mut current = None;
+        for operation in &content.operations {
+            match operation.operator.as_str() {
+                "Tf" => {
+                    current = operation.operands.first().and_then(|o| o.as_name().ok()).and_then(|n| decoders.get(n));
+                }
+                "Tj" | "TJ" | "'" | "\"" => {
+                    let Some((name, encoding, composite)) = current else {
+                        continue;
+                    };
+                    let usage = usage.entry(name.clone()).or_default();
+                    let mut strings = Vec::new();
+                    collect_strings(&operation.operands, &mut strings);
+                    for bytes in strings {
+                        match encoding.as_ref().and_then(|e| decode_pdf_string(e, bytes, *composite)) {
+                            Some(text) => add_text(usage, &text),
+                            None => usage.undecodable_runs += 1,
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    usage.retain(|_, u| !u.chars.is_empty() || u.undecodable_runs > 0);
+    Ok(usage)
+}
+
+fn collect_strings<'a>(operands: &'a [Object], out: &mut Vec<&'a [u8]>) {
+    for operand in operands {
+        match operand {
+            Object::String(bytes, _) => out.push(bytes),
+            Object::Array(items) => collect_strings(items, out),
+            _ => {}
+        }
+    }
+}
+
+fn decode_pdf_string(encoding: &Encoding, bytes: &[u8], composite: bool) -> Option<String> {
+    match encoding {
+        // ToUnicode maps of simple fonts key one-byte codes; lopdf reads two
+        Encoding::UnicodeMapEncoding(_) if !composite => {
+            let widened: Vec<u8> = bytes.iter().flat_map(|&b| [0, b]).collect();
+            encoding.bytes_to_string(&widened).ok()
+        }
+        _ => encoding.bytes_to_string(bytes).ok(),
+    }
+}
+
+/// `BaseFont` without the six-letter subset prefix (`ABCDEF+Foo` → `Foo`)
+fn pdf_font_name(font: &Dictionary) -> String {
+    let base = font
+        .get(b"BaseFont")
+        .and_then(Object::as_name_str)
+        .unwrap_or("(unnamed)");
+    match base.split_once('+') {
+        Some((prefix, rest)) if prefix.len() == 6 && prefix.bytes().all(|b| b.is_ascii_uppercase()) => rest.to_string(),
+        _ => base.to_string(),
+    }
+}
+
+/// Kind of embedded font program, looking through `DescendantFonts` for Type0
+fn pdf_embedded_file(doc: &Document, font: &Dictionary) -> Option<String> {
+    let descriptor_owner = match font.get(b"DescendantFonts").ok().map(|o| doc.dereference(o)) {
+        Some(Ok((_, Object::Array(descendants)))) => {
+            let first = descendants.first()?;
+            doc.dereference(first).ok()?.1.as_dict().ok()?
+        }
+        _ => font,
+    };
+    let descriptor = doc.dereference(descriptor_owner.get(b"FontDescriptor").ok()?).ok()?.1.as_dict().ok()?;
+    ["FontFile", "FontFile2", "FontFile3"]
+        .into_iter()
+        .find(|key| descriptor.has(key.as_bytes()))
+        .map(|key| format!("embedded {}", key))
+}
+
+/// `selector { font-family }` rule reduced to its last compound selector
+#[derive(Debug, Clone, PartialEq)]
+struct FamilyRule {
+    tag: Option<String>,
+    classes: Vec<String>,
+    id: Option<String>,
+    family: String,
+    order: usize,
+}
+
+impl FamilyRule {
+    fn specificity(&self) -> (usize, usize, usize, usize) {
+        (usize::from(self.id.is_some()), self.classes.len(), usize::from(self.tag.is_some()), self.order)
+    }
+
+    fn matches(&self, node: roxmltree::Node) -> bool {
+        let tag_ok = self.tag.as_deref().is_none_or(|t| t.eq_ignore_ascii_case(node.tag_name().name()));
+        let id_ok = self.id.as_deref().is_none_or(|id| node.attribute("id") == Some(id));
+        let node_classes: Vec<&str> = node.attribute("class").unwrap_or_default().split_whitespace().collect();
+        tag_ok && id_ok && self.classes.iter().all(|c| node_classes.contains(&c.as_str()))
+    }
+}
+
+/// Font families and `@font-face` sources declared by a stylesheet
+#[derive(Debug, Default)]
+struct Stylesheet {
+    rules: Vec<FamilyRule>,
+    /// family → `src: url(...)` of its `@font-face`
+    faces: BTreeMap<String, String>,
+}
+
+impl Stylesheet {
+    fn parse(css: &str, base: &str) -> Self {
+        let mut sheet = Self::default();
+        sheet.add(&strip_comments(css), base);
+        sheet
+    }
+
+    fn add(&mut self, css: &str, base: &str) {
+        let mut rest = css;
+        while let Some(open) = rest.find('{') {
+            let prelude = rest[..open].trim();
+            let Some(close) = matching_brace(&rest[open..]).map(|i| open + i) else {
+                break;
+            };
+            let body = &rest[open + 1..close];
+            rest = &rest[close + 1..];
+
+            if prelude.eq_ignore_ascii_case("@font-face") {
+                let decls = declarations(body);
+                if let (Some(family), Some(src)) = (decls.get("font-family"), decls.get("src")) {
+                    let family = first_family(family);
+                    if let Some(url) = css_url(src) {
+                        self.faces.insert(family, resolve_href(base, &url));
+                    }
+                }
+            } else if prelude.starts_with("@media") || prelude.starts_with("@supports") {
+                self.add(body, base);
+            } else if !prelude.starts_with('@')
+                && let Some(family) = declarations(body).get("font-family").map(|f| first_family(f))
+                && family != "inherit"
+            {
+                for selector in prelude.split(',') {
+                    if let Some(mut rule) = parse_selector(selector) {
+                        rule.family = family.clone();
+                        rule.order = self.rules.len();
+                        self.rules.push(rule);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        rest = rest[start + 2..].find("*/").map_or("", |end| &rest[start + 2 + end + 2..]);
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Index of the `}` closing the `{` at the start of `s`
+fn matching_brace(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn declarations(body: &str) -> HashMap<String, String> {
+    body.split(';')
+        .filter_map(|decl| decl.split_once(':'))
+        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().trim_end_matches("!important").trim().to_string()))
+        .collect()
+}
+
+/// First entry of a `font-family` list, unquoted
+fn first_family(value: &str) -> String {
+    value.split(',').next().unwrap_or_default().trim().trim_matches(['"', '\'']).to_string()
+}
+
+fn css_url(src: &str) -> Option<String> {
+    let start = src.find("url(")? + 4;
+    let end = start + src[start..].find(')')?;
+    Some(src[start..end].trim().trim_matches(['"', '\'']).to_string())
+}
+
+/// Last compound of a selector (`div p.note` → `p.note`); `None` when unsupported
+fn parse_selector(selector: &str) -> Option<FamilyRule> {
+    let compound = selector.split_whitespace().last()?;
+    if compound.contains(['[', ':', '>', '+', '~']) {
+        return None;
+    }
+    let mut rule = FamilyRule { tag: None, classes: Vec::new(), id: None, family: String::new(), order: 0 };
+    let mut token = String::new();
+    let mut kind = ' ';
+    for c in compound.chars().chain(std::iter::once('.')) {
+        if c == '.' || c == '#' {
+            match kind {
+                ' ' if !token.is_empty() && token != "*" => rule.tag = Some(token.clone()),
+                '.' => rule.classes.push(token.clone()),
+                '#' => rule.id = Some(token.clone()),
+                _ => {}
+            }
+            token.clear();
+            kind = c;
+        } else {
+            token.push(c);
+        }
+    }
+    Some(rule)
+}
+
+/// Resolve `href` relative to the directory of the archive entry `base`
+fn resolve_href(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+    let mut parts: Vec<&str> = base.split('/').collect();
+    parts.pop();
+    for segment in href.split('/') {
+        match segment {
+            "." | "" => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(segment),
+        }
+    }
+    parts.join("/")
+}
+
+fn read_entry(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<String> {
+    let mut entry = archive.by_name(name).with_context(|| format!("EPUB entry missing: {}", name))?;
+    let mut text = String::new();
+    entry.read_to_string(&mut text).with_context(|| format!("EPUB entry is not UTF-8: {}", name))?;
+    Ok(text)
+}
+
+fn parse_xml(text: &str) -> Result<roxmltree::Document<'_>> {
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    Ok(roxmltree::Document::parse_with_options(text, options)?)
+}
+
+fn epub_usage(data: &[u8]) -> Result<BTreeMap<String, FontUsage>> {
+    let mut archive = ZipArchive::new(Cursor::new(data)).context("Failed to open EPUB archive")?;
+
+    let container = read_entry(&mut archive, "META-INF/container.xml")?;
+    let opf_path = parse_xml(&container)?
+        .descendants()
+        .find(|n| n.has_tag_name("rootfile"))
+        .and_then(|n| n.attribute("full-path"))
+        .context("container.xml has no rootfile")?
+        .to_string();
+
+    let opf = read_entry(&mut archive, &opf_path)?;
+    let opf = parse_xml(&opf)?;
+    let mut manifest = HashMap::new();
+    let mut stylesheets = Vec::new();
+    for item in opf.descendants().filter(|n| n.has_tag_name("item")) {
+        let (Some(id), Some(href)) = (item.attribute("id"), item.attribute("href")) else {
+            continue;
+        };
+        let path = resolve_href(&opf_path, href);
+        if item.attribute("media-type") == Some("text/css") {
+            stylesheets.push(path.clone());
+        }
+        manifest.insert(id.to_string(), path);
+    }
+    let spine: Vec<String> = opf
+        .descendants()
+        .filter(|n| n.has_tag_name("itemref"))
+        .filter_map(|n| n.attribute("idref").and_then(|id| manifest.get(id)).cloned())
+        .collect();
+
+    let mut sheet = Stylesheet::default();
+    for path in &stylesheets {
+        let css = read_entry(&mut archive, path)?;
+        let parsed = Stylesheet::parse(&css, path);
+        sheet.faces.extend(parsed.faces);
+        merge_rules(&mut sheet, parsed.rules);
+    }
+
+    let mut usage: BTreeMap<String, FontUsage> = BTreeMap::new();
+    for path in &spine {
+        let text = read_entry(&mut archive, path)?;
+        let doc = parse_xml(&text).with_context(|| format!("Invalid XHTML: {}", path))?;
+        xhtml_usage(&doc, path, &sheet, &mut usage);
+    }
+
+    for (family, usage) in usage.iter_mut() {
+        usage.embedded = sheet.faces.get(family).cloned();
+    }
+    Ok(usage)
+}
+
+fn merge_rules(sheet: &mut Stylesheet, rules: Vec<FamilyRule>) {
+    for mut rule in rules {
+        rule.order = sheet.rules.len();
+        sheet.rules.push(rule);
+    }
+}
+
+/// Attribute text characters to the `font-family` in effect for each element
+fn xhtml_usage(doc: &roxmltree::Document, path: &str, linked: &Stylesheet, usage: &mut BTreeMap<String, FontUsage>) {
+    // Document-level <style> blocks cascade after the linked stylesheets
+    let mut sheet = Stylesheet { rules: linked.rules.clone(), faces: BTreeMap::new() };
+    for style in doc.descendants().filter(|n| n.has_tag_name("style")) {
+        let parsed = Stylesheet::parse(style.text().unwrap_or_default(), path);
+        merge_rules(&mut sheet, parsed.rules);
+    }
+
+    fn walk(node: roxmltree::Node, family: &str, sheet: &Stylesheet, usage: &mut BTreeMap<String, FontUsage>) {
+        for child in node.children() {
+            if child.is_text() {
+                if let Some(text) = child.text()
+                    && !text.trim().is_empty()
+                {
+                    add_text(usage.entry(family.to_string()).or_default(), text.trim());
+                }
+                continue;
+            }
+            if !child.is_element() || matches!(child.tag_name().name(), "head" | "script" | "style") {
+                continue;
+            }
+            let inline = child
+                .attribute("style")
+                .and_then(|s| declarations(s).get("font-family").map(|f| first_family(f)))
+                .filter(|f| f != "inherit");
+            let ruled = sheet
+                .rules
+                .iter()
+                .filter(|r| r.matches(child))
+                .max_by_key(|r| r.specificity())
+                .map(|r| r.family.clone());
+            let own = inline.or(ruled);
+            walk(child, own.as_deref().unwrap_or(family), sheet, usage);
+        }
+    }
+    walk(doc.root(), DEFAULT_FAMILY, &sheet, usage);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stylesheet_should_parse_faces_and_rules() {
+        let css = r#"
+            /* body text */
+            @font-face { font-family: "Source Han Serif"; src: url("../fonts/shs.otf"); }
+            body { font-family: "Source Han Serif", serif; }
+            @media screen { h1.title, #cover { font-family: 'Display'; } }
+            a:hover { font-family: Ignored; }
+        "#;
+        let sheet = Stylesheet::parse(css, "OEBPS/css/style.css");
+
+        assert_eq!(sheet.faces.get("Source Han Serif").map(String::as_str), Some("OEBPS/fonts/shs.otf"));
+        assert_eq!(sheet.rules.len(), 3);
+        assert_eq!(sheet.rules[0].tag.as_deref(), Some("body"));
+        assert_eq!(sheet.rules[1].classes, vec!["title"]);
+        assert_eq!(sheet.rules[2].id.as_deref(), Some("cover"));
+        assert!(sheet.rules.iter().all(|r| r.family != "Ignored"));
+    }
+
+    #[test]
+    fn xhtml_usage_should_follow_the_cascade() {
+        let sheet = Stylesheet::parse("body { font-family: Serif } .note { font-family: Sans }", "a.css");
+        let xhtml = r#"<html xmlns="http://www.w3.org/1999/xhtml"><head><title>T</title></head>
+            <body><p>你好</p><p class="note">AB<span style="font-family: Mono">x</span></p></body></html>"#;
+        let doc = parse_xml(xhtml).unwrap();
+        let mut usage = BTreeMap::new();
+        xhtml_usage(&doc, "text/ch1.xhtml", &sheet, &mut usage);
+
+        let chars = |family: &str| usage.get(family).map(|u: &FontUsage| u.chars.iter().collect::<String>());
+        assert_eq!(chars("Serif").as_deref(), Some("你好"));
+        assert_eq!(chars("Sans").as_deref(), Some("AB"));
+        assert_eq!(chars("Mono").as_deref(), Some("x"));
+        assert!(!usage.contains_key(DEFAULT_FAMILY));
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use ttf_parser::{Face, GlyphId};
+
+use crate::extractor;
+use crate::types::{ExtractOptions, GlyphInfo};
+
+/// Entry naming the glyph every font has at index 0
+const NOTDEF: &str = ".notdef";
+
+/// Distribution rules an export must meet whatever the selection was
+///
+/// ```toml
+/// # Exported even when the selection leaves them out
+/// require = [".notdef", "U+0020", "U+3000-U+303F", "「」"]
+/// # Never exported; wins over `require`
+/// exclude = ["U+E000-U+F8FF", "U+4E28"]
+/// ```
+///
+/// Entries are `U+XXXX`, ranges `U+XXXX-U+YYYY`, or literal characters.
+/// Required single characters must be in the font; required ranges take
+/// whatever part of them the font maps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportPolicy {
+    /// Policy file, for the report
+    pub source: String,
+    require: Vec<Rule>,
+    exclude: Vec<Rule>,
+    notdef: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rule {
+    Char(u32),
+    Range(u32, u32),
+}
+
+impl Rule {
+    fn contains(self, cp: u32) -> bool {
+        match self {
+            Self::Char(c) => c == cp,
+            Self::Range(start, end) => (start..=end).contains(&cp),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default)]
+    require: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+/// What a policy changed in one export
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PolicyReport {
+    pub policy: String,
+    /// Required characters the selection did not include
+    pub added: Vec<String>,
+    /// Selected characters the policy excludes
+    pub removed: Vec<String>,
+    /// Required characters the font does not map; an export fails while any remain
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>,
+    /// Required characters without an outline (spaces), exported as empty glyphs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blank: Vec<String>,
+    /// `.notdef` was added as the first glyph
+    #[serde(default)]
+    pub notdef: bool,
+}
+
+fn key(cp: u32) -> String {
+    format!("U+{:04X}", cp)
+}
+
+fn parse_codepoint(s: &str) -> Result<u32> {
+    let hex = s.trim().strip_prefix("U+").with_context(|| format!("Expected U+XXXX, got {}", s))?;
+    let cp = u32::from_str_radix(hex, 16).with_context(|| format!("Invalid codepoint: {}", s))?;
+    char::from_u32(cp).with_context(|| format!("Not a Unicode scalar value: {}", s))?;
+    Ok(cp)
+}
+
+fn parse_rules(entries: &[String], allow_notdef: bool, notdef: &mut bool) -> Result<Vec<Rule>> {
+    let mut rules = Vec::new();
+    for entry in entries {
+        if entry == NOTDEF {
+            if !allow_notdef {
+                bail!("{} can only be required; exports leave it out unless a policy adds it", NOTDEF);
+            }
+            *notdef = true;
+        } else if entry.starts_with("U+") {
+            match entry.split_once('-') {
+                Some((start, end)) => {
+                    let end = if end.starts_with("U+") { end.to_string() } else { format!("U+{}", end) };
+                    let (start, end) = (parse_codepoint(start)?, parse_codepoint(&end)?);
+                    if start > end {
+                        bail!("Invalid range {}: start must be <= end", entry);
+                    }
+                    rules.push(Rule::Range(start, end));
+                }
+                None => rules.push(Rule::Char(parse_codepoint(entry)?)),
+            }
+        } else if entry.is_empty() {
+            bail!("Empty policy entry");
+        } else {
+            rules.extend(entry.chars().map(|c| Rule::Char(c as u32)));
+        }
+    }
+    Ok(rules)
+}
+
+/// Every codepoint the font's Unicode cmap subtables map
+fn mapped_codepoints(face: &Face) -> BTreeSet<u32> {
+    let mut mapped = BTreeSet::new();
+    if let Some(cmap) = face.tables().cmap {
+        for subtable in cmap.subtables.into_iter().filter(|st| st.is_unicode()) {
+            subtable.codepoints(|cp| {
+                mapped.insert(cp);
+            });
+        }
+    }
+    mapped
+}
+
+/// A glyph exported without outline data
+fn blank_glyph(face: &Face, glyph_id: GlyphId, glyph_name: String, unicode: Option<char>) -> GlyphInfo {
+    GlyphInfo {
+        glyph_name,
+        unicode: unicode.map(|c| key(c as u32)).unwrap_or_default(),
+        unicode_char: unicode.map(String::from).unwrap_or_default(),
+        svg_path: String::new(),
+        advance_width: face.glyph_hor_advance(glyph_id).unwrap_or(0),
+        bounding_box: None,
+        contour_count: 0,
+        point_count: 0,
+        flipped_contours: None,
+        annotation: None,
+        outline_metrics: None,
+        review: None,
+    }
+}
+
+impl ExportPolicy {
+    /// # Errors
+    /// Returns error if the file cannot be read or holds an invalid entry
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read policy: {}", path.display()))?;
+        Self::parse(&text, &path.display().to_string())
+            .with_context(|| format!("Invalid policy: {}", path.display()))
+    }
+
+    /// # Errors
+    /// Returns error on malformed TOML, unknown keys or invalid entries
+    pub fn parse(text: &str, source: &str) -> Result<Self> {
+        let file: PolicyFile = toml::from_str(text)?;
+        let mut notdef = false;
+        let require = parse_rules(&file.require, true, &mut notdef)?;
+        let exclude = parse_rules(&file.exclude, false, &mut notdef)?;
+        Ok(Self { source: source.to_string(), require, exclude, notdef })
+    }
+
+    fn excludes(&self, cp: u32) -> bool {
+        self.exclude.iter().any(|rule| rule.contains(cp))
+    }
+
+    /// Enforce the policy on a selection of codepoints
+    ///
+    /// Excluded codepoints are dropped and required ones the font maps are
+    /// appended. Also returns the glyphs extraction cannot produce: `.notdef`
+    /// and required characters without an outline, which the caller puts in
+    /// front of the extracted glyphs.
+    ///
+    /// # Arguments
+    /// * `face` - Parsed font face
+    /// * `codepoints` - Selection to adjust in place
+    /// * `options` - Outline options for `.notdef`
+    pub fn apply(
+        &self,
+        face: &Face,
+        codepoints: &mut Vec<u32>,
+        options: &ExtractOptions,
+    ) -> (PolicyReport, Vec<GlyphInfo>) {
+        let mut report = PolicyReport { policy: self.source.clone(), ..Default::default() };
+
+        codepoints.retain(|&cp| {
+            let keep = !self.excludes(cp);
+            if !keep {
+                report.removed.push(key(cp));
+            }
+            keep
+        });
+
+        let mapped = mapped_codepoints(face);
+        let selected: BTreeSet<u32> = codepoints.iter().copied().collect();
+        let mut required = BTreeSet::new();
+        for rule in &self.require {
+            match *rule {
+                Rule::Char(cp) if !mapped.contains(&cp) => {
+                    if !self.excludes(cp) && !report.missing.contains(&key(cp)) {
+                        report.missing.push(key(cp));
+                    }
+                }
+                Rule::Char(cp) => {
+                    required.insert(cp);
+                }
+                Rule::Range(start, end) => required.extend(mapped.range(start..=end)),
+            }
+        }
+        required.retain(|&cp| !self.excludes(cp));
+        for &cp in required.difference(&selected) {
+            codepoints.push(cp);
+            report.added.push(key(cp));
+        }
+
+        let mut extra = Vec::new();
+        if self.notdef {
+            let notdef = GlyphId(0);
+            let mut glyph = extractor::extract_glyph_with(face, notdef, '\0', options)
+                .unwrap_or_else(|| blank_glyph(face, notdef, String::new(), None));
+            glyph.glyph_name = NOTDEF.to_string();
+            glyph.unicode = String::new();
+            glyph.unicode_char = String::new();
+            glyph.annotation = None;
+            glyph.review = None;
+            extra.push(glyph);
+            report.notdef = true;
+        }
+        for c in required.iter().filter_map(|&cp| char::from_u32(cp)) {
+            let Some(gid) = face.glyph_index(c) else {
+                continue;
+            };
+            if extractor::glyph_outline(face, gid).is_none_or(|path| path.elements().is_empty()) {
+                extra.push(blank_glyph(face, gid, format!("uni{:04X}", c as u32), Some(c)));
+                report.blank.push(key(c as u32));
+            }
+        }
+        (report, extra)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_should_parse_codepoints_ranges_and_characters() {
+        let policy = ExportPolicy::parse(
+            r#"
+            require = [".notdef", "U+0020", "U+3000-303F", "「」"]
+            exclude = ["U+E000-U+F8FF"]
+            "#,
+            "dist.toml",
+        )
+        .unwrap();
+        assert!(policy.notdef);
+        assert_eq!(
+            policy.require,
+            vec![Rule::Char(0x20), Rule::Range(0x3000, 0x303F), Rule::Char(0x300C), Rule::Char(0x300D)]
+        );
+        assert!(policy.excludes(0xE123) && !policy.excludes(0x20));
+    }
+
+    #[test]
+    fn policy_should_reject_bad_entries() {
+        assert!(ExportPolicy::parse(r#"exclude = [".notdef"]"#, "p").is_err());
+        assert!(ExportPolicy::parse(r#"require = ["U+3040-U+3000"]"#, "p").is_err());
+        assert!(ExportPolicy::parse(r#"require = ["U+D800"]"#, "p").is_err());
+        assert!(ExportPolicy::parse(r#"allow = ["A"]"#, "p").is_err());
+    }
+}
@@ -1,13 +1,17 @@
+use kurbo::BezPath;
 use ttf_parser::{Face, GlyphId, OutlineBuilder};
-use crate::types::{BBox, GlyphInfo};
+use crate::char_names;
+use crate::outline;
+use crate::path_optimizer::{self, PathOptimizeOptions};
+use crate::review;
+use crate::types::{BBox, ExtractOptions, GlyphInfo};
 
 /// SVG path builder implementing ttf-parser's OutlineBuilder trait
 ///
-/// Converts font outline commands to SVG path data format.
-/// Note: Y-axis is flipped because font coordinate system has Y pointing up,
-/// while SVG has Y pointing down.
+/// Collects font outline commands in font units; the SVG path data is
+/// rendered (with the Y-axis flip) once the outline is complete.
 struct SvgPathBuilder {
-    path: String,
+    path: BezPath,
     contour_count: usize,
     point_count: usize,
 }
@@ -15,55 +19,62 @@ struct SvgPathBuilder {
 impl SvgPathBuilder {
     fn new() -> Self {
         Self {
-            path: String::with_capacity(256), // Pre-allocate for typical glyph
+            path: BezPath::new(),
             contour_count: 0,
             point_count: 0,
         }
     }
 
-    fn finish(self) -> (String, usize, usize) {
-        (self.path, self.contour_count, self.point_count)
+    fn finish(self, optimize: Option<&PathOptimizeOptions>) -> (String, usize, usize) {
+        let svg_path = match optimize {
+            Some(opts) => path_optimizer::optimize_svg_path(&self.path, opts),
+            None => outline::to_svg_path(&self.path),
+        };
+        (svg_path, self.contour_count, self.point_count)
     }
 }
 
 impl OutlineBuilder for SvgPathBuilder {
     fn move_to(&mut self, x: f32, y: f32) {
-        use std::fmt::Write;
-        // Y-axis flip: font coordinates have Y up, SVG has Y down
-        let _ = write!(self.path, "M {:.2} {:.2} ", x, -y);
+        self.path.move_to((x as f64, y as f64));
         self.contour_count += 1;
         self.point_count += 1;
     }
 
     fn line_to(&mut self, x: f32, y: f32) {
-        use std::fmt::Write;
-        let _ = write!(self.path, "L {:.2} {:.2} ", x, -y);
+        self.path.line_to((x as f64, y as f64));
         self.point_count += 1;
     }
 
     fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
-        use std::fmt::Write;
-        // TrueType quadratic Bézier → SVG Q command
-        let _ = write!(self.path, "Q {:.2} {:.2} {:.2} {:.2} ", x1, -y1, x, -y);
+        self.path.quad_to((x1 as f64, y1 as f64), (x as f64, y as f64));
         self.point_count += 2;
     }
 
     fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
-        use std::fmt::Write;
-        // CFF cubic Bézier → SVG C command
-        let _ = write!(
-            self.path,
-            "C {:.2} {:.2} {:.2} {:.2} {:.2} {:.2} ",
-            x1, -y1, x2, -y2, x, -y
+        self.path.curve_to(
+            (x1 as f64, y1 as f64),
+            (x2 as f64, y2 as f64),
+            (x as f64, y as f64),
         );
         self.point_count += 3;
     }
 
     fn close(&mut self) {
-        self.path.push_str("Z ");
+        self.path.close_path();
     }
 }
 
+/// Outline of a glyph in font units (Y up), without SVG rendering
+///
+/// # Returns
+/// `None` when the glyph has no outline
+pub fn glyph_outline(face: &Face, glyph_id: GlyphId) -> Option<BezPath> {
+    let mut builder = SvgPathBuilder::new();
+    face.outline_glyph(glyph_id, &mut builder)?;
+    Some(builder.path)
+}
+
 /// Extract a single glyph's outline and metadata
 ///
 /// # Arguments
@@ -74,12 +85,51 @@ impl OutlineBuilder for SvgPathBuilder {
 /// # Returns
 /// `Some(GlyphInfo)` if glyph has an outline, `None` otherwise
 pub fn extract_glyph(face: &Face, glyph_id: GlyphId, unicode: char) -> Option<GlyphInfo> {
+    extract_glyph_with(face, glyph_id, unicode, &ExtractOptions::default())
+}
+
+/// Extract a single glyph's outline and metadata with explicit options
+///
+/// # Arguments
+/// * `face` - Parsed font face
+/// * `glyph_id` - Glyph identifier
+/// * `unicode` - Unicode character this glyph represents
+/// * `options` - Outline processing options
+///
+/// # Returns
+/// `Some(GlyphInfo)` if glyph has an outline, `None` otherwise
+pub fn extract_glyph_with(
+    face: &Face,
+    glyph_id: GlyphId,
+    unicode: char,
+    options: &ExtractOptions,
+) -> Option<GlyphInfo> {
     let mut builder = SvgPathBuilder::new();
 
     // Draw outline - ttf-parser calls builder methods
     face.outline_glyph(glyph_id, &mut builder)?;
 
-    let (svg_path, contour_count, point_count) = builder.finish();
+    let review = options
+        .reviews
+        .as_ref()
+        .and_then(|sheet| sheet.review_for(unicode, Some(&review::path_hash(&builder.path))));
+
+    let mut flipped_contours = None;
+    if options.normalize_direction {
+        let mut contours = outline::split_contours(&builder.path);
+        flipped_contours = Some(outline::normalize_winding(&mut contours));
+        builder.path = outline::join_contours(&contours);
+    }
+
+    // Get horizontal advance width
+    let advance_width = face.glyph_hor_advance(glyph_id).unwrap_or(0);
+
+    let outline_metrics = options
+        .outline_metrics
+        .then(|| outline::outline_metrics(&builder.path, advance_width))
+        .flatten();
+
+    let (svg_path, contour_count, point_count) = builder.finish(options.optimize_paths.as_ref());
 
     // Skip empty glyphs (e.g., space character)
     if svg_path.trim().is_empty() {
@@ -94,9 +144,6 @@ pub fn extract_glyph(face: &Face, glyph_id: GlyphId, unicode: char) -> Option<Gl
         y_max: bbox.y_max,
     });
 
-    // Get horizontal advance width
-    let advance_width = face.glyph_hor_advance(glyph_id).unwrap_or(0);
-
     Some(GlyphInfo {
         glyph_name: format!("uni{:04X}", unicode as u32),
         unicode: format!("U+{:04X}", unicode as u32),
@@ -106,6 +153,13 @@ pub fn extract_glyph(face: &Face, glyph_id: GlyphId, unicode: char) -> Option<Gl
         bounding_box,
         contour_count,
         point_count,
+        flipped_contours,
+        annotation: options
+            .char_names
+            .then(|| char_names::annotate(unicode, options.unihan.as_deref()))
+            .flatten(),
+        outline_metrics,
+        review,
     })
 }
 
@@ -120,6 +174,23 @@ pub fn extract_glyph(face: &Face, glyph_id: GlyphId, unicode: char) -> Option<Gl
 pub fn extract_glyphs_parallel(
     face: &Face,
     codepoints: &[u32],
+) -> Vec<GlyphInfo> {
+    extract_glyphs_parallel_with(face, codepoints, &ExtractOptions::default())
+}
+
+/// Extract glyphs for a set of Unicode codepoints in parallel with options
+///
+/// # Arguments
+/// * `face` - Parsed font face
+/// * `codepoints` - Set of Unicode codepoints to extract
+/// * `options` - Outline processing options applied to every glyph
+///
+/// # Returns
+/// Vector of successfully extracted glyphs
+pub fn extract_glyphs_parallel_with(
+    face: &Face,
+    codepoints: &[u32],
+    options: &ExtractOptions,
 ) -> Vec<GlyphInfo> {
     use rayon::prelude::*;
 
@@ -150,7 +221,39 @@ pub fn extract_glyphs_parallel(
     // Safety: Face is immutable and thread-safe for reading
     pairs
         .par_iter()
-        .filter_map(|&(c, gid)| extract_glyph(face, gid, c))
+        .filter_map(|&(c, gid)| extract_glyph_with(face, gid, c, options))
+        .collect()
+}
+
+/// Extract glyphs for a set of Unicode codepoints on the current thread
+///
+/// # Arguments
+/// * `face` - Parsed font face
+/// * `codepoints` - Set of Unicode codepoints to extract
+/// * `options` - Outline processing options applied to every glyph
+///
+/// # Returns
+/// Vector of successfully extracted glyphs, in codepoint order
+pub fn extract_glyphs_serial_with(
+    face: &Face,
+    codepoints: &[u32],
+    options: &ExtractOptions,
+) -> Vec<GlyphInfo> {
+    let Some(subtable) = face
+        .tables()
+        .cmap
+        .and_then(|cmap| cmap.subtables.into_iter().find(|st| st.is_unicode()))
+    else {
+        return Vec::new();
+    };
+
+    codepoints
+        .iter()
+        .filter_map(|&cp| {
+            let c = char::from_u32(cp)?;
+            let glyph_id = subtable.glyph_index(cp)?;
+            extract_glyph_with(face, glyph_id, c, options)
+        })
         .collect()
 }
 
@@ -162,7 +265,7 @@ mod tests {
     fn svg_path_builder_should_format_move_command() {
         let mut builder = SvgPathBuilder::new();
         builder.move_to(100.0, 200.0);
-        let (path, _, _) = builder.finish();
+        let (path, _, _) = builder.finish(None);
         assert_eq!(path.trim(), "M 100.00 -200.00");
     }
 
@@ -171,7 +274,7 @@ mod tests {
         let mut builder = SvgPathBuilder::new();
         builder.move_to(0.0, 100.0);
         builder.line_to(50.0, 100.0);
-        let (path, _, _) = builder.finish();
+        let (path, _, _) = builder.finish(None);
         assert!(path.contains("-100.00"));
     }
 }
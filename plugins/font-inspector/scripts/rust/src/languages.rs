@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use ttf_parser::Face;
+
+/// CLDR main exemplar sets, see the header of the file for its source
+const EXEMPLARS: &str = include_str!("data/exemplars.tsv");
+/// Georgian Mkhedruli uppercases to Mtavruli, which running text does not use
+const MKHEDRULI: std::ops::RangeInclusive<char> = '\u{10D0}'..='\u{10FF}';
+
+/// Characters a language needs in everyday text
+#[derive(Debug, Clone)]
+pub struct Exemplars {
+    /// CLDR locale id (`de`, `sr-Latn`, `zh-Hant`)
+    pub locale: &'static str,
+    pub name: &'static str,
+    /// Exemplars plus their single-character uppercase forms, in code point order
+    pub characters: Vec<char>,
+}
+
+/// How well a font covers one language
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Support {
+    /// Every exemplar is mapped
+    Full,
+    /// Some exemplars are mapped
+    Partial,
+    /// None of the exemplars are mapped
+    None,
+}
+
+/// Coverage of one language's exemplar set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageCoverage {
+    pub locale: String,
+    pub name: String,
+    pub support: Support,
+    pub required: usize,
+    pub covered: usize,
+    /// `U+XXXX c` for each unmapped exemplar; listed for partial support only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>,
+}
+
+/// Language support estimated from a font's cmap
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LanguageReport {
+    pub font: String,
+    pub full: usize,
+    pub partial: usize,
+    pub unsupported: usize,
+    /// Full support first, then partial by coverage, then unsupported
+    pub languages: Vec<LanguageCoverage>,
+}
+
+/// Parse the embedded exemplar table
+///
+/// CLDR lists lowercase letters only; uppercase forms that are a single
+/// character are added, so German needs `Ä` but not `SS` for `ß`.
+/// Georgian is left lowercase.
+pub fn exemplars() -> Vec<Exemplars> {
+    EXEMPLARS
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let (locale, name, set) = (fields.next()?, fields.next()?, fields.next()?);
+            let mut characters: Vec<char> = set.chars().collect();
+            for c in set.chars().filter(|c| !MKHEDRULI.contains(c)) {
+                let mut upper = c.to_uppercase();
+                if let (Some(u), None) = (upper.next(), upper.next())
+                    && u != c
+                {
+                    characters.push(u);
+                }
+            }
+            characters.sort_unstable();
+            characters.dedup();
+            Some(Exemplars { locale, name, characters })
+        })
+        .collect()
+}
+
+/// Check one exemplar set against a character predicate
+pub fn coverage(exemplars: &Exemplars, has_char: impl Fn(char) -> bool) -> LanguageCoverage {
+    let missing: Vec<char> = exemplars.characters.iter().copied().filter(|&c| !has_char(c)).collect();
+    let required = exemplars.characters.len();
+    let covered = required - missing.len();
+    let support = if missing.is_empty() {
+        Support::Full
+    } else if covered == 0 {
+        Support::None
+    } else {
+        Support::Partial
+    };
+    LanguageCoverage {
+        locale: exemplars.locale.to_string(),
+        name: exemplars.name.to_string(),
+        support,
+        required,
+        covered,
+        missing: match support {
+            Support::Partial => missing.iter().map(|&c| format!("U+{:04X} {}", c as u32, c)).collect(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+/// Estimate which languages a font supports from its cmap
+///
+/// # Arguments
+/// * `face` - Parsed font face
+/// * `font` - Font path for the report
+/// * `locales` - Restrict the report to these CLDR locale ids; empty checks all
+///
+/// # Errors
+/// Returns error if a requested locale has no embedded exemplar set
+pub fn language_coverage(face: &Face, font: &str, locales: &[String]) -> anyhow::Result<LanguageReport> {
+    let all = exemplars();
+    if let Some(unknown) = locales.iter().find(|l| !all.iter().any(|e| e.locale == l.as_str())) {
+        anyhow::bail!("No exemplar characters for locale '{}'", unknown);
+    }
+
+    let mut languages: Vec<LanguageCoverage> = all
+        .iter()
+        .filter(|e| locales.is_empty() || locales.iter().any(|l| l == e.locale))
+        .map(|e| coverage(e, |c| face.glyph_index(c).is_some()))
+        .collect();
+    languages.sort_by(|a, b| {
+        let ratio = |l: &LanguageCoverage| l.covered as f64 / l.required.max(1) as f64;
+        a.support.cmp(&b.support).then(ratio(b).total_cmp(&ratio(a))).then(a.locale.cmp(&b.locale))
+    });
+
+    let count = |support| languages.iter().filter(|l| l.support == support).count();
+    Ok(LanguageReport {
+        font: font.to_string(),
+        full: count(Support::Full),
+        partial: count(Support::Partial),
+        unsupported: count(Support::None),
+        languages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(locale: &str) -> Exemplars {
+        exemplars().into_iter().find(|e| e.locale == locale).unwrap()
+    }
+
+    #[test]
+    fn exemplars_should_parse_every_line_and_add_uppercase() {
+        let all = exemplars();
+        assert_eq!(all.len(), EXEMPLARS.lines().filter(|l| !l.starts_with('#')).count());
+        assert!(all.iter().all(|e| !e.characters.is_empty()));
+
+        let de = find("de");
+        assert_eq!(de.name, "German");
+        assert!(de.characters.contains(&'Ä') && de.characters.contains(&'ß'));
+        assert_eq!(find("en").characters.len(), 52);
+    }
+
+    #[test]
+    fn coverage_should_classify_full_partial_and_none() {
+        let ascii = |c: char| c.is_ascii();
+
+        assert_eq!(coverage(&find("en"), ascii).support, Support::Full);
+        assert_eq!(coverage(&find("ja"), ascii).support, Support::None);
+
+        let de = coverage(&find("de"), ascii);
+        assert_eq!(de.support, Support::Partial);
+        assert_eq!(de.required - de.covered, 7);
+        assert!(de.missing.contains(&"U+00DF ß".to_string()));
+        assert!(coverage(&find("ja"), ascii).missing.is_empty());
+    }
+}
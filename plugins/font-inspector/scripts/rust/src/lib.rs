@@ -1,5 +1,24 @@
 // Authors: Joysusy & Violet Klaudia 💖
+pub mod char_filter;
+pub mod char_names;
+pub mod document;
+pub mod export_policy;
 pub mod extractor;
+pub mod languages;
+pub mod math;
+pub mod memory;
+pub mod outline;
+pub mod path_optimizer;
+pub mod pipeline;
+pub mod render;
+pub mod report_writer;
+pub mod repro;
+pub mod review;
+pub mod server_policy;
+pub mod style;
 pub mod svg_writer;
+pub mod tables;
+pub mod ttx;
 pub mod types;
 pub mod ufo_writer;
+pub mod vertical;
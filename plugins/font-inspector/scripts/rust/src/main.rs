@@ -2,13 +2,35 @@
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use std::fs;
-use std::path::PathBuf;
-use ttf_parser::Face;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use ttf_parser::{Face, RawFace, Tag};
 
+use font_inspector::char_filter::CharFilter;
+use font_inspector::char_names::Unihan;
+use font_inspector::document::{self, DocumentCoverage};
+use font_inspector::export_policy::{ExportPolicy, PolicyReport};
 use font_inspector::extractor;
+use font_inspector::languages::{self, Support};
+use font_inspector::math;
+use font_inspector::memory::{ExtractionPlan, MemoryBudget};
+use font_inspector::path_optimizer::{self, PathOptimizeOptions};
+use font_inspector::pipeline::{ExtractStep, OutputStep, Pipeline, Step};
+use font_inspector::render;
+use font_inspector::report_writer::StreamingReportWriter;
+use font_inspector::repro;
+use font_inspector::review::{self, ReviewComment, ReviewSheet, ReviewStatus};
+use font_inspector::style::{self, Estimate};
 use font_inspector::svg_writer;
+use font_inspector::tables;
+use font_inspector::ttx;
 use font_inspector::ufo_writer;
-use font_inspector::types::{CharsetPreset, FontMetadata, FontReport, UnicodeRange};
+use font_inspector::vertical::{self, Transform};
+use font_inspector::types::{
+    CharsetPreset, DirectionReport, ExtractOptions, FontMetadata, FontReport, GlyphInfo,
+    UnicodeRange,
+};
 
 #[derive(Parser)]
 #[command(
@@ -66,6 +88,58 @@ enum Commands {
         /// Use parallel processing (faster for large fonts)
         #[arg(long, default_value = "true")]
         parallel: bool,
+
+        /// Normalize contour direction (outer CCW, inner CW) and report flipped glyphs
+        #[arg(long)]
+        normalize_direction: bool,
+
+        /// Optimize SVG path data (collapse collinear points, use S/T shorthands)
+        #[arg(long)]
+        optimize_paths: bool,
+
+        /// Maximum deviation allowed by --optimize-paths, in font units
+        #[arg(long, default_value_t = path_optimizer::DEFAULT_TOLERANCE, requires = "optimize_paths")]
+        optimize_tolerance: f64,
+
+        /// With --optimize-paths, replace circular curves with SVG arcs
+        #[arg(long, requires = "optimize_paths")]
+        arcs: bool,
+
+        /// Annotate each glyph with its Unicode character name
+        #[arg(long)]
+        char_names: bool,
+
+        /// Unihan_*.txt file or directory (strokes, radicals, readings for --char-names / --filter)
+        #[arg(long)]
+        unihan: Option<PathBuf>,
+
+        /// Report signed contour areas, the ink centroid and its offset from the advance center
+        #[arg(long)]
+        outline_metrics: bool,
+
+        /// Keep only characters matching Unihan data, e.g. "strokes<=8" or "radical=85,strokes<12"
+        #[arg(long, value_parser = CharFilter::parse, requires = "unihan")]
+        filter: Option<CharFilter>,
+
+        /// Memory budget (e.g. 512M, 2G); larger jobs are batched and streamed
+        #[arg(long, value_parser = parse_memory)]
+        max_memory: Option<MemoryBudget>,
+
+        /// Split path data longer than this many bytes into several <path> elements (per outer contour)
+        #[arg(long, value_name = "BYTES")]
+        split_paths_over: Option<usize>,
+
+        /// Add the OpenType MATH table (constants, italic corrections, accents, variants) to the report
+        #[arg(long)]
+        math: bool,
+
+        /// Review sidecar to merge into the report (see `review set`)
+        #[arg(long, value_name = "FILE")]
+        review: Option<PathBuf>,
+
+        /// Export policy TOML: characters always exported (`require`) or never (`exclude`)
+        #[arg(long, value_name = "FILE")]
+        policy: Option<PathBuf>,
     },
 
     /// Display font metadata and information
@@ -74,10 +148,289 @@ enum Commands {
         #[arg(short, long)]
         font: PathBuf,
 
+        /// Output format: json, text or ttx (fontTools XML)
+        #[arg(long, default_value = "json")]
+        format: String,
+
+        /// With --format ttx: tables to export, comma-separated (default: whole font)
+        #[arg(long, value_delimiter = ',')]
+        tables: Vec<String>,
+    },
+
+    /// Run the steps of a pipeline file (extract → svg/ufo/report/repro-check)
+    Run {
+        /// Pipeline definition (TOML)
+        pipeline: PathBuf,
+
+        /// Show progress bars for long steps
+        #[arg(long)]
+        progress: bool,
+    },
+
+    /// Extract twice (serial and parallel) and fail if the reports differ
+    ReproCheck {
+        /// Path to font file
+        #[arg(short, long)]
+        font: PathBuf,
+
+        /// Specific characters to check (e.g., "ABC你好")
+        #[arg(long)]
+        chars: Option<String>,
+
+        /// Unicode range to check (e.g., "0x4E00-0x9FFF")
+        #[arg(long)]
+        range: Option<String>,
+
+        /// Use predefined character set
+        #[arg(long, value_parser = parse_preset)]
+        preset: Option<CharsetPreset>,
+
+        /// Maximum number of characters to check
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Normalize contour direction in both runs
+        #[arg(long)]
+        normalize_direction: bool,
+
+        /// Optimize SVG path data in both runs
+        #[arg(long)]
+        optimize_paths: bool,
+
+        /// Maximum deviation allowed by --optimize-paths, in font units
+        #[arg(long, default_value_t = path_optimizer::DEFAULT_TOLERANCE, requires = "optimize_paths")]
+        optimize_tolerance: f64,
+
+        /// With --optimize-paths, replace circular curves with SVG arcs
+        #[arg(long, requires = "optimize_paths")]
+        arcs: bool,
+    },
+
+    /// Check whether a font covers the characters a PDF or EPUB sets in each of its fonts
+    CoverageFromDocument {
+        /// PDF or EPUB to scan
+        document: PathBuf,
+
+        /// Candidate replacement font
+        #[arg(short, long)]
+        font: PathBuf,
+
+        /// Only check declared fonts whose name contains this text
+        #[arg(long)]
+        only: Option<String>,
+
         /// Output format: json or text
         #[arg(long, default_value = "json")]
         format: String,
     },
+
+    /// Verify `vert` alternates are rotated/shifted copies of their base glyphs
+    VerticalCheck {
+        /// Path to font file
+        #[arg(short, long)]
+        font: PathBuf,
+
+        /// Maximum point deviation for outlines to match, in font units
+        #[arg(long, default_value_t = vertical::DEFAULT_TOLERANCE)]
+        tolerance: f64,
+
+        /// Output format: json or text
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// Estimate which languages a font supports from CLDR exemplar characters
+    Languages {
+        /// Path to font file
+        #[arg(short, long)]
+        font: PathBuf,
+
+        /// CLDR locales to check, comma-separated (default: all embedded ones)
+        #[arg(long, value_delimiter = ',')]
+        locales: Vec<String>,
+
+        /// Output format: json or text
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// Estimate weight, width and slant from outlines and check them against OS/2 and post
+    StyleCheck {
+        /// Path to font file
+        #[arg(short, long)]
+        font: PathBuf,
+
+        /// Only flag contradictions whose estimate is at least this confident (0–1)
+        #[arg(long, default_value_t = style::DEFAULT_MIN_CONFIDENCE)]
+        min_confidence: f64,
+
+        /// Output format: json or text
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// Rasterize text with simulated macOS/Windows renderers into side-by-side PNGs
+    RenderCompare {
+        /// Path to font file
+        #[arg(short, long)]
+        font: PathBuf,
+
+        /// Text to render on one line
+        #[arg(long, default_value = "Hamburgefonstiv")]
+        text: String,
+
+        /// Pixel sizes (em), comma-separated; one PNG each
+        #[arg(long, value_delimiter = ',', default_values_t = [12.0, 16.0, 24.0])]
+        sizes: Vec<f32>,
+
+        /// Profiles to compare, comma-separated (default: all)
+        #[arg(long, value_delimiter = ',')]
+        profiles: Vec<String>,
+
+        /// Nearest-neighbour magnification of the written PNGs
+        #[arg(long, default_value_t = 4)]
+        zoom: u32,
+
+        /// Output directory
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    /// Inspect raw SFNT tables (list, dump, diff)
+    Table {
+        #[command(subcommand)]
+        action: TableCommand,
+    },
+
+    /// Annotate glyphs for design review in a sidecar file (set, show, carry)
+    Review {
+        #[command(subcommand)]
+        action: ReviewCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReviewCommand {
+    /// Set the status, tags or a comment on glyphs
+    Set {
+        /// Path to font file
+        #[arg(short, long)]
+        font: PathBuf,
+
+        /// Characters to annotate (e.g., "AB你")
+        #[arg(long)]
+        chars: String,
+
+        /// pending, approved or changes-requested
+        #[arg(long, value_parser = ReviewStatus::parse)]
+        status: Option<ReviewStatus>,
+
+        /// Issue tag to add (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+
+        /// Comment to append
+        #[arg(long)]
+        comment: Option<String>,
+
+        /// Comment author
+        #[arg(long, requires = "comment")]
+        author: Option<String>,
+
+        /// Sidecar file (default: <font>.review.json)
+        #[arg(long)]
+        sidecar: Option<PathBuf>,
+    },
+
+    /// List the reviews in a sidecar, flagging glyphs edited since
+    Show {
+        /// Path to font file
+        #[arg(short, long)]
+        font: PathBuf,
+
+        /// Sidecar file (default: <font>.review.json)
+        #[arg(long)]
+        sidecar: Option<PathBuf>,
+
+        /// Output format: text or json
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// Carry reviews from an earlier build to a new one by outline hash
+    Carry {
+        /// The new build
+        #[arg(short, long)]
+        font: PathBuf,
+
+        /// Sidecar of the earlier build
+        #[arg(long)]
+        from: PathBuf,
+
+        /// Where to write the carried sidecar (default: <font>.review.json)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TableCommand {
+    /// List the table directory: tags, offsets, lengths and checksums
+    List {
+        /// Path to font file
+        #[arg(short, long)]
+        font: PathBuf,
+    },
+
+    /// Print one table as decoded fields and/or an annotated hex dump
+    Dump {
+        /// Table tag (e.g. head, OS/2, cmap)
+        tag: String,
+
+        /// Path to font file
+        #[arg(short, long)]
+        font: PathBuf,
+
+        /// Always include the hex dump, even for tables with decoded fields
+        #[arg(long)]
+        hex: bool,
+
+        /// Maximum bytes to hex dump (0 = whole table)
+        #[arg(long, default_value_t = 512)]
+        max_bytes: usize,
+    },
+
+    /// Compare one table between two fonts
+    Diff {
+        /// Table tag (e.g. head, OS/2, cmap)
+        tag: String,
+
+        /// First font
+        #[arg(long)]
+        a: PathBuf,
+
+        /// Second font
+        #[arg(long)]
+        b: PathBuf,
+
+        /// Maximum differing byte ranges to print (0 = all)
+        #[arg(long, default_value_t = 20)]
+        max_ranges: usize,
+    },
+
+    /// Compare a fontTools TTX file against a binary font
+    CompareTtx {
+        /// TTX file (e.g. from `ttx -t head -t OS/2 MyFont.ttf`)
+        ttx: PathBuf,
+
+        /// Binary font to check
+        #[arg(short, long)]
+        font: PathBuf,
+
+        /// Output format: json or text
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
 }
 
 fn parse_preset(s: &str) -> Result<CharsetPreset, String> {
@@ -89,6 +442,10 @@ fn parse_preset(s: &str) -> Result<CharsetPreset, String> {
     })
 }
 
+fn parse_memory(s: &str) -> Result<MemoryBudget, String> {
+    MemoryBudget::parse(s).map_err(|e| e.to_string())
+}
+
 /// Determine which codepoints to extract based on command arguments
 fn get_codepoints(
     face: &Face,
@@ -96,6 +453,7 @@ fn get_codepoints(
     range: &Option<String>,
     preset: &Option<CharsetPreset>,
     limit: &Option<usize>,
+    filter: Option<(&CharFilter, &Unihan)>,
 ) -> Result<Vec<u32>> {
     // Get all available codepoints from font
     let cmap = face
@@ -114,6 +472,11 @@ fn get_codepoints(
         all_codepoints.push(cp);
     });
 
+    // Unihan filters narrow the candidates before presets and limits apply
+    if let Some((filter, unihan)) = filter {
+        filter.retain(&mut all_codepoints, unihan);
+    }
+
     // Filter based on arguments
     let mut result = if let Some(chars_str) = chars {
         // Explicit characters
@@ -167,6 +530,31 @@ struct ExtractConfig {
     json_only: bool,
     progress: bool,
     parallel: bool,
+    normalize_direction: bool,
+    optimize_paths: Option<PathOptimizeOptions>,
+    char_names: bool,
+    unihan: Option<PathBuf>,
+    outline_metrics: bool,
+    filter: Option<CharFilter>,
+    max_memory: Option<MemoryBudget>,
+    split_paths_over: Option<usize>,
+    math: bool,
+    review: Option<PathBuf>,
+    policy: Option<PathBuf>,
+}
+
+/// Extract one slice of codepoints, in parallel or serially
+fn extract_batch(
+    face: &Face,
+    codepoints: &[u32],
+    options: &ExtractOptions,
+    parallel: bool,
+) -> Vec<GlyphInfo> {
+    if parallel {
+        extractor::extract_glyphs_parallel_with(face, codepoints, options)
+    } else {
+        extractor::extract_glyphs_serial_with(face, codepoints, options)
+    }
 }
 
 fn run_extract(config: ExtractConfig) -> Result<()> {
@@ -190,35 +578,49 @@ fn run_extract(config: ExtractConfig) -> Result<()> {
                 .to_string()
         });
 
+    let unihan = config.unihan.as_deref().map(Unihan::load).transpose()?.map(Arc::new);
+    let reviews = config.review.as_deref().map(ReviewSheet::load).transpose()?.map(Arc::new);
+    let math = extract_math(&config, &face)?;
+
     // Determine codepoints to extract
-    let codepoints = get_codepoints(&face, &config.chars, &config.range, &config.preset, &config.limit)?;
+    let filter = config.filter.as_ref().zip(unihan.as_deref());
+    let mut codepoints = get_codepoints(&face, &config.chars, &config.range, &config.preset, &config.limit, filter)?;
+
+    let options = ExtractOptions {
+        normalize_direction: config.normalize_direction,
+        optimize_paths: config.optimize_paths,
+        char_names: config.char_names,
+        unihan,
+        outline_metrics: config.outline_metrics,
+        reviews,
+    };
+    let (policy, policy_glyphs) = apply_policy(config.policy.as_deref(), &face, &mut codepoints, &options)?;
 
     if config.progress {
         eprintln!("Extracting {} characters from font...", codepoints.len());
     }
 
-    // Extract glyphs
-    let glyphs = if config.parallel {
-        extractor::extract_glyphs_parallel(&face, &codepoints)
-    } else {
-        codepoints
-            .iter()
-            .filter_map(|&cp| {
-                let c = char::from_u32(cp)?;
-                let subtable = face.tables().cmap?.subtables.into_iter().find(|st| st.is_unicode())?;
-                let glyph_id = subtable.glyph_index(cp)?;
-                extractor::extract_glyph(&face, glyph_id, c)
-            })
-            .collect()
-    };
+    // Jobs that would not fit the memory budget are streamed in batches
+    if let Some(budget) = &config.max_memory {
+        let plan = budget.plan(&face, &codepoints, &options);
+        if plan.streaming {
+            let extras = ReportExtras { math, policy, policy_glyphs };
+            return run_extract_streaming(&config, &face, &font_name, &codepoints, &options, plan, extras);
+        }
+    }
+
+    // Extract glyphs; policy glyphs extraction cannot produce (.notdef, spaces) go first
+    let mut glyphs = policy_glyphs;
+    glyphs.extend(extract_batch(&face, &codepoints, &options, config.parallel));
 
     // Write SVG files
     if !config.json_only {
         if config.parallel && glyphs.len() > 100 {
-            svg_writer::write_all_glyphs_parallel(&glyphs, &config.output, upem, config.progress)?;
+            svg_writer::write_all_glyphs_parallel(&glyphs, &config.output, upem, config.progress, config.split_paths_over)?;
         } else {
-            svg_writer::write_all_glyphs(&glyphs, &config.output, upem, config.progress)?;
+            svg_writer::write_all_glyphs(&glyphs, &config.output, upem, config.progress, config.split_paths_over)?;
         }
+        warn_long_paths(&config, svg_writer::count_long_paths(&glyphs, svg_writer::LONG_PATH_WARN_BYTES));
 
         // Write UFO if requested
         if config.ufo {
@@ -227,6 +629,14 @@ fn run_extract(config: ExtractConfig) -> Result<()> {
         }
     }
 
+    let direction_report = DirectionReport::from_glyphs(&glyphs);
+    if config.progress && let Some(dr) = &direction_report {
+        eprintln!(
+            "Normalized direction: {} contours flipped in {} glyphs",
+            dr.flipped_contour_count, dr.flipped_glyph_count
+        );
+    }
+
     // Output JSON report (always to stdout for Claude)
     let report = FontReport {
         font_file: config.font.display().to_string(),
@@ -235,6 +645,9 @@ fn run_extract(config: ExtractConfig) -> Result<()> {
         glyph_count,
         total_exported: glyphs.len(),
         glyphs,
+        direction_report,
+        math,
+        policy,
     };
 
     let json = serde_json::to_string_pretty(&report)?;
@@ -243,8 +656,158 @@ fn run_extract(config: ExtractConfig) -> Result<()> {
     Ok(())
 }
 
-fn run_info(font: PathBuf, format: String) -> Result<()> {
+/// Report sections computed before extraction starts
+struct ReportExtras {
+    math: Option<math::MathReport>,
+    policy: Option<PolicyReport>,
+    /// Glyphs added by the policy that extraction does not produce
+    policy_glyphs: Vec<GlyphInfo>,
+}
+
+/// Enforce `--policy` on a selection of codepoints
+///
+/// # Errors
+/// Returns error if the policy cannot be loaded or requires a character the font does not map
+fn apply_policy(
+    path: Option<&Path>,
+    face: &Face,
+    codepoints: &mut Vec<u32>,
+    options: &ExtractOptions,
+) -> Result<(Option<PolicyReport>, Vec<GlyphInfo>)> {
+    let Some(path) = path else {
+        return Ok((None, Vec::new()));
+    };
+    let (report, glyphs) = ExportPolicy::load(path)?.apply(face, codepoints, options);
+    if !report.missing.is_empty() {
+        anyhow::bail!(
+            "Export policy {} requires characters the font does not map: {}",
+            path.display(),
+            report.missing.join(", ")
+        );
+    }
+    eprintln!(
+        "Policy {}: {} added, {} removed, {} blank{}",
+        path.display(),
+        report.added.len(),
+        report.removed.len(),
+        report.blank.len(),
+        if report.notdef { ", .notdef included" } else { "" }
+    );
+    Ok((Some(report), glyphs))
+}
+
+/// Budget-constrained extraction
+///
+/// Processes codepoints in batches sized by the plan, writing SVG files and
+/// report entries as each batch completes so only one batch of glyphs is
+/// alive at a time.
+fn run_extract_streaming(
+    config: &ExtractConfig,
+    face: &Face,
+    font_name: &str,
+    codepoints: &[u32],
+    options: &ExtractOptions,
+    plan: ExtractionPlan,
+    extras: ReportExtras,
+) -> Result<()> {
+    let upem = face.units_per_em();
+    // Glyphs the policy adds that extraction does not produce (.notdef, spaces) go first, as a batch of their own
+    let policy_batch = (!extras.policy_glyphs.is_empty()).then_some(extras.policy_glyphs);
+    let batch_count = codepoints.len().div_ceil(plan.batch_size) + usize::from(policy_batch.is_some());
+
+    if config.progress {
+        eprintln!(
+            "Estimated {} bytes exceeds memory budget; streaming {} batches of {} glyphs",
+            plan.estimated_bytes, batch_count, plan.batch_size
+        );
+    }
+
+    let mut report = StreamingReportWriter::begin(
+        BufWriter::new(io::stdout().lock()),
+        &config.font.display().to_string(),
+        Some(font_name),
+        upem,
+        face.number_of_glyphs(),
+    )?;
+    let mut ufo_glyphs = Vec::new();
+    let mut long_paths = 0;
+
+    let extracted = codepoints.chunks(plan.batch_size).map(|batch| extract_batch(face, batch, options, config.parallel));
+    for (index, glyphs) in policy_batch.into_iter().chain(extracted).enumerate() {
+        if !config.json_only {
+            if config.parallel {
+                svg_writer::write_all_glyphs_parallel(&glyphs, &config.output, upem, false, config.split_paths_over)?;
+            } else {
+                svg_writer::write_all_glyphs(&glyphs, &config.output, upem, false, config.split_paths_over)?;
+            }
+            long_paths += svg_writer::count_long_paths(&glyphs, svg_writer::LONG_PATH_WARN_BYTES);
+        }
+        report.write_glyphs(&glyphs)?;
+
+        // UFO glyphs carry no outline data, so keep them without their paths
+        if config.ufo && !config.json_only {
+            ufo_glyphs.extend(glyphs.into_iter().map(|mut g| {
+                g.svg_path = String::new();
+                g
+            }));
+        }
+
+        if config.progress {
+            eprintln!("Batch {}/{} complete", index + 1, batch_count);
+        }
+    }
+
+    if let Some(math) = extras.math {
+        report.set_math(math);
+    }
+    if let Some(policy) = extras.policy {
+        report.set_policy(policy);
+    }
+    report.finish()?;
+    warn_long_paths(config, long_paths);
+
+    if config.ufo && !config.json_only {
+        let ufo_path = config.output.with_extension("ufo");
+        ufo_writer::write_ufo_with_progress(&ufo_glyphs, font_name, upem, &ufo_path, config.progress)?;
+    }
+
+    Ok(())
+}
+
+/// MATH table report for `--math`; fails when the font has no MATH table
+fn extract_math(config: &ExtractConfig, face: &Face) -> Result<Option<math::MathReport>> {
+    if !config.math {
+        return Ok(None);
+    }
+    match math::extract_math(face, None) {
+        Some(report) => Ok(Some(report)),
+        None => anyhow::bail!("--math: {} has no MATH table", config.font.display()),
+    }
+}
+
+/// Point at --split-paths-over when single-path output produced very long `d` attributes
+fn warn_long_paths(config: &ExtractConfig, count: usize) {
+    if count > 0 && config.split_paths_over.is_none() {
+        eprintln!(
+            "Warning: {} glyph(s) have path data over {} KiB, which some SVG consumers reject; \
+             rerun with --split-paths-over {} to write them as several <path> elements",
+            count,
+            svg_writer::LONG_PATH_WARN_BYTES / 1024,
+            svg_writer::LONG_PATH_WARN_BYTES
+        );
+    }
+}
+
+fn run_info(font: PathBuf, format: String, table_tags: Vec<String>) -> Result<()> {
     let font_data = fs::read(&font).context("Failed to read font file")?;
+    if format == "ttx" {
+        let tags = table_tags.iter().map(|t| tables::parse_tag(t)).collect::<Result<Vec<_>>>()?;
+        print!("{}", ttx::export_ttx(&font_data, (!tags.is_empty()).then_some(tags.as_slice()))?);
+        return Ok(());
+    }
+    if !table_tags.is_empty() {
+        anyhow::bail!("--tables requires --format ttx");
+    }
     let face = Face::parse(&font_data, 0).context("Failed to parse font")?;
 
     let family_name = face
@@ -264,6 +827,7 @@ fn run_info(font: PathBuf, format: String) -> Result<()> {
         ascender: Some(face.ascender()),
         descender: Some(face.descender()),
         line_gap: Some(face.line_gap()),
+        has_math: face.tables().math.is_some(),
     };
 
     match format.as_str() {
@@ -290,10 +854,589 @@ fn run_info(font: PathBuf, format: String) -> Result<()> {
             if let Some(gap) = metadata.line_gap {
                 println!("Line gap: {}", gap);
             }
+            println!("Has MATH table: {}", metadata.has_math);
+        }
+        _ => anyhow::bail!("Invalid format: {}. Use 'json', 'text' or 'ttx'", format),
+    }
+
+    Ok(())
+}
+
+/// Glyphs produced by a pipeline `extract` step
+struct GlyphSet {
+    step: ExtractStep,
+    codepoints: Vec<u32>,
+    options: ExtractOptions,
+    glyphs: Vec<GlyphInfo>,
+    policy: Option<PolicyReport>,
+}
+
+/// Resolve a consumer step's glyph set: its `from`, else the latest extract
+fn pipeline_set<'a>(sets: &'a [(String, Arc<GlyphSet>)], output: &OutputStep) -> Result<&'a (String, Arc<GlyphSet>)> {
+    match &output.from {
+        Some(from) => sets.iter().rev().find(|(name, _)| name == from),
+        None => sets.last(),
+    }
+    .context("Pipeline step has no glyph set to read")
+}
+
+fn run_pipeline(path: PathBuf, progress: bool) -> Result<()> {
+    let pipeline = Pipeline::load(&path)?;
+    let font_data = fs::read(&pipeline.font)
+        .with_context(|| format!("Failed to read font file: {}", pipeline.font.display()))?;
+    let face = Face::parse(&font_data, 0).context("Failed to parse font")?;
+    let upem = face.units_per_em();
+    let font_name = face
+        .names()
+        .into_iter()
+        .find(|n| n.name_id == ttf_parser::name_id::FAMILY)
+        .and_then(|n| n.to_string())
+        .unwrap_or_else(|| "Unknown".to_string());
+    let unihan = pipeline.unihan.as_deref().map(Unihan::load).transpose()?.map(Arc::new);
+
+    fs::create_dir_all(&pipeline.output)
+        .with_context(|| format!("Failed to create directory: {}", pipeline.output.display()))?;
+
+    // Extract results are shared: identical extract steps and every consumer reuse them
+    let mut sets: Vec<(String, Arc<GlyphSet>)> = Vec::new();
+    let total = pipeline.steps.len();
+
+    for (index, step) in pipeline.steps.iter().enumerate() {
+        let label = format!("[{}/{}]", index + 1, total);
+        match step {
+            Step::Extract(extract) => {
+                let name = extract.set_name(index);
+                let cached = sets.iter().find(|(_, set)| set.step.same_work(extract)).map(|(_, set)| set.clone());
+                let set = match cached {
+                    Some(set) => {
+                        eprintln!("{} extract {}: {} glyphs (cached)", label, name, set.glyphs.len());
+                        set
+                    }
+                    None => {
+                        let filter = extract.filter()?;
+                        let mut codepoints = get_codepoints(
+                            &face,
+                            &extract.chars,
+                            &extract.range,
+                            &extract.preset(),
+                            &extract.limit,
+                            filter.as_ref().zip(unihan.as_deref()),
+                        )?;
+                        let options = ExtractOptions {
+                            normalize_direction: extract.normalize_direction,
+                            optimize_paths: extract.path_options(),
+                            char_names: extract.char_names,
+                            unihan: unihan.clone(),
+                            outline_metrics: extract.outline_metrics,
+                            reviews: None,
+                        };
+                        let (policy, mut glyphs) =
+                            apply_policy(extract.policy.as_deref(), &face, &mut codepoints, &options)?;
+                        glyphs.extend(extractor::extract_glyphs_parallel_with(&face, &codepoints, &options));
+                        eprintln!("{} extract {}: {} glyphs", label, name, glyphs.len());
+                        Arc::new(GlyphSet { step: extract.clone(), codepoints, options, glyphs, policy })
+                    }
+                };
+                sets.push((name, set));
+            }
+            Step::Svg(output) => {
+                let (name, set) = pipeline_set(&sets, output)?;
+                let dir = pipeline.output.join(name);
+                svg_writer::write_all_glyphs_parallel(&set.glyphs, &dir, upem, progress, None)?;
+                eprintln!("{} svg {}: {}", label, name, dir.display());
+            }
+            Step::Ufo(output) => {
+                let (name, set) = pipeline_set(&sets, output)?;
+                let ufo_path = pipeline.output.join(format!("{}.ufo", name));
+                ufo_writer::write_ufo_with_progress(&set.glyphs, &font_name, upem, &ufo_path, progress)?;
+                eprintln!("{} ufo {}: {}", label, name, ufo_path.display());
+            }
+            Step::Report(output) => {
+                let (name, set) = pipeline_set(&sets, output)?;
+                let report = FontReport {
+                    font_file: pipeline.font.display().to_string(),
+                    family_name: Some(font_name.clone()),
+                    units_per_em: upem,
+                    glyph_count: face.number_of_glyphs(),
+                    total_exported: set.glyphs.len(),
+                    glyphs: set.glyphs.clone(),
+                    direction_report: DirectionReport::from_glyphs(&set.glyphs),
+                    math: None,
+                    policy: set.policy.clone(),
+                };
+                let report_path = pipeline.output.join(format!("{}.json", name));
+                fs::write(&report_path, serde_json::to_string_pretty(&report)?)
+                    .with_context(|| format!("Failed to write report: {}", report_path.display()))?;
+                eprintln!("{} report {}: {}", label, name, report_path.display());
+            }
+            Step::ReproCheck(output) => {
+                let (name, set) = pipeline_set(&sets, output)?;
+                let report = repro::repro_check(&face, &set.codepoints, &set.options)?;
+                let report_path = pipeline.output.join(format!("{}.repro.json", name));
+                fs::write(&report_path, serde_json::to_string_pretty(&report)?)
+                    .with_context(|| format!("Failed to write report: {}", report_path.display()))?;
+                if !report.deterministic {
+                    anyhow::bail!("{} repro-check {}: not deterministic, see {}", label, name, report_path.display());
+                }
+                eprintln!("{} repro-check {}: deterministic", label, name);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_repro_check(
+    font: PathBuf,
+    chars: Option<String>,
+    range: Option<String>,
+    preset: Option<CharsetPreset>,
+    limit: Option<usize>,
+    options: ExtractOptions,
+) -> Result<()> {
+    let font_data = fs::read(&font).context("Failed to read font file")?;
+    let face = Face::parse(&font_data, 0).context("Failed to parse font")?;
+    let codepoints = get_codepoints(&face, &chars, &range, &preset, &limit, None)?;
+
+    let report = repro::repro_check(&face, &codepoints, &options)?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if !report.deterministic {
+        anyhow::bail!(
+            "Extraction is not deterministic: {} mismatched, {} missing, order {}",
+            report.mismatched.len(),
+            report.missing_in_parallel.len() + report.missing_in_serial.len(),
+            if report.order_differs { "differs" } else { "matches" }
+        );
+    }
+    Ok(())
+}
+
+fn run_coverage_from_document(document: PathBuf, font: PathBuf, only: Option<String>, format: String) -> Result<()> {
+    let (kind, mut usage) = document::extract_usage(&document)?;
+    if let Some(only) = &only {
+        usage.retain(|name, _| name.contains(only.as_str()));
+        if usage.is_empty() {
+            anyhow::bail!("No declared font matching {:?} in {}", only, document.display());
+        }
+    }
+
+    let font_data = fs::read(&font).context("Failed to read font file")?;
+    let face = Face::parse(&font_data, 0).context("Failed to parse font")?;
+    let fonts = document::check_coverage(&usage, &face);
+    let report = DocumentCoverage {
+        document: document.display().to_string(),
+        kind,
+        candidate: font.display().to_string(),
+        can_replace_all: fonts.iter().all(|f| f.can_replace),
+        fonts,
+    };
+
+    match format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "text" => {
+            for coverage in &report.fonts {
+                let verdict = match (coverage.can_replace, coverage.missing.is_empty()) {
+                    (true, _) => "✓",
+                    (false, true) => "?",
+                    (false, false) => "✗",
+                };
+                println!("{} {}: {}/{} characters", verdict, coverage.font, coverage.covered, coverage.characters);
+                if let Some(embedded) = &coverage.embedded {
+                    println!("    embedded: {}", embedded);
+                }
+                if coverage.undecodable_runs > 0 {
+                    println!("    {} text run(s) without a Unicode mapping were not checked", coverage.undecodable_runs);
+                }
+                for missing in coverage.missing.iter().take(20) {
+                    println!("    missing {}", missing);
+                }
+                if coverage.missing.len() > 20 {
+                    println!("    ... {} more (use --format json for all)", coverage.missing.len() - 20);
+                }
+            }
+        }
+        _ => anyhow::bail!("Invalid format: {}. Use 'json' or 'text'", format),
+    }
+
+    if !report.can_replace_all {
+        let blocked = report.fonts.iter().filter(|f| !f.can_replace).count();
+        anyhow::bail!("{} cannot replace {} declared font(s) in {}", font.display(), blocked, document.display());
+    }
+    Ok(())
+}
+
+fn run_vertical_check(font: PathBuf, tolerance: f64, format: String) -> Result<()> {
+    let font_data = fs::read(&font).context("Failed to read font file")?;
+    let face = Face::parse(&font_data, 0).context("Failed to parse font")?;
+    let report = vertical::check_vertical_forms(&face, &font.display().to_string(), tolerance);
+
+    match format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "text" => {
+            println!(
+                "vert: {} lookup(s), {} substitution(s), {} issue(s)",
+                report.vert_lookups, report.substitutions, report.issue_count
+            );
+            for form in report.forms.iter().filter(|f| f.issue.is_some()) {
+                let alternate = form.alternate_glyph.map_or("none".to_string(), |g| format!("gid {}", g));
+                let transform = match &form.transform {
+                    None => "no vertical form".to_string(),
+                    Some(Transform::Unchanged) => "identical copy".to_string(),
+                    Some(Transform::Shifted { dx, dy }) => format!("shifted by ({}, {})", dx, dy),
+                    Some(Transform::Rotated { degrees, mirrored, .. }) => {
+                        format!("rotated {}°{}", degrees, if *mirrored { " (mirrored)" } else { "" })
+                    }
+                    Some(Transform::Redrawn) => "redrawn".to_string(),
+                };
+                let issue = serde_json::to_value(form.issue)?;
+                println!(
+                    "✗ {} (gid {} → {}): {} [{}]",
+                    form.character,
+                    form.base_glyph,
+                    alternate,
+                    transform,
+                    issue.as_str().unwrap_or_default()
+                );
+            }
+        }
+        _ => anyhow::bail!("Invalid format: {}. Use 'json' or 'text'", format),
+    }
+
+    if report.issue_count > 0 {
+        anyhow::bail!("{} vertical form issue(s) in {}", report.issue_count, font.display());
+    }
+    Ok(())
+}
+
+/// Missing characters listed per language in text output
+const MISSING_PREVIEW: usize = 24;
+
+fn run_languages(font: PathBuf, locales: Vec<String>, format: String) -> Result<()> {
+    let font_data = fs::read(&font).context("Failed to read font file")?;
+    let face = Face::parse(&font_data, 0).context("Failed to parse font")?;
+    let report = languages::language_coverage(&face, &font.display().to_string(), &locales)?;
+
+    match format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "text" => {
+            println!(
+                "{} full, {} partial, {} unsupported ({} languages checked)",
+                report.full,
+                report.partial,
+                report.unsupported,
+                report.languages.len()
+            );
+            for language in report.languages.iter().filter(|l| l.support != Support::None) {
+                let mark = if language.support == Support::Full { "✓" } else { "~" };
+                println!(
+                    "{} {} [{}]: {}/{}",
+                    mark, language.name, language.locale, language.covered, language.required
+                );
+                if !language.missing.is_empty() {
+                    let chars: String = language
+                        .missing
+                        .iter()
+                        .take(MISSING_PREVIEW)
+                        .filter_map(|m| m.split_once(' ').map(|(_, c)| c))
+                        .collect();
+                    let more = language.missing.len().saturating_sub(MISSING_PREVIEW);
+                    if more > 0 {
+                        println!("    missing: {} (+{} more)", chars, more);
+                    } else {
+                        println!("    missing: {}", chars);
+                    }
+                }
+            }
+        }
+        _ => anyhow::bail!("Invalid format: {}. Use 'json' or 'text'", format),
+    }
+    Ok(())
+}
+
+fn run_style_check(font: PathBuf, min_confidence: f64, format: String) -> Result<()> {
+    let font_data = fs::read(&font).context("Failed to read font file")?;
+    let face = Face::parse(&font_data, 0).context("Failed to parse font")?;
+    let report = style::classify_style(&face, &font.display().to_string(), min_confidence);
+
+    match format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "text" => {
+            let line = |label: &str, estimate: &Option<Estimate>, describe: &dyn Fn(f64) -> String| match estimate {
+                None => println!("  {:<7} not measurable (no probe glyphs)", label),
+                Some(e) => println!(
+                    "{} {:<7} declared {}, estimated {} (confidence {:.2}, from {})",
+                    if e.contradicts { "✗" } else { "✓" },
+                    label,
+                    describe(e.declared),
+                    describe(e.estimated),
+                    e.confidence,
+                    e.glyphs
+                ),
+            };
+            line("weight", &report.weight, &|w| format!("{} {}", w, style::weight_name(w)));
+            line("width", &report.width, &|w| format!("{} {}", w, style::width_name(w as u16)));
+            line("slant", &report.slant, &|a| format!("{:.1}°", a));
+            for issue in &report.issues {
+                println!("  {}", issue);
+            }
         }
         _ => anyhow::bail!("Invalid format: {}. Use 'json' or 'text'", format),
     }
 
+    if !report.issues.is_empty() {
+        anyhow::bail!("{} style metadata contradiction(s) in {}", report.issues.len(), font.display());
+    }
+    Ok(())
+}
+
+fn run_render_compare(
+    font: PathBuf,
+    text: String,
+    sizes: Vec<f32>,
+    profiles: Vec<String>,
+    zoom: u32,
+    output: PathBuf,
+) -> Result<()> {
+    if let Some(size) = sizes.iter().find(|s| !(**s > 0.0 && **s <= 512.0)) {
+        anyhow::bail!("Invalid size: {}. Use a pixel size between 0 and 512", size);
+    }
+    let profiles = render::select_profiles(&profiles)?;
+    let font_data = fs::read(&font).context("Failed to read font file")?;
+    let face = Face::parse(&font_data, 0).context("Failed to parse font")?;
+    let missing: String = text.chars().filter(|c| face.glyph_index(*c).is_none()).collect();
+    if !missing.is_empty() {
+        eprintln!("Warning: not in font, skipped: {}", missing);
+    }
+
+    let report = render::render_compare(
+        &face,
+        &font.display().to_string(),
+        &text,
+        &sizes,
+        &profiles,
+        zoom,
+        &output,
+    )?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+/// Read a font file and locate one of its tables
+fn load_table(font: &PathBuf, tag: Tag) -> Result<(Vec<u8>, Option<std::ops::Range<usize>>)> {
+    let font_data = fs::read(font).with_context(|| format!("Failed to read font file: {}", font.display()))?;
+    let raw = RawFace::parse(&font_data, 0).context("Failed to parse font")?;
+    let range = raw
+        .table_records
+        .into_iter()
+        .find(|record| record.tag == tag)
+        .map(|record| record.offset as usize..record.offset as usize + record.length as usize)
+        .filter(|range| range.end <= font_data.len());
+    Ok((font_data, range))
+}
+
+fn run_table(action: TableCommand) -> Result<()> {
+    match action {
+        TableCommand::List { font } => {
+            let font_data = fs::read(&font).context("Failed to read font file")?;
+            let raw = RawFace::parse(&font_data, 0).context("Failed to parse font")?;
+            println!("{:<6} {:>10} {:>10} {:>10}", "Tag", "Offset", "Length", "Checksum");
+            for record in raw.table_records {
+                println!(
+                    "{:<6} {:>#10x} {:>10} {:>#10x}",
+                    record.tag.to_string(),
+                    record.offset,
+                    record.length,
+                    record.check_sum
+                );
+            }
+        }
+        TableCommand::Dump { tag, font, hex, max_bytes } => {
+            let tag = tables::parse_tag(&tag)?;
+            let (font_data, range) = load_table(&font, tag)?;
+            let range = range.with_context(|| format!("Table '{}' not found in {}", tag, font.display()))?;
+            let data = &font_data[range.clone()];
+
+            println!("'{}' at offset {:#x}, {} bytes", tag, range.start, data.len());
+            let fields = tables::table_fields(tag, data);
+            if let Some(fields) = &fields {
+                for field in fields {
+                    println!("  {:#06x}  {:<24} {}", field.offset, field.name, field.value);
+                }
+            }
+            if hex || fields.is_none() {
+                let shown = if max_bytes == 0 { data.len() } else { data.len().min(max_bytes) };
+                print!("{}", tables::hexdump(&data[..shown], 0));
+                if shown < data.len() {
+                    println!("... {} more bytes (use --max-bytes 0 for all)", data.len() - shown);
+                }
+            }
+        }
+        TableCommand::Diff { tag, a, b, max_ranges } => {
+            let tag = tables::parse_tag(&tag)?;
+            let (data_a, range_a) = load_table(&a, tag)?;
+            let (data_b, range_b) = load_table(&b, tag)?;
+            let (range_a, range_b) = match (range_a, range_b) {
+                (Some(ra), Some(rb)) => (ra, rb),
+                (None, None) => anyhow::bail!("Table '{}' is missing from both fonts", tag),
+                (None, _) => anyhow::bail!("Table '{}' is missing from {}", tag, a.display()),
+                (_, None) => anyhow::bail!("Table '{}' is missing from {}", tag, b.display()),
+            };
+            let (table_a, table_b) = (&data_a[range_a], &data_b[range_b]);
+
+            let diff = tables::diff_table(tag, table_a, table_b);
+            if diff.is_identical() {
+                println!("'{}' is identical ({} bytes)", tag, diff.len_a);
+                return Ok(());
+            }
+
+            println!("'{}' length: {} → {}", tag, diff.len_a, diff.len_b);
+            for change in &diff.fields {
+                println!(
+                    "  {:<24} {} → {}",
+                    change.name,
+                    change.a.as_deref().unwrap_or("(absent)"),
+                    change.b.as_deref().unwrap_or("(absent)")
+                );
+            }
+
+            println!("{} differing byte range(s)", diff.byte_ranges.len());
+            let shown = if max_ranges == 0 { diff.byte_ranges.len() } else { max_ranges };
+            for range in diff.byte_ranges.iter().take(shown) {
+                let end = range.end.min(range.start + 16);
+                println!("  {:#06x}..{:#06x}", range.start, range.end);
+                println!("    a: {}", tables::hex_bytes(&table_a[range.start..end]));
+                println!("    b: {}", tables::hex_bytes(&table_b[range.start..end]));
+            }
+            if diff.byte_ranges.len() > shown {
+                println!("  ... {} more (use --max-ranges 0 for all)", diff.byte_ranges.len() - shown);
+            }
+            if diff.len_a != diff.len_b {
+                let (longer, name) = if diff.len_a > diff.len_b { (table_a, "a") } else { (table_b, "b") };
+                let common = diff.len_a.min(diff.len_b);
+                println!("  {} extra bytes only in {} from {:#06x}", longer.len() - common, name, common);
+            }
+        }
+        TableCommand::CompareTtx { ttx: ttx_path, font, format } => {
+            let text = fs::read_to_string(&ttx_path)
+                .with_context(|| format!("Failed to read TTX: {}", ttx_path.display()))?;
+            let font_data = fs::read(&font).context("Failed to read font file")?;
+            let report = ttx::compare_ttx(&text, &font_data)?;
+
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+                "text" => {
+                    println!("Compared: {}", report.compared.join(", "));
+                    if !report.skipped.is_empty() {
+                        println!("Skipped (no structured reader): {}", report.skipped.join(", "));
+                    }
+                    if !report.missing_in_font.is_empty() {
+                        println!("Missing in font: {}", report.missing_in_font.join(", "));
+                    }
+                    for difference in &report.differences {
+                        println!(
+                            "  {} {}: ttx {} ≠ font {}",
+                            difference.table,
+                            difference.field,
+                            difference.ttx.as_deref().unwrap_or("(absent)"),
+                            difference.font.as_deref().unwrap_or("(absent)")
+                        );
+                    }
+                }
+                _ => anyhow::bail!("Invalid format: {}. Use 'json' or 'text'", format),
+            }
+
+            if !report.matches() {
+                anyhow::bail!(
+                    "TTX does not match font: {} difference(s), {} missing table(s)",
+                    report.differences.len(),
+                    report.missing_in_font.len()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_review(action: ReviewCommand) -> Result<()> {
+    match action {
+        ReviewCommand::Set { font, chars, status, tags, comment, author, sidecar } => {
+            let font_data = fs::read(&font).context("Failed to read font file")?;
+            let face = Face::parse(&font_data, 0).context("Failed to parse font")?;
+            let sidecar = sidecar.unwrap_or_else(|| review::sidecar_path(&font));
+            let mut sheet = if sidecar.exists() { ReviewSheet::load(&sidecar)? } else { ReviewSheet::default() };
+            sheet.font = font.display().to_string();
+
+            for c in chars.chars() {
+                let glyph_id = face.glyph_index(c).with_context(|| format!("'{}' is not mapped in the font", c))?;
+                let entry = sheet.glyphs.entry(review::glyph_key(c)).or_default();
+                if let Some(status) = status {
+                    entry.status = status;
+                }
+                for tag in &tags {
+                    if !entry.tags.contains(tag) {
+                        entry.tags.push(tag.clone());
+                    }
+                }
+                if let Some(text) = &comment {
+                    entry.comments.push(ReviewComment { author: author.clone(), text: text.clone() });
+                }
+                // The review now speaks for the outline as it is
+                entry.outline_hash = review::outline_hash(&face, glyph_id);
+                entry.outline_changed = false;
+            }
+            sheet.save(&sidecar)?;
+            eprintln!("Updated {} glyphs in {}", chars.chars().count(), sidecar.display());
+        }
+        ReviewCommand::Show { font, sidecar, format } => {
+            let font_data = fs::read(&font).context("Failed to read font file")?;
+            let face = Face::parse(&font_data, 0).context("Failed to parse font")?;
+            let sidecar = sidecar.unwrap_or_else(|| review::sidecar_path(&font));
+            let mut sheet = ReviewSheet::load(&sidecar)?;
+
+            sheet.flag_changed(&review::outline_hashes(&face));
+
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&sheet)?),
+                "text" => {
+                    for (key, glyph) in &sheet.glyphs {
+                        let c = review::parse_key(key).unwrap_or('?');
+                        let edited = if glyph.outline_changed { "  (outline changed since review)" } else { "" };
+                        println!("{} {}  {}{}", key, c, glyph.status.as_str(), edited);
+                        if !glyph.tags.is_empty() {
+                            println!("    tags: {}", glyph.tags.join(", "));
+                        }
+                        for comment in &glyph.comments {
+                            match &comment.author {
+                                Some(author) => println!("    {}: {}", author, comment.text),
+                                None => println!("    {}", comment.text),
+                            }
+                        }
+                    }
+                }
+                other => anyhow::bail!("Unknown format '{}': use text or json", other),
+            }
+        }
+        ReviewCommand::Carry { font, from, output } => {
+            let font_data = fs::read(&font).context("Failed to read font file")?;
+            let face = Face::parse(&font_data, 0).context("Failed to parse font")?;
+            let old = ReviewSheet::load(&from)?;
+            let (carried, report) = old.carry_forward(&font.display().to_string(), &review::outline_hashes(&face));
+
+            let output = output.unwrap_or_else(|| review::sidecar_path(&font));
+            carried.save(&output)?;
+            eprintln!(
+                "Carried {} reviews to {}: {} unchanged, {} outlines changed, {} moved, {} orphaned",
+                carried.glyphs.len(),
+                output.display(),
+                report.kept,
+                report.outline_changed.len(),
+                report.moved.len(),
+                report.orphaned.len()
+            );
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+    }
     Ok(())
 }
 
@@ -312,6 +1455,19 @@ fn main() -> Result<()> {
             json_only,
             progress,
             parallel,
+            normalize_direction,
+            optimize_paths,
+            optimize_tolerance,
+            arcs,
+            char_names,
+            unihan,
+            outline_metrics,
+            filter,
+            max_memory,
+            split_paths_over,
+            math,
+            review,
+            policy,
         } => run_extract(ExtractConfig {
             font,
             output,
@@ -323,7 +1479,58 @@ fn main() -> Result<()> {
             json_only,
             progress,
             parallel,
+            normalize_direction,
+            optimize_paths: optimize_paths.then_some(PathOptimizeOptions {
+                tolerance: optimize_tolerance,
+                arcs,
+            }),
+            char_names,
+            unihan,
+            outline_metrics,
+            filter,
+            max_memory,
+            split_paths_over,
+            math,
+            review,
+            policy,
         }),
-        Commands::Info { font, format } => run_info(font, format),
+        Commands::Info { font, format, tables } => run_info(font, format, tables),
+        Commands::Run { pipeline, progress } => run_pipeline(pipeline, progress),
+        Commands::ReproCheck {
+            font,
+            chars,
+            range,
+            preset,
+            limit,
+            normalize_direction,
+            optimize_paths,
+            optimize_tolerance,
+            arcs,
+        } => run_repro_check(
+            font,
+            chars,
+            range,
+            preset,
+            limit,
+            ExtractOptions {
+                normalize_direction,
+                optimize_paths: optimize_paths.then_some(PathOptimizeOptions {
+                    tolerance: optimize_tolerance,
+                    arcs,
+                }),
+                ..Default::default()
+            },
+        ),
+        Commands::VerticalCheck { font, tolerance, format } => run_vertical_check(font, tolerance, format),
+        Commands::CoverageFromDocument { document, font, only, format } => {
+            run_coverage_from_document(document, font, only, format)
+        }
+        Commands::Languages { font, locales, format } => run_languages(font, locales, format),
+        Commands::StyleCheck { font, min_confidence, format } => run_style_check(font, min_confidence, format),
+        Commands::RenderCompare { font, text, sizes, profiles, zoom, output } => {
+            run_render_compare(font, text, sizes, profiles, zoom, output)
+        }
+        Commands::Table { action } => run_table(action),
+        Commands::Review { action } => run_review(action),
     }
 }
@@ -0,0 +1,384 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use ttf_parser::math::{self, Constants, GlyphConstruction, GlyphConstructions, Kern, MathValues};
+use ttf_parser::{Face, GlyphId};
+
+/// A glyph referenced by the MATH table
+///
+/// Most math glyphs (size variants, assembly parts) have no character of
+/// their own, so the glyph name is usually the more useful label.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlyphRef {
+    pub glyph_id: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// `U+XXXX` when the glyph is mapped from a character
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unicode: Option<String>,
+}
+
+/// A per-glyph value: italic correction or top accent attachment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlyphValue {
+    #[serde(flatten)]
+    pub glyph: GlyphRef,
+    pub value: i16,
+}
+
+/// Staircase kern at one corner of a glyph
+///
+/// `kerns[i]` applies below `heights[i]`; the last kern applies above every height.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MathKern {
+    pub heights: Vec<i16>,
+    pub kerns: Vec<i16>,
+}
+
+/// Cut-in kerns at the four corners of a glyph, used to tuck scripts in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlyphKerns {
+    #[serde(flatten)]
+    pub glyph: GlyphRef,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_right: Option<MathKern>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_left: Option<MathKern>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bottom_right: Option<MathKern>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bottom_left: Option<MathKern>,
+}
+
+/// A pre-drawn larger size of a stretchy glyph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizeVariant {
+    #[serde(flatten)]
+    pub glyph: GlyphRef,
+    /// Advance along the stretch direction, in font units
+    pub advance: u16,
+}
+
+/// One piece of a glyph assembly
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssemblyPart {
+    #[serde(flatten)]
+    pub glyph: GlyphRef,
+    pub start_connector_length: u16,
+    pub end_connector_length: u16,
+    pub full_advance: u16,
+    /// Extenders may be repeated (or left out) to reach the target size
+    pub extender: bool,
+}
+
+/// Recipe for building a stretchy glyph larger than its biggest variant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Assembly {
+    pub italics_correction: i16,
+    /// Parts in stretch order: bottom to top, or left to right
+    pub parts: Vec<AssemblyPart>,
+}
+
+/// How a stretchy glyph grows in one direction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Construction {
+    #[serde(flatten)]
+    pub glyph: GlyphRef,
+    /// Size variants, smallest first (the first is usually the glyph itself)
+    pub variants: Vec<SizeVariant>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assembly: Option<Assembly>,
+}
+
+/// Contents of an OpenType MATH table, in font units
+///
+/// Device-table corrections are not reported; values are the design-size ones.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MathReport {
+    /// MathConstants fields by their snake_case spec name (`axis_height`, `script_percent_scale_down`, …)
+    pub constants: BTreeMap<String, i32>,
+    pub italic_corrections: Vec<GlyphValue>,
+    pub top_accent_attachments: Vec<GlyphValue>,
+    /// Glyphs treated as extended shapes for superscript placement
+    pub extended_shapes: Vec<GlyphRef>,
+    pub kerns: Vec<GlyphKerns>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_connector_overlap: Option<u16>,
+    pub vertical_constructions: Vec<Construction>,
+    pub horizontal_constructions: Vec<Construction>,
+}
+
+/// Extract the MATH table of a font
+///
+/// # Arguments
+/// * `face` - Parsed font face
+/// * `only` - Restrict per-glyph sections to these base glyphs; constants are always reported
+///
+/// # Returns
+/// `None` when the font has no MATH table
+pub fn extract_math(face: &Face, only: Option<&BTreeSet<GlyphId>>) -> Option<MathReport> {
+    let table = face.tables().math?;
+
+    let mut characters = HashMap::new();
+    if let Some(cmap) = face.tables().cmap {
+        for subtable in cmap.subtables.into_iter().filter(|st| st.is_unicode()) {
+            subtable.codepoints(|cp| {
+                if let Some(glyph) = subtable.glyph_index(cp) {
+                    characters.entry(glyph).and_modify(|c: &mut u32| *c = (*c).min(cp)).or_insert(cp);
+                }
+            });
+        }
+    }
+
+    let label = |glyph: GlyphId| GlyphRef {
+        glyph_id: glyph.0,
+        name: face.glyph_name(glyph).map(str::to_string),
+        unicode: characters.get(&glyph).map(|cp| format!("U+{:04X}", cp)),
+    };
+    let glyphs: Vec<GlyphId> = match only {
+        Some(set) => set.iter().copied().collect(),
+        None => (0..face.number_of_glyphs()).map(GlyphId).collect(),
+    };
+    Some(math_report(&table, &glyphs, &label))
+}
+
+/// Collect every section of `table` for the given base glyphs
+fn math_report(table: &math::Table, glyphs: &[GlyphId], label: &dyn Fn(GlyphId) -> GlyphRef) -> MathReport {
+    let mut report = MathReport {
+        constants: table.constants.map(constants).unwrap_or_default(),
+        ..Default::default()
+    };
+
+    if let Some(info) = table.glyph_info {
+        report.italic_corrections = glyph_values(info.italic_corrections, glyphs, label);
+        report.top_accent_attachments = glyph_values(info.top_accent_attachments, glyphs, label);
+        if let Some(coverage) = info.extended_shapes {
+            report.extended_shapes = glyphs.iter().filter(|&&g| coverage.contains(g)).map(|&g| label(g)).collect();
+        }
+        if let Some(kern_infos) = info.kern_infos {
+            report.kerns = glyphs
+                .iter()
+                .filter_map(|&g| {
+                    let info = kern_infos.get(g)?;
+                    Some(GlyphKerns {
+                        glyph: label(g),
+                        top_right: info.top_right.as_ref().map(math_kern),
+                        top_left: info.top_left.as_ref().map(math_kern),
+                        bottom_right: info.bottom_right.as_ref().map(math_kern),
+                        bottom_left: info.bottom_left.as_ref().map(math_kern),
+                    })
+                })
+                .collect();
+        }
+    }
+
+    if let Some(variants) = table.variants {
+        report.min_connector_overlap = Some(variants.min_connector_overlap);
+        report.vertical_constructions = constructions(variants.vertical_constructions, glyphs, label);
+        report.horizontal_constructions = constructions(variants.horizontal_constructions, glyphs, label);
+    }
+
+    report
+}
+
+/// Name and value of every MathConstants field
+macro_rules! named_constants {
+    ($c:expr, [$($int:ident),* $(,)?], [$($record:ident),* $(,)?]) => {
+        [
+            $((stringify!($int), i32::from($c.$int())),)*
+            $((stringify!($record), i32::from($c.$record().value)),)*
+        ]
+    };
+}
+
+fn constants(c: Constants) -> BTreeMap<String, i32> {
+    let named = named_constants!(
+        c,
+        [
+            script_percent_scale_down,
+            script_script_percent_scale_down,
+            delimited_sub_formula_min_height,
+            display_operator_min_height,
+            radical_degree_bottom_raise_percent,
+        ],
+        [
+            math_leading,
+            axis_height,
+            accent_base_height,
+            flattened_accent_base_height,
+            subscript_shift_down,
+            subscript_top_max,
+            subscript_baseline_drop_min,
+            superscript_shift_up,
+            superscript_shift_up_cramped,
+            superscript_bottom_min,
+            superscript_baseline_drop_max,
+            sub_superscript_gap_min,
+            superscript_bottom_max_with_subscript,
+            space_after_script,
+            upper_limit_gap_min,
+            upper_limit_baseline_rise_min,
+            lower_limit_gap_min,
+            lower_limit_baseline_drop_min,
+            stack_top_shift_up,
+            stack_top_display_style_shift_up,
+            stack_bottom_shift_down,
+            stack_bottom_display_style_shift_down,
+            stack_gap_min,
+            stack_display_style_gap_min,
+            stretch_stack_top_shift_up,
+            stretch_stack_bottom_shift_down,
+            stretch_stack_gap_above_min,
+            stretch_stack_gap_below_min,
+            fraction_numerator_shift_up,
+            fraction_numerator_display_style_shift_up,
+            fraction_denominator_shift_down,
+            fraction_denominator_display_style_shift_down,
+            fraction_numerator_gap_min,
+            fraction_num_display_style_gap_min,
+            fraction_rule_thickness,
+            fraction_denominator_gap_min,
+            fraction_denom_display_style_gap_min,
+            skewed_fraction_horizontal_gap,
+            skewed_fraction_vertical_gap,
+            overbar_vertical_gap,
+            overbar_rule_thickness,
+            overbar_extra_ascender,
+            underbar_vertical_gap,
+            underbar_rule_thickness,
+            underbar_extra_descender,
+            radical_vertical_gap,
+            radical_display_style_vertical_gap,
+            radical_rule_thickness,
+            radical_extra_ascender,
+            radical_kern_before_degree,
+            radical_kern_after_degree,
+        ]
+    );
+    named.into_iter().map(|(name, value)| (name.to_string(), value)).collect()
+}
+
+fn glyph_values(values: Option<MathValues>, glyphs: &[GlyphId], label: &dyn Fn(GlyphId) -> GlyphRef) -> Vec<GlyphValue> {
+    let Some(values) = values else {
+        return Vec::new();
+    };
+    glyphs
+        .iter()
+        .filter_map(|&g| Some(GlyphValue { glyph: label(g), value: values.get(g)?.value }))
+        .collect()
+}
+
+fn math_kern(kern: &Kern) -> MathKern {
+    let count = kern.count();
+    MathKern {
+        heights: (0..count).filter_map(|i| kern.height(i)).map(|v| v.value).collect(),
+        kerns: (0..=count).filter_map(|i| kern.kern(i)).map(|v| v.value).collect(),
+    }
+}
+
+fn constructions(
+    table: GlyphConstructions,
+    glyphs: &[GlyphId],
+    label: &dyn Fn(GlyphId) -> GlyphRef,
+) -> Vec<Construction> {
+    glyphs
+        .iter()
+        .filter_map(|&g| Some(construction(g, table.get(g)?, label)))
+        .collect()
+}
+
+fn construction(glyph: GlyphId, construction: GlyphConstruction, label: &dyn Fn(GlyphId) -> GlyphRef) -> Construction {
+    Construction {
+        glyph: label(glyph),
+        variants: construction
+            .variants
+            .into_iter()
+            .map(|v| SizeVariant { glyph: label(v.variant_glyph), advance: v.advance_measurement })
+            .collect(),
+        assembly: construction.assembly.map(|assembly| Assembly {
+            italics_correction: assembly.italics_correction.value,
+            parts: assembly
+                .parts
+                .into_iter()
+                .map(|part| AssemblyPart {
+                    glyph: label(part.glyph_id),
+                    start_connector_length: part.start_connector_length,
+                    end_connector_length: part.end_connector_length,
+                    full_advance: part.full_advance,
+                    extender: part.part_flags.extender(),
+                })
+                .collect(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(out: &mut Vec<u8>, values: &[u16]) {
+        for v in values {
+            out.extend_from_slice(&v.to_be_bytes());
+        }
+    }
+
+    /// MATH table with constants and one vertical construction for glyph 5
+    fn sample_table() -> Vec<u8> {
+        let mut data = Vec::new();
+        // Header: version 1.0, constants at 10, no glyph info, variants at 224
+        push(&mut data, &[1, 0, 10, 0, 224]);
+        let mut constants = vec![0u8; 214];
+        constants[0..2].copy_from_slice(&70u16.to_be_bytes()); // script_percent_scale_down
+        constants[12..14].copy_from_slice(&250u16.to_be_bytes()); // axis_height
+        data.extend(constants);
+        // Variants: overlap 20, vertical coverage at +12, no horizontal, 1 + 0 constructions at +18
+        push(&mut data, &[20, 12, 0, 1, 0, 18]);
+        // Coverage format 1: glyph 5
+        push(&mut data, &[1, 1, 5]);
+        // Construction: assembly at +12, two variants
+        push(&mut data, &[12, 2, 5, 900, 6, 1400]);
+        // Assembly: italics correction 30, one extender part (glyph 7)
+        push(&mut data, &[30, 0, 1, 7, 100, 100, 600, 1]);
+        data
+    }
+
+    fn label(glyph: GlyphId) -> GlyphRef {
+        GlyphRef { glyph_id: glyph.0, name: None, unicode: None }
+    }
+
+    #[test]
+    fn math_report_should_name_constants() {
+        let data = sample_table();
+        let table = math::Table::parse(&data).unwrap();
+        let report = math_report(&table, &[], &label);
+
+        assert_eq!(report.constants.len(), 56);
+        assert_eq!(report.constants["script_percent_scale_down"], 70);
+        assert_eq!(report.constants["axis_height"], 250);
+        assert_eq!(report.constants["fraction_rule_thickness"], 0);
+        assert!(report.vertical_constructions.is_empty());
+    }
+
+    #[test]
+    fn math_report_should_read_variants_and_assembly() {
+        let data = sample_table();
+        let table = math::Table::parse(&data).unwrap();
+        let glyphs: Vec<GlyphId> = (0..10).map(GlyphId).collect();
+        let report = math_report(&table, &glyphs, &label);
+
+        assert_eq!(report.min_connector_overlap, Some(20));
+        assert!(report.horizontal_constructions.is_empty());
+        let [construction] = report.vertical_constructions.as_slice() else {
+            panic!("expected one vertical construction");
+        };
+        assert_eq!(construction.glyph.glyph_id, 5);
+        assert_eq!(
+            construction.variants.iter().map(|v| (v.glyph.glyph_id, v.advance)).collect::<Vec<_>>(),
+            vec![(5, 900), (6, 1400)]
+        );
+        let assembly = construction.assembly.as_ref().unwrap();
+        assert_eq!(assembly.italics_correction, 30);
+        assert_eq!(assembly.parts.len(), 1);
+        assert_eq!(assembly.parts[0].glyph.glyph_id, 7);
+        assert!(assembly.parts[0].extender);
+    }
+}
@@ -1,24 +1,62 @@
 // Authors: Joysusy & Violet Klaudia 💖
 //! Font Inspector MCP Server — JSON-RPC 2.0 over stdio
-//! Provides 5 tools: extract_glyph, extract_all, convert_ufo, compare_glyphs, analyze_metrics
+//! Provides 8 tools: extract_glyph, extract_all, convert_ufo, compare_glyphs, analyze_metrics, analyze_math,
+//! analyze_languages, analyze_style
 //! Stateful: caches parsed font data in memory for fast repeated access
 
 use anyhow::{Context, Result};
+use clap::Parser;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::io::{self, BufRead, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
+use font_inspector::char_filter::CharFilter;
+use font_inspector::char_names::Unihan;
 use font_inspector::extractor;
+use font_inspector::languages;
+use font_inspector::math;
+use font_inspector::memory::{BudgetExceeded, MemoryBudget};
+use font_inspector::server_policy::{PolicyViolation, RateLimiter, ServerPolicy};
+use font_inspector::style;
 use font_inspector::svg_writer;
 use font_inspector::ufo_writer;
-use font_inspector::types::UnicodeRange;
+use font_inspector::path_optimizer::{self, PathOptimizeOptions};
+use font_inspector::types::{DirectionReport, ExtractOptions, UnicodeRange};
 
 const SERVER_NAME: &str = "font-inspector-mcp";
 const SERVER_VERSION: &str = "2.0.0";
 const PROTOCOL_VERSION: &str = "2024-11-05";
 
+/// JSON-RPC error code for requests refused by the memory budget
+const BUDGET_EXCEEDED: i64 = -32001;
+/// JSON-RPC error code for requests refused by the operator policy
+const POLICY_DENIED: i64 = -32002;
+
+#[derive(Parser)]
+#[command(name = "font-inspector-mcp", version = SERVER_VERSION)]
+struct ServerArgs {
+    /// Memory budget (e.g. 512M, 2G); oversized requests are refused or batched
+    #[arg(long, value_parser = MemoryBudget::parse)]
+    max_memory: Option<MemoryBudget>,
+    /// Unihan_*.txt file or directory for stroke counts and readings in char_names
+    #[arg(long)]
+    unihan: Option<PathBuf>,
+    /// JSON policy file: disabled tools, read-only mode, glyph caps, rate limits
+    #[arg(long)]
+    config: Option<PathBuf>,
+}
+
+/// Server-wide settings shared by every tool call
+struct ServerContext {
+    budget: Option<MemoryBudget>,
+    unihan: Option<Arc<Unihan>>,
+    policy: ServerPolicy,
+}
+
 #[derive(Deserialize)]
 #[allow(dead_code)]
 struct JsonRpcRequest {
@@ -49,17 +87,28 @@ struct JsonRpcError {
 
 struct FontCache {
     data: HashMap<PathBuf, Vec<u8>>,
+    budget: Option<MemoryBudget>,
 }
 
 impl FontCache {
-    fn new() -> Self {
-        Self { data: HashMap::new() }
+    fn new(budget: Option<MemoryBudget>) -> Self {
+        Self { data: HashMap::new(), budget }
+    }
+
+    fn cached_bytes(&self) -> usize {
+        self.data.values().map(Vec::len).sum()
     }
 
     fn load_font(&mut self, path: &Path) -> Result<&[u8]> {
         if !self.data.contains_key(path) {
             let bytes = std::fs::read(path)
                 .with_context(|| format!("Failed to read font: {}", path.display()))?;
+            // Cached fonts may use at most half the budget; the rest is for extraction
+            if let Some(budget) = &self.budget
+                && self.cached_bytes() + bytes.len() > budget.bytes() / 2
+            {
+                self.data.clear();
+            }
             self.data.insert(path.to_path_buf(), bytes);
         }
         Ok(self.data.get(path).unwrap())
@@ -71,10 +120,14 @@ fn make_response(id: Value, result: Value) -> JsonRpcResponse {
 }
 
 fn make_error(id: Value, code: i64, message: String) -> JsonRpcResponse {
+    make_error_with_data(id, code, message, None)
+}
+
+fn make_error_with_data(id: Value, code: i64, message: String, data: Option<Value>) -> JsonRpcResponse {
     JsonRpcResponse {
         jsonrpc: "2.0".into(), id,
         result: None,
-        error: Some(JsonRpcError { code, message, data: None }),
+        error: Some(JsonRpcError { code, message, data }),
     }
 }
 
@@ -93,78 +146,130 @@ fn handle_initialize(id: Value) -> JsonRpcResponse {
     }))
 }
 
-fn handle_tools_list(id: Value) -> JsonRpcResponse {
-    make_response(id, json!({
-        "tools": [
-            {
-                "name": "extract_glyph",
-                "description": "Extract a single glyph from a font file as SVG path data with metrics",
-                "inputSchema": {
-                    "type": "object",
-                    "properties": {
-                        "font_path": { "type": "string", "description": "Absolute path to font file (.ttf, .otf)" },
-                        "character": { "type": "string", "description": "Single character to extract (e.g. 'A' or '你')" }
-                    },
-                    "required": ["font_path", "character"]
-                }
-            },
-            {
-                "name": "extract_all",
-                "description": "Extract multiple glyphs from a font. Supports character lists, Unicode ranges, and presets (latin, cjk-basic, cjk-common, cjk-full)",
-                "inputSchema": {
-                    "type": "object",
-                    "properties": {
-                        "font_path": { "type": "string", "description": "Absolute path to font file" },
-                        "chars": { "type": "string", "description": "Characters to extract (e.g. 'Hello你好')" },
-                        "range": { "type": "string", "description": "Unicode range (e.g. '0x4E00-0x4EFF')" },
-                        "preset": { "type": "string", "description": "Preset: latin, latin-extended, cjk-basic, cjk-common, cjk-full" },
-                        "limit": { "type": "integer", "description": "Max glyphs to extract" },
-                        "output_dir": { "type": "string", "description": "Directory to write SVG files (optional, returns JSON if omitted)" }
-                    },
-                    "required": ["font_path"]
-                }
-            },
-            {
-                "name": "convert_ufo",
-                "description": "Convert extracted glyphs to UFO (Unified Font Object) format for editing in font editors",
-                "inputSchema": {
-                    "type": "object",
-                    "properties": {
-                        "font_path": { "type": "string", "description": "Absolute path to font file" },
-                        "output_path": { "type": "string", "description": "Output UFO directory path" },
-                        "chars": { "type": "string", "description": "Characters to include" },
-                        "range": { "type": "string", "description": "Unicode range" },
-                        "preset": { "type": "string", "description": "Preset name" }
-                    },
-                    "required": ["font_path", "output_path"]
-                }
-            },
-            {
-                "name": "compare_glyphs",
-                "description": "Compare the same character across two fonts, returning SVG paths and metrics for both",
-                "inputSchema": {
-                    "type": "object",
-                    "properties": {
-                        "font_a": { "type": "string", "description": "Path to first font" },
-                        "font_b": { "type": "string", "description": "Path to second font" },
-                        "characters": { "type": "string", "description": "Characters to compare (e.g. 'ABCabc')" }
-                    },
-                    "required": ["font_a", "font_b", "characters"]
-                }
-            },
-            {
-                "name": "analyze_metrics",
-                "description": "Analyze font metadata: family name, UPM, glyph count, variable font status, ascender/descender, CFF/glyf tables",
-                "inputSchema": {
-                    "type": "object",
-                    "properties": {
-                        "font_path": { "type": "string", "description": "Absolute path to font file" }
-                    },
-                    "required": ["font_path"]
-                }
+fn handle_tools_list(id: Value, policy: &ServerPolicy) -> JsonRpcResponse {
+    let mut tools = json!([
+        {
+            "name": "extract_glyph",
+            "description": "Extract a single glyph from a font file as SVG path data with metrics",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "font_path": { "type": "string", "description": "Absolute path to font file (.ttf, .otf)" },
+                    "character": { "type": "string", "description": "Single character to extract (e.g. 'A' or '你')" },
+                    "normalize_direction": { "type": "boolean", "description": "Rewind contours to outer CCW / inner CW (default false)" },
+                    "optimize_paths": { "type": "boolean", "description": "Emit compact SVG path data (collinear collapse, S/T shorthands)" },
+                    "optimize_tolerance": { "type": "number", "description": "Max deviation for optimize_paths in font units (default 0.5)" },
+                    "arcs": { "type": "boolean", "description": "With optimize_paths, replace circular curves with SVG arcs" },
+                    "char_names": { "type": "boolean", "description": "Add Unicode names (and Unihan strokes/radicals/readings when the server has --unihan)" },
+                    "outline_metrics": { "type": "boolean", "description": "Add signed contour areas, ink centroid and offset from the advance center" }
+                },
+                "required": ["font_path", "character"]
             }
-        ]
-    }))
+        },
+        {
+            "name": "extract_all",
+            "description": "Extract multiple glyphs from a font. Supports character lists, Unicode ranges, and presets (latin, cjk-basic, cjk-common, cjk-full)",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "font_path": { "type": "string", "description": "Absolute path to font file" },
+                    "chars": { "type": "string", "description": "Characters to extract (e.g. 'Hello你好')" },
+                    "range": { "type": "string", "description": "Unicode range (e.g. '0x4E00-0x4EFF')" },
+                    "preset": { "type": "string", "description": "Preset: latin, latin-extended, cjk-basic, cjk-common, cjk-full" },
+                    "limit": { "type": "integer", "description": "Max glyphs to extract" },
+                    "output_dir": { "type": "string", "description": "Directory to write SVG files (optional, returns JSON if omitted)" },
+                    "normalize_direction": { "type": "boolean", "description": "Rewind contours to outer CCW / inner CW and report flipped glyphs" },
+                    "optimize_paths": { "type": "boolean", "description": "Emit compact SVG path data (collinear collapse, S/T shorthands)" },
+                    "optimize_tolerance": { "type": "number", "description": "Max deviation for optimize_paths in font units (default 0.5)" },
+                    "arcs": { "type": "boolean", "description": "With optimize_paths, replace circular curves with SVG arcs" },
+                    "char_names": { "type": "boolean", "description": "Add Unicode names (and Unihan strokes/radicals/readings when the server has --unihan)" },
+                    "outline_metrics": { "type": "boolean", "description": "Add signed contour areas, ink centroid and offset from the advance center" },
+                    "filter": { "type": "string", "description": "Unihan filter such as 'strokes<=8' or 'radical=85,strokes<12' (server needs --unihan)" }
+                },
+                "required": ["font_path"]
+            }
+        },
+        {
+            "name": "convert_ufo",
+            "description": "Convert extracted glyphs to UFO (Unified Font Object) format for editing in font editors",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "font_path": { "type": "string", "description": "Absolute path to font file" },
+                    "output_path": { "type": "string", "description": "Output UFO directory path" },
+                    "chars": { "type": "string", "description": "Characters to include" },
+                    "range": { "type": "string", "description": "Unicode range" },
+                    "preset": { "type": "string", "description": "Preset name" }
+                },
+                "required": ["font_path", "output_path"]
+            }
+        },
+        {
+            "name": "compare_glyphs",
+            "description": "Compare the same character across two fonts, returning SVG paths and metrics for both",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "font_a": { "type": "string", "description": "Path to first font" },
+                    "font_b": { "type": "string", "description": "Path to second font" },
+                    "characters": { "type": "string", "description": "Characters to compare (e.g. 'ABCabc')" }
+                },
+                "required": ["font_a", "font_b", "characters"]
+            }
+        },
+        {
+            "name": "analyze_metrics",
+            "description": "Analyze font metadata: family name, UPM, glyph count, variable font status, ascender/descender, CFF/glyf tables",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "font_path": { "type": "string", "description": "Absolute path to font file" }
+                },
+                "required": ["font_path"]
+            }
+        },
+        {
+            "name": "analyze_math",
+            "description": "Read the OpenType MATH table: layout constants, italic corrections, top accent positions, cut-in kerns and stretchy glyph variants/assemblies",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "font_path": { "type": "string", "description": "Absolute path to font file" },
+                    "characters": { "type": "string", "description": "Only report per-glyph data for these characters (e.g. '∫√(' ); constants are always included" }
+                },
+                "required": ["font_path"]
+            }
+        },
+        {
+            "name": "analyze_languages",
+            "description": "Estimate language support from CLDR exemplar characters: full, partial (with the missing characters) or unsupported",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "font_path": { "type": "string", "description": "Absolute path to font file" },
+                    "locales": { "type": "string", "description": "Comma-separated CLDR locales to check (e.g. 'de,pl,vi,sr-Latn'); default all" }
+                },
+                "required": ["font_path"]
+            }
+        },
+        {
+            "name": "analyze_style",
+            "description": "Estimate weight, width and slant from outlines with confidence scores and flag contradictions with usWeightClass, usWidthClass and italicAngle",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "font_path": { "type": "string", "description": "Absolute path to font file" },
+                    "min_confidence": { "type": "number", "description": "Only flag contradictions at least this confident, 0-1 (default: 0.5)" }
+                },
+                "required": ["font_path"]
+            }
+        }
+    ]);
+
+    if let Some(list) = tools.as_array_mut() {
+        list.retain(|tool| tool["name"].as_str().is_some_and(|name| policy.is_listed(name)));
+    }
+    make_response(id, json!({ "tools": tools }))
 }
 
 fn handle_resources_list(id: Value) -> JsonRpcResponse {
@@ -180,17 +285,33 @@ fn handle_resources_list(id: Value) -> JsonRpcResponse {
     }))
 }
 
-fn resolve_codepoints(params: &Value, font_bytes: &[u8]) -> Result<Vec<u32>> {
+fn resolve_codepoints(params: &Value, font_bytes: &[u8], unihan: Option<&Arc<Unihan>>) -> Result<Vec<u32>> {
     let face = ttf_parser::Face::parse(font_bytes, 0)
         .map_err(|e| anyhow::anyhow!("Failed to parse font: {}", e))?;
 
+    let filter = match params.get("filter").and_then(|v| v.as_str()) {
+        Some(expr) => {
+            let unihan = unihan.ok_or_else(|| anyhow::anyhow!("filter requires the server to be started with --unihan"))?;
+            Some((CharFilter::parse(expr)?, unihan))
+        }
+        None => None,
+    };
+    let apply_filter = |cps: &mut Vec<u32>| {
+        if let Some((filter, unihan)) = &filter {
+            filter.retain(cps, unihan);
+        }
+    };
+
     if let Some(chars) = params.get("chars").and_then(|v| v.as_str()) {
-        return Ok(chars.chars().map(|c| c as u32).collect());
+        let mut cps: Vec<u32> = chars.chars().map(|c| c as u32).collect();
+        apply_filter(&mut cps);
+        return Ok(cps);
     }
 
     if let Some(range_str) = params.get("range").and_then(|v| v.as_str()) {
         let range = UnicodeRange::parse(range_str)?;
         let mut cps: Vec<u32> = (range.start..=range.end).collect();
+        apply_filter(&mut cps);
         if let Some(limit) = params.get("limit").and_then(|v| v.as_u64()) {
             cps.truncate(limit as usize);
         }
@@ -204,6 +325,7 @@ fn resolve_codepoints(params: &Value, font_bytes: &[u8]) -> Result<Vec<u32>> {
         let limit = params.get("limit").and_then(|v| v.as_u64()).map(|l| l as usize)
             .or_else(|| preset.get_limit());
         let mut cps: Vec<u32> = (range.start..=range.end).collect();
+        apply_filter(&mut cps);
         if let Some(lim) = limit {
             cps.truncate(lim);
         }
@@ -212,13 +334,35 @@ fn resolve_codepoints(params: &Value, font_bytes: &[u8]) -> Result<Vec<u32>> {
 
     let _glyph_count = face.number_of_glyphs();
     let mut cps: Vec<u32> = (0x20u32..=0x7E).collect();
+    apply_filter(&mut cps);
     if let Some(limit) = params.get("limit").and_then(|v| v.as_u64()) {
         cps.truncate(limit as usize);
     }
     Ok(cps)
 }
 
-fn tool_extract_glyph(params: &Value, cache: &mut FontCache) -> Result<Value> {
+fn extract_options(params: &Value, unihan: Option<&Arc<Unihan>>) -> ExtractOptions {
+    ExtractOptions {
+        normalize_direction: params.get("normalize_direction").and_then(|v| v.as_bool()).unwrap_or(false),
+        optimize_paths: params
+            .get("optimize_paths")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+            .then(|| PathOptimizeOptions {
+                tolerance: params
+                    .get("optimize_tolerance")
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or(path_optimizer::DEFAULT_TOLERANCE),
+                arcs: params.get("arcs").and_then(|v| v.as_bool()).unwrap_or(false),
+            }),
+        char_names: params.get("char_names").and_then(|v| v.as_bool()).unwrap_or(false),
+        unihan: unihan.cloned(),
+        outline_metrics: params.get("outline_metrics").and_then(|v| v.as_bool()).unwrap_or(false),
+        reviews: None,
+    }
+}
+
+fn tool_extract_glyph(params: &Value, cache: &mut FontCache, ctx: &ServerContext) -> Result<Value> {
     let font_path = PathBuf::from(
         params.get("font_path").and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing font_path"))?
@@ -235,7 +379,7 @@ fn tool_extract_glyph(params: &Value, cache: &mut FontCache) -> Result<Value> {
     let glyph_id = face.glyph_index(ch)
         .ok_or_else(|| anyhow::anyhow!("Character '{}' not found in font", ch))?;
 
-    match extractor::extract_glyph(&face, glyph_id, ch) {
+    match extractor::extract_glyph_with(&face, glyph_id, ch, &extract_options(params, ctx.unihan.as_ref())) {
         Some(glyph) => {
             let json_str = serde_json::to_string_pretty(&glyph)?;
             Ok(make_text_content(&json_str))
@@ -244,7 +388,7 @@ fn tool_extract_glyph(params: &Value, cache: &mut FontCache) -> Result<Value> {
     }
 }
 
-fn tool_extract_all(params: &Value, cache: &mut FontCache) -> Result<Value> {
+fn tool_extract_all(params: &Value, cache: &mut FontCache, ctx: &ServerContext) -> Result<Value> {
     let font_path = PathBuf::from(
         params.get("font_path").and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing font_path"))?
@@ -254,21 +398,49 @@ fn tool_extract_all(params: &Value, cache: &mut FontCache) -> Result<Value> {
     let face = ttf_parser::Face::parse(font_bytes, 0)
         .map_err(|e| anyhow::anyhow!("Failed to parse font: {}", e))?;
 
-    let codepoints = resolve_codepoints(params, font_bytes)?;
-    let glyphs = extractor::extract_glyphs_parallel(&face, &codepoints);
+    let codepoints = resolve_codepoints(params, font_bytes, ctx.unihan.as_ref())?;
+    ctx.policy.check_glyph_count(codepoints.len())?;
+    let options = extract_options(params, ctx.unihan.as_ref());
+    let output_dir = params.get("output_dir").and_then(|v| v.as_str());
+
+    // Over-budget requests are batched to disk, or refused when the result
+    // would have to be returned inline
+    let mut batch_size = codepoints.len().max(1);
+    if let Some(budget) = &ctx.budget {
+        let plan = budget.plan(&face, &codepoints, &options);
+        if output_dir.is_none() {
+            budget.check(&plan)?;
+        }
+        batch_size = plan.batch_size;
+    }
 
-    if let Some(output_dir) = params.get("output_dir").and_then(|v| v.as_str()) {
+    if let Some(output_dir) = output_dir {
         let out_path = PathBuf::from(output_dir);
-        svg_writer::write_all_glyphs(&glyphs, &out_path, face.units_per_em(), false)?;
+        let mut total_exported = 0;
+        let mut direction_report: Option<DirectionReport> = None;
+        for batch in codepoints.chunks(batch_size) {
+            let glyphs = extractor::extract_glyphs_parallel_with(&face, batch, &options);
+            svg_writer::write_all_glyphs(&glyphs, &out_path, face.units_per_em(), false, None)?;
+            total_exported += glyphs.len();
+            if let Some(batch_report) = DirectionReport::from_glyphs(&glyphs) {
+                match &mut direction_report {
+                    Some(total) => total.absorb(batch_report),
+                    None => direction_report = Some(batch_report),
+                }
+            }
+        }
 
         let report = json!({
             "font_file": font_path.display().to_string(),
-            "total_exported": glyphs.len(),
+            "total_exported": total_exported,
             "output_directory": output_dir,
-            "units_per_em": face.units_per_em()
+            "units_per_em": face.units_per_em(),
+            "direction_report": direction_report
         });
         Ok(make_text_content(&serde_json::to_string_pretty(&report)?))
     } else {
+        let glyphs = extractor::extract_glyphs_parallel_with(&face, &codepoints, &options);
+        let direction_report = DirectionReport::from_glyphs(&glyphs);
         let report = font_inspector::types::FontReport {
             font_file: font_path.display().to_string(),
             family_name: face.names().into_iter()
@@ -278,12 +450,15 @@ fn tool_extract_all(params: &Value, cache: &mut FontCache) -> Result<Value> {
             glyph_count: face.number_of_glyphs(),
             total_exported: glyphs.len(),
             glyphs,
+            direction_report,
+            math: None,
+            policy: None,
         };
         Ok(make_text_content(&serde_json::to_string_pretty(&report)?))
     }
 }
 
-fn tool_convert_ufo(params: &Value, cache: &mut FontCache) -> Result<Value> {
+fn tool_convert_ufo(params: &Value, cache: &mut FontCache, ctx: &ServerContext) -> Result<Value> {
     let font_path = PathBuf::from(
         params.get("font_path").and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing font_path"))?
@@ -297,8 +472,27 @@ fn tool_convert_ufo(params: &Value, cache: &mut FontCache) -> Result<Value> {
     let face = ttf_parser::Face::parse(font_bytes, 0)
         .map_err(|e| anyhow::anyhow!("Failed to parse font: {}", e))?;
 
-    let codepoints = resolve_codepoints(params, font_bytes)?;
-    let glyphs = extractor::extract_glyphs_parallel(&face, &codepoints);
+    let codepoints = resolve_codepoints(params, font_bytes, None)?;
+    ctx.policy.check_glyph_count(codepoints.len())?;
+    let options = ExtractOptions::default();
+    let batch_size = ctx
+        .budget
+        .as_ref()
+        .map(|b| b.plan(&face, &codepoints, &options).batch_size)
+        .unwrap_or(codepoints.len().max(1));
+
+    // UFO glyphs carry no outline data, so paths are dropped batch by batch
+    let mut glyphs = Vec::with_capacity(codepoints.len());
+    for batch in codepoints.chunks(batch_size) {
+        glyphs.extend(
+            extractor::extract_glyphs_parallel_with(&face, batch, &options)
+                .into_iter()
+                .map(|mut g| {
+                    g.svg_path = String::new();
+                    g
+                }),
+        );
+    }
 
     let font_name = face.names().into_iter()
         .find(|n| n.name_id == ttf_parser::name_id::FAMILY)
@@ -405,11 +599,64 @@ fn tool_analyze_metrics(params: &Value, cache: &mut FontCache) -> Result<Value>
         "underline_position": face.underline_metrics().map(|m| m.position),
         "underline_thickness": face.underline_metrics().map(|m| m.thickness),
         "is_monospaced": face.is_monospaced(),
-        "italic_angle": face.italic_angle()
+        "italic_angle": face.italic_angle(),
+        "has_math": face.tables().math.is_some()
     });
     Ok(make_text_content(&serde_json::to_string_pretty(&metadata)?))
 }
 
+fn tool_analyze_math(params: &Value, cache: &mut FontCache) -> Result<Value> {
+    let font_path = PathBuf::from(
+        params.get("font_path").and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing font_path"))?
+    );
+
+    let font_bytes = cache.load_font(&font_path)?;
+    let face = ttf_parser::Face::parse(font_bytes, 0)
+        .map_err(|e| anyhow::anyhow!("Failed to parse font: {}", e))?;
+
+    // Characters the font does not map have no MATH data and are skipped
+    let only: Option<BTreeSet<ttf_parser::GlyphId>> = params.get("characters").and_then(|v| v.as_str())
+        .map(|chars| chars.chars().filter_map(|c| face.glyph_index(c)).collect());
+
+    let report = math::extract_math(&face, only.as_ref())
+        .ok_or_else(|| anyhow::anyhow!("{} has no MATH table", font_path.display()))?;
+    Ok(make_text_content(&serde_json::to_string_pretty(&report)?))
+}
+
+fn tool_analyze_languages(params: &Value, cache: &mut FontCache) -> Result<Value> {
+    let font_path = PathBuf::from(
+        params.get("font_path").and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing font_path"))?
+    );
+    let locales: Vec<String> = params.get("locales").and_then(|v| v.as_str())
+        .map(|s| s.split(',').map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect())
+        .unwrap_or_default();
+
+    let font_bytes = cache.load_font(&font_path)?;
+    let face = ttf_parser::Face::parse(font_bytes, 0)
+        .map_err(|e| anyhow::anyhow!("Failed to parse font: {}", e))?;
+
+    let report = languages::language_coverage(&face, &font_path.display().to_string(), &locales)?;
+    Ok(make_text_content(&serde_json::to_string_pretty(&report)?))
+}
+
+fn tool_analyze_style(params: &Value, cache: &mut FontCache) -> Result<Value> {
+    let font_path = PathBuf::from(
+        params.get("font_path").and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing font_path"))?
+    );
+    let min_confidence = params.get("min_confidence").and_then(|v| v.as_f64())
+        .unwrap_or(style::DEFAULT_MIN_CONFIDENCE);
+
+    let font_bytes = cache.load_font(&font_path)?;
+    let face = ttf_parser::Face::parse(font_bytes, 0)
+        .map_err(|e| anyhow::anyhow!("Failed to parse font: {}", e))?;
+
+    let report = style::classify_style(&face, &font_path.display().to_string(), min_confidence);
+    Ok(make_text_content(&serde_json::to_string_pretty(&report)?))
+}
+
 fn handle_resource_read(id: Value, params: &Value, cache: &mut FontCache) -> JsonRpcResponse {
     let uri = match params.get("uri").and_then(|v| v.as_str()) {
         Some(u) => u,
@@ -467,24 +714,49 @@ fn handle_resource_read(id: Value, params: &Value, cache: &mut FontCache) -> Jso
     }
 }
 
-fn handle_tool_call(id: Value, params: &Value, cache: &mut FontCache) -> JsonRpcResponse {
+fn handle_tool_call(
+    id: Value,
+    params: &Value,
+    cache: &mut FontCache,
+    ctx: &ServerContext,
+    limiter: &mut RateLimiter,
+) -> JsonRpcResponse {
     let tool_name = match params.get("name").and_then(|v| v.as_str()) {
         Some(n) => n,
         None => return make_error(id, -32602, "Missing tool name".into()),
     };
     let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
 
+    let admitted = ctx.policy.check_call(tool_name, &arguments).and_then(|()| {
+        let limit = ctx.policy.rate_limits.get(tool_name).copied();
+        limiter.check(tool_name, limit, Instant::now())
+    });
+    if let Err(violation) = admitted {
+        return make_error_with_data(id, POLICY_DENIED, violation.to_string(), serde_json::to_value(&violation).ok());
+    }
+
     let result = match tool_name {
-        "extract_glyph" => tool_extract_glyph(&arguments, cache),
-        "extract_all" => tool_extract_all(&arguments, cache),
-        "convert_ufo" => tool_convert_ufo(&arguments, cache),
+        "extract_glyph" => tool_extract_glyph(&arguments, cache, ctx),
+        "extract_all" => tool_extract_all(&arguments, cache, ctx),
+        "convert_ufo" => tool_convert_ufo(&arguments, cache, ctx),
         "compare_glyphs" => tool_compare_glyphs(&arguments, cache),
         "analyze_metrics" => tool_analyze_metrics(&arguments, cache),
+        "analyze_math" => tool_analyze_math(&arguments, cache),
+        "analyze_languages" => tool_analyze_languages(&arguments, cache),
+        "analyze_style" => tool_analyze_style(&arguments, cache),
         _ => return make_error(id, -32601, format!("Unknown tool: {}", tool_name)),
     };
 
     match result {
         Ok(content) => make_response(id, content),
+        Err(e) if e.is::<BudgetExceeded>() => {
+            let exceeded = e.downcast_ref::<BudgetExceeded>().unwrap();
+            make_error_with_data(id, BUDGET_EXCEEDED, e.to_string(), serde_json::to_value(exceeded).ok())
+        }
+        Err(e) if e.is::<PolicyViolation>() => {
+            let violation = e.downcast_ref::<PolicyViolation>().unwrap();
+            make_error_with_data(id, POLICY_DENIED, e.to_string(), serde_json::to_value(violation).ok())
+        }
         Err(e) => make_response(id, json!({
             "content": [{ "type": "text", "text": format!("Error: {}", e) }],
             "isError": true
@@ -492,10 +764,27 @@ fn handle_tool_call(id: Value, params: &Value, cache: &mut FontCache) -> JsonRpc
     }
 }
 
+fn load_context(args: &ServerArgs) -> Result<ServerContext> {
+    Ok(ServerContext {
+        budget: args.max_memory,
+        unihan: args.unihan.as_deref().map(Unihan::load).transpose()?.map(Arc::new),
+        policy: args.config.as_deref().map(ServerPolicy::load).transpose()?.unwrap_or_default(),
+    })
+}
+
 fn main() {
+    let args = ServerArgs::parse();
     let stdin = io::stdin();
     let mut stdout = io::stdout();
-    let mut cache = FontCache::new();
+    let mut cache = FontCache::new(args.max_memory);
+    let mut limiter = RateLimiter::new();
+    let ctx = match load_context(&args) {
+        Ok(ctx) => ctx,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
 
     for line in stdin.lock().lines() {
         let line = match line {
@@ -523,8 +812,8 @@ fn main() {
             "initialize" => handle_initialize(id),
             "initialized" => continue,
             "notifications/initialized" => continue,
-            "tools/list" => handle_tools_list(id),
-            "tools/call" => handle_tool_call(id, &request.params, &mut cache),
+            "tools/list" => handle_tools_list(id, &ctx.policy),
+            "tools/call" => handle_tool_call(id, &request.params, &mut cache, &ctx, &mut limiter),
             "resources/list" => handle_resources_list(id),
             "resources/read" => handle_resource_read(id, &request.params, &mut cache),
             "ping" => make_response(id, json!({})),
@@ -0,0 +1,162 @@
+use crate::extractor;
+use crate::types::{CharAnnotation, ExtractOptions, GlyphInfo, OutlineMetrics};
+use serde::Serialize;
+use thiserror::Error;
+use ttf_parser::Face;
+
+/// Number of glyphs sampled to estimate the per-glyph footprint
+const SAMPLE_SIZE: usize = 64;
+
+/// Fallback footprint when no sampled glyph has an outline
+const DEFAULT_GLYPH_BYTES: usize = 512;
+
+/// Extracted glyphs are held twice in the in-memory path: as `GlyphInfo`
+/// and again inside the pretty-printed JSON report.
+const REPORT_OVERHEAD_FACTOR: usize = 2;
+
+/// Request refused because it would exceed the memory budget
+#[derive(Debug, Error, Serialize)]
+#[error("request needs ~{estimated_bytes} bytes, over the {budget_bytes} byte memory budget (try limit <= {suggested_limit})")]
+pub struct BudgetExceeded {
+    pub estimated_bytes: usize,
+    pub budget_bytes: usize,
+    pub suggested_limit: usize,
+}
+
+/// Upper bound on memory used by extraction
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryBudget {
+    bytes: usize,
+}
+
+/// How an extraction should run under a budget
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractionPlan {
+    pub estimated_bytes: usize,
+    pub glyph_bytes: usize,
+    /// Glyphs per batch; equals the total when everything fits in memory
+    pub batch_size: usize,
+    /// Whether results must be streamed to sinks instead of collected
+    pub streaming: bool,
+}
+
+impl MemoryBudget {
+    pub fn new(bytes: usize) -> Self {
+        Self { bytes }
+    }
+
+    /// Parse a size such as `800000`, `512K`, `256M` or `2G` (binary units)
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        let upper = s.to_ascii_uppercase();
+        let digits = upper.trim_end_matches(['B', 'I']);
+        let (number, multiplier) = match digits.chars().last() {
+            Some('K') => (&digits[..digits.len() - 1], 1usize << 10),
+            Some('M') => (&digits[..digits.len() - 1], 1 << 20),
+            Some('G') => (&digits[..digits.len() - 1], 1 << 30),
+            _ => (digits, 1),
+        };
+
+        let value: usize = number
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid memory size: {}. Expected e.g. 512M or 2G", s))?;
+        if value == 0 {
+            anyhow::bail!("Memory budget must be greater than zero");
+        }
+
+        Ok(Self::new(value.saturating_mul(multiplier)))
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Plan an extraction of `codepoints` from `face` within this budget
+    pub fn plan(&self, face: &Face, codepoints: &[u32], options: &ExtractOptions) -> ExtractionPlan {
+        let glyph_bytes = estimate_glyph_bytes(face, codepoints, options);
+        let estimated_bytes = glyph_bytes
+            .saturating_mul(REPORT_OVERHEAD_FACTOR)
+            .saturating_mul(codepoints.len());
+
+        if estimated_bytes <= self.bytes {
+            return ExtractionPlan {
+                estimated_bytes,
+                glyph_bytes,
+                batch_size: codepoints.len().max(1),
+                streaming: false,
+            };
+        }
+
+        ExtractionPlan {
+            estimated_bytes,
+            glyph_bytes,
+            batch_size: (self.bytes / glyph_bytes).max(1),
+            streaming: true,
+        }
+    }
+
+    /// Refuse plans that cannot be streamed and do not fit in memory
+    pub fn check(&self, plan: &ExtractionPlan) -> Result<(), BudgetExceeded> {
+        if !plan.streaming {
+            return Ok(());
+        }
+        Err(BudgetExceeded {
+            estimated_bytes: plan.estimated_bytes,
+            budget_bytes: self.bytes,
+            suggested_limit: self.bytes / (plan.glyph_bytes * REPORT_OVERHEAD_FACTOR),
+        })
+    }
+}
+
+/// Approximate heap + struct size of one extracted glyph
+pub fn glyph_footprint(glyph: &GlyphInfo) -> usize {
+    std::mem::size_of::<GlyphInfo>()
+        + glyph.svg_path.len()
+        + glyph.glyph_name.len()
+        + glyph.unicode.len()
+        + glyph.unicode_char.len()
+        + glyph.annotation.as_ref().map_or(0, |a| {
+            std::mem::size_of::<CharAnnotation>()
+                + a.name.as_ref().map_or(0, String::len)
+                + a.mandarin.as_ref().map_or(0, String::len)
+        })
+        + glyph.outline_metrics.as_ref().map_or(0, |m| {
+            std::mem::size_of::<OutlineMetrics>() + m.contour_areas.len() * std::mem::size_of::<f64>()
+        })
+}
+
+/// Estimate the average glyph footprint by extracting an evenly spaced sample
+fn estimate_glyph_bytes(face: &Face, codepoints: &[u32], options: &ExtractOptions) -> usize {
+    if codepoints.is_empty() {
+        return DEFAULT_GLYPH_BYTES;
+    }
+
+    let step = (codepoints.len() / SAMPLE_SIZE).max(1);
+    let sample: Vec<u32> = codepoints.iter().step_by(step).take(SAMPLE_SIZE).copied().collect();
+    let glyphs = extractor::extract_glyphs_parallel_with(face, &sample, options);
+
+    if glyphs.is_empty() {
+        return DEFAULT_GLYPH_BYTES;
+    }
+    glyphs.iter().map(glyph_footprint).sum::<usize>() / glyphs.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_should_accept_binary_suffixes() {
+        assert_eq!(MemoryBudget::parse("1024").unwrap().bytes(), 1024);
+        assert_eq!(MemoryBudget::parse("512K").unwrap().bytes(), 512 << 10);
+        assert_eq!(MemoryBudget::parse("256MiB").unwrap().bytes(), 256 << 20);
+        assert_eq!(MemoryBudget::parse("2g").unwrap().bytes(), 2 << 30);
+    }
+
+    #[test]
+    fn parse_should_reject_zero_and_garbage() {
+        assert!(MemoryBudget::parse("0").is_err());
+        assert!(MemoryBudget::parse("lots").is_err());
+    }
+}
@@ -0,0 +1,308 @@
+use kurbo::{BezPath, PathEl, Point, Shape};
+
+use crate::types::OutlineMetrics;
+
+/// Flattening tolerance for centroid integration, in font units
+const CENTROID_TOLERANCE: f64 = 0.01;
+
+/// Render a path in font units as SVG path data
+///
+/// Note: Y-axis is flipped because font coordinate system has Y pointing up,
+/// while SVG has Y pointing down.
+pub fn to_svg_path(path: &BezPath) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::with_capacity(256); // Pre-allocate for typical glyph
+    for el in path.elements() {
+        let _ = match *el {
+            PathEl::MoveTo(p) => write!(out, "M {:.2} {:.2} ", p.x, -p.y),
+            PathEl::LineTo(p) => write!(out, "L {:.2} {:.2} ", p.x, -p.y),
+            // TrueType quadratic Bézier → SVG Q command
+            PathEl::QuadTo(p1, p) => {
+                write!(out, "Q {:.2} {:.2} {:.2} {:.2} ", p1.x, -p1.y, p.x, -p.y)
+            }
+            // CFF cubic Bézier → SVG C command
+            PathEl::CurveTo(p1, p2, p) => write!(
+                out,
+                "C {:.2} {:.2} {:.2} {:.2} {:.2} {:.2} ",
+                p1.x, -p1.y, p2.x, -p2.y, p.x, -p.y
+            ),
+            PathEl::ClosePath => write!(out, "Z "),
+        };
+    }
+    out
+}
+
+/// Split a glyph outline into one path per contour
+pub fn split_contours(path: &BezPath) -> Vec<BezPath> {
+    let mut contours = Vec::new();
+    let mut current = BezPath::new();
+
+    for el in path.elements() {
+        if matches!(el, PathEl::MoveTo(_)) && !current.elements().is_empty() {
+            contours.push(std::mem::take(&mut current));
+        }
+        current.push(*el);
+    }
+    if !current.elements().is_empty() {
+        contours.push(current);
+    }
+
+    contours
+}
+
+/// Join contours back into a single glyph outline
+pub fn join_contours(contours: &[BezPath]) -> BezPath {
+    let mut path = BezPath::new();
+    for contour in contours {
+        for el in contour.elements() {
+            path.push(*el);
+        }
+    }
+    path
+}
+
+/// Indices of the other contours enclosing each contour
+///
+/// A contour counts as enclosed when its bounding box lies inside the other
+/// contour's box and its start point has a non-zero winding number there.
+/// The box check keeps overlapping (but not nested) outers at depth 0.
+fn enclosing(contours: &[BezPath]) -> Vec<Vec<usize>> {
+    let boxes: Vec<_> = contours.iter().map(|c| c.bounding_box()).collect();
+
+    contours
+        .iter()
+        .enumerate()
+        .map(|(i, contour)| {
+            let Some(start) = contour.elements().first().and_then(|el| el.end_point()) else {
+                return Vec::new();
+            };
+            contours
+                .iter()
+                .enumerate()
+                .filter(|&(j, other)| {
+                    j != i
+                        && boxes[j].union(boxes[i]) == boxes[j]
+                        && other.winding(start) != 0
+                })
+                .map(|(j, _)| j)
+                .collect()
+        })
+        .collect()
+}
+
+/// Number of other contours enclosing each contour
+fn nesting_depths(contours: &[BezPath]) -> Vec<usize> {
+    enclosing(contours).iter().map(Vec::len).collect()
+}
+
+/// Group contours so each group fills correctly on its own
+///
+/// Every outer contour (even nesting depth) is grouped with the holes it
+/// directly encloses; islands inside a hole start their own group. Groups
+/// are ordered by their outer contour, contours within a group by index.
+///
+/// # Returns
+/// Contour indices per group
+pub fn contour_groups(contours: &[BezPath]) -> Vec<Vec<usize>> {
+    let enclosing = enclosing(contours);
+    let depth = |i: usize| enclosing[i].len();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of = vec![usize::MAX; contours.len()];
+
+    for i in (0..contours.len()).filter(|&i| depth(i) % 2 == 0) {
+        group_of[i] = groups.len();
+        groups.push(vec![i]);
+    }
+    for i in (0..contours.len()).filter(|&i| depth(i) % 2 == 1) {
+        // The enclosing outer one level up; an inconsistent nesting falls back to its own group
+        match enclosing[i].iter().find(|&&j| depth(j) + 1 == depth(i)) {
+            Some(&outer) => groups[group_of[outer]].push(i),
+            None => groups.push(vec![i]),
+        }
+    }
+    for group in &mut groups {
+        group.sort_unstable();
+    }
+    groups.sort_by_key(|group| group[0]);
+    groups
+}
+
+/// Normalize contour winding to the non-zero fill convention
+///
+/// Outer contours (even nesting depth) are made counter-clockwise and inner
+/// contours (odd depth) clockwise, measured in font units with Y up.
+/// Degenerate contours with zero area are left untouched.
+///
+/// # Returns
+/// Number of contours whose direction was reversed
+pub fn normalize_winding(contours: &mut [BezPath]) -> usize {
+    let depths = nesting_depths(contours);
+    let mut flipped = 0;
+
+    for (contour, depth) in contours.iter_mut().zip(depths) {
+        let area = contour.area();
+        if area == 0.0 {
+            continue;
+        }
+        let want_ccw = depth % 2 == 0;
+        if (area > 0.0) != want_ccw {
+            *contour = contour.reverse_subpaths();
+            flipped += 1;
+        }
+    }
+
+    flipped
+}
+
+/// Signed area and first moments (∫x dA, ∫y dA) of one contour
+///
+/// Green's theorem over the flattened contour; positive for counter-clockwise.
+/// The polygon area is returned alongside the moments so the centroid divides
+/// like by like; the exact area comes from [`Shape::area`].
+fn area_moments(contour: &BezPath) -> (f64, f64, f64) {
+    let (mut area, mut mx, mut my) = (0.0, 0.0, 0.0);
+    let mut start = Point::ZERO;
+    let mut last = Point::ZERO;
+    let mut edge = |a: Point, b: Point| {
+        let cross = a.x * b.y - b.x * a.y;
+        area += cross / 2.0;
+        mx += (a.x + b.x) * cross / 6.0;
+        my += (a.y + b.y) * cross / 6.0;
+    };
+    kurbo::flatten(contour.elements().iter().copied(), CENTROID_TOLERANCE, |el| match el {
+        PathEl::MoveTo(p) => {
+            start = p;
+            last = p;
+        }
+        PathEl::LineTo(p) => {
+            edge(last, p);
+            last = p;
+        }
+        PathEl::ClosePath => {
+            edge(last, start);
+            last = start;
+        }
+        _ => {}
+    });
+    // Contours are closed implicitly when the outline omits the final segment
+    if last != start {
+        edge(last, start);
+    }
+    (area, mx, my)
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Measure contour areas and the ink centroid of a glyph outline
+///
+/// Contours are summed with their signs, so counters subtract from the ink as
+/// long as they wind opposite to their outer contour; overlapping contours of
+/// the same direction are counted twice.
+///
+/// # Arguments
+/// * `path` - Glyph outline in font units (Y up)
+/// * `advance_width` - Horizontal advance, whose midpoint is the reference center
+///
+/// # Returns
+/// `None` when the outline encloses no area
+pub fn outline_metrics(path: &BezPath, advance_width: u16) -> Option<OutlineMetrics> {
+    let contours = split_contours(path);
+    let areas: Vec<f64> = contours.iter().map(|c| c.area()).collect();
+    let area: f64 = areas.iter().sum();
+    let (polygon_area, mx, my) = contours
+        .iter()
+        .map(area_moments)
+        .fold((0.0, 0.0, 0.0), |(a, x, y), (da, dx, dy)| (a + da, x + dx, y + dy));
+    if area.abs() < f64::EPSILON || polygon_area.abs() < f64::EPSILON {
+        return None;
+    }
+
+    let centroid_x = mx / polygon_area;
+    Some(OutlineMetrics {
+        contour_areas: areas.into_iter().map(round2).collect(),
+        ink_area: round2(area.abs()),
+        ink_centroid_x: round2(centroid_x),
+        ink_centroid_y: round2(my / polygon_area),
+        center_offset: round2(centroid_x - f64::from(advance_width) / 2.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64, ccw: bool) -> BezPath {
+        let mut path = BezPath::new();
+        path.move_to((x, y));
+        if ccw {
+            path.line_to((x + size, y));
+            path.line_to((x + size, y + size));
+            path.line_to((x, y + size));
+        } else {
+            path.line_to((x, y + size));
+            path.line_to((x + size, y + size));
+            path.line_to((x + size, y));
+        }
+        path.close_path();
+        path
+    }
+
+    #[test]
+    fn normalize_winding_should_make_outer_ccw_and_inner_cw() {
+        // Both contours drawn clockwise, as in a TrueType "O"
+        let mut contours = vec![square(0.0, 0.0, 100.0, false), square(25.0, 25.0, 50.0, false)];
+
+        let flipped = normalize_winding(&mut contours);
+
+        assert_eq!(flipped, 1);
+        assert!(contours[0].area() > 0.0);
+        assert!(contours[1].area() < 0.0);
+    }
+
+    #[test]
+    fn normalize_winding_should_keep_already_normalized_outlines() {
+        let mut contours = vec![square(0.0, 0.0, 100.0, true), square(25.0, 25.0, 50.0, false)];
+        assert_eq!(normalize_winding(&mut contours), 0);
+    }
+
+    #[test]
+    fn outline_metrics_should_subtract_counters_from_the_ink() {
+        // Square ring: 100×100 outer, 50×50 counter off to the right
+        let path = join_contours(&[square(0.0, 0.0, 100.0, true), square(40.0, 25.0, 50.0, false)]);
+
+        let metrics = outline_metrics(&path, 100).unwrap();
+
+        assert_eq!(metrics.contour_areas, vec![10000.0, -2500.0]);
+        assert_eq!(metrics.ink_area, 7500.0);
+        // (10000·50 − 2500·65) / 7500
+        assert_eq!(metrics.ink_centroid_x, 45.0);
+        assert_eq!(metrics.ink_centroid_y, 50.0);
+        assert_eq!(metrics.center_offset, -5.0);
+    }
+
+    #[test]
+    fn outline_metrics_should_integrate_curves_and_ignore_winding_convention() {
+        // Clockwise (TrueType-style) circle of radius 50 centered at (300, 200)
+        let circle = kurbo::Circle::new((300.0, 200.0), 50.0).to_path(0.01).reverse_subpaths();
+
+        let metrics = outline_metrics(&circle, 500).unwrap();
+
+        assert!(metrics.contour_areas[0] < 0.0);
+        assert!((metrics.ink_area - std::f64::consts::PI * 2500.0).abs() < 1.0);
+        assert!((metrics.ink_centroid_x - 300.0).abs() < 0.01);
+        assert!((metrics.ink_centroid_y - 200.0).abs() < 0.01);
+        assert!((metrics.center_offset - 50.0).abs() < 0.01);
+        assert!(outline_metrics(&BezPath::new(), 500).is_none());
+    }
+
+    #[test]
+    fn split_and_join_contours_should_round_trip() {
+        let path = join_contours(&[square(0.0, 0.0, 10.0, true), square(20.0, 0.0, 10.0, true)]);
+        let contours = split_contours(&path);
+        assert_eq!(contours.len(), 2);
+        assert_eq!(join_contours(&contours), path);
+    }
+}
@@ -0,0 +1,349 @@
+use kurbo::{Affine, BezPath, CubicBez, ParamCurve, PathEl, Point, QuadBez};
+use std::f64::consts::{PI, TAU};
+
+/// Tolerance used when none is configured, in font units
+pub const DEFAULT_TOLERANCE: f64 = 0.5;
+
+/// Settings for SVG path optimization
+#[derive(Debug, Clone, Copy)]
+pub struct PathOptimizeOptions {
+    /// Maximum allowed deviation from the original outline, in font units
+    pub tolerance: f64,
+    /// Replace curves that follow a circle with SVG `A` commands
+    pub arcs: bool,
+}
+
+impl Default for PathOptimizeOptions {
+    fn default() -> Self {
+        Self { tolerance: DEFAULT_TOLERANCE, arcs: false }
+    }
+}
+
+/// Intermediate SVG command in Y-down coordinates
+#[derive(Debug, Clone, Copy)]
+enum Cmd {
+    Move(Point),
+    Line(Point),
+    Quad(Point, Point),
+    SmoothQuad(Point),
+    Cubic(Point, Point, Point),
+    SmoothCubic(Point, Point),
+    Arc { radius: f64, large: bool, sweep: bool, to: Point },
+    Close,
+}
+
+/// Circle segment fitted to a single curve
+#[derive(Debug, Clone, Copy)]
+struct ArcFit {
+    center: Point,
+    radius: f64,
+    /// Swept angle in radians, always positive
+    span: f64,
+    sweep: bool,
+    to: Point,
+}
+
+/// One output command with the point it starts from
+#[derive(Debug, Clone, Copy)]
+struct Seg {
+    cmd: Cmd,
+    from: Point,
+    /// Arc equivalent of a curve, when one fits within tolerance
+    arc: Option<ArcFit>,
+}
+
+/// Render a path in font units as optimized SVG path data
+///
+/// Collinear line runs are collapsed, curves whose first control point
+/// mirrors the previous one become `S`/`T`, and (optionally) curves that
+/// follow a circle within tolerance become `A` commands where that is
+/// shorter. Numbers drop trailing zeros. The Y-axis is flipped like the
+/// unoptimized output.
+pub fn optimize_svg_path(path: &BezPath, options: &PathOptimizeOptions) -> String {
+    let path = Affine::FLIP_Y * path.clone();
+    let tolerance = options.tolerance.max(0.0);
+
+    let mut segs: Vec<Seg> = Vec::with_capacity(path.elements().len());
+    let mut current = Point::ZERO;
+    let mut contour_start = Point::ZERO;
+
+    for el in path.elements() {
+        let (cmd, arc) = match *el {
+            PathEl::MoveTo(p) => {
+                contour_start = p;
+                (Cmd::Move(p), None)
+            }
+            PathEl::LineTo(p) => {
+                if p == current {
+                    continue;
+                }
+                if let Some(Seg { cmd: Cmd::Line(mid), from, .. }) = segs.last()
+                    && is_collinear(*from, *mid, p, tolerance)
+                {
+                    segs.last_mut().unwrap().cmd = Cmd::Line(p);
+                    current = p;
+                    continue;
+                }
+                (Cmd::Line(p), None)
+            }
+            PathEl::QuadTo(c, p) => {
+                let quad = QuadBez::new(current, c, p);
+                let arc = options.arcs.then(|| fit_arc(&|t| quad.eval(t), current, p, tolerance));
+                (Cmd::Quad(c, p), arc.flatten())
+            }
+            PathEl::CurveTo(c1, c2, p) => {
+                let cubic = CubicBez::new(current, c1, c2, p);
+                let arc = options.arcs.then(|| fit_arc(&|t| cubic.eval(t), current, p, tolerance));
+                (Cmd::Cubic(c1, c2, p), arc.flatten())
+            }
+            PathEl::ClosePath => {
+                // A final line back to the contour start is implied by Z
+                if let Some(Seg { cmd: Cmd::Line(p), .. }) = segs.last()
+                    && *p == contour_start
+                {
+                    segs.pop();
+                }
+                segs.push(Seg { cmd: Cmd::Close, from: current, arc: None });
+                current = contour_start;
+                continue;
+            }
+        };
+        segs.push(Seg { cmd, from: current, arc });
+        current = el.end_point().unwrap_or(current);
+    }
+
+    smooth(&mut segs, tolerance);
+    if options.arcs {
+        segs = merge_arcs(&segs, tolerance);
+    }
+    render(segs.iter().map(|seg| &seg.cmd))
+}
+
+/// Whether `mid` lies on the segment `from`→`to` within `tolerance`
+fn is_collinear(from: Point, mid: Point, to: Point, tolerance: f64) -> bool {
+    let dir = to - from;
+    let len = dir.hypot();
+    if len == 0.0 {
+        return false;
+    }
+    let offset = mid - from;
+    let distance = dir.cross(offset).abs() / len;
+    let along = dir.dot(offset) / len;
+    distance <= tolerance && along >= 0.0 && along <= len
+}
+
+/// Replace curves whose first control mirrors the previous curve's last control
+fn smooth(segs: &mut [Seg], tolerance: f64) {
+    let mut prev_quad_ctrl: Option<Point> = None;
+    let mut prev_cubic_ctrl: Option<Point> = None;
+
+    for Seg { cmd, from, .. } in segs.iter_mut() {
+        let from = *from;
+        let reflected = |ctrl: Option<Point>| ctrl.map(|c| from + (from - c));
+        match *cmd {
+            Cmd::Quad(c, p) => {
+                if reflected(prev_quad_ctrl).is_some_and(|r| r.distance(c) <= tolerance) {
+                    *cmd = Cmd::SmoothQuad(p);
+                }
+                prev_quad_ctrl = Some(c);
+                prev_cubic_ctrl = None;
+            }
+            Cmd::Cubic(c1, c2, p) => {
+                if reflected(prev_cubic_ctrl).is_some_and(|r| r.distance(c1) <= tolerance) {
+                    *cmd = Cmd::SmoothCubic(c2, p);
+                }
+                prev_cubic_ctrl = Some(c2);
+                prev_quad_ctrl = None;
+            }
+            _ => {
+                prev_quad_ctrl = None;
+                prev_cubic_ctrl = None;
+            }
+        }
+    }
+}
+
+/// Replace runs of curves on one circle with a single `A` command
+///
+/// A run is only replaced when the arc is shorter than the curves it stands
+/// for, and never ends on a curve whose control point a following `S`/`T`
+/// relies on.
+fn merge_arcs(segs: &[Seg], tolerance: f64) -> Vec<Seg> {
+    let same_circle = |a: &ArcFit, b: &ArcFit| {
+        a.sweep == b.sweep
+            && a.center.distance(b.center) <= tolerance
+            && (a.radius - b.radius).abs() <= tolerance
+    };
+    let is_smooth = |i: usize| {
+        matches!(segs.get(i).map(|seg| seg.cmd), Some(Cmd::SmoothQuad(_) | Cmd::SmoothCubic(..)))
+    };
+
+    let mut out = Vec::with_capacity(segs.len());
+    let mut i = 0;
+    while i < segs.len() {
+        let Some(first) = segs[i].arc else {
+            out.push(segs[i]);
+            i += 1;
+            continue;
+        };
+
+        // SVG arcs cannot describe a full circle, so stop short of TAU
+        let mut end = i + 1;
+        let mut span = first.span;
+        while let Some(next) = segs.get(end).and_then(|seg| seg.arc)
+            && same_circle(&first, &next)
+            && span + next.span < TAU - 1e-6
+        {
+            span += next.span;
+            end += 1;
+        }
+        while end > i && is_smooth(end) {
+            end -= 1;
+        }
+        if end == i {
+            out.push(segs[i]);
+            i += 1;
+            continue;
+        }
+
+        let span: f64 = segs[i..end].iter().filter_map(|seg| seg.arc).map(|arc| arc.span).sum();
+        let last = segs[end - 1].arc.unwrap_or(first);
+        let merged = Cmd::Arc { radius: first.radius, large: span > PI, sweep: first.sweep, to: last.to };
+        let original: usize = segs[i..end].iter().map(|seg| render_cmd(&seg.cmd).len() + 1).sum();
+        if render_cmd(&merged).len() < original {
+            out.push(Seg { cmd: merged, from: segs[i].from, arc: None });
+        } else {
+            out.extend_from_slice(&segs[i..end]);
+        }
+        i = end;
+    }
+    out
+}
+
+/// Fit a circular arc through a curve's endpoints and midpoint
+///
+/// Returns `None` unless every sampled point lies within `tolerance` of the
+/// circle through `from`, the curve midpoint and `to`.
+fn fit_arc(eval: &dyn Fn(f64) -> Point, from: Point, to: Point, tolerance: f64) -> Option<ArcFit> {
+    let mid = eval(0.5);
+    let cross = (mid - from).cross(to - from);
+    if cross.abs() < 1e-9 {
+        return None;
+    }
+
+    // Circumcenter of from, mid, to
+    let (a, b, c) = (from.to_vec2(), mid.to_vec2(), to.to_vec2());
+    let d = 2.0 * (a.x * (b.y - c.y) + b.x * (c.y - a.y) + c.x * (a.y - b.y));
+    let center = Point::new(
+        (a.hypot2() * (b.y - c.y) + b.hypot2() * (c.y - a.y) + c.hypot2() * (a.y - b.y)) / d,
+        (a.hypot2() * (c.x - b.x) + b.hypot2() * (a.x - c.x) + c.hypot2() * (b.x - a.x)) / d,
+    );
+    let radius = center.distance(from);
+
+    let on_circle = [0.125, 0.25, 0.375, 0.625, 0.75, 0.875]
+        .iter()
+        .all(|&t| (eval(t).distance(center) - radius).abs() <= tolerance);
+    if !on_circle {
+        return None;
+    }
+
+    // Positive cross means increasing angle in Y-down space, i.e. sweep-flag 1
+    let sweep = cross > 0.0;
+    let angle = |p: Point| (p.y - center.y).atan2(p.x - center.x);
+    let span = if sweep {
+        (angle(to) - angle(from)).rem_euclid(TAU)
+    } else {
+        (angle(from) - angle(to)).rem_euclid(TAU)
+    };
+
+    Some(ArcFit { center, radius, span, sweep, to })
+}
+
+/// Format a coordinate with at most two decimals and no trailing zeros
+fn num(v: f64) -> String {
+    let s = format!("{:.2}", v);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" { "0".to_string() } else { s.to_string() }
+}
+
+fn render_cmd(cmd: &Cmd) -> String {
+    match *cmd {
+        Cmd::Move(p) => format!("M{} {}", num(p.x), num(p.y)),
+        Cmd::Line(p) => format!("L{} {}", num(p.x), num(p.y)),
+        Cmd::Quad(c, p) => format!("Q{} {} {} {}", num(c.x), num(c.y), num(p.x), num(p.y)),
+        Cmd::SmoothQuad(p) => format!("T{} {}", num(p.x), num(p.y)),
+        Cmd::Cubic(c1, c2, p) => format!(
+            "C{} {} {} {} {} {}",
+            num(c1.x), num(c1.y), num(c2.x), num(c2.y), num(p.x), num(p.y)
+        ),
+        Cmd::SmoothCubic(c2, p) => {
+            format!("S{} {} {} {}", num(c2.x), num(c2.y), num(p.x), num(p.y))
+        }
+        Cmd::Arc { radius, large, sweep, to } => format!(
+            "A{} {} 0 {} {} {} {}",
+            num(radius), num(radius), large as u8, sweep as u8, num(to.x), num(to.y)
+        ),
+        Cmd::Close => "Z".to_string(),
+    }
+}
+
+fn render<'a>(cmds: impl Iterator<Item = &'a Cmd>) -> String {
+    cmds.map(render_cmd).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optimize_should_collapse_collinear_lines_and_implied_close() {
+        let mut path = BezPath::new();
+        path.move_to((0.0, 0.0));
+        path.line_to((50.0, 0.0));
+        path.line_to((100.0, 0.0));
+        path.line_to((100.0, 100.0));
+        path.line_to((0.0, 0.0));
+        path.close_path();
+
+        let svg = optimize_svg_path(&path, &PathOptimizeOptions::default());
+        assert_eq!(svg, "M0 0 L100 0 L100 -100 Z");
+    }
+
+    #[test]
+    fn optimize_should_emit_smooth_quads() {
+        let mut path = BezPath::new();
+        path.move_to((0.0, 0.0));
+        path.quad_to((50.0, 50.0), (100.0, 0.0));
+        path.quad_to((150.0, -50.0), (200.0, 0.0));
+
+        let svg = optimize_svg_path(&path, &PathOptimizeOptions::default());
+        assert_eq!(svg, "M0 0 Q50 -50 100 0 T200 0");
+    }
+
+    #[test]
+    fn optimize_should_merge_arcs_on_one_circle() {
+        // Half circle (r = 100) drawn as two quarter-circle cubics
+        let k = 0.5522847498 * 100.0;
+        let mut path = BezPath::new();
+        path.move_to((100.0, 0.0));
+        path.curve_to((100.0, k), (k, 100.0), (0.0, 100.0));
+        path.curve_to((-k, 100.0), (-100.0, k), (-100.0, 0.0));
+
+        let options = PathOptimizeOptions { tolerance: 0.5, arcs: true };
+        let svg = optimize_svg_path(&path, &options);
+        assert_eq!(svg, "M100 0 A100 100 0 0 0 -100 0");
+    }
+
+    #[test]
+    fn optimize_should_fit_quarter_circle_as_arc() {
+        // Standard cubic approximation of a quarter circle (r = 100)
+        let k = 0.5522847498 * 100.0;
+        let mut path = BezPath::new();
+        path.move_to((100.0, 0.0));
+        path.curve_to((100.0, k), (k, 100.0), (0.0, 100.0));
+
+        let options = PathOptimizeOptions { tolerance: 0.5, arcs: true };
+        let svg = optimize_svg_path(&path, &options);
+        assert_eq!(svg, "M100 0 A100 100 0 0 0 0 -100");
+    }
+}
@@ -0,0 +1,249 @@
+use crate::char_filter::CharFilter;
+use crate::path_optimizer::{self, PathOptimizeOptions};
+use crate::types::CharsetPreset;
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Declarative multi-step workflow loaded from a TOML file
+///
+/// ```toml
+/// font = "NotoSansSC.ttf"
+/// output = "review"
+///
+/// [[step]]
+/// run = "extract"
+/// name = "common"
+/// preset = "cjk-common"
+/// optimize_paths = true
+///
+/// [[step]]
+/// run = "svg"
+///
+/// [[step]]
+/// run = "report"
+/// ```
+///
+/// Relative paths resolve against the pipeline file's directory.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pipeline {
+    pub font: PathBuf,
+    /// Base directory for step outputs
+    #[serde(default = "default_output")]
+    pub output: PathBuf,
+    /// Unihan data for `char_names` and `filter`
+    pub unihan: Option<PathBuf>,
+    #[serde(rename = "step")]
+    pub steps: Vec<Step>,
+}
+
+/// One pipeline step, selected by its `run` key
+#[derive(Debug, Deserialize)]
+#[serde(tag = "run", rename_all = "kebab-case")]
+pub enum Step {
+    /// Extract a named glyph set; later steps consume it
+    Extract(ExtractStep),
+    /// Write one SVG per glyph to `<output>/<set>/`
+    Svg(OutputStep),
+    /// Write `<output>/<set>.ufo`
+    Ufo(OutputStep),
+    /// Write the JSON report to `<output>/<set>.json`
+    Report(OutputStep),
+    /// Re-extract the set serially and in parallel; fail on any difference
+    ReproCheck(OutputStep),
+}
+
+/// Glyph selection and outline options of an `extract` step
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExtractStep {
+    /// Glyph set name (default: `extract-<step number>`)
+    pub name: Option<String>,
+    pub chars: Option<String>,
+    pub range: Option<String>,
+    pub preset: Option<String>,
+    pub limit: Option<usize>,
+    /// Unihan filter expression, e.g. `strokes<=8`
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub normalize_direction: bool,
+    #[serde(default)]
+    pub optimize_paths: bool,
+    pub optimize_tolerance: Option<f64>,
+    #[serde(default)]
+    pub arcs: bool,
+    #[serde(default)]
+    pub char_names: bool,
+    #[serde(default)]
+    pub outline_metrics: bool,
+    /// Export policy TOML forcing characters in or out of the set
+    pub policy: Option<PathBuf>,
+}
+
+/// Consumer step reading a glyph set
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputStep {
+    /// Glyph set to use (default: the most recent `extract`)
+    pub from: Option<String>,
+}
+
+fn default_output() -> PathBuf {
+    PathBuf::from("pipeline-output")
+}
+
+impl Pipeline {
+    /// Load, resolve relative paths and validate a pipeline file
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be read, is not valid TOML, or
+    /// fails validation
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read pipeline: {}", path.display()))?;
+        let mut pipeline = Self::parse(&text)
+            .with_context(|| format!("Invalid pipeline: {}", path.display()))?;
+
+        let base = path.parent().unwrap_or(Path::new("."));
+        pipeline.font = base.join(&pipeline.font);
+        pipeline.output = base.join(&pipeline.output);
+        pipeline.unihan = pipeline.unihan.map(|u| base.join(u));
+        for step in &mut pipeline.steps {
+            if let Step::Extract(extract) = step {
+                extract.policy = extract.policy.as_ref().map(|p| base.join(p));
+            }
+        }
+        Ok(pipeline)
+    }
+
+    /// Parse and validate pipeline TOML
+    ///
+    /// # Errors
+    /// Returns error on malformed TOML, unknown keys or inconsistent steps
+    pub fn parse(text: &str) -> Result<Self> {
+        let pipeline: Self = toml::from_str(text)?;
+        pipeline.validate()?;
+        Ok(pipeline)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.steps.is_empty() {
+            bail!("Pipeline has no [[step]] entries");
+        }
+
+        let mut sets: Vec<String> = Vec::new();
+        for (index, step) in self.steps.iter().enumerate() {
+            let number = index + 1;
+            match step {
+                Step::Extract(extract) => {
+                    if let Some(preset) = &extract.preset
+                        && CharsetPreset::from_str(preset).is_none()
+                    {
+                        bail!("Step {}: unknown preset {}", number, preset);
+                    }
+                    if let Some(filter) = &extract.filter {
+                        CharFilter::parse(filter).with_context(|| format!("Step {}", number))?;
+                        if self.unihan.is_none() {
+                            bail!("Step {}: filter requires a top-level unihan path", number);
+                        }
+                    }
+                    if !extract.optimize_paths && (extract.optimize_tolerance.is_some() || extract.arcs) {
+                        bail!("Step {}: optimize_tolerance and arcs require optimize_paths", number);
+                    }
+                    sets.push(extract.set_name(index));
+                }
+                Step::Svg(output) | Step::Ufo(output) | Step::Report(output) | Step::ReproCheck(output) => {
+                    match &output.from {
+                        Some(from) if !sets.contains(from) => {
+                            bail!("Step {}: no earlier extract step named {}", number, from)
+                        }
+                        None if sets.is_empty() => bail!("Step {}: needs an extract step before it", number),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ExtractStep {
+    /// Name of the glyph set produced by the step at `index`
+    pub fn set_name(&self, index: usize) -> String {
+        self.name.clone().unwrap_or_else(|| format!("extract-{}", index + 1))
+    }
+
+    pub fn preset(&self) -> Option<CharsetPreset> {
+        self.preset.as_deref().and_then(CharsetPreset::from_str)
+    }
+
+    pub fn filter(&self) -> Result<Option<CharFilter>> {
+        self.filter.as_deref().map(CharFilter::parse).transpose()
+    }
+
+    pub fn path_options(&self) -> Option<PathOptimizeOptions> {
+        self.optimize_paths.then(|| PathOptimizeOptions {
+            tolerance: self.optimize_tolerance.unwrap_or(path_optimizer::DEFAULT_TOLERANCE),
+            arcs: self.arcs,
+        })
+    }
+
+    /// Whether two steps select and process glyphs identically (names aside)
+    pub fn same_work(&self, other: &Self) -> bool {
+        Self { name: None, ..self.clone() } == Self { name: None, ..other.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipeline_should_parse_steps_and_defaults() {
+        let pipeline = Pipeline::parse(
+            r#"
+            font = "a.ttf"
+
+            [[step]]
+            run = "extract"
+            name = "latin"
+            preset = "latin"
+            optimize_paths = true
+
+            [[step]]
+            run = "svg"
+
+            [[step]]
+            run = "repro-check"
+            from = "latin"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(pipeline.output, PathBuf::from("pipeline-output"));
+        assert_eq!(pipeline.steps.len(), 3);
+        let Step::Extract(extract) = &pipeline.steps[0] else {
+            panic!("first step should be extract");
+        };
+        assert_eq!(extract.set_name(0), "latin");
+        assert!(extract.path_options().is_some());
+        assert!(matches!(pipeline.steps[2], Step::ReproCheck(_)));
+    }
+
+    #[test]
+    fn pipeline_should_reject_inconsistent_steps() {
+        let no_extract = "font = \"a.ttf\"\n[[step]]\nrun = \"svg\"\n";
+        assert!(Pipeline::parse(no_extract).is_err());
+
+        let unknown_set = "font = \"a.ttf\"\n[[step]]\nrun = \"extract\"\n[[step]]\nrun = \"ufo\"\nfrom = \"x\"\n";
+        assert!(Pipeline::parse(unknown_set).is_err());
+
+        let unknown_step = "font = \"a.ttf\"\n[[step]]\nrun = \"subset\"\n";
+        assert!(Pipeline::parse(unknown_step).is_err());
+
+        let bad_key = "font = \"a.ttf\"\n[[step]]\nrun = \"extract\"\npresett = \"latin\"\n";
+        assert!(Pipeline::parse(bad_key).is_err());
+    }
+}
@@ -0,0 +1,414 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Stroke, Transform};
+use ttf_parser::{Face, OutlineBuilder};
+
+/// Empty pixels around each rendered column
+const PADDING: u32 = 4;
+/// Width of the gray rule between columns, in unzoomed pixels
+const GUTTER: u32 = 1;
+/// ClearType-style FIR weights spreading each subpixel over its neighbours
+const SUBPIXEL_FILTER: [f32; 5] = [1.0 / 9.0, 2.0 / 9.0, 3.0 / 9.0, 2.0 / 9.0, 1.0 / 9.0];
+
+/// Which outline coordinates are rounded to the pixel grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GridFit {
+    /// Fractional positions, as on macOS and in unhinted FreeType
+    None,
+    /// Vertical positions only, as DirectWrite does with ClearType
+    Vertical,
+    /// Both axes, as GDI does with full hinting
+    Full,
+}
+
+/// A simulated platform rasterizer
+///
+/// These are approximations built from published behaviour, not the real
+/// engines: grid fitting rounds every outline point (no TrueType
+/// instructions run), stem darkening dilates the outline by a stroke, and
+/// gamma/contrast are applied to the coverage of black text on white.
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderProfile {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub grid_fit: GridFit,
+    /// Round advance widths to whole pixels
+    pub round_advances: bool,
+    /// Render at 3× horizontal resolution into R, G and B subpixels
+    pub subpixel: bool,
+    /// Display gamma used to blend coverage (1.0 blends in encoded space)
+    pub gamma: f32,
+    /// Coverage boost for thin features (DirectWrite "enhanced contrast")
+    pub contrast: f32,
+    /// Outline dilation per pixel size, capped at `max_darkening` pixels
+    pub darkening_per_px: f32,
+    pub max_darkening: f32,
+}
+
+impl RenderProfile {
+    /// Stroke width added around the outline at this pixel size
+    fn darkening(&self, pixel_size: f32) -> f32 {
+        (self.darkening_per_px * pixel_size).min(self.max_darkening)
+    }
+}
+
+/// Built-in profiles, in sheet column order
+pub fn profiles() -> Vec<RenderProfile> {
+    vec![
+        RenderProfile {
+            name: "unhinted",
+            description: "Reference: fractional outlines, grayscale, no darkening",
+            grid_fit: GridFit::None,
+            round_advances: false,
+            subpixel: false,
+            gamma: 1.0,
+            contrast: 0.0,
+            darkening_per_px: 0.0,
+            max_darkening: 0.0,
+        },
+        RenderProfile {
+            name: "macos",
+            description: "macOS: unhinted grayscale with stem darkening that grows with size",
+            grid_fit: GridFit::None,
+            round_advances: false,
+            subpixel: false,
+            gamma: 1.0,
+            contrast: 0.0,
+            darkening_per_px: 0.0121,
+            max_darkening: 0.3,
+        },
+        RenderProfile {
+            name: "cleartype",
+            description: "Windows DirectWrite ClearType: vertical grid fit, RGB subpixels, gamma 1.8",
+            grid_fit: GridFit::Vertical,
+            round_advances: false,
+            subpixel: true,
+            gamma: 1.8,
+            contrast: 0.5,
+            darkening_per_px: 0.0,
+            max_darkening: 0.0,
+        },
+        RenderProfile {
+            name: "gdi-gray",
+            description: "Windows GDI grayscale: full grid fit and whole-pixel advances",
+            grid_fit: GridFit::Full,
+            round_advances: true,
+            subpixel: false,
+            gamma: 1.4,
+            contrast: 0.0,
+            darkening_per_px: 0.0,
+            max_darkening: 0.0,
+        },
+    ]
+}
+
+/// Look up profiles by name, keeping the requested order
+///
+/// # Errors
+/// Returns error naming the available profiles when a name is unknown
+pub fn select_profiles(names: &[String]) -> Result<Vec<RenderProfile>> {
+    let all = profiles();
+    if names.is_empty() {
+        return Ok(all);
+    }
+    names
+        .iter()
+        .map(|name| {
+            all.iter().find(|p| p.name == name).cloned().with_context(|| {
+                let known: Vec<_> = all.iter().map(|p| p.name).collect();
+                format!("Unknown profile '{}'. Available: {}", name, known.join(", "))
+            })
+        })
+        .collect()
+}
+
+/// Turns a glyph outline into a tiny-skia path in pixel space
+struct PixelPathBuilder {
+    builder: PathBuilder,
+    scale: f32,
+    origin_x: f32,
+    baseline: f32,
+    grid_fit: GridFit,
+}
+
+impl PixelPathBuilder {
+    fn point(&self, x: f32, y: f32) -> (f32, f32) {
+        let px = self.origin_x + x * self.scale;
+        let py = self.baseline - y * self.scale;
+        match self.grid_fit {
+            GridFit::None => (px, py),
+            GridFit::Vertical => (px, py.round()),
+            GridFit::Full => (px.round(), py.round()),
+        }
+    }
+}
+
+impl OutlineBuilder for PixelPathBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.point(x, y);
+        self.builder.move_to(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.point(x, y);
+        self.builder.line_to(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (x1, y1) = self.point(x1, y1);
+        let (x, y) = self.point(x, y);
+        self.builder.quad_to(x1, y1, x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (x1, y1) = self.point(x1, y1);
+        let (x2, y2) = self.point(x2, y2);
+        let (x, y) = self.point(x, y);
+        self.builder.cubic_to(x1, y1, x2, y2, x, y);
+    }
+
+    fn close(&mut self) {
+        self.builder.close();
+    }
+}
+
+/// Per-channel coverage (0–1) of black text, row-major
+struct Coverage {
+    width: u32,
+    height: u32,
+    rgb: Vec<[f32; 3]>,
+}
+
+/// Pixel geometry shared by every column of one sheet
+struct Layout {
+    pixel_size: f32,
+    scale: f32,
+    baseline: f32,
+    height: u32,
+}
+
+impl Layout {
+    fn new(face: &Face, pixel_size: f32) -> Self {
+        let scale = pixel_size / face.units_per_em() as f32;
+        let ascent = (face.ascender() as f32 * scale).ceil();
+        let descent = (-(face.descender() as f32) * scale).ceil();
+        Self {
+            pixel_size,
+            scale,
+            baseline: PADDING as f32 + ascent,
+            height: PADDING * 2 + (ascent + descent) as u32,
+        }
+    }
+
+    fn advance(&self, face: &Face, glyph: ttf_parser::GlyphId, profile: &RenderProfile) -> f32 {
+        let advance = face.glyph_hor_advance(glyph).unwrap_or(0) as f32 * self.scale;
+        if profile.round_advances { advance.round() } else { advance }
+    }
+
+    fn text_width(&self, face: &Face, text: &str, profile: &RenderProfile) -> f32 {
+        text.chars()
+            .filter_map(|c| face.glyph_index(c))
+            .map(|g| self.advance(face, g, profile))
+            .sum()
+    }
+}
+
+/// Rasterize `text` on one line with a profile
+fn rasterize(face: &Face, text: &str, layout: &Layout, width: u32, profile: &RenderProfile) -> Result<Coverage> {
+    let oversample = if profile.subpixel { 3 } else { 1 };
+    let mut pixmap = Pixmap::new(width * oversample, layout.height).context("Sheet is too large")?;
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(0, 0, 0, 255);
+    paint.anti_alias = true;
+    // Outlines are built at output resolution; subpixel rendering stretches them (and the
+    // darkening stroke) horizontally so each output pixel covers three samples
+    let transform = Transform::from_scale(oversample as f32, 1.0);
+    let darkening = profile.darkening(layout.pixel_size);
+
+    let mut pen_x = PADDING as f32;
+    for c in text.chars() {
+        let Some(glyph) = face.glyph_index(c) else { continue };
+        let mut builder = PixelPathBuilder {
+            builder: PathBuilder::new(),
+            scale: layout.scale,
+            origin_x: pen_x,
+            baseline: layout.baseline,
+            grid_fit: profile.grid_fit,
+        };
+        face.outline_glyph(glyph, &mut builder);
+        if let Some(path) = builder.builder.finish() {
+            pixmap.fill_path(&path, &paint, FillRule::Winding, transform, None);
+            if darkening > 0.0 {
+                let stroke = Stroke { width: darkening, ..Stroke::default() };
+                pixmap.stroke_path(&path, &paint, &stroke, transform, None);
+            }
+        }
+        pen_x += layout.advance(face, glyph, profile);
+    }
+
+    let alpha: Vec<f32> = pixmap.pixels().iter().map(|p| p.alpha() as f32 / 255.0).collect();
+    let samples = (width * oversample) as usize;
+    let mut rgb = Vec::with_capacity((width * layout.height) as usize);
+    for row in alpha.chunks(samples) {
+        for x in 0..width as usize {
+            let pixel = if profile.subpixel {
+                let filtered = |sub: usize| {
+                    SUBPIXEL_FILTER
+                        .iter()
+                        .enumerate()
+                        .map(|(i, w)| w * row.get((sub + i).wrapping_sub(2)).copied().unwrap_or(0.0))
+                        .sum::<f32>()
+                };
+                [filtered(3 * x), filtered(3 * x + 1), filtered(3 * x + 2)]
+            } else {
+                [row[x]; 3]
+            };
+            rgb.push(pixel);
+        }
+    }
+    Ok(Coverage { width, height: layout.height, rgb })
+}
+
+/// Display value (0–255) of black text with coverage `c` over white
+fn shade(c: f32, profile: &RenderProfile) -> u8 {
+    let k = profile.contrast;
+    let c = (c * (k + 1.0) / (c * k + 1.0)).clamp(0.0, 1.0);
+    ((1.0 - c).powf(1.0 / profile.gamma) * 255.0).round() as u8
+}
+
+/// One sheet written by [`render_compare`]
+#[derive(Debug, Serialize)]
+pub struct Sheet {
+    pub file: PathBuf,
+    pub pixel_size: f32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Result of [`render_compare`]: the sheets and their column order
+#[derive(Debug, Serialize)]
+pub struct CompareReport {
+    pub font: String,
+    pub text: String,
+    pub zoom: u32,
+    /// Profiles from left to right in every sheet
+    pub columns: Vec<RenderProfile>,
+    pub sheets: Vec<Sheet>,
+}
+
+/// Render `text` with each profile side by side, one PNG per pixel size
+///
+/// Columns are separated by a gray rule and every sheet is magnified with
+/// nearest-neighbour zoom so individual pixels (and subpixel colour
+/// fringes) stay visible.
+///
+/// # Arguments
+/// * `face` - Parsed font
+/// * `font_name` - Used in output file names and the report
+/// * `text` - Characters to render; those missing from the font are skipped
+/// * `pixel_sizes` - Em sizes in pixels, one sheet each
+/// * `profiles` - Columns, left to right
+/// * `zoom` - Integer magnification of the written PNGs
+/// * `output_dir` - Directory for `render-<size>px.png`
+///
+/// # Errors
+/// Returns error if the directory or a PNG cannot be written
+pub fn render_compare(
+    face: &Face,
+    font_name: &str,
+    text: &str,
+    pixel_sizes: &[f32],
+    profiles: &[RenderProfile],
+    zoom: u32,
+    output_dir: &Path,
+) -> Result<CompareReport> {
+    std::fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create directory: {}", output_dir.display()))?;
+    let zoom = zoom.max(1);
+    let mut sheets = Vec::new();
+
+    for &pixel_size in pixel_sizes {
+        let layout = Layout::new(face, pixel_size);
+        let column_width = profiles
+            .iter()
+            .map(|p| layout.text_width(face, text, p).ceil() as u32)
+            .max()
+            .unwrap_or(0)
+            + PADDING * 2;
+        let columns = profiles
+            .iter()
+            .map(|p| rasterize(face, text, &layout, column_width, p).map(|c| (p, c)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let count = profiles.len() as u32;
+        let width = column_width * count + GUTTER * count.saturating_sub(1);
+        let mut pixels = vec![[255u8; 3]; (width * layout.height) as usize];
+        for (index, (profile, coverage)) in columns.iter().enumerate() {
+            let left = index as u32 * (column_width + GUTTER);
+            if index > 0 {
+                for y in 0..layout.height {
+                    for g in 0..GUTTER {
+                        pixels[(y * width + left - GUTTER + g) as usize] = [200; 3];
+                    }
+                }
+            }
+            for y in 0..coverage.height {
+                for x in 0..coverage.width {
+                    let c = coverage.rgb[(y * coverage.width + x) as usize];
+                    pixels[(y * width + left + x) as usize] = c.map(|c| shade(c, profile));
+                }
+            }
+        }
+
+        let mut sheet = Pixmap::new(width * zoom, layout.height * zoom).context("Sheet is too large")?;
+        let data = sheet.data_mut();
+        for y in 0..layout.height * zoom {
+            for x in 0..width * zoom {
+                let [r, g, b] = pixels[((y / zoom) * width + x / zoom) as usize];
+                let offset = ((y * width * zoom + x) * 4) as usize;
+                data[offset..offset + 4].copy_from_slice(&[r, g, b, 255]);
+            }
+        }
+
+        let file = output_dir.join(format!("render-{}px.png", pixel_size));
+        sheet
+            .save_png(&file)
+            .with_context(|| format!("Failed to write PNG: {}", file.display()))?;
+        sheets.push(Sheet { file, pixel_size, width: width * zoom, height: layout.height * zoom });
+    }
+
+    Ok(CompareReport {
+        font: font_name.to_string(),
+        text: text.to_string(),
+        zoom,
+        columns: profiles.to_vec(),
+        sheets,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shade_should_map_full_coverage_to_black_and_none_to_white() {
+        for profile in profiles() {
+            assert_eq!(shade(0.0, &profile), 255);
+            assert_eq!(shade(1.0, &profile), 0);
+        }
+        // Gamma above 1 blends in linear light, so half coverage looks lighter
+        let cleartype = &profiles()[2];
+        assert!(shade(0.5, cleartype) > shade(0.5, &profiles()[0]));
+    }
+
+    #[test]
+    fn select_profiles_should_keep_order_and_reject_unknown_names() {
+        let picked = select_profiles(&["gdi-gray".to_string(), "macos".to_string()]).unwrap();
+        assert_eq!(picked.iter().map(|p| p.name).collect::<Vec<_>>(), ["gdi-gray", "macos"]);
+
+        assert!(select_profiles(&["amiga".to_string()]).is_err());
+        assert_eq!(select_profiles(&[]).unwrap().len(), profiles().len());
+    }
+}
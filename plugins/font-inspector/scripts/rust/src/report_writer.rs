@@ -0,0 +1,150 @@
+use crate::export_policy::PolicyReport;
+use crate::math::MathReport;
+use crate::types::{DirectionReport, GlyphInfo};
+use anyhow::Result;
+use std::io::Write;
+
+/// Streaming sink producing a `FontReport`-compatible JSON document
+///
+/// Glyphs are serialized as they arrive so a report never has to be held in
+/// memory as a whole. The output deserializes into `FontReport`; only the
+/// field order (totals last) and per-glyph compaction differ from the
+/// pretty-printed in-memory report.
+pub struct StreamingReportWriter<W: Write> {
+    out: W,
+    total_exported: usize,
+    direction_report: Option<DirectionReport>,
+    math: Option<MathReport>,
+    policy: Option<PolicyReport>,
+}
+
+impl<W: Write> StreamingReportWriter<W> {
+    /// Write the report header and open the glyph array
+    ///
+    /// # Errors
+    /// Returns error if writing to the sink fails
+    pub fn begin(
+        mut out: W,
+        font_file: &str,
+        family_name: Option<&str>,
+        units_per_em: u16,
+        glyph_count: u16,
+    ) -> Result<Self> {
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"font_file\": {},", serde_json::to_string(font_file)?)?;
+        writeln!(out, "  \"family_name\": {},", serde_json::to_string(&family_name)?)?;
+        writeln!(out, "  \"units_per_em\": {},", units_per_em)?;
+        writeln!(out, "  \"glyph_count\": {},", glyph_count)?;
+        write!(out, "  \"glyphs\": [")?;
+
+        Ok(Self {
+            out,
+            total_exported: 0,
+            direction_report: None,
+            math: None,
+            policy: None,
+        })
+    }
+
+    /// Attach MATH table contents, written with the totals
+    pub fn set_math(&mut self, math: MathReport) {
+        self.math = Some(math);
+    }
+
+    /// Attach the export policy report, written with the totals
+    pub fn set_policy(&mut self, policy: PolicyReport) {
+        self.policy = Some(policy);
+    }
+
+    /// Append a batch of glyphs to the report
+    ///
+    /// # Errors
+    /// Returns error if serialization or writing fails
+    pub fn write_glyphs(&mut self, glyphs: &[GlyphInfo]) -> Result<()> {
+        for glyph in glyphs {
+            let separator = if self.total_exported == 0 { "" } else { "," };
+            write!(self.out, "{}\n    {}", separator, serde_json::to_string(glyph)?)?;
+            self.total_exported += 1;
+        }
+
+        if let Some(batch) = DirectionReport::from_glyphs(glyphs) {
+            match &mut self.direction_report {
+                Some(total) => total.absorb(batch),
+                None => self.direction_report = Some(batch),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Close the glyph array, write totals and return the sink
+    ///
+    /// # Errors
+    /// Returns error if writing to the sink fails
+    pub fn finish(mut self) -> Result<W> {
+        if self.total_exported > 0 {
+            write!(self.out, "\n  ")?;
+        }
+        writeln!(self.out, "],")?;
+        write!(self.out, "  \"total_exported\": {}", self.total_exported)?;
+        if let Some(report) = &self.direction_report {
+            write!(self.out, ",\n  \"direction_report\": {}", serde_json::to_string(report)?)?;
+        }
+        if let Some(math) = &self.math {
+            write!(self.out, ",\n  \"math\": {}", serde_json::to_string(math)?)?;
+        }
+        if let Some(policy) = &self.policy {
+            write!(self.out, ",\n  \"policy\": {}", serde_json::to_string(policy)?)?;
+        }
+        writeln!(self.out, "\n}}")?;
+        self.out.flush()?;
+
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FontReport;
+
+    fn glyph(unicode: &str, flipped: Option<usize>) -> GlyphInfo {
+        GlyphInfo {
+            glyph_name: "A".to_string(),
+            unicode: unicode.to_string(),
+            unicode_char: "A".to_string(),
+            svg_path: "M 0 0 L 100 0 Z".to_string(),
+            advance_width: 600,
+            bounding_box: None,
+            contour_count: 1,
+            point_count: 2,
+            flipped_contours: flipped,
+            annotation: None,
+            outline_metrics: None,
+            review: None,
+        }
+    }
+
+    #[test]
+    fn streaming_report_should_deserialize_as_font_report() -> Result<()> {
+        let mut writer = StreamingReportWriter::begin(Vec::new(), "a.ttf", Some("Fam"), 1000, 3)?;
+        writer.write_glyphs(&[glyph("U+0041", Some(1))])?;
+        writer.write_glyphs(&[glyph("U+0042", Some(0))])?;
+        let bytes = writer.finish()?;
+
+        let report: FontReport = serde_json::from_slice(&bytes)?;
+        assert_eq!(report.total_exported, 2);
+        assert_eq!(report.glyphs.len(), 2);
+        assert_eq!(report.direction_report.unwrap().flipped_glyphs, vec!["U+0041"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn streaming_report_should_handle_empty_glyph_list() -> Result<()> {
+        let writer = StreamingReportWriter::begin(Vec::new(), "a.ttf", None, 1000, 0)?;
+        let report: FontReport = serde_json::from_slice(&writer.finish()?)?;
+        assert!(report.glyphs.is_empty());
+        Ok(())
+    }
+}
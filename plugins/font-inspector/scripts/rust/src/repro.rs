@@ -0,0 +1,178 @@
+use crate::extractor;
+use crate::types::{ExtractOptions, GlyphInfo};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use ttf_parser::Face;
+
+/// Glyph whose serialized form differs between the two runs
+#[derive(Debug, Serialize)]
+pub struct GlyphMismatch {
+    pub unicode: String,
+    /// Report fields whose values differ
+    pub fields: Vec<String>,
+}
+
+/// Outcome of extracting the same codepoints serially and in parallel
+#[derive(Debug, Serialize)]
+pub struct ReproReport {
+    pub deterministic: bool,
+    pub serial_count: usize,
+    pub parallel_count: usize,
+    /// Same glyphs, but emitted in a different order
+    pub order_differs: bool,
+    pub missing_in_parallel: Vec<String>,
+    pub missing_in_serial: Vec<String>,
+    pub mismatched: Vec<GlyphMismatch>,
+}
+
+/// Run extraction serially and in parallel and compare the results
+///
+/// # Arguments
+/// * `face` - Parsed font face
+/// * `codepoints` - Codepoints to extract in both runs
+/// * `options` - Outline processing options shared by both runs
+///
+/// # Errors
+/// Returns error if a glyph cannot be serialized
+pub fn repro_check(face: &Face, codepoints: &[u32], options: &ExtractOptions) -> Result<ReproReport> {
+    let serial = extractor::extract_glyphs_serial_with(face, codepoints, options);
+    let parallel = extractor::extract_glyphs_parallel_with(face, codepoints, options);
+    compare_runs(&serial, &parallel)
+}
+
+/// Compare two extraction runs byte-for-byte after sorting by codepoint
+///
+/// # Errors
+/// Returns error if a glyph cannot be serialized
+pub fn compare_runs(serial: &[GlyphInfo], parallel: &[GlyphInfo]) -> Result<ReproReport> {
+    let order_differs = serial
+        .iter()
+        .map(|g| &g.unicode)
+        .ne(parallel.iter().map(|g| &g.unicode));
+
+    let serial_map = serialize_sorted(serial)?;
+    let parallel_map = serialize_sorted(parallel)?;
+
+    let missing_in_parallel = missing_from(&serial_map, &parallel_map);
+    let missing_in_serial = missing_from(&parallel_map, &serial_map);
+
+    let mut mismatched = Vec::new();
+    for (key, (unicode, serial_json)) in &serial_map {
+        let Some((_, parallel_json)) = parallel_map.get(key) else {
+            continue;
+        };
+        if serial_json != parallel_json {
+            mismatched.push(GlyphMismatch {
+                unicode: unicode.clone(),
+                fields: differing_fields(serial_json, parallel_json)?,
+            });
+        }
+    }
+
+    Ok(ReproReport {
+        deterministic: !order_differs
+            && missing_in_parallel.is_empty()
+            && missing_in_serial.is_empty()
+            && mismatched.is_empty(),
+        serial_count: serial.len(),
+        parallel_count: parallel.len(),
+        order_differs,
+        missing_in_parallel,
+        missing_in_serial,
+        mismatched,
+    })
+}
+
+/// Serialized glyphs keyed by codepoint, so both runs compare in one order
+fn serialize_sorted(glyphs: &[GlyphInfo]) -> Result<BTreeMap<(u32, String), (String, String)>> {
+    let mut map = BTreeMap::new();
+    for glyph in glyphs {
+        let codepoint = glyph
+            .unicode
+            .strip_prefix("U+")
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .unwrap_or(u32::MAX);
+        map.insert(
+            (codepoint, glyph.glyph_name.clone()),
+            (glyph.unicode.clone(), serde_json::to_string(glyph)?),
+        );
+    }
+    Ok(map)
+}
+
+fn missing_from(
+    from: &BTreeMap<(u32, String), (String, String)>,
+    other: &BTreeMap<(u32, String), (String, String)>,
+) -> Vec<String> {
+    from.iter()
+        .filter(|(key, _)| !other.contains_key(key))
+        .map(|(_, (unicode, _))| unicode.clone())
+        .collect()
+}
+
+/// Top-level report fields that differ between two serialized glyphs
+fn differing_fields(a: &str, b: &str) -> Result<Vec<String>> {
+    let (Value::Object(a), Value::Object(b)) = (serde_json::from_str(a)?, serde_json::from_str(b)?) else {
+        return Ok(Vec::new());
+    };
+    let mut fields: Vec<String> = a
+        .keys()
+        .chain(b.keys())
+        .filter(|key| a.get(*key) != b.get(*key))
+        .cloned()
+        .collect();
+    fields.sort();
+    fields.dedup();
+    // Same values but different bytes means only formatting differs
+    if fields.is_empty() {
+        fields.push("(formatting)".to_string());
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glyph(unicode: &str, svg_path: &str) -> GlyphInfo {
+        GlyphInfo {
+            glyph_name: unicode.to_string(),
+            unicode: unicode.to_string(),
+            unicode_char: "A".to_string(),
+            svg_path: svg_path.to_string(),
+            advance_width: 600,
+            bounding_box: None,
+            contour_count: 1,
+            point_count: 2,
+            flipped_contours: None,
+            annotation: None,
+            outline_metrics: None,
+            review: None,
+        }
+    }
+
+    #[test]
+    fn compare_runs_should_accept_identical_runs() {
+        let run = vec![glyph("U+0041", "M 0 0 Z"), glyph("U+0042", "M 1 1 Z")];
+
+        let report = compare_runs(&run, &run.clone()).unwrap();
+        assert!(report.deterministic);
+        assert_eq!(report.serial_count, 2);
+    }
+
+    #[test]
+    fn compare_runs_should_flag_order_missing_and_changed_glyphs() {
+        let serial = vec![glyph("U+0041", "M 0 0 Z"), glyph("U+0042", "M 1 1 Z"), glyph("U+0043", "M 2 2 Z")];
+        let parallel = vec![glyph("U+0042", "M 1 1 Z"), glyph("U+0041", "M 0.0 0 Z")];
+
+        let report = compare_runs(&serial, &parallel).unwrap();
+        assert!(!report.deterministic);
+        assert!(report.order_differs);
+        assert_eq!(report.missing_in_parallel, vec!["U+0043"]);
+        assert!(report.missing_in_serial.is_empty());
+        assert_eq!(report.mismatched.len(), 1);
+        assert_eq!(report.mismatched[0].fields, vec!["svg_path"]);
+    }
+}
@@ -0,0 +1,280 @@
+use anyhow::{Context, Result};
+use kurbo::BezPath;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use ttf_parser::{Face, GlyphId};
+
+use crate::extractor;
+use crate::outline;
+
+/// Appended to the font file name for the default sidecar path
+pub const SIDECAR_SUFFIX: &str = ".review.json";
+
+/// Where a glyph stands in design review
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReviewStatus {
+    #[default]
+    Pending,
+    Approved,
+    ChangesRequested,
+}
+
+impl ReviewStatus {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "approved" => Ok(Self::Approved),
+            "changes-requested" => Ok(Self::ChangesRequested),
+            other => anyhow::bail!("Unknown review status '{}': use pending, approved or changes-requested", other),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::ChangesRequested => "changes-requested",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewComment {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub text: String,
+}
+
+/// Reviewer notes for one glyph
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GlyphReview {
+    #[serde(default)]
+    pub status: ReviewStatus,
+    /// Issue tags, e.g. `spacing`, `overshoot`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<ReviewComment>,
+    /// [`outline_hash`] of the outline the review was written against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outline_hash: Option<String>,
+    /// The outline was edited after the review, so its status may no longer hold
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub outline_changed: bool,
+}
+
+/// Per-glyph review sidecar, keyed by `U+XXXX` like report glyphs
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReviewSheet {
+    /// Font file the reviews were written for
+    #[serde(default)]
+    pub font: String,
+    #[serde(default)]
+    pub glyphs: BTreeMap<String, GlyphReview>,
+}
+
+/// What happened to each review when carried to a new build of the font
+#[derive(Debug, Default, Serialize)]
+pub struct CarryReport {
+    /// Same codepoint, same outline
+    pub kept: usize,
+    /// Same codepoint, edited outline: reset to pending
+    pub outline_changed: Vec<String>,
+    /// Codepoint gone, identical outline found at another one: `old → new`
+    pub moved: Vec<String>,
+    /// No glyph at the codepoint or with the outline; dropped
+    pub orphaned: Vec<String>,
+}
+
+/// `U+0041` for a character, as used in reports and sidecars
+pub fn glyph_key(c: char) -> String {
+    format!("U+{:04X}", c as u32)
+}
+
+/// Character of a `U+XXXX` key
+pub fn parse_key(key: &str) -> Option<char> {
+    let hex = key.strip_prefix("U+")?;
+    char::from_u32(u32::from_str_radix(hex, 16).ok()?)
+}
+
+/// Default sidecar next to the font: `MyFont.ttf` → `MyFont.ttf.review.json`
+pub fn sidecar_path(font: &Path) -> PathBuf {
+    let mut name = font.file_name().unwrap_or_default().to_os_string();
+    name.push(SIDECAR_SUFFIX);
+    font.with_file_name(name)
+}
+
+/// Stable hash of an unprocessed outline in font units (64-bit FNV-1a, hex)
+///
+/// Taken before direction normalization and path optimization, so reports
+/// made with and without them agree.
+pub fn path_hash(path: &BezPath) -> String {
+    let hash = outline::to_svg_path(path)
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |h, b| (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+/// [`path_hash`] of a glyph; `None` when it has no outline
+pub fn outline_hash(face: &Face, glyph_id: GlyphId) -> Option<String> {
+    extractor::glyph_outline(face, glyph_id).map(|path| path_hash(&path))
+}
+
+/// Outline hash of every mapped character with an outline
+pub fn outline_hashes(face: &Face) -> BTreeMap<char, String> {
+    let mut hashes = BTreeMap::new();
+    let Some(cmap) = face.tables().cmap else {
+        return hashes;
+    };
+    for subtable in cmap.subtables.into_iter().filter(|st| st.is_unicode()) {
+        subtable.codepoints(|cp| {
+            if let (Some(c), Some(gid)) = (char::from_u32(cp), subtable.glyph_index(cp))
+                && !hashes.contains_key(&c)
+                && let Some(hash) = outline_hash(face, gid)
+            {
+                hashes.insert(c, hash);
+            }
+        });
+    }
+    hashes
+}
+
+impl ReviewSheet {
+    /// # Errors
+    /// Returns error if the file cannot be read or is not a review sheet
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("Invalid review sheet {}", path.display()))
+    }
+
+    /// # Errors
+    /// Returns error if the file cannot be written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json + "\n").with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// The review of `c` as it applies to an outline hashing to `current`
+    pub fn review_for(&self, c: char, current: Option<&str>) -> Option<GlyphReview> {
+        let mut review = self.glyphs.get(&glyph_key(c))?.clone();
+        if let (Some(recorded), Some(current)) = (&review.outline_hash, current) {
+            review.outline_changed |= recorded != current;
+        }
+        Some(review)
+    }
+
+    /// Flag every review whose outline no longer hashes as recorded
+    pub fn flag_changed(&mut self, current: &BTreeMap<char, String>) {
+        for (key, review) in &mut self.glyphs {
+            let hash = parse_key(key).and_then(|c| current.get(&c));
+            if let (Some(recorded), Some(hash)) = (&review.outline_hash, hash) {
+                review.outline_changed |= recorded != hash;
+            }
+        }
+    }
+
+    /// Carry reviews to a new build whose outlines hash to `current`
+    ///
+    /// Reviews of unchanged outlines carry over as they are; edited outlines
+    /// go back to pending with their comments kept. A review whose codepoint
+    /// disappeared follows an identical outline to a codepoint that has no
+    /// review yet.
+    pub fn carry_forward(&self, font: &str, current: &BTreeMap<char, String>) -> (Self, CarryReport) {
+        let mut by_hash: HashMap<&str, char> = HashMap::new();
+        for (&c, hash) in current.iter().rev() {
+            by_hash.insert(hash, c);
+        }
+
+        let mut carried = Self { font: font.to_string(), glyphs: BTreeMap::new() };
+        let mut report = CarryReport::default();
+        for (key, review) in &self.glyphs {
+            let mut review = review.clone();
+            match parse_key(key).and_then(|c| current.get(&c)) {
+                Some(hash) if review.outline_hash.as_ref().is_none_or(|h| h == hash) => {
+                    review.outline_hash = Some(hash.clone());
+                    report.kept += 1;
+                    carried.glyphs.insert(key.clone(), review);
+                }
+                Some(hash) => {
+                    review.status = ReviewStatus::Pending;
+                    review.outline_changed = true;
+                    review.outline_hash = Some(hash.clone());
+                    report.outline_changed.push(key.clone());
+                    carried.glyphs.insert(key.clone(), review);
+                }
+                None => {
+                    let target = review
+                        .outline_hash
+                        .as_deref()
+                        .and_then(|h| by_hash.get(h))
+                        .map(|&c| glyph_key(c))
+                        .filter(|k| !self.glyphs.contains_key(k) && !carried.glyphs.contains_key(k));
+                    match target {
+                        Some(new_key) => {
+                            report.moved.push(format!("{} → {}", key, new_key));
+                            carried.glyphs.insert(new_key, review);
+                        }
+                        None => report.orphaned.push(key.clone()),
+                    }
+                }
+            }
+        }
+        (carried, report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(status: ReviewStatus, hash: &str) -> GlyphReview {
+        GlyphReview {
+            status,
+            tags: vec!["spacing".into()],
+            comments: vec![ReviewComment { author: Some("ana".into()), text: "tighten".into() }],
+            outline_hash: Some(hash.into()),
+            outline_changed: false,
+        }
+    }
+
+    #[test]
+    fn review_sheet_should_round_trip_and_flag_edited_outlines() {
+        let mut sheet = ReviewSheet { font: "A.ttf".into(), glyphs: BTreeMap::new() };
+        sheet.glyphs.insert(glyph_key('A'), review(ReviewStatus::Approved, "aa"));
+
+        let json = serde_json::to_string(&sheet).unwrap();
+        assert!(json.contains("\"U+0041\"") && json.contains("\"approved\"") && !json.contains("outline_changed"));
+        let back: ReviewSheet = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.glyphs[&glyph_key('A')], sheet.glyphs[&glyph_key('A')]);
+
+        assert!(!sheet.review_for('A', Some("aa")).unwrap().outline_changed);
+        assert!(sheet.review_for('A', Some("bb")).unwrap().outline_changed);
+        assert!(sheet.review_for('B', Some("aa")).is_none());
+    }
+
+    #[test]
+    fn carry_forward_should_keep_reset_move_and_orphan() {
+        let mut sheet = ReviewSheet::default();
+        sheet.glyphs.insert(glyph_key('A'), review(ReviewStatus::Approved, "aa"));
+        sheet.glyphs.insert(glyph_key('B'), review(ReviewStatus::Approved, "bb"));
+        sheet.glyphs.insert(glyph_key('C'), review(ReviewStatus::ChangesRequested, "cc"));
+        sheet.glyphs.insert(glyph_key('D'), review(ReviewStatus::Approved, "dd"));
+
+        let current = BTreeMap::from([('A', "aa".into()), ('B', "b2".into()), ('E', "cc".into())]);
+        let (carried, report) = sheet.carry_forward("A2.ttf", &current);
+
+        assert_eq!(report.kept, 1);
+        assert_eq!(report.outline_changed, vec!["U+0042"]);
+        assert_eq!(report.moved, vec!["U+0043 → U+0045"]);
+        assert_eq!(report.orphaned, vec!["U+0044"]);
+
+        let b = &carried.glyphs["U+0042"];
+        assert_eq!(b.status, ReviewStatus::Pending);
+        assert!(b.outline_changed && b.outline_hash.as_deref() == Some("b2") && b.comments.len() == 1);
+        assert_eq!(carried.glyphs["U+0045"].status, ReviewStatus::ChangesRequested);
+        assert_eq!(carried.glyphs["U+0041"].status, ReviewStatus::Approved);
+    }
+}
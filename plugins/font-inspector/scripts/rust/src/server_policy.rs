@@ -0,0 +1,182 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Operator policy for the MCP server, loaded from a JSON config file
+///
+/// ```json
+/// {
+///   "disabled_tools": ["compare_glyphs"],
+///   "read_only": true,
+///   "max_glyphs_per_request": 5000,
+///   "rate_limits": { "extract_all": 10, "convert_ufo": 2 }
+/// }
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerPolicy {
+    /// Tools hidden from `tools/list` and refused when called
+    pub disabled_tools: HashSet<String>,
+    /// Refuse every call that would write to disk
+    pub read_only: bool,
+    /// Upper bound on glyphs resolved by a single request
+    pub max_glyphs_per_request: Option<usize>,
+    /// Maximum calls per minute, keyed by tool name
+    pub rate_limits: HashMap<String, u32>,
+}
+
+/// Request refused by server policy
+#[derive(Debug, Error, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum PolicyViolation {
+    #[error("Tool '{tool}' is disabled by server policy")]
+    ToolDisabled { tool: String },
+    #[error("Tool '{tool}' would write to disk, but the server is read-only")]
+    WritesDisabled { tool: String },
+    #[error("Request covers {requested} glyphs; server policy allows at most {max}")]
+    GlyphCapExceeded { requested: usize, max: usize },
+    #[error("Rate limit for '{tool}' reached ({limit}/min); retry in {retry_after_secs}s")]
+    RateLimited { tool: String, limit: u32, retry_after_secs: u64 },
+}
+
+impl ServerPolicy {
+    /// Load a policy from a JSON file
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be read or contains unknown keys
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read server config: {}", path.display()))?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("Invalid server config: {}", path.display()))
+    }
+
+    /// Whether a tool should be advertised in `tools/list`
+    ///
+    /// Tools that always write are hidden in read-only mode.
+    pub fn is_listed(&self, tool: &str) -> bool {
+        if self.disabled_tools.contains(tool) {
+            return false;
+        }
+        !(self.read_only && writes_to_disk(tool, &Value::Null))
+    }
+
+    /// Check a tool call against the disabled-tool and read-only rules
+    ///
+    /// # Errors
+    /// Returns the violated rule
+    pub fn check_call(&self, tool: &str, arguments: &Value) -> Result<(), PolicyViolation> {
+        if self.disabled_tools.contains(tool) {
+            return Err(PolicyViolation::ToolDisabled { tool: tool.to_string() });
+        }
+        if self.read_only && writes_to_disk(tool, arguments) {
+            return Err(PolicyViolation::WritesDisabled { tool: tool.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Check the number of glyphs a request resolved to
+    ///
+    /// # Errors
+    /// Returns `GlyphCapExceeded` when over `max_glyphs_per_request`
+    pub fn check_glyph_count(&self, requested: usize) -> Result<(), PolicyViolation> {
+        match self.max_glyphs_per_request {
+            Some(max) if requested > max => Err(PolicyViolation::GlyphCapExceeded { requested, max }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Whether a call to `tool` with these arguments writes files
+fn writes_to_disk(tool: &str, arguments: &Value) -> bool {
+    match tool {
+        "convert_ufo" => true,
+        "extract_all" => arguments.get("output_dir").is_some_and(|v| !v.is_null()),
+        _ => false,
+    }
+}
+
+/// Sliding one-minute window of calls per tool
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    calls: HashMap<String, VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a call to `tool` at `now` if it fits within `limit` per minute
+    ///
+    /// # Errors
+    /// Returns `RateLimited` (without recording the call) when the window is full
+    pub fn check(&mut self, tool: &str, limit: Option<u32>, now: Instant) -> Result<(), PolicyViolation> {
+        let Some(limit) = limit else {
+            return Ok(());
+        };
+
+        let window = self.calls.entry(tool.to_string()).or_default();
+        while window.front().is_some_and(|&t| now.duration_since(t) >= RATE_WINDOW) {
+            window.pop_front();
+        }
+
+        if window.len() >= limit as usize {
+            let oldest = window.front().copied().unwrap_or(now);
+            let retry_after = RATE_WINDOW.saturating_sub(now.duration_since(oldest));
+            return Err(PolicyViolation::RateLimited {
+                tool: tool.to_string(),
+                limit,
+                retry_after_secs: retry_after.as_secs().max(1),
+            });
+        }
+
+        window.push_back(now);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn policy_should_refuse_disabled_tools_and_writes() {
+        let policy: ServerPolicy = serde_json::from_value(json!({
+            "disabled_tools": ["compare_glyphs"],
+            "read_only": true
+        }))
+        .unwrap();
+
+        assert!(policy.check_call("compare_glyphs", &json!({})).is_err());
+        assert!(policy.check_call("extract_all", &json!({ "output_dir": "/tmp/x" })).is_err());
+        assert!(policy.check_call("extract_all", &json!({})).is_ok());
+        assert!(!policy.is_listed("convert_ufo"));
+        assert!(policy.is_listed("extract_glyph"));
+    }
+
+    #[test]
+    fn policy_should_reject_unknown_keys() {
+        let parsed = serde_json::from_value::<ServerPolicy>(json!({ "max_glyphs": 10 }));
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn rate_limiter_should_reopen_after_window() {
+        let mut limiter = RateLimiter::new();
+        let start = Instant::now();
+
+        assert!(limiter.check("extract_all", Some(2), start).is_ok());
+        assert!(limiter.check("extract_all", Some(2), start).is_ok());
+        assert!(limiter.check("extract_all", Some(2), start + Duration::from_secs(30)).is_err());
+        assert!(limiter.check("extract_glyph", Some(2), start).is_ok());
+        assert!(limiter.check("extract_all", Some(2), start + RATE_WINDOW).is_ok());
+    }
+}
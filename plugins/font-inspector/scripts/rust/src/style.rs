@@ -0,0 +1,466 @@
+use kurbo::{BezPath, PathEl, Point};
+use serde::Serialize;
+use ttf_parser::Face;
+
+use crate::extractor;
+
+/// Estimates below this confidence are reported but never flagged
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.5;
+/// Weight classes an estimate may differ from usWeightClass before it is flagged
+const WEIGHT_TOLERANCE: f64 = 150.0;
+/// Width classes an estimate may differ from usWidthClass before it is flagged
+const WIDTH_TOLERANCE: u16 = 2;
+/// Degrees a measured slant may differ from post.italicAngle before it is flagged
+const SLANT_TOLERANCE: f64 = 3.0;
+const FLATTEN_TOLERANCE: f64 = 0.5;
+
+/// Stem thickness over cap height at each usWeightClass, from Thin to Black
+///
+/// Fitted to sans and serif families with a full weight range; estimates
+/// interpolate between the points and clamp at the ends.
+const STEM_RATIOS: [(f64, f64); 9] = [
+    (0.035, 100.0),
+    (0.060, 200.0),
+    (0.095, 300.0),
+    (0.130, 400.0),
+    (0.160, 500.0),
+    (0.200, 600.0),
+    (0.245, 700.0),
+    (0.290, 800.0),
+    (0.340, 900.0),
+];
+
+/// Width of each usWidthClass as a percentage of normal (OS/2 specification)
+const WIDTH_PERCENTS: [f64; 9] = [50.0, 62.5, 75.0, 87.5, 100.0, 112.5, 125.0, 150.0, 200.0];
+
+/// Height a probe's cuts are fractions of
+#[derive(Debug, Clone, Copy)]
+enum Reference {
+    CapHeight,
+    XHeight,
+}
+
+/// A glyph cut horizontally to measure its stems
+///
+/// The lower and upper cuts sit on straight stems, clear of serifs, bars and
+/// arches; slant is the lean of the first stem between them. Stem thickness
+/// and the spacing between the outer strokes are read halfway between.
+struct Probe {
+    c: char,
+    reference: Reference,
+    low: f64,
+    high: f64,
+    /// The first stroke at the cut is a vertical stem, not a bowl
+    stem: bool,
+    /// Distance between the centres of the outer strokes at normal width, over the reference height
+    normal_spacing: Option<f64>,
+}
+
+const PROBES: [Probe; 7] = [
+    Probe { c: 'H', reference: Reference::CapHeight, low: 0.15, high: 0.40, stem: true, normal_spacing: Some(0.61) },
+    Probe { c: 'I', reference: Reference::CapHeight, low: 0.25, high: 0.75, stem: true, normal_spacing: None },
+    Probe { c: 'O', reference: Reference::CapHeight, low: 0.50, high: 0.50, stem: false, normal_spacing: Some(0.77) },
+    Probe { c: 'l', reference: Reference::XHeight, low: 0.25, high: 0.75, stem: true, normal_spacing: None },
+    Probe { c: 'i', reference: Reference::XHeight, low: 0.25, high: 0.65, stem: true, normal_spacing: None },
+    Probe { c: 'n', reference: Reference::XHeight, low: 0.15, high: 0.50, stem: true, normal_spacing: Some(0.68) },
+    Probe { c: 'o', reference: Reference::XHeight, low: 0.50, high: 0.50, stem: false, normal_spacing: Some(0.78) },
+];
+
+/// What the font's OS/2 and post tables claim
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Declared {
+    /// usWeightClass
+    pub weight: u16,
+    /// usWidthClass, 1–9
+    pub width: u16,
+    /// post.italicAngle in degrees; negative leans right
+    pub italic_angle: f64,
+    /// fsSelection ITALIC or OBLIQUE is set
+    pub italic: bool,
+}
+
+/// Raw per-glyph measurements
+#[derive(Debug, Default)]
+pub struct Measurements {
+    /// Stem thickness over cap height, corrected for slant
+    pub stems: Vec<(char, f64)>,
+    /// Outer stroke spacing as a percentage of the probe's normal spacing
+    pub widths: Vec<(char, f64)>,
+    /// Lean in post.italicAngle convention, degrees
+    pub slants: Vec<(char, f64)>,
+    /// Glyph widths follow the cell of a monospaced font, not the design width
+    pub monospaced: bool,
+}
+
+/// One classification compared with its declaration
+#[derive(Debug, Clone, Serialize)]
+pub struct Estimate {
+    pub declared: f64,
+    pub estimated: f64,
+    /// What the estimate was derived from: stem over cap height, percent of normal width, or degrees
+    pub measured: f64,
+    /// 0–1: share of probe glyphs that could be measured, times how well they agree
+    pub confidence: f64,
+    /// Probe glyphs that contributed
+    pub glyphs: String,
+    pub contradicts: bool,
+}
+
+/// Weight, width and slant of a font's outlines against its metadata
+#[derive(Debug, Serialize)]
+pub struct StyleReport {
+    pub font: String,
+    pub declared: Declared,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<Estimate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<Estimate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slant: Option<Estimate>,
+    /// One sentence per contradiction found with enough confidence
+    pub issues: Vec<String>,
+}
+
+/// Name of the usWeightClass nearest `weight`
+pub fn weight_name(weight: f64) -> &'static str {
+    const NAMES: [&str; 9] =
+        ["Thin", "ExtraLight", "Light", "Regular", "Medium", "SemiBold", "Bold", "ExtraBold", "Black"];
+    NAMES[((weight / 100.0).round() as usize).clamp(1, 9) - 1]
+}
+
+/// Name of usWidthClass `class`
+pub fn width_name(class: u16) -> &'static str {
+    const NAMES: [&str; 9] = [
+        "UltraCondensed",
+        "ExtraCondensed",
+        "Condensed",
+        "SemiCondensed",
+        "Normal",
+        "SemiExpanded",
+        "Expanded",
+        "ExtraExpanded",
+        "UltraExpanded",
+    ];
+    NAMES[usize::from(class.clamp(1, 9)) - 1]
+}
+
+/// usWeightClass a stem of `ratio` × cap height suggests
+pub fn weight_for_stem(ratio: f64) -> f64 {
+    let (first, last) = (STEM_RATIOS[0], STEM_RATIOS[STEM_RATIOS.len() - 1]);
+    if ratio <= first.0 {
+        return first.1;
+    }
+    if ratio >= last.0 {
+        return last.1;
+    }
+    let upper = STEM_RATIOS.iter().position(|&(r, _)| r >= ratio).unwrap_or(STEM_RATIOS.len() - 1);
+    let ((r0, w0), (r1, w1)) = (STEM_RATIOS[upper - 1], STEM_RATIOS[upper]);
+    w0 + (ratio - r0) / (r1 - r0) * (w1 - w0)
+}
+
+/// usWidthClass whose nominal percentage is nearest `percent`
+pub fn width_class_for(percent: f64) -> u16 {
+    let nearest = WIDTH_PERCENTS
+        .iter()
+        .enumerate()
+        .min_by(|a, b| (a.1 - percent).abs().total_cmp(&(b.1 - percent).abs()))
+        .map_or(4, |(i, _)| i);
+    nearest as u16 + 1
+}
+
+/// Filled spans of `path` along the horizontal line at `y`, left to right
+///
+/// Crossings are paired in order, which matches the fill of the simple
+/// stems and bowls probes are cut through.
+pub fn ink_runs(path: &BezPath, y: f64) -> Vec<(f64, f64)> {
+    let mut xs = Vec::new();
+    let mut start = Point::ZERO;
+    let mut last = Point::ZERO;
+    let mut edge = |a: Point, b: Point| {
+        if (a.y <= y) != (b.y <= y) {
+            xs.push(a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x));
+        }
+    };
+    kurbo::flatten(path.elements().iter().copied(), FLATTEN_TOLERANCE, |el| match el {
+        PathEl::MoveTo(p) => {
+            if last != start {
+                edge(last, start);
+            }
+            start = p;
+            last = p;
+        }
+        PathEl::LineTo(p) => {
+            edge(last, p);
+            last = p;
+        }
+        PathEl::ClosePath => {
+            edge(last, start);
+            last = start;
+        }
+        _ => {}
+    });
+    if last != start {
+        edge(last, start);
+    }
+    xs.sort_by(f64::total_cmp);
+    xs.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+/// Top of the glyph mapped from `c`, if it has one
+fn glyph_top(face: &Face, c: char) -> Option<f64> {
+    let bbox = face.glyph_bounding_box(face.glyph_index(c)?)?;
+    Some(f64::from(bbox.y_max)).filter(|&top| top > 0.0)
+}
+
+/// Cut every probe glyph the font maps
+///
+/// Cap and x-height come from the outlines of `H` and `x` when the font has
+/// them, since the OS/2 values are metadata too.
+pub fn measure(face: &Face) -> Measurements {
+    let cap_height = glyph_top(face, 'H')
+        .or_else(|| face.capital_height().map(f64::from).filter(|&h| h > 0.0))
+        .unwrap_or(f64::from(face.units_per_em()) * 0.7);
+    let x_height = glyph_top(face, 'x')
+        .or_else(|| face.x_height().map(f64::from).filter(|&h| h > 0.0))
+        .unwrap_or(cap_height * 0.72);
+
+    let mut measurements = Measurements { monospaced: face.is_monospaced(), ..Default::default() };
+    let mut cuts = Vec::new();
+    for probe in &PROBES {
+        let Some(path) = face.glyph_index(probe.c).and_then(|gid| extractor::glyph_outline(face, gid)) else {
+            continue;
+        };
+        let height = match probe.reference {
+            Reference::CapHeight => cap_height,
+            Reference::XHeight => x_height,
+        };
+        let middle = ink_runs(&path, height * (probe.low + probe.high) / 2.0);
+        let Some(&(left, right)) = middle.first() else {
+            continue;
+        };
+
+        if probe.high > probe.low {
+            let (low, high) = (height * probe.low, height * probe.high);
+            if let (Some(a), Some(b)) = (ink_runs(&path, low).first(), ink_runs(&path, high).first()) {
+                let lean = ((b.0 + b.1) - (a.0 + a.1)) / 2.0 / (high - low);
+                measurements.slants.push((probe.c, -lean.atan().to_degrees()));
+            }
+        }
+        if probe.stem {
+            cuts.push((probe.c, right - left));
+        }
+        if let (Some(normal), [first, .., last]) = (probe.normal_spacing, middle.as_slice()) {
+            let spacing = ((last.0 + last.1) - (first.0 + first.1)) / 2.0;
+            measurements.widths.push((probe.c, spacing / height / normal * 100.0));
+        }
+    }
+
+    // A horizontal cut through a slanted stem is wider than the stem itself
+    let lean = median(measurements.slants.iter().map(|&(_, s)| s)).unwrap_or(0.0).to_radians().cos();
+    measurements.stems = cuts.into_iter().map(|(c, cut)| (c, cut * lean / cap_height)).collect();
+    measurements
+}
+
+fn median(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let mut values: Vec<f64> = values.collect();
+    values.sort_by(f64::total_cmp);
+    let n = values.len();
+    match n {
+        0 => None,
+        _ if n % 2 == 1 => Some(values[n / 2]),
+        _ => Some((values[n / 2 - 1] + values[n / 2]) / 2.0),
+    }
+}
+
+fn mean_and_spread(samples: &[(char, f64)]) -> (f64, f64) {
+    let n = samples.len() as f64;
+    let mean = samples.iter().map(|s| s.1).sum::<f64>() / n;
+    let variance = samples.iter().map(|s| (s.1 - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+fn round(value: f64, places: i32) -> f64 {
+    let scale = 10f64.powi(places);
+    // Adding zero turns -0.0 into 0.0
+    (value * scale).round() / scale + 0.0
+}
+
+/// Confidence from coverage of the probes and the spread of their samples
+///
+/// `scale` is the spread at which agreement drops to zero.
+fn confidence(samples: &[(char, f64)], probes: usize, spread: f64, scale: f64) -> f64 {
+    let coverage = samples.len() as f64 / probes as f64;
+    let agreement = (1.0 - spread / scale).clamp(0.0, 1.0);
+    round(coverage * agreement, 2)
+}
+
+fn glyph_list(samples: &[(char, f64)]) -> String {
+    samples.iter().map(|s| s.0).collect()
+}
+
+/// Compare measurements with the declarations and flag confident contradictions
+pub fn assess(font: &str, declared: Declared, measured: &Measurements, min_confidence: f64) -> StyleReport {
+    let mut issues = Vec::new();
+
+    let weight = (!measured.stems.is_empty()).then(|| {
+        let (ratio, spread) = mean_and_spread(&measured.stems);
+        let estimated = (weight_for_stem(ratio) / 10.0).round() * 10.0;
+        let probes = PROBES.iter().filter(|p| p.stem).count();
+        let confidence = confidence(&measured.stems, probes, spread / ratio, 0.35);
+        let contradicts =
+            confidence >= min_confidence && (estimated - f64::from(declared.weight)).abs() >= WEIGHT_TOLERANCE;
+        if contradicts {
+            issues.push(format!(
+                "usWeightClass {} ({}) but stems look {} ({}), confidence {:.2}",
+                declared.weight,
+                weight_name(f64::from(declared.weight)),
+                estimated,
+                weight_name(estimated),
+                confidence
+            ));
+        }
+        Estimate {
+            declared: f64::from(declared.weight),
+            estimated,
+            measured: round(ratio, 3),
+            confidence,
+            glyphs: glyph_list(&measured.stems),
+            contradicts,
+        }
+    });
+
+    let width = (!measured.widths.is_empty()).then(|| {
+        let (percent, spread) = mean_and_spread(&measured.widths);
+        let class = width_class_for(percent);
+        let probes = PROBES.iter().filter(|p| p.normal_spacing.is_some()).count();
+        let mut confidence = confidence(&measured.widths, probes, spread / percent, 0.25);
+        if measured.monospaced {
+            confidence = round(confidence / 2.0, 2);
+        }
+        let contradicts = confidence >= min_confidence && class.abs_diff(declared.width) >= WIDTH_TOLERANCE;
+        if contradicts {
+            issues.push(format!(
+                "usWidthClass {} ({}) but proportions look {} ({}, {:.0}% of normal), confidence {:.2}",
+                declared.width,
+                width_name(declared.width),
+                class,
+                width_name(class),
+                percent,
+                confidence
+            ));
+        }
+        Estimate {
+            declared: f64::from(declared.width),
+            estimated: f64::from(class),
+            measured: round(percent, 1),
+            confidence,
+            glyphs: glyph_list(&measured.widths),
+            contradicts,
+        }
+    });
+
+    let slant = (!measured.slants.is_empty()).then(|| {
+        let (angle, spread) = mean_and_spread(&measured.slants);
+        let probes = PROBES.iter().filter(|p| p.high > p.low).count();
+        let confidence = confidence(&measured.slants, probes, spread, 4.0);
+        let confident = confidence >= min_confidence;
+        let mut contradicts = false;
+        if confident && (angle - declared.italic_angle).abs() > SLANT_TOLERANCE {
+            contradicts = true;
+            issues.push(format!(
+                "post.italicAngle {:.1}° but stems lean {:.1}°, confidence {:.2}",
+                declared.italic_angle, angle, confidence
+            ));
+        }
+        if confident && angle.abs() > SLANT_TOLERANCE && !declared.italic {
+            contradicts = true;
+            issues.push(format!(
+                "stems lean {:.1}° but fsSelection sets neither ITALIC nor OBLIQUE, confidence {:.2}",
+                angle, confidence
+            ));
+        }
+        Estimate {
+            declared: declared.italic_angle,
+            estimated: round(angle, 1),
+            measured: round(angle, 2),
+            confidence,
+            glyphs: glyph_list(&measured.slants),
+            contradicts,
+        }
+    });
+
+    StyleReport { font: font.to_string(), declared, weight, width, slant, issues }
+}
+
+/// Estimate weight, width and slant from the outlines and check them against OS/2 and post
+///
+/// # Arguments
+/// * `face` - Parsed font face
+/// * `font` - Font path, for the report
+/// * `min_confidence` - Contradictions with lower confidence are not flagged
+pub fn classify_style(face: &Face, font: &str, min_confidence: f64) -> StyleReport {
+    let declared = Declared {
+        weight: face.weight().to_number(),
+        width: face.width().to_number(),
+        italic_angle: face.italic_angle().map_or(0.0, f64::from),
+        italic: face.is_italic() || face.is_oblique(),
+    };
+    assess(font, declared, &measure(face), min_confidence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurbo::Affine;
+
+    /// A 100-unit stem, 700 units tall, from x = 200
+    fn stem() -> BezPath {
+        let mut path = BezPath::new();
+        path.move_to((200.0, 0.0));
+        path.line_to((300.0, 0.0));
+        path.line_to((300.0, 700.0));
+        path.line_to((200.0, 700.0));
+        path.close_path();
+        path
+    }
+
+    #[test]
+    fn ink_runs_should_measure_stems_and_their_lean() {
+        let mut two = stem();
+        two.extend(Affine::translate((400.0, 0.0)) * stem());
+        assert_eq!(ink_runs(&two, 350.0), vec![(200.0, 300.0), (600.0, 700.0)]);
+        assert!(ink_runs(&two, 800.0).is_empty());
+
+        // Sheared right by tan 12°: runs move right as y rises, post.italicAngle would be -12
+        let shear = Affine::new([1.0, 0.0, 12f64.to_radians().tan(), 1.0, 0.0, 0.0]);
+        let slanted = shear * stem();
+        let (low, high) = (ink_runs(&slanted, 100.0)[0], ink_runs(&slanted, 600.0)[0]);
+        let lean = ((high.0 + high.1) - (low.0 + low.1)) / 2.0 / 500.0;
+        assert!((-lean.atan().to_degrees() + 12.0).abs() < 1e-6);
+        assert!((low.1 - low.0 - 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn assess_should_flag_confident_contradictions_only() {
+        let declared = Declared { weight: 400, width: 5, italic_angle: 0.0, italic: false };
+        let bold = Measurements {
+            stems: "HIlin".chars().map(|c| (c, 0.245)).collect(),
+            widths: "HOno".chars().map(|c| (c, 101.0)).collect(),
+            slants: "HIlin".chars().map(|c| (c, -11.0)).collect(),
+            monospaced: false,
+        };
+        let report = assess("A.ttf", declared, &bold, DEFAULT_MIN_CONFIDENCE);
+        let weight = report.weight.as_ref().unwrap();
+        assert_eq!((weight.estimated, weight.confidence, weight.contradicts), (700.0, 1.0, true));
+        assert!(!report.width.as_ref().unwrap().contradicts);
+        assert!(report.slant.as_ref().unwrap().contradicts);
+        assert_eq!(report.issues.len(), 3, "{:?}", report.issues);
+
+        // Two probes that disagree wildly are not enough to flag anything
+        let sparse = Measurements { stems: vec![('H', 0.06), ('I', 0.3)], ..Default::default() };
+        let report = assess("A.ttf", declared, &sparse, DEFAULT_MIN_CONFIDENCE);
+        assert!(report.weight.unwrap().confidence < DEFAULT_MIN_CONFIDENCE && report.issues.is_empty());
+        assert_eq!(width_class_for(76.0), 3);
+        assert_eq!(weight_name(weight_for_stem(0.13)), "Regular");
+    }
+}
@@ -1,37 +1,117 @@
+use crate::outline;
 use crate::types::GlyphInfo;
 use anyhow::{Context, Result};
+use kurbo::BezPath;
 use std::fs;
 use std::path::Path;
 
+/// Path data size above which some SVG consumers start to struggle
+pub const LONG_PATH_WARN_BYTES: usize = 64 * 1024;
+
+/// Count glyphs whose path data exceeds `limit` bytes
+pub fn count_long_paths(glyphs: &[GlyphInfo], limit: usize) -> usize {
+    glyphs.iter().filter(|g| g.svg_path.len() > limit).count()
+}
+
+/// Split path data into several `d` strings of at most `max_bytes` each
+///
+/// Subpaths are cut at their `M` commands and kept with the contours they
+/// interact with: a counter stays in the same piece as the outer contour that
+/// encloses it, so every piece fills correctly on its own. A single outer
+/// contour with its counters may still exceed `max_bytes`. Path data that
+/// cannot be parsed is returned whole.
+///
+/// # Arguments
+/// * `d` - SVG path data with absolute commands
+/// * `max_bytes` - Target upper bound per piece
+///
+/// # Returns
+/// One or more pieces whose union draws the same shape
+pub fn split_path_data(d: &str, max_bytes: usize) -> Vec<String> {
+    if d.len() <= max_bytes {
+        return vec![d.to_string()];
+    }
+    let starts: Vec<usize> = d.match_indices('M').map(|(i, _)| i).collect();
+    let subpaths: Vec<&str> = starts
+        .iter()
+        .enumerate()
+        .map(|(n, &start)| d[start..starts.get(n + 1).copied().unwrap_or(d.len())].trim())
+        .collect();
+    let Ok(contours) = subpaths.iter().map(|s| BezPath::from_svg(s)).collect::<Result<Vec<_>, _>>() else {
+        return vec![d.to_string()];
+    };
+    if contours.len() < 2 {
+        return vec![d.to_string()];
+    }
+
+    let mut pieces: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for group in outline::contour_groups(&contours) {
+        let text = group.iter().map(|&i| subpaths[i]).collect::<Vec<_>>().join(" ");
+        if !current.is_empty() && current.len() + 1 + text.len() > max_bytes {
+            pieces.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(&text);
+    }
+    if !current.is_empty() {
+        pieces.push(current);
+    }
+    pieces
+}
+
 /// Write a single glyph as an SVG file
 ///
 /// # Arguments
 /// * `glyph` - Glyph information including SVG path data
 /// * `output_dir` - Directory to write SVG file
 /// * `upem` - Units per EM from font (for viewBox)
+/// * `split_over` - Split path data longer than this many bytes into several
+///   `<path>` elements sharing one fill (see [`split_path_data`])
 ///
 /// # Errors
 /// Returns error if file write fails
-pub fn write_glyph_svg(glyph: &GlyphInfo, output_dir: &Path, upem: u16) -> Result<()> {
+pub fn write_glyph_svg(
+    glyph: &GlyphInfo,
+    output_dir: &Path,
+    upem: u16,
+    split_over: Option<usize>,
+) -> Result<()> {
     let height = upem as i32;
 
+    let pieces = match split_over {
+        Some(max_bytes) => split_path_data(&glyph.svg_path, max_bytes),
+        None => vec![glyph.svg_path.clone()],
+    };
+    let paths = if pieces.len() == 1 {
+        format!(r#"<path d="{}" fill="currentColor"/>"#, pieces[0])
+    } else {
+        let children: String = pieces
+            .iter()
+            .map(|d| format!("\n    <path d=\"{}\"/>", d))
+            .collect();
+        format!("<g fill=\"currentColor\">{}\n  </g>", children)
+    };
+
     let svg = format!(
         r#"<svg xmlns="http://www.w3.org/2000/svg"
      width="{upem}" height="{height}"
      viewBox="0 -{height} {upem} {height}">
   <!-- Glyph: {name} | Unicode: {unicode} | Char: {char} -->
-  <path d="{path}" fill="currentColor"/>
+  {paths}
 </svg>"#,
         upem = upem,
         height = height,
         name = glyph.glyph_name,
         unicode = glyph.unicode,
         char = glyph.unicode_char,
-        path = glyph.svg_path,
+        paths = paths,
     );
 
-    // Safe filename: use Unicode hex without '+'
-    let safe_name = glyph.unicode.replace('+', "");
+    // Safe filename: use Unicode hex without '+'; unencoded glyphs (.notdef) go by name
+    let safe_name = if glyph.unicode.is_empty() { glyph.glyph_name.clone() } else { glyph.unicode.replace('+', "") };
     let out_path = output_dir.join(format!("{}.svg", safe_name));
 
     fs::write(&out_path, svg)
@@ -47,6 +127,7 @@ pub fn write_glyph_svg(glyph: &GlyphInfo, output_dir: &Path, upem: u16) -> Resul
 /// * `output_dir` - Directory to write SVG files
 /// * `upem` - Units per EM from font
 /// * `show_progress` - Whether to show progress bar
+/// * `split_over` - Byte threshold for multi-path output (see [`write_glyph_svg`])
 ///
 /// # Errors
 /// Returns error if directory creation or file writes fail
@@ -55,6 +136,7 @@ pub fn write_all_glyphs(
     output_dir: &Path,
     upem: u16,
     show_progress: bool,
+    split_over: Option<usize>,
 ) -> Result<()> {
     // Create output directory
     fs::create_dir_all(output_dir)
@@ -72,7 +154,7 @@ pub fn write_all_glyphs(
         );
 
         for glyph in glyphs {
-            write_glyph_svg(glyph, output_dir, upem)?;
+            write_glyph_svg(glyph, output_dir, upem, split_over)?;
             pb.inc(1);
         }
 
@@ -80,7 +162,7 @@ pub fn write_all_glyphs(
     } else {
         // No progress bar - just write files
         for glyph in glyphs {
-            write_glyph_svg(glyph, output_dir, upem)?;
+            write_glyph_svg(glyph, output_dir, upem, split_over)?;
         }
     }
 
@@ -96,6 +178,7 @@ pub fn write_all_glyphs(
 /// * `output_dir` - Directory to write SVG files
 /// * `upem` - Units per EM from font
 /// * `show_progress` - Whether to show progress bar
+/// * `split_over` - Byte threshold for multi-path output (see [`write_glyph_svg`])
 ///
 /// # Errors
 /// Returns error if directory creation or file writes fail
@@ -104,6 +187,7 @@ pub fn write_all_glyphs_parallel(
     output_dir: &Path,
     upem: u16,
     show_progress: bool,
+    split_over: Option<usize>,
 ) -> Result<()> {
     use rayon::prelude::*;
 
@@ -125,12 +209,12 @@ pub fn write_all_glyphs_parallel(
         glyphs
             .par_iter()
             .progress_with(pb)
-            .try_for_each(|glyph| write_glyph_svg(glyph, output_dir, upem))?;
+            .try_for_each(|glyph| write_glyph_svg(glyph, output_dir, upem, split_over))?;
     } else {
         // Parallel without progress bar
         glyphs
             .par_iter()
-            .try_for_each(|glyph| write_glyph_svg(glyph, output_dir, upem))?;
+            .try_for_each(|glyph| write_glyph_svg(glyph, output_dir, upem, split_over))?;
     }
 
     Ok(())
@@ -153,6 +237,10 @@ mod tests {
             bounding_box: None,
             contour_count: 1,
             point_count: 3,
+            flipped_contours: None,
+            annotation: None,
+            outline_metrics: None,
+            review: None,
         }
     }
 
@@ -161,7 +249,7 @@ mod tests {
         let temp_dir = TempDir::new()?;
         let glyph = create_test_glyph();
 
-        write_glyph_svg(&glyph, temp_dir.path(), 1000)?;
+        write_glyph_svg(&glyph, temp_dir.path(), 1000, None)?;
 
         let svg_path = temp_dir.path().join("U0041.svg");
         assert!(svg_path.exists());
@@ -186,6 +274,10 @@ mod tests {
                 bounding_box: None,
                 contour_count: 1,
                 point_count: 2,
+                flipped_contours: None,
+                annotation: None,
+                outline_metrics: None,
+                review: None,
             },
             GlyphInfo {
                 glyph_name: "B".to_string(),
@@ -196,6 +288,10 @@ mod tests {
                 bounding_box: None,
                 contour_count: 1,
                 point_count: 2,
+                flipped_contours: None,
+                annotation: None,
+                outline_metrics: None,
+                review: None,
             },
             GlyphInfo {
                 glyph_name: "C".to_string(),
@@ -206,14 +302,47 @@ mod tests {
                 bounding_box: None,
                 contour_count: 1,
                 point_count: 2,
+                flipped_contours: None,
+                annotation: None,
+                outline_metrics: None,
+                review: None,
             },
         ];
 
-        write_all_glyphs(&glyphs, temp_dir.path(), 1000, false)?;
+        write_all_glyphs(&glyphs, temp_dir.path(), 1000, false, None)?;
 
         let files: Vec<_> = fs::read_dir(temp_dir.path())?.collect();
         assert_eq!(files.len(), 3);
 
         Ok(())
     }
+
+    #[test]
+    fn split_path_data_should_keep_counters_with_their_outer_contour() {
+        // "O" (outer + counter) followed by a separate dot
+        let o = "M 0 0 L 0 -100 L 100 -100 L 100 0 Z M 25 -25 L 75 -25 L 75 -75 L 25 -75 Z";
+        let dot = "M 200 0 L 200 -10 L 210 -10 L 210 0 Z";
+        let d = format!("{} {}", o, dot);
+
+        let pieces = split_path_data(&d, 40);
+
+        assert_eq!(pieces, vec![o.to_string(), dot.to_string()]);
+    }
+
+    #[test]
+    fn write_glyph_svg_should_share_fill_across_split_paths() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut glyph = create_test_glyph();
+        glyph.svg_path = "M 0 0 L 10 0 L 5 10 Z M 20 0 L 30 0 L 25 10 Z".to_string();
+
+        write_glyph_svg(&glyph, temp_dir.path(), 1000, Some(10))?;
+        let split = fs::read_to_string(temp_dir.path().join("U0041.svg"))?;
+        write_glyph_svg(&glyph, temp_dir.path(), 1000, None)?;
+        let single = fs::read_to_string(temp_dir.path().join("U0041.svg"))?;
+
+        assert!(split.contains(r#"<g fill="currentColor">"#));
+        assert_eq!(split.matches("<path ").count(), 2);
+        assert_eq!(single.matches("<path ").count(), 1);
+        Ok(())
+    }
 }
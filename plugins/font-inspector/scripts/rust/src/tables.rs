@@ -0,0 +1,334 @@
+use anyhow::{Result, bail};
+use std::fmt::Write;
+use std::ops::Range;
+use ttf_parser::Tag;
+
+/// Binary field encodings used by the structured table views
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    U16,
+    I16,
+    U32,
+    Hex16,
+    Hex32,
+    /// 16.16 fixed-point number
+    Fixed,
+    /// Seconds since 1904-01-01
+    DateTime,
+    Tag,
+    Bytes(usize),
+}
+
+impl Kind {
+    fn size(self) -> usize {
+        match self {
+            Self::U16 | Self::I16 | Self::Hex16 => 2,
+            Self::U32 | Self::Hex32 | Self::Fixed | Self::Tag => 4,
+            Self::DateTime => 8,
+            Self::Bytes(n) => n,
+        }
+    }
+
+    fn format(self, bytes: &[u8]) -> String {
+        let u16_at = || u16::from_be_bytes([bytes[0], bytes[1]]);
+        let u32_at = || u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        match self {
+            Self::U16 => u16_at().to_string(),
+            Self::I16 => (u16_at() as i16).to_string(),
+            Self::U32 => u32_at().to_string(),
+            Self::Hex16 => format!("0x{:04X}", u16_at()),
+            Self::Hex32 => format!("0x{:08X}", u32_at()),
+            Self::Fixed => format!("{}", u32_at() as i32 as f64 / 65536.0),
+            Self::DateTime => {
+                let secs = i64::from_be_bytes(bytes[..8].try_into().unwrap_or_default());
+                format!("{} (seconds since 1904)", secs)
+            }
+            Self::Tag => String::from_utf8_lossy(bytes).into_owned(),
+            Self::Bytes(_) => hex_bytes(bytes),
+        }
+    }
+}
+
+const HEAD: &[(&str, Kind)] = &[
+    ("majorVersion", Kind::U16),
+    ("minorVersion", Kind::U16),
+    ("fontRevision", Kind::Fixed),
+    ("checksumAdjustment", Kind::Hex32),
+    ("magicNumber", Kind::Hex32),
+    ("flags", Kind::Hex16),
+    ("unitsPerEm", Kind::U16),
+    ("created", Kind::DateTime),
+    ("modified", Kind::DateTime),
+    ("xMin", Kind::I16),
+    ("yMin", Kind::I16),
+    ("xMax", Kind::I16),
+    ("yMax", Kind::I16),
+    ("macStyle", Kind::Hex16),
+    ("lowestRecPPEM", Kind::U16),
+    ("fontDirectionHint", Kind::I16),
+    ("indexToLocFormat", Kind::I16),
+    ("glyphDataFormat", Kind::I16),
+];
+
+const HHEA: &[(&str, Kind)] = &[
+    ("majorVersion", Kind::U16),
+    ("minorVersion", Kind::U16),
+    ("ascender", Kind::I16),
+    ("descender", Kind::I16),
+    ("lineGap", Kind::I16),
+    ("advanceWidthMax", Kind::U16),
+    ("minLeftSideBearing", Kind::I16),
+    ("minRightSideBearing", Kind::I16),
+    ("xMaxExtent", Kind::I16),
+    ("caretSlopeRise", Kind::I16),
+    ("caretSlopeRun", Kind::I16),
+    ("caretOffset", Kind::I16),
+    ("reserved", Kind::Bytes(8)),
+    ("metricDataFormat", Kind::I16),
+    ("numberOfHMetrics", Kind::U16),
+];
+
+const MAXP: &[(&str, Kind)] = &[
+    ("version", Kind::Hex32),
+    ("numGlyphs", Kind::U16),
+    ("maxPoints", Kind::U16),
+    ("maxContours", Kind::U16),
+    ("maxCompositePoints", Kind::U16),
+    ("maxCompositeContours", Kind::U16),
+    ("maxZones", Kind::U16),
+    ("maxTwilightPoints", Kind::U16),
+    ("maxStorage", Kind::U16),
+    ("maxFunctionDefs", Kind::U16),
+    ("maxInstructionDefs", Kind::U16),
+    ("maxStackElements", Kind::U16),
+    ("maxSizeOfInstructions", Kind::U16),
+    ("maxComponentElements", Kind::U16),
+    ("maxComponentDepth", Kind::U16),
+];
+
+const POST: &[(&str, Kind)] = &[
+    ("version", Kind::Hex32),
+    ("italicAngle", Kind::Fixed),
+    ("underlinePosition", Kind::I16),
+    ("underlineThickness", Kind::I16),
+    ("isFixedPitch", Kind::U32),
+    ("minMemType42", Kind::U32),
+    ("maxMemType42", Kind::U32),
+    ("minMemType1", Kind::U32),
+    ("maxMemType1", Kind::U32),
+];
+
+const OS2: &[(&str, Kind)] = &[
+    ("version", Kind::U16),
+    ("xAvgCharWidth", Kind::I16),
+    ("usWeightClass", Kind::U16),
+    ("usWidthClass", Kind::U16),
+    ("fsType", Kind::Hex16),
+    ("ySubscriptXSize", Kind::I16),
+    ("ySubscriptYSize", Kind::I16),
+    ("ySubscriptXOffset", Kind::I16),
+    ("ySubscriptYOffset", Kind::I16),
+    ("ySuperscriptXSize", Kind::I16),
+    ("ySuperscriptYSize", Kind::I16),
+    ("ySuperscriptXOffset", Kind::I16),
+    ("ySuperscriptYOffset", Kind::I16),
+    ("yStrikeoutSize", Kind::I16),
+    ("yStrikeoutPosition", Kind::I16),
+    ("sFamilyClass", Kind::I16),
+    ("panose", Kind::Bytes(10)),
+    ("ulUnicodeRange1", Kind::Hex32),
+    ("ulUnicodeRange2", Kind::Hex32),
+    ("ulUnicodeRange3", Kind::Hex32),
+    ("ulUnicodeRange4", Kind::Hex32),
+    ("achVendID", Kind::Tag),
+    ("fsSelection", Kind::Hex16),
+    ("usFirstCharIndex", Kind::Hex16),
+    ("usLastCharIndex", Kind::Hex16),
+    ("sTypoAscender", Kind::I16),
+    ("sTypoDescender", Kind::I16),
+    ("sTypoLineGap", Kind::I16),
+    ("usWinAscent", Kind::U16),
+    ("usWinDescent", Kind::U16),
+    ("ulCodePageRange1", Kind::Hex32),
+    ("ulCodePageRange2", Kind::Hex32),
+    ("sxHeight", Kind::I16),
+    ("sCapHeight", Kind::I16),
+    ("usDefaultChar", Kind::Hex16),
+    ("usBreakChar", Kind::Hex16),
+    ("usMaxContext", Kind::U16),
+    ("usLowerOpticalPointSize", Kind::U16),
+    ("usUpperOpticalPointSize", Kind::U16),
+];
+
+/// A decoded field of a fixed-layout table
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableField {
+    pub name: &'static str,
+    pub offset: usize,
+    pub size: usize,
+    pub value: String,
+}
+
+/// Field that differs between two copies of a table
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub name: &'static str,
+    pub a: Option<String>,
+    pub b: Option<String>,
+}
+
+/// Structural differences between two copies of one table
+#[derive(Debug, Default)]
+pub struct TableDiff {
+    pub len_a: usize,
+    pub len_b: usize,
+    /// Decoded field changes (fixed-layout tables only)
+    pub fields: Vec<FieldChange>,
+    /// Byte ranges that differ over the common length
+    pub byte_ranges: Vec<Range<usize>>,
+}
+
+impl TableDiff {
+    pub fn is_identical(&self) -> bool {
+        self.len_a == self.len_b && self.byte_ranges.is_empty()
+    }
+}
+
+/// Parse a table tag such as `head`, `OS/2` or `cvt` (padded with spaces)
+///
+/// # Errors
+/// Returns error if the tag is empty, longer than 4 bytes or not ASCII
+pub fn parse_tag(s: &str) -> Result<Tag> {
+    if s.is_empty() || s.len() > 4 || !s.is_ascii() {
+        bail!("Invalid table tag: {:?}. Expected 1-4 ASCII characters, e.g. head or OS/2", s);
+    }
+    Ok(Tag::from_bytes_lossy(s.as_bytes()))
+}
+
+/// Decode a fixed-layout table (`head`, `hhea`, `maxp`, `post`, `OS/2`)
+///
+/// Fields past the end of the data (older table versions) are omitted.
+///
+/// # Returns
+/// `None` for tables without a structured view
+pub fn table_fields(tag: Tag, data: &[u8]) -> Option<Vec<TableField>> {
+    let layout = match &tag.to_bytes() {
+        b"head" => HEAD,
+        b"hhea" | b"vhea" => HHEA,
+        b"maxp" => MAXP,
+        b"post" => POST,
+        b"OS/2" => OS2,
+        _ => return None,
+    };
+
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    for &(name, kind) in layout {
+        let size = kind.size();
+        let Some(bytes) = data.get(offset..offset + size) else {
+            break;
+        };
+        fields.push(TableField { name, offset, size, value: kind.format(bytes) });
+        offset += size;
+    }
+    Some(fields)
+}
+
+/// Compare two copies of a table field-by-field (when decodable) and byte-by-byte
+pub fn diff_table(tag: Tag, a: &[u8], b: &[u8]) -> TableDiff {
+    let mut diff = TableDiff { len_a: a.len(), len_b: b.len(), ..Default::default() };
+
+    if let (Some(fields_a), Some(fields_b)) = (table_fields(tag, a), table_fields(tag, b)) {
+        let count = fields_a.len().max(fields_b.len());
+        for i in 0..count {
+            let (fa, fb) = (fields_a.get(i), fields_b.get(i));
+            if fa.map(|f| &f.value) != fb.map(|f| &f.value) {
+                diff.fields.push(FieldChange {
+                    name: fa.or(fb).map(|f| f.name).unwrap_or_default(),
+                    a: fa.map(|f| f.value.clone()),
+                    b: fb.map(|f| f.value.clone()),
+                });
+            }
+        }
+    }
+
+    let mut start = None;
+    for i in 0..a.len().min(b.len()) {
+        match (a[i] != b[i], start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                diff.byte_ranges.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        diff.byte_ranges.push(s..a.len().min(b.len()));
+    }
+    diff
+}
+
+/// Classic 16-bytes-per-line hex dump with offsets and an ASCII column
+pub fn hexdump(data: &[u8], base_offset: usize) -> String {
+    let mut out = String::new();
+    for (line, chunk) in data.chunks(16).enumerate() {
+        let _ = write!(out, "{:08x}  ", base_offset + line * 16);
+        for i in 0..16 {
+            match chunk.get(i) {
+                Some(byte) => {
+                    let _ = write!(out, "{:02x} ", byte);
+                }
+                None => out.push_str("   "),
+            }
+            if i == 7 {
+                out.push(' ');
+            }
+        }
+        out.push_str(" |");
+        out.extend(chunk.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }));
+        out.push_str("|\n");
+    }
+    out
+}
+
+/// Space-separated hex bytes
+pub fn hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hhea(ascender: i16) -> Vec<u8> {
+        let mut data = vec![0u8; 36];
+        data[1] = 1;
+        data[4..6].copy_from_slice(&ascender.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn parse_tag_should_pad_short_tags() {
+        assert_eq!(parse_tag("cvt").unwrap().to_bytes(), *b"cvt ");
+        assert_eq!(parse_tag("OS/2").unwrap().to_bytes(), *b"OS/2");
+        assert!(parse_tag("toolong").is_err());
+        assert!(parse_tag("").is_err());
+    }
+
+    #[test]
+    fn diff_table_should_report_fields_and_byte_ranges() {
+        let tag = parse_tag("hhea").unwrap();
+        let fields = table_fields(tag, &hhea(800)).unwrap();
+        assert_eq!(fields[2].name, "ascender");
+        assert_eq!(fields[2].value, "800");
+
+        let diff = diff_table(tag, &hhea(800), &hhea(-5));
+        assert!(!diff.is_identical());
+        assert_eq!(
+            diff.fields,
+            vec![FieldChange { name: "ascender", a: Some("800".into()), b: Some("-5".into()) }]
+        );
+        assert_eq!(diff.byte_ranges, vec![4..6]);
+    }
+}
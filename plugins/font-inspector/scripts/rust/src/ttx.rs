@@ -0,0 +1,693 @@
+use crate::tables;
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use ttf_parser::{Face, RawFace, Tag};
+
+/// Seconds between the SFNT epoch (1904-01-01) and the Unix epoch
+const MAC_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+/// How fontTools renders a field in TTX
+#[derive(Debug, Clone, Copy)]
+enum Fmt {
+    U8,
+    U16,
+    I16,
+    U32,
+    /// 16.16 fixed as the shortest round-tripping decimal, e.g. `2.37`
+    Fixed,
+    /// `hex(value)`, e.g. `0x5f0f3cf5`
+    Hex,
+    /// Zero-padded 32-bit hex, e.g. `0x00010000`
+    Hex8,
+    /// Bit string in groups of eight, e.g. `00000000 00001011`
+    Bin16,
+    Bin32,
+    /// `asctime` of a 1904-based timestamp
+    Date,
+    Tag,
+}
+
+impl Fmt {
+    fn size(self) -> usize {
+        match self {
+            Self::U8 => 1,
+            Self::U16 | Self::I16 | Self::Bin16 => 2,
+            Self::U32 | Self::Fixed | Self::Hex | Self::Hex8 | Self::Bin32 | Self::Tag => 4,
+            Self::Date => 8,
+        }
+    }
+
+    fn format(self, b: &[u8]) -> String {
+        let u16_at = || u16::from_be_bytes([b[0], b[1]]);
+        let u32_at = || u32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+        match self {
+            Self::U8 => b[0].to_string(),
+            Self::U16 => u16_at().to_string(),
+            Self::I16 => (u16_at() as i16).to_string(),
+            Self::U32 => u32_at().to_string(),
+            Self::Fixed => fixed_to_str(u32_at() as i32),
+            Self::Hex => format!("{:#x}", u32_at()),
+            Self::Hex8 => format!("0x{:08x}", u32_at()),
+            Self::Bin16 => num_to_binary(u16_at() as u32, 16),
+            Self::Bin32 => num_to_binary(u32_at(), 32),
+            Self::Date => timestamp_to_string(i64::from_be_bytes(b[..8].try_into().unwrap_or_default())),
+            Self::Tag => String::from_utf8_lossy(&b[..4]).into_owned(),
+        }
+    }
+}
+
+const HEAD: &[(&str, Fmt)] = &[
+    ("tableVersion", Fmt::Fixed),
+    ("fontRevision", Fmt::Fixed),
+    ("checkSumAdjustment", Fmt::Hex),
+    ("magicNumber", Fmt::Hex),
+    ("flags", Fmt::Bin16),
+    ("unitsPerEm", Fmt::U16),
+    ("created", Fmt::Date),
+    ("modified", Fmt::Date),
+    ("xMin", Fmt::I16),
+    ("yMin", Fmt::I16),
+    ("xMax", Fmt::I16),
+    ("yMax", Fmt::I16),
+    ("macStyle", Fmt::Bin16),
+    ("lowestRecPPEM", Fmt::U16),
+    ("fontDirectionHint", Fmt::I16),
+    ("indexToLocFormat", Fmt::I16),
+    ("glyphDataFormat", Fmt::I16),
+];
+
+const HHEA: &[(&str, Fmt)] = &[
+    ("tableVersion", Fmt::Hex8),
+    ("ascent", Fmt::I16),
+    ("descent", Fmt::I16),
+    ("lineGap", Fmt::I16),
+    ("advanceWidthMax", Fmt::U16),
+    ("minLeftSideBearing", Fmt::I16),
+    ("minRightSideBearing", Fmt::I16),
+    ("xMaxExtent", Fmt::I16),
+    ("caretSlopeRise", Fmt::I16),
+    ("caretSlopeRun", Fmt::I16),
+    ("caretOffset", Fmt::I16),
+    ("reserved0", Fmt::I16),
+    ("reserved1", Fmt::I16),
+    ("reserved2", Fmt::I16),
+    ("reserved3", Fmt::I16),
+    ("metricDataFormat", Fmt::I16),
+    ("numberOfHMetrics", Fmt::U16),
+];
+
+const MAXP: &[(&str, Fmt)] = &[
+    ("tableVersion", Fmt::Hex),
+    ("numGlyphs", Fmt::U16),
+    ("maxPoints", Fmt::U16),
+    ("maxContours", Fmt::U16),
+    ("maxCompositePoints", Fmt::U16),
+    ("maxCompositeContours", Fmt::U16),
+    ("maxZones", Fmt::U16),
+    ("maxTwilightPoints", Fmt::U16),
+    ("maxStorage", Fmt::U16),
+    ("maxFunctionDefs", Fmt::U16),
+    ("maxInstructionDefs", Fmt::U16),
+    ("maxStackElements", Fmt::U16),
+    ("maxSizeOfInstructions", Fmt::U16),
+    ("maxComponentElements", Fmt::U16),
+    ("maxComponentDepth", Fmt::U16),
+];
+
+const POST: &[(&str, Fmt)] = &[
+    ("formatType", Fmt::Fixed),
+    ("italicAngle", Fmt::Fixed),
+    ("underlinePosition", Fmt::I16),
+    ("underlineThickness", Fmt::I16),
+    ("isFixedPitch", Fmt::U32),
+    ("minMemType42", Fmt::U32),
+    ("maxMemType42", Fmt::U32),
+    ("minMemType1", Fmt::U32),
+    ("maxMemType1", Fmt::U32),
+];
+
+/// OS/2 fields; `panose/…` entries nest inside a `<panose>` element
+const OS2: &[(&str, Fmt)] = &[
+    ("version", Fmt::U16),
+    ("xAvgCharWidth", Fmt::I16),
+    ("usWeightClass", Fmt::U16),
+    ("usWidthClass", Fmt::U16),
+    ("fsType", Fmt::Bin16),
+    ("ySubscriptXSize", Fmt::I16),
+    ("ySubscriptYSize", Fmt::I16),
+    ("ySubscriptXOffset", Fmt::I16),
+    ("ySubscriptYOffset", Fmt::I16),
+    ("ySuperscriptXSize", Fmt::I16),
+    ("ySuperscriptYSize", Fmt::I16),
+    ("ySuperscriptXOffset", Fmt::I16),
+    ("ySuperscriptYOffset", Fmt::I16),
+    ("yStrikeoutSize", Fmt::I16),
+    ("yStrikeoutPosition", Fmt::I16),
+    ("sFamilyClass", Fmt::I16),
+    ("panose/bFamilyType", Fmt::U8),
+    ("panose/bSerifStyle", Fmt::U8),
+    ("panose/bWeight", Fmt::U8),
+    ("panose/bProportion", Fmt::U8),
+    ("panose/bContrast", Fmt::U8),
+    ("panose/bStrokeVariation", Fmt::U8),
+    ("panose/bArmStyle", Fmt::U8),
+    ("panose/bLetterForm", Fmt::U8),
+    ("panose/bMidline", Fmt::U8),
+    ("panose/bXHeight", Fmt::U8),
+    ("ulUnicodeRange1", Fmt::Bin32),
+    ("ulUnicodeRange2", Fmt::Bin32),
+    ("ulUnicodeRange3", Fmt::Bin32),
+    ("ulUnicodeRange4", Fmt::Bin32),
+    ("achVendID", Fmt::Tag),
+    ("fsSelection", Fmt::Bin16),
+    ("usFirstCharIndex", Fmt::U16),
+    ("usLastCharIndex", Fmt::U16),
+    ("sTypoAscender", Fmt::I16),
+    ("sTypoDescender", Fmt::I16),
+    ("sTypoLineGap", Fmt::I16),
+    ("usWinAscent", Fmt::U16),
+    ("usWinDescent", Fmt::U16),
+    ("ulCodePageRange1", Fmt::Bin32),
+    ("ulCodePageRange2", Fmt::Bin32),
+    ("sxHeight", Fmt::I16),
+    ("sCapHeight", Fmt::I16),
+    ("usDefaultChar", Fmt::U16),
+    ("usBreakChar", Fmt::U16),
+    ("usMaxContext", Fmt::U16),
+    ("usLowerOpticalPointSize", Fmt::U16),
+    ("usUpperOpticalPointSize", Fmt::U16),
+];
+
+/// A field that differs between a TTX file and a binary font
+#[derive(Debug, Serialize)]
+pub struct TtxDifference {
+    pub table: String,
+    pub field: String,
+    pub ttx: Option<String>,
+    pub font: Option<String>,
+}
+
+/// Result of comparing a TTX file against a binary font
+#[derive(Debug, Default, Serialize)]
+pub struct TtxReport {
+    /// Tables compared field-by-field or byte-by-byte
+    pub compared: Vec<String>,
+    /// Tables in the TTX without a structured reader here
+    pub skipped: Vec<String>,
+    /// Tables in the TTX that the font lacks
+    pub missing_in_font: Vec<String>,
+    /// Tables in the font that the TTX does not mention
+    pub not_in_ttx: Vec<String>,
+    pub differences: Vec<TtxDifference>,
+}
+
+impl TtxReport {
+    pub fn matches(&self) -> bool {
+        self.missing_in_font.is_empty() && self.differences.is_empty()
+    }
+}
+
+/// fontTools' XML element name for a table tag (`OS/2` → `OS_2`, `cvt ` → `cvt`)
+pub fn tag_to_xml(tag: Tag) -> String {
+    let bytes = tag.to_bytes();
+    if &bytes == b"OS/2" {
+        return "OS_2".to_string();
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let trimmed = text.trim_end_matches(' ');
+    let is_identifier = trimmed
+        .chars()
+        .enumerate()
+        .all(|(i, c)| c == '_' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit()));
+    if is_identifier && !trimmed.is_empty() {
+        trimmed.to_string()
+    } else {
+        format!("_{}", tables::hex_bytes(&bytes).replace(' ', ""))
+    }
+}
+
+/// Inverse of [`tag_to_xml`]
+pub fn xml_to_tag(name: &str) -> Option<Tag> {
+    if name == "OS_2" {
+        return Some(Tag::from_bytes(b"OS/2"));
+    }
+    if let Some(hex) = name.strip_prefix('_')
+        && hex.len() == 8
+        && let Ok(value) = u32::from_str_radix(hex, 16)
+    {
+        return Some(Tag(value));
+    }
+    tables::parse_tag(name).ok()
+}
+
+/// Export tables as fontTools-compatible TTX XML
+///
+/// `head`, `hhea`, `maxp`, `post`, `OS/2` and `name` are written field by field;
+/// every other table is written as `<hexdata>` with `raw="True"`, which
+/// fontTools reads back as opaque bytes.
+///
+/// # Arguments
+/// * `font_data` - Complete font file
+/// * `tags` - Tables to export; `None` exports the glyph order and all tables
+///
+/// # Errors
+/// Returns error if the font cannot be parsed or a requested table is absent
+pub fn export_ttx(font_data: &[u8], tags: Option<&[Tag]>) -> Result<String> {
+    let raw = RawFace::parse(font_data, 0).context("Failed to parse font")?;
+    let face = Face::parse(font_data, 0).context("Failed to parse font")?;
+
+    let mut records: Vec<(Tag, &[u8])> = Vec::new();
+    match tags {
+        Some(tags) => {
+            for &tag in tags {
+                let data = raw.table(tag).with_context(|| format!("Table '{}' not found", tag))?;
+                records.push((tag, data));
+            }
+        }
+        None => {
+            for record in raw.table_records {
+                if let Some(data) = raw.table(record.tag) {
+                    records.push((record.tag, data));
+                }
+            }
+            // fontTools order: sorted by tag, DSIG last
+            records.sort_by_key(|(tag, _)| (&tag.to_bytes() == b"DSIG", tag.to_bytes()));
+        }
+    }
+
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(out, "<ttFont sfntVersion=\"{}\" ttLibVersion=\"4.0\">", sfnt_version(font_data));
+
+    if tags.is_none() {
+        out.push('\n');
+        write_glyph_order(&mut out, &face);
+    }
+    for (tag, data) in records {
+        out.push('\n');
+        write_table(&mut out, tag, data, &face);
+    }
+    out.push_str("\n</ttFont>\n");
+    Ok(out)
+}
+
+/// Compare the tables of a TTX document against a binary font
+///
+/// Structured tables are compared field by field (numbers by value, so `0x10000`
+/// equals `0x00010000`), `<hexdata>` tables byte by byte. Tables written
+/// structurally by fontTools that this module cannot decode (e.g. `cmap`) are
+/// reported as skipped.
+///
+/// # Errors
+/// Returns error if the TTX is not well-formed XML or the font cannot be parsed
+pub fn compare_ttx(ttx: &str, font_data: &[u8]) -> Result<TtxReport> {
+    let doc = roxmltree::Document::parse(ttx).context("Invalid TTX XML")?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "ttFont" {
+        bail!("Not a TTX document: root element is <{}>", root.tag_name().name());
+    }
+    let raw = RawFace::parse(font_data, 0).context("Failed to parse font")?;
+    let face = Face::parse(font_data, 0).context("Failed to parse font")?;
+
+    let mut report = TtxReport::default();
+    let mut seen = Vec::new();
+    for element in root.children().filter(|n| n.is_element()) {
+        let name = element.tag_name().name();
+        if name == "GlyphOrder" {
+            continue;
+        }
+        let Some(tag) = xml_to_tag(name) else {
+            report.skipped.push(name.to_string());
+            continue;
+        };
+        seen.push(tag);
+        let Some(data) = raw.table(tag) else {
+            report.missing_in_font.push(tag.to_string());
+            continue;
+        };
+
+        let table = tag.to_string().trim_end().to_string();
+        if let Some(hexdata) = element.children().find(|n| n.has_tag_name("hexdata")) {
+            let expected = parse_hexdata(hexdata.text().unwrap_or_default())
+                .with_context(|| format!("Invalid <hexdata> in {}", name))?;
+            let diff = tables::diff_table(tag, &expected, data);
+            if !diff.is_identical() {
+                report.differences.push(TtxDifference {
+                    table: table.clone(),
+                    field: format!("{} differing byte range(s)", diff.byte_ranges.len()),
+                    ttx: Some(format!("{} bytes", diff.len_a)),
+                    font: Some(format!("{} bytes", diff.len_b)),
+                });
+            }
+            report.compared.push(table);
+            continue;
+        }
+
+        let Some(font_fields) = structured_fields(tag, data, &face) else {
+            report.skipped.push(table);
+            continue;
+        };
+        let ttx_fields = flatten(element);
+        for (field, font_value) in &font_fields {
+            let ttx_value = ttx_fields.get(field);
+            if !ttx_value.is_some_and(|v| same_value(v, font_value)) {
+                report.differences.push(TtxDifference {
+                    table: table.clone(),
+                    field: field.clone(),
+                    ttx: ttx_value.cloned(),
+                    font: Some(font_value.clone()),
+                });
+            }
+        }
+        for (field, ttx_value) in &ttx_fields {
+            if !font_fields.contains_key(field) {
+                report.differences.push(TtxDifference {
+                    table: table.clone(),
+                    field: field.clone(),
+                    ttx: Some(ttx_value.clone()),
+                    font: None,
+                });
+            }
+        }
+        report.compared.push(table);
+    }
+
+    report.not_in_ttx = raw
+        .table_records
+        .into_iter()
+        .filter(|record| !seen.contains(&record.tag))
+        .map(|record| record.tag.to_string())
+        .collect();
+    Ok(report)
+}
+
+fn write_glyph_order(out: &mut String, face: &Face) {
+    out.push_str("  <GlyphOrder>\n");
+    out.push_str("    <!-- The 'id' attribute is only for humans; it is ignored when parsed. -->\n");
+    let mut used = BTreeMap::new();
+    for id in 0..face.number_of_glyphs() {
+        let base = face
+            .glyph_name(ttf_parser::GlyphId(id))
+            .map(str::to_string)
+            .unwrap_or_else(|| if id == 0 { ".notdef".to_string() } else { format!("glyph{:05}", id) });
+        // fontTools disambiguates duplicate names with #1, #2, ...
+        let count = used.entry(base.clone()).or_insert(0u32);
+        let name = if *count == 0 { base } else { format!("{}#{}", base, count) };
+        *count += 1;
+        let _ = writeln!(out, "    <GlyphID id=\"{}\" name=\"{}\"/>", id, escape(&name));
+    }
+    out.push_str("  </GlyphOrder>\n");
+}
+
+fn write_table(out: &mut String, tag: Tag, data: &[u8], face: &Face) {
+    let element = tag_to_xml(tag);
+    if &tag.to_bytes() == b"name" {
+        let _ = writeln!(out, "  <{}>", element);
+        for (key, text) in name_records(face) {
+            let _ = writeln!(out, "    <namerecord {}>", key);
+            let _ = writeln!(out, "      {}", escape(&text));
+            out.push_str("    </namerecord>\n");
+        }
+        let _ = writeln!(out, "  </{}>", element);
+        return;
+    }
+
+    if let Some(fields) = layout(tag).map(|layout| decode(layout, tag, data)) {
+        let _ = writeln!(out, "  <{}>", element);
+        let mut open_group: Option<&str> = None;
+        for (name, value) in &fields {
+            let (group, field) = match name.split_once('/') {
+                Some((group, field)) => (Some(group), field),
+                None => (None, name.as_str()),
+            };
+            if open_group != group {
+                if let Some(previous) = open_group {
+                    let _ = writeln!(out, "    </{}>", previous);
+                }
+                if let Some(next) = group {
+                    let _ = writeln!(out, "    <{}>", next);
+                }
+                open_group = group;
+            }
+            let indent = if group.is_some() { "      " } else { "    " };
+            let _ = writeln!(out, "{}<{} value=\"{}\"/>", indent, field, escape(value));
+        }
+        if let Some(previous) = open_group {
+            let _ = writeln!(out, "    </{}>", previous);
+        }
+        let _ = writeln!(out, "  </{}>", element);
+        return;
+    }
+
+    let _ = writeln!(out, "  <{} raw=\"True\">", element);
+    out.push_str("    <hexdata>\n");
+    for chunk in data.chunks(16) {
+        let line: Vec<String> = chunk
+            .chunks(4)
+            .map(|group| group.iter().map(|b| format!("{:02x}", b)).collect())
+            .collect();
+        let _ = writeln!(out, "      {}", line.join(" "));
+    }
+    out.push_str("    </hexdata>\n");
+    let _ = writeln!(out, "  </{}>", element);
+}
+
+fn layout(tag: Tag) -> Option<&'static [(&'static str, Fmt)]> {
+    match &tag.to_bytes() {
+        b"head" => Some(HEAD),
+        b"hhea" => Some(HHEA),
+        b"maxp" => Some(MAXP),
+        b"post" => Some(POST),
+        b"OS/2" => Some(OS2),
+        _ => None,
+    }
+}
+
+/// Decode a fixed layout, stopping where the table (or its version) ends
+fn decode(layout: &[(&str, Fmt)], tag: Tag, data: &[u8]) -> Vec<(String, String)> {
+    let limit = match &tag.to_bytes() {
+        // OS/2 grows by version: v0 ends at usWinDescent, v1 adds code pages,
+        // v2-4 add sxHeight..usMaxContext, v5 adds optical sizes
+        b"OS/2" => match data.get(..2).map(|v| u16::from_be_bytes([v[0], v[1]])) {
+            Some(0) => 78,
+            Some(1) => 86,
+            Some(2..=4) => 96,
+            _ => usize::MAX,
+        },
+        // maxp 0.5 (CFF fonts) has only numGlyphs
+        b"maxp" if data.starts_with(&[0, 0, 0x50, 0]) => 6,
+        _ => usize::MAX,
+    };
+
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    for &(name, fmt) in layout {
+        let end = offset + fmt.size();
+        if end > limit.min(data.len()) {
+            break;
+        }
+        fields.push((name.to_string(), fmt.format(&data[offset..end])));
+        offset = end;
+    }
+    fields
+}
+
+/// Field path → value for the tables this module can decode
+fn structured_fields(tag: Tag, data: &[u8], face: &Face) -> Option<BTreeMap<String, String>> {
+    if &tag.to_bytes() == b"name" {
+        return Some(
+            name_records(face)
+                .into_iter()
+                .map(|(key, text)| (format!("namerecord {}", key), text))
+                .collect(),
+        );
+    }
+    layout(tag).map(|layout| decode(layout, tag, data).into_iter().collect())
+}
+
+/// `nameID=… platformID=… platEncID=… langID=…` attributes and decoded text
+fn name_records(face: &Face) -> Vec<(String, String)> {
+    face.names()
+        .into_iter()
+        .filter_map(|name| {
+            let text = name.to_string().or_else(|| {
+                // Mac Roman records: only the ASCII subset is decoded here
+                name.name.is_ascii().then(|| String::from_utf8_lossy(name.name).into_owned())
+            })?;
+            let key = format!(
+                "nameID=\"{}\" platformID=\"{}\" platEncID=\"{}\" langID=\"{:#x}\"",
+                name.name_id, name.platform_id as u16, name.encoding_id, name.language_id
+            );
+            Some((key, text))
+        })
+        .collect()
+}
+
+/// Field path → value of a TTX table element (`value` attributes and namerecords)
+fn flatten(table: roxmltree::Node) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    for child in table.children().filter(|n| n.is_element()) {
+        let name = child.tag_name().name();
+        if name == "namerecord" {
+            let attr = |key: &str| child.attribute(key).unwrap_or_default().to_string();
+            let lang = parse_number(&attr("langID")).map(|v| format!("{:#x}", v as u32)).unwrap_or_else(|| attr("langID"));
+            let key = format!(
+                "namerecord nameID=\"{}\" platformID=\"{}\" platEncID=\"{}\" langID=\"{}\"",
+                attr("nameID"),
+                attr("platformID"),
+                attr("platEncID"),
+                lang
+            );
+            fields.insert(key, child.text().unwrap_or_default().trim().to_string());
+        } else if let Some(value) = child.attribute("value") {
+            fields.insert(name.to_string(), value.to_string());
+        } else {
+            for grandchild in child.children().filter(|n| n.is_element()) {
+                if let Some(value) = grandchild.attribute("value") {
+                    fields.insert(format!("{}/{}", name, grandchild.tag_name().name()), value.to_string());
+                }
+            }
+        }
+    }
+    fields
+}
+
+fn parse_hexdata(text: &str) -> Result<Vec<u8>> {
+    let digits: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        bail!("odd number of hex digits");
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair)?;
+            u8::from_str_radix(pair, 16).with_context(|| format!("invalid hex byte {:?}", pair))
+        })
+        .collect()
+}
+
+fn parse_number(s: &str) -> Option<f64> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("-0x")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok().map(|v| if s.starts_with('-') { -(v as f64) } else { v as f64 }),
+        None => s.parse().ok(),
+    }
+}
+
+/// Equal as text, as numbers, or as bit strings ignoring grouping
+fn same_value(a: &str, b: &str) -> bool {
+    let (a, b) = (a.trim(), b.trim());
+    if a == b {
+        return true;
+    }
+    if let (Some(x), Some(y)) = (parse_number(a), parse_number(b)) {
+        return x == y;
+    }
+    let squash = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    let (a, b) = (squash(a), squash(b));
+    a.chars().all(|c| c == '0' || c == '1') && a == b
+}
+
+/// `\x00\x01\x00\x00`, `OTTO` or `true` as fontTools writes it
+fn sfnt_version(font_data: &[u8]) -> String {
+    let mut version = font_data.get(..4).unwrap_or_default();
+    // Collections: use the first face's offset table
+    if version == b"ttcf"
+        && let Some(offset) = font_data.get(12..16)
+    {
+        let offset = u32::from_be_bytes([offset[0], offset[1], offset[2], offset[3]]) as usize;
+        version = font_data.get(offset..offset + 4).unwrap_or_default();
+    }
+    version
+        .iter()
+        .map(|&b| if b.is_ascii_alphanumeric() { (b as char).to_string() } else { format!("\\x{:02x}", b) })
+        .collect()
+}
+
+/// Shortest decimal that rounds back to the same 16.16 value, like fontTools' `fl2str`
+fn fixed_to_str(raw: i32) -> String {
+    let value = raw as f64 / 65536.0;
+    for digits in 1..=6 {
+        let text = format!("{:.*}", digits, value);
+        if text.parse::<f64>().is_ok_and(|v| (v * 65536.0).round() as i64 == raw as i64) {
+            let trimmed = text.trim_end_matches('0');
+            return if trimmed.ends_with('.') { format!("{}0", trimmed) } else { trimmed.to_string() };
+        }
+    }
+    value.to_string()
+}
+
+/// fontTools' `num2binary`: bits MSB first, grouped by eight
+fn num_to_binary(value: u32, bits: u32) -> String {
+    (0..bits / 8)
+        .rev()
+        .map(|byte| format!("{:08b}", (value >> (byte * 8)) & 0xFF))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `asctime(gmtime(...))` of seconds since 1904, clamped at the Unix epoch
+fn timestamp_to_string(mac_seconds: i64) -> String {
+    const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+    let secs = (mac_seconds - MAC_EPOCH_OFFSET).max(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{} {} {:>2} {:02}:{:02}:{:02} {}",
+        DAYS[(days % 7) as usize],
+        MONTHS[(month - 1) as usize],
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60,
+        year
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_formats_should_match_fonttools() {
+        assert_eq!(fixed_to_str(0x0001_0000), "1.0");
+        assert_eq!(fixed_to_str(155_320), "2.37");
+        assert_eq!(fixed_to_str(-12 << 16), "-12.0");
+        assert_eq!(num_to_binary(0x1F, 16), "00000000 00011111");
+        assert_eq!(timestamp_to_string(MAC_EPOCH_OFFSET + 1_488_190_676), "Mon Feb 27 10:17:56 2017");
+        assert_eq!(tag_to_xml(Tag::from_bytes(b"OS/2")), "OS_2");
+        assert_eq!(tag_to_xml(Tag::from_bytes(b"cvt ")), "cvt");
+        assert_eq!(xml_to_tag("cvt"), Some(Tag::from_bytes(b"cvt ")));
+        assert_eq!(xml_to_tag(&tag_to_xml(Tag::from_bytes(b"a/b "))), Some(Tag::from_bytes(b"a/b ")));
+    }
+
+    #[test]
+    fn same_value_should_compare_numbers_and_bits() {
+        assert!(same_value("0x10000", "0x00010000"));
+        assert!(same_value("1.0", "1"));
+        assert!(same_value("00000000 00011111", "0000000000011111"));
+        assert!(!same_value("0x5000", "0x10000"));
+        assert_eq!(parse_hexdata("0001 ff\n 10").unwrap(), vec![0, 1, 0xff, 0x10]);
+    }
+}
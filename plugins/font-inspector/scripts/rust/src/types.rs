@@ -1,4 +1,10 @@
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use crate::char_names::Unihan;
+use crate::export_policy::PolicyReport;
+use crate::math::MathReport;
+use crate::path_optimizer::PathOptimizeOptions;
+use crate::review::{GlyphReview, ReviewSheet};
 
 /// Bounding box for a glyph
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +27,49 @@ pub struct GlyphInfo {
     pub bounding_box: Option<BBox>,
     pub contour_count: usize,
     pub point_count: usize,
+    /// Contours reversed by direction normalization (absent when disabled)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flipped_contours: Option<usize>,
+    /// Character name and Unihan data (present with `--char-names`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<CharAnnotation>,
+    /// Outline area and ink balance (present with `--outline-metrics`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outline_metrics: Option<OutlineMetrics>,
+    /// Design review status and comments (present with `--review`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub review: Option<GlyphReview>,
+}
+
+/// Area and ink balance of a glyph outline, in font units with Y up
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutlineMetrics {
+    /// Signed area of each contour; positive is counter-clockwise
+    pub contour_areas: Vec<f64>,
+    /// Net inked area (counters subtracted)
+    pub ink_area: f64,
+    /// Area-weighted center of the ink
+    pub ink_centroid_x: f64,
+    pub ink_centroid_y: f64,
+    /// `ink_centroid_x` minus half the advance width; positive when the ink sits right of center
+    pub center_offset: f64,
+}
+
+/// Human-readable identification of a glyph's character
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharAnnotation {
+    /// Unicode character name, e.g. `CJK UNIFIED IDEOGRAPH-4F60`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Unihan `kTotalStrokes`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_strokes: Option<u8>,
+    /// Kangxi radical number from Unihan `kRSUnicode`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub radical: Option<u8>,
+    /// Unihan `kMandarin` reading(s)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mandarin: Option<String>,
 }
 
 /// Complete font analysis report
@@ -32,6 +81,75 @@ pub struct FontReport {
     pub glyph_count: u16,
     pub total_exported: usize,
     pub glyphs: Vec<GlyphInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction_report: Option<DirectionReport>,
+    /// OpenType MATH table contents (present with `--math` on fonts that have one)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub math: Option<MathReport>,
+    /// Characters an export policy added or removed (present with `--policy`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<PolicyReport>,
+}
+
+/// Summary of contour direction normalization across a report
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DirectionReport {
+    pub flipped_glyph_count: usize,
+    pub flipped_contour_count: usize,
+    /// Unicode values of glyphs whose original direction was changed
+    pub flipped_glyphs: Vec<String>,
+}
+
+impl DirectionReport {
+    /// Build a report from glyphs extracted with direction normalization
+    ///
+    /// Returns `None` when normalization was not requested.
+    pub fn from_glyphs(glyphs: &[GlyphInfo]) -> Option<Self> {
+        let mut report = Self {
+            flipped_glyph_count: 0,
+            flipped_contour_count: 0,
+            flipped_glyphs: Vec::new(),
+        };
+        let mut normalized = false;
+
+        for glyph in glyphs {
+            let Some(flipped) = glyph.flipped_contours else {
+                continue;
+            };
+            normalized = true;
+            if flipped > 0 {
+                report.flipped_glyph_count += 1;
+                report.flipped_contour_count += flipped;
+                report.flipped_glyphs.push(glyph.unicode.clone());
+            }
+        }
+
+        normalized.then_some(report)
+    }
+
+    /// Merge the report of another batch of glyphs into this one
+    pub fn absorb(&mut self, other: DirectionReport) {
+        self.flipped_glyph_count += other.flipped_glyph_count;
+        self.flipped_contour_count += other.flipped_contour_count;
+        self.flipped_glyphs.extend(other.flipped_glyphs);
+    }
+}
+
+/// Options controlling how glyph outlines are extracted
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// Rewind contours to the non-zero convention (outer CCW, inner CW)
+    pub normalize_direction: bool,
+    /// Emit optimized SVG path data (collinear collapse, S/T, optional arcs)
+    pub optimize_paths: Option<PathOptimizeOptions>,
+    /// Annotate glyphs with character names
+    pub char_names: bool,
+    /// Unihan subset for stroke counts and readings (with `char_names`)
+    pub unihan: Option<Arc<Unihan>>,
+    /// Measure contour areas and the ink centroid
+    pub outline_metrics: bool,
+    /// Review sidecar to merge into each glyph
+    pub reviews: Option<Arc<ReviewSheet>>,
 }
 
 /// Font metadata for info command
@@ -47,6 +165,9 @@ pub struct FontMetadata {
     pub ascender: Option<i16>,
     pub descender: Option<i16>,
     pub line_gap: Option<i16>,
+    /// Whether the font carries an OpenType MATH table
+    #[serde(default)]
+    pub has_math: bool,
 }
 
 /// Character range specification
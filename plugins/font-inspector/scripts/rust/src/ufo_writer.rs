@@ -177,6 +177,10 @@ mod tests {
             bounding_box: None,
             contour_count: 1,
             point_count: 2,
+            flipped_contours: None,
+            annotation: None,
+            outline_metrics: None,
+            review: None,
         };
 
         let glyph = create_norad_glyph(&glyph_info)?;
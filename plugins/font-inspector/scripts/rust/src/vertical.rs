@@ -0,0 +1,332 @@
+use kurbo::{Affine, BezPath, Point, Vec2};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use ttf_parser::gsub::{SingleSubstitution, SubstitutionSubtable};
+use ttf_parser::{Face, GlyphId, Tag};
+
+use crate::extractor;
+
+/// Maximum point deviation, in font units, for two outlines to count as the same shape
+pub const DEFAULT_TOLERANCE: f64 = 2.0;
+
+/// How a character is expected to appear in vertical text
+///
+/// Follows the `Tr` / `Tu` classes of Unicode's Vertical_Orientation property
+/// for the CJK punctuation, brackets and small kana that fonts usually cover.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Expected {
+    /// Brackets, dashes, long vowel marks: turned 90°
+    Rotate,
+    /// Commas, full stops, small kana: kept upright and moved toward the upper right
+    Shift,
+}
+
+/// What a `vert` alternate is, relative to its base glyph
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Transform {
+    /// The same outline in the same place
+    Unchanged,
+    /// The same outline, moved
+    Shifted { dx: f64, dy: f64 },
+    /// Rotated clockwise by `degrees` (optionally mirrored first), then moved
+    Rotated { degrees: i32, mirrored: bool, dx: f64, dy: f64 },
+    /// A different outline that no rotation or shift reproduces
+    Redrawn,
+}
+
+/// Problem found with a vertical form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Issue {
+    /// A vertical form is expected but `vert` has none
+    Missing,
+    /// The alternate is a copy of the base glyph
+    Copied,
+    /// The alternate should be rotated but is only shifted
+    NotRotated,
+    /// The alternate should stay upright but is rotated
+    UnexpectedRotation,
+}
+
+/// One base glyph and its vertical alternate
+#[derive(Debug, Serialize)]
+pub struct VerticalForm {
+    /// `U+XXXX c`
+    pub character: String,
+    pub base_glyph: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alternate_glyph: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<Expected>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transform: Option<Transform>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issue: Option<Issue>,
+}
+
+/// Result of checking a font's `vert` feature
+#[derive(Debug, Serialize)]
+pub struct VerticalReport {
+    pub font: String,
+    /// Lookups referenced by any `vert` feature
+    pub vert_lookups: usize,
+    pub substitutions: usize,
+    pub forms: Vec<VerticalForm>,
+    pub issue_count: usize,
+}
+
+/// Expected vertical behaviour of `c`, for characters that need a vertical form
+pub fn expected_orientation(c: char) -> Option<Expected> {
+    match c as u32 {
+        0x2014 | 0x2015 | 0x2025 | 0x2026 => Some(Expected::Rotate),
+        0x3008..=0x3011 | 0x3014..=0x301C | 0x30FC => Some(Expected::Rotate),
+        0xFF08 | 0xFF09 | 0xFF0D | 0xFF1A..=0xFF1E | 0xFF3B | 0xFF3D | 0xFF3F => Some(Expected::Rotate),
+        0xFF5B | 0xFF5D..=0xFF60 => Some(Expected::Rotate),
+        0x3001 | 0x3002 | 0xFF0C | 0xFF0E => Some(Expected::Shift),
+        0x3041 | 0x3043 | 0x3045 | 0x3047 | 0x3049 | 0x3063 | 0x3083 | 0x3085 | 0x3087 | 0x308E | 0x3095
+        | 0x3096 => Some(Expected::Shift),
+        0x30A1 | 0x30A3 | 0x30A5 | 0x30A7 | 0x30A9 | 0x30C3 | 0x30E3 | 0x30E5 | 0x30E7 | 0x30EE | 0x30F5
+        | 0x30F6 | 0x31F0..=0x31FF => Some(Expected::Shift),
+        _ => None,
+    }
+}
+
+/// Single substitutions of every lookup referenced by a `vert` feature, in lookup order
+fn vert_subtables<'a>(face: &Face<'a>) -> (usize, Vec<SingleSubstitution<'a>>) {
+    let Some(gsub) = face.tables().gsub else {
+        return (0, Vec::new());
+    };
+    let vert = Tag::from_bytes(b"vert");
+    let indices: BTreeSet<u16> = gsub
+        .features
+        .into_iter()
+        .filter(|feature| feature.tag == vert)
+        .flat_map(|feature| feature.lookup_indices)
+        .collect();
+
+    let subtables = indices
+        .iter()
+        .filter_map(|&index| gsub.lookups.get(index))
+        .flat_map(|lookup| lookup.subtables.into_iter::<SubstitutionSubtable>())
+        .filter_map(|subtable| match subtable {
+            SubstitutionSubtable::Single(single) => Some(single),
+            _ => None,
+        })
+        .collect();
+    (indices.len(), subtables)
+}
+
+/// Apply the first single substitution that covers `glyph`
+fn substitute(subtables: &[SingleSubstitution], glyph: GlyphId) -> Option<GlyphId> {
+    subtables.iter().find_map(|subtable| {
+        let index = subtable.coverage().get(glyph)?;
+        match subtable {
+            SingleSubstitution::Format1 { delta, .. } => Some(GlyphId(glyph.0.wrapping_add(*delta as u16))),
+            SingleSubstitution::Format2 { substitutes, .. } => substitutes.get(index),
+        }
+    })
+}
+
+fn outline_points(path: &BezPath) -> Vec<Point> {
+    path.elements()
+        .iter()
+        .flat_map(|el| {
+            let mut points = Vec::with_capacity(3);
+            match *el {
+                kurbo::PathEl::MoveTo(p) | kurbo::PathEl::LineTo(p) => points.push(p),
+                kurbo::PathEl::QuadTo(p1, p) => points.extend([p1, p]),
+                kurbo::PathEl::CurveTo(p1, p2, p) => points.extend([p1, p2, p]),
+                kurbo::PathEl::ClosePath => {}
+            }
+            points
+        })
+        .collect()
+}
+
+fn mean(points: &[Point]) -> Point {
+    let sum = points.iter().fold(Vec2::ZERO, |acc, p| acc + p.to_vec2());
+    (sum / points.len() as f64).to_point()
+}
+
+/// Largest distance from a point in either set to its nearest neighbour in the other
+///
+/// Order-free, so redrawn start points and reordered contours still match.
+fn hausdorff(a: &[Point], b: &[Point]) -> f64 {
+    let directed = |from: &[Point], to: &[Point]| {
+        from.iter()
+            .map(|p| to.iter().map(|q| p.distance(*q)).fold(f64::INFINITY, f64::min))
+            .fold(0.0, f64::max)
+    };
+    directed(a, b).max(directed(b, a))
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Describe `alternate` as a transform of `base`
+///
+/// Tries the identity, then 90°/180°/270° clockwise rotations, each with and
+/// without a horizontal mirror, aligning point centroids before comparing.
+///
+/// # Arguments
+/// * `base` - Outline of the horizontal glyph, in font units
+/// * `alternate` - Outline of its `vert` substitute
+/// * `tolerance` - Maximum point deviation in font units
+pub fn classify(base: &BezPath, alternate: &BezPath, tolerance: f64) -> Transform {
+    let base_points = outline_points(base);
+    let alt_points = outline_points(alternate);
+    if base_points.is_empty() || base_points.len() != alt_points.len() {
+        return Transform::Redrawn;
+    }
+    let alt_center = mean(&alt_points);
+
+    for mirrored in [false, true] {
+        for degrees in [0, 90, 180, 270] {
+            let mirror = if mirrored { Affine::FLIP_X } else { Affine::IDENTITY };
+            let transform = Affine::rotate(-(degrees as f64).to_radians()) * mirror;
+            let moved: Vec<Point> = base_points.iter().map(|&p| transform * p).collect();
+            let shift = alt_center - mean(&moved);
+            let aligned: Vec<Point> = moved.iter().map(|&p| p + shift).collect();
+            if hausdorff(&aligned, &alt_points) > tolerance {
+                continue;
+            }
+            let (dx, dy) = (round2(shift.x), round2(shift.y));
+            return match (degrees, mirrored) {
+                (0, false) if shift.hypot() <= tolerance => Transform::Unchanged,
+                (0, false) => Transform::Shifted { dx, dy },
+                _ => Transform::Rotated { degrees, mirrored, dx, dy },
+            };
+        }
+    }
+    Transform::Redrawn
+}
+
+fn issue_for(expected: Option<Expected>, transform: Option<&Transform>) -> Option<Issue> {
+    match (expected, transform) {
+        (Some(_), None) => Some(Issue::Missing),
+        (_, Some(Transform::Unchanged)) => Some(Issue::Copied),
+        (Some(Expected::Rotate), Some(Transform::Shifted { .. })) => Some(Issue::NotRotated),
+        (Some(Expected::Shift), Some(Transform::Rotated { .. })) => Some(Issue::UnexpectedRotation),
+        _ => None,
+    }
+}
+
+/// Pair every mapped character with its `vert` alternate and verify the alternate
+///
+/// Characters are reported when they have an alternate or are expected to
+/// need one (see [`expected_orientation`]).
+///
+/// # Arguments
+/// * `face` - Font to check
+/// * `font` - Name used in the report
+/// * `tolerance` - Maximum point deviation in font units
+///
+/// # Returns
+/// One entry per reported character, in codepoint order
+pub fn check_vertical_forms(face: &Face, font: &str, tolerance: f64) -> VerticalReport {
+    let (vert_lookups, subtables) = vert_subtables(face);
+
+    let mut codepoints = BTreeSet::new();
+    if let Some(cmap) = face.tables().cmap {
+        for subtable in cmap.subtables.into_iter().filter(|st| st.is_unicode()) {
+            subtable.codepoints(|cp| {
+                codepoints.insert(cp);
+            });
+        }
+    }
+
+    let mut forms = Vec::new();
+    for c in codepoints.into_iter().filter_map(char::from_u32) {
+        let Some(base_glyph) = face.glyph_index(c) else {
+            continue;
+        };
+        let alternate = substitute(&subtables, base_glyph).filter(|&alt| alt != base_glyph);
+        let expected = expected_orientation(c);
+        if alternate.is_none() && expected.is_none() {
+            continue;
+        }
+
+        let transform = alternate.map(|alt| {
+            match (extractor::glyph_outline(face, base_glyph), extractor::glyph_outline(face, alt)) {
+                (Some(base), Some(alt)) => classify(&base, &alt, tolerance),
+                _ => Transform::Redrawn,
+            }
+        });
+        forms.push(VerticalForm {
+            character: format!("U+{:04X} {}", c as u32, c),
+            base_glyph: base_glyph.0,
+            alternate_glyph: alternate.map(|g| g.0),
+            expected,
+            issue: issue_for(expected, transform.as_ref()),
+            transform,
+        });
+    }
+
+    VerticalReport {
+        font: font.to_string(),
+        vert_lookups,
+        substitutions: forms.iter().filter(|f| f.alternate_glyph.is_some()).count(),
+        issue_count: forms.iter().filter(|f| f.issue.is_some()).count(),
+        forms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asymmetric "L" shape so rotations and mirrors are distinguishable
+    fn ell() -> BezPath {
+        let mut path = BezPath::new();
+        path.move_to((100.0, 100.0));
+        path.line_to((100.0, 500.0));
+        path.line_to((160.0, 500.0));
+        path.quad_to((160.0, 200.0), (400.0, 160.0));
+        path.line_to((400.0, 100.0));
+        path.close_path();
+        path
+    }
+
+    #[test]
+    fn classify_should_recognize_rotations_and_shifts() {
+        let base = ell();
+
+        assert_eq!(classify(&base, &base, DEFAULT_TOLERANCE), Transform::Unchanged);
+
+        let shifted = Affine::translate((300.0, 420.0)) * base.clone();
+        assert_eq!(classify(&base, &shifted, DEFAULT_TOLERANCE), Transform::Shifted { dx: 300.0, dy: 420.0 });
+
+        // 90° clockwise about the em center, the usual vertical bracket
+        let rotated = Affine::rotate_about(-std::f64::consts::FRAC_PI_2, (500.0, 380.0)) * base.clone();
+        assert!(matches!(
+            classify(&base, &rotated, DEFAULT_TOLERANCE),
+            Transform::Rotated { degrees: 90, mirrored: false, .. }
+        ));
+
+        let mirrored = Affine::rotate(-std::f64::consts::FRAC_PI_2) * Affine::FLIP_X * base.clone();
+        assert!(matches!(
+            classify(&base, &mirrored, DEFAULT_TOLERANCE),
+            Transform::Rotated { degrees: 90, mirrored: true, .. }
+        ));
+    }
+
+    #[test]
+    fn classify_should_report_redrawn_outlines_and_issues() {
+        let base = ell();
+        let mut redrawn = ell();
+        redrawn.line_to((50.0, 50.0));
+        assert_eq!(classify(&base, &redrawn, DEFAULT_TOLERANCE), Transform::Redrawn);
+
+        let shifted = Transform::Shifted { dx: 0.0, dy: 500.0 };
+        assert_eq!(issue_for(Some(Expected::Rotate), Some(&shifted)), Some(Issue::NotRotated));
+        assert_eq!(issue_for(Some(Expected::Shift), Some(&shifted)), None);
+        assert_eq!(issue_for(Some(Expected::Shift), None), Some(Issue::Missing));
+        assert_eq!(issue_for(None, Some(&Transform::Unchanged)), Some(Issue::Copied));
+        assert_eq!(expected_orientation('「'), Some(Expected::Rotate));
+        assert_eq!(expected_orientation('。'), Some(Expected::Shift));
+        assert_eq!(expected_orientation('A'), None);
+    }
+}
@@ -0,0 +1,71 @@
+/* Authors: Joysusy & Violet Klaudia 💖
+ * violet_cipher.h — C API of libviolet_cipher (src/ffi.rs)
+ *
+ * Ownership
+ *   - Every pointer argument is borrowed for the duration of the call only;
+ *     the library keeps none of them. A byte pointer may be NULL when its
+ *     length is 0.
+ *   - On success *out holds bytes the library allocated. Hand them back to
+ *     violet_buffer_free exactly once, from any thread; never free() them.
+ *     violet_buffer_free wipes the bytes before releasing them.
+ *   - On failure the call returns VIOLET_ERR, *out is empty ({NULL, 0}) and
+ *     violet_last_error() describes the failure.
+ *
+ * Errors
+ *   violet_last_error() is per thread: it describes the last violet_encrypt
+ *   or violet_decrypt on the calling thread, or is NULL if that call
+ *   succeeded. The string belongs to the library and stays valid until the
+ *   next violet_encrypt or violet_decrypt on the same thread.
+ *
+ * Arguments
+ *   salt_label  "local" (<name>.enc), "git" (<name>.git.enc) or any other
+ *               domain of 1-32 characters a-z 0-9 . _ -, NUL-terminated
+ *   name        logical file name, e.g. "rules-index.json", NUL-terminated
+ *               UTF-8; files are bound to it and to the salt label and will
+ *               not open under others
+ *   kdf         Argon2id costs per layer, or NULL for the CLI's defaults.
+ *               Files with a VSC! preamble carry their own and ignore it
+ *
+ * violet_encrypt writes v5 JSON, as `violet-cipher encrypt-local` does.
+ * violet_decrypt opens v2–v6 and returns the plaintext bytes unchanged.
+ * Calls are thread-safe; each derives its own keys (three Argon2id runs).
+ */
+#ifndef VIOLET_CIPHER_H
+#define VIOLET_CIPHER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define VIOLET_OK 0
+#define VIOLET_ERR (-1)
+
+typedef struct {
+    uint8_t *ptr;
+    size_t len;
+} VioletBuffer;
+
+typedef struct {
+    uint32_t memory_kib;
+    uint32_t iterations;
+    uint32_t parallelism;
+} VioletKdf;
+
+int violet_encrypt(const uint8_t *passphrase, size_t passphrase_len, const char *salt_label, const char *name,
+                   const uint8_t *plaintext, size_t plaintext_len, const VioletKdf *kdf, VioletBuffer *out);
+
+int violet_decrypt(const uint8_t *passphrase, size_t passphrase_len, const char *salt_label, const char *name,
+                   const uint8_t *data, size_t data_len, const VioletKdf *kdf, VioletBuffer *out);
+
+void violet_buffer_free(VioletBuffer *buf);
+
+const char *violet_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* VIOLET_CIPHER_H */
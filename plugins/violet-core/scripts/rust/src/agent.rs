@@ -0,0 +1,327 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Unlock agent — hold the passphrase once, seal and open files for other invocations
+//
+// `agent start` asks for the passphrase once (or takes --key/--key-file/--key-fd),
+// then answers on a Unix socket (default: .violet-agent.sock in the data
+// directory, mode 0600). Commands given `--agent <socket>` or VIOLET_AGENT_SOCK,
+// and no passphrase of their own, send the agent the plaintext or ciphertext
+// and get the result back; the passphrase never leaves the agent.
+//
+// Derived layer keys stay in the key cache for the agent's lifetime, so
+// a file opened twice costs Argon2id once. Sealing draws each layer's salt
+// once per agent and salt label, so every file sealed in one session shares
+// its layer keys (nonces stay random per file); files from different sessions
+// still have unrelated keys. After --ttl seconds without a request the agent
+// wipes the cache and passphrase and exits.
+//
+// One JSON request per connection, answered with one JSON reply. Binary
+// payloads travel as hex.
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{bail, Context, Result};
+use argon2::Params as KdfParams;
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::header::{Compression, ContentType, Header};
+use crate::key_source::KeyArgs;
+use crate::key_cache;
+use crate::keyring::KdfCosts;
+use crate::preamble::Suite;
+use crate::logging;
+use crate::{decrypt_payload, resolve_data_dir, v5_encrypt, verify_token, Binding};
+
+pub const SOCKET_FILE: &str = ".violet-agent.sock";
+/// How often the idle agent checks for connections and its TTL
+#[cfg(unix)]
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Subcommand)]
+pub enum AgentCommand {
+    /// Ask for the passphrase once and serve other invocations until stopped or idle for --ttl
+    Start {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Socket to listen on (default: .violet-agent.sock in the data directory)
+        #[arg(long)]
+        socket: Option<PathBuf>,
+        /// Seconds without a request before the agent wipes its keys and exits (0: never)
+        #[arg(long, default_value_t = 900)]
+        ttl: u64,
+    },
+    /// Show the key ID the agent on a socket holds
+    Status {
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+    /// Wipe the agent's keys and stop it
+    Stop {
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "kebab-case")]
+enum Request {
+    KeyId,
+    Seal {
+        salt_label: String,
+        name: String,
+        content_type: String,
+        compress: bool,
+        #[serde(default)]
+        suite: Suite,
+        kdf: KdfCosts,
+        plaintext: String,
+        #[serde(default)]
+        plaintext_mtime: Option<SystemTime>,
+    },
+    Open { salt_label: String, name: String, kdf: KdfCosts, data: String },
+    Stop,
+}
+
+impl Drop for Request {
+    fn drop(&mut self) {
+        if let Request::Seal { plaintext, .. } = self {
+            plaintext.zeroize();
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "reply", rename_all = "kebab-case")]
+enum Reply {
+    KeyId { key_id: String },
+    Sealed { data: String },
+    Opened { content_type: String, data: String },
+    Stopped,
+    Error { message: String },
+}
+
+impl Drop for Reply {
+    fn drop(&mut self) {
+        if let Reply::Opened { data, .. } = self {
+            data.zeroize();
+        }
+    }
+}
+
+/// The client end of an agent's socket
+pub struct Client {
+    socket: PathBuf,
+    key_id: OnceLock<String>,
+}
+
+impl Client {
+    pub fn new(socket: PathBuf) -> Self {
+        Self { socket, key_id: OnceLock::new() }
+    }
+
+    fn call(&self, request: &Request) -> Result<Reply> {
+        let json = Zeroizing::new(serde_json::to_vec(request)?);
+        let reply = Zeroizing::new(exchange(&self.socket, &json)?);
+        let reply: Reply = serde_json::from_slice(&reply).context("parse agent reply")?;
+        if let Reply::Error { message } = &reply {
+            bail!("agent: {}", message);
+        }
+        Ok(reply)
+    }
+
+    pub fn key_id(&self) -> Result<String> {
+        if let Some(key_id) = self.key_id.get() {
+            return Ok(key_id.clone());
+        }
+        match &self.call(&Request::KeyId)? {
+            Reply::KeyId { key_id } => Ok(self.key_id.get_or_init(|| key_id.clone()).clone()),
+            _ => bail!("agent sent an unexpected reply"),
+        }
+    }
+
+    pub fn seal(&self, binding: Binding, plaintext: &[u8], header: &Header, kdf: &KdfParams) -> Result<Vec<u8>> {
+        let request = Request::Seal {
+            salt_label: binding.salt_label.to_string(),
+            name: binding.name.to_string(),
+            content_type: header.content_type.as_str().to_string(),
+            compress: header.compression != Compression::None,
+            suite: header.suite,
+            kdf: KdfCosts::from(kdf),
+            plaintext: hex::encode(plaintext),
+            plaintext_mtime: header.plaintext_mtime,
+        };
+        match &self.call(&request)? {
+            Reply::Sealed { data } => hex::decode(data).context("agent sent invalid hex"),
+            _ => bail!("agent sent an unexpected reply"),
+        }
+    }
+
+    pub fn open(&self, binding: Binding, data: &[u8], kdf: &KdfParams) -> Result<(Header, Vec<u8>)> {
+        let request = Request::Open {
+            salt_label: binding.salt_label.to_string(),
+            name: binding.name.to_string(),
+            kdf: KdfCosts::from(kdf),
+            data: hex::encode(data),
+        };
+        match &self.call(&request)? {
+            Reply::Opened { content_type, data } => {
+                let header = Header::new(ContentType::parse(content_type)?, Compression::None);
+                Ok((header, hex::decode(data).context("agent sent invalid hex")?))
+            }
+            _ => bail!("agent sent an unexpected reply"),
+        }
+    }
+}
+
+fn answer(passphrase: &[u8], request: &Request) -> Result<Reply> {
+    Ok(match request {
+        Request::KeyId => Reply::KeyId { key_id: verify_token::derive_key_id(passphrase)? },
+        Request::Seal { salt_label, name, content_type, compress, suite, kdf, plaintext, plaintext_mtime } => {
+            let plaintext = Zeroizing::new(hex::decode(plaintext).context("plaintext is not hex")?);
+            let header = Header::new(ContentType::parse(content_type)?, Compression::from_flag(*compress));
+            let header = header.with_suite(*suite).with_plaintext_mtime(*plaintext_mtime);
+            let binding = Binding::new(salt_label, name);
+            let sealed = v5_encrypt(passphrase, binding, &plaintext, &header, &kdf.params()?)?;
+            Reply::Sealed { data: hex::encode(sealed) }
+        }
+        Request::Open { salt_label, name, kdf, data } => {
+            let data = hex::decode(data).context("data is not hex")?;
+            let binding = Binding::new(salt_label, name);
+            let (header, plain) = decrypt_payload(passphrase, binding, &data, &kdf.params()?)?;
+            let plain = Zeroizing::new(plain);
+            Reply::Opened { content_type: header.content_type.as_str().to_string(), data: hex::encode(&*plain) }
+        }
+        Request::Stop => Reply::Stopped,
+    })
+}
+
+#[cfg(unix)]
+fn exchange(socket: &Path, request: &[u8]) -> Result<Vec<u8>> {
+    use std::io::{Read, Write};
+    use std::net::Shutdown;
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket)
+        .with_context(|| format!("no agent answering on {:?} — is `violet-cipher agent start` running?", socket))?;
+    stream.write_all(request).with_context(|| format!("write {:?}", socket))?;
+    stream.shutdown(Shutdown::Write)?;
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).with_context(|| format!("read {:?}", socket))?;
+    Ok(reply)
+}
+
+#[cfg(not(unix))]
+fn exchange(_socket: &Path, _request: &[u8]) -> Result<Vec<u8>> {
+    bail!("the unlock agent is only supported on Unix")
+}
+
+/// Answer requests on `socket` until stopped or idle for `ttl` (zero: never)
+#[cfg(unix)]
+fn serve(passphrase: &[u8], socket: &Path, ttl: Duration) -> Result<()> {
+    use std::io::{ErrorKind, Read, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::time::Instant;
+
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            bail!("another agent is already serving {:?}", socket);
+        }
+        std::fs::remove_file(socket).with_context(|| format!("remove stale socket {:?}", socket))?;
+    }
+    let listener = UnixListener::bind(socket).with_context(|| format!("bind {:?}", socket))?;
+    std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))
+        .with_context(|| format!("chmod {:?}", socket))?;
+    listener.set_nonblocking(true)?;
+
+    key_cache::share_salts();
+    let served = (|| -> Result<()> {
+        // The export line is meant for `eval`, so it is all that goes to stdout
+        logging::claim_stdout();
+        match verify_token::salted_key_id(passphrase)? {
+            Some(key_id) => info!("🔑 Agent holding key ID {} on {:?}", key_id, socket),
+            None => info!("🔑 Agent holding a key on {:?} (no key ID until the data directory has a salt)", socket),
+        }
+        println!("VIOLET_AGENT_SOCK={}; export VIOLET_AGENT_SOCK", socket.display());
+        let mut last_request = Instant::now();
+        loop {
+            let mut stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    if !ttl.is_zero() && last_request.elapsed() >= ttl {
+                        info!("⏱️  Idle for {}s — wiping keys.", ttl.as_secs());
+                        return Ok(());
+                    }
+                    std::thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+                Err(e) => return Err(e).context("accept agent connection"),
+            };
+            last_request = Instant::now();
+            stream.set_nonblocking(false)?;
+            let mut json = Zeroizing::new(Vec::new());
+            if stream.read_to_end(&mut json).is_err() {
+                continue;
+            }
+            let reply = match serde_json::from_slice::<Request>(&json) {
+                Ok(request) => answer(passphrase, &request)
+                    .unwrap_or_else(|e| Reply::Error { message: format!("{:#}", e) }),
+                Err(e) => Reply::Error { message: format!("bad request: {}", e) },
+            };
+            let stop = matches!(reply, Reply::Stopped);
+            let _ = stream.write_all(&Zeroizing::new(serde_json::to_vec(&reply)?));
+            if stop {
+                info!("🛑 Stop requested — wiping keys.");
+                return Ok(());
+            }
+        }
+    })();
+    key_cache::end();
+    let _ = std::fs::remove_file(socket);
+    served
+}
+
+#[cfg(not(unix))]
+fn serve(_passphrase: &[u8], _socket: &Path, _ttl: Duration) -> Result<()> {
+    bail!("the unlock agent is only supported on Unix")
+}
+
+pub fn default_socket(data_dir: &Path) -> PathBuf {
+    data_dir.join(SOCKET_FILE)
+}
+
+fn socket_path(socket: Option<PathBuf>, data_dir: Option<PathBuf>) -> PathBuf {
+    socket.unwrap_or_else(|| default_socket(&resolve_data_dir(data_dir)))
+}
+
+pub fn run(command: AgentCommand) -> Result<()> {
+    match command {
+        AgentCommand::Start { key, data_dir, socket, ttl } => {
+            // Absolute, so the VIOLET_AGENT_SOCK line works from any directory
+            let socket = std::path::absolute(socket_path(socket, data_dir))?;
+            let passphrase = key.resolve_or_prompt("🔑 Passphrase for the agent: ")?;
+            serve(&passphrase, &socket, Duration::from_secs(ttl))
+        }
+        AgentCommand::Status { data_dir, socket } => {
+            let socket = socket_path(socket, data_dir);
+            let key_id = Client::new(socket.clone()).key_id()?;
+            info!("🔑 Agent on {:?} holds key ID {}", socket, key_id);
+            Ok(())
+        }
+        AgentCommand::Stop { data_dir, socket } => {
+            let socket = socket_path(socket, data_dir);
+            Client::new(socket.clone()).call(&Request::Stop)?;
+            info!("🛑 Agent on {:?} stopped.", socket);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,395 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Data-tree backups — the whole data directory as one dated, encrypted archive
+//
+// Archive body, sealed once as a zstd-compressed v5 blob:
+//   manifest length u32 (big-endian) | manifest JSON | file contents in manifest order
+// Encrypted files go in byte-for-byte; plaintext files are protected by the
+// archive's own encryption. The manifest records each file's size and
+// SHA-256, and restore checks every entry before it writes anything.
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{bail, Context, Result};
+use argon2::Params as KdfParams;
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+use crate::header::{Compression, ContentType, Header};
+use crate::kdf::KdfArgs;
+use crate::key_source::KeyArgs;
+use crate::transaction::Transaction;
+use crate::{
+    atomic, backup, bundle, container, describe_blob, lock, logging, preamble, print_dry_run_banner, resolve_data_dir,
+    transaction, v5_decrypt, v5_encrypt, write_atomic, Binding, LOCAL_SALT,
+};
+
+const ARCHIVE_PREFIX: &str = "violet-data-";
+const ARCHIVE_SUFFIX: &str = ".vbak";
+/// Archives are bound to this name instead of their file name, so they can be renamed and moved
+const ARCHIVE_NAME: &str = "violet-data-backup";
+const CONTENT_TYPE: &str = "violet-backup";
+const MANIFEST_FORMAT: u32 = 1;
+
+#[derive(Subcommand)]
+pub enum BackupCommand {
+    /// Snapshot the data directory to <archive dir>/violet-data-<UTC timestamp>.vbak
+    Create {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Where archives are kept (default: data-backups next to the data directory)
+        #[arg(long)]
+        archive_dir: Option<PathBuf>,
+        /// Archives kept; older ones are deleted once the new one is written
+        #[arg(long, default_value_t = 10)]
+        keep: usize,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write an archive's files back into the data directory (all-or-nothing)
+    Restore {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        /// Archive to restore (default: the newest in the archive directory)
+        #[arg(long)]
+        archive: Option<PathBuf>,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        #[arg(long)]
+        archive_dir: Option<PathBuf>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List archives, oldest first (needs no passphrase)
+    List {
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        #[arg(long)]
+        archive_dir: Option<PathBuf>,
+    },
+    /// Delete all but the newest archives
+    Prune {
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        #[arg(long)]
+        archive_dir: Option<PathBuf>,
+        /// Archives kept
+        #[arg(long)]
+        keep: usize,
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Table of contents written at the front of every archive (and export bundle)
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: u32,
+    /// UTC timestamp of the snapshot
+    pub created: String,
+    pub files: Vec<Entry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Entry {
+    /// Relative to the data directory, `/`-separated
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+    /// Already ciphertext when archived (.enc or a v4–v6 blob)
+    pub encrypted: bool,
+}
+
+impl Manifest {
+    pub fn new(created: String) -> Self {
+        Self { format: MANIFEST_FORMAT, created, files: Vec::new() }
+    }
+
+    /// List `data` under `path`; its contents go into the body in the same order
+    pub fn add(&mut self, path: String, data: &[u8]) {
+        self.files.push(Entry {
+            encrypted: path.ends_with(".enc") || preamble::version(data).is_some(),
+            size: data.len() as u64,
+            sha256: hex::encode(Sha256::digest(data)),
+            path,
+        });
+    }
+}
+
+fn resolve_archive_dir(archive_dir: Option<PathBuf>, data_dir: &Path) -> PathBuf {
+    archive_dir.unwrap_or_else(|| data_dir.join("..").join("data-backups"))
+}
+
+pub fn run(command: BackupCommand) -> Result<()> {
+    match command {
+        BackupCommand::Create { key, kdf, data_dir, archive_dir, keep, dry_run } => {
+            let key = key.resolve()?;
+            let data_dir = resolve_data_dir(data_dir);
+            let archive_dir = resolve_archive_dir(archive_dir, &data_dir);
+            create(&key, &kdf.params()?, &data_dir, &archive_dir, keep, dry_run)
+        }
+        BackupCommand::Restore { key, kdf, archive, data_dir, archive_dir, dry_run } => {
+            let key = key.resolve()?;
+            let data_dir = resolve_data_dir(data_dir);
+            let archive = match archive {
+                Some(path) => path,
+                None => {
+                    let archive_dir = resolve_archive_dir(archive_dir, &data_dir);
+                    list_archives(&archive_dir)?
+                        .pop()
+                        .with_context(|| format!("no archives found in {:?}", archive_dir))?
+                }
+            };
+            restore(&key, &kdf.params()?, &archive, &data_dir, dry_run)
+        }
+        BackupCommand::List { data_dir, archive_dir } => {
+            let archive_dir = resolve_archive_dir(archive_dir, &resolve_data_dir(data_dir));
+            let archives = list_archives(&archive_dir)?;
+            logging::out(format_args!("💾 {} archive(s) in {:?} (oldest first):", archives.len(), archive_dir));
+            for path in &archives {
+                let data = fs::read(path).with_context(|| format!("read {:?}", path))?;
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                logging::out(format_args!("  {} ({})", name, describe_blob(path, &data)));
+            }
+            Ok(())
+        }
+        BackupCommand::Prune { data_dir, archive_dir, keep, dry_run } => {
+            let archive_dir = resolve_archive_dir(archive_dir, &resolve_data_dir(data_dir));
+            print_dry_run_banner(dry_run);
+            let removed = prune(&archive_dir, keep.max(1), dry_run)?;
+            let verb = if dry_run { "would be deleted" } else { "deleted" };
+            info!("💾 {} archive(s) past the newest {} {}.", removed, keep.max(1), verb);
+            Ok(())
+        }
+    }
+}
+
+fn create(
+    passphrase: &[u8],
+    kdf: &KdfParams,
+    data_dir: &Path,
+    archive_dir: &Path,
+    keep: usize,
+    dry_run: bool,
+) -> Result<()> {
+    let stamp = backup::utc_timestamp(SystemTime::now());
+    let mut manifest = Manifest::new(stamp.clone());
+    let mut contents = Vec::new();
+    for (relative, path) in collect_files(data_dir)? {
+        let data = fs::read(&path).with_context(|| format!("read {:?}", path))?;
+        manifest.add(relative, &data);
+        contents.push(data);
+    }
+    if manifest.files.is_empty() {
+        bail!("nothing to back up in {:?}", data_dir);
+    }
+    let encrypted = manifest.files.iter().filter(|e| e.encrypted).count();
+    let plaintext = manifest.files.len() - encrypted;
+
+    let mut dest = archive_dir.join(format!("{}{}{}", ARCHIVE_PREFIX, stamp, ARCHIVE_SUFFIX));
+    let mut n = 1;
+    while dest.exists() {
+        dest = archive_dir.join(format!("{}{}-{}{}", ARCHIVE_PREFIX, stamp, n, ARCHIVE_SUFFIX));
+        n += 1;
+    }
+
+    print_dry_run_banner(dry_run);
+    if dry_run {
+        info!(
+            "  📝 Would write {} ({} encrypted + {} plaintext file(s))",
+            dest.display(),
+            encrypted,
+            plaintext
+        );
+        // The new archive counts toward `keep`
+        prune(archive_dir, keep.max(1) - 1, true)?;
+        return Ok(());
+    }
+
+    let body = pack(&manifest, &contents)?;
+    let header = Header::new(ContentType::parse(CONTENT_TYPE)?, Compression::Zstd);
+    let sealed = v5_encrypt(passphrase, Binding::new(LOCAL_SALT, ARCHIVE_NAME), &body, &header, kdf)?;
+    fs::create_dir_all(archive_dir).with_context(|| format!("create {:?}", archive_dir))?;
+    write_atomic(&dest, &sealed)?;
+    info!(
+        "💾 Backed up {} encrypted + {} plaintext file(s) → {:?} ({} bytes)",
+        encrypted,
+        plaintext,
+        dest,
+        sealed.len()
+    );
+    prune(archive_dir, keep.max(1), false)?;
+    Ok(())
+}
+
+fn restore(passphrase: &[u8], kdf: &KdfParams, archive: &Path, data_dir: &Path, dry_run: bool) -> Result<()> {
+    let data = fs::read(archive).with_context(|| format!("read {:?}", archive))?;
+    let (header, body) = v5_decrypt(passphrase, Binding::new(LOCAL_SALT, ARCHIVE_NAME), &data, kdf)
+        .with_context(|| format!("open archive {:?}", archive))?;
+    if header.content_type.as_str() != CONTENT_TYPE {
+        bail!("{:?} is a {} file, not a data backup", archive, header.content_type);
+    }
+    let (manifest, files) = unpack(&body)?;
+
+    print_dry_run_banner(dry_run);
+    let mut txn = Transaction::new();
+    let (mut created, mut replaced, mut unchanged) = (0, 0, 0);
+    for (entry, contents) in manifest.files.iter().zip(files) {
+        let dest = data_dir.join(&entry.path);
+        let verb = match fs::read(&dest) {
+            Ok(old) if old == contents => {
+                unchanged += 1;
+                continue;
+            }
+            Ok(_) => {
+                replaced += 1;
+                "overwrite"
+            }
+            Err(_) => {
+                created += 1;
+                "create"
+            }
+        };
+        if dry_run {
+            info!("  📝 Would {} {}", verb, entry.path);
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("create {:?}", parent))?;
+        }
+        txn.stage(&dest, contents)?;
+    }
+    if !dry_run {
+        txn.commit()?;
+    }
+    info!(
+        "💾 {} snapshot {} into {:?}: {} created, {} overwritten, {} unchanged",
+        if dry_run { "Would restore" } else { "Restored" },
+        manifest.created,
+        data_dir,
+        created,
+        replaced,
+        unchanged
+    );
+    Ok(())
+}
+
+/// Every regular file under `dir` with its `/`-separated relative path, sorted.
+/// Symlinks, in-flight temp files, archives, bundles and containers are skipped
+pub fn collect_files(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current).with_context(|| format!("read {:?}", current))? {
+            let path = entry?.path();
+            let meta = fs::symlink_metadata(&path).with_context(|| format!("stat {:?}", path))?;
+            if meta.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let transient = [
+                lock::LOCK_FILE,
+                atomic::TMP_SUFFIX,
+                transaction::STAGE_SUFFIX,
+                ARCHIVE_SUFFIX,
+                bundle::BUNDLE_SUFFIX,
+                container::CONTAINER_SUFFIX,
+            ];
+            if !meta.is_file() || transient.iter().any(|suffix| name.ends_with(suffix)) {
+                continue;
+            }
+            let relative = path
+                .strip_prefix(dir)?
+                .components()
+                .map(|c| c.as_os_str().to_str().with_context(|| format!("non-UTF-8 file name {:?}", path)))
+                .collect::<Result<Vec<_>>>()?
+                .join("/");
+            files.push((relative, path));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+pub fn pack(manifest: &Manifest, contents: &[Vec<u8>]) -> Result<Vec<u8>> {
+    let json = serde_json::to_vec(manifest)?;
+    let mut body = Vec::with_capacity(4 + json.len() + contents.iter().map(Vec::len).sum::<usize>());
+    body.extend_from_slice(&u32::try_from(json.len()).context("manifest too large")?.to_be_bytes());
+    body.extend_from_slice(&json);
+    for data in contents {
+        body.extend_from_slice(data);
+    }
+    Ok(body)
+}
+
+/// Split an archive body and check every entry's path, size and digest
+pub fn unpack(body: &[u8]) -> Result<(Manifest, Vec<&[u8]>)> {
+    let len_bytes = body.get(..4).context("archive truncated")?;
+    let json_len = u32::from_be_bytes(len_bytes.try_into()?) as usize;
+    let json = body.get(4..4 + json_len).context("archive truncated")?;
+    let manifest: Manifest = serde_json::from_slice(json).context("archive manifest")?;
+    if manifest.format != MANIFEST_FORMAT {
+        bail!("archive manifest format {} — upgrade violet-cipher", manifest.format);
+    }
+
+    let mut offset = 4 + json_len;
+    let mut files = Vec::with_capacity(manifest.files.len());
+    for entry in &manifest.files {
+        if entry.path.is_empty() || !Path::new(&entry.path).components().all(|c| matches!(c, Component::Normal(_))) {
+            bail!("archive entry {:?} points outside the data directory", entry.path);
+        }
+        let end = usize::try_from(entry.size).ok().and_then(|size| offset.checked_add(size));
+        let contents = end.and_then(|end| body.get(offset..end)).context("archive truncated")?;
+        if hex::encode(Sha256::digest(contents)) != entry.sha256 {
+            bail!("archive entry {} fails its SHA-256 check", entry.path);
+        }
+        files.push(contents);
+        offset += contents.len();
+    }
+    if offset != body.len() {
+        bail!("archive has {} bytes past its last entry", body.len() - offset);
+    }
+    Ok((manifest, files))
+}
+
+/// Archives in `dir`, oldest first
+fn list_archives(dir: &Path) -> Result<Vec<PathBuf>> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut archives: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            let name = p.file_name().unwrap_or_default().to_string_lossy();
+            name.starts_with(ARCHIVE_PREFIX) && name.ends_with(ARCHIVE_SUFFIX)
+        })
+        .collect();
+    // Fixed-width timestamps sort chronologically; same-second suffixes sort after
+    archives.sort_by_key(|p| {
+        let name = p.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        (name.len(), name)
+    });
+    Ok(archives)
+}
+
+/// Delete all but the newest `keep` archives; returns how many went
+fn prune(dir: &Path, keep: usize, dry_run: bool) -> Result<usize> {
+    let stale: Vec<PathBuf> = list_archives(dir)?.into_iter().rev().skip(keep).collect();
+    for path in &stale {
+        if dry_run {
+            info!("  🗑️  Would delete {}", path.display());
+        } else {
+            fs::remove_file(path).with_context(|| format!("remove old archive {:?}", path))?;
+        }
+    }
+    Ok(stale.len())
+}
@@ -0,0 +1,77 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Crash-safe file writes — synced sibling temp file renamed over the destination
+//
+// A crash at any point leaves either the old file or the complete new one,
+// never a truncated mix. Every output path in the cipher goes through here.
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tracing::trace;
+
+pub const TMP_SUFFIX: &str = ".vsc-tmp";
+
+/// Replace `dest` with `contents` atomically, keeping its permissions if it exists
+pub fn write_atomic(dest: &Path, contents: &[u8]) -> Result<()> {
+    let tmp = sibling_path(dest, &format!(".{}{}", std::process::id(), TMP_SUFFIX))?;
+    let result = write_synced(&tmp, contents).and_then(|()| {
+        if let Ok(meta) = fs::metadata(dest) {
+            fs::set_permissions(&tmp, meta.permissions()).with_context(|| format!("chmod {:?}", tmp))?;
+        }
+        fs::rename(&tmp, dest).with_context(|| format!("rename {:?} → {:?}", tmp, dest))
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result?;
+    sync_parent_dir(dest)?;
+    trace!(path = ?dest, bytes = contents.len(), "      💾 {:?} written ({} bytes)", dest, contents.len());
+    Ok(())
+}
+
+/// `dest` with `suffix` appended to its file name
+pub fn sibling_path(dest: &Path, suffix: &str) -> Result<PathBuf> {
+    let mut name = dest.file_name().context("output path has no file name")?.to_os_string();
+    name.push(suffix);
+    Ok(dest.with_file_name(name))
+}
+
+/// Create (or truncate) `path`, write `contents` and fsync before returning
+pub fn write_synced(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = fs::File::create(path).with_context(|| format!("create {:?}", path))?;
+    file.write_all(contents).with_context(|| format!("write {:?}", path))?;
+    file.sync_all().with_context(|| format!("fsync {:?}", path))
+}
+
+/// Persist a rename by syncing the containing directory (no-op off Unix)
+pub fn sync_parent_dir(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        let parent = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        fs::File::open(parent)
+            .and_then(|dir| dir.sync_all())
+            .with_context(|| format!("fsync directory {:?}", parent))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Create `path` for key material: mode 0600, fsynced, never replacing an existing file
+pub fn write_secret_new(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("create {:?} (refusing to overwrite an existing file)", path))?;
+    file.write_all(contents).with_context(|| format!("write {:?}", path))?;
+    file.sync_all().with_context(|| format!("fsync {:?}", path))?;
+    trace!(path = ?path, bytes = contents.len(), "      💾 {:?} written, mode 0600 ({} bytes)", path, contents.len());
+    Ok(())
+}
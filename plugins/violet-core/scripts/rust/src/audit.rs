@@ -0,0 +1,232 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Audit log — an append-only, HMAC-chained record of every operation on the soul data
+//
+// Each encrypt, decrypt, verify and rotate run appends one JSON line to
+// .violet-audit.log in the data directory: sequence number, UTC time,
+// command, the file or directory it worked on and how it ended. Every
+// entry's MAC covers the previous entry's MAC, so editing, reordering or
+// deleting a line breaks the chain from there on. .violet-audit.head records
+// the entry count and last MAC under its own MAC, which catches a log cut
+// short. `audit verify` checks both.
+//
+// The MAC key comes from the compiled-in seed, like the trailing HMAC of
+// every .enc: it needs no passphrase, so commands that fail before one is
+// known are logged too, and it stops anyone without the binary from forging
+// entries. Someone who keeps an old copy of both files can still roll them
+// back together; compare the last MAC `audit verify` prints with one kept
+// elsewhere to rule that out.
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::{error, info};
+
+use crate::atomic::write_atomic;
+use crate::backup::utc_timestamp;
+use crate::{derive_embedded_key, logging, resolve_data_dir};
+
+pub const AUDIT_LOG: &str = ".violet-audit.log";
+pub const AUDIT_HEAD: &str = ".violet-audit.head";
+const AUDIT_CONTEXT: &[u8] = b"violet-audit-v1\0";
+const HEAD_CONTEXT: &[u8] = b"violet-audit-head-v1\0";
+
+#[derive(Subcommand)]
+pub enum AuditCommand {
+    /// Check the audit log's MAC chain and that it has not been cut short
+    Verify {
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+}
+
+/// Everything an entry's MAC covers, besides the previous MAC
+#[derive(Serialize, Deserialize)]
+struct Body {
+    seq: u64,
+    /// UTC, `20261017T040912Z`
+    at: String,
+    command: String,
+    file: String,
+    /// `ok`, `error: …` or `issues: N`
+    result: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    #[serde(flatten)]
+    body: Body,
+    mac: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Head {
+    entries: u64,
+    /// MAC of the last entry
+    last: String,
+    mac: String,
+}
+
+/// Entry begun by `begin`, written by the first `finish`
+struct Pending {
+    data_dir: PathBuf,
+    command: &'static str,
+    file: String,
+}
+
+static PENDING: Mutex<Option<Pending>> = Mutex::new(None);
+
+fn mac(parts: &[&[u8]]) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&derive_embedded_key()).expect("HMAC init");
+    for part in parts {
+        mac.update(part);
+    }
+    mac
+}
+
+fn entry_mac(previous: &[u8], body: &Body) -> Result<Vec<u8>> {
+    Ok(mac(&[AUDIT_CONTEXT, previous, &serde_json::to_vec(body)?]).finalize().into_bytes().to_vec())
+}
+
+fn head_mac(entries: u64, last: &str) -> Vec<u8> {
+    mac(&[HEAD_CONTEXT, &entries.to_be_bytes(), last.as_bytes()]).finalize().into_bytes().to_vec()
+}
+
+/// Remember the entry the running command should get; `finish` writes it
+pub fn begin(data_dir: PathBuf, command: &'static str, file: String) {
+    *PENDING.lock().unwrap_or_else(|e| e.into_inner()) = Some(Pending { data_dir, command, file });
+}
+
+/// Write the pending entry with `result`, once; a log that cannot be written only warns
+pub fn finish(result: &str) {
+    let Some(pending) = PENDING.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+    if let Err(e) = append(&pending, result) {
+        logging::note(format_args!("⚠️  Audit log not updated: {:#}", e));
+    }
+}
+
+/// `ok` or the error's first line, as recorded in an entry
+pub fn outcome<T>(result: &Result<T>) -> String {
+    match result {
+        Ok(_) => "ok".to_string(),
+        Err(e) => format!("error: {}", e),
+    }
+}
+
+fn append(pending: &Pending, result: &str) -> Result<()> {
+    // Nowhere to log a command that ran without a data directory
+    if !pending.data_dir.is_dir() {
+        return Ok(());
+    }
+    let log = pending.data_dir.join(AUDIT_LOG);
+    let (seq, previous) = match fs::read_to_string(&log) {
+        Ok(text) => match text.lines().rev().find(|line| !line.trim().is_empty()) {
+            Some(line) => {
+                let last: Entry = serde_json::from_str(line).with_context(|| format!("parse last entry of {:?}", log))?;
+                (last.body.seq + 1, hex::decode(&last.mac).context("last audit MAC is not hex")?)
+            }
+            None => (0, vec![0; 32]),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => (0, vec![0; 32]),
+        Err(e) => return Err(e).with_context(|| format!("read {:?}", log)),
+    };
+    let body = Body {
+        seq,
+        at: utc_timestamp(SystemTime::now()),
+        command: pending.command.to_string(),
+        file: pending.file.clone(),
+        result: result.lines().next().unwrap_or_default().to_string(),
+    };
+    let mac = hex::encode(entry_mac(&previous, &body)?);
+    let mut line = serde_json::to_string(&Entry { body, mac: mac.clone() })?;
+    line.push('\n');
+
+    let mut options = fs::OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&log).with_context(|| format!("open {:?}", log))?;
+    file.write_all(line.as_bytes()).with_context(|| format!("append to {:?}", log))?;
+    file.sync_all().with_context(|| format!("fsync {:?}", log))?;
+
+    let head = Head { entries: seq + 1, mac: hex::encode(head_mac(seq + 1, &mac)), last: mac };
+    write_atomic(&pending.data_dir.join(AUDIT_HEAD), serde_json::to_string_pretty(&head)?.as_bytes())
+}
+
+/// Check the chain and the head; returns how many problems were found
+fn verify(data_dir: &Path) -> Result<u32> {
+    let log = data_dir.join(AUDIT_LOG);
+    if !log.exists() {
+        bail!("no {} in {:?} — nothing has been audited yet", AUDIT_LOG, data_dir);
+    }
+    info!("📜 Checking {:?}...", log);
+    let text = fs::read_to_string(&log).with_context(|| format!("read {:?}", log))?;
+    let mut issues = 0u32;
+    let mut previous = vec![0u8; 32];
+    let mut entries = 0u64;
+    for (number, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let number = number + 1;
+        let entry: Entry = match serde_json::from_str(line) {
+            Ok(entry) => entry,
+            Err(e) => {
+                error!("  ❌ line {}: not an audit entry ({})", number, e);
+                issues += 1;
+                continue;
+            }
+        };
+        let recorded = hex::decode(&entry.mac).unwrap_or_default();
+        if entry.body.seq != entries {
+            error!("  ❌ line {}: entry #{} where #{} belongs — entries removed or reordered", number, entry.body.seq, entries);
+            issues += 1;
+        } else if entry_mac(&previous, &entry.body)? != recorded {
+            error!("  ❌ line {}: entry #{} ({} {}) was altered", number, entry.body.seq, entry.body.command, entry.body.at);
+            issues += 1;
+        }
+        previous = recorded;
+        entries = entry.body.seq + 1;
+    }
+    if !text.is_empty() && !text.ends_with('\n') {
+        error!("  ❌ the last line is incomplete — the log was cut mid-entry");
+        issues += 1;
+    }
+
+    let last = hex::encode(&previous);
+    let head_path = data_dir.join(AUDIT_HEAD);
+    match fs::read_to_string(&head_path).ok().and_then(|json| serde_json::from_str::<Head>(&json).ok()) {
+        None => {
+            error!("  ❌ {} is missing or unreadable — truncation cannot be ruled out", AUDIT_HEAD);
+            issues += 1;
+        }
+        Some(head) if hex::decode(&head.mac).unwrap_or_default() != head_mac(head.entries, &head.last) => {
+            error!("  ❌ {} was altered", AUDIT_HEAD);
+            issues += 1;
+        }
+        Some(head) if head.entries != entries || head.last != last => {
+            error!("  ❌ log ends at entry #{} but {} records {} entries — the log was truncated", entries, AUDIT_HEAD, head.entries);
+            issues += 1;
+        }
+        Some(_) => {}
+    }
+
+    if issues > 0 {
+        info!("📜 {} problem(s) found in the audit log.", issues);
+    } else {
+        info!("📜 All {} entries chain intact. Last MAC: {}", entries, last);
+    }
+    Ok(issues)
+}
+
+/// Run an audit subcommand; returns how many problems `verify` found
+pub fn run(command: AuditCommand) -> Result<u32> {
+    match command {
+        AuditCommand::Verify { data_dir } => verify(&resolve_data_dir(data_dir)),
+    }
+}
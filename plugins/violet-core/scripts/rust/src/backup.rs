@@ -0,0 +1,98 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Timestamped backups of encrypted files before they are overwritten
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use clap::Args;
+
+use crate::atomic;
+
+pub const BACKUP_MARKER: &str = ".bak-";
+
+#[derive(Args)]
+pub struct BackupArgs {
+    /// Copy each existing .enc to <name>.enc.bak-<UTC timestamp> before overwriting it
+    #[arg(long)]
+    backup: bool,
+    /// Backups kept per file; older ones are deleted
+    #[arg(long, default_value_t = 5, requires = "backup")]
+    keep_backups: usize,
+}
+
+impl BackupArgs {
+    /// Retention count when backups are enabled
+    pub fn retention(&self) -> Option<usize> {
+        self.backup.then_some(self.keep_backups.max(1))
+    }
+}
+
+/// Back up `path` if it exists, then prune to the newest `keep` backups
+pub fn backup_file(path: &Path, keep: usize) -> Result<Option<PathBuf>> {
+    let Ok(contents) = fs::read(path) else {
+        return Ok(None);
+    };
+    let stamp = utc_timestamp(SystemTime::now());
+    let mut dest = atomic::sibling_path(path, &format!("{}{}", BACKUP_MARKER, stamp))?;
+    let mut n = 1;
+    while dest.exists() {
+        dest = atomic::sibling_path(path, &format!("{}{}-{}", BACKUP_MARKER, stamp, n))?;
+        n += 1;
+    }
+    atomic::write_atomic(&dest, &contents)?;
+
+    for stale in list_backups(path)?.into_iter().rev().skip(keep) {
+        fs::remove_file(&stale).with_context(|| format!("remove old backup {:?}", stale))?;
+    }
+    Ok(Some(dest))
+}
+
+/// Backups of `path`, oldest first
+pub fn list_backups(path: &Path) -> Result<Vec<PathBuf>> {
+    let name = path.file_name().context("path has no file name")?.to_string_lossy();
+    let prefix = format!("{}{}", name, BACKUP_MARKER);
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("read {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.file_name().is_some_and(|n| n.to_string_lossy().starts_with(&prefix)))
+        .collect();
+    // Fixed-width timestamps sort chronologically; same-second suffixes sort after
+    backups.sort_by_key(|p| {
+        let name = p.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        (name.len(), name)
+    });
+    Ok(backups)
+}
+
+/// `20261017T040912Z` for the given instant
+pub fn utc_timestamp(now: SystemTime) -> String {
+    let secs = now.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
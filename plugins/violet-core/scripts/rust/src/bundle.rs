@@ -0,0 +1,237 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Export bundles — every target .enc in one file under a separate backup passphrase
+//
+// `export-bundle` packs each target's .enc and .git.enc, byte for byte, with
+// an archive manifest (size and SHA-256 per file) and seals the lot as one v5
+// blob under the bundle passphrase. The files inside stay encrypted under the
+// day-to-day key, so an off-site copy needs both passphrases to read anything,
+// and a leaked bundle passphrase exposes nothing on its own. A bundle
+// passphrase that keyring.json lists as a day-to-day generation is refused.
+// `import-bundle` checks every entry before it writes, then restores them
+// all-or-nothing.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use tracing::info;
+
+use crate::archive::{self, Manifest};
+use crate::header::{Compression, ContentType, Header};
+use crate::kdf::KdfArgs;
+use crate::key_source::{resolve_passphrase, Passphrase};
+use crate::keyring::Keyring;
+use crate::transaction::Transaction;
+use crate::{
+    backup, describe_blob, preamble, print_dry_run_banner, read_header, resolve_data_dir, v5_decrypt, v5_encrypt,
+    verify_token, write_atomic, Binding, LOCAL_SALT, TARGET_FILES,
+};
+
+pub const BUNDLE_SUFFIX: &str = ".vbundle";
+/// Bundles are bound to this name instead of their file name, so they can be renamed and moved
+const BUNDLE_NAME: &str = "violet-export-bundle";
+const CONTENT_TYPE: &str = "violet-bundle";
+
+/// The bundle's own passphrase, never the day-to-day key
+#[derive(Args)]
+pub struct BundleKeyArgs {
+    /// Backup passphrase the bundle is sealed with
+    #[arg(long, env = "VIOLET_BUNDLE_KEY", hide_env_values = true)]
+    bundle_key: Option<String>,
+    /// Read the backup passphrase from a file (overrides --bundle-key)
+    #[arg(long)]
+    bundle_key_file: Option<PathBuf>,
+}
+
+impl BundleKeyArgs {
+    fn resolve(self) -> Result<Passphrase> {
+        resolve_passphrase(self.bundle_key, self.bundle_key_file.as_deref(), None)
+            .context("bundle key — pass --bundle-key, --bundle-key-file or set VIOLET_BUNDLE_KEY")
+    }
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    key: BundleKeyArgs,
+    #[command(flatten)]
+    kdf: KdfArgs,
+    #[arg(long)]
+    data_dir: Option<PathBuf>,
+    /// Bundle to write (default: violet-bundle-<UTC timestamp>.vbundle in the current directory)
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// Replace --output if it exists
+    #[arg(long)]
+    force: bool,
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct ImportArgs {
+    #[command(flatten)]
+    key: BundleKeyArgs,
+    #[command(flatten)]
+    kdf: KdfArgs,
+    /// Bundle written by export-bundle
+    #[arg(long)]
+    bundle: PathBuf,
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// File names a bundle may carry: `<target>.enc` and `<target>.git.enc`
+fn bundle_files() -> impl Iterator<Item = String> {
+    TARGET_FILES.iter().flat_map(|name| [format!("{}.enc", name), format!("{}.git.enc", name)])
+}
+
+/// Refuse a bundle passphrase that also opens the data directory day to day
+fn check_independent(bundle_key: &[u8], data_dir: &Path) -> Result<()> {
+    // Without a key-ID salt the keyring lists no generations to compare with
+    let Some(key_id) = verify_token::salted_key_id(bundle_key)? else {
+        return Ok(());
+    };
+    if let Some(entry) = Keyring::load(data_dir)?.find(&key_id) {
+        bail!(
+            "the bundle key is day-to-day key generation {} in {:?} — choose a separate backup passphrase",
+            entry.generation,
+            Keyring::path(data_dir)
+        );
+    }
+    Ok(())
+}
+
+/// Only ciphertext goes into a bundle: v4–v6 files must pass the keyless
+/// integrity check, and legacy v2/v3 ones must at least not read as JSON
+fn check_ciphertext(name: &str, data: &[u8]) -> Result<()> {
+    if preamble::version(data).is_some() {
+        read_header(data).with_context(|| format!("{} fails its integrity check", name))?;
+    } else if serde_json::from_slice::<serde_json::Value>(data).is_ok() {
+        bail!("{} holds plaintext JSON", name);
+    }
+    Ok(())
+}
+
+pub fn export(args: ExportArgs) -> Result<()> {
+    let data_dir = resolve_data_dir(args.data_dir);
+    let bundle_key = args.key.resolve()?;
+    let kdf = args.kdf.params()?;
+    check_independent(&bundle_key, &data_dir)?;
+
+    let stamp = backup::utc_timestamp(SystemTime::now());
+    let dest = args.output.unwrap_or_else(|| PathBuf::from(format!("violet-bundle-{}{}", stamp, BUNDLE_SUFFIX)));
+    if dest.exists() && !args.force {
+        bail!("{:?} already exists — pass --force to replace it", dest);
+    }
+
+    let mut manifest = Manifest::new(stamp);
+    let mut contents = Vec::new();
+    for file_name in bundle_files() {
+        let path = data_dir.join(&file_name);
+        if !path.exists() {
+            continue;
+        }
+        let data = fs::read(&path).with_context(|| format!("read {:?}", path))?;
+        check_ciphertext(&file_name, &data)?;
+        manifest.add(file_name, &data);
+        contents.push(data);
+    }
+    if manifest.files.is_empty() {
+        bail!("no encrypted target files in {:?} — run encrypt-local first", data_dir);
+    }
+
+    print_dry_run_banner(args.dry_run);
+    if args.dry_run {
+        for (entry, data) in manifest.files.iter().zip(&contents) {
+            info!("  📝 Would pack {} ({})", entry.path, describe_blob(Path::new(&entry.path), data));
+        }
+        info!("  📝 Would write {}", dest.display());
+        return Ok(());
+    }
+
+    let body = archive::pack(&manifest, &contents)?;
+    let header = Header::new(ContentType::parse(CONTENT_TYPE)?, Compression::None);
+    let sealed = v5_encrypt(&bundle_key, Binding::new(LOCAL_SALT, BUNDLE_NAME), &body, &header, &kdf)?;
+    write_atomic(&dest, &sealed)?;
+    for entry in &manifest.files {
+        info!("  ✅ {} ({} bytes)", entry.path, entry.size);
+    }
+    info!("📦 Exported {} file(s) → {:?} ({} bytes)", manifest.files.len(), dest, sealed.len());
+    info!("   Keep the bundle key apart from the day-to-day key: restoring needs both.");
+    Ok(())
+}
+
+pub fn import(args: ImportArgs) -> Result<()> {
+    let data_dir = resolve_data_dir(args.data_dir);
+    let bundle_key = args.key.resolve()?;
+    let kdf = args.kdf.params()?;
+
+    let data = fs::read(&args.bundle).with_context(|| format!("read {:?}", args.bundle))?;
+    let (header, body) = v5_decrypt(&bundle_key, Binding::new(LOCAL_SALT, BUNDLE_NAME), &data, &kdf)
+        .with_context(|| format!("open bundle {:?}", args.bundle))?;
+    if header.content_type.as_str() != CONTENT_TYPE {
+        bail!("{:?} is a {} file, not an export bundle", args.bundle, header.content_type);
+    }
+    let (manifest, files) = archive::unpack(&body)?;
+    let known: Vec<String> = bundle_files().collect();
+    for (entry, contents) in manifest.files.iter().zip(&files) {
+        if !known.contains(&entry.path) {
+            bail!("bundle entry {:?} is not a target .enc file", entry.path);
+        }
+        check_ciphertext(&entry.path, contents)?;
+    }
+
+    print_dry_run_banner(args.dry_run);
+    if !args.dry_run {
+        fs::create_dir_all(&data_dir).with_context(|| format!("create {:?}", data_dir))?;
+    }
+    let keyring = Keyring::load(&data_dir)?;
+    let mut txn = Transaction::new();
+    let (mut created, mut replaced, mut unchanged) = (0, 0, 0);
+    for (entry, contents) in manifest.files.iter().zip(files) {
+        let dest = data_dir.join(&entry.path);
+        let verb = match fs::read(&dest) {
+            Ok(old) if old == contents => {
+                unchanged += 1;
+                continue;
+            }
+            Ok(_) => {
+                replaced += 1;
+                "overwrite"
+            }
+            Err(_) => {
+                created += 1;
+                "create"
+            }
+        };
+        let generation = read_header(contents)
+            .ok()
+            .flatten()
+            .and_then(|header| header.key_id)
+            .and_then(|key_id| keyring.find(&key_id).map(|entry| format!(", key generation {}", entry.generation)))
+            .unwrap_or_default();
+        if args.dry_run {
+            info!("  📝 Would {} {}{}", verb, entry.path, generation);
+            continue;
+        }
+        txn.stage(&dest, contents)?;
+        info!("  ✅ {} staged{}", entry.path, generation);
+    }
+    if !args.dry_run {
+        txn.commit()?;
+    }
+    info!(
+        "📦 {} bundle of {} into {:?}: {} created, {} overwritten, {} unchanged",
+        if args.dry_run { "Would import" } else { "Imported" },
+        manifest.created,
+        data_dir,
+        created,
+        replaced,
+        unchanged
+    );
+    Ok(())
+}
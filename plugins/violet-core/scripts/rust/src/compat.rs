@@ -0,0 +1,172 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Compatibility check — prove legacy Node.js (v2/v3) artifacts open and survive re-encryption
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use argon2::Params as KdfParams;
+use serde::Serialize;
+use tracing::{error, info};
+
+use crate::{
+    decrypt_payload, logical_name, preamble, v2_decrypt, v3_decrypt, v4_encrypt, Binding, GIT_SALT, LOCAL_SALT,
+};
+
+/// Outcome for one legacy .enc file
+#[derive(Serialize)]
+struct Entry {
+    file: String,
+    /// "v2" or "v3"; absent when neither opens it
+    format: Option<&'static str>,
+    salt: &'static str,
+    plaintext_bytes: usize,
+    valid_json: bool,
+    round_trip: bool,
+    error: Option<String>,
+}
+
+impl Entry {
+    fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+#[derive(Serialize)]
+struct Report {
+    directory: String,
+    checked: usize,
+    passed: usize,
+    failed: usize,
+    /// Files already in a current format (v4–v6), not part of the check
+    skipped: Vec<String>,
+    entries: Vec<Entry>,
+}
+
+/// Check every legacy .enc under `dir`; returns the number of failures
+///
+/// `*.git.enc` files are opened with the git salt, everything else with the
+/// local salt. A file passes when the same decryption `re-encrypt` uses opens
+/// it as JSON and a v4 re-encryption decrypts back to identical bytes.
+pub fn run(key: &[u8], kdf: &KdfParams, dir: &Path, report_path: Option<&Path>) -> Result<u32> {
+    info!("🧬 Checking legacy artifacts in {:?}...", dir);
+    let mut files = Vec::new();
+    collect_enc_files(dir, &mut files)?;
+    files.sort();
+
+    let mut report = Report {
+        directory: dir.display().to_string(),
+        checked: 0,
+        passed: 0,
+        failed: 0,
+        skipped: Vec::new(),
+        entries: Vec::new(),
+    };
+    for path in files {
+        let name = path.strip_prefix(dir).unwrap_or(&path).display().to_string();
+        let data = fs::read(&path).with_context(|| format!("read {:?}", path))?;
+        if let Some(version) = preamble::version(&data) {
+            info!("  ⏭️  {} — already v{}", name, version);
+            report.skipped.push(name);
+            continue;
+        }
+        let entry = check_file(key, kdf, name, &data);
+        match (&entry.error, entry.format) {
+            (None, Some(format)) => info!(
+                "  ✅ {} — {}, {} bytes JSON, v4 round trip OK",
+                entry.file, format, entry.plaintext_bytes
+            ),
+            (Some(e), Some(format)) => error!("  ❌ {} — {}: {}", entry.file, format, e),
+            (Some(e), None) => error!("  ❌ {} — {}", entry.file, e),
+            (None, None) => unreachable!("entries without a format always carry an error"),
+        }
+        report.checked += 1;
+        if entry.passed() {
+            report.passed += 1;
+        } else {
+            report.failed += 1;
+        }
+        report.entries.push(entry);
+    }
+
+    if let Some(path) = report_path {
+        fs::write(path, serde_json::to_string_pretty(&report)?).with_context(|| format!("write {:?}", path))?;
+        info!("  📝 Report written to {:?}", path);
+    }
+    if report.checked == 0 {
+        info!("🧬 No legacy (v2/v3) files found.");
+    } else if report.failed == 0 {
+        info!("🧬 All {} legacy file(s) compatible.", report.checked);
+    } else {
+        info!("🧬 {} of {} legacy file(s) failed. Review above.", report.failed, report.checked);
+    }
+    Ok(report.failed as u32)
+}
+
+fn check_file(key: &[u8], kdf: &KdfParams, file: String, data: &[u8]) -> Entry {
+    let salt = if file.ends_with(".git.enc") { GIT_SALT } else { LOCAL_SALT };
+    let name = logical_name(Path::new(&file));
+    let binding = Binding::new(salt, &name);
+    let mut entry = Entry {
+        file,
+        format: None,
+        salt: if salt == GIT_SALT { "git" } else { "local" },
+        plaintext_bytes: 0,
+        valid_json: false,
+        round_trip: false,
+        error: None,
+    };
+
+    // Identify the format directly; the production path below only says whether it opened
+    let utf8 = |plain: Vec<u8>| String::from_utf8(plain).ok();
+    let (format, plain) = match v3_decrypt(key, salt, data).ok().and_then(utf8) {
+        Some(plain) => ("v3", plain),
+        None => match v2_decrypt(key, data).ok().and_then(utf8) {
+            Some(plain) => ("v2", plain),
+            None => {
+                entry.error = Some("neither v3 nor v2 decryption yields text — wrong key or not a legacy file".into());
+                return entry;
+            }
+        },
+    };
+    entry.format = Some(format);
+    entry.plaintext_bytes = plain.len();
+
+    match decrypt_payload(key, binding, data, kdf) {
+        Ok((_, production)) if production == plain.as_bytes() => {}
+        Ok(_) => {
+            entry.error = Some(format!("re-encrypt would decode it differently than {}", format));
+            return entry;
+        }
+        Err(e) => {
+            entry.error = Some(format!("re-encrypt cannot open it: {}", e));
+            return entry;
+        }
+    }
+
+    entry.valid_json = serde_json::from_str::<serde_json::Value>(&plain).is_ok();
+    if !entry.valid_json {
+        entry.error = Some("decrypts, but the plaintext is not valid JSON".into());
+        return entry;
+    }
+
+    let round_trip = v4_encrypt(key, binding, plain.as_bytes(), kdf)
+        .and_then(|upgraded| decrypt_payload(key, binding, &upgraded, kdf));
+    match round_trip {
+        Ok((_, back)) if back == plain.as_bytes() => entry.round_trip = true,
+        Ok(_) => entry.error = Some("v4 re-encryption decrypts to different bytes".into()),
+        Err(e) => entry.error = Some(format!("v4 re-encryption failed: {}", e)),
+    }
+    entry
+}
+
+fn collect_enc_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("read directory {:?}", dir))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_enc_files(&path, out)?;
+        } else if path.extension().is_some_and(|ext| ext == "enc") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
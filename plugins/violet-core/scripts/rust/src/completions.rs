@@ -0,0 +1,49 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Shell completions — `completions <shell>` prints a bash/zsh/fish/powershell/elvish script
+//
+// The script is generated from the same clap definition the parser uses, so
+// new subcommands and flags complete as soon as they exist. A few hints are
+// added on a copy made for generation only, where parsing is more lenient
+// than the suggestions: directory options complete directories, target-file
+// options (`--only`, `--file NAME`) the three target names, and
+// `--salt-label` the two built-in domains while still accepting any label.
+//
+//   violet-cipher completions bash > ~/.local/share/bash-completion/completions/violet-cipher
+//   violet-cipher completions zsh > "${fpath[1]}/_violet-cipher"
+//   violet-cipher completions fish > ~/.config/fish/completions/violet-cipher.fish
+use std::io;
+
+use anyhow::Result;
+use clap::builder::PossibleValuesParser;
+use clap::{Args, Command, CommandFactory, ValueHint};
+use clap_complete::{generate, Shell};
+
+use crate::{logging, Cli, TARGET_FILES};
+
+#[derive(Args)]
+pub struct CompletionArgs {
+    /// Shell to write the completion script for
+    shell: Shell,
+}
+
+pub fn run(args: CompletionArgs) -> Result<()> {
+    logging::claim_stdout();
+    let mut command = hinted(Cli::command());
+    generate(args.shell, &mut command, "violet-cipher", &mut io::stdout());
+    Ok(())
+}
+
+/// `command` and its subcommands with the completion hints the parser does not need
+fn hinted(command: Command) -> Command {
+    command
+        .mut_args(|arg| {
+            let target_name = arg.get_value_names().is_some_and(|names| names == ["NAME"]);
+            match arg.get_id().as_str() {
+                "data_dir" | "output_dir" | "dir" => arg.value_hint(ValueHint::DirPath),
+                "salt_label" => arg.value_parser(PossibleValuesParser::new(["local", "git"])),
+                _ if target_name => arg.value_parser(PossibleValuesParser::new(TARGET_FILES.iter().copied())),
+                _ => arg,
+            }
+        })
+        .mut_subcommands(hinted)
+}
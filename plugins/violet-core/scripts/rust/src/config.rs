@@ -0,0 +1,126 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Configuration sources — `.violet.env` / `.env` files and `config show`
+//
+// VIOLET_SOUL_KEY and VIOLET_DATA_DIR may also come from an env file in the
+// current directory: `.violet.env`, else `.env`. Lines are `NAME=value`
+// (`export ` and matching quotes allowed); comments and any other names
+// are ignored, and the file is read only when a value is needed, so
+// the passphrase is never copied into the process environment. Each value
+// is taken from the first source that has it:
+//
+//   1. command-line flag (--key, --key-file, --key-fd, --identity; --data-dir)
+//   2. environment variable
+//   3. env file
+//   4. for the passphrase, the machine keychain: the TPM-sealed key
+//      (--sealed-key / VIOLET_SEALED_KEY), then the unlock agent; for the
+//      data directory, `data` next to the binary
+//
+// `config show` reports where each value came from without printing the
+// passphrase, and lists the profiles (profiles.json) of the data directory.
+// `env-audit` still warns about env files git does not ignore.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use zeroize::Zeroizing;
+
+use crate::key_source::KeyArgs;
+use crate::{logging, profile, resolve_data_dir, salt};
+
+pub const SOUL_KEY_VAR: &str = "VIOLET_SOUL_KEY";
+pub const DATA_DIR_VAR: &str = "VIOLET_DATA_DIR";
+/// Env files looked for in the current directory, in order
+const ENV_FILES: [&str; 2] = [".violet.env", ".env"];
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the passphrase source and data directory in effect, and where each came from
+    Show {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+}
+
+/// The env file in effect, if the current directory has one
+pub fn env_file() -> Option<PathBuf> {
+    ENV_FILES.iter().map(PathBuf::from).find(|path| path.is_file())
+}
+
+/// `name` as set in the env file, with the file's path
+pub fn env_file_value(name: &str) -> Result<Option<(Zeroizing<String>, PathBuf)>> {
+    let Some(path) = env_file() else {
+        return Ok(None);
+    };
+    let text = Zeroizing::new(fs::read_to_string(&path).with_context(|| format!("read env file {:?}", path))?);
+    for line in text.lines() {
+        let line = line.trim();
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        // Comments, blank lines and whatever else the file holds for other tools
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() == name {
+            return Ok(Some((Zeroizing::new(unquote(value.trim()).to_string()), path)));
+        }
+    }
+    Ok(None)
+}
+
+/// `value` without one pair of matching quotes, or without a trailing ` # comment` when unquoted
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|v| v.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    value.split_once(" #").map_or(value, |(value, _)| value.trim_end())
+}
+
+/// Data directory from VIOLET_DATA_DIR, else the env file; `resolve_data_dir` falls back to these
+pub fn data_dir_setting() -> Option<(PathBuf, String)> {
+    if let Some(dir) = std::env::var_os(DATA_DIR_VAR).filter(|dir| !dir.is_empty()) {
+        return Some((PathBuf::from(dir), format!("environment ({})", DATA_DIR_VAR)));
+    }
+    match env_file_value(DATA_DIR_VAR) {
+        Ok(Some((dir, path))) => Some((PathBuf::from(dir.as_str()), format!("env file {}", path.display()))),
+        _ => None,
+    }
+}
+
+pub fn run(command: ConfigCommand) -> Result<()> {
+    match command {
+        ConfigCommand::Show { key, data_dir } => {
+            let dir_source = match (&data_dir, data_dir_setting()) {
+                (Some(_), _) => "flag (--data-dir)".to_string(),
+                (None, Some((_, source))) => source,
+                (None, None) => "default (next to the binary)".to_string(),
+            };
+            let dir = resolve_data_dir(data_dir);
+            logging::out(format_args!("⚙️  Precedence: flag > environment > env file > sealed key / agent / default"));
+            match env_file() {
+                Some(path) => logging::out(format_args!("  📄 Env file: {}", absolute(&path).display())),
+                None => logging::out(format_args!("  📄 Env file: none ({} or {})", ENV_FILES[0], ENV_FILES[1])),
+            }
+            logging::out(format_args!("  🔑 Passphrase: {}", key.source()?));
+            logging::out(format_args!("  📁 Data directory: {} — {}", absolute(&dir).display(), dir_source));
+            match profile::all(&dir) {
+                Ok(profiles) => {
+                    for p in profiles {
+                        let (name, suffix, salt) = (&p.name, &p.suffix, salt::label_of(&p.salt));
+                        let count = p.targets.len();
+                        logging::out(format_args!("  🗂️  Profile {name}: *{suffix}, salt {salt}, {count} target(s)"));
+                    }
+                }
+                Err(e) => logging::out(format_args!("  🗂️  Profiles: {:#}", e)),
+            }
+            Ok(())
+        }
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,204 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Data containers — the whole data directory as the one opaque blob git tracks
+//
+// `pack` seals every source file in the data directory into violet-data.vpack
+// (default: inside the data directory), using the archive layout of
+// archive.rs: an index (manifest of paths, sizes and SHA-256s) followed by the
+// file contents, compressed and encrypted together as one v5 blob, so not even
+// file names are visible. Per-file ciphertext (.enc, .git.enc, their backups,
+// .vtok and .sig sidecars) of a packed source is left out: the container
+// replaces it. Ignore everything else in the data directory and git only ever
+// sees the container. A pack whose index matches the existing container leaves
+// it untouched, so unchanged data never shows up as a diff.
+// `unpack` checks every entry before it writes, then restores them
+// all-or-nothing.
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{bail, Context, Result};
+use argon2::Params as KdfParams;
+use clap::Args;
+use tracing::{info, warn};
+
+use crate::archive::{self, Manifest};
+use crate::header::{Compression, ContentType, Header};
+use crate::kdf::KdfArgs;
+use crate::key_source::KeyArgs;
+use crate::transaction::Transaction;
+use crate::{
+    backup, print_dry_run_banner, resolve_data_dir, v5_decrypt, v5_encrypt, verify_token, write_atomic, Binding,
+    LOCAL_SALT,
+};
+
+pub const CONTAINER_SUFFIX: &str = ".vpack";
+const CONTAINER_FILE: &str = "violet-data.vpack";
+/// Containers are bound to this name instead of their file name, so they can be renamed and moved
+const CONTAINER_NAME: &str = "violet-data-container";
+const CONTENT_TYPE: &str = "violet-container";
+
+#[derive(Args)]
+pub struct PackArgs {
+    #[command(flatten)]
+    key: KeyArgs,
+    #[command(flatten)]
+    kdf: KdfArgs,
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+    /// Container to write (default: violet-data.vpack in the data directory)
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// Replace a container the key cannot open
+    #[arg(long)]
+    force: bool,
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct UnpackArgs {
+    #[command(flatten)]
+    key: KeyArgs,
+    #[command(flatten)]
+    kdf: KdfArgs,
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+    /// Container to read (default: violet-data.vpack in the data directory)
+    #[arg(long)]
+    container: Option<PathBuf>,
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// The packed file `name` was derived from, when it is per-file ciphertext or a sidecar
+fn source_of(name: &str) -> Option<&str> {
+    let name = name.split_once(backup::BACKUP_MARKER).map_or(name, |(original, _)| original);
+    let name = name
+        .strip_suffix(verify_token::TOKEN_SUFFIX)
+        .or_else(|| name.strip_suffix(".sig"))
+        .unwrap_or(name);
+    let name = name.strip_suffix(".enc")?;
+    Some(name.strip_suffix(".git").unwrap_or(name))
+}
+
+/// Source files to pack: everything except ciphertext and sidecars of another packed file
+fn collect_sources(data_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let files = archive::collect_files(data_dir)?;
+    let names: BTreeSet<&str> = files.iter().map(|(relative, _)| relative.as_str()).collect();
+    let derived = |relative: &str| source_of(relative).is_some_and(|source| names.contains(source));
+    Ok(files.iter().filter(|(relative, _)| !derived(relative)).cloned().collect())
+}
+
+/// Index of a container: each path with its digest
+fn index(manifest: &Manifest) -> Vec<(&str, &str)> {
+    manifest.files.iter().map(|e| (e.path.as_str(), e.sha256.as_str())).collect()
+}
+
+fn open(passphrase: &[u8], kdf: &KdfParams, path: &Path) -> Result<Vec<u8>> {
+    let data = fs::read(path).with_context(|| format!("read {:?}", path))?;
+    let (header, body) = v5_decrypt(passphrase, Binding::new(LOCAL_SALT, CONTAINER_NAME), &data, kdf)
+        .with_context(|| format!("open container {:?}", path))?;
+    if header.content_type.as_str() != CONTENT_TYPE {
+        bail!("{:?} is a {} file, not a data container", path, header.content_type);
+    }
+    Ok(body)
+}
+
+pub fn pack(args: PackArgs) -> Result<()> {
+    let passphrase = args.key.resolve()?;
+    let kdf = args.kdf.params()?;
+    let data_dir = resolve_data_dir(args.data_dir);
+    let dest = args.output.unwrap_or_else(|| data_dir.join(CONTAINER_FILE));
+
+    let mut manifest = Manifest::new(backup::utc_timestamp(SystemTime::now()));
+    let mut contents = Vec::new();
+    for (relative, path) in collect_sources(&data_dir)? {
+        let data = fs::read(&path).with_context(|| format!("read {:?}", path))?;
+        manifest.add(relative, &data);
+        contents.push(data);
+    }
+    if manifest.files.is_empty() {
+        bail!("nothing to pack in {:?}", data_dir);
+    }
+
+    if dest.exists() {
+        match open(&passphrase, &kdf, &dest).and_then(|body| Ok(archive::unpack(&body)?.0)) {
+            Ok(existing) if index(&existing) == index(&manifest) => {
+                info!("📦 {:?} already holds these {} file(s) — left untouched", dest, manifest.files.len());
+                return Ok(());
+            }
+            Ok(_) => {}
+            Err(e) if args.force => warn!("  ⚠️  Replacing {:?}: {:#}", dest, e),
+            Err(e) => return Err(e.context("pass --force to replace it")),
+        }
+    }
+
+    print_dry_run_banner(args.dry_run);
+    if args.dry_run {
+        for entry in &manifest.files {
+            info!("  📝 Would pack {} ({} bytes)", entry.path, entry.size);
+        }
+        info!("  📝 Would write {}", dest.display());
+        return Ok(());
+    }
+
+    let body = archive::pack(&manifest, &contents)?;
+    let header = Header::new(ContentType::parse(CONTENT_TYPE)?, Compression::Zstd);
+    let sealed = v5_encrypt(&passphrase, Binding::new(LOCAL_SALT, CONTAINER_NAME), &body, &header, &kdf)?;
+    write_atomic(&dest, &sealed)?;
+    info!("📦 Packed {} file(s) → {:?} ({} bytes)", manifest.files.len(), dest, sealed.len());
+    Ok(())
+}
+
+pub fn unpack(args: UnpackArgs) -> Result<()> {
+    let passphrase = args.key.resolve()?;
+    let kdf = args.kdf.params()?;
+    let data_dir = resolve_data_dir(args.data_dir);
+    let container = args.container.unwrap_or_else(|| data_dir.join(CONTAINER_FILE));
+    let body = open(&passphrase, &kdf, &container)?;
+    let (manifest, files) = archive::unpack(&body)?;
+
+    print_dry_run_banner(args.dry_run);
+    let mut txn = Transaction::new();
+    let (mut created, mut replaced, mut unchanged) = (0, 0, 0);
+    for (entry, contents) in manifest.files.iter().zip(files) {
+        let dest = data_dir.join(&entry.path);
+        let verb = match fs::read(&dest) {
+            Ok(old) if old == contents => {
+                unchanged += 1;
+                continue;
+            }
+            Ok(_) => {
+                replaced += 1;
+                "overwrite"
+            }
+            Err(_) => {
+                created += 1;
+                "create"
+            }
+        };
+        if args.dry_run {
+            info!("  📝 Would {} {}", verb, entry.path);
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("create {:?}", parent))?;
+        }
+        txn.stage(&dest, contents)?;
+    }
+    if !args.dry_run {
+        txn.commit()?;
+    }
+    info!(
+        "📦 {} {:?} (packed {}) into {:?}: {} created, {} overwritten, {} unchanged",
+        if args.dry_run { "Would unpack" } else { "Unpacked" },
+        container,
+        manifest.created,
+        data_dir,
+        created,
+        replaced,
+        unchanged
+    );
+    Ok(())
+}
@@ -0,0 +1,142 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Detached MACs — `<name>.enc.mac` sidecars checkable without the container format
+//
+// `encrypt-local --detached-mac` (and `watch`) write, next to each .enc, an
+// HMAC-SHA256 of the whole file under the key in `--mac-key-file` (or
+// VIOLET_MAC_KEY_FILE), in `sha256sum` layout:
+//
+//   <64 hex digits>  rules-index.json.enc
+//
+// The key is the file's bytes as they are (one trailing newline stripped),
+// so any HMAC tool can check a sidecar without parsing the container:
+//
+//   openssl dgst -sha256 -mac HMAC -macopt hexkey:$(xxd -p -c 256 mac.key) rules-index.json.enc
+//
+// The MAC key shares nothing with the passphrase, so whoever holds it can
+// vouch for the ciphertexts without being able to open them. `verify`, with
+// or without `--no-key`, checks every sidecar when a MAC key is configured;
+// `rotate-key` and `re-encrypt` refresh the sidecars of files they rewrite.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context, Result};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tracing::{error, info, warn};
+use zeroize::Zeroizing;
+
+use crate::sarif::{self, Rule};
+use crate::transaction::Transaction;
+use crate::write_atomic;
+
+pub const MAC_SUFFIX: &str = ".mac";
+/// Shortest MAC key file accepted, as for a pepper
+const MIN_KEY_LEN: usize = 32;
+
+pub struct MacKey(Zeroizing<Vec<u8>>);
+
+static CONFIGURED: OnceLock<MacKey> = OnceLock::new();
+static WRITES: AtomicBool = AtomicBool::new(false);
+
+impl MacKey {
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut bytes = Zeroizing::new(fs::read(path).with_context(|| format!("read MAC key file {:?}", path))?);
+        if bytes.ends_with(b"\n") {
+            bytes.pop();
+            if bytes.ends_with(b"\r") {
+                bytes.pop();
+            }
+        }
+        if bytes.len() < MIN_KEY_LEN {
+            bail!("MAC key file {:?} holds {} bytes; at least {} are required", path, bytes.len(), MIN_KEY_LEN);
+        }
+        Ok(Self(bytes))
+    }
+
+    fn mac(&self, data: &[u8]) -> Hmac<Sha256> {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.0).expect("HMAC init");
+        mac.update(data);
+        mac
+    }
+}
+
+/// Use `key` for the rest of the run
+pub fn configure(key: MacKey) -> Result<()> {
+    if CONFIGURED.set(key).is_err() {
+        bail!("MAC key already configured");
+    }
+    Ok(())
+}
+
+/// Write a sidecar next to every .enc this run writes (`--detached-mac`)
+pub fn enable_writes() -> Result<()> {
+    if CONFIGURED.get().is_none() {
+        bail!("--detached-mac needs a key: pass --mac-key-file or set VIOLET_MAC_KEY_FILE");
+    }
+    WRITES.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn writes_enabled() -> bool {
+    WRITES.load(Ordering::Relaxed)
+}
+
+pub fn mac_path(enc_path: &Path) -> PathBuf {
+    let mut name = enc_path.as_os_str().to_os_string();
+    name.push(MAC_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Sidecar contents for `data`, written as `enc_path`
+fn sidecar(enc_path: &Path, data: &[u8]) -> Result<String> {
+    let key = CONFIGURED.get().context("no MAC key: pass --mac-key-file or set VIOLET_MAC_KEY_FILE")?;
+    let name = enc_path.file_name().unwrap_or_default().to_string_lossy();
+    Ok(format!("{}  {}\n", hex::encode(key.mac(data).finalize().into_bytes()), name))
+}
+
+/// Write the sidecar of a freshly written .enc, when `--detached-mac` is on
+pub fn write(enc_path: &Path, data: &[u8]) -> Result<()> {
+    if !writes_enabled() {
+        return Ok(());
+    }
+    write_atomic(&mac_path(enc_path), sidecar(enc_path, data)?.as_bytes()).context("write .mac")
+}
+
+/// Stage a fresh sidecar for `data` if `enc_path` has one, which would otherwise go stale
+pub fn refresh(txn: &mut Transaction, enc_path: &Path, data: &[u8]) -> Result<()> {
+    let path = mac_path(enc_path);
+    if !path.exists() {
+        return Ok(());
+    }
+    let contents = sidecar(enc_path, data).with_context(|| format!("refresh {:?}", path))?;
+    txn.stage(&path, contents.as_bytes())
+}
+
+/// `verify`: check the sidecar of `enc_path`; returns the number of issues
+pub fn report(enc_path: &Path, data: &[u8]) -> Result<u32> {
+    let path = mac_path(enc_path);
+    let Some(key) = CONFIGURED.get() else {
+        if path.exists() {
+            info!("      ℹ️  Detached MAC present (pass --mac-key-file to check it)");
+        }
+        return Ok(0);
+    };
+    if !path.exists() {
+        warn!("      ⚠️  No detached MAC (encrypt with --detached-mac)");
+        return Ok(0);
+    }
+    let contents = fs::read_to_string(&path).with_context(|| format!("read {:?}", path))?;
+    let expected = contents.split_whitespace().next().and_then(|digest| hex::decode(digest).ok());
+    if expected.is_some_and(|expected| key.mac(data).verify_slice(&expected).is_ok()) {
+        info!("      ✅ Detached MAC matches");
+        Ok(0)
+    } else {
+        error!("      🚨 Detached MAC does not match — modified, or written under another MAC key");
+        let name = enc_path.file_name().unwrap_or_default().to_string_lossy();
+        let message = format_args!("{} does not match its detached MAC (modified, or another MAC key)", name);
+        sarif::record(Rule::MacMismatch, enc_path, message);
+        Ok(1)
+    }
+}
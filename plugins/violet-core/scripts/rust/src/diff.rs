@@ -0,0 +1,117 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Structural diff — what encrypt-local would change, decrypted in memory only
+//
+// Each target's .enc is decrypted in memory and compared with the plaintext
+// JSON beside it. Changes are listed by JSON pointer: objects key by key,
+// arrays index by index (items past the shorter array are added or removed).
+// The decrypted text is zeroized once compared.
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use argon2::Params as KdfParams;
+use serde_json::Value;
+use tracing::info;
+use zeroize::Zeroizing;
+
+use crate::{auto_decrypt_text, logging, scrub_json, Binding, LOCAL_SALT, TARGET_FILES};
+
+/// Longest rendering of a value before it is cut short
+const MAX_VALUE_CHARS: usize = 80;
+
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    Added(String, String),
+    Removed(String, String),
+    Changed(String, String, String),
+}
+
+fn render(value: &Value) -> String {
+    let text = value.to_string();
+    match text.char_indices().nth(MAX_VALUE_CHARS) {
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text,
+    }
+}
+
+fn pointer_token(pointer: &str, token: &str) -> String {
+    format!("{}/{}", pointer, token.replace('~', "~0").replace('/', "~1"))
+}
+
+/// Changes that turn `old` into `new`, by JSON pointer
+pub fn diff(old: &Value, new: &Value, pointer: &str, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            for (key, old_value) in old_map {
+                let at = pointer_token(pointer, key);
+                match new_map.get(key) {
+                    Some(new_value) => diff(old_value, new_value, &at, changes),
+                    None => changes.push(Change::Removed(at, render(old_value))),
+                }
+            }
+            for (key, new_value) in new_map.iter().filter(|(key, _)| !old_map.contains_key(*key)) {
+                changes.push(Change::Added(pointer_token(pointer, key), render(new_value)));
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for (index, old_value) in old_items.iter().enumerate() {
+                let at = pointer_token(pointer, &index.to_string());
+                match new_items.get(index) {
+                    Some(new_value) => diff(old_value, new_value, &at, changes),
+                    None => changes.push(Change::Removed(at, render(old_value))),
+                }
+            }
+            for (index, new_value) in new_items.iter().enumerate().skip(old_items.len()) {
+                changes.push(Change::Added(pointer_token(pointer, &index.to_string()), render(new_value)));
+            }
+        }
+        _ if old != new => changes.push(Change::Changed(pointer.to_string(), render(old), render(new))),
+        _ => {}
+    }
+}
+
+fn parse(text: &str, what: &str) -> Result<Value> {
+    serde_json::from_str(text).with_context(|| format!("{} is not JSON", what))
+}
+
+/// Print each target's changes from its .enc to its plaintext; returns how many files differ
+pub fn run(key: &[u8], kdf: &KdfParams, data_dir: &Path) -> Result<u32> {
+    info!("🔍 Comparing each .enc with its plaintext (decrypted in memory)...");
+    let mut differing = 0u32;
+    for &name in TARGET_FILES {
+        let enc_path = data_dir.join(format!("{}.enc", name));
+        let (Ok(data), Ok(plaintext)) = (fs::read(&enc_path), fs::read_to_string(data_dir.join(name))) else {
+            info!("  ⏭️  Skip: {} needs both {} and {}.enc", name, name, name);
+            continue;
+        };
+        let plaintext = Zeroizing::new(plaintext);
+        let decrypted = auto_decrypt_text(key, Binding::new(LOCAL_SALT, name), &data, kdf)
+            .with_context(|| format!("decrypt {}.enc", name))?;
+        let mut old = parse(&decrypted, &format!("decrypted {}.enc", name))?;
+        let mut new = parse(&plaintext, name)?;
+
+        let mut changes = Vec::new();
+        diff(&old, &new, "", &mut changes);
+        scrub_json(&mut old);
+        scrub_json(&mut new);
+        if changes.is_empty() {
+            info!("  ✅ {} — same as {}.enc", name, name);
+            continue;
+        }
+        differing += 1;
+        logging::out(format_args!("  📝 {} — {} change(s) since {}.enc:", name, changes.len(), name));
+        for change in &changes {
+            match change {
+                Change::Added(at, value) => logging::out(format_args!("      + {} {}", at, value)),
+                Change::Removed(at, value) => logging::out(format_args!("      - {} {}", at, value)),
+                Change::Changed(at, old, new) => logging::out(format_args!("      ~ {} {} → {}", at, old, new)),
+            }
+        }
+    }
+    if differing > 0 {
+        info!("🔍 {} file(s) differ — encrypt-local would write these changes.", differing);
+    } else {
+        info!("🔍 No differences.");
+    }
+    Ok(differing)
+}
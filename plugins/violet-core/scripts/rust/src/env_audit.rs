@@ -0,0 +1,297 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Environment audit — where the passphrase can leak outside the .enc files
+//
+// `verify` looks at the data directory; this looks at everything around it:
+// shell history, other processes' environment and argv, `.env` files in the
+// repository, and whether a crash could write the key to a core dump.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::Result;
+use tracing::{error, info, warn};
+
+const KEY_ENV: &str = "VIOLET_SOUL_KEY";
+/// Shorter passphrases match too much unrelated text to be worth searching for
+const MIN_SEARCHABLE_LEN: usize = 4;
+const SKIP_DIRS: &[&str] = &[".git", "target", "node_modules"];
+
+struct Finding {
+    location: String,
+    problem: String,
+    fix: String,
+}
+
+#[derive(Default)]
+struct Audit {
+    findings: Vec<Finding>,
+}
+
+impl Audit {
+    fn report(&mut self, location: impl Into<String>, problem: impl Into<String>, fix: impl Into<String>) {
+        let finding = Finding { location: location.into(), problem: problem.into(), fix: fix.into() };
+        error!("  ❌ {} — {}", finding.location, finding.problem);
+        error!("     ↳ {}", finding.fix);
+        self.findings.push(finding);
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// 1-based numbers of the lines that contain `needle`
+fn matching_lines(haystack: &[u8], needle: &[u8]) -> Vec<usize> {
+    haystack
+        .split(|&b| b == b'\n')
+        .enumerate()
+        .filter(|(_, line)| contains(line, needle))
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+fn line_list(lines: &[usize]) -> String {
+    let shown: Vec<String> = lines.iter().take(5).map(usize::to_string).collect();
+    let more = if lines.len() > 5 { format!(" (+{} more)", lines.len() - 5) } else { String::new() };
+    format!("line {}{}", shown.join(", "), more)
+}
+
+/// Run every check; returns the number of findings
+pub fn run(key: &[u8], repo: Option<&Path>) -> Result<u32> {
+    info!("🧹 Auditing the environment for passphrase leaks...");
+    let searchable = key.len() >= MIN_SEARCHABLE_LEN;
+    if !searchable {
+        warn!(
+            "  ⚠️  Passphrase is shorter than {} bytes — skipping content searches (too many false matches)",
+            MIN_SEARCHABLE_LEN
+        );
+    }
+    let mut audit = Audit::default();
+
+    info!("📜 Shell history");
+    if searchable {
+        check_history(key, &mut audit);
+    }
+    info!("⚙️  Processes");
+    check_processes(key, searchable, &mut audit);
+    info!("📄 .env files");
+    let repo = repo.map(Path::to_path_buf).or_else(repo_root).unwrap_or_else(|| PathBuf::from("."));
+    check_env_files(key, searchable, &repo, &mut audit);
+    info!("💥 Core dumps");
+    check_core_dumps(&mut audit);
+
+    if audit.findings.is_empty() {
+        info!("🧹 No environment leaks found.");
+    } else {
+        info!("🧹 {} finding(s). Apply the fixes above, then rotate the key if it was exposed.", audit.findings.len());
+    }
+    Ok(audit.findings.len() as u32)
+}
+
+fn history_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Some(histfile) = std::env::var_os("HISTFILE") {
+        files.push(PathBuf::from(histfile));
+    }
+    if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+        for name in [
+            ".bash_history",
+            ".zsh_history",
+            ".zhistory",
+            ".sh_history",
+            ".local/share/fish/fish_history",
+            ".python_history",
+            ".node_repl_history",
+            ".psql_history",
+        ] {
+            files.push(home.join(name));
+        }
+    }
+    files.sort();
+    files.dedup();
+    files
+}
+
+fn check_history(key: &[u8], audit: &mut Audit) {
+    let mut checked = 0;
+    for path in history_files() {
+        let Ok(data) = fs::read(&path) else { continue };
+        checked += 1;
+        let lines = matching_lines(&data, key);
+        if !lines.is_empty() {
+            audit.report(
+                format!("{} ({})", path.display(), line_list(&lines)),
+                "passphrase recorded in shell history",
+                "delete those lines, set `HISTCONTROL=ignorespace` (bash) or `setopt HIST_IGNORE_SPACE` (zsh), \
+                 and pass the key with --key-file / --key-fd instead of --key",
+            );
+        }
+    }
+    if checked == 0 {
+        info!("  ⏭️  No history files found");
+    } else {
+        info!("  🔍 {} history file(s) checked", checked);
+    }
+}
+
+fn process_name(proc_dir: &Path) -> String {
+    fs::read_to_string(proc_dir.join("comm")).map(|s| s.trim().to_string()).unwrap_or_else(|_| "?".into())
+}
+
+/// Other processes' environment and argv, via /proc (Linux)
+fn check_processes(key: &[u8], searchable: bool, audit: &mut Audit) {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        info!("  ⏭️  /proc not available — process checks need Linux");
+        return;
+    };
+    let own_pid = std::process::id();
+    let (mut checked, mut unreadable) = (0, 0);
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else { continue };
+        let dir = entry.path();
+        let Ok(environ) = fs::read(dir.join("environ")) else {
+            unreadable += 1;
+            continue;
+        };
+        checked += 1;
+
+        // Our own environment is inherited; the parent that exported it is reported instead
+        if pid != own_pid {
+            for var in environ.split(|&b| b == 0) {
+                let Some(eq) = var.iter().position(|&b| b == b'=') else { continue };
+                let (name, value) = (String::from_utf8_lossy(&var[..eq]), &var[eq + 1..]);
+                if (name == KEY_ENV && !value.is_empty()) || (searchable && contains(value, key)) {
+                    audit.report(
+                        format!("pid {} ({}) ${}", pid, process_name(&dir), name),
+                        "passphrase in a process environment, readable by this user and inherited by children",
+                        format!("`unset {}` in that shell and start the cipher with --key-file / --key-fd", name),
+                    );
+                }
+            }
+        }
+        let cmdline = fs::read(dir.join("cmdline")).unwrap_or_default();
+        if searchable && contains(&cmdline, key) {
+            audit.report(
+                format!("pid {} ({}) argv", pid, process_name(&dir)),
+                "passphrase on a command line, visible to every user via ps",
+                "pass it with --key-file / --key-fd instead of --key",
+            );
+        }
+    }
+    info!("  🔍 {} process(es) checked, {} not readable by this user", checked, unreadable);
+}
+
+fn repo_root() -> Option<PathBuf> {
+    let output = Command::new("git").args(["rev-parse", "--show-toplevel"]).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+fn is_env_file(name: &str) -> bool {
+    name == ".env" || name.starts_with(".env.") || name.ends_with(".env")
+}
+
+fn collect_env_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if path.is_dir() {
+            if !SKIP_DIRS.contains(&name.as_str()) {
+                collect_env_files(&path, out);
+            }
+        } else if is_env_file(&name) {
+            out.push(path);
+        }
+    }
+}
+
+/// Whether git would ignore `path`; None outside a repository
+fn git_ignored(repo: &Path, path: &Path) -> Option<bool> {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["check-ignore", "-q", "--no-index"])
+        .arg(path)
+        .stderr(Stdio::null())
+        .status()
+        .ok()?;
+    match status.code() {
+        Some(0) => Some(true),
+        Some(1) => Some(false),
+        _ => None,
+    }
+}
+
+/// A `VIOLET_SOUL_KEY=<value>` line; empty placeholders in templates are fine
+fn sets_key_var(data: &[u8]) -> bool {
+    String::from_utf8_lossy(data).lines().any(|line| {
+        let line = line.trim();
+        let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+        line.strip_prefix(KEY_ENV)
+            .and_then(|rest| rest.trim_start().strip_prefix('='))
+            .is_some_and(|value| !matches!(value.trim(), "" | "\"\"" | "''"))
+    })
+}
+
+fn check_env_files(key: &[u8], searchable: bool, repo: &Path, audit: &mut Audit) {
+    let mut files = Vec::new();
+    collect_env_files(repo, &mut files);
+    files.sort();
+    for path in &files {
+        let Ok(data) = fs::read(path) else { continue };
+        let has_key = searchable && contains(&data, key);
+        let names_var = sets_key_var(&data);
+        if !has_key && !names_var {
+            continue;
+        }
+        let fix = match git_ignored(repo, path) {
+            Some(false) => "add it to .gitignore (it can be committed as is) and load the key from a --key-file outside the repo",
+            _ => "move the key to a --key-file outside the repository (mode 0600)",
+        };
+        let problem = if has_key { "contains the passphrase" } else { "sets VIOLET_SOUL_KEY" };
+        let location = path.strip_prefix(repo).unwrap_or(path).display().to_string();
+        audit.report(location, problem, fix);
+    }
+    info!("  🔍 {} .env file(s) under {:?}", files.len(), repo);
+}
+
+/// Soft core-size limit from /proc/self/limits; None when unlimited
+fn core_limit() -> Option<Option<u64>> {
+    let limits = fs::read_to_string("/proc/self/limits").ok()?;
+    let line = limits.lines().find(|l| l.starts_with("Max core file size"))?;
+    let soft = line.trim_start_matches("Max core file size").split_whitespace().next()?;
+    Some(soft.parse().ok())
+}
+
+fn check_core_dumps(audit: &mut Audit) {
+    let Some(limit) = core_limit() else {
+        info!("  ⏭️  /proc not available — core-dump checks need Linux");
+        return;
+    };
+    let pattern = fs::read_to_string("/proc/sys/kernel/core_pattern").unwrap_or_default();
+    let pattern = pattern.trim();
+    match limit {
+        Some(0) if !pattern.starts_with('|') => info!("  ✅ Core dumps disabled (ulimit -c 0)"),
+        Some(0) => info!("  ✅ Core dumps disabled (ulimit -c 0; handler: {})", pattern),
+        _ => {
+            let size = limit.map_or_else(|| "unlimited".to_string(), |bytes| format!("{} bytes", bytes));
+            let fix = if pattern.starts_with('|') {
+                format!(
+                    "run `ulimit -c 0` in the shell profile; dumps go to `{}` — for systemd-coredump also set \
+                     Storage=none in /etc/systemd/coredump.conf",
+                    pattern.trim_start_matches('|')
+                )
+            } else {
+                format!("run `ulimit -c 0` in the shell profile (dumps would be written as `{}`)", pattern)
+            };
+            audit.report(
+                format!("core file size limit: {}", size),
+                "a crash would write process memory, including the derived keys, to disk",
+                fix,
+            );
+        }
+    }
+}
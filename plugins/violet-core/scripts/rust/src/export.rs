@@ -0,0 +1,104 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Export — hand a decrypted target to someone using standard tooling
+//
+// `export --to age --recipient age1… rules-index.json` opens the target's
+// .enc in the data directory (of the chosen --profile) and encrypts the
+// plaintext to the given age recipients, to stdout or --output, binary or
+// --armor. Anyone holding a matching identity reads it with plain `age -d`;
+// `import --from age` brings such a file back. The plaintext only ever lives
+// in zeroized memory.
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+use age::armor::{ArmoredWriter, Format};
+use age::Encryptor;
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+use tracing::info;
+use zeroize::Zeroizing;
+
+use crate::kdf::KdfArgs;
+use crate::key_source::KeyArgs;
+use crate::{logging, profile, resolve_data_dir, write_atomic, Binding};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// age v1 (age-encryption.org/v1)
+    Age,
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    key: KeyArgs,
+    #[command(flatten)]
+    kdf: KdfArgs,
+    /// Format to write
+    #[arg(long, value_enum)]
+    to: ExportFormat,
+    /// age recipient to encrypt to, age1… (repeatable)
+    #[arg(long = "recipient", value_name = "AGE1…", required = true)]
+    recipients: Vec<String>,
+    /// Target to export, e.g. rules-index.json
+    target: String,
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+    /// Profile from profiles.json in the data directory the target belongs to (default: local)
+    #[arg(long)]
+    profile: Option<String>,
+    /// Where to write the age file (default: stdout)
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// ASCII-armored (PEM-style) output rather than binary
+    #[arg(long)]
+    armor: bool,
+}
+
+pub fn run(args: ExportArgs) -> Result<()> {
+    match &args.output {
+        Some(output) if output.exists() => bail!("{:?} exists — move it aside or pass another --output", output),
+        Some(_) => {}
+        None => logging::claim_stdout(),
+    }
+    let recipients = args
+        .recipients
+        .iter()
+        .map(|r| r.parse::<age::x25519::Recipient>().map_err(|e| anyhow::anyhow!("age recipient {:?}: {}", r, e)))
+        .collect::<Result<Vec<_>>>()?;
+    let data_dir = resolve_data_dir(args.data_dir);
+    let profile = profile::load(&data_dir, args.profile.as_deref())?;
+    let target = profile.select(std::slice::from_ref(&args.target))?[0];
+    let path = data_dir.join(profile.output(target));
+    let data = fs::read(&path).with_context(|| format!("read {:?}", path))?;
+    let key = args.key.holder()?;
+    let (_, plain) = key.open(Binding::new(&profile.salt, target), &data, &args.kdf.params()?)?;
+    let plain = Zeroizing::new(plain);
+
+    let encrypted = match args.to {
+        ExportFormat::Age => seal_age(&recipients, &plain, args.armor)?,
+    };
+    match &args.output {
+        Some(output) => {
+            write_atomic(output, &encrypted).with_context(|| format!("write {:?}", output))?;
+            info!("📤 {:?} → {:?} (age, {} recipient(s), {} bytes)", path, output, recipients.len(), encrypted.len());
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(&encrypted).and_then(|()| stdout.flush()).context("write age file to stdout")?;
+        }
+    }
+    Ok(())
+}
+
+fn seal_age(recipients: &[age::x25519::Recipient], plaintext: &[u8], armor: bool) -> Result<Vec<u8>> {
+    let encryptor = Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn age::Recipient))
+        .context("no age recipients")?;
+    let mut out = Vec::new();
+    let format = if armor { Format::AsciiArmor } else { Format::Binary };
+    let armored = ArmoredWriter::wrap_output(&mut out, format).context("age armor")?;
+    let mut writer = encryptor.wrap_output(armored).context("age encrypt")?;
+    writer.write_all(plaintext).context("age encrypt")?;
+    writer.finish().and_then(|armored| armored.finish()).context("age encrypt")?;
+    Ok(out)
+}
@@ -0,0 +1,197 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// C API — `violet_encrypt` / `violet_decrypt` for plugin hosts that embed the cipher (cdylib)
+//
+// Declarations and the ownership rules live in include/violet_cipher.h. In
+// short: inputs are only borrowed for the call; a result is a VioletBuffer
+// the library allocated, handed back to `violet_buffer_free` exactly once,
+// which wipes it; a failed call returns VIOLET_ERR with an empty buffer and
+// `violet_last_error` says why until the next encrypt or decrypt on that
+// thread. Panics are caught here and reported the same way, never unwound
+// into the host.
+//
+// Nothing here reads or writes files or prints. Encryption writes v5 JSON,
+// as `encrypt-local` / `encrypt-git` do; decryption takes any version and
+// hands back the raw plaintext, text or not.
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice};
+
+use anyhow::{bail, Context, Result};
+use argon2::Params as KdfParams;
+use zeroize::Zeroize;
+
+use crate::header::{Compression, ContentType, Header};
+use crate::{decrypt_payload, salt, v5_encrypt, Binding};
+
+pub const VIOLET_OK: c_int = 0;
+pub const VIOLET_ERR: c_int = -1;
+
+/// Bytes owned by the library; `ptr` is NULL when empty
+#[repr(C)]
+pub struct VioletBuffer {
+    pub ptr: *mut u8,
+    pub len: usize,
+}
+
+impl VioletBuffer {
+    const EMPTY: Self = Self { ptr: ptr::null_mut(), len: 0 };
+
+    fn new(bytes: Vec<u8>) -> Self {
+        if bytes.is_empty() {
+            return Self::EMPTY;
+        }
+        let len = bytes.len();
+        let ptr = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+        Self { ptr, len }
+    }
+}
+
+/// Argon2id costs per layer; files with a VSC! preamble carry their own
+#[repr(C)]
+pub struct VioletKdf {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: Option<String>) {
+    // Messages never hold NUL on purpose; one slipping through must not lose the rest
+    let message = message.map(|m| CString::new(m.replace('\0', " ")).expect("NUL bytes replaced"));
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+/// Shared body of the entry points: reset the last error, run `op` with
+/// panics caught, and store its result in `out`
+///
+/// # Safety
+/// `out` must be NULL or valid for writes.
+unsafe fn call(out: *mut VioletBuffer, op: impl FnOnce() -> Result<Vec<u8>>) -> c_int {
+    set_last_error(None);
+    let Some(out) = out.as_mut() else {
+        set_last_error(Some("out is NULL".to_string()));
+        return VIOLET_ERR;
+    };
+    *out = VioletBuffer::EMPTY;
+    match panic::catch_unwind(AssertUnwindSafe(op)) {
+        Ok(Ok(bytes)) => {
+            *out = VioletBuffer::new(bytes);
+            VIOLET_OK
+        }
+        Ok(Err(e)) => {
+            set_last_error(Some(format!("{:#}", e)));
+            VIOLET_ERR
+        }
+        Err(_) => {
+            set_last_error(Some("internal error (panic) — please report it".to_string()));
+            VIOLET_ERR
+        }
+    }
+}
+
+/// # Safety
+/// `ptr` must be NULL with `len` 0, or valid for `len` bytes for the call.
+unsafe fn borrow_bytes<'a>(ptr: *const u8, len: usize, what: &str) -> Result<&'a [u8]> {
+    match (ptr.is_null(), len) {
+        (true, 0) => Ok(&[]),
+        (true, _) => bail!("{} is NULL", what),
+        (false, _) => Ok(slice::from_raw_parts(ptr, len)),
+    }
+}
+
+/// # Safety
+/// `ptr` must be NULL or a NUL-terminated string valid for the call.
+unsafe fn borrow_str<'a>(ptr: *const c_char, what: &str) -> Result<&'a str> {
+    if ptr.is_null() {
+        bail!("{} is NULL", what);
+    }
+    CStr::from_ptr(ptr).to_str().with_context(|| format!("{} is not UTF-8", what))
+}
+
+/// # Safety
+/// `kdf` must be NULL (the CLI's default costs) or point to a VioletKdf.
+unsafe fn kdf_params(kdf: *const VioletKdf) -> Result<KdfParams> {
+    match kdf.as_ref() {
+        None => Ok(KdfParams::default()),
+        Some(kdf) => crate::kdf::bounded_params(kdf.memory_kib, kdf.iterations, kdf.parallelism),
+    }
+}
+
+/// Encrypt `plaintext` for `name` under `salt_label` ("local", "git" or another domain)
+///
+/// # Safety
+/// See include/violet_cipher.h: every pointer must be valid as described there for the call.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn violet_encrypt(
+    passphrase: *const u8,
+    passphrase_len: usize,
+    salt_label: *const c_char,
+    name: *const c_char,
+    plaintext: *const u8,
+    plaintext_len: usize,
+    kdf: *const VioletKdf,
+    out: *mut VioletBuffer,
+) -> c_int {
+    call(out, || {
+        let passphrase = borrow_bytes(passphrase, passphrase_len, "passphrase")?;
+        let name = borrow_str(name, "name")?;
+        let salt = salt::salt_for(borrow_str(salt_label, "salt_label")?)?;
+        let binding = Binding::new(&salt, name);
+        let plaintext = borrow_bytes(plaintext, plaintext_len, "plaintext")?;
+        let header = Header::new(ContentType::json(), Compression::None);
+        v5_encrypt(passphrase, binding, plaintext, &header, &kdf_params(kdf)?)
+    })
+}
+
+/// Decrypt a file of any supported version for `name` under `salt_label`
+///
+/// # Safety
+/// See include/violet_cipher.h: every pointer must be valid as described there for the call.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn violet_decrypt(
+    passphrase: *const u8,
+    passphrase_len: usize,
+    salt_label: *const c_char,
+    name: *const c_char,
+    data: *const u8,
+    data_len: usize,
+    kdf: *const VioletKdf,
+    out: *mut VioletBuffer,
+) -> c_int {
+    call(out, || {
+        let passphrase = borrow_bytes(passphrase, passphrase_len, "passphrase")?;
+        let name = borrow_str(name, "name")?;
+        let salt = salt::salt_for(borrow_str(salt_label, "salt_label")?)?;
+        let binding = Binding::new(&salt, name);
+        let data = borrow_bytes(data, data_len, "data")?;
+        let (_, plain) = decrypt_payload(passphrase, binding, data, &kdf_params(kdf)?)?;
+        Ok(plain)
+    })
+}
+
+/// Wipe and release a buffer from `violet_encrypt` / `violet_decrypt`; NULL and empty buffers are ignored
+///
+/// # Safety
+/// `buf` must be NULL or hold a buffer this library returned that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn violet_buffer_free(buf: *mut VioletBuffer) {
+    let Some(buf) = buf.as_mut() else { return };
+    if !buf.ptr.is_null() {
+        let mut bytes = Box::from_raw(ptr::slice_from_raw_parts_mut(buf.ptr, buf.len));
+        bytes.zeroize();
+    }
+    *buf = VioletBuffer::EMPTY;
+}
+
+/// Why the last encrypt or decrypt on this thread failed, or NULL after a
+/// success; valid until the next one on the thread
+#[no_mangle]
+pub extern "C" fn violet_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
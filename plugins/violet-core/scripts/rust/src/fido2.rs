@@ -0,0 +1,164 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// FIDO2 pepper — the second key factor from a security key's hmac-secret (`--features fido2`)
+//
+// `key fido2-enroll` makes a credential with the hmac-secret extension on the
+// token and writes its ID, with a random salt, to a credential file. `--fido2
+// <file>` then asks the token for HMAC(credential secret, salt) — touch, and
+// the PIN with --fido2-pin — and uses the 32-byte answer as the pepper in
+// place of --pepper-file. The secret never leaves the token, so the credential
+// file alone is useless: v5 files sealed this way record the pepper ID and
+// open only while the same token is plugged in.
+//
+// The token is driven through the libfido2 tools (fido2-token, fido2-cred,
+// fido2-assert), which read their base64 parameters from stdin.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::atomic::write_secret_new;
+use crate::pepper::{self, Pepper};
+use crate::{logging, random_bytes, tool};
+
+pub const CREDENTIAL_FILE: &str = ".violet-fido2";
+const CREDENTIAL_VERSION: u32 = 1;
+const RP_ID: &str = "violet-cipher";
+const HMAC_SALT_LEN: usize = 32;
+const PACKAGE: &str = "libfido2's fido2-tools";
+
+#[derive(Args)]
+pub struct Fido2Args {
+    /// Second key factor for v5 files from this security key credential (`key fido2-enroll`)
+    #[arg(long = "fido2", global = true, value_name = "CREDENTIAL_FILE", conflicts_with = "pepper_file")]
+    credential: Option<PathBuf>,
+    /// Token to use, e.g. /dev/hidraw3 (default: the first one `fido2-token -L` lists)
+    #[arg(long, global = true)]
+    fido2_device: Option<String>,
+    /// Require the token's PIN as well as a touch (fido2 tools prompt for it)
+    #[arg(long, global = true)]
+    fido2_pin: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Credential {
+    version: u32,
+    rp_id: String,
+    /// Base64 credential ID the token returned at enrollment
+    credential_id: String,
+    /// Base64 hmac-secret salt
+    salt: String,
+}
+
+struct Options {
+    device: Option<String>,
+    pin: bool,
+}
+
+static OPTIONS: OnceLock<Options> = OnceLock::new();
+
+/// Record the device options and, with --fido2, ask the token for the pepper
+pub fn configure(args: &Fido2Args) -> Result<()> {
+    let _ = OPTIONS.set(Options { device: args.fido2_device.clone(), pin: args.fido2_pin });
+    if let Some(path) = &args.credential {
+        let secret = hmac_secret(path)?;
+        pepper::configure(Pepper::from_secret(&secret))?;
+    }
+    Ok(())
+}
+
+fn options() -> &'static Options {
+    OPTIONS.get_or_init(|| Options { device: None, pin: false })
+}
+
+/// --fido2-device, else the first token plugged in
+fn device() -> Result<String> {
+    if let Some(device) = &options().device {
+        return Ok(device.clone());
+    }
+    let list = tool::run("fido2-token", &["-L"], b"", PACKAGE)?;
+    let list = String::from_utf8_lossy(&list);
+    match list.lines().find_map(|line| line.split_once(": ").map(|(path, _)| path.trim().to_string())) {
+        Some(device) => Ok(device),
+        None => bail!("no FIDO2 token found — plug one in or pass --fido2-device"),
+    }
+}
+
+/// Flags, then the device, for a fido2-cred / fido2-assert call
+fn tool_args<'a>(flags: &[&'a str], device: &'a str) -> Vec<&'a str> {
+    let mut args = flags.to_vec();
+    if options().pin {
+        args.push("-v");
+    }
+    args.push(device);
+    args
+}
+
+/// Fresh random client data hash; nothing verifies the attestation or assertion signature
+fn client_data_hash() -> String {
+    BASE64.encode(Sha256::digest(random_bytes::<32>()))
+}
+
+fn lines(output: &[u8]) -> Result<Vec<String>> {
+    let text = std::str::from_utf8(output).context("fido2 tool output is not text")?;
+    Ok(text.lines().map(str::to_string).collect())
+}
+
+/// Make an hmac-secret credential on the token and write it to `output`; returns the pepper ID
+pub fn enroll(output: &Path) -> Result<String> {
+    if output.exists() {
+        bail!("{:?} already exists — remove it first or choose another --output", output);
+    }
+    let device = device()?;
+    logging::note(format_args!("👆 Touch the security key on {} to create the credential...", device));
+    let input = format!("{}\n{}\nviolet\n{}\n", client_data_hash(), RP_ID, BASE64.encode(random_bytes::<16>()));
+    let made = tool::run("fido2-cred", &tool_args(&["-M", "-h"], &device), input.as_bytes(), PACKAGE)?;
+    // client data hash, rp id, format, authenticator data, credential ID, ...
+    let credential_id = lines(&made)?.into_iter().nth(4).context("fido2-cred printed no credential ID")?;
+    let credential = Credential {
+        version: CREDENTIAL_VERSION,
+        rp_id: RP_ID.to_string(),
+        credential_id,
+        salt: BASE64.encode(random_bytes::<HMAC_SALT_LEN>()),
+    };
+    logging::note(format_args!("👆 Touch it again to check the credential..."));
+    let secret = assert(&credential, &device)?;
+    write_secret_new(output, serde_json::to_string_pretty(&credential)?.as_bytes())?;
+    Ok(Pepper::from_secret(&secret).id)
+}
+
+/// The token's hmac-secret for the credential in `path`
+fn hmac_secret(path: &Path) -> Result<Zeroizing<Vec<u8>>> {
+    let json = fs::read_to_string(path).with_context(|| format!("read FIDO2 credential {:?}", path))?;
+    let credential: Credential =
+        serde_json::from_str(&json).with_context(|| format!("parse FIDO2 credential {:?}", path))?;
+    if credential.version != CREDENTIAL_VERSION {
+        bail!("FIDO2 credential {:?} is version {} — upgrade violet-cipher", path, credential.version);
+    }
+    let device = device()?;
+    logging::note(format_args!("👆 Touch the security key on {}...", device));
+    assert(&credential, &device).with_context(|| format!("FIDO2 credential {:?}", path))
+}
+
+fn assert(credential: &Credential, device: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let input = format!("{}\n{}\n{}\n{}\n", client_data_hash(), credential.rp_id, credential.credential_id, credential.salt);
+    let output = tool::run("fido2-assert", &tool_args(&["-G", "-h", "-p"], device), input.as_bytes(), PACKAGE)
+        .context("the token refused — is it the one the credential was enrolled on?")?;
+    // The hmac-secret output is the last line
+    let last = lines(&output)?.pop().context("fido2-assert printed nothing")?;
+    let secret = Zeroizing::new(BASE64.decode(last.trim()).context("fido2-assert hmac-secret is not base64")?);
+    if secret.len() != HMAC_SALT_LEN {
+        bail!("fido2-assert returned a {}-byte hmac-secret, expected {}", secret.len(), HMAC_SALT_LEN);
+    }
+    Ok(secret)
+}
+
+pub fn default_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(CREDENTIAL_FILE)
+}
@@ -0,0 +1,343 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Field-level encryption — only the sensitive leaves of a target JSON file become ciphertext
+//
+// `encrypt-fields` writes <stem>.fields.json next to each target file, with the
+// value at every selected path replaced by the string `violet-field:1:<hex>`
+// (nonce ‖ AES-256-GCM ciphertext of the value's JSON). Keys, key order and
+// every other value stay as they are, so the file diffs and merges like the
+// plaintext. Paths are a JSONPath subset: `$`, `.key`, `['key']`, `[N]`, `[*]`
+// and `.*`, e.g. `$.minds[*].private_notes`; they come from --path or, without
+// it, from field-paths.json in the data directory (`{"<target>": [paths]}`).
+//
+// The value key is derived once per file: Argon2id over the passphrase with
+// salt SHA-256(FIELD_CONTEXT ‖ file name). Each value's associated data is the
+// file name and its path with array indexes written `[*]`, so a value moved to
+// another field fails to open while reordered arrays (merges) do not.
+// Re-encrypting keeps a value's previous ciphertext while it still opens to
+// the same value, so unchanged leaves never show up in a diff.
+// `decrypt-fields` opens every sealed value it finds; it needs no path list.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use argon2::Params as KdfParams;
+use clap::Args;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::kdf::KdfArgs;
+use crate::key_source::KeyArgs;
+use crate::secret::SecretKey;
+use crate::{
+    decrypt_aes_gcm, derive_key_argon2, encrypt_aes_gcm, layer_passphrase, print_dry_run_banner, report_planned_write,
+    resolve_data_dir, select_targets, write_atomic, LOCAL_SALT,
+};
+
+pub const SEALED_PREFIX: &str = "violet-field:1:";
+pub const FIELDS_SUFFIX: &str = ".fields.json";
+const PATHS_FILE: &str = "field-paths.json";
+const FIELD_CONTEXT: &[u8] = b"violet-field-v1\0";
+
+#[derive(Args)]
+pub struct EncryptArgs {
+    #[command(flatten)]
+    key: KeyArgs,
+    #[command(flatten)]
+    kdf: KdfArgs,
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+    /// Value to encrypt, e.g. '$.minds[*].private_notes' (repeatable; default: field-paths.json)
+    #[arg(long = "path", value_name = "JSONPATH")]
+    paths: Vec<String>,
+    /// Only this target file (repeatable; default: every target present)
+    #[arg(long = "file", value_name = "NAME")]
+    files: Vec<String>,
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct DecryptArgs {
+    #[command(flatten)]
+    key: KeyArgs,
+    #[command(flatten)]
+    kdf: KdfArgs,
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+    /// Only this target file (repeatable; default: every .fields.json present)
+    #[arg(long = "file", value_name = "NAME")]
+    files: Vec<String>,
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Any,
+}
+
+/// Parse the JSONPath subset described above
+fn parse_path(path: &str) -> Result<Vec<Segment>> {
+    let mut rest = path.trim().strip_prefix('$').with_context(|| format!("path must start with $: {}", path))?;
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            let (name, tail) = after.split_at(end);
+            segments.push(match name {
+                "" => bail!("empty key in path {}", path),
+                "*" => Segment::Any,
+                name => Segment::Key(name.to_string()),
+            });
+            rest = tail;
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let (inner, tail) = after.split_once(']').with_context(|| format!("unclosed [ in path {}", path))?;
+            let quoted = inner.strip_prefix('\'').and_then(|s| s.strip_suffix('\''));
+            let quoted = quoted.or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+            segments.push(match (inner, quoted) {
+                (_, Some(name)) => Segment::Key(name.to_string()),
+                ("*", None) => Segment::Any,
+                (index, None) => {
+                    Segment::Index(index.parse().with_context(|| format!("bad index [{}] in path {}", index, path))?)
+                }
+            });
+            rest = tail;
+        } else {
+            bail!("expected . or [ at {:?} in path {}", rest, path);
+        }
+    }
+    Ok(segments)
+}
+
+/// Value key of one target file
+struct FieldKey {
+    key: SecretKey,
+    name: String,
+}
+
+impl FieldKey {
+    fn derive(passphrase: &[u8], name: &str, kdf: &KdfParams) -> Result<Self> {
+        let layered = layer_passphrase(passphrase, "fields", LOCAL_SALT);
+        let salt = Sha256::new().chain_update(FIELD_CONTEXT).chain_update(name).finalize();
+        Ok(Self { key: derive_key_argon2(&layered, &salt, kdf)?, name: name.to_string() })
+    }
+
+    fn aad(&self, location: &str) -> Vec<u8> {
+        [FIELD_CONTEXT, self.name.as_bytes(), b"\0", location.as_bytes()].concat()
+    }
+
+    fn seal(&self, value: &Value, location: &str) -> Result<String> {
+        let sealed = encrypt_aes_gcm(&self.key, &serde_json::to_vec(value)?, &self.aad(location))?;
+        Ok(format!("{}{}", SEALED_PREFIX, hex::encode(sealed)))
+    }
+
+    fn open(&self, sealed: &str, location: &str) -> Result<Value> {
+        let data = hex::decode(&sealed[SEALED_PREFIX.len()..]).with_context(|| format!("{} is not hex", location))?;
+        let plain = decrypt_aes_gcm(&self.key, &data, &self.aad(location)).with_context(|| {
+            format!("{} — wrong passphrase, --kdf-* settings, or a value moved from another field", location)
+        })?;
+        serde_json::from_slice(&plain).with_context(|| format!("{} does not hold JSON", location))
+    }
+}
+
+fn sealed_str(value: &Value) -> Option<&str> {
+    value.as_str().filter(|s| s.starts_with(SEALED_PREFIX))
+}
+
+/// `location` extended by an object key: `.key`, or `["key"]` when it is not an identifier
+fn key_location(location: &str, key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+        format!("{}.{}", location, key)
+    } else {
+        format!("{}[{}]", location, Value::from(key))
+    }
+}
+
+/// JSON pointer extended by one token, for finding the same value in the previous output
+fn pointer_token(pointer: &str, token: &str) -> String {
+    format!("{}/{}", pointer, token.replace('~', "~0").replace('/', "~1"))
+}
+
+struct Sealer<'a> {
+    key: &'a FieldKey,
+    previous: Option<&'a Value>,
+    sealed: usize,
+    kept: usize,
+}
+
+impl Sealer<'_> {
+    /// Seal every value `pattern` selects under `value`; returns how many matched
+    fn walk(&mut self, value: &mut Value, pattern: &[Segment], location: &str, pointer: &str) -> Result<usize> {
+        let Some((segment, rest)) = pattern.split_first() else {
+            if sealed_str(value).is_none() {
+                self.seal(value, location, pointer)?;
+            }
+            return Ok(1);
+        };
+        let mut matched = 0;
+        match (segment, value) {
+            (Segment::Key(key), Value::Object(map)) => {
+                if let Some(child) = map.get_mut(key) {
+                    matched += self.walk(child, rest, &key_location(location, key), &pointer_token(pointer, key))?;
+                }
+            }
+            (Segment::Index(index), Value::Array(items)) => {
+                if let Some(child) = items.get_mut(*index) {
+                    let location = format!("{}[*]", location);
+                    matched += self.walk(child, rest, &location, &pointer_token(pointer, &index.to_string()))?;
+                }
+            }
+            (Segment::Any, Value::Object(map)) => {
+                for (key, child) in map.iter_mut() {
+                    matched += self.walk(child, rest, &key_location(location, key), &pointer_token(pointer, key))?;
+                }
+            }
+            (Segment::Any, Value::Array(items)) => {
+                let location = format!("{}[*]", location);
+                for (index, child) in items.iter_mut().enumerate() {
+                    matched += self.walk(child, rest, &location, &pointer_token(pointer, &index.to_string()))?;
+                }
+            }
+            _ => {}
+        }
+        Ok(matched)
+    }
+
+    fn seal(&mut self, value: &mut Value, location: &str, pointer: &str) -> Result<()> {
+        let previous = self.previous.and_then(|p| p.pointer(pointer)).and_then(sealed_str);
+        if let Some(previous) = previous {
+            if self.key.open(previous, location).is_ok_and(|old| old == *value) {
+                *value = Value::from(previous);
+                self.kept += 1;
+                return Ok(());
+            }
+        }
+        *value = Value::from(self.key.seal(value, location)?);
+        self.sealed += 1;
+        Ok(())
+    }
+}
+
+/// Open every sealed string under `value`; returns how many there were
+fn open_all(key: &FieldKey, value: &mut Value, location: &str) -> Result<usize> {
+    if let Some(sealed) = sealed_str(value) {
+        *value = key.open(sealed, location)?;
+        return Ok(1);
+    }
+    let mut opened = 0;
+    match value {
+        Value::Object(map) => {
+            for (name, child) in map.iter_mut() {
+                opened += open_all(key, child, &key_location(location, name))?;
+            }
+        }
+        Value::Array(items) => {
+            let location = format!("{}[*]", location);
+            for child in items {
+                opened += open_all(key, child, &location)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(opened)
+}
+
+/// `rules-index.json` → `rules-index.fields.json`
+pub fn fields_name(name: &str) -> String {
+    format!("{}{}", name.strip_suffix(".json").unwrap_or(name), FIELDS_SUFFIX)
+}
+
+/// Paths per target: --path for every target, else field-paths.json
+fn load_paths(data_dir: &Path, paths: Vec<String>, targets: &[&str]) -> Result<BTreeMap<String, Vec<String>>> {
+    if !paths.is_empty() {
+        return Ok(targets.iter().map(|&name| (name.to_string(), paths.clone())).collect());
+    }
+    let config = data_dir.join(PATHS_FILE);
+    let text = fs::read_to_string(&config)
+        .with_context(|| format!("no --path given and no {:?} to read paths from", config))?;
+    serde_json::from_str(&text).with_context(|| format!("{:?} must map target names to path lists", config))
+}
+
+pub fn encrypt(args: EncryptArgs) -> Result<()> {
+    let passphrase = args.key.resolve()?;
+    let kdf = args.kdf.params()?;
+    let data_dir = resolve_data_dir(args.data_dir);
+    let targets = select_targets(&args.files)?;
+    let paths = load_paths(&data_dir, args.paths, &targets)?;
+
+    info!("🔐 Encrypting selected JSON fields...");
+    print_dry_run_banner(args.dry_run);
+    for name in targets {
+        let Some(patterns) = paths.get(name).filter(|p| !p.is_empty()) else {
+            continue;
+        };
+        let source = data_dir.join(name);
+        let Ok(text) = fs::read_to_string(&source) else {
+            info!("  ⏭️  Skip (not found): {}", name);
+            continue;
+        };
+        let mut document: Value = serde_json::from_str(&text).with_context(|| format!("{} is not JSON", name))?;
+        let dest = data_dir.join(fields_name(name));
+        let previous = match fs::read_to_string(&dest) {
+            Ok(text) => Some(serde_json::from_str::<Value>(&text).with_context(|| format!("{:?} is not JSON", dest))?),
+            Err(_) => None,
+        };
+
+        let key = FieldKey::derive(&passphrase, name, &kdf)?;
+        let mut sealer = Sealer { key: &key, previous: previous.as_ref(), sealed: 0, kept: 0 };
+        for pattern in patterns {
+            if sealer.walk(&mut document, &parse_path(pattern)?, "$", "")? == 0 {
+                warn!("  ⚠️  {}: {} matches nothing", name, pattern);
+            }
+        }
+
+        let output = format!("{}\n", serde_json::to_string_pretty(&document)?);
+        if args.dry_run {
+            report_planned_write(&dest, output.as_bytes(), false);
+            continue;
+        }
+        write_atomic(&dest, output.as_bytes())?;
+        info!(
+            "  ✅ {} → {} ({} value(s) sealed, {} unchanged kept as they were)",
+            name,
+            fields_name(name),
+            sealer.sealed,
+            sealer.kept
+        );
+    }
+    Ok(())
+}
+
+pub fn decrypt(args: DecryptArgs) -> Result<()> {
+    let passphrase = args.key.resolve()?;
+    let kdf = args.kdf.params()?;
+    let data_dir = resolve_data_dir(args.data_dir);
+
+    info!("🔓 Decrypting sealed JSON fields...");
+    print_dry_run_banner(args.dry_run);
+    for name in select_targets(&args.files)? {
+        let source = data_dir.join(fields_name(name));
+        let Ok(text) = fs::read_to_string(&source) else {
+            info!("  ⏭️  Skip (not found): {}", fields_name(name));
+            continue;
+        };
+        let mut document: Value =
+            serde_json::from_str(&text).with_context(|| format!("{} is not JSON", fields_name(name)))?;
+        let key = FieldKey::derive(&passphrase, name, &kdf)?;
+        let opened = open_all(&key, &mut document, "$").with_context(|| format!("open {}", fields_name(name)))?;
+
+        let dest = data_dir.join(name);
+        let output = format!("{}\n", serde_json::to_string_pretty(&document)?);
+        if args.dry_run {
+            report_planned_write(&dest, output.as_bytes(), false);
+            continue;
+        }
+        write_atomic(&dest, output.as_bytes())?;
+        info!("  ✅ {} → {} ({} value(s) opened)", fields_name(name), name, opened);
+    }
+    Ok(())
+}
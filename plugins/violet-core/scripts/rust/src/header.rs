@@ -0,0 +1,492 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// v5 header — authenticated TLV metadata in front of the layered ciphertext
+//
+// Layout: `u16 length (BE)` then entries of `tag u8 | u16 length (BE) | value`.
+// The whole header is AEAD associated data for the outer layer and is covered
+// by the trailing HMAC, so a content-type tag cannot be swapped undetected.
+// Unknown tags are skipped to let newer writers add fields.
+//
+// Writers stamp every header with the encryption time. v5 writers in a data
+// directory add an HMAC of the plaintext keyed from the passphrase
+// (verify_token::digest_key), so `encrypt-local` can leave an unchanged .enc
+// as it is and `decrypt-local` can tell an edited .json. Older headers carry
+// the plaintext's bare SHA-256 instead, which anyone could test a guess
+// against; `status` still compares it without the key.
+// Passphrase writers in a data directory also record their key ID, salted
+// per directory (verify_token.rs), which keyring.rs maps to a passphrase
+// generation. Key IDs in headers from before the layer-keyed HMAC were
+// derived under one fixed salt; they match no passphrase now and are not
+// read. Writers record the ID of the external pepper when one was used
+// (pepper.rs) and the ID of the keyfile the passphrase was paired with
+// (keyfile.rs). Every writer records the salt string it sealed under
+// (salt.rs). v5 writers mark the trailing HMAC as keyed by the file's own
+// outer layer key (`layer_mac_key` in lib.rs); files without a mark are
+// checked with the compiled-in seed or pepper, which anyone holding the
+// binary can use. Files marked by earlier writers as keyed by the passphrase
+// took that key from a fixed-salt derivation, and their HMAC goes unchecked.
+// Writers that read the plaintext from a file record its modification time,
+// which the decrypt commands give back to the file they write. With a
+// recovery key set up, v5 writers also escrow the passphrase to it (recovery.rs).
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::keyfile::KEYFILE_ID_LEN;
+use crate::pepper::PEPPER_ID_LEN;
+use crate::preamble::Suite;
+use crate::recovery;
+use crate::secret::SecretKey;
+use crate::verify_token::KEY_ID_LEN;
+
+const TAG_CONTENT_TYPE: u8 = 0x01;
+const TAG_COMPRESSION: u8 = 0x02;
+const TAG_FILE_NAME: u8 = 0x03;
+const TAG_ENCRYPTED_AT: u8 = 0x04;
+const TAG_PLAINTEXT_SHA256: u8 = 0x05;
+const TAG_KEY_ID: u8 = 0x06;
+const TAG_PEPPER_ID: u8 = 0x07;
+const TAG_KEYFILE_ID: u8 = 0x08;
+const TAG_SALT_LABEL: u8 = 0x09;
+const TAG_SUITE: u8 = 0x0a;
+const TAG_PASSPHRASE_MAC: u8 = 0x0b;
+const TAG_PLAINTEXT_MTIME: u8 = 0x0c;
+const TAG_RECOVERY: u8 = 0x0d;
+const TAG_LAYER_MAC: u8 = 0x0e;
+const TAG_PLAINTEXT_HMAC: u8 = 0x0f;
+const MAX_CONTENT_TYPE_LEN: usize = 32;
+/// Index files are small, so the slowest levels still cost only milliseconds
+const ZSTD_LEVEL: i32 = 19;
+
+/// Short lowercase label for what the plaintext is (`json`, `png`, `sqlite`, …)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentType(String);
+
+impl ContentType {
+    pub const JSON: &'static str = "json";
+    pub const BINARY: &'static str = "binary";
+
+    pub fn parse(label: &str) -> Result<Self> {
+        let label = label.trim().to_ascii_lowercase();
+        if label.is_empty() || label.len() > MAX_CONTENT_TYPE_LEN {
+            bail!("content type must be 1–{} characters", MAX_CONTENT_TYPE_LEN);
+        }
+        if !label.bytes().all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(&b)) {
+            bail!("content type may only contain a-z, 0-9, '+', '-' and '.': {}", label);
+        }
+        Ok(Self(label))
+    }
+
+    pub fn json() -> Self {
+        Self(Self::JSON.to_string())
+    }
+
+    /// Guess from a file extension, falling back to `binary`
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .unwrap_or_default();
+        let label = match ext.as_str() {
+            "json" => Self::JSON,
+            "txt" | "md" => "text",
+            "png" => "png",
+            "jpg" | "jpeg" => "jpeg",
+            "webp" => "webp",
+            "db" | "sqlite" | "sqlite3" => "sqlite",
+            "ttf" | "otf" => "font",
+            _ => Self::BINARY,
+        };
+        Self(label.to_string())
+    }
+
+    /// Whether the plaintext is expected to be UTF-8
+    pub fn is_text(&self) -> bool {
+        matches!(self.0.as_str(), "json" | "text")
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for ContentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Codec applied to the plaintext before the inner layer
+///
+/// Only written to the header when not `None`, so uncompressed v5 files keep
+/// their original layout. Readers that predate the tag skip it and would hand
+/// back compressed bytes, which then fail the usual UTF-8/JSON checks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    Zstd,
+}
+
+impl Compression {
+    pub fn from_flag(compress: bool) -> Self {
+        if compress {
+            Self::Zstd
+        } else {
+            Self::None
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self> {
+        match byte {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Zstd),
+            other => bail!("v5 header names unknown compression {} — upgrade violet-cipher", other),
+        }
+    }
+
+    fn byte(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zstd => 1,
+        }
+    }
+
+    pub fn compress(self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(plaintext.to_vec()),
+            Self::Zstd => zstd::encode_all(plaintext, ZSTD_LEVEL).context("zstd compress"),
+        }
+    }
+
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Zstd => zstd::decode_all(data).context("zstd decompress"),
+        }
+    }
+}
+
+impl std::fmt::Display for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::None => "uncompressed",
+            Self::Zstd => "zstd",
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct Header {
+    pub content_type: ContentType,
+    pub compression: Compression,
+    /// Logical name the ciphertext was written for (`rules-index.json`);
+    /// None in files written before names were bound
+    pub file_name: Option<String>,
+    /// Unix seconds when the file was written; None in files from before stamping
+    pub encrypted_at: Option<u64>,
+    /// SHA-256 of the plaintext before compression; only in files from before `plaintext_hmac`
+    pub plaintext_sha256: Option<[u8; 32]>,
+    /// HMAC-SHA256 of the plaintext before compression under verify_token::digest_key;
+    /// None outside a data directory, in envelopes and in older files
+    pub plaintext_hmac: Option<[u8; 32]>,
+    /// Hex key ID of the passphrase that wrote the file; None for envelopes and older files
+    pub key_id: Option<String>,
+    /// Hex ID of the external pepper the file was sealed with; None for the compiled-in seed
+    pub pepper_id: Option<String>,
+    /// Hex ID of the keyfile mixed into the KDF input; None when the passphrase alone opens it
+    pub keyfile_id: Option<String>,
+    /// Salt string the layers were sealed under (`violet-soul-salt-<label>-2026`); None in older files
+    pub salt_label: Option<String>,
+    /// Algorithms of the passphrase layers (v5); only written when not the default
+    pub suite: Suite,
+    /// The trailing HMAC is keyed from a fixed-salt derivation of the passphrase; only
+    /// in files from before `layer_mac`, whose HMAC is no longer checked
+    pub passphrase_mac: bool,
+    /// The trailing HMAC is keyed from the outer layer key rather than the compiled-in
+    /// seed or pepper; false in envelopes and older files
+    pub layer_mac: bool,
+    /// Modification time of the plaintext file; None when it came from elsewhere, and in older files
+    pub plaintext_mtime: Option<SystemTime>,
+    /// The passphrase wrapped to the recovery key (recovery.rs); None without one
+    pub recovery: Option<Vec<u8>>,
+}
+
+impl Header {
+    pub fn new(content_type: ContentType, compression: Compression) -> Self {
+        Self {
+            content_type,
+            compression,
+            file_name: None,
+            encrypted_at: None,
+            plaintext_sha256: None,
+            plaintext_hmac: None,
+            key_id: None,
+            pepper_id: None,
+            keyfile_id: None,
+            salt_label: None,
+            suite: Suite::default(),
+            passphrase_mac: false,
+            layer_mac: false,
+            plaintext_mtime: None,
+            recovery: None,
+        }
+    }
+
+    /// The same header, naming `file_name`
+    pub fn bound_to(&self, file_name: &str) -> Self {
+        Self { file_name: Some(file_name.to_string()), ..self.clone() }
+    }
+
+    /// The same header, stamped with the current time and, given a digest key, the HMAC of `plaintext`
+    pub fn stamped(&self, plaintext: &[u8], digest_key: Option<&SecretKey>) -> Self {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let plaintext_hmac = digest_key.map(|key| plaintext_hmac(key, plaintext));
+        Self { encrypted_at: Some(now), plaintext_sha256: None, plaintext_hmac, ..self.clone() }
+    }
+
+    /// The same header, naming the passphrase by its key ID (verify_token::salted_key_id; None outside a data
+    /// directory)
+    pub fn keyed(&self, key_id: Option<&str>) -> Self {
+        Self { key_id: key_id.map(str::to_string), ..self.clone() }
+    }
+
+    /// The same header, naming the external pepper it is sealed with (None: the compiled-in seed)
+    pub fn peppered(&self, pepper_id: Option<&str>) -> Self {
+        Self { pepper_id: pepper_id.map(str::to_string), ..self.clone() }
+    }
+
+    /// The same header, naming the keyfile paired with the passphrase (None: no keyfile)
+    pub fn with_keyfile(&self, keyfile_id: Option<&str>) -> Self {
+        Self { keyfile_id: keyfile_id.map(str::to_string), ..self.clone() }
+    }
+
+    /// The same header, recording the salt string the layers are sealed under
+    pub fn with_salt_label(&self, salt_label: &str) -> Self {
+        Self { salt_label: Some(salt_label.to_string()), ..self.clone() }
+    }
+
+    /// The same header, sealing the passphrase layers with `suite`
+    pub fn with_suite(&self, suite: Suite) -> Self {
+        Self { suite, ..self.clone() }
+    }
+
+    /// The same header, recording whether the HMAC is keyed from the outer layer key
+    pub fn with_layer_mac(&self, layer_mac: bool) -> Self {
+        Self { layer_mac, ..self.clone() }
+    }
+
+    /// The same header, recording the plaintext file's modification time (None: not from a file)
+    pub fn with_plaintext_mtime(&self, plaintext_mtime: Option<SystemTime>) -> Self {
+        Self { plaintext_mtime, ..self.clone() }
+    }
+
+    /// The same header, carrying a recovery entry (None: no recovery key)
+    pub fn with_recovery(&self, recovery: Option<Vec<u8>>) -> Self {
+        Self { recovery, ..self.clone() }
+    }
+
+    /// Whether `plaintext` is what was encrypted; None when the header records no digest,
+    /// or an HMAC and no `digest_key` is given
+    pub fn matches_plaintext(&self, plaintext: &[u8], digest_key: Option<&SecretKey>) -> Option<bool> {
+        match (self.plaintext_hmac, digest_key) {
+            (Some(hmac), Some(key)) => Some(hmac == plaintext_hmac(key, plaintext)),
+            (Some(_), None) => None,
+            (None, _) => self.plaintext_sha256.map(|digest| digest == <[u8; 32]>::from(Sha256::digest(plaintext))),
+        }
+    }
+
+    /// Serialize including the leading length prefix
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        push_entry(&mut body, TAG_CONTENT_TYPE, self.content_type.as_str().as_bytes());
+        if self.compression != Compression::None {
+            push_entry(&mut body, TAG_COMPRESSION, &[self.compression.byte()]);
+        }
+        if let Some(name) = &self.file_name {
+            push_entry(&mut body, TAG_FILE_NAME, name.as_bytes());
+        }
+        if let Some(at) = self.encrypted_at {
+            push_entry(&mut body, TAG_ENCRYPTED_AT, &at.to_be_bytes());
+        }
+        if let Some(digest) = &self.plaintext_sha256 {
+            push_entry(&mut body, TAG_PLAINTEXT_SHA256, digest);
+        }
+        if let Some(hmac) = &self.plaintext_hmac {
+            push_entry(&mut body, TAG_PLAINTEXT_HMAC, hmac);
+        }
+        if let Some(id) = self.key_id.as_deref().and_then(|id| hex::decode(id).ok()) {
+            push_entry(&mut body, TAG_KEY_ID, &id);
+        }
+        if let Some(id) = self.pepper_id.as_deref().and_then(|id| hex::decode(id).ok()) {
+            push_entry(&mut body, TAG_PEPPER_ID, &id);
+        }
+        if let Some(id) = self.keyfile_id.as_deref().and_then(|id| hex::decode(id).ok()) {
+            push_entry(&mut body, TAG_KEYFILE_ID, &id);
+        }
+        if let Some(salt) = &self.salt_label {
+            push_entry(&mut body, TAG_SALT_LABEL, salt.as_bytes());
+        }
+        if self.suite != Suite::default() {
+            push_entry(&mut body, TAG_SUITE, &[self.suite.byte()]);
+        }
+        if self.passphrase_mac {
+            push_entry(&mut body, TAG_PASSPHRASE_MAC, &[]);
+        }
+        if self.layer_mac {
+            push_entry(&mut body, TAG_LAYER_MAC, &[]);
+        }
+        // Seconds (u64 BE) then nanoseconds (u32 BE) since the epoch; earlier times are not recorded
+        if let Some(since) = self.plaintext_mtime.and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok()) {
+            let value = [since.as_secs().to_be_bytes().as_slice(), &since.subsec_nanos().to_be_bytes()].concat();
+            push_entry(&mut body, TAG_PLAINTEXT_MTIME, &value);
+        }
+        if let Some(entry) = &self.recovery {
+            push_entry(&mut body, TAG_RECOVERY, entry);
+        }
+
+        let mut out = Vec::with_capacity(2 + body.len());
+        out.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        out.extend_from_slice(&body);
+        out
+    }
+
+    /// Parse a header from the start of `data`; returns it with its encoded length
+    pub fn parse(data: &[u8]) -> Result<(Self, usize)> {
+        let len_bytes = data.get(..2).context("v5 header truncated")?;
+        let body_len = u16::from_be_bytes([len_bytes[0], len_bytes[1]]) as usize;
+        let mut body = data.get(2..2 + body_len).context("v5 header truncated")?;
+
+        let mut content_type = None;
+        let mut compression = Compression::None;
+        let mut file_name = None;
+        let mut encrypted_at = None;
+        let mut plaintext_sha256 = None;
+        let mut plaintext_hmac = None;
+        let mut key_id = None;
+        let mut pepper_id = None;
+        let mut keyfile_id = None;
+        let mut salt_label = None;
+        let mut suite = Suite::default();
+        let mut passphrase_mac = false;
+        let mut layer_mac = false;
+        let mut plaintext_mtime = None;
+        let mut recovery = None;
+        while !body.is_empty() {
+            if body.len() < 3 {
+                bail!("v5 header entry truncated");
+            }
+            let tag = body[0];
+            let len = u16::from_be_bytes([body[1], body[2]]) as usize;
+            let value = body.get(3..3 + len).context("v5 header entry truncated")?;
+            if tag == TAG_CONTENT_TYPE {
+                let label = std::str::from_utf8(value).context("v5 content type is not UTF-8")?;
+                content_type = Some(ContentType::parse(label)?);
+            } else if tag == TAG_COMPRESSION {
+                let &[byte] = value else {
+                    bail!("v5 compression entry must be one byte");
+                };
+                compression = Compression::from_byte(byte)?;
+            } else if tag == TAG_FILE_NAME {
+                let name = std::str::from_utf8(value).context("v5 file name is not UTF-8")?;
+                file_name = Some(name.to_string());
+            } else if tag == TAG_ENCRYPTED_AT {
+                let seconds: [u8; 8] = value.try_into().map_err(|_| anyhow::anyhow!("v5 timestamp must be 8 bytes"))?;
+                encrypted_at = Some(u64::from_be_bytes(seconds));
+            } else if tag == TAG_PLAINTEXT_SHA256 {
+                let digest = value.try_into().map_err(|_| anyhow::anyhow!("v5 plaintext digest must be 32 bytes"))?;
+                plaintext_sha256 = Some(digest);
+            } else if tag == TAG_PLAINTEXT_HMAC {
+                let hmac = value.try_into().map_err(|_| anyhow::anyhow!("v5 plaintext HMAC must be 32 bytes"))?;
+                plaintext_hmac = Some(hmac);
+            } else if tag == TAG_KEY_ID {
+                if value.len() != KEY_ID_LEN {
+                    bail!("v5 key ID must be {} bytes", KEY_ID_LEN);
+                }
+                key_id = Some(hex::encode(value));
+            } else if tag == TAG_PEPPER_ID {
+                if value.len() != PEPPER_ID_LEN {
+                    bail!("v5 pepper ID must be {} bytes", PEPPER_ID_LEN);
+                }
+                pepper_id = Some(hex::encode(value));
+            } else if tag == TAG_KEYFILE_ID {
+                if value.len() != KEYFILE_ID_LEN {
+                    bail!("v5 keyfile ID must be {} bytes", KEYFILE_ID_LEN);
+                }
+                keyfile_id = Some(hex::encode(value));
+            } else if tag == TAG_SALT_LABEL {
+                let salt = std::str::from_utf8(value).context("v5 salt label is not UTF-8")?;
+                salt_label = Some(salt.to_string());
+            } else if tag == TAG_SUITE {
+                let &[byte] = value else {
+                    bail!("v5 cipher suite entry must be one byte");
+                };
+                suite = Suite::from_byte(byte)?;
+            } else if tag == TAG_PASSPHRASE_MAC {
+                if !value.is_empty() {
+                    bail!("v5 passphrase MAC entry must be empty");
+                }
+                passphrase_mac = true;
+            } else if tag == TAG_LAYER_MAC {
+                if !value.is_empty() {
+                    bail!("v5 layer MAC entry must be empty");
+                }
+                layer_mac = true;
+            } else if tag == TAG_PLAINTEXT_MTIME {
+                if value.len() != 12 {
+                    bail!("v5 plaintext mtime must be 12 bytes");
+                }
+                let seconds = u64::from_be_bytes(value[..8].try_into()?);
+                let nanos = u32::from_be_bytes(value[8..].try_into()?);
+                if nanos >= 1_000_000_000 {
+                    bail!("v5 plaintext mtime has {} nanoseconds", nanos);
+                }
+                let since = Duration::new(seconds, nanos);
+                plaintext_mtime = Some(UNIX_EPOCH.checked_add(since).context("v5 plaintext mtime out of range")?);
+            } else if tag == TAG_RECOVERY {
+                if value.len() < recovery::MIN_ENTRY_LEN {
+                    bail!("v5 recovery entry must be at least {} bytes", recovery::MIN_ENTRY_LEN);
+                }
+                recovery = Some(value.to_vec());
+            }
+            body = &body[3 + len..];
+        }
+
+        let content_type = content_type.context("v5 header has no content type")?;
+        // Only writers of the layer-keyed HMAC salt their key ID
+        let key_id = key_id.filter(|_| layer_mac);
+        let header = Self {
+            content_type,
+            compression,
+            file_name,
+            encrypted_at,
+            plaintext_sha256,
+            plaintext_hmac,
+            key_id,
+            pepper_id,
+            keyfile_id,
+            salt_label,
+            suite,
+            passphrase_mac,
+            layer_mac,
+            plaintext_mtime,
+            recovery,
+        };
+        Ok((header, 2 + body_len))
+    }
+}
+
+fn plaintext_hmac(key: &SecretKey, plaintext: &[u8]) -> [u8; 32] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&**key).expect("HMAC init");
+    mac.update(plaintext);
+    mac.finalize().into_bytes().into()
+}
+
+fn push_entry(out: &mut Vec<u8>, tag: u8, value: &[u8]) {
+    out.push(tag);
+    out.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    out.extend_from_slice(value);
+}
@@ -0,0 +1,172 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Git hooks — `install-hooks` wires check-staged and decrypt-local into a repository
+//
+// pre-commit runs `check-staged` and blocks the commit when plaintext soul
+// data is staged; post-checkout (of a branch, not of single files) and
+// post-merge run `decrypt-local --keep-modified`, so the working copy follows
+// the .enc files a branch switch or pull brought in. A .json that holds the
+// plaintext of neither the new .enc nor the one of the revision checked out
+// before (`--previous`) holds edits not yet encrypted: it is warned about and kept
+// (a failure there is reported but cannot undo the checkout). Hooks go where
+// git looks for them (`git rev-parse --git-path hooks`, so core.hooksPath
+// and worktrees are honoured) and call this binary by its absolute path.
+//
+// Each hook holds one marked block, placed right after the shebang so an
+// `exit 0` further down an existing hook cannot skip it. Installing again
+// replaces the block (a moved binary or new --data-dir is picked up), and
+// `--uninstall` removes it, deleting hooks that held nothing else.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use tracing::info;
+
+
+const BEGIN: &str = "# >>> violet-cipher >>>";
+const END: &str = "# <<< violet-cipher <<<";
+
+#[derive(Args)]
+pub struct HookArgs {
+    /// Repository to install into (default: the one containing the current directory)
+    #[arg(long)]
+    repo: Option<PathBuf>,
+    /// Data directory the hooks pass to check-staged and decrypt-local
+    #[arg(long)]
+    data_dir: Option<PathBuf>,
+    /// Remove the violet-cipher block from each hook instead
+    #[arg(long, conflicts_with = "data_dir")]
+    uninstall: bool,
+}
+
+/// Hook name and the commands of its block, given the quoted binary and its `--data-dir` argument
+fn hooks(bin: &str, dir: &str) -> [(&'static str, String); 3] {
+    let decrypt = |previous: &str| {
+        format!(
+            "{bin} decrypt-local --keep-modified --previous {previous}{dir} \
+             || echo \"violet-cipher: decrypt-local failed; run it by hand\" >&2"
+        )
+    };
+    [
+        ("pre-commit", format!("{bin} check-staged{dir} || exit $?")),
+        // $1 is the HEAD checked out before; $3 is 1 for a branch checkout, 0 for `git checkout -- <path>`
+        ("post-checkout", format!("if [ \"$3\" = 1 ]; then\n    {}\nfi", decrypt("\"$1\""))),
+        ("post-merge", decrypt("ORIG_HEAD")),
+    ]
+}
+
+pub fn run(args: HookArgs) -> Result<()> {
+    let repo = args.repo.unwrap_or_else(|| PathBuf::from("."));
+    let hooks_dir = hooks_dir(&repo)?;
+    let bin = std::env::current_exe().context("locate violet-cipher")?;
+    let dir = match &args.data_dir {
+        Some(dir) => format!(" --data-dir {}", quote(&std::path::absolute(dir)?)),
+        None => String::new(),
+    };
+
+    for (name, commands) in hooks(&quote(&bin), &dir) {
+        let path = hooks_dir.join(name);
+        let existing = match fs::read_to_string(&path) {
+            Ok(text) => Some(text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e).with_context(|| format!("read {:?}", path)),
+        };
+        if args.uninstall {
+            match existing.as_deref().map(without_block) {
+                Some(Some(rest)) if rest.lines().all(|l| l.trim().is_empty() || l.starts_with("#!")) => {
+                    fs::remove_file(&path).with_context(|| format!("remove {:?}", path))?;
+                    info!("  🗑️  {} removed", name);
+                }
+                Some(Some(rest)) => {
+                    fs::write(&path, rest).with_context(|| format!("write {:?}", path))?;
+                    info!("  ✂️  {}: violet-cipher block removed, the rest kept", name);
+                }
+                _ => info!("  ⏭️  {}: not installed", name),
+            }
+            continue;
+        }
+
+        let block = format!("{}\n{}\n{}\n", BEGIN, commands, END);
+        let (text, verb) = match existing.as_deref() {
+            None => (format!("#!/bin/sh\n{}", block), "installed"),
+            Some(text) => match without_block(text) {
+                Some(rest) => (with_block(&rest, &block, &path)?, "updated"),
+                None => (with_block(text, &block, &path)?, "added to the existing hook"),
+            },
+        };
+        if existing.as_deref() == Some(text.as_str()) {
+            info!("  ✅ {}: already up to date", name);
+            continue;
+        }
+        fs::create_dir_all(&hooks_dir).with_context(|| format!("create {:?}", hooks_dir))?;
+        fs::write(&path, text).with_context(|| format!("write {:?}", path))?;
+        make_executable(&path)?;
+        info!("  ✅ {}: {}", name, verb);
+    }
+    let done = if args.uninstall { "removed from" } else { "installed in" };
+    info!("🪝 Git hooks {} {:?}", done, hooks_dir);
+    Ok(())
+}
+
+/// Where git reads hooks for `repo`
+fn hooks_dir(repo: &Path) -> Result<PathBuf> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["rev-parse", "--path-format=absolute", "--git-path", "hooks"])
+        .output()
+        .context("run git")?;
+    if !output.status.success() {
+        bail!("{:?} is not a git repository: {}", repo, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+/// `enc_name` in `data_dir` as it was at git revision `rev`; None when it was not there
+pub fn enc_at_revision(data_dir: &Path, rev: &str, enc_name: &str) -> Option<Vec<u8>> {
+    let output = Command::new("git").arg("-C").arg(data_dir).arg("show").arg(format!("{}:./{}", rev, enc_name)).output();
+    match output {
+        Ok(output) if output.status.success() => Some(output.stdout),
+        _ => None,
+    }
+}
+
+/// `text` without its violet-cipher block; None when it has none
+fn without_block(text: &str) -> Option<String> {
+    let start = text.find(BEGIN)?;
+    let end = text[start..].find(END)? + start + END.len();
+    let end = if text[end..].starts_with('\n') { end + 1 } else { end };
+    Some(format!("{}{}", &text[..start], &text[end..]))
+}
+
+/// `block` placed right after the shebang of a shell hook
+fn with_block(text: &str, block: &str, path: &Path) -> Result<String> {
+    let (shebang, body) = match text.split_once('\n') {
+        Some((first, rest)) if first.starts_with("#!") => (first, rest),
+        None if text.starts_with("#!") => (text, ""),
+        _ => ("#!/bin/sh", text),
+    };
+    if !shebang.split_whitespace().last().is_some_and(|interpreter| interpreter.ends_with("sh")) {
+        bail!("{:?} is not a shell script ({}); add `violet-cipher` to it by hand", path, shebang);
+    }
+    Ok(format!("{}\n{}{}", shebang, block, body))
+}
+
+/// `path` in single quotes for sh
+fn quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', r"'\''"))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o755);
+    fs::set_permissions(path, permissions).with_context(|| format!("chmod {:?}", path))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
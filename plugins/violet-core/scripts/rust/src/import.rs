@@ -0,0 +1,93 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Import — re-encrypt another tool's ciphertext as v4 without a plaintext copy on disk
+//
+// `import --from age --age-identity key.txt secrets.json.age` opens the age
+// file (binary or ASCII-armored) with the X25519 identities of an age key
+// file, then seals the plaintext exactly as `encrypt-file` would, to
+// secrets.json.enc next to it unless --output says otherwise. The plaintext
+// only ever lives in zeroized memory. Passphrase-encrypted age files are not
+// taken: decrypt those with age itself. (--identity keeps its meaning from
+// every other command, the `keygen` identity standing in for a passphrase.)
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use age::armor::ArmoredReader;
+use age::{Decryptor, IdentityFile};
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+use tracing::info;
+use zeroize::Zeroizing;
+
+use crate::kdf::KdfArgs;
+use crate::key_source::KeyArgs;
+use crate::salt::SaltArgs;
+use crate::{logical_name, v4_encrypt, write_atomic, Binding};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// age v1 (age-encryption.org/v1), binary or armored
+    Age,
+}
+
+#[derive(Args)]
+pub struct ImportArgs {
+    #[command(flatten)]
+    key: KeyArgs,
+    #[command(flatten)]
+    kdf: KdfArgs,
+    /// Format of the file to import
+    #[arg(long, value_enum)]
+    from: ImportFormat,
+    /// age identity file (AGE-SECRET-KEY-1… lines, as age-keygen writes them)
+    #[arg(long)]
+    age_identity: PathBuf,
+    /// File to import
+    pub file: PathBuf,
+    /// Where to write the v4 file (default: the input less .age, plus .enc); it is bound to this name, less any .enc
+    #[arg(long)]
+    output: Option<PathBuf>,
+    #[command(flatten)]
+    salt: SaltArgs,
+}
+
+pub fn run(args: ImportArgs) -> Result<()> {
+    let output = args.output.clone().unwrap_or_else(|| default_output(&args.file));
+    if output.exists() {
+        bail!("{:?} exists — move it aside or pass another --output", output);
+    }
+    let plaintext = match args.from {
+        ImportFormat::Age => open_age(&args.file, &args.age_identity)?,
+    };
+    let key = args.key.resolve()?;
+    let (salt_label, name) = (args.salt.for_writing()?, logical_name(&output));
+    let encrypted = v4_encrypt(&key, Binding::new(&salt_label, &name), &plaintext, &args.kdf.params()?)?;
+    write_atomic(&output, &encrypted).with_context(|| format!("write {:?}", output))?;
+    info!("📥 {:?} (age) → {:?} (v4, {} bytes)", args.file, output, encrypted.len());
+    Ok(())
+}
+
+/// `secrets.json.age` → `secrets.json.enc`
+fn default_output(file: &Path) -> PathBuf {
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    file.with_file_name(format!("{}.enc", name.strip_suffix(".age").unwrap_or(&name)))
+}
+
+fn open_age(file: &Path, identity: &Path) -> Result<Zeroizing<Vec<u8>>> {
+    let identities = IdentityFile::from_file(identity.display().to_string())
+        .with_context(|| format!("read age identity file {:?}", identity))?
+        .into_identities()
+        .with_context(|| format!("{:?} holds no usable age identity", identity))?;
+    let data = fs::read(file).with_context(|| format!("read {:?}", file))?;
+    let decryptor = Decryptor::new_buffered(ArmoredReader::new(data.as_slice()))
+        .with_context(|| format!("{:?} is not an age file", file))?;
+    if decryptor.is_scrypt() {
+        bail!("{:?} is passphrase-encrypted — decrypt it with age, then use encrypt-file", file);
+    }
+    let mut reader = decryptor
+        .decrypt(identities.iter().map(|identity| identity.as_ref() as &dyn age::Identity))
+        .with_context(|| format!("{:?} does not open with the identities in {:?}", file, identity))?;
+    let mut plaintext = Zeroizing::new(Vec::new());
+    reader.read_to_end(&mut plaintext).with_context(|| format!("{:?}: age payload is damaged", file))?;
+    Ok(plaintext)
+}
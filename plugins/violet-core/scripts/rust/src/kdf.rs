@@ -0,0 +1,124 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Tunable Argon2id costs and on-machine KDF calibration
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use clap::Args;
+
+/// scrypt block size and parallelism used by the legacy v2/v3 formats
+pub const SCRYPT_R: u32 = 8;
+pub const SCRYPT_P: u32 = 1;
+pub const SCRYPT_LEGACY_LOG_N: u8 = 14;
+const SCRYPT_MAX_LOG_N: u8 = 20;
+/// Largest Argon2id costs written or read; a preamble asking for more is refused
+/// before any key is derived, so a crafted file cannot exhaust memory or time
+pub const MAX_MEMORY_KIB: u32 = 2 * 1024 * 1024;
+pub const MAX_ITERATIONS: u32 = 64;
+pub const MAX_PARALLELISM: u32 = 64;
+
+#[derive(Args)]
+pub struct KdfArgs {
+    /// Argon2id memory per layer in KiB (files without a VSC! header decrypt only with the value used to encrypt)
+    #[arg(long, default_value_t = Params::DEFAULT_M_COST)]
+    kdf_memory: u32,
+    /// Argon2id iterations per layer
+    #[arg(long, default_value_t = Params::DEFAULT_T_COST)]
+    kdf_iterations: u32,
+    /// Argon2id lanes per layer
+    #[arg(long, default_value_t = Params::DEFAULT_P_COST)]
+    kdf_parallelism: u32,
+}
+
+impl KdfArgs {
+    pub fn params(&self) -> Result<Params> {
+        bounded_params(self.kdf_memory, self.kdf_iterations, self.kdf_parallelism)
+    }
+}
+
+/// Argon2id parameters, refused above `MAX_MEMORY_KIB`, `MAX_ITERATIONS` or `MAX_PARALLELISM`
+pub fn bounded_params(memory_kib: u32, iterations: u32, parallelism: u32) -> Result<Params> {
+    if memory_kib > MAX_MEMORY_KIB || iterations > MAX_ITERATIONS || parallelism > MAX_PARALLELISM {
+        bail!(
+            "Argon2id costs m={} KiB, t={}, p={} exceed the limits ({} KiB, {}, {})",
+            memory_kib,
+            iterations,
+            parallelism,
+            MAX_MEMORY_KIB,
+            MAX_ITERATIONS,
+            MAX_PARALLELISM
+        );
+    }
+    Params::new(memory_kib, iterations, parallelism, None)
+        .map_err(|e| anyhow::anyhow!("invalid Argon2id parameters: {}", e))
+}
+
+pub fn argon2id(params: &Params) -> Argon2<'static> {
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone())
+}
+
+/// The `--kdf-*` flags that reproduce `params`
+pub fn as_flags(params: &Params) -> String {
+    format!(
+        "--kdf-memory {} --kdf-iterations {} --kdf-parallelism {}",
+        params.m_cost(),
+        params.t_cost(),
+        params.p_cost()
+    )
+}
+
+/// Wall-clock time of one Argon2id derivation with `params`
+pub fn time_argon2(params: &Params) -> Result<Duration> {
+    let mut out = [0u8; 32];
+    let start = Instant::now();
+    argon2id(params)
+        .hash_password_into(b"violet-bench-passphrase", b"violet-bench-salt-2026", &mut out)
+        .map_err(|e| anyhow::anyhow!("Argon2id KDF failed: {}", e))?;
+    Ok(start.elapsed())
+}
+
+/// Strongest Argon2id costs that fit `target` per layer
+///
+/// Memory is spent first (up to `max_memory_kib`), then iterations fill the
+/// remaining time. Never recommends less than the built-in defaults, nor more
+/// than files may declare.
+pub fn calibrate_argon2(target: Duration, max_memory_kib: u32) -> Result<(Params, Duration)> {
+    let mut memory = max_memory_kib.clamp(Params::DEFAULT_M_COST, MAX_MEMORY_KIB);
+    loop {
+        let single_pass = time_argon2(&Params::new(memory, 1, 1, None).map_err(anyhow::Error::msg)?)?;
+        if single_pass <= target || memory == Params::DEFAULT_M_COST {
+            let fitting = (target.as_secs_f64() / single_pass.as_secs_f64()).floor().max(1.0) as u32;
+            let mut iterations = fitting.min(MAX_ITERATIONS);
+            if memory == Params::DEFAULT_M_COST {
+                iterations = iterations.max(Params::DEFAULT_T_COST);
+            }
+            let params = Params::new(memory, iterations, 1, None).map_err(anyhow::Error::msg)?;
+            let measured = time_argon2(&params)?;
+            return Ok((params, measured));
+        }
+        memory = (memory / 2).max(Params::DEFAULT_M_COST);
+    }
+}
+
+/// Wall-clock time of one scrypt derivation at cost `2^log_n`
+pub fn time_scrypt(log_n: u8) -> Result<Duration> {
+    let params = scrypt::Params::new(log_n, SCRYPT_R, SCRYPT_P, 32)
+        .map_err(|e| anyhow::anyhow!("scrypt params: {}", e))?;
+    let mut out = [0u8; 32];
+    let start = Instant::now();
+    scrypt::scrypt(b"violet-bench-passphrase", b"violet-bench-salt-2026", &params, &mut out)
+        .map_err(|e| anyhow::anyhow!("scrypt KDF failed: {}", e))?;
+    Ok(start.elapsed())
+}
+
+/// Largest scrypt `log_n` (at least the legacy 14) whose time fits `target`
+pub fn calibrate_scrypt(target: Duration) -> Result<(u8, Duration)> {
+    let mut log_n = SCRYPT_LEGACY_LOG_N;
+    let mut elapsed = time_scrypt(log_n)?;
+    // Each step doubles the cost; stop before the prediction overshoots
+    while log_n < SCRYPT_MAX_LOG_N && elapsed * 2 <= target {
+        log_n += 1;
+        elapsed = time_scrypt(log_n)?;
+    }
+    Ok((log_n, elapsed))
+}
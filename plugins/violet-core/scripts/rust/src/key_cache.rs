@@ -0,0 +1,104 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Key cache — each Argon2id key derived once per run (or per agent session)
+//
+// A key is a pure function of its KDF input, salt and costs, so `main` keeps
+// every one it derives until the command ends: the key ID behind verify
+// tokens and the keyring, a file opened twice by `verify --deep`, or files
+// that share salts cost one derivation each. Workers asking for the same key
+// at once wait for the first one's result instead of deriving it again.
+// Cached keys are locked secrets like any other and are wiped by `end`.
+//
+// Salts are only shared on request: `encrypt-local --fast`, `serve --fast`
+// and the unlock agent draw each layer's salt once per salt label and seal
+// every file with it, so a whole run costs three derivations instead of three
+// per file (nonces stay random per file). Those files share their layer keys:
+// anyone who learns one file's keys, not the passphrase, can open the others,
+// and opening them later costs three derivations for the lot as well. Runs
+// and sessions without `--fast` draw fresh salts for every file and layer.
+//
+// Outside the CLI (the C API, WebAssembly) no run is begun and every
+// derivation is computed afresh.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use argon2::Params as KdfParams;
+use sha2::{Digest, Sha256};
+
+use crate::secret::SecretKey;
+use crate::ARGON2_SALT_LEN;
+
+type Slot = Arc<Mutex<Option<SecretKey>>>;
+
+struct Cache {
+    /// SHA-256 of KDF input, salt and costs → derived key, once the first caller has it
+    keys: HashMap<[u8; 32], Slot>,
+    /// (layer, salt label) → the salt every file sealed this run uses; None unless shared
+    salts: Option<HashMap<(&'static str, String), [u8; ARGON2_SALT_LEN]>>,
+}
+
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Start caching keys for this run
+pub fn begin() {
+    *lock(&CACHE) = Some(Cache { keys: HashMap::new(), salts: None });
+}
+
+/// Seal every file of the rest of the run with one salt per layer and salt label
+pub fn share_salts() {
+    if let Some(cache) = lock(&CACHE).as_mut() {
+        cache.salts.get_or_insert_with(HashMap::new);
+    }
+}
+
+/// Wipe every cached key and salt; nothing is cached after this
+pub fn end() {
+    *lock(&CACHE) = None;
+}
+
+fn cache_id(input: &[u8], salt: &[u8], params: &KdfParams) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(input)
+        .chain_update(salt)
+        .chain_update(params.m_cost().to_le_bytes())
+        .chain_update(params.t_cost().to_le_bytes())
+        .chain_update(params.p_cost().to_le_bytes())
+        .finalize()
+        .into()
+}
+
+fn copy(key: &SecretKey) -> SecretKey {
+    let mut copy = SecretKey::zeroed();
+    copy.copy_from_slice(&**key);
+    copy
+}
+
+/// The key for this KDF input, salt and costs: cached, or made by `derive` and cached
+pub fn derive<E>(
+    input: &[u8],
+    salt: &[u8],
+    params: &KdfParams,
+    derive: impl FnOnce() -> Result<SecretKey, E>,
+) -> Result<SecretKey, E> {
+    let slot = match lock(&CACHE).as_mut() {
+        Some(cache) => Slot::clone(cache.keys.entry(cache_id(input, salt, params)).or_default()),
+        None => return derive(),
+    };
+    let mut slot = lock(&slot);
+    if let Some(key) = slot.as_ref() {
+        return Ok(copy(key));
+    }
+    let key = derive()?;
+    *slot = Some(copy(&key));
+    Ok(key)
+}
+
+/// The salt `layer` is sealed with under `salt_label`, when salts are shared
+pub fn layer_salt(layer: &'static str, salt_label: &str) -> Option<[u8; ARGON2_SALT_LEN]> {
+    let mut cache = lock(&CACHE);
+    let salts = cache.as_mut()?.salts.as_mut()?;
+    Some(*salts.entry((layer, salt_label.to_string())).or_insert_with(crate::random_bytes))
+}
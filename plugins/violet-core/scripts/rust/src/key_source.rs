@@ -0,0 +1,241 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Passphrase sources — argv/env, key file, or inherited file descriptor
+//
+// Flags and VIOLET_SOUL_KEY come first, then an env file, then the sealed
+// key and the agent (see config.rs for the whole order).
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use argon2::Params as KdfParams;
+use clap::Args;
+use zeroize::Zeroizing;
+
+use crate::agent::Client;
+use crate::config;
+use crate::header::Header;
+use crate::secret::SecretKey;
+use crate::{decrypt_payload, v5_encrypt, verify_token, Binding};
+
+/// Passphrase bytes, wiped from memory on drop
+pub type Passphrase = Zeroizing<Vec<u8>>;
+
+/// Who turns the passphrase into keys: this process, or a running agent
+/// (or, with --kms, a cloud KMS key instead of any passphrase)
+pub enum Keyholder {
+    Passphrase(Passphrase),
+    Agent(Client),
+    #[cfg(feature = "kms")]
+    Kms,
+}
+
+impl Keyholder {
+    /// Seal `plaintext` as v5
+    pub fn seal(&self, binding: Binding, plaintext: &[u8], header: &Header, kdf: &KdfParams) -> Result<Vec<u8>> {
+        match self {
+            Keyholder::Passphrase(key) => v5_encrypt(key, binding, plaintext, header, kdf),
+            Keyholder::Agent(agent) => agent.seal(binding, plaintext, header, kdf),
+            #[cfg(feature = "kms")]
+            Keyholder::Kms => bail!("--kms only decrypts; encrypt to a KMS key with --kms-key-id"),
+        }
+    }
+
+    /// Open any format `decrypt_payload` reads; from an agent the header carries only the content type
+    pub fn open(&self, binding: Binding, data: &[u8], kdf: &KdfParams) -> Result<(Header, Vec<u8>)> {
+        match self {
+            Keyholder::Passphrase(key) => decrypt_payload(key, binding, data, kdf),
+            Keyholder::Agent(agent) => agent.open(binding, data, kdf),
+            #[cfg(feature = "kms")]
+            Keyholder::Kms => {
+                let (header, plain) = crate::recipient::decrypt_kms(binding.salt_label, data)?;
+                crate::check_bound_name(&header, binding)?;
+                Ok((header, plain))
+            }
+        }
+    }
+
+    /// Key of the plaintext HMAC in v5 headers (verify_token::digest_key); None through
+    /// an agent or KMS, whose callers open the file to compare instead
+    pub fn digest_key(&self) -> Result<Option<SecretKey>> {
+        match self {
+            Keyholder::Passphrase(key) => verify_token::digest_key(key),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn key_id(&self) -> Result<String> {
+        match self {
+            Keyholder::Passphrase(key) => verify_token::derive_key_id(key),
+            Keyholder::Agent(agent) => agent.key_id(),
+            #[cfg(feature = "kms")]
+            Keyholder::Kms => bail!("--kms uses no passphrase, so there is no key ID"),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct KeyArgs {
+    /// Passphrase (visible in argv/env — prefer --key-file or --key-fd in CI)
+    #[arg(long, env = "VIOLET_SOUL_KEY", hide_env_values = true)]
+    key: Option<String>,
+    /// Read the passphrase from a file (trailing newlines are stripped)
+    #[arg(long, conflicts_with = "key_fd")]
+    key_file: Option<PathBuf>,
+    /// Read the passphrase from an inherited file descriptor until EOF
+    #[arg(long)]
+    key_fd: Option<i32>,
+    /// X25519 identity from `keygen`, for files encrypted with --recipient
+    #[arg(long, conflicts_with_all = ["key_file", "key_fd"])]
+    identity: Option<PathBuf>,
+    /// Passphrase sealed to this machine's TPM by `key seal`
+    #[arg(long, env = "VIOLET_SEALED_KEY", conflicts_with_all = ["key_file", "key_fd", "identity"])]
+    sealed_key: Option<PathBuf>,
+    /// Socket of a running `agent start`, used when no passphrase is given (encrypt/decrypt-local, decrypt-file, assets)
+    #[arg(long, env = "VIOLET_AGENT_SOCK")]
+    agent: Option<PathBuf>,
+    /// Decrypt v6 files through their --kms-key-id recipient with this machine's cloud credentials
+    #[cfg(feature = "kms")]
+    #[arg(long, conflicts_with_all = ["key", "key_file", "key_fd", "identity"])]
+    kms: bool,
+}
+
+impl KeyArgs {
+    /// Resolve the passphrase: --key-fd / --key-file / --identity take precedence over --key and the
+    /// env var, then come the env file and the sealed key
+    ///
+    /// An identity file is returned whole; v6 decryption picks the key line out of it.
+    pub fn resolve(self) -> Result<Passphrase> {
+        let key_file = self.key_file.or(self.identity);
+        if self.key.is_none() && key_file.is_none() && self.key_fd.is_none() {
+            if let Some((key, _)) = config::env_file_value(config::SOUL_KEY_VAR)? {
+                return resolve_passphrase(Some(key.to_string()), None, None);
+            }
+            if let Some(path) = &self.sealed_key {
+                return crate::sealed_key::unseal(path);
+            }
+            if self.agent.is_some() {
+                bail!("no passphrase — this command needs it directly; the agent only serves encrypting and decrypting");
+            }
+            bail!("no passphrase — pass --key, --key-file, --key-fd, --identity, --sealed-key or set VIOLET_SOUL_KEY");
+        }
+        resolve_passphrase(self.key, key_file.as_deref(), self.key_fd)
+    }
+
+    /// Whether any passphrase source was given
+    fn has_source(&self) -> bool {
+        self.key.is_some()
+            || self.key_file.is_some()
+            || self.key_fd.is_some()
+            || self.identity.is_some()
+            || self.sealed_key.is_some()
+            || config::env_file_value(config::SOUL_KEY_VAR).is_ok_and(|value| value.is_some())
+    }
+
+    /// Where `resolve` / `holder` would take the passphrase from, for `config show`
+    pub fn source(&self) -> Result<String> {
+        let from_env = self.key.is_some() && std::env::var(config::SOUL_KEY_VAR).ok() == self.key;
+        Ok(if let Some(fd) = self.key_fd {
+            format!("flag (--key-fd {})", fd)
+        } else if let Some(path) = &self.key_file {
+            format!("flag (--key-file {})", path.display())
+        } else if let Some(path) = &self.identity {
+            format!("flag (--identity {})", path.display())
+        } else if from_env {
+            format!("environment ({})", config::SOUL_KEY_VAR)
+        } else if self.key.is_some() {
+            "flag (--key)".to_string()
+        } else if let Some((_, path)) = config::env_file_value(config::SOUL_KEY_VAR)? {
+            format!("env file {}", path.display())
+        } else if let Some(path) = &self.sealed_key {
+            format!("sealed key {}", path.display())
+        } else if let Some(socket) = &self.agent {
+            format!("unlock agent at {}", socket.display())
+        } else {
+            "none given".to_string()
+        })
+    }
+
+    /// The passphrase if one is given, else the agent on --agent / VIOLET_AGENT_SOCK
+    pub fn holder(mut self) -> Result<Keyholder> {
+        #[cfg(feature = "kms")]
+        if self.kms {
+            return Ok(Keyholder::Kms);
+        }
+        if !self.has_source() {
+            if let Some(socket) = self.agent.take() {
+                return Ok(Keyholder::Agent(Client::new(socket)));
+            }
+        }
+        Ok(Keyholder::Passphrase(self.resolve()?))
+    }
+
+    /// The passphrase from any source given, else typed at the terminal
+    pub fn resolve_or_prompt(self, prompt: &str) -> Result<Passphrase> {
+        if self.has_source() {
+            return self.resolve();
+        }
+        let typed = Zeroizing::new(rpassword::prompt_password(prompt).context("read passphrase from the terminal")?);
+        let passphrase = Zeroizing::new(trim_line_endings(typed.as_bytes()).to_vec());
+        if passphrase.is_empty() {
+            bail!("passphrase is empty");
+        }
+        Ok(passphrase)
+    }
+}
+
+/// Resolve one passphrase from the first source present: fd, file, then literal
+pub fn resolve_passphrase(
+    key: Option<String>,
+    key_file: Option<&Path>,
+    key_fd: Option<i32>,
+) -> Result<Passphrase> {
+    let raw = if let Some(fd) = key_fd {
+        Zeroizing::new(read_fd(fd)?)
+    } else if let Some(path) = key_file {
+        Zeroizing::new(fs::read(path).with_context(|| format!("read key file {:?}", path))?)
+    } else if let Some(key) = key {
+        Zeroizing::new(key.into_bytes())
+    } else {
+        bail!("no passphrase source given");
+    };
+
+    let passphrase = Zeroizing::new(trim_line_endings(&raw).to_vec());
+    if passphrase.is_empty() {
+        bail!("passphrase is empty");
+    }
+    Ok(passphrase)
+}
+
+/// Strip trailing CR/LF only, so binary keys and meaningful spaces survive
+fn trim_line_endings(raw: &[u8]) -> &[u8] {
+    let end = raw
+        .iter()
+        .rposition(|&b| b != b'\n' && b != b'\r')
+        .map_or(0, |i| i + 1);
+    &raw[..end]
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<Vec<u8>> {
+    use std::io::Read;
+    use std::os::fd::FromRawFd;
+
+    if fd == 1 || fd == 2 {
+        bail!("--key-fd {} would consume stdout/stderr", fd);
+    }
+    if fd < 0 {
+        bail!("invalid file descriptor: {}", fd);
+    }
+    // SAFETY: the descriptor is handed to us by the parent process for this
+    // purpose; we take ownership and close it once the passphrase is read.
+    let mut file = unsafe { fs::File::from_raw_fd(fd) };
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)
+        .with_context(|| format!("read key from fd {}", fd))?;
+    Ok(buf)
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: i32) -> Result<Vec<u8>> {
+    bail!("--key-fd is only supported on Unix; use --key-file instead")
+}
@@ -0,0 +1,95 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Keyfile — something you have, mixed into the KDF input next to the passphrase
+//
+// `--keyfile` (or VIOLET_KEYFILE) names any file — random bytes, a photo, a
+// file on a USB stick — whose digest is appended to the passphrase before
+// Argon2id. v5 writers record the keyfile's ID in the header, and a reader
+// mixes it in only for files that name it, so opening such a file takes both
+// the passphrase and the keyfile, and files without one are unaffected. Not
+// to be confused with --key-file, which holds the passphrase itself.
+//
+// The keyfile key is SHA-256 over a context string and the file's exact
+// bytes; its ID is the first 8 bytes of a second, separately labelled digest.
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::header::Header;
+use crate::KEY_LEN;
+
+const KEYFILE_CONTEXT: &[u8] = b"violet-keyfile-v1\0";
+const KEYFILE_ID_CONTEXT: &[u8] = b"violet-keyfile-id-v1\0";
+pub const KEYFILE_ID_LEN: usize = 8;
+
+pub struct Keyfile {
+    key: Zeroizing<[u8; KEY_LEN]>,
+    /// Hex fingerprint recorded in headers
+    pub id: String,
+}
+
+static CONFIGURED: OnceLock<Keyfile> = OnceLock::new();
+
+thread_local! {
+    /// Keyfile key of the file being sealed or opened on this thread
+    static ACTIVE: RefCell<Option<[u8; KEY_LEN]>> = const { RefCell::new(None) };
+}
+
+impl Keyfile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = Zeroizing::new(fs::read(path).with_context(|| format!("read keyfile {:?}", path))?);
+        if bytes.is_empty() {
+            bail!("keyfile {:?} is empty", path);
+        }
+        Ok(Self::from_bytes(&bytes))
+    }
+
+    /// Keyfile made of `bytes`, e.g. as restored from a paper backup
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let key: [u8; KEY_LEN] = Sha256::new().chain_update(KEYFILE_CONTEXT).chain_update(bytes).finalize().into();
+        let id = Sha256::new().chain_update(KEYFILE_ID_CONTEXT).chain_update(key).finalize();
+        Self { key: Zeroizing::new(key), id: hex::encode(&id[..KEYFILE_ID_LEN]) }
+    }
+}
+
+/// Use `keyfile` for the rest of the run
+pub fn configure(keyfile: Keyfile) -> Result<()> {
+    if CONFIGURED.set(keyfile).is_err() {
+        bail!("keyfile already configured");
+    }
+    Ok(())
+}
+
+/// The keyfile from --keyfile, if one was given
+pub fn configured() -> Option<&'static Keyfile> {
+    CONFIGURED.get()
+}
+
+/// The keyfile a file with `header` was sealed with; None when it needs none
+pub fn for_header(header: &Header) -> Result<Option<&'static Keyfile>> {
+    let Some(needed) = header.keyfile_id.as_deref() else {
+        return Ok(None);
+    };
+    match configured() {
+        Some(keyfile) if keyfile.id == needed => Ok(Some(keyfile)),
+        Some(keyfile) => bail!("file was sealed with keyfile {}, but --keyfile is keyfile {}", needed, keyfile.id),
+        None => bail!("file was sealed with keyfile {} as well as the passphrase — pass --keyfile", needed),
+    }
+}
+
+/// Run `f` with `keyfile` mixed into every Argon2id input on this thread
+pub fn with<T>(keyfile: Option<&Keyfile>, f: impl FnOnce() -> T) -> T {
+    let previous = ACTIVE.with(|slot| slot.replace(keyfile.map(|k| *k.key)));
+    let result = f();
+    ACTIVE.with(|slot| slot.replace(previous)).zeroize();
+    result
+}
+
+/// The keyfile key `with` made current on this thread, if any
+pub fn active() -> Option<[u8; KEY_LEN]> {
+    ACTIVE.with(|slot| *slot.borrow())
+}
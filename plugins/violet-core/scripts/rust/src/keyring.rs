@@ -0,0 +1,258 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Local keyring — which passphrase generation a file was written under
+//
+// v5 headers written in a data directory carry the writer's key ID (the
+// same fingerprint .vtok tokens use). `keyring.json` in the data directory lists each passphrase
+// generation the data has been encrypted under: its key ID, the Argon2id
+// costs it was last used with, when it was added and when rotation retired
+// it. It also keeps the random salt and costs key IDs are derived with in
+// this directory (verify_token.rs), and holds no key material. When a file
+// will not open, decrypt-local looks the file's key ID up here and names the
+// generation it needs instead of stopping at a bare authentication failure.
+// Version 1 keyrings predate the salt; their key IDs match no passphrase now.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{bail, Context, Result};
+use argon2::Params as KdfParams;
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::backup::utc_timestamp;
+use crate::kdf::{self, KdfArgs};
+use crate::key_source::KeyArgs;
+use crate::{logging, resolve_data_dir, verify_token, write_atomic};
+
+pub const KEYRING_FILE: &str = "keyring.json";
+const KEYRING_VERSION: u32 = 2;
+/// Keyrings from before key IDs were salted; read, and written back as the current version
+const UNSALTED_KEYRING_VERSION: u32 = 1;
+
+#[derive(Subcommand)]
+pub enum KeyringCommand {
+    /// List key generations, oldest first (needs no passphrase)
+    List {
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+    /// Record the current passphrase as a key generation (encrypt-local and rotate-key do this themselves)
+    Add {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Note to keep with the generation, e.g. where the passphrase is stored
+        #[arg(long)]
+        label: Option<String>,
+    },
+}
+
+/// Argon2id costs a generation was used with
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfCosts {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl From<&KdfParams> for KdfCosts {
+    fn from(params: &KdfParams) -> Self {
+        Self { memory_kib: params.m_cost(), iterations: params.t_cost(), parallelism: params.p_cost() }
+    }
+}
+
+impl KdfCosts {
+    pub fn params(&self) -> Result<KdfParams> {
+        kdf::bounded_params(self.memory_kib, self.iterations, self.parallelism)
+    }
+}
+
+impl std::fmt::Display for KdfCosts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Argon2id m={} t={} p={}", self.memory_kib, self.iterations, self.parallelism)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct KeyEntry {
+    /// 1 for the first passphrase, counting up with each rotation
+    pub generation: u32,
+    pub key_id: String,
+    pub kdf: KdfCosts,
+    /// UTC, `20261017T040912Z`
+    pub added: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retired: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl KeyEntry {
+    /// `generation 2 (key ID 1a2b…, added …, retired …)`
+    fn describe(&self) -> String {
+        let retired = self.retired.as_ref().map(|at| format!(", retired {}", at)).unwrap_or_default();
+        let label = self.label.as_ref().map(|l| format!(", \"{}\"", l)).unwrap_or_default();
+        format!("generation {} (key ID {}, added {}{}{})", self.generation, self.key_id, self.added, retired, label)
+    }
+}
+
+/// Salt and costs every key ID in a data directory is derived with
+#[derive(Serialize, Deserialize)]
+pub struct KeyIdSalt {
+    /// Hex
+    pub salt: String,
+    pub kdf: KdfCosts,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Keyring {
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id_salt: Option<KeyIdSalt>,
+    pub keys: Vec<KeyEntry>,
+}
+
+impl Keyring {
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join(KEYRING_FILE)
+    }
+
+    /// The keyring of `data_dir`; empty when there is none yet
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let path = Self::path(data_dir);
+        if !path.exists() {
+            return Ok(Self { version: KEYRING_VERSION, key_id_salt: None, keys: Vec::new() });
+        }
+        let json = fs::read_to_string(&path).with_context(|| format!("read {:?}", path))?;
+        let mut keyring: Self = serde_json::from_str(&json).with_context(|| format!("parse {:?}", path))?;
+        match keyring.version {
+            KEYRING_VERSION => {}
+            UNSALTED_KEYRING_VERSION => {
+                keyring.version = KEYRING_VERSION;
+                keyring.key_id_salt = None;
+            }
+            version => bail!("unsupported keyring version {}", version),
+        }
+        Ok(keyring)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        write_atomic(&Self::path(data_dir), self.to_json()?.as_bytes())
+    }
+
+    pub fn find(&self, key_id: &str) -> Option<&KeyEntry> {
+        self.keys.iter().find(|entry| entry.key_id == key_id)
+    }
+
+    /// Record `key_id` as in use with `kdf`, as a new generation if it is not
+    /// listed yet; returns whether anything changed
+    pub fn register(&mut self, key_id: &str, kdf: &KdfParams, label: Option<&str>) -> bool {
+        let costs = KdfCosts::from(kdf);
+        if let Some(entry) = self.keys.iter_mut().find(|entry| entry.key_id == key_id) {
+            let relabeled = label.is_some() && entry.label.as_deref() != label;
+            let changed = entry.kdf != costs || entry.retired.is_some() || relabeled;
+            entry.kdf = costs;
+            entry.retired = None;
+            if let Some(label) = label {
+                entry.label = Some(label.to_string());
+            }
+            return changed;
+        }
+        self.keys.push(KeyEntry {
+            generation: self.keys.iter().map(|entry| entry.generation).max().unwrap_or(0) + 1,
+            key_id: key_id.to_string(),
+            kdf: costs,
+            added: utc_timestamp(SystemTime::now()),
+            retired: None,
+            label: label.map(str::to_string),
+        });
+        true
+    }
+
+    /// Mark every generation but `key_id` as rotated out
+    pub fn retire_others(&mut self, key_id: &str) {
+        let now = utc_timestamp(SystemTime::now());
+        for entry in self.keys.iter_mut().filter(|entry| entry.key_id != key_id && entry.retired.is_none()) {
+            entry.retired = Some(now.clone());
+        }
+    }
+}
+
+/// Add the generation of the passphrase with `key_id` to the keyring of
+/// `data_dir` if it is new; returns the generation number
+pub fn record(data_dir: &Path, key_id: &str, kdf: &KdfParams) -> Result<u32> {
+    let mut keyring = Keyring::load(data_dir)?;
+    if keyring.register(key_id, kdf, None) {
+        keyring.save(data_dir)?;
+    }
+    Ok(keyring.find(key_id).map_or(0, |entry| entry.generation))
+}
+
+/// Why the passphrase with key ID `supplied` does not open a file written under `file_key_id`
+///
+/// None when the passphrase is the one the file names, so the failure lies elsewhere.
+pub fn explain_mismatch(keyring: &Keyring, file_key_id: &str, supplied: &str) -> Option<String> {
+    if supplied == file_key_id {
+        return None;
+    }
+    let needed = match keyring.find(file_key_id) {
+        Some(entry) => format!("file needs key {}", entry.describe()),
+        None => format!("file was written under key ID {}, which {} does not list", file_key_id, KEYRING_FILE),
+    };
+    let have = match keyring.find(supplied) {
+        Some(entry) => format!("the key supplied is {}", entry.describe()),
+        None => format!("the key supplied has key ID {} and is not in {}", supplied, KEYRING_FILE),
+    };
+    Some(format!("wrong passphrase: {}; {}", needed, have))
+}
+
+pub fn run(command: KeyringCommand) -> Result<()> {
+    match command {
+        KeyringCommand::List { data_dir } => {
+            let data_dir = resolve_data_dir(data_dir);
+            let keyring = Keyring::load(&data_dir)?;
+            if keyring.keys.is_empty() {
+                logging::out(format_args!("🔑 No key generations recorded in {:?}.", Keyring::path(&data_dir)));
+                return Ok(());
+            }
+            logging::out(format_args!("🔑 Key generations in {:?}:", Keyring::path(&data_dir)));
+            for entry in &keyring.keys {
+                let state = match &entry.retired {
+                    Some(at) => format!("retired {}", at),
+                    None => "current".to_string(),
+                };
+                let label = entry.label.as_ref().map(|l| format!(" — {}", l)).unwrap_or_default();
+                logging::out(format_args!(
+                    "  {:>3}  {}  {}  added {}, {}{}",
+                    entry.generation, entry.key_id, entry.kdf, entry.added, state, label
+                ));
+            }
+            Ok(())
+        }
+        KeyringCommand::Add { key, kdf, data_dir, label } => {
+            let data_dir = resolve_data_dir(data_dir);
+            let passphrase = key.resolve()?;
+            let kdf = kdf.params()?;
+            verify_token::ensure_salt(&kdf)?;
+            let key_id = verify_token::derive_key_id(&passphrase)?;
+            let mut keyring = Keyring::load(&data_dir)?;
+            let changed = keyring.register(&key_id, &kdf, label.as_deref());
+            let generation = keyring.find(&key_id).map_or(0, |entry| entry.generation);
+            if changed {
+                keyring.save(&data_dir)?;
+                info!("🔑 Key ID {} recorded as generation {}.", key_id, generation);
+            } else {
+                info!("🔑 Key ID {} is already generation {}.", key_id, generation);
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Cloud KMS recipients — wrap a v6 file key with AWS KMS or GCP Cloud KMS (`--features kms`)
+//
+// A KMS recipient's stanza holds a random wrap key encrypted by the cloud
+// key, so opening the file needs permission to call Decrypt on that key and
+// nothing else: CI runners use their instance role or workload identity
+// instead of a shared passphrase. The calls go through the `aws` and
+// `gcloud` CLIs, which pick up those credentials the usual way; wrap keys
+// travel over their stdin and stdout, never argv.
+//
+// `projects/…/cryptoKeys/…` names a GCP key; anything else (key ID, ARN,
+// alias/…) an AWS one.
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use zeroize::Zeroizing;
+
+use crate::tool;
+
+/// Longest key name a stanza records
+pub const MAX_KEY_ID_LEN: usize = u8::MAX as usize;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    Aws,
+    Gcp,
+}
+
+impl Provider {
+    pub fn byte(self) -> u8 {
+        match self {
+            Self::Aws => 1,
+            Self::Gcp => 2,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Result<Self> {
+        match byte {
+            1 => Ok(Self::Aws),
+            2 => Ok(Self::Gcp),
+            other => bail!("KMS stanza names unknown provider {} — upgrade violet-cipher", other),
+        }
+    }
+}
+
+pub struct KmsKey {
+    pub provider: Provider,
+    pub key_id: String,
+}
+
+impl KmsKey {
+    pub fn parse(key_id: &str) -> Result<Self> {
+        let key_id = key_id.trim();
+        if key_id.is_empty() || key_id.len() > MAX_KEY_ID_LEN {
+            bail!("KMS key ID must be 1–{} characters", MAX_KEY_ID_LEN);
+        }
+        let provider = if key_id.starts_with("projects/") { Provider::Gcp } else { Provider::Aws };
+        Ok(Self { provider, key_id: key_id.to_string() })
+    }
+
+    /// Encrypt `plaintext` (a wrap key) under this key; returns the provider's ciphertext blob
+    pub fn wrap(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        match self.provider {
+            Provider::Aws => {
+                let args = ["kms", "encrypt", "--key-id", &self.key_id, "--plaintext", "fileb:///dev/stdin"];
+                let query = [&args[..], &["--output", "text", "--query", "CiphertextBlob"]].concat();
+                let output = tool::run("aws", &query, plaintext, "the AWS CLI")?;
+                decode_base64(&output)
+            }
+            Provider::Gcp => {
+                let args = ["kms", "encrypt", "--key", &self.key_id, "--plaintext-file", "-", "--ciphertext-file", "-"];
+                Ok(tool::run("gcloud", &args, plaintext, "the gcloud CLI")?.to_vec())
+            }
+        }
+    }
+
+    /// Decrypt a blob `wrap` returned
+    pub fn unwrap(&self, blob: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        match self.provider {
+            Provider::Aws => {
+                let args = ["kms", "decrypt", "--key-id", &self.key_id, "--ciphertext-blob", "fileb:///dev/stdin"];
+                let query = [&args[..], &["--output", "text", "--query", "Plaintext"]].concat();
+                let output = tool::run("aws", &query, blob, "the AWS CLI")?;
+                decode_base64(&output).map(Zeroizing::new)
+            }
+            Provider::Gcp => {
+                let args = ["kms", "decrypt", "--key", &self.key_id, "--ciphertext-file", "-", "--plaintext-file", "-"];
+                tool::run("gcloud", &args, blob, "the gcloud CLI")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for KmsKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let provider = match self.provider {
+            Provider::Aws => "aws-kms",
+            Provider::Gcp => "gcp-kms",
+        };
+        write!(f, "{}:{}", provider, self.key_id)
+    }
+}
+
+fn decode_base64(text: &[u8]) -> Result<Vec<u8>> {
+    let text = std::str::from_utf8(text).context("KMS output is not text")?;
+    base64::engine::general_purpose::STANDARD.decode(text.trim()).context("KMS output is not base64")
+}
@@ -0,0 +1,3210 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Violet Soul Cipher v4 — Multi-layer Rust encryption with backward compatibility
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes_gcm::{aead::{Aead, Payload}, Aes256Gcm, KeyInit, Nonce as GcmNonce};
+use aes_gcm_siv::Aes256GcmSiv;
+use anyhow::{bail, Context, Result};
+use argon2::Params as KdfParams;
+use chacha20poly1305::{ChaCha20Poly1305, Nonce as ChaChaNonce, XChaCha20Poly1305, XNonce};
+use clap::{Parser, Subcommand};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use rayon::prelude::*;
+use sha2::Sha256;
+use tracing::{debug, error, info, warn};
+use zeroize::{Zeroize, Zeroizing};
+
+mod agent;
+mod archive;
+mod audit;
+mod atomic;
+mod backup;
+mod bundle;
+mod compat;
+mod completions;
+mod config;
+mod container;
+mod detached_mac;
+mod diff;
+mod env_audit;
+mod export;
+mod ffi;
+mod fields;
+#[cfg(feature = "fido2")]
+mod fido2;
+mod header;
+mod hooks;
+mod import;
+mod kdf;
+mod key_cache;
+mod key_source;
+mod keyfile;
+mod keyring;
+mod lock;
+mod logging;
+#[cfg(feature = "kms")]
+mod kms;
+mod manifest;
+mod monitor;
+mod nonces;
+mod paper;
+mod pepper;
+mod preamble;
+mod profile;
+mod progress;
+mod recipient;
+mod recovery;
+mod salt;
+mod sarif;
+mod schema;
+mod sealed_key;
+mod secret;
+mod self_test;
+mod serve;
+mod shamir;
+mod signing;
+mod staged;
+mod status;
+#[cfg(feature = "sqlite-store")]
+mod store;
+mod strict;
+mod tool;
+mod transaction;
+#[cfg(feature = "test-vectors")]
+mod vectors;
+mod verify_token;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod watch;
+
+use atomic::write_atomic;
+use backup::BackupArgs;
+use header::{Compression, ContentType, Header};
+use kdf::KdfArgs;
+use key_source::{resolve_passphrase, KeyArgs, Keyholder, Passphrase};
+use keyring::Keyring;
+use preamble::{Preamble, Suite};
+use profile::Profile;
+use recipient::Recipient;
+use salt::SaltArgs;
+use sarif::Rule;
+use secret::{Secret, SecretKey};
+use signing::Signer;
+use transaction::Transaction;
+use verify_token::VerifyToken;
+
+const VERSION_V4: u8 = 0x04;
+const VERSION_V5: u8 = 0x05;
+const VERSION_V6: u8 = 0x06;
+const ARGON2_SALT_LEN: usize = 32;
+const GCM_NONCE_LEN: usize = 12;
+const XCHACHA_NONCE_LEN: usize = 24;
+const AES_CBC_IV_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+/// Bytes `seal_layers` adds to its plaintext, outer salt included: a salt, nonce and tag per layer.
+/// Suites with XChaCha20 layers add 12 more per such layer; see `Suite::overhead`.
+const LAYERS_OVERHEAD: usize = 3 * (ARGON2_SALT_LEN + GCM_NONCE_LEN + 16);
+
+const LOCAL_SALT: &str = "violet-soul-salt-local-2026";
+const GIT_SALT: &str = "violet-soul-salt-git-2026";
+const OUTER_SALT: &str = "violet-outer-shell-2026";
+const BINDING_CONTEXT: &[u8] = b"violet-binding-v1\0";
+const LAYER_MAC_INFO: &[u8] = b"violet-v5-layer-hmac-2026";
+
+const EMBEDDED_SEED: &[u8; 32] = b"V10l3t-C1ph3r-S33d-2026-Kl4ud1a!";
+
+const TARGET_FILES: &[&str] = &["rules-index.json", "minds-index.json", "vibe-library.json"];
+
+/// Exit status of `verify`, `check-staged`, `compat-check`, `env-audit`, `verify-signature`,
+/// `diff --exit-code`, `manifest verify`, `audit verify` and `self-test` when they find issues
+/// (1 is left for hard errors)
+const EXIT_VERIFY_ISSUES: i32 = 2;
+
+type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+
+#[derive(Parser)]
+#[command(name = "violet-cipher", version = "4.0.0")]
+#[command(about = "Violet Soul Cipher v4 — Multi-layer encryption")]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+    /// Second key factor for v5 files from this file instead of the compiled-in seed (32+ bytes)
+    #[arg(long, global = true, env = "VIOLET_PEPPER_FILE")]
+    pepper_file: Option<PathBuf>,
+    /// Keyfile needed alongside the passphrase for v5 files (any bytes; not --key-file, the passphrase)
+    #[arg(long, global = true, env = "VIOLET_KEYFILE")]
+    keyfile: Option<PathBuf>,
+    /// Key of the detached .mac sidecars `--detached-mac` writes and `verify` checks (32+ bytes)
+    #[arg(long, global = true, env = "VIOLET_MAC_KEY_FILE")]
+    mac_key_file: Option<PathBuf>,
+    #[cfg(feature = "fido2")]
+    #[command(flatten)]
+    fido2: fido2::Fido2Args,
+    #[command(flatten)]
+    log: logging::LogArgs,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Encrypt data files with local key (v5 multi-layer)
+    EncryptLocal {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Profile from profiles.json in the data directory, giving salt label, targets and suffix (default: local)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Only this target file, e.g. rules-index.json (repeatable; default: all of the profile's)
+        #[arg(long, value_name = "NAME")]
+        only: Vec<String>,
+        /// zstd-compress before encrypting
+        #[arg(long)]
+        compress: bool,
+        /// Encrypt to this violet-pub- or post-quantum violet-pq-pub- key instead of the passphrase (v6, repeatable)
+        #[arg(long, value_name = "PUBKEY", conflicts_with = "verify_token")]
+        recipient: Vec<String>,
+        /// Also let whoever knows the passphrase in this file open it (v6, repeatable)
+        #[arg(long, value_name = "FILE", conflicts_with = "verify_token")]
+        recipient_key_file: Vec<PathBuf>,
+        /// Also let whoever may decrypt with this AWS or GCP KMS key open it (v6, repeatable)
+        #[cfg(feature = "kms")]
+        #[arg(long, value_name = "KEY", conflicts_with_all = ["verify_token", "fast", "suite"])]
+        kms_key_id: Vec<String>,
+        /// Also write a .vtok verification token next to each output
+        #[arg(long)]
+        verify_token: bool,
+        /// Also write a .mac sidecar next to each output: HMAC-SHA256 under --mac-key-file
+        #[arg(long)]
+        detached_mac: bool,
+        #[command(flatten)]
+        backup: BackupArgs,
+        /// Files to encrypt at once (default: one per CPU); each holds three layers' Argon2id memory at once
+        #[arg(long, default_value_t = 0)]
+        jobs: usize,
+        /// Show a progress bar per file, stepped as each layer key is derived
+        #[arg(long)]
+        progress: bool,
+        /// Seal every file with the same layer salts, so the run derives three keys instead of three per file
+        #[arg(long, conflicts_with_all = ["recipient", "recipient_key_file"])]
+        fast: bool,
+        /// Algorithms of the three layers (v5)
+        #[arg(long, value_enum, default_value_t, conflicts_with_all = ["recipient", "recipient_key_file"])]
+        suite: Suite,
+        /// Re-encrypt files whose .enc already holds their current content (default: keep those as they are)
+        #[arg(long)]
+        force: bool,
+        /// Report what would be written, overwritten or skipped without touching disk
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Decrypt .enc files to .json (auto-detect v2–v6)
+    DecryptLocal {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Profile from profiles.json in the data directory, giving salt label, targets and suffix (default: local)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Only this target file, e.g. rules-index.json (repeatable; default: all of the profile's)
+        #[arg(long, value_name = "NAME")]
+        only: Vec<String>,
+        /// Show a progress bar per file, stepped as each layer key is derived
+        #[arg(long)]
+        progress: bool,
+        /// Leave a .json that no longer matches its .enc as it is, with a warning, instead of overwriting it
+        #[arg(long)]
+        keep_modified: bool,
+        /// Git revision the .enc files were checked out at before; a .json matching its .enc there is overwritten
+        #[arg(long, value_name = "REV", requires = "keep_modified")]
+        previous: Option<String>,
+        /// Report what would be written, overwritten or skipped without touching disk
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Encrypt only the values at chosen JSON paths, writing a diffable <stem>.fields.json
+    EncryptFields(fields::EncryptArgs),
+    /// Open every sealed value of each <stem>.fields.json back into the target JSON file
+    DecryptFields(fields::DecryptArgs),
+    /// Generate empty .git.enc placeholders for git
+    EncryptGit {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Also wrap the placeholders for this AWS or GCP KMS key (v6, repeatable), so CI can check them with --kms
+        #[cfg(feature = "kms")]
+        #[arg(long, value_name = "KEY")]
+        kms_key_id: Vec<String>,
+        /// Also write a .vtok verification token next to each output
+        #[arg(long)]
+        verify_token: bool,
+        /// Report what would be written, overwritten or skipped without touching disk
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Verify git placeholder decryption
+    DecryptGit {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+    /// Upgrade v2/v3 .enc files to v4 format
+    ReEncrypt {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Profile from profiles.json in the data directory, giving salt label, targets and suffix (default: local)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Only this target file, e.g. rules-index.json (repeatable; default: all of the profile's)
+        #[arg(long, value_name = "NAME")]
+        only: Vec<String>,
+        /// Also write .vtok verification tokens (including for files already v4)
+        #[arg(long)]
+        verify_token: bool,
+        #[command(flatten)]
+        backup: BackupArgs,
+        /// Report what would be written, overwritten or skipped without touching disk
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Re-encrypt .enc files DOWN to v3 for the legacy Node.js tool (unauthenticated CBC — migration only)
+    ExportLegacy {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Legacy format to write
+        #[arg(long = "version", value_name = "VERSION", value_parser = ["v3"])]
+        legacy_version: String,
+        /// Only this target file, e.g. rules-index.json (repeatable; default: every target present)
+        #[arg(long = "file", value_name = "NAME")]
+        files: Vec<String>,
+        /// Write the v3 files here instead of replacing the .enc files in the data directory
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+        #[command(flatten)]
+        backup: BackupArgs,
+        /// Report what would be written, overwritten or skipped without touching disk
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Check encryption integrity and detect plaintext leaks
+    Verify {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Profile from profiles.json in the data directory, giving salt label, targets and suffix (default: local)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Only this target file, e.g. rules-index.json (repeatable; default: all of the profile's)
+        #[arg(long, value_name = "NAME")]
+        only: Vec<String>,
+        /// Check structure, .vtok tokens and .mac sidecars (with --mac-key-file) only, without the passphrase
+        #[arg(long)]
+        no_key: bool,
+        /// Key ID every token must carry (with --no-key)
+        #[arg(long, requires = "no_key")]
+        expect_key_id: Option<String>,
+        /// Also count legacy (v2/v3) files as issues
+        #[arg(long)]
+        strict: bool,
+        /// Also validate each decrypted file against its bundled JSON Schema
+        #[arg(long, conflicts_with = "no_key")]
+        deep: bool,
+        /// Also check every committed version of the files for repeated nonces (outer layers)
+        #[arg(long)]
+        git_history: bool,
+        /// Also write the findings here as a SARIF 2.1.0 log, for code scanning dashboards
+        #[arg(long, value_name = "PATH")]
+        sarif: Option<PathBuf>,
+    },
+    /// Per target: plaintext and .enc sizes, mtimes, format version, key generation and staleness (no key needed)
+    Status {
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Ask the running `watch` instead: uptime, key IDs, counters, recent operations
+        #[arg(long)]
+        daemon: bool,
+        /// Status socket of the watch (default: .violet-watch.sock in the data directory)
+        #[arg(long, requires = "daemon")]
+        socket: Option<PathBuf>,
+        /// Print as JSON (the daemon's snapshot with --daemon)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Re-encrypt every .enc and .git.enc under a new passphrase (all-or-nothing)
+    RotateKey {
+        /// Current passphrase
+        #[arg(long, env = "VIOLET_SOUL_KEY", hide_env_values = true)]
+        old_key: Option<String>,
+        /// Read the current passphrase from a file (overrides --old-key)
+        #[arg(long)]
+        old_key_file: Option<PathBuf>,
+        /// New passphrase
+        #[arg(long, env = "VIOLET_SOUL_NEW_KEY", hide_env_values = true)]
+        new_key: Option<String>,
+        /// Read the new passphrase from a file (overrides --new-key)
+        #[arg(long)]
+        new_key_file: Option<PathBuf>,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Rotate only this profile's files (profiles.json), leaving the old key current for the rest
+        /// (default: every profile, and the .git.enc placeholders)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Report what would be written, overwritten or skipped without touching disk
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// v4-encrypt any single file to stdout or --output; `decrypt-file` reverses it
+    EncryptFile {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        /// File to encrypt
+        #[arg(long)]
+        file: PathBuf,
+        /// Where to write the ciphertext (default: stdout); it is bound to this name, less any .enc
+        #[arg(long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        salt: SaltArgs,
+    },
+    /// Encrypt a decrypted target for other tools (--to age), to hand to collaborators
+    Export(export::ExportArgs),
+    /// Decrypt another tool's file (--from age) and re-encrypt it as v4, with no plaintext step
+    Import(import::ImportArgs),
+    /// Decrypt a single .enc file and print its text, or its bytes with --raw / --output
+    DecryptFile {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        /// Path to the .enc file
+        #[arg(long)]
+        file: PathBuf,
+        #[command(flatten)]
+        salt: SaltArgs,
+        /// Refuse anything but a well-formed, intact VSC!-framed v4–v6 file within size and KDF limits
+        #[arg(long)]
+        strict: bool,
+        /// Write the plaintext bytes here instead of printing them, whatever the content type
+        #[arg(long, conflicts_with = "raw")]
+        output: Option<PathBuf>,
+        /// Print the plaintext bytes unchanged instead of requiring text
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Show, by JSON pointer, how each plaintext target differs from its decrypted .enc
+    Diff {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Exit with status 2 when any file differs
+        #[arg(long)]
+        exit_code: bool,
+    },
+    /// Print the value at a JSON pointer of a .enc file; the plaintext never touches disk
+    Query {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        /// Path to the .enc file
+        #[arg(long)]
+        file: PathBuf,
+        /// JSON pointer (RFC 6901), e.g. /rules/0/name; "" selects the whole document
+        #[arg(long)]
+        path: String,
+        /// Print a string value bare instead of as JSON
+        #[arg(long)]
+        raw: bool,
+        #[command(flatten)]
+        salt: SaltArgs,
+    },
+    /// Encrypt any file (binary-safe, v5) to <file>.enc with a content-type tag
+    EncryptAsset {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        /// File to encrypt
+        #[arg(long)]
+        file: PathBuf,
+        /// Content type tag, e.g. json, png, sqlite (default: guessed from the extension)
+        #[arg(long)]
+        content_type: Option<String>,
+        /// zstd-compress before encrypting
+        #[arg(long)]
+        compress: bool,
+        /// Algorithms of the three layers
+        #[arg(long, value_enum, default_value_t)]
+        suite: Suite,
+        #[command(flatten)]
+        salt: SaltArgs,
+        /// Also write a .vtok verification token next to the output
+        #[arg(long)]
+        verify_token: bool,
+        /// Show a progress bar per file, stepped as each layer key is derived
+        #[arg(long)]
+        progress: bool,
+        /// Report what would be written, overwritten or skipped without touching disk
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Decrypt a .enc file back to its original bytes (any version)
+    DecryptAsset {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        /// Path to the .enc file
+        #[arg(long)]
+        file: PathBuf,
+        /// Where to write the plaintext (default: the input without .enc)
+        #[arg(long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        salt: SaltArgs,
+        /// Refuse anything but a well-formed, intact VSC!-framed v4–v6 file within size and KDF limits
+        #[arg(long)]
+        strict: bool,
+        /// Show a progress bar per file, stepped as each layer key is derived
+        #[arg(long)]
+        progress: bool,
+        /// Report what would be written, overwritten or skipped without touching disk
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Encrypted single-file datastore: documents as rows in one SQLite file
+    #[cfg(feature = "sqlite-store")]
+    Store {
+        #[command(subcommand)]
+        action: store::StoreCommand,
+    },
+    /// Shamir secret sharing: split the key among holders, rebuild it from a quorum
+    Key {
+        #[command(subcommand)]
+        action: shamir::KeyCommand,
+    },
+    /// Roll a .enc file back to one of its --backup copies
+    RestoreBackup {
+        /// The .enc file to restore
+        #[arg(long)]
+        file: PathBuf,
+        /// Backup to restore (default: the newest)
+        #[arg(long)]
+        from: Option<PathBuf>,
+        /// List available backups and exit
+        #[arg(long)]
+        list: bool,
+        /// Report what would be restored without touching disk
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Fixed-salt, fixed-nonce v2/v3/v4 samples for checking other implementations (JSON)
+    #[cfg(feature = "test-vectors")]
+    #[command(visible_alias = "gen-vectors")]
+    TestVectors(vectors::VectorArgs),
+    /// Encrypted snapshots of the whole data directory: create, restore, list, prune
+    Backup {
+        #[command(subcommand)]
+        action: archive::BackupCommand,
+    },
+    /// Pack every target .enc into one bundle sealed with a separate backup passphrase
+    ExportBundle(bundle::ExportArgs),
+    /// Restore the .enc files of an export bundle into the data directory (all-or-nothing)
+    ImportBundle(bundle::ImportArgs),
+    /// Seal the data directory into one encrypted container, the only file git needs to see
+    Pack(container::PackArgs),
+    /// Restore the files of a data container into the data directory (all-or-nothing)
+    Unpack(container::UnpackArgs),
+    /// Where the passphrase and data directory come from: flags, environment, .violet.env/.env
+    Config {
+        #[command(subcommand)]
+        action: config::ConfigCommand,
+    },
+    /// Passphrase generations recorded in keyring.json: list, add
+    Keyring {
+        #[command(subcommand)]
+        action: keyring::KeyringCommand,
+    },
+    /// HMAC manifest of every .enc/.git.enc: write, verify (catches changes made outside the tool)
+    Manifest {
+        #[command(subcommand)]
+        action: manifest::ManifestCommand,
+    },
+    /// HMAC-chained audit log of encrypt/decrypt/verify/rotate runs: verify
+    Audit {
+        #[command(subcommand)]
+        action: audit::AuditCommand,
+    },
+    /// Answer JSON-RPC decrypt / encrypt / status requests on stdio, keys cached for the session (editor plugins)
+    Serve(serve::ServeArgs),
+    /// Unlock agent: hold the passphrase and derived keys, seal and open for other invocations
+    Agent {
+        #[command(subcommand)]
+        action: agent::AgentCommand,
+    },
+    /// Re-encrypt target JSON files to .enc whenever they are saved
+    Watch {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// zstd-compress before encrypting
+        #[arg(long)]
+        compress: bool,
+        /// Algorithms of the three layers
+        #[arg(long, value_enum, default_value_t)]
+        suite: Suite,
+        /// Also write a .vtok verification token next to each output
+        #[arg(long)]
+        verify_token: bool,
+        /// Also write a .mac sidecar next to each output: HMAC-SHA256 under --mac-key-file
+        #[arg(long)]
+        detached_mac: bool,
+        #[command(flatten)]
+        backup: BackupArgs,
+        /// Quiet period after the last change before encrypting, in milliseconds
+        #[arg(long, default_value_t = 500)]
+        debounce_ms: u64,
+        /// Unix socket `status --daemon` queries (default: .violet-watch.sock in the data directory)
+        #[arg(long)]
+        status_socket: Option<PathBuf>,
+        /// Do not open a status socket
+        #[arg(long, conflicts_with = "status_socket")]
+        no_status_socket: bool,
+    },
+    /// Prove a directory of legacy Node.js (v2/v3) .enc files decrypts and survives re-encryption
+    CompatCheck {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        /// Directory of legacy .enc files (searched recursively)
+        #[arg(long)]
+        dir: PathBuf,
+        /// Also write the report as JSON to this path
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+    /// Pre-commit hook: fail if the git index holds plaintext soul data
+    CheckStaged {
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Read every indexed file, not only those changed from HEAD
+        #[arg(long)]
+        all: bool,
+        /// Also write the findings here as a SARIF 2.1.0 log, for code scanning dashboards
+        #[arg(long, value_name = "PATH")]
+        sarif: Option<PathBuf>,
+    },
+    /// Install git hooks: check-staged before each commit, decrypt-local after checkout and merge
+    InstallHooks(hooks::HookArgs),
+    /// Look for the passphrase in shell history, process environments, .env files and core dumps
+    EnvAudit {
+        #[command(flatten)]
+        key: KeyArgs,
+        /// Repository to search for .env files (default: the enclosing git repository)
+        #[arg(long)]
+        repo: Option<PathBuf>,
+    },
+    /// Generate an X25519 keypair for `encrypt-local --recipient` (or an Ed25519 one for `sign`)
+    Keygen {
+        /// Write the private identity here (mode 0600) instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Generate an Ed25519 signing key instead
+        #[arg(long)]
+        signing: bool,
+        /// Hybrid X25519 + ML-KEM-768 keypair, for data that must outlast quantum computers
+        #[arg(long, conflicts_with = "signing")]
+        pq: bool,
+    },
+    /// Write a detached Ed25519 signature <file>.sig over each encrypted file
+    Sign {
+        /// Signing key from `keygen --signing`
+        #[arg(long)]
+        signing_key: PathBuf,
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Check each file's <file>.sig against the trusted signer keys
+    VerifySignature {
+        /// Trusted public key (violet-sig-pub-…); repeat to trust several
+        #[arg(long = "signer", required = true)]
+        signers: Vec<String>,
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Dump a file's header as JSON; needs no key
+    Inspect {
+        #[arg(long)]
+        file: PathBuf,
+    },
+    /// Run known-answer tests of every primitive and the v2/v3/v4 pipelines; needs no key
+    SelfTest,
+    /// Time Argon2id/scrypt on this machine and recommend --kdf-* values
+    BenchKdf {
+        /// Target derivation time per layer, in milliseconds
+        #[arg(long, default_value_t = 500)]
+        target_ms: u64,
+        /// Upper bound for the recommended Argon2id memory, in MiB
+        #[arg(long, default_value_t = 256)]
+        max_memory_mib: u32,
+    },
+    /// Print a shell completion script for the subcommands and their flags
+    Completions(completions::CompletionArgs),
+}
+
+/// --data-dir, else VIOLET_DATA_DIR or the env file (config.rs), else `data` next to the binary
+fn resolve_data_dir(custom: Option<PathBuf>) -> PathBuf {
+    custom.or_else(|| config::data_dir_setting().map(|(dir, _)| dir)).unwrap_or_else(|| {
+        let exe = std::env::current_exe().unwrap_or_default();
+        exe.parent()
+            .unwrap_or(Path::new("."))
+            .join("..")
+            .join("..")
+            .join("data")
+    })
+}
+
+/// Target files to work on: the names given (`--only`, `--file`), checked
+/// against TARGET_FILES, in target order; all of them when none is given
+fn select_targets(names: &[String]) -> Result<Vec<&'static str>> {
+    if let Some(unknown) = names.iter().find(|name| !TARGET_FILES.contains(&name.as_str())) {
+        bail!("{} is not a target file — choose from {}", unknown, TARGET_FILES.join(", "));
+    }
+    Ok(TARGET_FILES.iter().copied().filter(|target| names.is_empty() || names.iter().any(|n| n == target)).collect())
+}
+
+/// Key ID for verification tokens, derived once per run when requested
+fn token_key_id(passphrase: &[u8], enabled: bool) -> Result<Option<String>> {
+    enabled.then(|| verify_token::derive_key_id(passphrase)).transpose()
+}
+
+fn derive_embedded_key() -> [u8; KEY_LEN] {
+    let mut key = [0u8; KEY_LEN];
+    for (i, byte) in EMBEDDED_SEED.iter().enumerate() {
+        key[i] = byte ^ ((i as u8).wrapping_mul(0x5A).wrapping_add(0x3C));
+    }
+    key
+}
+
+/// The pepper of the v5 file being sealed or opened, else the compiled-in seed
+fn second_factor() -> [u8; KEY_LEN] {
+    pepper::active().unwrap_or_else(derive_embedded_key)
+}
+
+/// Argon2id over passphrase ‖ keyfile key (when one is active) ‖ second factor
+fn derive_key_argon2(passphrase: &[u8], salt: &[u8], params: &KdfParams) -> Result<SecretKey> {
+    argon2_key(&kdf_input(passphrase), salt, params)
+}
+
+/// Passphrase ‖ keyfile key ‖ second factor; the keyfile and pepper are this thread's
+fn kdf_input(passphrase: &[u8]) -> Secret<Vec<u8>> {
+    let mut embedded = second_factor();
+    let mut keyfile = keyfile::active();
+    let keyfile_len = keyfile.map_or(0, |k| k.len());
+    let mut combined = Secret::<Vec<u8>>::zeroed(passphrase.len() + keyfile_len + KEY_LEN);
+    let (head, tail) = combined.as_mut_slice().split_at_mut(passphrase.len());
+    let (middle, tail) = tail.split_at_mut(keyfile_len);
+    head.copy_from_slice(passphrase);
+    if let Some(keyfile) = &keyfile {
+        middle.copy_from_slice(keyfile);
+    }
+    tail.copy_from_slice(&embedded);
+    embedded.zeroize();
+    keyfile.zeroize();
+    combined
+}
+
+/// Argon2id over a `kdf_input`; reads no thread state, so any thread may run it
+fn argon2_key(input: &[u8], salt: &[u8], params: &KdfParams) -> Result<SecretKey> {
+    key_cache::derive(input, salt, params, || {
+        let mut key = SecretKey::zeroed();
+        kdf::argon2id(params)
+            .hash_password_into(input, salt, &mut *key)
+            .map_err(|e| anyhow::anyhow!("Argon2id KDF failed: {}", e))?;
+        Ok(key)
+    })
+}
+
+fn derive_key_scrypt(passphrase: &[u8], salt: &str) -> Result<[u8; KEY_LEN]> {
+    let params = scrypt::Params::new(kdf::SCRYPT_LEGACY_LOG_N, kdf::SCRYPT_R, kdf::SCRYPT_P, KEY_LEN)
+        .map_err(|e| anyhow::anyhow!("scrypt params: {}", e))?;
+    let mut key = [0u8; KEY_LEN];
+    scrypt::scrypt(passphrase, salt.as_bytes(), &params, &mut key)
+        .map_err(|e| anyhow::anyhow!("scrypt KDF failed: {}", e))?;
+    Ok(key)
+}
+
+/// Every salt, nonce, IV and generated key comes from here
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut buf = [0u8; N];
+    #[cfg(feature = "test-vectors")]
+    if vectors::fill_injected(&mut buf) {
+        return buf;
+    }
+    rand::thread_rng().fill_bytes(&mut buf);
+    buf
+}
+
+fn encrypt_aes_gcm(key: &[u8; KEY_LEN], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    encrypt_aes_gcm_with(key, &random_bytes(), plaintext, aad)
+}
+
+/// `encrypt_aes_gcm` with a nonce the caller drew
+fn encrypt_aes_gcm_with(
+    key: &[u8; KEY_LEN],
+    nonce_bytes: &[u8; GCM_NONCE_LEN],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>> {
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|e| anyhow::anyhow!("AES-GCM init: {}", e))?;
+    let nonce = GcmNonce::from_slice(nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(nonce, Payload { msg: plaintext, aad })
+        .map_err(|e| anyhow::anyhow!("AES-GCM encrypt: {}", e))?;
+    let mut out = Vec::with_capacity(GCM_NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(nonce_bytes);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn decrypt_aes_gcm(key: &[u8; KEY_LEN], data: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < GCM_NONCE_LEN + 16 {
+        bail!("AES-GCM data too short");
+    }
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|e| anyhow::anyhow!("AES-GCM init: {}", e))?;
+    let nonce = GcmNonce::from_slice(&data[..GCM_NONCE_LEN]);
+    cipher
+        .decrypt(nonce, Payload { msg: &data[GCM_NONCE_LEN..], aad })
+        .map_err(|e| anyhow::anyhow!("AES-GCM decrypt failed: {}", e))
+}
+
+fn encrypt_chacha20(key: &[u8; KEY_LEN], plaintext: &[u8]) -> Result<Vec<u8>> {
+    encrypt_chacha20_with(key, &random_bytes(), plaintext, &[])
+}
+
+/// `encrypt_chacha20` with a nonce the caller drew and associated data
+fn encrypt_chacha20_with(
+    key: &[u8; KEY_LEN],
+    nonce_bytes: &[u8; GCM_NONCE_LEN],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>> {
+    let cipher = ChaCha20Poly1305::new_from_slice(key)
+        .map_err(|e| anyhow::anyhow!("ChaCha20 init: {}", e))?;
+    let nonce = ChaChaNonce::from_slice(nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(nonce, Payload { msg: plaintext, aad })
+        .map_err(|e| anyhow::anyhow!("ChaCha20 encrypt: {}", e))?;
+    let mut out = Vec::with_capacity(GCM_NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(nonce_bytes);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn decrypt_chacha20(key: &[u8; KEY_LEN], data: &[u8]) -> Result<Vec<u8>> {
+    decrypt_chacha20_with(key, data, &[])
+}
+
+/// `decrypt_chacha20` of a layer sealed with associated data
+fn decrypt_chacha20_with(key: &[u8; KEY_LEN], data: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < GCM_NONCE_LEN + 16 {
+        bail!("ChaCha20 data too short");
+    }
+    let cipher = ChaCha20Poly1305::new_from_slice(key)
+        .map_err(|e| anyhow::anyhow!("ChaCha20 init: {}", e))?;
+    let nonce = ChaChaNonce::from_slice(&data[..GCM_NONCE_LEN]);
+    cipher
+        .decrypt(nonce, Payload { msg: &data[GCM_NONCE_LEN..], aad })
+        .map_err(|e| anyhow::anyhow!("ChaCha20 decrypt failed: {}", e))
+}
+
+/// AES-256-GCM-SIV layer: laid out like AES-GCM, but a repeated nonce only
+/// reveals that two plaintexts are equal rather than the authentication key
+fn encrypt_aes_gcm_siv_with(
+    key: &[u8; KEY_LEN],
+    nonce_bytes: &[u8; GCM_NONCE_LEN],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>> {
+    let cipher = Aes256GcmSiv::new_from_slice(key)
+        .map_err(|e| anyhow::anyhow!("AES-GCM-SIV init: {}", e))?;
+    let nonce = GcmNonce::from_slice(nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(nonce, Payload { msg: plaintext, aad })
+        .map_err(|e| anyhow::anyhow!("AES-GCM-SIV encrypt: {}", e))?;
+    let mut out = Vec::with_capacity(GCM_NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(nonce_bytes);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn decrypt_aes_gcm_siv(key: &[u8; KEY_LEN], data: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < GCM_NONCE_LEN + 16 {
+        bail!("AES-GCM-SIV data too short");
+    }
+    let cipher = Aes256GcmSiv::new_from_slice(key)
+        .map_err(|e| anyhow::anyhow!("AES-GCM-SIV init: {}", e))?;
+    let nonce = GcmNonce::from_slice(&data[..GCM_NONCE_LEN]);
+    cipher
+        .decrypt(nonce, Payload { msg: &data[GCM_NONCE_LEN..], aad })
+        .map_err(|e| anyhow::anyhow!("AES-GCM-SIV decrypt failed: {}", e))
+}
+
+/// XChaCha20-Poly1305 layer: a random 192-bit nonce followed by ciphertext
+fn encrypt_xchacha20_with(
+    key: &[u8; KEY_LEN],
+    nonce_bytes: &[u8; XCHACHA_NONCE_LEN],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>> {
+    let cipher = XChaCha20Poly1305::new_from_slice(key)
+        .map_err(|e| anyhow::anyhow!("XChaCha20 init: {}", e))?;
+    let nonce = XNonce::from_slice(nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(nonce, Payload { msg: plaintext, aad })
+        .map_err(|e| anyhow::anyhow!("XChaCha20 encrypt: {}", e))?;
+    let mut out = Vec::with_capacity(XCHACHA_NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(nonce_bytes);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn decrypt_xchacha20_with(key: &[u8; KEY_LEN], data: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < XCHACHA_NONCE_LEN + 16 {
+        bail!("XChaCha20 data too short");
+    }
+    let cipher = XChaCha20Poly1305::new_from_slice(key)
+        .map_err(|e| anyhow::anyhow!("XChaCha20 init: {}", e))?;
+    let nonce = XNonce::from_slice(&data[..XCHACHA_NONCE_LEN]);
+    cipher
+        .decrypt(nonce, Payload { msg: &data[XCHACHA_NONCE_LEN..], aad })
+        .map_err(|e| anyhow::anyhow!("XChaCha20 decrypt failed: {}", e))
+}
+
+/// AEAD of one passphrase layer; the file's cipher suite assigns one to each
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LayerCipher {
+    AesGcm,
+    AesGcmSiv,
+    ChaCha20,
+    XChaCha20,
+}
+
+/// A nonce drawn for one layer, sized for its cipher
+enum LayerNonce {
+    Short([u8; GCM_NONCE_LEN]),
+    Extended([u8; XCHACHA_NONCE_LEN]),
+}
+
+impl LayerCipher {
+    fn nonce_len(self) -> usize {
+        match self {
+            Self::AesGcm | Self::AesGcmSiv | Self::ChaCha20 => GCM_NONCE_LEN,
+            Self::XChaCha20 => XCHACHA_NONCE_LEN,
+        }
+    }
+
+    fn draw_nonce(self) -> LayerNonce {
+        match self {
+            Self::AesGcm | Self::AesGcmSiv | Self::ChaCha20 => LayerNonce::Short(random_bytes()),
+            Self::XChaCha20 => LayerNonce::Extended(random_bytes()),
+        }
+    }
+
+    fn seal(self, key: &[u8; KEY_LEN], nonce: &LayerNonce, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        match (self, nonce) {
+            (Self::AesGcm, LayerNonce::Short(nonce)) => encrypt_aes_gcm_with(key, nonce, plaintext, aad),
+            (Self::AesGcmSiv, LayerNonce::Short(nonce)) => encrypt_aes_gcm_siv_with(key, nonce, plaintext, aad),
+            (Self::ChaCha20, LayerNonce::Short(nonce)) => encrypt_chacha20_with(key, nonce, plaintext, aad),
+            (Self::XChaCha20, LayerNonce::Extended(nonce)) => encrypt_xchacha20_with(key, nonce, plaintext, aad),
+            _ => bail!("layer nonce drawn for another cipher"),
+        }
+    }
+
+    fn open(self, key: &[u8; KEY_LEN], data: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::AesGcm => decrypt_aes_gcm(key, data, aad),
+            Self::AesGcmSiv => decrypt_aes_gcm_siv(key, data, aad),
+            Self::ChaCha20 => decrypt_chacha20_with(key, data, aad),
+            Self::XChaCha20 => decrypt_xchacha20_with(key, data, aad),
+        }
+    }
+}
+
+fn encrypt_aes_cbc(key: &[u8; KEY_LEN], plaintext: &[u8]) -> Vec<u8> {
+    let iv = random_bytes::<AES_CBC_IV_LEN>();
+    let cipher = Aes256CbcEnc::new_from_slices(key, &iv).expect("CBC init");
+    let pad_len = 16 - (plaintext.len() % 16);
+    let mut buf = vec![0u8; plaintext.len() + pad_len];
+    buf[..plaintext.len()].copy_from_slice(plaintext);
+    let ct = cipher.encrypt_padded_mut::<Pkcs7>(&mut buf, plaintext.len()).expect("CBC encrypt");
+    let mut out = Vec::with_capacity(AES_CBC_IV_LEN + ct.len());
+    out.extend_from_slice(&iv);
+    out.extend_from_slice(ct);
+    out
+}
+
+fn decrypt_aes_cbc(key: &[u8; KEY_LEN], data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < AES_CBC_IV_LEN + 16 {
+        bail!("AES-CBC data too short");
+    }
+    let iv = &data[..AES_CBC_IV_LEN];
+    let ciphertext = &data[AES_CBC_IV_LEN..];
+    let cipher = Aes256CbcDec::new_from_slices(key, iv)
+        .map_err(|e| anyhow::anyhow!("CBC init: {}", e))?;
+    let mut buf = ciphertext.to_vec();
+    let pt = cipher
+        .decrypt_padded_mut::<Pkcs7>(&mut buf)
+        .map_err(|e| anyhow::anyhow!("AES-CBC decrypt failed: {}", e))?;
+    Ok(pt.to_vec())
+}
+
+/// Per-layer passphrase: `<passphrase>-<layer>-<salt label>`, byte-for-byte
+fn layer_passphrase(passphrase: &[u8], layer: &str, salt_label: &str) -> Zeroizing<Vec<u8>> {
+    Zeroizing::new([passphrase, b"-", layer.as_bytes(), b"-", salt_label.as_bytes()].concat())
+}
+
+/// Where a ciphertext belongs: its salt label and logical file name
+///
+/// v4/v5 put both (and the version byte) in the outer layer's associated
+/// data, so an .enc renamed to another target no longer decrypts.
+#[derive(Clone, Copy)]
+struct Binding<'a> {
+    salt_label: &'a str,
+    /// Plaintext name without `.enc` / `.git.enc`, e.g. `rules-index.json`
+    name: &'a str,
+}
+
+impl<'a> Binding<'a> {
+    fn new(salt_label: &'a str, name: &'a str) -> Self {
+        Self { salt_label, name }
+    }
+
+    /// `BINDING_CONTEXT ‖ version ‖ salt label ‖ 0x00 ‖ name`
+    fn aad(&self, version: u8) -> Vec<u8> {
+        [BINDING_CONTEXT, &[version], self.salt_label.as_bytes(), b"\0", self.name.as_bytes()].concat()
+    }
+}
+
+/// Logical name of an encrypted file: `rules-index.json.git.enc` → `rules-index.json`
+fn logical_name(path: &Path) -> String {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let name = file_name.strip_suffix(".enc").unwrap_or(&file_name);
+    name.strip_suffix(".git").unwrap_or(name).to_string()
+}
+
+/// A header that names a file or salt must name this one; headers from before either was recorded pass
+fn check_bound_name(header: &Header, binding: Binding) -> Result<()> {
+    if let Some(name) = header.file_name.as_deref().filter(|name| *name != binding.name) {
+        bail!("ciphertext belongs to {}, not {} — file renamed or swapped", name, binding.name);
+    }
+    if let Some(salt) = header.salt_label.as_deref().filter(|salt| *salt != binding.salt_label) {
+        bail!(
+            "ciphertext is sealed under salt label {:?}, not {:?} — pass --salt-label {}",
+            salt::label_of(salt),
+            salt::label_of(binding.salt_label),
+            salt::label_of(salt)
+        );
+    }
+    Ok(())
+}
+
+fn compute_hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC init");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// HMAC key of a v5 file, expanded from its outer layer key: salted per file
+/// like the layers, and no cheaper to guess at than opening the file
+fn layer_mac_key(outer_key: &SecretKey) -> Result<SecretKey> {
+    let mut key = SecretKey::zeroed();
+    Hkdf::<Sha256>::new(None, &**outer_key)
+        .expand(LAYER_MAC_INFO, &mut *key)
+        .map_err(|e| anyhow::anyhow!("HKDF expand: {}", e))?;
+    Ok(key)
+}
+
+// ═══════════════════════════════════════════
+// V4 Multi-Layer Encryption (3 layers)
+// ═══════════════════════════════════════════
+
+/// Argon2id key of one layer of `open_layers`: steps the progress bar and
+/// logs how long the derivation took (`-v`)
+fn derive_layer_key(passphrase: &[u8], salt: &[u8], kdf: &KdfParams, layer: &'static str) -> Result<SecretKey> {
+    let key = timed_layer_key(&kdf_input(passphrase), salt, kdf, layer)?;
+    progress::layer_keyed(layer);
+    Ok(key)
+}
+
+/// `argon2_key` for `layer`, logging how long it took (`-v`)
+fn timed_layer_key(input: &[u8], salt: &[u8], kdf: &KdfParams, layer: &'static str) -> Result<SecretKey> {
+    let started = Instant::now();
+    let key = argon2_key(input, salt, kdf)?;
+    let ms = started.elapsed().as_millis() as u64;
+    debug!(layer, ms, memory_kib = kdf.m_cost(), iterations = kdf.t_cost(), "      ⏱️  {} layer keyed in {} ms", layer, ms);
+    Ok(key)
+}
+
+/// Key of the outer layer sealed under `outer_salt`
+fn outer_layer_key(passphrase: &[u8], salt_label: &str, outer_salt: &[u8], kdf: &KdfParams) -> Result<SecretKey> {
+    derive_layer_key(&layer_passphrase(passphrase, "outer", salt_label), outer_salt, kdf, "outer")
+}
+
+/// Inner → middle → outer layer in the AEADs of `suite` (AES-GCM → ChaCha20 →
+/// AES-GCM by default); `aad` binds the outer layer to any metadata stored in
+/// front of it. Returns the outer salt, ciphertext and key.
+///
+/// Every salt is known before anything is sealed, so the three keys are
+/// derived at once on the rayon pool: a file costs about one Argon2id run of
+/// wall-clock time, for three runs' memory. The inputs are built here, as the
+/// keyfile, pepper, RNG override and progress bar belong to this thread, and
+/// salts and nonces are drawn in the order the test vectors list them.
+fn seal_layers(
+    passphrase: &[u8],
+    salt_label: &str,
+    plaintext: &[u8],
+    kdf: &KdfParams,
+    aad: &[u8],
+    suite: Suite,
+) -> Result<([u8; ARGON2_SALT_LEN], Vec<u8>, SecretKey)> {
+    let [inner_cipher, middle_cipher, outer_cipher] = suite.layers()?;
+    let inner_salt = key_cache::layer_salt("inner", salt_label).unwrap_or_else(random_bytes);
+    let inner_nonce = inner_cipher.draw_nonce();
+    let middle_salt = key_cache::layer_salt("middle", salt_label).unwrap_or_else(random_bytes);
+    let middle_nonce = middle_cipher.draw_nonce();
+    let outer_salt = key_cache::layer_salt("outer", salt_label).unwrap_or_else(random_bytes);
+    let outer_nonce = outer_cipher.draw_nonce();
+
+    let inner_input = kdf_input(passphrase);
+    let middle_input = kdf_input(&layer_passphrase(passphrase, "middle", salt_label));
+    let outer_input = kdf_input(&layer_passphrase(passphrase, "outer", salt_label));
+    let (inner_key, (middle_key, outer_key)) = rayon::join(
+        || timed_layer_key(&inner_input, &inner_salt, kdf, "inner"),
+        || {
+            rayon::join(
+                || timed_layer_key(&middle_input, &middle_salt, kdf, "middle"),
+                || timed_layer_key(&outer_input, &outer_salt, kdf, "outer"),
+            )
+        },
+    );
+    let (inner_key, middle_key, outer_key) = (inner_key?, middle_key?, outer_key?);
+    for layer in ["inner", "middle", "outer"] {
+        progress::layer_keyed(layer);
+    }
+
+    let inner_enc = inner_cipher.seal(&inner_key, &inner_nonce, plaintext, &[])?;
+    let mut inner_payload = Zeroizing::new(Vec::with_capacity(ARGON2_SALT_LEN + inner_enc.len()));
+    inner_payload.extend_from_slice(&inner_salt);
+    inner_payload.extend_from_slice(&inner_enc);
+
+    let middle_enc = middle_cipher.seal(&middle_key, &middle_nonce, &inner_payload, &[])?;
+    let mut middle_payload = Zeroizing::new(Vec::with_capacity(ARGON2_SALT_LEN + middle_enc.len()));
+    middle_payload.extend_from_slice(&middle_salt);
+    middle_payload.extend_from_slice(&middle_enc);
+
+    let outer_enc = outer_cipher.seal(&outer_key, &outer_nonce, &middle_payload, aad)?;
+    Ok((outer_salt, outer_enc, outer_key))
+}
+
+/// Reverse of `seal_layers`, under the outer key from `outer_layer_key`; each
+/// of `aads` is tried on the outer layer in turn
+fn open_layers(
+    passphrase: &[u8],
+    salt_label: &str,
+    outer_key: &SecretKey,
+    outer_enc: &[u8],
+    kdf: &KdfParams,
+    aads: &[&[u8]],
+    suite: Suite,
+) -> Result<Vec<u8>> {
+    let [inner_cipher, middle_cipher, outer_cipher] = suite.layers()?;
+    let middle_payload = aads
+        .iter()
+        .find_map(|aad| outer_cipher.open(outer_key, outer_enc, aad).ok())
+        .map(Zeroizing::new)
+        .context("outer layer — wrong passphrase, --kdf-* settings, or a file renamed from another target")?;
+
+    if middle_payload.len() < ARGON2_SALT_LEN + middle_cipher.nonce_len() + 16 {
+        bail!("middle payload too short");
+    }
+    let middle_salt = &middle_payload[..ARGON2_SALT_LEN];
+    let middle_enc = &middle_payload[ARGON2_SALT_LEN..];
+    let middle_passphrase = layer_passphrase(passphrase, "middle", salt_label);
+    let middle_key = derive_layer_key(&middle_passphrase, middle_salt, kdf, "middle")?;
+    let inner_payload = Zeroizing::new(middle_cipher.open(&middle_key, middle_enc, &[])?);
+    nonces::opened("middle", Some(middle_salt), middle_enc, middle_cipher.nonce_len());
+
+    if inner_payload.len() < ARGON2_SALT_LEN + inner_cipher.nonce_len() + 16 {
+        bail!("inner payload too short");
+    }
+    let inner_salt = &inner_payload[..ARGON2_SALT_LEN];
+    let inner_enc = &inner_payload[ARGON2_SALT_LEN..];
+    let inner_key = derive_layer_key(passphrase, inner_salt, kdf, "inner")?;
+    let plaintext = inner_cipher.open(&inner_key, inner_enc, &[])?;
+    nonces::opened("inner", Some(inner_salt), inner_enc, inner_cipher.nonce_len());
+    Ok(plaintext)
+}
+
+fn v4_encrypt(passphrase: &[u8], binding: Binding, plaintext: &[u8], kdf: &KdfParams) -> Result<Vec<u8>> {
+    log_file("Sealing", binding, plaintext.len(), Some(VERSION_V4));
+    let preamble = Preamble::new(VERSION_V4, kdf, false, true, 0, plaintext.len() + LAYERS_OVERHEAD);
+    let mut output = preamble.encode().to_vec();
+    let aad = [output.as_slice(), &binding.aad(VERSION_V4)].concat();
+    let (outer_salt, outer_enc, _) = seal_layers(passphrase, binding.salt_label, plaintext, kdf, &aad, Suite::default())?;
+
+    output.extend_from_slice(&outer_salt);
+    output.extend_from_slice(&outer_enc);
+    debug_assert_eq!(output.len(), preamble::LEN + preamble.payload_len);
+    let hmac_data = compute_hmac(&derive_embedded_key(), &output);
+    output.extend_from_slice(&hmac_data);
+    Ok(output)
+}
+
+/// Structural and outer-HMAC check of a v4 blob; needs no passphrase.
+/// Returns the preamble of a framed file and the offset of the outer salt
+fn v4_check_integrity(data: &[u8]) -> Result<(Option<Preamble>, usize)> {
+    if preamble::version(data) != Some(VERSION_V4) {
+        bail!("not v4 format");
+    }
+    let (preamble, prefix) = preamble::split(data)?;
+    if data.len() < prefix + ARGON2_SALT_LEN + GCM_NONCE_LEN + 16 + 32 {
+        bail!("v4 data too short");
+    }
+
+    // Bare v4 files only MAC the outer ciphertext; framed ones MAC everything before the HMAC
+    let hmac_offset = data.len() - 32;
+    let covered = match preamble {
+        Some(_) => &data[..hmac_offset],
+        None => &data[prefix + ARGON2_SALT_LEN..hmac_offset],
+    };
+    if data[hmac_offset..] != compute_hmac(&derive_embedded_key(), covered)[..] {
+        bail!("HMAC verification failed — data tampered or wrong binary");
+    }
+    Ok((preamble, prefix))
+}
+
+fn v4_decrypt(passphrase: &[u8], binding: Binding, data: &[u8], kdf: &KdfParams) -> Result<Vec<u8>> {
+    let (preamble, prefix) = v4_check_integrity(data)?;
+    let hmac_offset = data.len() - 32;
+    let outer_salt = &data[prefix..prefix + ARGON2_SALT_LEN];
+    let outer_enc = &data[prefix + ARGON2_SALT_LEN..hmac_offset];
+    match preamble {
+        Some(preamble) => {
+            let aad = [&data[..prefix], &binding.aad(VERSION_V4)].concat();
+            let outer_key = outer_layer_key(passphrase, binding.salt_label, outer_salt, &preamble.kdf)?;
+            open_layers(passphrase, binding.salt_label, &outer_key, outer_enc, &preamble.kdf, &[&aad], preamble.suite)
+        }
+        None => {
+            // A bare v4 file cannot say whether it is bound; files written before binding used no associated data
+            let aad = binding.aad(VERSION_V4);
+            let outer_key = outer_layer_key(passphrase, binding.salt_label, outer_salt, kdf)?;
+            open_layers(passphrase, binding.salt_label, &outer_key, outer_enc, kdf, &[&aad, &[]], Suite::default())
+        }
+    }
+}
+
+// ═══════════════════════════════════════════
+// V5 Tagged Payloads (binary-safe, same 3 layers)
+// ═══════════════════════════════════════════
+//
+// preamble (preamble.rs) | header (header.rs) | outer_salt | outer AES-GCM | HMAC-SHA256
+// Files from before the preamble start with a bare 0x05 instead.
+// Everything before the outer salt, followed by the binding, is the outer
+// layer's associated data, and the HMAC covers every byte before it. New
+// files key the HMAC from the outer layer key and say so in the header;
+// older ones use the compiled-in seed or pepper, still accepted, or a
+// fixed-salt derivation of the passphrase, which is no longer checked: the
+// outer layer authenticates those bytes as well. The header names
+// the file, so a swap is reported before any key derivation.
+// A compressed payload is compressed before the inner layer and
+// decompressed after opening it. The cipher suite, in both the preamble and the header,
+// picks each layer's AEAD (preamble.rs); the default is the v4 triple.
+// XChaCha20 layers carry a 24-byte nonce in place of the 12-byte one, so
+// the payload length follows the suite (`Suite::overhead`).
+
+fn v5_encrypt(passphrase: &[u8], binding: Binding, plaintext: &[u8], header: &Header, kdf: &KdfParams) -> Result<Vec<u8>> {
+    log_file("Sealing", binding, plaintext.len(), Some(VERSION_V5));
+    let key_id = verify_token::salted_key_id(passphrase)?;
+    let digest_key = verify_token::digest_key(passphrase)?;
+    let pepper = pepper::configured();
+    let keyfile = keyfile::configured();
+    let header = header
+        .bound_to(binding.name)
+        .with_salt_label(binding.salt_label)
+        .stamped(plaintext, digest_key.as_ref())
+        .keyed(key_id.as_deref())
+        .peppered(pepper.map(|p| p.id.as_str()))
+        .with_keyfile(keyfile.map(|k| k.id.as_str()))
+        .with_layer_mac(true)
+        .with_recovery(recovery::escrow(passphrase)?);
+    let metadata = header.encode();
+    let packed = Zeroizing::new(header.compression.compress(plaintext)?);
+    let compressed = header.compression != Compression::None;
+    let payload_len = packed.len() + header.suite.overhead()?;
+    let mut preamble = Preamble::new(VERSION_V5, kdf, compressed, true, metadata.len(), payload_len);
+    preamble.suite = header.suite;
+    let mut output = preamble.encode().to_vec();
+    output.extend_from_slice(&metadata);
+    let aad = [output.as_slice(), &binding.aad(VERSION_V5)].concat();
+    pepper::with(pepper, || {
+        let (outer_salt, outer_enc, outer_key) = keyfile::with(keyfile, || {
+            seal_layers(passphrase, binding.salt_label, &packed, kdf, &aad, header.suite)
+        })?;
+
+        output.extend_from_slice(&outer_salt);
+        output.extend_from_slice(&outer_enc);
+        debug_assert_eq!(output.len(), preamble::LEN + preamble.metadata_len + preamble.payload_len);
+        let hmac_data = compute_hmac(&*layer_mac_key(&outer_key)?, &output);
+        output.extend_from_slice(&hmac_data);
+        Ok(output)
+    })
+}
+
+/// Structural and HMAC check of a v5 blob; returns the preamble of a framed
+/// file, the header and its end offset. An HMAC keyed from the outer layer is
+/// left to `v5_check_mac`
+fn v5_check_integrity(data: &[u8]) -> Result<(Option<Preamble>, Header, usize)> {
+    if preamble::version(data) != Some(VERSION_V5) {
+        bail!("not v5 format");
+    }
+    let (preamble, prefix) = preamble::split(data)?;
+    let (header, header_len) = Header::parse(&data[prefix..])?;
+    if let Some(preamble) = &preamble {
+        preamble.check_metadata(&header, header_len)?;
+    }
+    let body_offset = prefix + header_len;
+    if data.len() < body_offset + ARGON2_SALT_LEN + GCM_NONCE_LEN + 16 + 32 {
+        bail!("v5 data too short");
+    }
+
+    if header.layer_mac || header.passphrase_mac {
+        return Ok((preamble, header, body_offset));
+    }
+    let hmac_offset = data.len() - 32;
+    let pepper = pepper::for_header(&header)?;
+    let computed_hmac = pepper::with(pepper, || compute_hmac(&second_factor(), &data[..hmac_offset]));
+    if data[hmac_offset..] != computed_hmac[..] {
+        bail!("HMAC verification failed — data tampered or wrong binary");
+    }
+    Ok((preamble, header, body_offset))
+}
+
+/// The HMAC check `v5_check_integrity` leaves to holders of the passphrase, for
+/// files whose header keys it from the outer layer; derives that layer's key
+fn v5_check_mac(passphrase: &[u8], data: &[u8]) -> Result<()> {
+    let (preamble, header, body_offset) = v5_check_integrity(data)?;
+    if !header.layer_mac {
+        return Ok(());
+    }
+    let (Some(preamble), Some(salt_label)) = (preamble, header.salt_label.as_deref()) else {
+        bail!("v5 HMAC keyed from the outer layer, but no preamble or salt label to derive it with");
+    };
+    let outer_salt = &data[body_offset..body_offset + ARGON2_SALT_LEN];
+    let pepper = pepper::for_header(&header)?;
+    let keyfile = keyfile::for_header(&header)?;
+    let outer_key = pepper::with(pepper, || {
+        keyfile::with(keyfile, || outer_layer_key(passphrase, salt_label, outer_salt, &preamble.kdf))
+    })?;
+    check_layer_mac(&outer_key, data)
+}
+
+fn check_layer_mac(outer_key: &SecretKey, data: &[u8]) -> Result<()> {
+    let hmac_offset = data.len() - 32;
+    if data[hmac_offset..] != compute_hmac(&*layer_mac_key(outer_key)?, &data[..hmac_offset])[..] {
+        bail!("HMAC verification failed — wrong passphrase or data tampered");
+    }
+    Ok(())
+}
+
+fn v5_decrypt(passphrase: &[u8], binding: Binding, data: &[u8], kdf: &KdfParams) -> Result<(Header, Vec<u8>)> {
+    let (preamble, header, body_offset) = v5_check_integrity(data)?;
+    let kdf = preamble.as_ref().map_or(kdf, |p| &p.kdf);
+    check_bound_name(&header, binding)?;
+    let hmac_offset = data.len() - 32;
+    let outer_salt = &data[body_offset..body_offset + ARGON2_SALT_LEN];
+    let outer_enc = &data[body_offset + ARGON2_SALT_LEN..hmac_offset];
+    let aad = match header.file_name {
+        Some(_) => [&data[..body_offset], &binding.aad(VERSION_V5)].concat(),
+        None => data[..body_offset].to_vec(),
+    };
+    let pepper = pepper::for_header(&header)?;
+    let keyfile = keyfile::for_header(&header)?;
+    let packed = pepper::with(pepper, || {
+        keyfile::with(keyfile, || {
+            let outer_key = outer_layer_key(passphrase, binding.salt_label, outer_salt, kdf)?;
+            if header.layer_mac {
+                check_layer_mac(&outer_key, data)?;
+            }
+            open_layers(passphrase, binding.salt_label, &outer_key, outer_enc, kdf, &[&aad], header.suite)
+        })
+    })?;
+    let plain = header.compression.decompress(&Zeroizing::new(packed))?;
+    Ok((header, plain))
+}
+
+/// Passphrase-free check for any current-format (v4–v6) blob
+fn check_integrity(data: &[u8]) -> Result<()> {
+    match preamble::version(data) {
+        Some(VERSION_V5) => v5_check_integrity(data).map(|_| ()),
+        Some(VERSION_V6) => recipient::check_integrity(data).map(|_| ()),
+        _ => v4_check_integrity(data).map(|_| ()),
+    }
+}
+
+// ═══════════════════════════════════════════
+// V3 Legacy Decryption (Node.js multi-layer)
+// ═══════════════════════════════════════════
+
+/// Inner CBC under scrypt(passphrase, salt label), wrapped in CBC under scrypt(passphrase ‖ "-outer", OUTER_SALT)
+///
+/// Only for `export-legacy` and test vectors: CBC without a MAC is malleable.
+fn v3_encrypt(passphrase: &[u8], salt: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let inner_key = derive_key_scrypt(passphrase, salt)?;
+    let inner_enc = encrypt_aes_cbc(&inner_key, plaintext);
+    let outer_key = derive_key_scrypt(&[passphrase, b"-outer"].concat(), OUTER_SALT)?;
+    Ok(encrypt_aes_cbc(&outer_key, &inner_enc))
+}
+
+fn v3_decrypt(passphrase: &[u8], salt: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let outer_key = derive_key_scrypt(&[passphrase, b"-outer"].concat(), OUTER_SALT)?;
+    let inner_enc = decrypt_aes_cbc(&outer_key, data)?;
+    let inner_key = derive_key_scrypt(passphrase, salt)?;
+    decrypt_aes_cbc(&inner_key, &inner_enc)
+}
+
+fn v2_decrypt(passphrase: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let key = derive_key_scrypt(passphrase, "violet-soul-salt")?;
+    decrypt_aes_cbc(&key, data)
+}
+
+/// Decrypt any supported version; only v5/v6 carry a header, older formats
+/// get a bare JSON one. For v6, `passphrase` may also hold identity file contents
+fn decrypt_payload(passphrase: &[u8], binding: Binding, data: &[u8], kdf: &KdfParams) -> Result<(Header, Vec<u8>)> {
+    log_file("Opening", binding, data.len(), preamble::version(data));
+    let json = || Header::new(ContentType::json(), Compression::None);
+    match preamble::version(data) {
+        Some(VERSION_V6) => {
+            let (header, plain) = recipient::decrypt(passphrase, binding.salt_label, data, kdf)?;
+            check_bound_name(&header, binding)?;
+            return Ok((header, plain));
+        }
+        Some(VERSION_V5) => return v5_decrypt(passphrase, binding, data, kdf),
+        Some(VERSION_V4) => return Ok((json(), v4_decrypt(passphrase, binding, data, kdf)?)),
+        _ => {}
+    }
+    // CBC has no authentication; valid UTF-8 is what tells a right key from a lucky padding
+    if let Ok(plain) = v3_decrypt(passphrase, binding.salt_label, data) {
+        if std::str::from_utf8(&plain).is_ok() {
+            return Ok((json(), plain));
+        }
+    }
+    if let Ok(plain) = v2_decrypt(passphrase, data) {
+        if std::str::from_utf8(&plain).is_ok() {
+            return Ok((json(), plain));
+        }
+    }
+    bail!("decryption failed — tried v5, v4, v3, v2")
+}
+
+/// `-v` detail of a file about to be sealed or opened; `version` is None for legacy (v2/v3) data
+fn log_file(action: &str, binding: Binding, bytes: usize, version: Option<u8>) {
+    let format = version.map_or_else(|| "v2/v3".to_string(), |v| format!("v{}", v));
+    let salt_label = salt::label_of(binding.salt_label);
+    debug!(
+        file = binding.name,
+        format = %format,
+        bytes,
+        salt_label,
+        "  📄 {} {} — {}, {} bytes, salt label {}",
+        action,
+        binding.name,
+        format,
+        bytes,
+        salt_label
+    );
+}
+
+/// Plaintext bytes of any supported version, whatever the content type
+fn auto_decrypt(passphrase: &[u8], binding: Binding, data: &[u8], kdf: &KdfParams) -> Result<Zeroizing<Vec<u8>>> {
+    let (_, plain) = decrypt_payload(passphrase, binding, data, kdf)?;
+    Ok(Zeroizing::new(plain))
+}
+
+/// `auto_decrypt` for callers that want text; binary content types are refused
+fn auto_decrypt_text(passphrase: &[u8], binding: Binding, data: &[u8], kdf: &KdfParams) -> Result<Zeroizing<String>> {
+    as_text(decrypt_payload(passphrase, binding, data, kdf)?)
+}
+
+/// A decrypted payload as text; binary content types are refused
+fn as_text((Header { content_type, .. }, plain): (Header, Vec<u8>)) -> Result<Zeroizing<String>> {
+    let plain = Zeroizing::new(plain);
+    if !content_type.is_text() {
+        bail!("payload is {} (binary) — use decrypt-asset or decrypt-file --output / --raw", content_type);
+    }
+    let text = std::str::from_utf8(&plain).with_context(|| format!("{} UTF-8 decode", content_type))?;
+    Ok(Zeroizing::new(text.to_string()))
+}
+
+// ═══════════════════════════════════════════
+// Dry Run Reporting
+// ═══════════════════════════════════════════
+
+fn print_dry_run_banner(dry_run: bool) {
+    if dry_run {
+        info!("🧪 Dry run — nothing will be written.");
+    }
+}
+
+/// Version and size of file contents, for dry-run before/after reports
+fn describe_blob(path: &Path, data: &[u8]) -> String {
+    match preamble::version(data) {
+        Some(version) if check_integrity(data).is_ok() => {
+            format!("v{}, {} bytes", version, data.len())
+        }
+        _ if path.extension().is_some_and(|ext| ext == "enc") => format!("legacy v2/v3, {} bytes", data.len()),
+        _ => format!("plaintext, {} bytes", data.len()),
+    }
+}
+
+/// `encrypted 20261017T040912Z from rules-index.json`, from whatever the header records
+fn describe_stamp(header: &Header) -> String {
+    let when = match header.encrypted_at {
+        Some(at) => backup::utc_timestamp(UNIX_EPOCH + Duration::from_secs(at)),
+        None => "at an unrecorded time".to_string(),
+    };
+    match &header.file_name {
+        Some(name) => format!("encrypted {} from {}", when, name),
+        None => format!("encrypted {}", when),
+    }
+}
+
+/// Header of a v5/v6 blob after the keyless integrity check; None for v4
+fn read_header(data: &[u8]) -> Result<Option<Header>> {
+    match preamble::version(data) {
+        Some(VERSION_V5) => v5_check_integrity(data).map(|(_, header, _)| Some(header)),
+        Some(VERSION_V6) => recipient::check_integrity(data).map(|(header, _)| Some(header)),
+        _ => v4_check_integrity(data).map(|_| None),
+    }
+}
+
+/// Back up `path` before it is overwritten, when `--backup` is on
+fn backup_before_write(path: &Path, retention: Option<usize>, dry_run: bool) -> Result<()> {
+    let Some(keep) = retention else {
+        return Ok(());
+    };
+    if !path.exists() {
+        return Ok(());
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    if dry_run {
+        info!("      + backup {}.bak-<timestamp> (keeping {})", name, keep);
+    } else if let Some(dest) = backup::backup_file(path, keep)? {
+        info!("  💾 {} → {}", name, dest.file_name().unwrap_or_default().to_string_lossy());
+    }
+    Ok(())
+}
+
+/// Print the write a dry run skipped: create vs overwrite, with before/after state
+fn report_planned_write(path: &Path, new: &[u8], with_token: bool) {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    match fs::read(path) {
+        Ok(old) => info!(
+            "  📝 Would overwrite {} ({} → {})",
+            name,
+            describe_blob(path, &old),
+            describe_blob(path, new)
+        ),
+        Err(_) => info!("  📝 Would create {} ({})", name, describe_blob(path, new)),
+    }
+    if with_token {
+        info!("      + {}{}", name, verify_token::TOKEN_SUFFIX);
+    }
+    if detached_mac::writes_enabled() {
+        info!("      + {}{}", name, detached_mac::MAC_SUFFIX);
+    }
+}
+
+// ═══════════════════════════════════════════
+// CLI Command Handlers
+// ═══════════════════════════════════════════
+
+/// Who can open a freshly written local .enc
+enum Sealer {
+    /// Passphrase layers (v5)
+    Passphrase(Passphrase),
+    /// Passphrase layers (v5), sealed by a running `agent`
+    Agent(agent::Client),
+    /// v6 envelope: any one of the public keys or passphrases opens it
+    Recipients(Vec<Recipient>),
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cmd_encrypt_local(
+    sealer: &Sealer,
+    kdf: &KdfParams,
+    data_dir: &Path,
+    profile: &Profile,
+    targets: &[&str],
+    header: &Header,
+    key_id: Option<&str>,
+    backup: Option<usize>,
+    jobs: usize,
+    force: bool,
+    dry_run: bool,
+) -> Result<()> {
+    match (sealer, header.suite) {
+        (Sealer::Recipients(r), _) => {
+            info!("🔐 Encrypting local files (v6, {} recipient(s), {})...", r.len(), header.compression)
+        }
+        (_, Suite::PassphraseLayers) => {
+            info!("🔐 Encrypting local files (v5 multi-layer, {})...", header.compression)
+        }
+        (_, suite) => info!("🔐 Encrypting local files (v5 {} layers, {})...", suite, header.compression),
+    }
+    print_dry_run_banner(dry_run);
+    // Files are sealed in parallel, each deriving its three layer keys at once; writes
+    // and output stay in target order. An .enc that already holds the plaintext is
+    // kept as it is (None), so its bytes and mtime only change with the content
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs).build().context("start encryption threads")?;
+    let sealed: Vec<Option<Result<Option<Vec<u8>>>>> = pool.install(|| {
+        targets
+            .par_iter()
+            .map(|&name| {
+                let json_path = data_dir.join(name);
+                let enc_path = data_dir.join(profile.output(name));
+                json_path.exists().then(|| {
+                    progress::file(name, "sealing", || {
+                        let plaintext = Zeroizing::new(fs::read(&json_path).context("read JSON")?);
+                        let binding = Binding::new(&profile.salt, name);
+                        if !force && still_current(sealer, binding, &plaintext, header, &enc_path, kdf) {
+                            return Ok(None);
+                        }
+                        let header = header.with_plaintext_mtime(file_mtime(&json_path));
+                        seal_local(sealer, &profile.salt, name, &plaintext, &header, kdf).map(Some)
+                    })
+                })
+            })
+            .collect()
+    });
+    let mut failed = 0;
+    for (&name, sealed) in targets.iter().zip(sealed) {
+        let enc_path = data_dir.join(profile.output(name));
+        let encrypted = match sealed {
+            None => {
+                info!("  ⏭️  Skip (not found): {}", name);
+                continue;
+            }
+            Some(Ok(Some(encrypted))) => encrypted,
+            Some(Ok(None)) => {
+                if !dry_run {
+                    let existing = fs::read(&enc_path).context("read .enc")?;
+                    write_local_sidecars(&enc_path, &existing, key_id)?;
+                }
+                info!("  ✅ {} unchanged — {} kept as it is", name, profile.output(name));
+                continue;
+            }
+            Some(Err(e)) => {
+                error!("  ❌ {}: {:#}", name, e);
+                failed += 1;
+                continue;
+            }
+        };
+        if dry_run {
+            report_planned_write(&enc_path, &encrypted, key_id.is_some());
+            backup_before_write(&enc_path, backup, dry_run)?;
+            continue;
+        }
+        write_local_enc(&enc_path, &encrypted, key_id, backup)?;
+        info!("  ✅ {} → {} ({} bytes)", name, profile.output(name), encrypted.len());
+    }
+    if failed > 0 {
+        bail!("{} file(s) could not be encrypted; the others were {}", failed, if dry_run { "checked" } else { "written" });
+    }
+    if dry_run {
+        return Ok(());
+    }
+    let key_id = match sealer {
+        Sealer::Passphrase(key) => Some(verify_token::derive_key_id(key)?),
+        Sealer::Agent(agent) => Some(agent.key_id()?),
+        Sealer::Recipients(_) => None,
+    };
+    if let Some(key_id) = key_id {
+        let generation = keyring::record(data_dir, &key_id, kdf)?;
+        info!("🔐 Local encryption complete (key generation {}).", generation);
+    } else {
+        info!("🔐 Local encryption complete.");
+    }
+    Ok(())
+}
+
+/// Local target JSON as v5, or as v6 for recipients; both carry the header
+/// `status` reads. `header` gives the compression and suite, `salt` the profile's key domain
+fn seal_local(
+    sealer: &Sealer,
+    salt: &str,
+    name: &str,
+    plaintext: &[u8],
+    header: &Header,
+    kdf: &KdfParams,
+) -> Result<Vec<u8>> {
+    let header = header.bound_to(name);
+    match sealer {
+        Sealer::Recipients(recipients) => recipient::encrypt(recipients, salt, plaintext, &header, kdf),
+        Sealer::Passphrase(key) => v5_encrypt(key, Binding::new(salt, name), plaintext, &header, kdf),
+        Sealer::Agent(agent) => agent.seal(Binding::new(salt, name), plaintext, &header, kdf),
+    }
+}
+
+/// Whether the .enc at `enc_path` already holds `plaintext`, sealed the way
+/// `header` and the current key, pepper and keyfile would seal it. The header's
+/// keyed plaintext digest decides, once the HMAC keyed from the outer layer has
+/// vouched for it; an unchanged file costs that one layer key (files sharing
+/// salts under `--fast`, one per run). Through an agent the file is opened
+/// instead. Envelopes are always rewritten, their recipients cannot be read
+/// back, and so are headers with a bare SHA-256
+fn still_current(
+    sealer: &Sealer,
+    binding: Binding,
+    plaintext: &[u8],
+    header: &Header,
+    enc_path: &Path,
+    kdf: &KdfParams,
+) -> bool {
+    if matches!(sealer, Sealer::Recipients(_)) {
+        return false;
+    }
+    let Ok(data) = fs::read(enc_path) else {
+        return false;
+    };
+    let (Ok(Some(preamble)), Ok(Some(existing))) = (Preamble::parse(&data), read_header(&data)) else {
+        return false;
+    };
+    let same_form = preamble.version == VERSION_V5
+        && preamble.kdf == *kdf
+        && existing.plaintext_sha256.is_none()
+        && existing.compression == header.compression
+        && existing.suite == header.suite
+        && existing.layer_mac
+        && existing.salt_label.as_deref() == Some(binding.salt_label)
+        && existing.pepper_id.as_deref() == pepper::configured().map(|p| p.id.as_str())
+        && existing.keyfile_id.as_deref() == keyfile::configured().map(|k| k.id.as_str())
+        && existing.recovery.as_deref().map(recovery::entry_id) == recovery::configured_id();
+    if !same_form {
+        return false;
+    }
+    match sealer {
+        Sealer::Passphrase(key) => {
+            let digest_key = verify_token::digest_key(key).ok().flatten();
+            existing.matches_plaintext(plaintext, digest_key.as_ref()) == Some(true)
+                && check_bound_name(&existing, binding).is_ok()
+                && v5_check_mac(key, &data).is_ok()
+        }
+        Sealer::Agent(agent) => {
+            agent.open(binding, &data, kdf).is_ok_and(|(_, opened)| *Zeroizing::new(opened) == *plaintext)
+        }
+        Sealer::Recipients(_) => false,
+    }
+}
+
+/// Back up, atomically write, token and MAC a freshly encrypted .enc
+fn write_local_enc(enc_path: &Path, encrypted: &[u8], key_id: Option<&str>, backup: Option<usize>) -> Result<()> {
+    backup_before_write(enc_path, backup, false)?;
+    write_atomic(enc_path, encrypted).context("write .enc")?;
+    write_local_sidecars(enc_path, encrypted, key_id)
+}
+
+/// Token and MAC of an .enc, when asked for
+fn write_local_sidecars(enc_path: &Path, encrypted: &[u8], key_id: Option<&str>) -> Result<()> {
+    if let Some(id) = key_id {
+        VerifyToken::for_ciphertext(id, encrypted).write(enc_path)?;
+    }
+    detached_mac::write(enc_path, encrypted)
+}
+
+/// Modification time of `path`, for the header to record
+fn file_mtime(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Give `path` the plaintext mtime the header of `data` records, if it records one;
+/// a file system that refuses is warned about, the decrypted file stays
+fn restore_mtime(path: &Path, data: &[u8]) {
+    let Some(mtime) = read_header(data).ok().flatten().and_then(|header| header.plaintext_mtime) else {
+        return;
+    };
+    let restored = fs::File::options().write(true).open(path).and_then(|file| file.set_modified(mtime));
+    if let Err(e) = restored {
+        warn!("  ⚠️  Could not restore the modification time of {:?}: {}", path, e);
+    }
+}
+
+fn cmd_decrypt_local(
+    key: &Keyholder,
+    kdf: &KdfParams,
+    data_dir: &Path,
+    profile: &Profile,
+    targets: &[&str],
+    keep_modified: Option<Option<&str>>,
+    dry_run: bool,
+) -> Result<()> {
+    info!("🔓 Decrypting {} *{} files (auto-detect v2–v6)...", profile.name, profile.suffix);
+    print_dry_run_banner(dry_run);
+    let digest_key = keep_modified.map(|_| key.digest_key()).transpose()?.flatten();
+    for &name in targets {
+        let enc_name = profile.output(name);
+        let enc_path = data_dir.join(&enc_name);
+        if !enc_path.exists() {
+            info!("  ⏭️  Skip (not found): {}", enc_name);
+            continue;
+        }
+        let data = fs::read(&enc_path).context("read .enc")?;
+        let json_path = data_dir.join(name);
+        let binding = Binding::new(&profile.salt, name);
+        // With --keep-modified, the .json on disk unless it is the plaintext of the .enc before a checkout
+        let current = match keep_modified {
+            Some(previous) => fs::read(&json_path).ok().map(Zeroizing::new).filter(|current| {
+                let before = previous.and_then(|rev| hooks::enc_at_revision(data_dir, rev, &enc_name));
+                !before.is_some_and(|before| holds_plaintext(key, binding, &before, current, kdf, digest_key.as_ref()))
+            }),
+            None => None,
+        };
+        let recorded = current
+            .as_ref()
+            .and_then(|current| read_header(&data).ok()??.matches_plaintext(current, digest_key.as_ref()));
+        match recorded {
+            Some(true) => {
+                info!("  ✅ {} unchanged — {} kept as it is", enc_name, name);
+                continue;
+            }
+            Some(false) => {
+                warn_modified(name, &enc_name);
+                continue;
+            }
+            None => {}
+        }
+        let opened = progress::file(name, "opening", || as_text(key.open(binding, &data, kdf)?));
+        let json_str = match opened {
+            Ok(json_str) => json_str,
+            Err(e) => {
+                return Err(explain_key_mismatch(e, key, &data, data_dir).context(format!("decrypt {}", enc_name)));
+            }
+        };
+        // Files from before plaintext hashes were recorded are compared once opened
+        if current.as_ref().is_some_and(|current| current.as_slice() != json_str.as_bytes()) {
+            warn_modified(name, &enc_name);
+            continue;
+        }
+        if dry_run {
+            report_planned_write(&json_path, json_str.as_bytes(), false);
+            continue;
+        }
+        write_atomic(&json_path, json_str.as_bytes()).context("write JSON")?;
+        restore_mtime(&json_path, &data);
+        info!("  ✅ {} → {} ({} bytes)", enc_name, name, json_str.len());
+    }
+    if !dry_run {
+        info!("🔓 Local decryption complete.");
+    }
+    Ok(())
+}
+
+/// Whether the .enc `data` holds `plaintext`: by its header's digest, or opened when that cannot say
+fn holds_plaintext(
+    key: &Keyholder,
+    binding: Binding,
+    data: &[u8],
+    plaintext: &[u8],
+    kdf: &KdfParams,
+    digest_key: Option<&SecretKey>,
+) -> bool {
+    match read_header(data).ok().flatten().and_then(|header| header.matches_plaintext(plaintext, digest_key)) {
+        Some(matches) => matches,
+        None => key.open(binding, data, kdf).is_ok_and(|(_, opened)| *Zeroizing::new(opened) == *plaintext),
+    }
+}
+
+/// A .json left alone by `decrypt-local --keep-modified`
+fn warn_modified(name: &str, enc_name: &str) {
+    warn!("  ⚠️  {} differs from {} — kept; encrypt-local saves it, decrypt-local overwrites it", name, enc_name);
+}
+
+/// Name the key generation a file needs when the header's key ID is not the supplied key's
+fn explain_key_mismatch(err: anyhow::Error, key: &Keyholder, data: &[u8], data_dir: &Path) -> anyhow::Error {
+    let Some(file_key_id) = read_header(data).ok().flatten().and_then(|header| header.key_id) else {
+        return err;
+    };
+    let explained = key.key_id().and_then(|supplied| {
+        let keyring = Keyring::load(data_dir)?;
+        Ok(keyring::explain_mismatch(&keyring, &file_key_id, &supplied))
+    });
+    match explained {
+        Ok(Some(reason)) => err.context(reason),
+        _ => err,
+    }
+}
+
+/// Placeholders are v4, or v6 to `envelope` when it names any recipients
+fn cmd_encrypt_git(
+    key: &[u8],
+    kdf: &KdfParams,
+    data_dir: &Path,
+    envelope: &[Recipient],
+    key_id: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    info!("📦 Generating .git.enc placeholders for git...");
+    print_dry_run_banner(dry_run);
+    let placeholder = b"{}";
+    for &name in TARGET_FILES {
+        let encrypted = if envelope.is_empty() {
+            v4_encrypt(key, Binding::new(GIT_SALT, name), placeholder, kdf)?
+        } else {
+            let header = Header::new(ContentType::json(), Compression::None).bound_to(name);
+            recipient::encrypt(envelope, GIT_SALT, placeholder, &header, kdf)?
+        };
+        let git_enc_path = data_dir.join(format!("{}.git.enc", name));
+        if dry_run {
+            report_planned_write(&git_enc_path, &encrypted, key_id.is_some());
+            continue;
+        }
+        write_atomic(&git_enc_path, &encrypted).context("write .git.enc")?;
+        if let Some(id) = key_id {
+            VerifyToken::for_ciphertext(id, &encrypted).write(&git_enc_path)?;
+        }
+        info!("  ✅ {}.git.enc ({} bytes, empty placeholder)", name, encrypted.len());
+    }
+    if !dry_run {
+        info!("📦 Git placeholders generated.");
+    }
+    Ok(())
+}
+
+fn cmd_decrypt_git(key: &Keyholder, kdf: &KdfParams, data_dir: &Path) -> Result<()> {
+    info!("🔍 Verifying .git.enc placeholder decryption...");
+    for &name in TARGET_FILES {
+        let git_enc_path = data_dir.join(format!("{}.git.enc", name));
+        if !git_enc_path.exists() {
+            info!("  ⏭️  Skip (not found): {}.git.enc", name);
+            continue;
+        }
+        let data = fs::read(&git_enc_path).context("read .git.enc")?;
+        let json_str = as_text(key.open(Binding::new(GIT_SALT, name), &data, kdf)?)?;
+        if json_str.trim() == "{}" {
+            info!("  ✅ {}.git.enc → verified (empty placeholder)", name);
+        } else {
+            warn!("  ⚠️  {}.git.enc contains non-empty data: {} bytes", name, json_str.len());
+        }
+    }
+    info!("🔍 Git placeholder verification complete.");
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cmd_re_encrypt(
+    key: &[u8],
+    kdf: &KdfParams,
+    data_dir: &Path,
+    profile: &Profile,
+    targets: &[&str],
+    key_id: Option<&str>,
+    backup: Option<usize>,
+    dry_run: bool,
+) -> Result<()> {
+    info!("🔄 Re-encrypting {} *{} files to v4 format...", profile.name, profile.suffix);
+    print_dry_run_banner(dry_run);
+    // Every upgrade is staged first, so a failure on any file leaves all of them as they were
+    let mut txn = Transaction::new();
+    let mut upgraded = Vec::new();
+    for &name in targets {
+        let enc_name = profile.output(name);
+        let enc_path = data_dir.join(&enc_name);
+        if !enc_path.exists() {
+            info!("  ⏭️  Skip (not found): {}", enc_name);
+            continue;
+        }
+        let aborted = || format!("{} — re-encryption aborted, no files were changed", enc_name);
+        let data = fs::read(&enc_path).context("read .enc").with_context(aborted)?;
+        let binding = Binding::new(&profile.salt, name);
+        if let Some(version) = preamble::version(&data) {
+            // Attest existing current-format files only once the key is proven to open them
+            if let Some(id) = key_id {
+                decrypt_payload(key, binding, &data, kdf).with_context(aborted)?;
+                if dry_run {
+                    info!("  ⏭️  Already v{}: {} (would write {}{})", version, enc_name, enc_name, verify_token::TOKEN_SUFFIX);
+                    continue;
+                }
+                let token = VerifyToken::for_ciphertext(id, &data);
+                txn.stage(&verify_token::token_path(&enc_path), serde_json::to_string_pretty(&token)?.as_bytes())?;
+                info!("  ✅ Already v{}: {} (verification token staged)", version, enc_name);
+            } else {
+                info!("  ⏭️  Already v{}: {}", version, enc_name);
+            }
+            continue;
+        }
+        let plain = auto_decrypt(key, binding, &data, kdf).with_context(aborted)?;
+        let re_encrypted = v4_encrypt(key, binding, &plain, kdf).with_context(aborted)?;
+        if *auto_decrypt(key, binding, &re_encrypted, kdf).with_context(aborted)? != *plain {
+            bail!("{} — the v4 output does not decrypt back to the original", aborted());
+        }
+        if dry_run {
+            report_planned_write(&enc_path, &re_encrypted, key_id.is_some());
+            backup_before_write(&enc_path, backup, dry_run)?;
+            continue;
+        }
+        txn.stage(&enc_path, &re_encrypted)?;
+        if let Some(id) = key_id {
+            let token = VerifyToken::for_ciphertext(id, &re_encrypted);
+            txn.stage(&verify_token::token_path(&enc_path), serde_json::to_string_pretty(&token)?.as_bytes())?;
+        }
+        detached_mac::refresh(&mut txn, &enc_path, &re_encrypted).with_context(aborted)?;
+        info!("  ✅ {} staged for v4 ({} bytes)", enc_name, re_encrypted.len());
+        upgraded.push(enc_path);
+    }
+    if dry_run {
+        return Ok(());
+    }
+    for path in &upgraded {
+        backup_before_write(path, backup, dry_run)?;
+    }
+    txn.commit()?;
+    info!("🔄 Re-encryption complete: {} file(s) upgraded together.", upgraded.len());
+    Ok(())
+}
+
+/// Write selected targets as v3 for the legacy Node.js tool, all or none
+fn cmd_export_legacy(
+    key: &[u8],
+    kdf: &KdfParams,
+    data_dir: &Path,
+    targets: &[&str],
+    output_dir: Option<&Path>,
+    backup: Option<usize>,
+    dry_run: bool,
+) -> Result<()> {
+    logging::note(format_args!("⚠️  ─────────────────────────────────────────────────────────────"));
+    logging::note(format_args!("⚠️  DOWNGRADE: writing v3 — AES-256-CBC under scrypt with fixed salts."));
+    logging::note(format_args!("⚠️  v3 has NO authentication: anyone can alter these files undetected,"));
+    logging::note(format_args!("⚠️  and the Argon2id costs, file binding and HMAC of v4+ are gone."));
+    logging::note(format_args!("⚠️  Only for teammates still on the Node.js tool; re-encrypt to v4 as"));
+    logging::note(format_args!("⚠️  soon as they have moved over (`re-encrypt`)."));
+    logging::note(format_args!("⚠️  ─────────────────────────────────────────────────────────────"));
+    info!("📼 Exporting .enc files to legacy v3...");
+    print_dry_run_banner(dry_run);
+    let out_dir = output_dir.unwrap_or(data_dir);
+    if !dry_run {
+        fs::create_dir_all(out_dir).with_context(|| format!("create {:?}", out_dir))?;
+    }
+    let mut txn = Transaction::new();
+    let mut exported = Vec::new();
+    for &name in targets {
+        let enc_path = data_dir.join(format!("{}.enc", name));
+        if !enc_path.exists() {
+            info!("  ⏭️  Skip (not found): {}.enc", name);
+            continue;
+        }
+        let aborted = || format!("{}.enc — export aborted, no files were changed", name);
+        let data = fs::read(&enc_path).context("read .enc").with_context(aborted)?;
+        let plain = auto_decrypt_text(key, Binding::new(LOCAL_SALT, name), &data, kdf).with_context(aborted)?;
+        let legacy = v3_encrypt(key, LOCAL_SALT, plain.as_bytes()).with_context(aborted)?;
+        if *v3_decrypt(key, LOCAL_SALT, &legacy).with_context(aborted)? != *plain.as_bytes() {
+            bail!("{} — the v3 output does not decrypt back to the original", aborted());
+        }
+        let dest = out_dir.join(format!("{}.enc", name));
+        if dry_run {
+            report_planned_write(&dest, &legacy, false);
+            backup_before_write(&dest, backup, dry_run)?;
+            continue;
+        }
+        txn.stage(&dest, &legacy)?;
+        // A v4 verification token would no longer match the file it sits next to
+        let token = verify_token::token_path(&dest);
+        let note = if token.exists() { ", its .vtok goes — v3 carries no token" } else { "" };
+        info!("  📼 {}.enc staged as v3 ({} bytes{})", name, legacy.len(), note);
+        exported.push((dest, token));
+    }
+    if dry_run {
+        return Ok(());
+    }
+    for (dest, _) in &exported {
+        backup_before_write(dest, backup, dry_run)?;
+    }
+    txn.commit()?;
+    for (_, token) in &exported {
+        if token.exists() {
+            fs::remove_file(token).with_context(|| format!("remove {:?}", token))?;
+        }
+    }
+    info!("📼 {} file(s) exported as v3 to {:?}.", exported.len(), out_dir);
+    Ok(())
+}
+
+/// Without `only_profile`, every profile's files and the .git.enc placeholders
+/// move to the new key and the old one is retired; with it, only that
+/// profile's files move and the old key stays current for the rest.
+fn cmd_rotate_key(
+    old_key: &[u8],
+    new_key: &[u8],
+    kdf: &KdfParams,
+    data_dir: &Path,
+    only_profile: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    if old_key == new_key {
+        bail!("new key must differ from the old key");
+    }
+    let profiles = match only_profile {
+        Some(name) => vec![profile::load(data_dir, Some(name))?],
+        None => profile::all(data_dir)?,
+    };
+    // (file name, salt label, target) of every file to rotate
+    let mut files: Vec<(String, &str, &str)> = Vec::new();
+    for profile in &profiles {
+        files.extend(profile.targets.iter().map(|name| (profile.output(name), profile.salt.as_str(), name.as_str())));
+    }
+    match only_profile {
+        Some(name) => info!("🔑 Rotating key for profile {} (v4)...", name),
+        None => {
+            files.extend(TARGET_FILES.iter().map(|&name| (format!("{}.git.enc", name), GIT_SALT, name)));
+            info!("🔑 Rotating key for all encrypted targets (v4)...");
+        }
+    }
+    print_dry_run_banner(dry_run);
+
+    let mut txn = Transaction::new();
+    let (mut staged, mut current) = (0u32, 0u32);
+    let mut seen = std::collections::HashSet::new();
+    let new_key_id = verify_token::derive_key_id(new_key)?;
+    for (file_name, salt, name) in files {
+        let path = data_dir.join(&file_name);
+        if !path.exists() || !seen.insert(file_name.clone()) {
+            continue;
+        }
+        let binding = Binding::new(salt, name);
+        let rotated = match rotate_file(old_key, new_key, binding, &path, kdf) {
+            Ok(rotated) => rotated,
+            // An earlier `--profile` rotation already moved it
+            Err(_) if decrypt_payload(new_key, binding, &fs::read(&path)?, kdf).is_ok() => {
+                info!("  ⏭️  {} is already on the new key", file_name);
+                current += 1;
+                continue;
+            }
+            Err(e) => return Err(e.context(format!("{} — rotation aborted, no files were changed", file_name))),
+        };
+        if dry_run {
+            report_planned_write(&path, &rotated, VerifyToken::read(&path)?.is_some());
+            staged += 1;
+            continue;
+        }
+        txn.stage(&path, &rotated)?;
+        // Existing verification tokens would go stale; refresh them in the same commit
+        if VerifyToken::read(&path)?.is_some() {
+            let token = VerifyToken::for_ciphertext(&new_key_id, &rotated);
+            txn.stage(&verify_token::token_path(&path), serde_json::to_string_pretty(&token)?.as_bytes())?;
+        }
+        detached_mac::refresh(&mut txn, &path, &rotated)
+            .with_context(|| format!("{} — rotation aborted, no files were changed", file_name))?;
+        info!("  ✅ {} staged ({} bytes)", file_name, rotated.len());
+        staged += 1;
+    }
+
+    if staged + current == 0 {
+        info!("  ⏭️  No encrypted targets found.");
+        return Ok(());
+    }
+    if dry_run {
+        info!("🔑 {} file(s) would be rotated.", staged);
+        return Ok(());
+    }
+    // After a full rotation the old passphrase becomes a retired generation, so files it
+    // still opens can be named; files outside a single profile still need it, so it stays current
+    let mut keyring = Keyring::load(data_dir)?;
+    keyring.register(&verify_token::derive_key_id(old_key)?, kdf, None);
+    keyring.register(&new_key_id, kdf, None);
+    if only_profile.is_none() {
+        keyring.retire_others(&new_key_id);
+    }
+    txn.stage(&Keyring::path(data_dir), keyring.to_json()?.as_bytes())?;
+    txn.commit()?;
+    let generation = keyring.find(&new_key_id).map_or(0, |entry| entry.generation);
+    info!("🔑 Key rotated for {} file(s); new key is generation {}.", staged, generation);
+    if let Some(name) = only_profile {
+        info!("   Files outside profile {} keep the old key, which stays current until they are rotated.", name);
+    }
+    Ok(())
+}
+
+/// Decrypt with the old key, re-encrypt with the new key, and prove the round trip
+///
+/// v5 payloads keep their header (content type, compression); everything else is written as v4.
+fn rotate_file(old_key: &[u8], new_key: &[u8], binding: Binding, path: &Path, kdf: &KdfParams) -> Result<Vec<u8>> {
+    let data = fs::read(path).context("read")?;
+    let (_, plain) = decrypt_payload(old_key, binding, &data, kdf).context("decrypt with old key")?;
+    let rotated = if preamble::version(&data) == Some(VERSION_V6) {
+        recipient::rotate_passphrase(old_key, new_key, binding.salt_label, &data, kdf)?
+    } else if preamble::version(&data) == Some(VERSION_V5) {
+        let (_, header, _) = v5_check_integrity(&data)?;
+        v5_encrypt(new_key, binding, &plain, &header, kdf)?
+    } else {
+        v4_encrypt(new_key, binding, &plain, kdf)?
+    };
+    if decrypt_payload(new_key, binding, &rotated, kdf)?.1 != plain {
+        bail!("round-trip check with new key failed");
+    }
+    Ok(rotated)
+}
+
+/// Returns the number of issues found
+#[allow(clippy::too_many_arguments)]
+fn cmd_verify(
+    key: &[u8],
+    kdf: &KdfParams,
+    data_dir: &Path,
+    profile: &Profile,
+    targets: &[&str],
+    strict: bool,
+    deep: bool,
+    git_history: bool,
+) -> Result<u32> {
+    info!("🛡️  Verifying encryption integrity...");
+    let mut issues = 0u32;
+    let mut ledger = nonces::Ledger::default();
+    let digest_key = verify_token::digest_key(key)?;
+
+    for &name in targets {
+        let json_path = data_dir.join(name);
+        if json_path.exists() {
+            let content = fs::read(&json_path).unwrap_or_default();
+            if content.windows(key.len()).any(|w| w == key) {
+                error!("  🚨 LEAK: {} contains the encryption key!", name);
+                sarif::record(Rule::KeyLeak, &json_path, format_args!("{} contains the encryption key", name));
+                issues += 1;
+            }
+        }
+
+        let enc_name = profile.output(name);
+        let enc_path = data_dir.join(&enc_name);
+        if enc_path.exists() {
+            let data = fs::read(&enc_path).with_context(|| format!("read {}", enc_name))?;
+            if data.is_empty() {
+                warn!("  ⚠️  Empty file: {}", enc_name);
+                sarif::record(Rule::Integrity, &enc_path, format_args!("{} is empty", enc_name));
+                issues += 1;
+            } else if let Some(version) = preamble::version(&data) {
+                let (result, opened) = nonces::capture(|| decrypt_payload(key, Binding::new(&profile.salt, name), &data, kdf));
+                ledger.add(&enc_name, &data, opened);
+                match result {
+                    Ok((header, plain)) => {
+                        let content_type = &header.content_type;
+                        if !content_type.is_text() {
+                            info!("  ✅ {} — v{}, {} ({} bytes)", enc_name, version, content_type, plain.len());
+                        } else if std::str::from_utf8(&plain).is_ok() {
+                            let size = plain.len();
+                            info!("  ✅ {} — v{}, valid {} ({} bytes)", enc_name, version, content_type, size);
+                        } else {
+                            warn!("  ⚠️  {} — v{} decrypts but not valid UTF-8", enc_name, version);
+                            let message = format_args!("{} decrypts, but its text payload is not UTF-8", enc_name);
+                            sarif::record(Rule::Integrity, &enc_path, message);
+                            issues += 1;
+                        }
+                        // The digest was taken before encryption, so this needs no second decrypt
+                        match header.matches_plaintext(&plain, digest_key.as_ref()) {
+                            Some(true) => info!("      {}, plaintext digest matches", describe_stamp(&header)),
+                            Some(false) => {
+                                error!("      ❌ Plaintext differs from the digest recorded at encryption");
+                                let message = format_args!("{}: plaintext differs from its recorded digest", enc_name);
+                                sarif::record(Rule::Integrity, &enc_path, message);
+                                issues += 1;
+                            }
+                            None => {}
+                        }
+                        if deep && content_type.is_text() {
+                            issues += report_schema(name, &enc_path, &plain)?;
+                        }
+                    }
+                    Err(e) => {
+                        error!("  ❌ {} — v{} decrypt failed: {}", enc_name, version, e);
+                        let message = format_args!("{} (v{}) fails to decrypt: {}", enc_name, version, e);
+                        sarif::record(Rule::Integrity, &enc_path, message);
+                        issues += 1;
+                    }
+                }
+            } else {
+                if strict {
+                    warn!("  ⚠️  {} — legacy format (v2/v3), re-encrypt required (--strict)", enc_name);
+                    let message = format_args!("{} is legacy v2/v3 — re-encrypt it", enc_name);
+                    sarif::record(Rule::LegacyFormat, &enc_path, message);
+                    issues += 1;
+                } else {
+                    info!("  ℹ️  {} — legacy format (v2/v3), consider re-encrypt", enc_name);
+                }
+                match auto_decrypt_text(key, Binding::new(&profile.salt, name), &data, kdf) {
+                    Ok(s) => {
+                        info!("      ✅ Decrypts OK ({} bytes)", s.len());
+                        if deep {
+                            issues += report_schema(name, &enc_path, s.as_bytes())?;
+                        }
+                    }
+                    Err(e) => {
+                        error!("      ❌ Decrypt failed: {}", e);
+                        let message = format_args!("{} (legacy) fails to decrypt: {}", enc_name, e);
+                        sarif::record(Rule::Integrity, &enc_path, message);
+                        issues += 1;
+                    }
+                }
+            }
+            if !data.is_empty() {
+                issues += detached_mac::report(&enc_path, &data)?;
+            }
+        }
+
+        let git_enc_path = data_dir.join(format!("{}.git.enc", name));
+        if git_enc_path.exists() {
+            let data = fs::read(&git_enc_path).context("read .git.enc")?;
+            let (result, opened) = nonces::capture(|| auto_decrypt_text(key, Binding::new(GIT_SALT, name), &data, kdf));
+            ledger.add(&format!("{}.git.enc", name), &data, opened);
+            match result {
+                Ok(s) if s.trim() == "{}" => {
+                    info!("  ✅ {}.git.enc — valid empty placeholder", name);
+                }
+                Ok(s) => {
+                    error!("  🚨 {}.git.enc contains real data ({} bytes)!", name, s.len());
+                    let message = format_args!("{}.git.enc holds {} bytes of real data, not {{}}", name, s.len());
+                    sarif::record(Rule::PlaceholderData, &git_enc_path, message);
+                    issues += 1;
+                }
+                Err(e) => {
+                    error!("  ❌ {}.git.enc — decrypt failed: {}", name, e);
+                    let message = format_args!("{}.git.enc fails to decrypt: {}", name, e);
+                    sarif::record(Rule::Integrity, &git_enc_path, message);
+                    issues += 1;
+                }
+            }
+        }
+    }
+
+    issues += check_nonces(&mut ledger, data_dir, profile, targets, git_history)?;
+
+    if issues == 0 {
+        info!("🛡️  All checks passed — no issues found.");
+    } else {
+        info!("🛡️  Found {} issue(s). Review above.", issues);
+    }
+    Ok(issues)
+}
+
+/// `verify --deep`: print schema violations of a decrypted target; returns 1 when there are any
+fn report_schema(name: &str, enc_path: &Path, plaintext: &[u8]) -> Result<u32> {
+    let Some(violations) = schema::validate(name, plaintext)? else {
+        return Ok(0);
+    };
+    if violations.is_empty() {
+        info!("      ✅ Matches the bundled {} schema", name);
+        return Ok(0);
+    }
+    error!("      ❌ {} schema violation(s) — valid ciphertext, corrupt payload:", violations.len());
+    let message = format!("{} schema violation(s) in decrypted {}, first: {}", violations.len(), name, violations[0]);
+    sarif::record(Rule::SchemaViolation, enc_path, message);
+    for violation in violations.iter().take(schema::MAX_REPORTED) {
+        error!("         {}", violation);
+    }
+    if violations.len() > schema::MAX_REPORTED {
+        error!("         … and {} more", violations.len() - schema::MAX_REPORTED);
+    }
+    Ok(1)
+}
+
+/// Report repeated nonces among the files `verify` read, plus their committed
+/// versions with `--git-history`; returns the number of issues
+fn check_nonces(
+    ledger: &mut nonces::Ledger,
+    data_dir: &Path,
+    profile: &Profile,
+    targets: &[&str],
+    git_history: bool,
+) -> Result<u32> {
+    if git_history {
+        let files: Vec<String> =
+            targets.iter().flat_map(|name| [profile.output(name), format!("{}.git.enc", name)]).collect();
+        let versions = ledger.add_history(data_dir, &files)?;
+        info!("  ℹ️  Read {} committed version(s) for the nonce check", versions);
+    }
+    Ok(ledger.report(data_dir))
+}
+
+/// Returns the number of issues found
+fn cmd_verify_no_key(
+    data_dir: &Path,
+    profile: &Profile,
+    targets: &[&str],
+    expect_key_id: Option<&str>,
+    strict: bool,
+    git_history: bool,
+) -> Result<u32> {
+    info!("🛡️  Verifying encryption integrity without key (structure + verification tokens + detached MACs)...");
+    let mut issues = 0u32;
+    let mut ledger = nonces::Ledger::default();
+    let mut key_ids = std::collections::BTreeSet::new();
+
+    for &name in targets {
+        for file_name in [profile.output(name), format!("{}.git.enc", name)] {
+            let path = data_dir.join(&file_name);
+            if !path.exists() {
+                continue;
+            }
+            let data = fs::read(&path).with_context(|| format!("read {}", file_name))?;
+            ledger.add(&file_name, &data, Vec::new());
+
+            if data.is_empty() {
+                warn!("  ⚠️  Empty file: {}", file_name);
+                sarif::record(Rule::Integrity, &path, format_args!("{} is empty", file_name));
+                issues += 1;
+                continue;
+            } else if let Some(version) = preamble::version(&data) {
+                match read_header(&data) {
+                    Ok(Some(header)) if header.layer_mac => {
+                        let note = "its HMAC needs the passphrase (verify without --no-key)";
+                        info!("  ✅ {} — v{}, well-formed; {}", file_name, version, note)
+                    }
+                    Ok(Some(header)) if header.passphrase_mac => {
+                        let note = "its HMAC is keyed the pre-layer-key way and not checked (encrypt-local rewrites it)";
+                        info!("  ✅ {} — v{}, well-formed; {}", file_name, version, note)
+                    }
+                    Ok(_) => info!("  ✅ {} — v{}, well-formed, HMAC intact", file_name, version),
+                    Err(e) => {
+                        error!("  ❌ {} — {}", file_name, e);
+                        sarif::record(Rule::Integrity, &path, format_args!("{}: {}", file_name, e));
+                        issues += 1;
+                    }
+                }
+            } else if data.len() < 32 || data.len() % 16 != 0 {
+                error!("  ❌ {} — not a valid v4–v6 or legacy CBC layout", file_name);
+                let message = format_args!("{} is not a valid v4–v6 or legacy layout", file_name);
+                sarif::record(Rule::Integrity, &path, message);
+                issues += 1;
+            } else if strict {
+                warn!("  ⚠️  {} — legacy format (v2/v3), re-encrypt required (--strict)", file_name);
+                sarif::record(Rule::LegacyFormat, &path, format_args!("{} is legacy v2/v3 — re-encrypt it", file_name));
+                issues += 1;
+            } else {
+                info!("  ℹ️  {} — legacy format (v2/v3), structure only checkable with key", file_name);
+            }
+
+            match VerifyToken::read(&path)? {
+                None => warn!("      ⚠️  No verification token (encrypt with --verify-token)"),
+                Some(token) if !token.matches(&data) => {
+                    error!("      🚨 Modified since its verification token was written");
+                    let message = format_args!("{} was modified after its verification token was written", file_name);
+                    sarif::record(Rule::TokenMismatch, &path, message);
+                    issues += 1;
+                }
+                Some(token) => {
+                    if let Some(expected) = expect_key_id.filter(|e| *e != token.key_id) {
+                        error!("      🚨 Key ID {} does not match expected {}", token.key_id, expected);
+                        let message =
+                            format_args!("{} carries key ID {}, not the expected {}", file_name, token.key_id, expected);
+                        sarif::record(Rule::TokenMismatch, &path, message);
+                        issues += 1;
+                    } else {
+                        info!("      ✅ Token matches (key ID {})", token.key_id);
+                    }
+                    key_ids.insert(token.key_id);
+                }
+            }
+            issues += detached_mac::report(&path, &data)?;
+        }
+    }
+
+    if key_ids.len() > 1 {
+        warn!("  ⚠️  Files span {} key families: {:?}", key_ids.len(), key_ids);
+        let message = format_args!("the encrypted files span {} key families: {:?}", key_ids.len(), key_ids);
+        sarif::record(Rule::MixedKeyFamilies, data_dir, message);
+        issues += 1;
+    }
+    issues += check_nonces(&mut ledger, data_dir, profile, targets, git_history)?;
+
+    if issues == 0 {
+        info!("🛡️  All keyless checks passed — no issues found.");
+    } else {
+        info!("🛡️  Found {} issue(s). Review above.", issues);
+    }
+    Ok(issues)
+}
+
+/// Which .enc files still match their plaintext, from header digests alone
+fn cmd_daemon_status(socket: &Path, json: bool) -> Result<()> {
+    let snapshot = monitor::query(socket)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&snapshot)?);
+        return Ok(());
+    }
+    let uptime = snapshot.uptime_secs;
+    logging::out(format_args!(
+        "📡 watch (pid {}) up {}h{:02}m{:02}s since {} on {}",
+        snapshot.pid,
+        uptime / 3_600,
+        uptime % 3_600 / 60,
+        uptime % 60,
+        snapshot.started,
+        snapshot.data_dir
+    ));
+    logging::out(format_args!("  🔑 Key IDs: {}", snapshot.key_fingerprints.join(", ")));
+    logging::out(format_args!("  👀 Watching: {}", snapshot.files_watched.join(", ")));
+    logging::out(format_args!(
+        "  📊 {} encrypted, {} skipped, {} error(s)",
+        snapshot.encrypted, snapshot.skipped, snapshot.errors
+    ));
+    if snapshot.recent.is_empty() {
+        logging::out(format_args!("  ⏭️  No operations yet."));
+    }
+    for op in &snapshot.recent {
+        let icon = match op.outcome {
+            monitor::Outcome::Encrypted => "🔐",
+            monitor::Outcome::Skipped => "⏳",
+            monitor::Outcome::Failed => "❌",
+        };
+        logging::out(format_args!("  {} {} {} — {}", icon, op.at, op.file, op.detail));
+    }
+    Ok(())
+}
+
+/// Decrypt in memory, print the value at `pointer` and zeroize every copy of the plaintext we hold
+fn cmd_query(key: &[u8], kdf: &KdfParams, file: &Path, salt: &SaltArgs, pointer: &str, raw: bool) -> Result<()> {
+    logging::claim_stdout();
+    let data = fs::read(file).with_context(|| format!("read {:?}", file))?;
+    let name = logical_name(file);
+    let json = auto_decrypt_text(key, Binding::new(&salt.for_reading(&data)?, &name), &data, kdf)?;
+    let mut document: serde_json::Value = serde_json::from_str(&json).context("plaintext is not JSON")?;
+    let found = document.pointer(pointer).map(|value| match value {
+        serde_json::Value::String(s) if raw => Ok(s.clone()),
+        value => serde_json::to_string_pretty(value),
+    });
+    let output = found.transpose()?.map(Zeroizing::new);
+    scrub_json(&mut document);
+    let output = output.with_context(|| format!("{} has nothing at {:?}", name, pointer))?;
+    println!("{}", output.as_str());
+    Ok(())
+}
+
+/// Zeroize every string (keys included) in a parsed document before it is dropped
+fn scrub_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(s) => s.zeroize(),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(scrub_json),
+        serde_json::Value::Object(map) => {
+            for (mut key, mut child) in std::mem::take(map) {
+                key.zeroize();
+                scrub_json(&mut child);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(feature = "kms")]
+fn kms_recipients(key_ids: &[String]) -> Result<Vec<Recipient>> {
+    key_ids.iter().map(|id| kms::KmsKey::parse(id).map(Recipient::Kms)).collect()
+}
+
+/// v4 ciphertext of one file, bound to the logical name of where it is written
+/// (the input's own name on stdout) so `decrypt-file` opens it from there
+fn cmd_encrypt_file(key: &[u8], kdf: &KdfParams, file: &Path, output: Option<&Path>, salt_label: &str) -> Result<()> {
+    if output.is_none() {
+        logging::claim_stdout();
+    }
+    let plaintext = Zeroizing::new(fs::read(file).with_context(|| format!("read {:?}", file))?);
+    let name = logical_name(output.unwrap_or(file));
+    let encrypted = v4_encrypt(key, Binding::new(salt_label, &name), &plaintext, kdf)?;
+    match output {
+        Some(path) => {
+            write_atomic(path, &encrypted).with_context(|| format!("write {:?}", path))?;
+            info!("🔐 {:?} → {:?} (v4, {} bytes)", file, path, encrypted.len());
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(&encrypted).and_then(|()| stdout.flush()).context("write ciphertext to stdout")?;
+        }
+    }
+    Ok(())
+}
+
+/// Text to stdout by default; with `raw` or an `output` path the plaintext
+/// goes out byte for byte, binary content types included
+fn cmd_decrypt_file(
+    key: &Keyholder,
+    kdf: &KdfParams,
+    file: &Path,
+    salt: &SaltArgs,
+    strict: bool,
+    output: Option<&Path>,
+    raw: bool,
+) -> Result<()> {
+    if output.is_none() {
+        logging::claim_stdout();
+    }
+    let data = if strict { strict::read(file)? } else { fs::read(file).with_context(|| format!("read {:?}", file))? };
+    let name = logical_name(file);
+    let opened = key.open(Binding::new(&salt.for_reading(&data)?, &name), &data, kdf)?;
+    if output.is_none() && !raw {
+        print!("{}", *as_text(opened)?);
+        return Ok(());
+    }
+    let (header, plain) = opened;
+    let plain = Zeroizing::new(plain);
+    match output {
+        Some(path) => {
+            write_atomic(path, &plain).with_context(|| format!("write {:?}", path))?;
+            restore_mtime(path, &data);
+            info!("🔓 {:?} → {:?} ({}, {} bytes)", file, path, header.content_type, plain.len());
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(&plain).and_then(|()| stdout.flush()).context("write plaintext to stdout")?;
+        }
+    }
+    Ok(())
+}
+
+fn cmd_encrypt_asset(
+    key: &Keyholder,
+    kdf: &KdfParams,
+    file: &Path,
+    header: &Header,
+    salt_label: &str,
+    key_id: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    let plaintext = Zeroizing::new(fs::read(file).with_context(|| format!("read {:?}", file))?);
+    let mut enc_name = file.as_os_str().to_os_string();
+    enc_name.push(".enc");
+    let enc_path = PathBuf::from(enc_name);
+    let name = logical_name(&enc_path);
+    let header = header.with_plaintext_mtime(file_mtime(file));
+    let encrypted =
+        progress::file(&name, "sealing", || key.seal(Binding::new(salt_label, &name), &plaintext, &header, kdf))?;
+    if dry_run {
+        print_dry_run_banner(dry_run);
+        report_planned_write(&enc_path, &encrypted, key_id.is_some());
+        return Ok(());
+    }
+    write_atomic(&enc_path, &encrypted).context("write .enc")?;
+    if let Some(id) = key_id {
+        VerifyToken::for_ciphertext(id, &encrypted).write(&enc_path)?;
+    }
+    info!(
+        "🔐 {:?} → {:?} (v5, {}, {}, {} bytes)",
+        file, enc_path, header.content_type, header.compression, encrypted.len()
+    );
+    Ok(())
+}
+
+fn cmd_decrypt_asset(
+    key: &Keyholder,
+    kdf: &KdfParams,
+    file: &Path,
+    output: Option<PathBuf>,
+    salt: &SaltArgs,
+    strict: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let output = match output {
+        Some(path) => path,
+        None => {
+            let name = file.to_str().and_then(|f| f.strip_suffix(".enc"));
+            PathBuf::from(name.context("input has no .enc suffix — pass --output")?)
+        }
+    };
+    let data = if strict { strict::read(file)? } else { fs::read(file).with_context(|| format!("read {:?}", file))? };
+    let name = logical_name(file);
+    let salt_label = salt.for_reading(&data)?;
+    let (header, plain) =
+        progress::file(&name, "opening", || key.open(Binding::new(&salt_label, &name), &data, kdf))?;
+    let plain = Zeroizing::new(plain);
+    if dry_run {
+        print_dry_run_banner(dry_run);
+        report_planned_write(&output, &plain, false);
+        return Ok(());
+    }
+    write_atomic(&output, &plain).with_context(|| format!("write {:?}", output))?;
+    restore_mtime(&output, &data);
+    info!("🔓 {:?} → {:?} ({}, {} bytes)", file, output, header.content_type, plain.len());
+    Ok(())
+}
+
+fn cmd_restore_backup(file: &Path, from: Option<PathBuf>, list: bool, dry_run: bool) -> Result<()> {
+    let backups = backup::list_backups(file)?;
+    if list {
+        logging::out(format_args!("💾 {} backup(s) of {:?} (oldest first):", backups.len(), file));
+        for path in &backups {
+            let data = fs::read(path).with_context(|| format!("read {:?}", path))?;
+            logging::out(format_args!("  {} ({})", path.file_name().unwrap_or_default().to_string_lossy(), describe_blob(file, &data)));
+        }
+        return Ok(());
+    }
+
+    let source = match from {
+        Some(path) => path,
+        None => backups.last().cloned().with_context(|| format!("no backups found for {:?}", file))?,
+    };
+    let data = fs::read(&source).with_context(|| format!("read {:?}", source))?;
+    if dry_run {
+        print_dry_run_banner(dry_run);
+        report_planned_write(file, &data, false);
+        return Ok(());
+    }
+    write_atomic(file, &data).context("restore backup")?;
+    info!("💾 Restored {:?} from {:?} ({})", file, source, describe_blob(file, &data));
+    if VerifyToken::read(file)?.is_some_and(|token| !token.matches(&data)) {
+        warn!("  ⚠️  Its .vtok no longer matches — re-run with --verify-token to refresh it");
+    }
+    Ok(())
+}
+
+fn cmd_bench_kdf(target: Duration, max_memory_mib: u32) -> Result<()> {
+    info!(
+        "⏱️  Calibrating KDFs for {} ms per layer (memory cap {} MiB)...",
+        target.as_millis(),
+        max_memory_mib
+    );
+
+    let current = KdfParams::default();
+    let current_time = kdf::time_argon2(&current)?;
+    logging::out(format_args!(
+        "  Argon2id current  m={} KiB t={} p={} → {} ms/layer",
+        current.m_cost(),
+        current.t_cost(),
+        current.p_cost(),
+        current_time.as_millis()
+    ));
+    let (tuned, tuned_time) = kdf::calibrate_argon2(target, max_memory_mib.saturating_mul(1024))?;
+    logging::out(format_args!(
+        "  Argon2id tuned    m={} KiB t={} p={} → {} ms/layer (~{} ms per file, 3 layers)",
+        tuned.m_cost(),
+        tuned.t_cost(),
+        tuned.p_cost(),
+        tuned_time.as_millis(),
+        tuned_time.as_millis() * 3
+    ));
+
+    let legacy_time = kdf::time_scrypt(kdf::SCRYPT_LEGACY_LOG_N)?;
+    logging::out(format_args!(
+        "  scrypt   current  log_n={} r={} p={} → {} ms",
+        kdf::SCRYPT_LEGACY_LOG_N,
+        kdf::SCRYPT_R,
+        kdf::SCRYPT_P,
+        legacy_time.as_millis()
+    ));
+    let (log_n, scrypt_time) = kdf::calibrate_scrypt(target)?;
+    logging::out(format_args!(
+        "  scrypt   tuned    log_n={} r={} p={} → {} ms (reference only — scrypt just reads legacy v2/v3)",
+        log_n,
+        kdf::SCRYPT_R,
+        kdf::SCRYPT_P,
+        scrypt_time.as_millis()
+    ));
+
+    if tuned_time < target / 2 {
+        warn!("  ⚠️  Memory cap reached well below target; raise --max-memory-mib for a stronger setting");
+    }
+    logging::out(format_args!("⏱️  Use with every v4 command (decryption needs the same values):"));
+    logging::out(format_args!("  {}", kdf::as_flags(&tuned)));
+    Ok(())
+}
+
+/// The `violet-cipher` command line; the binary is a thin wrapper around this
+pub fn main() -> Result<()> {
+    let cli = Cli::parse();
+    logging::init(&cli.log);
+    if let Some(path) = &cli.pepper_file {
+        pepper::configure(pepper::Pepper::load(path)?)?;
+    }
+    #[cfg(feature = "fido2")]
+    fido2::configure(&cli.fido2)?;
+    if let Some(path) = &cli.keyfile {
+        keyfile::configure(keyfile::Keyfile::load(path)?)?;
+    }
+    if let Some(path) = &cli.mac_key_file {
+        detached_mac::configure(detached_mac::MacKey::load(path)?)?;
+    }
+    let writer = data_dir_writer(&cli.command);
+    let _lock = match &writer {
+        Some((data_dir, command, false)) => lock::acquire(data_dir, command)?,
+        _ => None,
+    };
+    // A dry run seals too, to preview it, so it escrows to the same recovery key
+    if let Some((data_dir, _, _)) = &writer {
+        recovery::load(data_dir)?;
+    }
+    match &writer {
+        Some((data_dir, _, dry_run)) => verify_token::begin(data_dir, !dry_run),
+        None => verify_token::begin(&key_id_dir(&cli.command), false),
+    }
+    if let Some((data_dir, command, file)) = audit_target(&cli.command) {
+        audit::begin(data_dir, command, file);
+    }
+    key_cache::begin();
+    let result = run(cli.command);
+    key_cache::end();
+    audit::finish(&audit::outcome(&result));
+    secret::assert_released();
+    result
+}
+
+/// The `violet-test-vectors` binary; a separate one so release builds of `violet-cipher` never carry the seeded source
+#[cfg(feature = "test-vectors")]
+pub fn test_vectors_main() -> Result<()> {
+    vectors::main()
+}
+
+/// Audit log entry for commands that encrypt, decrypt, verify or rotate:
+/// data directory, command name and the file or directory worked on
+fn audit_target(command: &Commands) -> Option<(PathBuf, &'static str, String)> {
+    let (name, data_dir, file) = match command {
+        Commands::EncryptLocal { data_dir, .. } => ("encrypt-local", data_dir, None),
+        Commands::DecryptLocal { data_dir, .. } => ("decrypt-local", data_dir, None),
+        Commands::EncryptFields(args) => ("encrypt-fields", &args.data_dir, None),
+        Commands::DecryptFields(args) => ("decrypt-fields", &args.data_dir, None),
+        Commands::EncryptGit { data_dir, .. } => ("encrypt-git", data_dir, None),
+        Commands::DecryptGit { data_dir, .. } => ("decrypt-git", data_dir, None),
+        Commands::ReEncrypt { data_dir, .. } => ("re-encrypt", data_dir, None),
+        Commands::ExportLegacy { data_dir, .. } => ("export-legacy", data_dir, None),
+        Commands::Verify { data_dir, .. } => ("verify", data_dir, None),
+        Commands::RotateKey { data_dir, .. } => ("rotate-key", data_dir, None),
+        Commands::EncryptFile { file, .. } => ("encrypt-file", &None, Some(file)),
+        Commands::DecryptFile { file, .. } => ("decrypt-file", &None, Some(file)),
+        Commands::Export(args) => ("export", &args.data_dir, None),
+        Commands::Serve(args) => ("serve", &args.data_dir, None),
+        Commands::Import(args) => ("import", &None, Some(&args.file)),
+        Commands::EncryptAsset { file, .. } => ("encrypt-asset", &None, Some(file)),
+        Commands::DecryptAsset { file, .. } => ("decrypt-asset", &None, Some(file)),
+        _ => return None,
+    };
+    let data_dir = resolve_data_dir(data_dir.clone());
+    let file = file.unwrap_or(&data_dir).display().to_string();
+    Some((data_dir, name, file))
+}
+
+/// Data directory whose keyring salts the key IDs of a command that does not write one
+fn key_id_dir(command: &Commands) -> PathBuf {
+    let data_dir = match command {
+        Commands::Agent { action: agent::AgentCommand::Start { data_dir, .. } } => data_dir,
+        Commands::Key { action: shamir::KeyCommand::Seal { data_dir, .. } } => data_dir,
+        _ => return audit_target(command).map_or_else(|| resolve_data_dir(None), |(data_dir, _, _)| data_dir),
+    };
+    resolve_data_dir(data_dir.clone())
+}
+
+/// Data directory a command writes into, the command name and whether it is a dry run;
+/// a real run locks it (lock.rs)
+fn data_dir_writer(command: &Commands) -> Option<(PathBuf, &'static str, bool)> {
+    let (name, data_dir, dry_run) = match command {
+        Commands::EncryptLocal { data_dir, dry_run, .. } => ("encrypt-local", data_dir, *dry_run),
+        Commands::DecryptLocal { data_dir, dry_run, .. } => ("decrypt-local", data_dir, *dry_run),
+        Commands::EncryptFields(args) => ("encrypt-fields", &args.data_dir, args.dry_run),
+        Commands::DecryptFields(args) => ("decrypt-fields", &args.data_dir, args.dry_run),
+        Commands::EncryptGit { data_dir, dry_run, .. } => ("encrypt-git", data_dir, *dry_run),
+        Commands::DecryptGit { data_dir, .. } => ("decrypt-git", data_dir, false),
+        Commands::ReEncrypt { data_dir, dry_run, .. } => ("re-encrypt", data_dir, *dry_run),
+        Commands::ExportLegacy { data_dir, output_dir: None, dry_run, .. } => ("export-legacy", data_dir, *dry_run),
+        Commands::RotateKey { data_dir, dry_run, .. } => ("rotate-key", data_dir, *dry_run),
+        Commands::Backup { action: archive::BackupCommand::Restore { data_dir, dry_run, .. } } => {
+            ("backup restore", data_dir, *dry_run)
+        }
+        Commands::ImportBundle(args) => ("import-bundle", &args.data_dir, args.dry_run),
+        Commands::Pack(args) => ("pack", &args.data_dir, args.dry_run),
+        Commands::Unpack(args) => ("unpack", &args.data_dir, args.dry_run),
+        Commands::Keyring { action: keyring::KeyringCommand::Add { data_dir, .. } } => ("keyring add", data_dir, false),
+        Commands::Manifest { action: manifest::ManifestCommand::Write { data_dir, dry_run, .. } } => {
+            ("manifest write", data_dir, *dry_run)
+        }
+        Commands::Watch { data_dir, .. } => ("watch", data_dir, false),
+        Commands::Key { action: shamir::KeyCommand::RecoveryGenerate { data_dir, .. } } => {
+            ("key recovery-generate", data_dir, false)
+        }
+        _ => return None,
+    };
+    Some((resolve_data_dir(data_dir.clone()), name, dry_run))
+}
+
+fn run(command: Commands) -> Result<()> {
+    match command {
+        Commands::EncryptLocal {
+            key,
+            kdf,
+            data_dir,
+            profile,
+            only,
+            compress,
+            recipient,
+            recipient_key_file,
+            #[cfg(feature = "kms")]
+            kms_key_id,
+            verify_token,
+            detached_mac,
+            backup,
+            jobs,
+            progress,
+            fast,
+            suite,
+            force,
+            dry_run,
+        } => {
+            if progress {
+                progress::enable();
+            }
+            if detached_mac {
+                detached_mac::enable_writes()?;
+            }
+            if fast {
+                key_cache::share_salts();
+            }
+            let kdf = kdf.params()?;
+            let dir = resolve_data_dir(data_dir);
+            let profile = profile::load(&dir, profile.as_deref())?;
+            let targets = profile.select(&only)?;
+            #[cfg(feature = "kms")]
+            let kms = kms_recipients(&kms_key_id)?;
+            #[cfg(not(feature = "kms"))]
+            let kms = Vec::new();
+            let (sealer, key_id) = if recipient.is_empty() && recipient_key_file.is_empty() && kms.is_empty() {
+                verify_token::ensure_salt(&kdf)?;
+                let key = key.holder()?;
+                let key_id = verify_token.then(|| key.key_id()).transpose()?;
+                let sealer = match key {
+                    Keyholder::Passphrase(key) => Sealer::Passphrase(key),
+                    Keyholder::Agent(agent) => Sealer::Agent(agent),
+                    #[cfg(feature = "kms")]
+                    Keyholder::Kms => bail!("--kms only decrypts; encrypt to a KMS key with --kms-key-id"),
+                };
+                (sealer, key_id)
+            } else {
+                let mut recipients = recipient.iter().map(|r| Recipient::parse(r)).collect::<Result<Vec<_>>>()?;
+                for file in &recipient_key_file {
+                    let passphrase = resolve_passphrase(None, Some(file), None)?;
+                    recipients.push(Recipient::Passphrase(passphrase));
+                }
+                recipients.extend(kms);
+                (Sealer::Recipients(recipients), None)
+            };
+            let header = Header::new(ContentType::json(), Compression::from_flag(compress)).with_suite(suite);
+            let (key_id, backup) = (key_id.as_deref(), backup.retention());
+            cmd_encrypt_local(&sealer, &kdf, &dir, &profile, &targets, &header, key_id, backup, jobs, force, dry_run)
+        }
+        Commands::DecryptLocal { key, kdf, data_dir, profile, only, progress, keep_modified, previous, dry_run } => {
+            if progress {
+                progress::enable();
+            }
+            let dir = resolve_data_dir(data_dir);
+            let profile = profile::load(&dir, profile.as_deref())?;
+            let targets = profile.select(&only)?;
+            let key = key.holder()?;
+            let keep_modified = keep_modified.then_some(previous.as_deref());
+            cmd_decrypt_local(&key, &kdf.params()?, &dir, &profile, &targets, keep_modified, dry_run)
+        }
+        Commands::EncryptFields(args) => fields::encrypt(args),
+        Commands::DecryptFields(args) => fields::decrypt(args),
+        Commands::EncryptGit {
+            key,
+            kdf,
+            data_dir,
+            #[cfg(feature = "kms")]
+            kms_key_id,
+            verify_token,
+            dry_run,
+        } => {
+            let key = key.resolve()?;
+            let kdf = kdf.params()?;
+            let dir = resolve_data_dir(data_dir);
+            verify_token::ensure_salt(&kdf)?;
+            let key_id = token_key_id(&key, verify_token)?;
+            #[cfg(feature = "kms")]
+            let envelope = match kms_recipients(&kms_key_id)? {
+                kms if kms.is_empty() => kms,
+                kms => std::iter::once(Recipient::Passphrase(key.clone())).chain(kms).collect(),
+            };
+            #[cfg(not(feature = "kms"))]
+            let envelope = Vec::new();
+            cmd_encrypt_git(&key, &kdf, &dir, &envelope, key_id.as_deref(), dry_run)
+        }
+        Commands::DecryptGit { key, kdf, data_dir } => {
+            let key = key.holder()?;
+            let dir = resolve_data_dir(data_dir);
+            cmd_decrypt_git(&key, &kdf.params()?, &dir)
+        }
+        Commands::ReEncrypt { key, kdf, data_dir, profile, only, verify_token, backup, dry_run } => {
+            let dir = resolve_data_dir(data_dir);
+            let profile = profile::load(&dir, profile.as_deref())?;
+            let targets = profile.select(&only)?;
+            let key = key.resolve()?;
+            let kdf = kdf.params()?;
+            verify_token::ensure_salt(&kdf)?;
+            let key_id = token_key_id(&key, verify_token)?;
+            cmd_re_encrypt(&key, &kdf, &dir, &profile, &targets, key_id.as_deref(), backup.retention(), dry_run)
+        }
+        Commands::ExportLegacy { key, kdf, data_dir, legacy_version: _, files, output_dir, backup, dry_run } => {
+            let targets = select_targets(&files)?;
+            let key = key.resolve()?;
+            let dir = resolve_data_dir(data_dir);
+            cmd_export_legacy(&key, &kdf.params()?, &dir, &targets, output_dir.as_deref(), backup.retention(), dry_run)
+        }
+        Commands::Verify { key, kdf, data_dir, profile, only, no_key, expect_key_id, strict, deep, git_history, sarif } => {
+            let dir = resolve_data_dir(data_dir);
+            let profile = profile::load(&dir, profile.as_deref())?;
+            let targets = profile.select(&only)?;
+            if sarif.is_some() {
+                sarif::begin();
+            }
+            let issues = if no_key {
+                cmd_verify_no_key(&dir, &profile, &targets, expect_key_id.as_deref(), strict, git_history)?
+            } else {
+                let key = key.resolve()?;
+                cmd_verify(&key, &kdf.params()?, &dir, &profile, &targets, strict, deep, git_history)?
+            };
+            if let Some(path) = &sarif {
+                sarif::write(path, "verify")?;
+            }
+            if issues > 0 {
+                audit::finish(&format!("issues: {}", issues));
+                std::process::exit(EXIT_VERIFY_ISSUES);
+            }
+            Ok(())
+        }
+        Commands::Status { data_dir, daemon: false, json, .. } => status::run(&resolve_data_dir(data_dir), json).map(|_| ()),
+        Commands::Status { data_dir, daemon: true, socket, json } => {
+            let socket = socket.unwrap_or_else(|| monitor::default_socket(&resolve_data_dir(data_dir)));
+            cmd_daemon_status(&socket, json)
+        }
+        Commands::RotateKey { old_key, old_key_file, new_key, new_key_file, kdf, data_dir, profile, dry_run } => {
+            let old = resolve_passphrase(old_key, old_key_file.as_deref(), None)
+                .context("old key — pass --old-key, --old-key-file or set VIOLET_SOUL_KEY")?;
+            let new = resolve_passphrase(new_key, new_key_file.as_deref(), None)
+                .context("new key — pass --new-key, --new-key-file or set VIOLET_SOUL_NEW_KEY")?;
+            let dir = resolve_data_dir(data_dir);
+            let kdf = kdf.params()?;
+            verify_token::ensure_salt(&kdf)?;
+            cmd_rotate_key(&old, &new, &kdf, &dir, profile.as_deref(), dry_run)
+        }
+        Commands::EncryptFile { key, kdf, file, output, salt } => {
+            let key = key.resolve()?;
+            cmd_encrypt_file(&key, &kdf.params()?, &file, output.as_deref(), &salt.for_writing()?)
+        }
+        Commands::Export(args) => export::run(args),
+        Commands::Serve(args) => serve::run(args),
+        Commands::Import(args) => import::run(args),
+        Commands::DecryptFile { key, kdf, file, salt, strict, output, raw } => {
+            let key = key.holder()?;
+            cmd_decrypt_file(&key, &kdf.params()?, &file, &salt, strict, output.as_deref(), raw)
+        }
+        Commands::Diff { key, kdf, data_dir, exit_code } => {
+            let key = key.resolve()?;
+            let dir = resolve_data_dir(data_dir);
+            if diff::run(&key, &kdf.params()?, &dir)? > 0 && exit_code {
+                std::process::exit(EXIT_VERIFY_ISSUES);
+            }
+            Ok(())
+        }
+        Commands::Query { key, kdf, file, path, raw, salt } => {
+            let key = key.resolve()?;
+            cmd_query(&key, &kdf.params()?, &file, &salt, &path, raw)
+        }
+        Commands::EncryptAsset {
+            key,
+            kdf,
+            file,
+            content_type,
+            compress,
+            suite,
+            salt,
+            verify_token,
+            progress,
+            dry_run,
+        } => {
+            if progress {
+                progress::enable();
+            }
+            let key = key.holder()?;
+            let content_type = match content_type {
+                Some(label) => ContentType::parse(&label)?,
+                None => ContentType::from_path(&file),
+            };
+            let header = Header::new(content_type, Compression::from_flag(compress)).with_suite(suite);
+            let key_id = verify_token.then(|| key.key_id()).transpose()?;
+            cmd_encrypt_asset(&key, &kdf.params()?, &file, &header, &salt.for_writing()?, key_id.as_deref(), dry_run)
+        }
+        Commands::DecryptAsset { key, kdf, file, output, salt, strict, progress, dry_run } => {
+            if progress {
+                progress::enable();
+            }
+            let key = key.holder()?;
+            cmd_decrypt_asset(&key, &kdf.params()?, &file, output, &salt, strict, dry_run)
+        }
+        #[cfg(feature = "sqlite-store")]
+        Commands::Store { action } => store::run(action),
+        Commands::Key { action } => shamir::run(action),
+        Commands::RestoreBackup { file, from, list, dry_run } => cmd_restore_backup(&file, from, list, dry_run),
+        #[cfg(feature = "test-vectors")]
+        Commands::TestVectors(args) => vectors::run(args),
+        Commands::Backup { action } => archive::run(action),
+        Commands::ExportBundle(args) => bundle::export(args),
+        Commands::ImportBundle(args) => bundle::import(args),
+        Commands::Pack(args) => container::pack(args),
+        Commands::Unpack(args) => container::unpack(args),
+        Commands::Config { action } => config::run(action),
+        Commands::Keyring { action } => keyring::run(action),
+        Commands::Audit { action } => {
+            if audit::run(action)? > 0 {
+                std::process::exit(EXIT_VERIFY_ISSUES);
+            }
+            Ok(())
+        }
+        Commands::Agent { action } => agent::run(action),
+        Commands::Manifest { action } => {
+            if manifest::run(action)? > 0 {
+                std::process::exit(EXIT_VERIFY_ISSUES);
+            }
+            Ok(())
+        }
+        Commands::Watch {
+            key,
+            kdf,
+            data_dir,
+            compress,
+            suite,
+            verify_token,
+            detached_mac,
+            backup,
+            debounce_ms,
+            status_socket,
+            no_status_socket,
+        } => {
+            if detached_mac {
+                detached_mac::enable_writes()?;
+            }
+            let key = key.resolve()?;
+            let kdf = kdf.params()?;
+            let dir = resolve_data_dir(data_dir);
+            verify_token::ensure_salt(&kdf)?;
+            let key_id = token_key_id(&key, verify_token)?;
+            let debounce = Duration::from_millis(debounce_ms);
+            let header = Header::new(ContentType::json(), Compression::from_flag(compress)).with_suite(suite);
+            let socket = (!no_status_socket).then(|| status_socket.unwrap_or_else(|| monitor::default_socket(&dir)));
+            let sealer = Sealer::Passphrase(key);
+            let backup = backup.retention();
+            watch::run(&sealer, &kdf, &dir, &header, key_id.as_deref(), backup, debounce, socket.as_deref())
+        }
+        Commands::CompatCheck { key, kdf, dir, report } => {
+            let key = key.resolve()?;
+            if compat::run(&key, &kdf.params()?, &dir, report.as_deref())? > 0 {
+                std::process::exit(EXIT_VERIFY_ISSUES);
+            }
+            Ok(())
+        }
+        Commands::CheckStaged { data_dir, all, sarif } => {
+            let dir = resolve_data_dir(data_dir);
+            if sarif.is_some() {
+                sarif::begin();
+            }
+            let issues = staged::check_staged(&dir, all)?;
+            if let Some(path) = &sarif {
+                sarif::write(path, "check-staged")?;
+            }
+            if issues > 0 {
+                std::process::exit(EXIT_VERIFY_ISSUES);
+            }
+            Ok(())
+        }
+        Commands::InstallHooks(args) => hooks::run(args),
+        Commands::EnvAudit { key, repo } => {
+            let key = key.resolve()?;
+            if env_audit::run(&key, repo.as_deref())? > 0 {
+                std::process::exit(EXIT_VERIFY_ISSUES);
+            }
+            Ok(())
+        }
+        Commands::Keygen { output, signing, pq } => {
+            let public = if signing {
+                signing::keygen(output.as_deref())?.to_string()
+            } else {
+                recipient::keygen(output.as_deref(), pq)?.to_string()
+            };
+            match output {
+                Some(path) => {
+                    let what = if signing { "Signing key" } else { "Identity" };
+                    info!("🔑 {} written to {:?} — keep it secret", what, path);
+                    println!("{}", public);
+                }
+                None => logging::note(format_args!("🔑 Public key: {}", public)),
+            }
+            Ok(())
+        }
+        Commands::Sign { signing_key, files, dry_run } => signing::sign(&signing_key, &files, dry_run),
+        Commands::VerifySignature { signers, files } => {
+            let trusted = signers.iter().map(|s| Signer::parse(s)).collect::<Result<Vec<_>>>()?;
+            if signing::verify(&files, &trusted)? > 0 {
+                std::process::exit(EXIT_VERIFY_ISSUES);
+            }
+            Ok(())
+        }
+        Commands::SelfTest => {
+            if self_test::run()? > 0 {
+                std::process::exit(EXIT_VERIFY_ISSUES);
+            }
+            Ok(())
+        }
+        Commands::Inspect { file } => {
+            let data = fs::read(&file).with_context(|| format!("read {:?}", file))?;
+            let inspection = preamble::inspect(&file.display().to_string(), &data);
+            println!("{}", serde_json::to_string_pretty(&inspection)?);
+            Ok(())
+        }
+        Commands::BenchKdf { target_ms, max_memory_mib } => {
+            cmd_bench_kdf(Duration::from_millis(target_ms), max_memory_mib)
+        }
+        Commands::Completions(args) => completions::run(args),
+    }
+}
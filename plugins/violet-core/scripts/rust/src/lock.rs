@@ -0,0 +1,51 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Data directory lock — one writing run per data directory at a time
+//
+// Commands that write into the data directory hold an exclusive advisory
+// lock on `.violet.lock` there for the whole run (`watch` for as long as it
+// watches). A second one, e.g. a manual `encrypt-local` next to the watcher
+// or a git hook's `decrypt-local`, fails at once rather than interleaving its
+// writes with the first. The lock belongs to the open file, so the OS drops
+// it when a run ends or dies and a crash never leaves the directory locked;
+// the file stays, naming the last holder's PID and command for the message.
+// Dry runs and commands that only read take no lock.
+use std::fs::{self, File, TryLockError};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+pub const LOCK_FILE: &str = ".violet.lock";
+
+/// Held for the run; dropping it releases the lock
+pub struct DataDirLock(#[allow(dead_code)] File);
+
+/// Lock `data_dir` for `command`; None when the directory does not exist yet
+pub fn acquire(data_dir: &Path, command: &str) -> Result<Option<DataDirLock>> {
+    if !data_dir.is_dir() {
+        return Ok(None);
+    }
+    let path = data_dir.join(LOCK_FILE);
+    // Not truncated on open: until the lock is ours the file names whoever holds it
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .with_context(|| format!("open lock file {:?}", path))?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let holder = fs::read_to_string(&path).ok().map(|text| text.trim().to_string()).filter(|t| !t.is_empty());
+            bail!(
+                "{:?} is in use by another violet-cipher run ({}) — let it finish or stop it, then retry",
+                data_dir,
+                holder.as_deref().unwrap_or("holder unknown")
+            );
+        }
+        Err(TryLockError::Error(e)) => return Err(e).with_context(|| format!("lock {:?}", path)),
+    }
+    file.set_len(0).and_then(|()| writeln!(file, "{}, pid {}", command, std::process::id())).ok();
+    Ok(Some(DataDirLock(file)))
+}
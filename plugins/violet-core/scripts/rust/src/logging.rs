@@ -0,0 +1,168 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Logging — what a run reports, from `--quiet` to `-vv`, as JSON lines (`--log-json`) or plain ASCII (`--plain`)
+//
+// Reports are tracing events: errors (a file that failed, a verify finding),
+// warnings, info (the emoji lines every command prints), debug (`-v`: each
+// layer's Argon2id time, each file's format and size) and trace (`-vv`: every
+// file written). Plain output is the message alone, as the println!s printed
+// it; `--log-json` prints one object per event with its timestamp, level,
+// message and fields (file, layer, ms, ...) for the plugin host to parse.
+//
+// Lines go to stdout, where the reports always went, unless a command writes
+// its result there (decrypted bytes, keys, --json): it calls `claim_stdout`
+// first and the lines move to stderr. Results are printed at every level,
+// JSON and keys with println!, lines meant for people with `out` (stdout) or
+// `note` (stderr, beside a result or as a prompt).
+//
+// `--plain` / `--no-emoji`, or NO_COLOR in the environment, is for CI logs
+// and pipes: findings keep a word tag ([ok], [FAIL], [WARN], ...), the other
+// emoji go, arrows and dashes become ASCII, and neither progress bars nor the
+// watch status line draw colour or cursor movement. Text from files and
+// errors passes through untouched; JSON lines are left as they are.
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use clap::{ArgAction, Args};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::format;
+
+static STDOUT_CLAIMED: AtomicBool = AtomicBool::new(false);
+static STATUS_LINE: AtomicBool = AtomicBool::new(false);
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Emoji that carry meaning, and the tag that stands in for them in ASCII output
+const TAGS: &[(char, &str)] = &[
+    ('✅', "[ok]"),
+    ('❌', "[FAIL]"),
+    ('🚨', "[ALERT]"),
+    ('⚠', "[WARN]"),
+    ('⏭', "[skip]"),
+    ('ℹ', "[info]"),
+    ('⏳', "[wait]"),
+];
+
+/// Typographic characters of the messages and their ASCII spelling
+const SYMBOLS: &[(char, &str)] = &[
+    ('→', "->"),
+    ('↳', "->"),
+    ('—', "-"),
+    ('–', "-"),
+    ('─', "-"),
+    ('…', "..."),
+    ('‖', "||"),
+    ('×', "x"),
+];
+
+#[derive(Args)]
+pub struct LogArgs {
+    /// Print errors only
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// More detail: -v adds per-layer KDF timings and file details, -vv every file written
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// Print each log line as a JSON object (timestamp, level, message, fields)
+    #[arg(long, global = true)]
+    log_json: bool,
+    /// ASCII output for CI logs and pipes: word tags instead of emoji, no colour (also set by NO_COLOR)
+    #[arg(long, visible_alias = "no-emoji", global = true)]
+    plain: bool,
+}
+
+/// Install the subscriber for the run; a no-op if the host already set one
+pub fn init(args: &LogArgs) {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    ASCII.store(args.plain || no_color, Ordering::Relaxed);
+    let level = match (args.quiet, args.verbose) {
+        (true, _) => LevelFilter::ERROR,
+        (false, 0) => LevelFilter::INFO,
+        (false, 1) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    };
+    let builder = tracing_subscriber::fmt().with_max_level(level).with_writer(writer);
+    let installed = if args.log_json {
+        builder.json().flatten_event(true).with_current_span(false).with_span_list(false).try_init()
+    } else {
+        builder
+            .without_time()
+            .with_level(false)
+            .with_target(false)
+            .with_ansi(false)
+            .fmt_fields(format::debug_fn(|writer, field, value| match field.name() {
+                "message" => write!(writer, "{}", text(&format!("{:?}", value))),
+                _ => Ok(()),
+            }))
+            .try_init()
+    };
+    STATUS_LINE.store(installed.is_ok() && !args.log_json && !args.quiet && !ascii(), Ordering::Relaxed);
+}
+
+/// The command prints its result on stdout: send log lines to stderr from now on
+pub fn claim_stdout() {
+    STDOUT_CLAIMED.store(true, Ordering::Relaxed);
+}
+
+/// Info lines are printed for a terminal, so a redrawn status line can sit among them
+pub fn status_line() -> bool {
+    STATUS_LINE.load(Ordering::Relaxed)
+}
+
+/// ASCII output (`--plain` or NO_COLOR)
+pub fn ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+/// A result line for people, on stdout
+pub fn out(line: fmt::Arguments) {
+    println!("{}", text(&line.to_string()));
+}
+
+/// A line on stderr, beside a result on stdout or as a prompt
+pub fn note(line: fmt::Arguments) {
+    eprintln!("{}", text(&line.to_string()));
+}
+
+/// `line` as it should be printed: unchanged, or in ASCII under `--plain`
+pub fn text(line: &str) -> Cow<'_, str> {
+    if !ascii() || line.is_ascii() {
+        return Cow::Borrowed(line);
+    }
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some((_, ascii)) = SYMBOLS.iter().find(|(symbol, _)| *symbol == c) {
+            out.push_str(ascii);
+            continue;
+        }
+        if !is_emoji(c) {
+            out.push(c);
+            continue;
+        }
+        // The variation selector and the spaces that pad the emoji go with it
+        chars.next_if_eq(&'\u{fe0f}');
+        let padded = chars.next_if_eq(&' ').is_some();
+        while chars.next_if_eq(&' ').is_some() {}
+        if let Some((_, tag)) = TAGS.iter().find(|(emoji, _)| *emoji == c) {
+            out.push_str(tag);
+            if padded {
+                out.push(' ');
+            }
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Pictographs and the symbol blocks the messages draw their emoji from
+fn is_emoji(c: char) -> bool {
+    matches!(c, '\u{1f000}'..='\u{1faff}' | '\u{2600}'..='\u{27bf}' | '\u{2300}'..='\u{23ff}' | 'ℹ')
+}
+
+fn writer() -> Box<dyn Write> {
+    if STDOUT_CLAIMED.load(Ordering::Relaxed) {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    }
+}
@@ -0,0 +1,203 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Artifact manifest — an HMAC of every encrypted file, to catch changes made outside the tool
+//
+// `manifest write` records, for every .enc and .git.enc under the data
+// directory, an HMAC-SHA256 of its relative path and contents in
+// manifest.vsc. The HMAC key is Argon2id over the passphrase with a random
+// salt kept in the manifest, and a final HMAC covers the whole manifest, so
+// without the passphrase neither a file nor the manifest can be changed to
+// match. `manifest verify` recomputes everything and names each file that
+// was added, removed or modified since; re-run `manifest write` after
+// encrypting on purpose.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{bail, Context, Result};
+use argon2::Params as KdfParams;
+use clap::Subcommand;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::{error, info};
+
+use crate::archive::collect_files;
+use crate::backup::utc_timestamp;
+use crate::kdf::KdfArgs;
+use crate::key_source::KeyArgs;
+use crate::keyring::KdfCosts;
+use crate::secret::SecretKey;
+use crate::{
+    derive_key_argon2, layer_passphrase, print_dry_run_banner, random_bytes, report_planned_write, resolve_data_dir,
+    write_atomic, ARGON2_SALT_LEN, LOCAL_SALT,
+};
+
+pub const MANIFEST_FILE: &str = "manifest.vsc";
+const MANIFEST_VERSION: u32 = 1;
+const MANIFEST_CONTEXT: &[u8] = b"violet-manifest-v1\0";
+
+#[derive(Subcommand)]
+pub enum ManifestCommand {
+    /// Record an HMAC of every .enc and .git.enc in the data directory
+    Write {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Report encrypted files added, removed or modified since the manifest was written
+    Verify {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+}
+
+/// Everything the closing HMAC covers
+#[derive(Serialize, Deserialize)]
+struct Body {
+    version: u32,
+    /// Hex Argon2id salt of the HMAC key
+    salt: String,
+    kdf: KdfCosts,
+    /// UTC, `20261017T040912Z`
+    written: String,
+    /// Relative path → hex HMAC of path and contents
+    files: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    #[serde(flatten)]
+    body: Body,
+    mac: String,
+}
+
+fn manifest_key(passphrase: &[u8], salt: &[u8], kdf: &KdfParams) -> Result<SecretKey> {
+    derive_key_argon2(&layer_passphrase(passphrase, "manifest", LOCAL_SALT), salt, kdf)
+}
+
+fn hmac(key: &[u8], parts: &[&[u8]]) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC init");
+    mac.update(MANIFEST_CONTEXT);
+    for part in parts {
+        mac.update(part);
+    }
+    mac
+}
+
+/// HMAC of every .enc and .git.enc under `data_dir`, by relative path
+fn artifact_macs(key: &[u8], data_dir: &Path) -> Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+    for (relative, path) in collect_files(data_dir)? {
+        if !relative.ends_with(".enc") {
+            continue;
+        }
+        let data = fs::read(&path).with_context(|| format!("read {:?}", path))?;
+        let mac = hmac(key, &[relative.as_bytes(), b"\0", &data]).finalize().into_bytes();
+        files.insert(relative, hex::encode(mac));
+    }
+    Ok(files)
+}
+
+fn write(passphrase: &[u8], kdf: &KdfParams, data_dir: &Path, dry_run: bool) -> Result<()> {
+    print_dry_run_banner(dry_run);
+    info!("🧾 Recording an HMAC of every encrypted file in {:?}...", data_dir);
+    let salt = random_bytes::<ARGON2_SALT_LEN>();
+    let key = manifest_key(passphrase, &salt, kdf)?;
+    let files = artifact_macs(&*key, data_dir)?;
+    for relative in files.keys() {
+        info!("  🔏 {}", relative);
+    }
+    let body = Body {
+        version: MANIFEST_VERSION,
+        salt: hex::encode(salt),
+        kdf: KdfCosts::from(kdf),
+        written: utc_timestamp(SystemTime::now()),
+        files,
+    };
+    let mac = hex::encode(hmac(&*key, &[&serde_json::to_vec(&body)?]).finalize().into_bytes());
+    let count = body.files.len();
+    let json = serde_json::to_string_pretty(&Manifest { body, mac })?;
+    let path = data_dir.join(MANIFEST_FILE);
+    if dry_run {
+        report_planned_write(&path, json.as_bytes(), false);
+        return Ok(());
+    }
+    write_atomic(&path, json.as_bytes())?;
+    info!("🧾 {} file(s) recorded in {}.", count, MANIFEST_FILE);
+    Ok(())
+}
+
+/// Compare the encrypted files with the manifest; returns how many differ
+fn verify(passphrase: &[u8], kdf: &KdfParams, data_dir: &Path) -> Result<u32> {
+    let path = data_dir.join(MANIFEST_FILE);
+    if !path.exists() {
+        bail!("no {} in {:?} — run `manifest write` first", MANIFEST_FILE, data_dir);
+    }
+    let json = fs::read_to_string(&path).with_context(|| format!("read {:?}", path))?;
+    let manifest: Manifest = serde_json::from_str(&json).with_context(|| format!("parse {:?}", path))?;
+    let body = &manifest.body;
+    if body.version != MANIFEST_VERSION {
+        bail!("unsupported manifest version {}", body.version);
+    }
+    let salt = hex::decode(&body.salt).context("manifest salt is not hex")?;
+    let expected = hex::decode(&manifest.mac).context("manifest mac is not hex")?;
+    let key = manifest_key(passphrase, &salt, kdf)?;
+    if hmac(&*key, &[&serde_json::to_vec(body)?]).verify_slice(&expected).is_err() {
+        bail!(
+            "{} does not authenticate — wrong passphrase or --kdf-* settings (it was written with {}), \
+             or the manifest itself was edited",
+            MANIFEST_FILE,
+            body.kdf
+        );
+    }
+
+    info!("🧾 Checking encrypted files against {} (written {})...", MANIFEST_FILE, body.written);
+    let current = artifact_macs(&*key, data_dir)?;
+    let mut issues = 0u32;
+    for (relative, recorded) in &body.files {
+        match current.get(relative) {
+            Some(mac) if mac == recorded => info!("  ✅ {}", relative),
+            Some(_) => {
+                error!("  ❌ {} — modified since the manifest was written", relative);
+                issues += 1;
+            }
+            None => {
+                error!("  ❌ {} — removed since the manifest was written", relative);
+                issues += 1;
+            }
+        }
+    }
+    for relative in current.keys().filter(|relative| !body.files.contains_key(*relative)) {
+        error!("  ❌ {} — added since the manifest was written", relative);
+        issues += 1;
+    }
+    if issues > 0 {
+        info!("🧾 {} file(s) changed outside the manifest. Re-run `manifest write` if that was you.", issues);
+    } else {
+        info!("🧾 All {} file(s) match the manifest.", body.files.len());
+    }
+    Ok(issues)
+}
+
+/// Run a manifest subcommand; returns how many files `verify` found changed
+pub fn run(command: ManifestCommand) -> Result<u32> {
+    match command {
+        ManifestCommand::Write { key, kdf, data_dir, dry_run } => {
+            write(&key.resolve()?, &kdf.params()?, &resolve_data_dir(data_dir), dry_run)?;
+            Ok(0)
+        }
+        ManifestCommand::Verify { key, kdf, data_dir } => {
+            verify(&key.resolve()?, &kdf.params()?, &resolve_data_dir(data_dir))
+        }
+    }
+}
@@ -0,0 +1,361 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Violet Cipher MCP Server — JSON-RPC 2.0 over stdio
+//
+// Tools: decrypt_file, encrypt_file, verify_integrity, status, diff. Each call
+// runs the violet-cipher binary that sits next to this one (or --cipher) and
+// hands back what it printed, so an agent can work with the encrypted data
+// without anyone copy-pasting CLI output. The passphrase never travels
+// through the protocol: the CLI reads it from VIOLET_SOUL_KEY in the server's
+// environment or from the server's --key-file, and tool arguments cannot
+// name one.
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+const SERVER_NAME: &str = "violet-cipher-mcp";
+const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Exit status the CLI uses for "ran fine, found issues" (1 is a hard error)
+const EXIT_VERIFY_ISSUES: i32 = 2;
+
+#[derive(Parser)]
+#[command(name = "violet-cipher-mcp", version = SERVER_VERSION)]
+struct ServerArgs {
+    /// violet-cipher binary to run (default: the one next to this server)
+    #[arg(long)]
+    cipher: Option<PathBuf>,
+    /// Data directory passed to every call (default: the CLI's own default)
+    #[arg(long)]
+    data_dir: Option<PathBuf>,
+    /// Passphrase file passed to every call (default: VIOLET_SOUL_KEY from the environment)
+    #[arg(long)]
+    key_file: Option<PathBuf>,
+    /// Argon2id memory per layer in KiB, passed to every call that derives keys
+    #[arg(long)]
+    kdf_memory: Option<u32>,
+    /// Argon2id iterations per layer
+    #[arg(long)]
+    kdf_iterations: Option<u32>,
+    /// Argon2id lanes per layer
+    #[arg(long)]
+    kdf_parallelism: Option<u32>,
+}
+
+/// Server-wide settings shared by every tool call
+struct ServerContext {
+    cipher: PathBuf,
+    data_dir: Option<PathBuf>,
+    key_flags: Vec<String>,
+    kdf_flags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct JsonRpcRequest {
+    jsonrpc: String,
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize)]
+struct JsonRpcResponse {
+    jsonrpc: String,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Serialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+/// What one violet-cipher run printed and how it exited
+struct CliOutput {
+    status: i32,
+    stdout: String,
+    stderr: String,
+}
+
+impl ServerContext {
+    fn load(args: ServerArgs) -> Result<Self> {
+        let cipher = match args.cipher {
+            Some(path) => path,
+            None => {
+                let exe = std::env::current_exe().context("locate the server binary")?;
+                exe.with_file_name(format!("violet-cipher{}", std::env::consts::EXE_SUFFIX))
+            }
+        };
+        if !cipher.is_file() {
+            bail!("violet-cipher not found at {:?} — build it or pass --cipher", cipher);
+        }
+        let mut key_flags = Vec::new();
+        if let Some(key_file) = args.key_file {
+            key_flags.extend(["--key-file".to_string(), key_file.display().to_string()]);
+        }
+        let mut kdf_flags = Vec::new();
+        for (flag, value) in [
+            ("--kdf-memory", args.kdf_memory),
+            ("--kdf-iterations", args.kdf_iterations),
+            ("--kdf-parallelism", args.kdf_parallelism),
+        ] {
+            if let Some(value) = value {
+                kdf_flags.extend([flag.to_string(), value.to_string()]);
+            }
+        }
+        Ok(Self { cipher, data_dir: args.data_dir, key_flags, kdf_flags })
+    }
+
+    /// A file argument as given, or relative to the data directory
+    fn resolve_file(&self, file: &str) -> PathBuf {
+        match &self.data_dir {
+            Some(dir) if Path::new(file).is_relative() => dir.join(file),
+            _ => PathBuf::from(file),
+        }
+    }
+
+    fn data_dir_flags(&self) -> Vec<String> {
+        match &self.data_dir {
+            Some(dir) => vec!["--data-dir".to_string(), dir.display().to_string()],
+            None => Vec::new(),
+        }
+    }
+
+    /// Run violet-cipher; `keyed` adds the passphrase and KDF flags
+    fn run(&self, args: &[String], keyed: bool) -> Result<CliOutput> {
+        let mut command = Command::new(&self.cipher);
+        command.args(args);
+        if keyed {
+            command.args(&self.key_flags).args(&self.kdf_flags);
+        }
+        let output = command
+            .stdin(Stdio::null())
+            .output()
+            .with_context(|| format!("run {:?}", self.cipher))?;
+        Ok(CliOutput {
+            status: output.status.code().unwrap_or(-1),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+fn make_response(id: Value, result: Value) -> JsonRpcResponse {
+    JsonRpcResponse { jsonrpc: "2.0".into(), id, result: Some(result), error: None }
+}
+
+fn make_error(id: Value, code: i64, message: String) -> JsonRpcResponse {
+    JsonRpcResponse { jsonrpc: "2.0".into(), id, result: None, error: Some(JsonRpcError { code, message }) }
+}
+
+fn make_text_content(text: &str, is_error: bool) -> Value {
+    json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })
+}
+
+/// Tool result from a CLI run: stdout on success, stderr too on failure
+///
+/// Exit status 2 ("issues found") is a result, not a tool error.
+fn cli_content(output: CliOutput) -> Value {
+    match output.status {
+        0 => make_text_content(&output.stdout, false),
+        EXIT_VERIFY_ISSUES => {
+            make_text_content(&format!("{}\n(issues found — exit status {})", output.stdout, output.status), false)
+        }
+        status => {
+            let text = format!("{}{}", output.stdout, output.stderr);
+            make_text_content(&format!("{}\n(violet-cipher failed — exit status {})", text.trim_end(), status), true)
+        }
+    }
+}
+
+fn handle_initialize(id: Value) -> JsonRpcResponse {
+    make_response(id, json!({
+        "protocolVersion": PROTOCOL_VERSION,
+        "capabilities": {
+            "tools": { "listChanged": false }
+        },
+        "serverInfo": { "name": SERVER_NAME, "version": SERVER_VERSION }
+    }))
+}
+
+fn handle_tools_list(id: Value) -> JsonRpcResponse {
+    make_response(id, json!({ "tools": [
+        {
+            "name": "decrypt_file",
+            "description": "Decrypt a .enc file in memory and return its JSON, or only the value at a JSON pointer; nothing is written to disk",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": { "type": "string", "description": "The .enc file, absolute or relative to the data directory (e.g. 'minds-index.json.enc')" },
+                    "pointer": { "type": "string", "description": "JSON pointer to return instead of the whole document (e.g. '/minds/Lilith')" },
+                    "salt": { "type": "string", "enum": ["local", "git"], "description": "Salt label (default: local)" }
+                },
+                "required": ["file"]
+            }
+        },
+        {
+            "name": "encrypt_file",
+            "description": "Encrypt a file to <file>.enc (v5, binary-safe); without a file, encrypt every target JSON file in the data directory",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": { "type": "string", "description": "File to encrypt, absolute or relative to the data directory (default: all target files)" },
+                    "compress": { "type": "boolean", "description": "zstd-compress before encrypting" },
+                    "dry_run": { "type": "boolean", "description": "Report what would be written without touching disk" }
+                }
+            }
+        },
+        {
+            "name": "verify_integrity",
+            "description": "Check that every .enc in the data directory decrypts, and optionally that it matches the bundled JSON Schemas",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "deep": { "type": "boolean", "description": "Also validate decrypted payloads against the bundled JSON Schemas" },
+                    "strict": { "type": "boolean", "description": "Also count legacy (v2/v3) files as issues" }
+                }
+            }
+        },
+        {
+            "name": "status",
+            "description": "Per-target encryption state as JSON: in-sync, stale, not-encrypted, encrypted-only, damaged or missing (no key needed)",
+            "inputSchema": { "type": "object", "properties": {} }
+        },
+        {
+            "name": "diff",
+            "description": "List, by JSON pointer, how each plaintext target differs from its decrypted .enc",
+            "inputSchema": { "type": "object", "properties": {} }
+        }
+    ]}))
+}
+
+fn string_arg<'a>(params: &'a Value, name: &str) -> Option<&'a str> {
+    params.get(name).and_then(|v| v.as_str())
+}
+
+fn bool_arg(params: &Value, name: &str) -> bool {
+    params.get(name).and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+fn tool_decrypt_file(params: &Value, ctx: &ServerContext) -> Result<Value> {
+    let file = string_arg(params, "file").context("Missing file")?;
+    let file = ctx.resolve_file(file).display().to_string();
+    let salt = string_arg(params, "salt").unwrap_or("local").to_string();
+    let args = match string_arg(params, "pointer") {
+        Some(pointer) => vec!["query".into(), "--file".into(), file, "--path".into(), pointer.into(), "--salt".into(), salt],
+        None => vec!["decrypt-file".into(), "--file".into(), file, "--salt".into(), salt],
+    };
+    Ok(cli_content(ctx.run(&args, true)?))
+}
+
+fn tool_encrypt_file(params: &Value, ctx: &ServerContext) -> Result<Value> {
+    let mut args = match string_arg(params, "file") {
+        Some(file) => vec!["encrypt-asset".into(), "--file".into(), ctx.resolve_file(file).display().to_string()],
+        None => [vec!["encrypt-local".to_string()], ctx.data_dir_flags()].concat(),
+    };
+    if bool_arg(params, "compress") {
+        args.push("--compress".into());
+    }
+    if bool_arg(params, "dry_run") {
+        args.push("--dry-run".into());
+    }
+    Ok(cli_content(ctx.run(&args, true)?))
+}
+
+fn tool_verify_integrity(params: &Value, ctx: &ServerContext) -> Result<Value> {
+    let mut args = [vec!["verify".to_string()], ctx.data_dir_flags()].concat();
+    for flag in ["deep", "strict"] {
+        if bool_arg(params, flag) {
+            args.push(format!("--{}", flag));
+        }
+    }
+    Ok(cli_content(ctx.run(&args, true)?))
+}
+
+fn tool_status(ctx: &ServerContext) -> Result<Value> {
+    let args = [vec!["status".to_string(), "--json".to_string()], ctx.data_dir_flags()].concat();
+    Ok(cli_content(ctx.run(&args, false)?))
+}
+
+fn tool_diff(ctx: &ServerContext) -> Result<Value> {
+    let args = [vec!["diff".to_string(), "--exit-code".to_string()], ctx.data_dir_flags()].concat();
+    Ok(cli_content(ctx.run(&args, true)?))
+}
+
+fn handle_tool_call(id: Value, params: &Value, ctx: &ServerContext) -> JsonRpcResponse {
+    let tool_name = match params.get("name").and_then(|v| v.as_str()) {
+        Some(n) => n,
+        None => return make_error(id, -32602, "Missing tool name".into()),
+    };
+    let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+
+    let result = match tool_name {
+        "decrypt_file" => tool_decrypt_file(&arguments, ctx),
+        "encrypt_file" => tool_encrypt_file(&arguments, ctx),
+        "verify_integrity" => tool_verify_integrity(&arguments, ctx),
+        "status" => tool_status(ctx),
+        "diff" => tool_diff(ctx),
+        _ => return make_error(id, -32601, format!("Unknown tool: {}", tool_name)),
+    };
+
+    match result {
+        Ok(content) => make_response(id, content),
+        Err(e) => make_response(id, make_text_content(&format!("Error: {:#}", e), true)),
+    }
+}
+
+fn main() {
+    let ctx = match ServerContext::load(ServerArgs::parse()) {
+        Ok(ctx) => ctx,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request: JsonRpcRequest = match serde_json::from_str(&line) {
+            Ok(r) => r,
+            Err(e) => {
+                let err = make_error(json!(null), -32700, format!("Parse error: {}", e));
+                let _ = writeln!(stdout, "{}", serde_json::to_string(&err).unwrap());
+                let _ = stdout.flush();
+                continue;
+            }
+        };
+
+        let id = request.id.clone().unwrap_or(json!(null));
+        let response = match request.method.as_str() {
+            "initialize" => handle_initialize(id),
+            "initialized" | "notifications/initialized" => continue,
+            "tools/list" => handle_tools_list(id),
+            "tools/call" => handle_tool_call(id, &request.params, &ctx),
+            "ping" => make_response(id, json!({})),
+            _ => make_error(id, -32601, format!("Method not found: {}", request.method)),
+        };
+
+        let _ = writeln!(stdout, "{}", serde_json::to_string(&response).unwrap());
+        let _ = stdout.flush();
+    }
+}
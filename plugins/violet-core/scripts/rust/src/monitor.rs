@@ -0,0 +1,189 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Watch daemon status socket — what a running `watch` is doing, for `status --daemon`
+//
+// `watch` listens on a Unix socket (default: .violet-watch.sock in the data
+// directory, mode 0600). Each connection gets one JSON snapshot and is
+// closed: uptime, the key IDs of the passphrases it holds (never the keys),
+// the files it watches, counters and the most recent operations. A socket
+// left behind by a daemon that died is replaced on the next start; one that
+// still answers is refused, so two daemons never race on the same .enc.
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::backup::utc_timestamp;
+use crate::recipient::Recipient;
+use crate::{verify_token, Sealer};
+
+pub const SOCKET_FILE: &str = ".violet-watch.sock";
+/// Operations kept for the snapshot, newest last
+const RECENT_OPERATIONS: usize = 20;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Outcome {
+    Encrypted,
+    /// Saved but not valid JSON yet
+    Skipped,
+    Failed,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Operation {
+    /// UTC, `20261017T040912Z`
+    pub at: String,
+    pub file: String,
+    pub outcome: Outcome,
+    pub detail: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub pid: u32,
+    pub started: String,
+    pub uptime_secs: u64,
+    pub data_dir: String,
+    /// Key IDs of the passphrases in use; public-key recipients as their key prefix
+    pub key_fingerprints: Vec<String>,
+    pub files_watched: Vec<String>,
+    pub encrypted: u64,
+    pub skipped: u64,
+    pub errors: u64,
+    pub recent: Vec<Operation>,
+}
+
+struct State {
+    started: SystemTime,
+    clock: Instant,
+    data_dir: String,
+    key_fingerprints: Vec<String>,
+    files_watched: Vec<String>,
+    encrypted: u64,
+    skipped: u64,
+    errors: u64,
+    recent: VecDeque<Operation>,
+}
+
+/// Shared counters the watch loop updates and the socket thread reads
+#[derive(Clone)]
+pub struct Monitor(Arc<Mutex<State>>);
+
+impl Monitor {
+    pub fn new(data_dir: &Path, key_fingerprints: Vec<String>, files_watched: &[&str]) -> Self {
+        Self(Arc::new(Mutex::new(State {
+            started: SystemTime::now(),
+            clock: Instant::now(),
+            data_dir: data_dir.display().to_string(),
+            key_fingerprints,
+            files_watched: files_watched.iter().map(|name| name.to_string()).collect(),
+            encrypted: 0,
+            skipped: 0,
+            errors: 0,
+            recent: VecDeque::with_capacity(RECENT_OPERATIONS),
+        })))
+    }
+
+    pub fn record(&self, file: &str, outcome: Outcome, detail: String) {
+        let mut state = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match outcome {
+            Outcome::Encrypted => state.encrypted += 1,
+            Outcome::Skipped => state.skipped += 1,
+            Outcome::Failed => state.errors += 1,
+        }
+        if state.recent.len() == RECENT_OPERATIONS {
+            state.recent.pop_front();
+        }
+        let at = utc_timestamp(SystemTime::now());
+        state.recent.push_back(Operation { at, file: file.to_string(), outcome, detail });
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let state = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Snapshot {
+            pid: std::process::id(),
+            started: utc_timestamp(state.started),
+            uptime_secs: state.clock.elapsed().as_secs(),
+            data_dir: state.data_dir.clone(),
+            key_fingerprints: state.key_fingerprints.clone(),
+            files_watched: state.files_watched.clone(),
+            encrypted: state.encrypted,
+            skipped: state.skipped,
+            errors: state.errors,
+            recent: state.recent.iter().cloned().collect(),
+        }
+    }
+
+    /// Answer status queries on `socket` from a background thread
+    #[cfg(unix)]
+    pub fn serve(&self, socket: &Path) -> Result<()> {
+        use std::io::Write;
+        use std::os::unix::fs::PermissionsExt;
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        if socket.exists() {
+            if UnixStream::connect(socket).is_ok() {
+                bail!("another watch is already serving {:?}", socket);
+            }
+            std::fs::remove_file(socket).with_context(|| format!("remove stale socket {:?}", socket))?;
+        }
+        let listener = UnixListener::bind(socket).with_context(|| format!("bind {:?}", socket))?;
+        std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("chmod {:?}", socket))?;
+
+        let monitor = self.clone();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                if let Ok(json) = serde_json::to_vec_pretty(&monitor.snapshot()) {
+                    let _ = stream.write_all(&json);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn serve(&self, _socket: &Path) -> Result<()> {
+        bail!("the watch status socket is only supported on Unix")
+    }
+}
+
+pub fn default_socket(data_dir: &Path) -> PathBuf {
+    data_dir.join(SOCKET_FILE)
+}
+
+/// Key IDs of passphrases and shortened public keys: enough to tell keys apart, nothing to decrypt with
+pub fn fingerprints(sealer: &Sealer) -> Result<Vec<String>> {
+    match sealer {
+        Sealer::Passphrase(key) => Ok(vec![verify_token::derive_key_id(key)?]),
+        Sealer::Agent(agent) => Ok(vec![agent.key_id()?]),
+        Sealer::Recipients(recipients) => recipients
+            .iter()
+            .map(|recipient| match recipient {
+                Recipient::Passphrase(key) => verify_token::derive_key_id(key),
+                public => Ok(format!("{}…", public.to_string().chars().take(32).collect::<String>())),
+            })
+            .collect(),
+    }
+}
+
+/// Ask the watch serving `socket` for its snapshot
+#[cfg(unix)]
+pub fn query(socket: &Path) -> Result<Snapshot> {
+    use std::io::Read;
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket)
+        .with_context(|| format!("no watch answering on {:?} — is `violet-cipher watch` running?", socket))?;
+    let mut json = Vec::new();
+    stream.read_to_end(&mut json).with_context(|| format!("read {:?}", socket))?;
+    serde_json::from_slice(&json).context("parse watch status")
+}
+
+#[cfg(not(unix))]
+pub fn query(_socket: &Path) -> Result<Snapshot> {
+    bail!("the watch status socket is only supported on Unix")
+}
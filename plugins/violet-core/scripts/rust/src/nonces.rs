@@ -0,0 +1,197 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Nonce ledger — `verify` flags a GCM/ChaCha nonce that turns up twice
+//
+// Every layer draws a fresh 96-bit nonce (192-bit for XChaCha20), so two
+// equal ones never happen by chance. Under the same key a repeat breaks the layer outright (GCM leaks
+// its authentication key, both ciphers the XOR of the plaintexts); under
+// different keys it still means the RNG is broken. A nonce is filed with the
+// salt its key was derived from, when known, so the report can tell the two
+// apart.
+//
+// Without a key the outer nonce of v4/v5 and the stanza and outer nonces of
+// v6 can be read; with one, `open_layers` and the v6 payload report the inner
+// ones while `capture` runs. Identical blobs count once, so a file kept
+// unchanged across commits or copied elsewhere is not a repeat. History scans
+// (`verify --git-history`) read every committed version of the target files
+// and stay keyless: opening each one would cost three Argon2id runs.
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+
+use crate::sarif::{self, Rule};
+use crate::{
+    preamble, recipient, v4_check_integrity, v5_check_integrity, ARGON2_SALT_LEN, GCM_NONCE_LEN, VERSION_V5, VERSION_V6,
+};
+
+/// A nonce read from one layer of a file
+pub struct Found {
+    layer: &'static str,
+    /// Salt (or ephemeral key) the layer key was derived from; None when the
+    /// key comes from a v6 file key, which is never visible
+    key_salt: Option<Vec<u8>>,
+    nonce: Vec<u8>,
+}
+
+impl Found {
+    /// `sealed` is a layer as written: a nonce of `nonce_len` bytes followed by ciphertext
+    pub fn new(layer: &'static str, key_salt: Option<&[u8]>, sealed: &[u8], nonce_len: usize) -> Option<Self> {
+        let nonce = sealed.get(..nonce_len)?.to_vec();
+        Some(Self { layer, key_salt: key_salt.map(<[u8]>::to_vec), nonce })
+    }
+}
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<Found>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` and return, beside its result, the nonces of the layers it opened
+pub fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<Found>) {
+    let previous = CAPTURED.with(|slot| slot.borrow_mut().replace(Vec::new()));
+    let result = f();
+    let found = CAPTURED.with(|slot| std::mem::replace(&mut *slot.borrow_mut(), previous));
+    (result, found.unwrap_or_default())
+}
+
+/// A layer opened; recorded only inside `capture`
+pub fn opened(layer: &'static str, key_salt: Option<&[u8]>, sealed: &[u8], nonce_len: usize) {
+    CAPTURED.with(|slot| {
+        if let Some(found) = slot.borrow_mut().as_mut() {
+            found.extend(Found::new(layer, key_salt, sealed, nonce_len));
+        }
+    });
+}
+
+/// Nonces readable without a key; none for legacy or malformed files
+fn keyless(data: &[u8]) -> Vec<Found> {
+    match preamble::version(data) {
+        Some(VERSION_V6) => recipient::nonces(data).unwrap_or_default(),
+        Some(VERSION_V5) => v5_check_integrity(data)
+            .ok()
+            .and_then(|(_, _, body_offset)| outer(&data[body_offset..data.len() - 32]))
+            .into_iter()
+            .collect(),
+        Some(_) => v4_check_integrity(data)
+            .ok()
+            .and_then(|(_, prefix)| outer(&data[prefix..data.len() - 32]))
+            .into_iter()
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Outer layer of v4/v5: salt then a nonce-prefixed AEAD, 12-byte in every suite
+fn outer(body: &[u8]) -> Option<Found> {
+    let (salt, sealed) = body.split_at_checked(ARGON2_SALT_LEN)?;
+    Found::new("outer", Some(salt), sealed, GCM_NONCE_LEN)
+}
+
+struct Sighting {
+    file: String,
+    layer: &'static str,
+    key_salt: Option<Vec<u8>>,
+}
+
+/// Every nonce seen during one `verify`, by value
+#[derive(Default)]
+pub struct Ledger {
+    blobs: HashSet<[u8; 32]>,
+    seen: BTreeMap<Vec<u8>, Vec<Sighting>>,
+    count: usize,
+}
+
+impl Ledger {
+    /// File the nonces of `data`: the keyless ones plus `opened` from a
+    /// `capture` around its decryption. A blob already filed is skipped
+    pub fn add(&mut self, file: &str, data: &[u8], opened: Vec<Found>) {
+        if !self.blobs.insert(Sha256::digest(data).into()) {
+            return;
+        }
+        for found in keyless(data).into_iter().chain(opened) {
+            self.count += 1;
+            let sighting = Sighting { file: file.to_string(), layer: found.layer, key_salt: found.key_salt };
+            self.seen.entry(found.nonce).or_default().push(sighting);
+        }
+    }
+
+    /// File the keyless nonces of every committed version of `files` (names
+    /// in `data_dir`); returns the number of versions read
+    pub fn add_history(&mut self, data_dir: &Path, files: &[String]) -> Result<usize> {
+        let mut args = vec!["log", "--all", "--no-renames", "--format=%H", "--raw", "--no-abbrev", "--"];
+        args.extend(files.iter().map(String::as_str));
+        let log = String::from_utf8(git(data_dir, &args)?).context("git log output is not UTF-8")?;
+
+        let mut read = HashSet::new();
+        let mut commit = "";
+        for line in log.lines() {
+            // `:<old mode> <new mode> <old blob> <new blob> <status>\t<path>`, after its commit's hash
+            let Some(raw) = line.strip_prefix(':') else {
+                if !line.is_empty() {
+                    commit = line;
+                }
+                continue;
+            };
+            let (meta, path) = raw.split_once('\t').context("malformed git log --raw record")?;
+            let Some(blob) = meta.split(' ').nth(3).filter(|b| b.bytes().any(|c| c != b'0')) else {
+                continue; // deleted in this commit
+            };
+            if read.insert(blob.to_string()) {
+                let data = git(data_dir, &["cat-file", "blob", blob])?;
+                self.add(&format!("{}@{}", path, &commit[..commit.len().min(12)]), &data, Vec::new());
+            }
+        }
+        Ok(read.len())
+    }
+
+    /// Print every repeated nonce; returns the number of issues
+    pub fn report(&self, data_dir: &Path) -> u32 {
+        let mut issues = 0u32;
+        for (nonce, sightings) in self.seen.iter().filter(|(_, s)| s.len() > 1) {
+            issues += 1;
+            let same_key = sightings.iter().enumerate().any(|(i, a)| {
+                a.key_salt.is_some()
+                    && sightings[i + 1..].iter().any(|b| b.layer == a.layer && b.key_salt == a.key_salt)
+            });
+            let nonce = hex::encode(nonce);
+            if same_key {
+                error!("  🚨 NONCE REUSE: {} under the same key salt — that layer is broken, re-encrypt now", nonce);
+            } else {
+                error!("  🚨 Repeated nonce {} under different keys — the random number generator is suspect", nonce);
+            }
+            for sighting in sightings {
+                error!("      {} ({} layer)", sighting.file, sighting.layer);
+            }
+            // Committed versions are named `<path>@<commit>`; the alert goes on a current file if one is involved
+            let files: Vec<String> = sightings.iter().map(|s| format!("{} ({} layer)", s.file, s.layer)).collect();
+            let current = sightings.iter().find(|s| !s.file.contains('@')).map(|s| data_dir.join(&s.file));
+            let message = format!(
+                "nonce {} repeats{}: {}",
+                nonce,
+                if same_key { " under the same key salt" } else { " under different keys" },
+                files.join(", ")
+            );
+            sarif::record(Rule::NonceReuse, current.as_deref().unwrap_or(data_dir), message);
+        }
+        if issues == 0 && self.count > 0 {
+            info!("  ✅ {} nonce(s) across {} blob(s), all distinct", self.count, self.blobs.len());
+        }
+        issues
+    }
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .context("run git")?;
+    if !output.status.success() {
+        bail!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(output.stdout)
+}
@@ -0,0 +1,250 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Paper backup — a recovery key or keyfile as words and/or a QR code for printing
+//
+// `key export-paper` turns the secret into a sheet of BIP39 English words
+// (the bytes are the mnemonic's entropy, so 16 to 32 bytes in steps of 4; a
+// recovery key is 24 words) headed `violet-paper <kind> <id>`, and/or a QR
+// code (--qr, SVG or PNG by extension) of
+//
+//   VIOLET-PAPER:<KIND>:<HEX>
+//
+// all upper case, so it fits the QR alphanumeric mode; a keyfile of up to
+// about 1.6 KB fits. `key import-paper` reads either form back — the sheet
+// as typed in, numbering and all, or a QR scanner's text — checks the words'
+// checksum and the ID the heading names, and writes the secret file again.
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use bip39::{Language, Mnemonic};
+use clap::ValueEnum;
+use qrcode::render::svg;
+use qrcode::{Color, QrCode};
+use tracing::info;
+use zeroize::Zeroizing;
+
+use crate::atomic::write_secret_new;
+use crate::keyfile::Keyfile;
+use crate::{logging, recovery};
+
+const QR_PREFIX: &str = "VIOLET-PAPER:";
+const HEADING: &str = "violet-paper";
+const WORDS_PER_ROW: usize = 4;
+/// Pixels per module and modules of white border in PNG output
+const PNG_SCALE: usize = 8;
+const QUIET_ZONE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PaperKind {
+    /// A `key recovery-generate` key
+    RecoveryKey,
+    /// A --keyfile
+    Keyfile,
+}
+
+impl PaperKind {
+    fn name(self) -> &'static str {
+        match self {
+            PaperKind::RecoveryKey => "recovery-key",
+            PaperKind::Keyfile => "keyfile",
+        }
+    }
+
+    fn parse(name: &str) -> Result<Self> {
+        PaperKind::from_str(name, true).map_err(|_| anyhow::anyhow!("unknown paper kind {:?}", name))
+    }
+}
+
+struct Secret {
+    kind: PaperKind,
+    bytes: Zeroizing<Vec<u8>>,
+    id: String,
+}
+
+impl Secret {
+    /// The secret file at `path`: a recovery key if it has the recovery key line, a keyfile otherwise
+    fn read(path: &Path) -> Result<Self> {
+        let data = Zeroizing::new(fs::read(path).with_context(|| format!("read {:?}", path))?);
+        let recovery_key = std::str::from_utf8(&data).ok().and_then(recovery::parse_secret);
+        match recovery_key {
+            Some(secret) => Self::new(PaperKind::RecoveryKey, Zeroizing::new(secret?.to_bytes().to_vec())),
+            None => Self::new(PaperKind::Keyfile, Zeroizing::new(data.to_vec())),
+        }
+    }
+
+    fn new(kind: PaperKind, bytes: Zeroizing<Vec<u8>>) -> Result<Self> {
+        let id = match kind {
+            PaperKind::RecoveryKey => {
+                let key: [u8; 32] = bytes.as_slice().try_into().context("a recovery key is 32 bytes")?;
+                recovery::secret_id(&key.into())
+            }
+            PaperKind::Keyfile if bytes.is_empty() => bail!("the keyfile is empty"),
+            PaperKind::Keyfile => Keyfile::from_bytes(&bytes).id,
+        };
+        Ok(Self { kind, bytes, id })
+    }
+
+    /// Contents of the file the secret lives in
+    fn file_contents(&self) -> Zeroizing<Vec<u8>> {
+        match self.kind {
+            PaperKind::RecoveryKey => {
+                let key: [u8; 32] = self.bytes.as_slice().try_into().expect("checked in Secret::new");
+                Zeroizing::new(recovery::encode_secret(&key.into()).as_bytes().to_vec())
+            }
+            PaperKind::Keyfile => self.bytes.clone(),
+        }
+    }
+
+    fn heading(&self) -> String {
+        format!("{} {} {}", HEADING, self.kind.name(), self.id)
+    }
+}
+
+/// `key export-paper`: the word sheet on stdout (unless `words` is false) and the QR code to `qr`
+pub fn export(file: &Path, qr: Option<&Path>, words: bool) -> Result<()> {
+    let secret = Secret::read(file)?;
+    let qr_format = qr.map(|path| match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("svg") => Ok(QrFormat::Svg),
+        Some(ext) if ext.eq_ignore_ascii_case("png") => Ok(QrFormat::Png),
+        _ => bail!("--qr {:?} must end in .svg or .png", path),
+    });
+    let qr_format = qr_format.transpose()?;
+
+    let sheet = if words {
+        let mnemonic = Mnemonic::from_entropy(&secret.bytes).map_err(|_| {
+            anyhow::anyhow!(
+                "a {}-byte {} does not fit in words (16 to 32 bytes, in steps of 4) — use --qr with --no-words",
+                secret.bytes.len(),
+                secret.kind.name()
+            )
+        })?;
+        let mut sheet = Zeroizing::new(format!("{}\n", secret.heading()));
+        let words: Vec<&str> = mnemonic.words().collect();
+        for (row, chunk) in words.chunks(WORDS_PER_ROW).enumerate() {
+            let cells: Vec<String> = chunk
+                .iter()
+                .enumerate()
+                .map(|(i, word)| format!("{:>2}. {:<9}", row * WORDS_PER_ROW + i + 1, word))
+                .collect();
+            sheet.push_str(cells.join(" ").trim_end());
+            sheet.push('\n');
+        }
+        Some(sheet)
+    } else {
+        None
+    };
+
+    if let (Some(path), Some(format)) = (qr, qr_format) {
+        let payload = Zeroizing::new(format!("{}{}:{}", QR_PREFIX, secret.kind.name(), hex::encode(&secret.bytes)));
+        let code = QrCode::new(payload.to_ascii_uppercase().as_bytes()).map_err(|e| {
+            anyhow::anyhow!("a {}-byte {} does not fit in a QR code: {}", secret.bytes.len(), secret.kind.name(), e)
+        })?;
+        let image = match format {
+            QrFormat::Svg => Zeroizing::new(code.render::<svg::Color>().min_dimensions(256, 256).build().into_bytes()),
+            QrFormat::Png => Zeroizing::new(png(&code)?),
+        };
+        write_secret_new(path, &image)?;
+    }
+
+    if let Some(sheet) = &sheet {
+        logging::claim_stdout();
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(sheet.as_bytes()).and_then(|()| stdout.flush()).context("write stdout")?;
+    }
+    logging::note(format_args!("🧾 Paper backup of {} {} from {:?}", secret.kind.name(), secret.id, file));
+    if let Some(path) = qr {
+        logging::note(format_args!("   QR code → {:?}", path));
+    }
+    logging::note(format_args!("   Print it, check it with `key import-paper`, then delete any copy on disk."));
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+enum QrFormat {
+    Svg,
+    Png,
+}
+
+/// Grayscale PNG of `code`, PNG_SCALE pixels per module inside a quiet zone
+fn png(code: &QrCode) -> Result<Vec<u8>> {
+    let width = code.width();
+    let side = (width + 2 * QUIET_ZONE) * PNG_SCALE;
+    let mut pixels = vec![0xffu8; side * side];
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color != Color::Dark {
+            continue;
+        }
+        let (x, y) = ((i % width + QUIET_ZONE) * PNG_SCALE, (i / width + QUIET_ZONE) * PNG_SCALE);
+        for row in y..y + PNG_SCALE {
+            pixels[row * side + x..row * side + x + PNG_SCALE].fill(0);
+        }
+    }
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, side as u32, side as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().context("encode PNG")?;
+    writer.write_image_data(&pixels).context("encode PNG")?;
+    writer.finish().context("encode PNG")?;
+    Ok(out)
+}
+
+/// `key import-paper`: the secret from a typed-in sheet or QR text (`from`, default stdin), written to `output`
+pub fn import(from: Option<&Path>, kind: Option<PaperKind>, output: &Path) -> Result<()> {
+    let text = Zeroizing::new(match from {
+        Some(path) => fs::read_to_string(path).with_context(|| format!("read {:?}", path))?,
+        None => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text).context("read stdin")?;
+            text
+        }
+    });
+    let secret = parse(&text, kind)?;
+    write_secret_new(output, &secret.file_contents())?;
+    info!("🧾 {} {} restored from paper → {:?}", secret.kind.name(), secret.id, output);
+    Ok(())
+}
+
+fn parse(text: &str, kind: Option<PaperKind>) -> Result<Secret> {
+    let text = text.trim();
+    let check_kind = |found: PaperKind| match kind {
+        Some(kind) if kind != found => bail!("this is a {} backup, not a {}", found.name(), kind.name()),
+        _ => Ok(found),
+    };
+
+    if text.get(..QR_PREFIX.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(QR_PREFIX)) {
+        let (name, hex_bytes) = text[QR_PREFIX.len()..].split_once(':').context("QR text has no kind")?;
+        let bytes = Zeroizing::new(hex::decode(hex_bytes.trim()).context("QR text is not hex")?);
+        return Secret::new(check_kind(PaperKind::parse(name)?)?, bytes);
+    }
+
+    let mut heading = None;
+    let mut words = Vec::new();
+    for line in text.lines() {
+        let mut tokens = line.split_whitespace().peekable();
+        if tokens.peek().is_some_and(|first| first.eq_ignore_ascii_case(HEADING)) {
+            tokens.next();
+            let name = tokens.next().context("the violet-paper heading names no kind")?;
+            heading = Some((PaperKind::parse(name)?, tokens.next().map(str::to_ascii_lowercase)));
+            continue;
+        }
+        // Word numbers ("7." or "7") are skipped
+        words.extend(tokens.filter(|t| !t.trim_end_matches('.').chars().all(|c| c.is_ascii_digit())));
+    }
+    let found = match (heading.as_ref().map(|(kind, _)| *kind), kind) {
+        (Some(found), _) => check_kind(found)?,
+        (None, Some(kind)) => kind,
+        (None, None) => bail!("no `{}` heading — pass --kind", HEADING),
+    };
+    let phrase = Zeroizing::new(words.join(" ").to_lowercase());
+    let mnemonic = Mnemonic::parse_in_normalized(Language::English, &phrase)
+        .map_err(|e| anyhow::anyhow!("the words do not check out ({}) — look for a misread word", e))?;
+    let secret = Secret::new(found, Zeroizing::new(mnemonic.to_entropy()))?;
+    if let Some((_, Some(id))) = heading {
+        if id != secret.id {
+            bail!("the words give {} {} but the sheet is headed {} — check the heading", found.name(), secret.id, id);
+        }
+    }
+    Ok(secret)
+}
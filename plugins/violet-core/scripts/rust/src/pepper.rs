@@ -0,0 +1,107 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// External pepper — the second key factor from a file instead of the compiled-in seed
+//
+// Every layer key mixes a second factor into the passphrase, and the trailing
+// HMAC is keyed with it. By default that is the seed built into the binary.
+// `--pepper-file` (or VIOLET_PEPPER_FILE) replaces it for v5 files: the
+// writer records the pepper's ID in the header, and a reader uses the pepper
+// only for files that name it, so files from before keep opening with the
+// seed. A pepper kept outside the repository can be rotated by decrypting
+// with the old file and encrypting with the new one, with no rebuild.
+// `--fido2` derives the pepper from a hardware token instead (see fido2.rs).
+// Key IDs, v4 placeholders and v6 envelopes always use the seed.
+//
+// The pepper key is SHA-256 over a context string and the file's bytes (one
+// trailing newline stripped); its ID is the first 8 bytes of a second,
+// separately labelled digest, so it can be shown without revealing the key.
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::header::Header;
+use crate::KEY_LEN;
+
+const PEPPER_CONTEXT: &[u8] = b"violet-pepper-v1\0";
+const PEPPER_ID_CONTEXT: &[u8] = b"violet-pepper-id-v1\0";
+/// Shortest pepper file accepted; anything less is a password, not a pepper
+const MIN_PEPPER_LEN: usize = 32;
+pub const PEPPER_ID_LEN: usize = 8;
+
+pub struct Pepper {
+    key: Zeroizing<[u8; KEY_LEN]>,
+    /// Hex fingerprint recorded in headers
+    pub id: String,
+}
+
+static CONFIGURED: OnceLock<Pepper> = OnceLock::new();
+
+thread_local! {
+    /// Pepper key of the file being sealed or opened on this thread
+    static ACTIVE: RefCell<Option<[u8; KEY_LEN]>> = const { RefCell::new(None) };
+}
+
+impl Pepper {
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut bytes = Zeroizing::new(fs::read(path).with_context(|| format!("read pepper file {:?}", path))?);
+        if bytes.ends_with(b"\n") {
+            bytes.pop();
+            if bytes.ends_with(b"\r") {
+                bytes.pop();
+            }
+        }
+        if bytes.len() < MIN_PEPPER_LEN {
+            bail!("pepper file {:?} holds {} bytes; at least {} are required", path, bytes.len(), MIN_PEPPER_LEN);
+        }
+        Ok(Self::from_secret(&bytes))
+    }
+
+    /// Pepper from secret bytes obtained some other way, e.g. a FIDO2 token's hmac-secret
+    pub fn from_secret(bytes: &[u8]) -> Self {
+        let key: [u8; KEY_LEN] = Sha256::new().chain_update(PEPPER_CONTEXT).chain_update(bytes).finalize().into();
+        let id = Sha256::new().chain_update(PEPPER_ID_CONTEXT).chain_update(key).finalize();
+        Self { key: Zeroizing::new(key), id: hex::encode(&id[..PEPPER_ID_LEN]) }
+    }
+}
+
+/// Use `pepper` for the rest of the run
+pub fn configure(pepper: Pepper) -> Result<()> {
+    if CONFIGURED.set(pepper).is_err() {
+        bail!("pepper already configured");
+    }
+    Ok(())
+}
+
+/// The pepper from --pepper-file or --fido2, if one was given
+pub fn configured() -> Option<&'static Pepper> {
+    CONFIGURED.get()
+}
+
+/// The pepper a file with `header` was sealed with; None for the compiled-in seed
+pub fn for_header(header: &Header) -> Result<Option<&'static Pepper>> {
+    let Some(needed) = header.pepper_id.as_deref() else {
+        return Ok(None);
+    };
+    match configured() {
+        Some(pepper) if pepper.id == needed => Ok(Some(pepper)),
+        Some(pepper) => bail!("file was sealed with pepper {}, but the one given is pepper {}", needed, pepper.id),
+        None => bail!("file was sealed with external pepper {} — pass its --pepper-file (or --fido2 credential)", needed),
+    }
+}
+
+/// Run `f` with `pepper` as the second factor on this thread (None: the compiled-in seed)
+pub fn with<T>(pepper: Option<&Pepper>, f: impl FnOnce() -> T) -> T {
+    let previous = ACTIVE.with(|slot| slot.replace(pepper.map(|p| *p.key)));
+    let result = f();
+    ACTIVE.with(|slot| slot.replace(previous)).zeroize();
+    result
+}
+
+/// The pepper key `with` made current on this thread, if any
+pub fn active() -> Option<[u8; KEY_LEN]> {
+    ACTIVE.with(|slot| *slot.borrow())
+}
@@ -0,0 +1,478 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// File preamble — fixed 32-byte header in front of every v4–v6 body
+//
+// Layout (big-endian):
+//   "VSC!" | version u8 | suite u8 | flags u16
+//   | Argon2id m_cost u32 | t_cost u32 | p_cost u32
+//   | metadata length u32 | payload length u64
+//
+// It replaces the bare version byte older files start with; those still
+// read. `version` names the body that follows (4, 5 or 6), the metadata is
+// the v5/v6 TLV header (header.rs) and the payload is everything after it up
+// to the trailing HMAC. The preamble is part of the outer layer's associated
+// data and is covered by the HMAC. Its Argon2id costs are the ones the file
+// was written with, so framed files decrypt without matching --kdf-* flags;
+// costs above the caps in kdf.rs are refused before any key is derived.
+// `inspect` reads all of this back without the key.
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use argon2::Params as KdfParams;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::backup::utc_timestamp;
+use crate::header::{Compression, Header};
+use crate::kdf;
+use crate::recipient::{self, Recipients};
+use crate::recovery;
+use crate::salt;
+use crate::{check_integrity, LayerCipher, GCM_NONCE_LEN, LAYERS_OVERHEAD, VERSION_V4, VERSION_V5, VERSION_V6};
+
+pub const MAGIC: &[u8; 4] = b"VSC!";
+pub const LEN: usize = 32;
+const HMAC_LEN: usize = 32;
+const FLAG_COMPRESSED: u16 = 1 << 0;
+const FLAG_NAME_BOUND: u16 = 1 << 1;
+const KNOWN_FLAGS: u16 = FLAG_COMPRESSED | FLAG_NAME_BOUND;
+
+/// Algorithms protecting the payload
+///
+/// v5 files choose the AEAD of each passphrase layer (`--suite`); the v5
+/// header records the choice as well, and readers dispatch on it, so a new
+/// suite is one more variant rather than another format version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Suite {
+    /// AES-256-GCM → ChaCha20-Poly1305 → AES-256-GCM, each layer Argon2id-keyed (v4, v5)
+    #[default]
+    #[value(name = "gcm-chacha-gcm")]
+    PassphraseLayers,
+    /// One file key wrapped per recipient (X25519, X25519 + ML-KEM-768 or Argon2id),
+    /// payload in ChaCha20-Poly1305 inside AES-256-GCM (v6)
+    #[value(skip)]
+    Envelope,
+    /// ChaCha20-Poly1305 in all three layers, for CPUs without AES instructions (v5)
+    #[serde(rename = "chacha-only")]
+    #[value(name = "chacha-only")]
+    ChachaLayers,
+    /// AES-256-GCM in all three layers (v5)
+    #[serde(rename = "gcm-only")]
+    #[value(name = "gcm-only")]
+    GcmLayers,
+    /// AES-256-GCM → XChaCha20-Poly1305 → AES-256-GCM, a 192-bit middle nonce for keys sealing many files (v5)
+    #[serde(rename = "gcm-xchacha-gcm")]
+    #[value(name = "gcm-xchacha-gcm")]
+    GcmXchachaGcm,
+    /// XChaCha20-Poly1305 → XChaCha20-Poly1305 → AES-256-GCM, 192-bit inner and middle nonces (v5)
+    #[serde(rename = "xchacha-xchacha-gcm")]
+    #[value(name = "xchacha-xchacha-gcm")]
+    XchachaXchachaGcm,
+    /// AES-256-GCM → ChaCha20-Poly1305 → AES-256-GCM-SIV; a repeated outer nonce, say from a
+    /// cloned VM's RNG, leaks only whether two payloads are equal (v5)
+    #[serde(rename = "gcm-chacha-siv")]
+    #[value(name = "gcm-chacha-siv")]
+    GcmChachaSiv,
+}
+
+impl Suite {
+    pub fn for_version(version: u8) -> Self {
+        if version == VERSION_V6 {
+            Self::Envelope
+        } else {
+            Self::PassphraseLayers
+        }
+    }
+
+    pub fn byte(self) -> u8 {
+        match self {
+            Self::PassphraseLayers => 0x01,
+            Self::Envelope => 0x02,
+            Self::ChachaLayers => 0x03,
+            Self::GcmLayers => 0x04,
+            Self::GcmXchachaGcm => 0x05,
+            Self::XchachaXchachaGcm => 0x06,
+            Self::GcmChachaSiv => 0x07,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Result<Self> {
+        match byte {
+            0x01 => Ok(Self::PassphraseLayers),
+            0x02 => Ok(Self::Envelope),
+            0x03 => Ok(Self::ChachaLayers),
+            0x04 => Ok(Self::GcmLayers),
+            0x05 => Ok(Self::GcmXchachaGcm),
+            0x06 => Ok(Self::XchachaXchachaGcm),
+            0x07 => Ok(Self::GcmChachaSiv),
+            other => bail!("unknown cipher suite {:#04x} — upgrade violet-cipher", other),
+        }
+    }
+
+    /// Whether a file of format `version` may be sealed with this suite
+    fn fits(self, version: u8) -> bool {
+        match version {
+            VERSION_V6 => self == Self::Envelope,
+            VERSION_V5 => self != Self::Envelope,
+            _ => self == Self::PassphraseLayers,
+        }
+    }
+
+    /// AEAD of the inner, middle and outer passphrase layer
+    ///
+    /// The outer layer always takes a 96-bit nonce, which `verify` reads
+    /// without a key.
+    pub fn layers(self) -> Result<[LayerCipher; 3]> {
+        use LayerCipher::{AesGcm, AesGcmSiv, ChaCha20, XChaCha20};
+        Ok(match self {
+            Self::PassphraseLayers => [AesGcm, ChaCha20, AesGcm],
+            Self::ChachaLayers => [ChaCha20; 3],
+            Self::GcmLayers => [AesGcm; 3],
+            Self::GcmXchachaGcm => [AesGcm, XChaCha20, AesGcm],
+            Self::XchachaXchachaGcm => [XChaCha20, XChaCha20, AesGcm],
+            Self::GcmChachaSiv => [AesGcm, ChaCha20, AesGcmSiv],
+            Self::Envelope => bail!("the v6 envelope has no passphrase layers"),
+        })
+    }
+
+    /// Bytes the passphrase layers of this suite add to a payload, outer salt included
+    pub fn overhead(self) -> Result<usize> {
+        let nonces: usize = self.layers()?.iter().map(|cipher| cipher.nonce_len()).sum();
+        Ok(LAYERS_OVERHEAD - 3 * GCM_NONCE_LEN + nonces)
+    }
+}
+
+impl std::fmt::Display for Suite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::PassphraseLayers => "gcm-chacha-gcm",
+            Self::Envelope => "envelope",
+            Self::ChachaLayers => "chacha-only",
+            Self::GcmLayers => "gcm-only",
+            Self::GcmXchachaGcm => "gcm-xchacha-gcm",
+            Self::XchachaXchachaGcm => "xchacha-xchacha-gcm",
+            Self::GcmChachaSiv => "gcm-chacha-siv",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Preamble {
+    pub version: u8,
+    pub suite: Suite,
+    pub compressed: bool,
+    /// The file's logical name is bound into the associated data
+    pub name_bound: bool,
+    pub kdf: KdfParams,
+    pub metadata_len: usize,
+    pub payload_len: usize,
+}
+
+impl Preamble {
+    pub fn new(
+        version: u8,
+        kdf: &KdfParams,
+        compressed: bool,
+        name_bound: bool,
+        metadata_len: usize,
+        payload_len: usize,
+    ) -> Self {
+        Self {
+            version,
+            suite: Suite::for_version(version),
+            compressed,
+            name_bound,
+            kdf: kdf.clone(),
+            metadata_len,
+            payload_len,
+        }
+    }
+
+    pub fn encode(&self) -> [u8; LEN] {
+        let mut flags = 0;
+        if self.compressed {
+            flags |= FLAG_COMPRESSED;
+        }
+        if self.name_bound {
+            flags |= FLAG_NAME_BOUND;
+        }
+
+        let mut out = [0u8; LEN];
+        out[..4].copy_from_slice(MAGIC);
+        out[4] = self.version;
+        out[5] = self.suite.byte();
+        out[6..8].copy_from_slice(&flags.to_be_bytes());
+        out[8..12].copy_from_slice(&self.kdf.m_cost().to_be_bytes());
+        out[12..16].copy_from_slice(&self.kdf.t_cost().to_be_bytes());
+        out[16..20].copy_from_slice(&self.kdf.p_cost().to_be_bytes());
+        out[20..24].copy_from_slice(&(self.metadata_len as u32).to_be_bytes());
+        out[24..32].copy_from_slice(&(self.payload_len as u64).to_be_bytes());
+        out
+    }
+
+    /// Parse the preamble of `data` and check the lengths it declares; None for bare files
+    pub fn parse(data: &[u8]) -> Result<Option<Self>> {
+        if !data.starts_with(MAGIC) {
+            return Ok(None);
+        }
+        let bytes: &[u8; LEN] = data.get(..LEN).and_then(|b| b.try_into().ok()).context("VSC! header truncated")?;
+        let u32_at = |at: usize| u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap());
+
+        let version = bytes[4];
+        if !matches!(version, VERSION_V4 | VERSION_V5 | VERSION_V6) {
+            bail!("VSC! header names format v{} — upgrade violet-cipher", version);
+        }
+        let suite = Suite::from_byte(bytes[5])?;
+        if !suite.fits(version) {
+            bail!("VSC! header pairs format v{} with the wrong cipher suite", version);
+        }
+        let flags = u16::from_be_bytes([bytes[6], bytes[7]]);
+        if flags & !KNOWN_FLAGS != 0 {
+            bail!("VSC! header has unknown flags {:#06x} — upgrade violet-cipher", flags & !KNOWN_FLAGS);
+        }
+        let kdf = kdf::bounded_params(u32_at(8), u32_at(12), u32_at(16))
+            .map_err(|e| anyhow::anyhow!("VSC! header: {}", e))?;
+        let metadata_len = u32_at(20) as usize;
+        let payload_len = usize::try_from(u64::from_be_bytes(bytes[24..32].try_into().unwrap()))?;
+
+        let expected = LEN.checked_add(metadata_len).and_then(|n| n.checked_add(payload_len)).map(|n| n + HMAC_LEN);
+        if expected != Some(data.len()) {
+            bail!(
+                "VSC! header declares {} bytes of metadata and {} of payload, file has {} bytes — truncated or padded",
+                metadata_len,
+                payload_len,
+                data.len()
+            );
+        }
+
+        Ok(Some(Self {
+            version,
+            suite,
+            compressed: flags & FLAG_COMPRESSED != 0,
+            name_bound: flags & FLAG_NAME_BOUND != 0,
+            kdf,
+            metadata_len,
+            payload_len,
+        }))
+    }
+
+    /// Cross-check the flags and metadata length against the v5/v6 header that follows
+    pub fn check_metadata(&self, header: &Header, header_len: usize) -> Result<()> {
+        if header_len != self.metadata_len {
+            bail!("VSC! header declares {} bytes of metadata, found {}", self.metadata_len, header_len);
+        }
+        if self.compressed != (header.compression != Compression::None) {
+            bail!("VSC! header compression flag disagrees with the metadata");
+        }
+        if self.name_bound != header.file_name.is_some() {
+            bail!("VSC! header name-binding flag disagrees with the metadata");
+        }
+        // v6 headers never record a suite: theirs is the envelope
+        let layers = if self.version == VERSION_V6 { Suite::default() } else { self.suite };
+        if header.suite != layers {
+            bail!("VSC! header cipher suite disagrees with the metadata");
+        }
+        Ok(())
+    }
+}
+
+/// Format version of a framed or bare v4–v6 blob; None for legacy and plaintext
+pub fn version(data: &[u8]) -> Option<u8> {
+    let version = if data.starts_with(MAGIC) { data.get(4) } else { data.first() };
+    version.copied().filter(|v| matches!(*v, VERSION_V4 | VERSION_V5 | VERSION_V6))
+}
+
+/// The preamble, if any, and the offset its body starts at
+///
+/// Bare files have a one-byte prefix: the version.
+pub fn split(data: &[u8]) -> Result<(Option<Preamble>, usize)> {
+    Ok(match Preamble::parse(data)? {
+        Some(preamble) => (Some(preamble), LEN),
+        None => (None, 1),
+    })
+}
+
+/// How a file announces its format
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum Framing {
+    /// 32-byte VSC! preamble
+    Vsc,
+    /// Bare leading version byte, written before the preamble existed
+    VersionByte,
+    /// Neither: legacy v2/v3 or not a violet-cipher file
+    Unknown,
+}
+
+#[derive(Serialize)]
+struct Flags {
+    compressed: bool,
+    name_bound: bool,
+}
+
+#[derive(Serialize)]
+struct Kdf {
+    algorithm: &'static str,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+/// Byte counts of each section; `prefix` is the preamble or the bare version byte
+#[derive(Serialize)]
+struct Lengths {
+    prefix: usize,
+    metadata: usize,
+    payload: usize,
+    hmac: usize,
+}
+
+#[derive(Serialize)]
+struct Metadata {
+    content_type: String,
+    compression: String,
+    file_name: Option<String>,
+    /// UTC, `20261017T040912Z`
+    encrypted_at: Option<String>,
+    plaintext_sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plaintext_hmac: Option<String>,
+    key_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pepper_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keyfile_id: Option<String>,
+    /// The label, e.g. `staging`, not the whole salt string
+    salt_label: Option<String>,
+    /// `outer layer`, `passphrase (unchecked)` from before it, or `binary` for the compiled-in seed or pepper
+    hmac_key: &'static str,
+    /// UTC, as `encrypted_at`; decrypting gives it back to the plaintext file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plaintext_mtime: Option<String>,
+    /// ID of the recovery key the passphrase is escrowed to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recovery_key_id: Option<String>,
+}
+
+/// Everything readable about a file without its key
+#[derive(Serialize)]
+pub struct Inspection {
+    file: String,
+    bytes: usize,
+    framing: Framing,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suite: Option<Suite>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flags: Option<Flags>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kdf: Option<Kdf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lengths: Option<Lengths>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<Metadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recipients: Option<Recipients>,
+    /// Structure and outer HMAC check out; structure only when the HMAC is keyed from the outer layer
+    intact: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Read what the header says; a damaged file reports as far as it parses
+pub fn inspect(file: &str, data: &[u8]) -> Inspection {
+    let mut out = Inspection {
+        file: file.to_string(),
+        bytes: data.len(),
+        framing: Framing::Unknown,
+        version: None,
+        suite: None,
+        flags: None,
+        kdf: None,
+        lengths: None,
+        metadata: None,
+        recipients: None,
+        intact: false,
+        error: None,
+    };
+    let Some(version) = self::version(data) else {
+        return out;
+    };
+    out.version = Some(version);
+    out.suite = Some(Suite::for_version(version));
+    out.framing = if data.starts_with(MAGIC) { Framing::Vsc } else { Framing::VersionByte };
+
+    let prefix = match Preamble::parse(data) {
+        Ok(Some(preamble)) => {
+            out.flags = Some(Flags { compressed: preamble.compressed, name_bound: preamble.name_bound });
+            out.kdf = Some(Kdf {
+                algorithm: "argon2id",
+                memory_kib: preamble.kdf.m_cost(),
+                iterations: preamble.kdf.t_cost(),
+                parallelism: preamble.kdf.p_cost(),
+            });
+            LEN
+        }
+        Ok(None) => 1,
+        Err(e) => {
+            out.error = Some(e.to_string());
+            return out;
+        }
+    };
+
+    let metadata_len = if version == VERSION_V4 {
+        0
+    } else {
+        match Header::parse(&data[prefix..]) {
+            Ok((header, len)) => {
+                if version == VERSION_V5 {
+                    out.suite = Some(header.suite);
+                }
+                out.metadata = Some(Metadata {
+                    content_type: header.content_type.to_string(),
+                    compression: header.compression.to_string(),
+                    file_name: header.file_name,
+                    encrypted_at: header.encrypted_at.map(|at| utc_timestamp(UNIX_EPOCH + Duration::from_secs(at))),
+                    plaintext_sha256: header.plaintext_sha256.map(hex::encode),
+                    plaintext_hmac: header.plaintext_hmac.map(hex::encode),
+                    key_id: header.key_id,
+                    pepper_id: header.pepper_id,
+                    keyfile_id: header.keyfile_id,
+                    salt_label: header.salt_label.as_deref().map(|salt| salt::label_of(salt).to_string()),
+                    hmac_key: match (header.layer_mac, header.passphrase_mac) {
+                        (true, _) => "outer layer",
+                        (false, true) => "passphrase (unchecked)",
+                        (false, false) => "binary",
+                    },
+                    plaintext_mtime: header.plaintext_mtime.map(utc_timestamp),
+                    recovery_key_id: header.recovery.as_deref().map(recovery::entry_id),
+                });
+                len
+            }
+            Err(e) => {
+                out.error = Some(e.to_string());
+                return out;
+            }
+        }
+    };
+    out.lengths = Some(Lengths {
+        prefix,
+        metadata: metadata_len,
+        payload: data.len().saturating_sub(prefix + metadata_len + HMAC_LEN),
+        hmac: HMAC_LEN,
+    });
+
+    let checked = match version {
+        VERSION_V6 => recipient::check_integrity(data).map(|(_, recipients)| Some(recipients)),
+        _ => check_integrity(data).map(|()| None),
+    };
+    match checked {
+        Ok(recipients) => {
+            out.recipients = recipients;
+            out.intact = true;
+        }
+        Err(e) => out.error = Some(e.to_string()),
+    }
+    out
+}
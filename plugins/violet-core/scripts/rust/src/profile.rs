@@ -0,0 +1,147 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Profiles — named sets of salt label, target files and output suffix (`--profile`)
+//
+// `encrypt-local`, `decrypt-local`, `re-encrypt` and `verify` work on one
+// profile; `rotate-key` rotates every profile unless given one, and only a
+// full rotation retires the old key. The built-in "local" profile is what
+// they always did: the target files, sealed under the local salt, written
+// as `<name>.enc`. More profiles come from profiles.json in the data
+// directory:
+//
+//   {
+//     "ci":      { "targets": ["rules-index.json"], "suffix": ".ci.enc" },
+//     "staging": { "salt_label": "stage" }
+//   }
+//
+// Every field is optional: the salt label defaults to the profile's name,
+// the targets to the built-in target files and the suffix to
+// `.<profile>.enc`. Target names are plain file names in the data
+// directory, so a profile may cover files the built-in list does not.
+// "local" and "git" cannot be redefined, and no profile may use the git
+// salt, which belongs to the .git.enc placeholders.
+//
+// Profiles are not part of the env file (config.rs): that file belongs to
+// the current directory, holds flat `NAME=value` secrets and is kept out of
+// git, while a profile describes how the data directory's files were
+// written, so it has to travel and be committed with them. `config show`
+// lists both.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::{salt, LOCAL_SALT, TARGET_FILES};
+
+pub const PROFILES_FILE: &str = "profiles.json";
+pub const DEFAULT_PROFILE: &str = "local";
+/// Reserved names: the built-in profile and the placeholder domain
+const RESERVED: [&str; 2] = [DEFAULT_PROFILE, "git"];
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    salt_label: Option<String>,
+    targets: Option<Vec<String>>,
+    suffix: Option<String>,
+}
+
+pub struct Profile {
+    pub name: String,
+    /// Salt string the profile's files are sealed under
+    pub salt: String,
+    pub targets: Vec<String>,
+    /// Appended to a target's name to name its ciphertext, e.g. `.enc`
+    pub suffix: String,
+}
+
+impl Profile {
+    fn builtin() -> Self {
+        Self {
+            name: DEFAULT_PROFILE.to_string(),
+            salt: LOCAL_SALT.to_string(),
+            targets: TARGET_FILES.iter().map(|name| name.to_string()).collect(),
+            suffix: ".enc".to_string(),
+        }
+    }
+
+    fn from_entry(name: &str, entry: Entry) -> Result<Self> {
+        let label = entry.salt_label.as_deref().unwrap_or(name);
+        if label == "git" {
+            bail!("profile {:?}: the git salt label is reserved for .git.enc placeholders", name);
+        }
+        let salt = salt::salt_for(label).with_context(|| format!("profile {:?}", name))?;
+        let targets = entry.targets.unwrap_or_else(|| TARGET_FILES.iter().map(|name| name.to_string()).collect());
+        if targets.is_empty() {
+            bail!("profile {:?} lists no targets", name);
+        }
+        if let Some(bad) = targets.iter().find(|target| !is_plain_name(target)) {
+            bail!("profile {:?}: target {:?} must be a file name in the data directory", name, bad);
+        }
+        let suffix = entry.suffix.unwrap_or_else(|| format!(".{}.enc", name));
+        if !suffix.starts_with('.') || !suffix.ends_with(".enc") || !is_plain_name(&suffix) || suffix == ".git.enc" {
+            bail!("profile {:?}: suffix {:?} must start with '.' and end in .enc (not .git.enc)", name, suffix);
+        }
+        Ok(Self { name: name.to_string(), salt, targets, suffix })
+    }
+
+    /// Ciphertext file name of `target`
+    pub fn output(&self, target: &str) -> String {
+        format!("{}{}", target, self.suffix)
+    }
+
+    /// `names` checked against the profile's targets, in target order; all of them when none is given
+    pub fn select(&self, names: &[String]) -> Result<Vec<&str>> {
+        if let Some(unknown) = names.iter().find(|name| !self.targets.contains(name)) {
+            bail!("{} is not a target of profile {} — choose from {}", unknown, self.name, self.targets.join(", "));
+        }
+        Ok(self.targets.iter().map(String::as_str).filter(|t| names.is_empty() || names.iter().any(|n| n == t)).collect())
+    }
+}
+
+/// Profile `name` (default: local) as configured for `data_dir`
+pub fn load(data_dir: &Path, name: Option<&str>) -> Result<Profile> {
+    let name = name.unwrap_or(DEFAULT_PROFILE);
+    if name == DEFAULT_PROFILE {
+        return Ok(Profile::builtin());
+    }
+    let mut entries = read(data_dir)?;
+    match entries.remove(name) {
+        Some(entry) => Profile::from_entry(name, entry),
+        None if entries.is_empty() => bail!("no profile {:?}: {:?} defines none", name, data_dir.join(PROFILES_FILE)),
+        None => bail!("no profile {:?} — choose from local, {}", name, entries.into_keys().collect::<Vec<_>>().join(", ")),
+    }
+}
+
+/// Every profile for `data_dir`, the built-in one first
+pub fn all(data_dir: &Path) -> Result<Vec<Profile>> {
+    let mut profiles = vec![Profile::builtin()];
+    for (name, entry) in read(data_dir)? {
+        profiles.push(Profile::from_entry(&name, entry)?);
+    }
+    Ok(profiles)
+}
+
+/// profiles.json of `data_dir`; empty when there is none
+fn read(data_dir: &Path) -> Result<BTreeMap<String, Entry>> {
+    let path = data_dir.join(PROFILES_FILE);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e).with_context(|| format!("read {:?}", path)),
+    };
+    let entries: BTreeMap<String, Entry> = serde_json::from_str(&text)
+        .with_context(|| format!("{:?} must map profile names to {{salt_label, targets, suffix}}", path))?;
+    if let Some(name) = entries.keys().find(|name| RESERVED.contains(&name.as_str())) {
+        bail!("{:?}: profile {:?} is built in and cannot be redefined", path, name);
+    }
+    for name in entries.keys() {
+        salt::salt_for(name).with_context(|| format!("{:?}: profile name {:?}", path, name))?;
+    }
+    Ok(entries)
+}
+
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
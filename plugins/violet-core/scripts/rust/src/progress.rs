@@ -0,0 +1,74 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Progress bars — one per file, one step per Argon2id-keyed layer (`--progress`)
+//
+// The three layer-key derivations are where the time goes, so each file's bar
+// counts them. `enable` switches bars on for the run; `file` makes a file's
+// bar current on the calling thread while it is sealed or opened, and
+// `seal_layers`/`open_layers` step whichever bar is current, so no caller in
+// between has to pass one down. Bars draw on stderr and leave stdout as is.
+use std::cell::RefCell;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::Result;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+
+use crate::logging;
+
+/// Argon2id derivations in a layered (v4/v5) file
+const LAYERS: u64 = 3;
+
+static BARS: OnceLock<MultiProgress> = OnceLock::new();
+
+thread_local! {
+    static CURRENT: RefCell<Option<ProgressBar>> = const { RefCell::new(None) };
+}
+
+/// Draw progress bars for the rest of the run
+pub fn enable() {
+    BARS.get_or_init(MultiProgress::new);
+}
+
+fn new_bar(name: &str, action: &str) -> ProgressBar {
+    let Some(bars) = BARS.get() else {
+        return ProgressBar::hidden();
+    };
+    let bar = bars.add(ProgressBar::new(LAYERS));
+    let template = match logging::ascii() {
+        true => "{prefix:>20} [{elapsed_precise}] {bar:30} {pos}/{len} {msg}",
+        false => "{prefix:>20} [{elapsed_precise}] {bar:30.cyan/blue} {pos}/{len} {msg}",
+    };
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template(template)
+            .expect("Invalid progress bar template")
+            .progress_chars("=>-"),
+    );
+    bar.set_prefix(name.to_string());
+    bar.set_message(logging::text(&format!("{} — deriving layer keys", action)).into_owned());
+    bar.enable_steady_tick(Duration::from_millis(200));
+    bar
+}
+
+/// Run `f` (sealing or opening `name`) under its own bar when bars are enabled
+pub fn file<T>(name: &str, action: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let bar = new_bar(name, action);
+    let previous = CURRENT.with(|slot| slot.borrow_mut().replace(bar.clone()));
+    let result = f();
+    CURRENT.with(|slot| *slot.borrow_mut() = previous);
+    match &result {
+        Ok(_) => bar.finish_with_message(logging::text(&format!("{} — done", action)).into_owned()),
+        Err(_) => bar.abandon_with_message(logging::text(&format!("{} — failed", action)).into_owned()),
+    }
+    result
+}
+
+/// Step the current bar: the key of `layer` has been derived
+pub fn layer_keyed(layer: &str) {
+    CURRENT.with(|slot| {
+        if let Some(bar) = slot.borrow().as_ref() {
+            bar.inc(1);
+            bar.set_message(format!("{} layer keyed", layer));
+        }
+    });
+}
@@ -0,0 +1,623 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// v6 envelope — one content key, wrapped for X25519 recipients and/or passphrases
+//
+// preamble (preamble.rs) | header (header.rs) | u8 count | count × stanza | outer AES-GCM | HMAC-SHA256
+// stanza = kind (1) | body (length fixed by kind) | ChaCha20-Poly1305(wrap key, file key)
+//   kind 0x01: body = ephemeral public key (32),
+//              wrap key = HKDF-SHA256(X25519(ephemeral, recipient), salt = ephemeral ‖ recipient)
+//   kind 0x02: body = random Argon2id salt (32), wrap key = Argon2id(passphrase, body)
+//   kind 0x03: body = ephemeral public key (32) ‖ ML-KEM-768 ciphertext (1088),
+//              wrap key = HKDF-SHA256(ML-KEM secret ‖ X25519 secret,
+//                                     salt = ephemeral ‖ recipient X25519 key ‖ ML-KEM ciphertext)
+//   kind 0x04: body = provider (1) ‖ u8 key ID length ‖ key ID ‖ u16 BE blob length ‖ blob,
+//              wrap key = the random key the cloud KMS key encrypted into blob (kms.rs)
+//
+// Kind 0x03 is the hybrid post-quantum recipient: the wrap key holds as long
+// as either X25519 or ML-KEM does, so ciphertext kept in git today stays
+// closed to a future quantum computer that can only break X25519.
+//
+// The payload is ChaCha20-Poly1305 inside AES-GCM, both keyed from the file
+// key and the salt label; everything before the outer layer is its
+// associated data, so header and stanzas cannot be swapped undetected.
+// Files from before the preamble start with a bare 0x06 instead.
+//
+// Kind 0x04 is the only stanza with a variable length; builds without the
+// `kms` feature still parse and count it, and open the file through any other
+// stanza.
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use argon2::Params as KdfParams;
+use hkdf::Hkdf;
+use ml_kem::kem::{Decapsulate, Encapsulate};
+use ml_kem::{EncodedSizeUser, KemCore, MlKem768, B32};
+use serde::Serialize;
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use crate::atomic::write_secret_new;
+use crate::header::{Compression, Header};
+use crate::key_source::Passphrase;
+#[cfg(feature = "kms")]
+use crate::kms::{KmsKey, Provider};
+use crate::nonces;
+use crate::preamble::{self, Preamble, Suite};
+use crate::{
+    compute_hmac, decrypt_aes_gcm, decrypt_chacha20, derive_embedded_key, derive_key_argon2, encrypt_aes_gcm,
+    encrypt_chacha20, layer_passphrase, random_bytes, ARGON2_SALT_LEN, GCM_NONCE_LEN, KEY_LEN, VERSION_V6,
+};
+
+pub const PUBLIC_PREFIX: &str = "violet-pub-";
+pub const SECRET_PREFIX: &str = "VIOLET-SECRET-KEY-";
+pub const HYBRID_PUBLIC_PREFIX: &str = "violet-pq-pub-";
+pub const HYBRID_SECRET_PREFIX: &str = "VIOLET-PQ-SECRET-KEY-";
+const WRAP_INFO: &[u8] = b"violet-x25519-wrap";
+const HYBRID_WRAP_INFO: &[u8] = b"violet-x25519-mlkem768-wrap";
+const MAX_RECIPIENTS: usize = u8::MAX as usize;
+const STANZA_X25519: u8 = 0x01;
+const STANZA_PASSPHRASE: u8 = 0x02;
+const STANZA_HYBRID: u8 = 0x03;
+const STANZA_KMS: u8 = 0x04;
+/// Encrypted file key at the end of every stanza
+const WRAPPED_LEN: usize = GCM_NONCE_LEN + KEY_LEN + 16;
+/// Nonce and tag of the ChaCha20-Poly1305 and AES-GCM payload layers
+const PAYLOAD_OVERHEAD: usize = 2 * (GCM_NONCE_LEN + 16);
+/// ML-KEM-768 sizes (FIPS 203): encapsulation key, ciphertext, and the d ‖ z key-generation seed
+const KEM_PUBLIC_LEN: usize = 1184;
+const KEM_CIPHERTEXT_LEN: usize = 1088;
+const KEM_SEED_LEN: usize = 64;
+const _: () = assert!(ARGON2_SALT_LEN == 32);
+
+type KemEncapsulationKey = <MlKem768 as KemCore>::EncapsulationKey;
+type KemDecapsulationKey = <MlKem768 as KemCore>::DecapsulationKey;
+
+/// Body length of a stanza of `kind` whose body starts `rest`; None for kinds
+/// this version cannot read, or a KMS body cut short
+fn body_len(kind: u8, rest: &[u8]) -> Option<usize> {
+    match kind {
+        STANZA_X25519 | STANZA_PASSPHRASE => Some(32),
+        STANZA_HYBRID => Some(32 + KEM_CIPHERTEXT_LEN),
+        STANZA_KMS => {
+            let id_len = *rest.get(1)? as usize;
+            let blob_len = rest.get(2 + id_len..4 + id_len)?;
+            Some(4 + id_len + u16::from_be_bytes([blob_len[0], blob_len[1]]) as usize)
+        }
+        _ => None,
+    }
+}
+
+/// Provider byte, key ID and blob of a KMS stanza body
+#[cfg(feature = "kms")]
+fn kms_body(body: &[u8]) -> Result<(u8, &str, &[u8])> {
+    let id_len = body[1] as usize;
+    let key_id = std::str::from_utf8(&body[2..2 + id_len]).context("KMS key ID is not UTF-8")?;
+    Ok((body[0], key_id, &body[4 + id_len..]))
+}
+
+/// Someone who can open a v6 file on their own
+pub enum Recipient {
+    /// Holder of the matching identity file
+    X25519(PublicKey),
+    /// Holder of the matching post-quantum identity file (X25519 + ML-KEM-768)
+    Hybrid { x25519: PublicKey, kem: Box<KemEncapsulationKey> },
+    /// Anyone who knows this passphrase
+    Passphrase(Passphrase),
+    /// Anyone allowed to decrypt with this cloud KMS key
+    #[cfg(feature = "kms")]
+    Kms(KmsKey),
+}
+
+impl Recipient {
+    /// Parse a public key: `violet-pub-<64 hex>` or `violet-pq-pub-<2432 hex>`
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if let Some(hex_part) = text.strip_prefix(HYBRID_PUBLIC_PREFIX) {
+            let expected = 32 + KEM_PUBLIC_LEN;
+            let bytes = hex::decode(hex_part)
+                .ok()
+                .filter(|b| b.len() == expected)
+                .with_context(|| format!("post-quantum recipient must be {} hex characters", 2 * expected))?;
+            let (x25519, kem) = bytes.split_at(32);
+            let x25519: [u8; 32] = x25519.try_into()?;
+            let kem = KemEncapsulationKey::from_bytes(kem.try_into()?);
+            return Ok(Self::Hybrid { x25519: PublicKey::from(x25519), kem: Box::new(kem) });
+        }
+        let hex_part = text
+            .strip_prefix(PUBLIC_PREFIX)
+            .with_context(|| format!("recipient must start with {} or {}", PUBLIC_PREFIX, HYBRID_PUBLIC_PREFIX))?;
+        let bytes: [u8; 32] = hex::decode(hex_part)
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .context("recipient must be 64 hex characters after the prefix")?;
+        Ok(Self::X25519(PublicKey::from(bytes)))
+    }
+}
+
+impl std::fmt::Display for Recipient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::X25519(key) => write!(f, "{}{}", PUBLIC_PREFIX, hex::encode(key.as_bytes())),
+            Self::Hybrid { x25519, kem } => write!(
+                f,
+                "{}{}{}",
+                HYBRID_PUBLIC_PREFIX,
+                hex::encode(x25519.as_bytes()),
+                hex::encode(kem.as_bytes())
+            ),
+            Self::Passphrase(_) => f.write_str("passphrase"),
+            #[cfg(feature = "kms")]
+            Self::Kms(key) => write!(f, "{}", key),
+        }
+    }
+}
+
+/// The private half, as read from an identity file
+enum Identity {
+    X25519(StaticSecret),
+    /// The ML-KEM key is kept as its 64-byte seed and expanded when needed
+    Hybrid { x25519: StaticSecret, kem_seed: Zeroizing<[u8; KEM_SEED_LEN]> },
+}
+
+impl Identity {
+    /// Find the `VIOLET-SECRET-KEY-` or `VIOLET-PQ-SECRET-KEY-` line; `#` comment lines are ignored
+    fn parse(text: &[u8]) -> Result<Self> {
+        let text = std::str::from_utf8(text).ok();
+        let (hybrid, line) = text
+            .into_iter()
+            .flat_map(str::lines)
+            .map(str::trim)
+            .find_map(|line| {
+                let classic = line.strip_prefix(SECRET_PREFIX).map(|key| (false, key));
+                classic.or_else(|| line.strip_prefix(HYBRID_SECRET_PREFIX).map(|key| (true, key)))
+            })
+            .context("no VIOLET-SECRET-KEY- or VIOLET-PQ-SECRET-KEY- line — not an identity file")?;
+        let bytes = Zeroizing::new(hex::decode(line).context("identity key is not hex")?);
+        if !hybrid {
+            let bytes: [u8; 32] = bytes.as_slice().try_into().context("identity key must be 32 bytes")?;
+            return Ok(Self::X25519(StaticSecret::from(bytes)));
+        }
+        if bytes.len() != 32 + KEM_SEED_LEN {
+            bail!("post-quantum identity key must be {} bytes", 32 + KEM_SEED_LEN);
+        }
+        let x25519: [u8; 32] = bytes[..32].try_into()?;
+        let mut kem_seed = Zeroizing::new([0u8; KEM_SEED_LEN]);
+        kem_seed.copy_from_slice(&bytes[32..]);
+        Ok(Self::Hybrid { x25519: StaticSecret::from(x25519), kem_seed })
+    }
+
+    fn x25519(&self) -> &StaticSecret {
+        match self {
+            Self::X25519(secret) | Self::Hybrid { x25519: secret, .. } => secret,
+        }
+    }
+
+    fn recipient(&self) -> Recipient {
+        let x25519 = PublicKey::from(self.x25519());
+        match self {
+            Self::X25519(_) => Recipient::X25519(x25519),
+            Self::Hybrid { kem_seed, .. } => Recipient::Hybrid { x25519, kem: Box::new(kem_keys(kem_seed).1) },
+        }
+    }
+
+    fn encode(&self) -> Zeroizing<String> {
+        let (prefix, secret) = match self {
+            Self::X25519(secret) => (SECRET_PREFIX, Zeroizing::new(secret.to_bytes().to_vec())),
+            Self::Hybrid { x25519, kem_seed } => {
+                (HYBRID_SECRET_PREFIX, Zeroizing::new([x25519.as_bytes().as_slice(), kem_seed.as_slice()].concat()))
+            }
+        };
+        Zeroizing::new(format!(
+            "# public key: {}\n{}{}\n",
+            self.recipient(),
+            prefix,
+            hex::encode(secret.as_slice())
+        ))
+    }
+}
+
+/// Expand an ML-KEM-768 seed (d ‖ z) into its key pair
+fn kem_keys(seed: &[u8; KEM_SEED_LEN]) -> (KemDecapsulationKey, KemEncapsulationKey) {
+    let (d, z) = seed.split_at(32);
+    let d: [u8; 32] = d.try_into().expect("seed half is 32 bytes");
+    let z: [u8; 32] = z.try_into().expect("seed half is 32 bytes");
+    MlKem768::generate_deterministic(&B32::from(d), &B32::from(z))
+}
+
+/// Generate a keypair — X25519, or X25519 + ML-KEM-768 when `post_quantum` —
+/// writes the identity to `output` (0600, never overwritten) or stdout, and
+/// returns the public key
+pub fn keygen(output: Option<&Path>, post_quantum: bool) -> Result<Recipient> {
+    let x25519 = StaticSecret::from(random_bytes::<32>());
+    let identity = if post_quantum {
+        Identity::Hybrid { x25519, kem_seed: Zeroizing::new(random_bytes::<KEM_SEED_LEN>()) }
+    } else {
+        Identity::X25519(x25519)
+    };
+    let encoded = identity.encode();
+    match output {
+        Some(path) => write_secret_new(path, encoded.as_bytes())?,
+        None => print!("{}", encoded.as_str()),
+    }
+    Ok(identity.recipient())
+}
+
+fn hkdf_key(salt: Option<&[u8]>, ikm: &[u8], info: &[&[u8]]) -> Result<Zeroizing<[u8; KEY_LEN]>> {
+    let mut okm = Zeroizing::new([0u8; KEY_LEN]);
+    Hkdf::<Sha256>::new(salt, ikm)
+        .expand_multi_info(info, okm.as_mut())
+        .map_err(|e| anyhow::anyhow!("HKDF expand: {}", e))?;
+    Ok(okm)
+}
+
+pub fn wrap_key(shared: &[u8; 32], ephemeral: &PublicKey, recipient: &PublicKey) -> Result<Zeroizing<[u8; KEY_LEN]>> {
+    let salt = [ephemeral.as_bytes().as_slice(), recipient.as_bytes()].concat();
+    hkdf_key(Some(&salt), shared, &[WRAP_INFO])
+}
+
+/// Both shared secrets feed one HKDF, so the wrap key stays secret while either holds
+fn hybrid_wrap_key(
+    kem_shared: &[u8],
+    x25519_shared: &[u8; 32],
+    ephemeral: &PublicKey,
+    recipient: &PublicKey,
+    kem_ciphertext: &[u8],
+) -> Result<Zeroizing<[u8; KEY_LEN]>> {
+    let ikm = Zeroizing::new([kem_shared, x25519_shared.as_slice()].concat());
+    let salt = [ephemeral.as_bytes().as_slice(), recipient.as_bytes(), kem_ciphertext].concat();
+    hkdf_key(Some(&salt), &ikm, &[HYBRID_WRAP_INFO])
+}
+
+fn passphrase_wrap_key(
+    passphrase: &[u8],
+    salt: &[u8],
+    salt_label: &str,
+    kdf: &KdfParams,
+) -> Result<Zeroizing<[u8; KEY_LEN]>> {
+    let layered = layer_passphrase(passphrase, "v6-wrap", salt_label);
+    Ok(Zeroizing::new(*derive_key_argon2(&layered, salt, kdf)?))
+}
+
+/// Inner and outer payload keys, bound to the salt label like the passphrase layers
+fn payload_keys(file_key: &[u8], salt_label: &str) -> Result<[Zeroizing<[u8; KEY_LEN]>; 2]> {
+    let label = salt_label.as_bytes();
+    Ok([
+        hkdf_key(None, file_key, &[b"violet-v6-inner-", label])?,
+        hkdf_key(None, file_key, &[b"violet-v6-outer-", label])?,
+    ])
+}
+
+/// Fresh ephemeral key agreement with `public`; returns the ephemeral public key and shared secret
+pub fn ephemeral_agreement(public: &PublicKey) -> Result<(PublicKey, Zeroizing<[u8; 32]>)> {
+    let ephemeral = StaticSecret::from(random_bytes::<32>());
+    let shared = ephemeral.diffie_hellman(public);
+    if !shared.was_contributory() {
+        bail!("recipient {}{} is a low-order point", PUBLIC_PREFIX, hex::encode(public.as_bytes()));
+    }
+    Ok((PublicKey::from(&ephemeral), Zeroizing::new(shared.to_bytes())))
+}
+
+fn stanza(recipient: &Recipient, file_key: &[u8], salt_label: &str, kdf: &KdfParams) -> Result<Vec<u8>> {
+    let (kind, body, key) = match recipient {
+        Recipient::X25519(public) => {
+            let (ephemeral, shared) = ephemeral_agreement(public)?;
+            let key = wrap_key(&shared, &ephemeral, public)?;
+            (STANZA_X25519, ephemeral.as_bytes().to_vec(), key)
+        }
+        Recipient::Hybrid { x25519, kem } => {
+            let (ephemeral, shared) = ephemeral_agreement(x25519)?;
+            let (kem_ciphertext, kem_shared) = kem
+                .encapsulate(&mut rand::thread_rng())
+                .map_err(|_| anyhow::anyhow!("ML-KEM encapsulation failed"))?;
+            let kem_shared = Zeroizing::new(kem_shared.to_vec());
+            let key = hybrid_wrap_key(&kem_shared, &shared, &ephemeral, x25519, &kem_ciphertext)?;
+            (STANZA_HYBRID, [ephemeral.as_bytes().as_slice(), kem_ciphertext.as_slice()].concat(), key)
+        }
+        Recipient::Passphrase(passphrase) => {
+            let salt = random_bytes::<ARGON2_SALT_LEN>();
+            (STANZA_PASSPHRASE, salt.to_vec(), passphrase_wrap_key(passphrase, &salt, salt_label, kdf)?)
+        }
+        #[cfg(feature = "kms")]
+        Recipient::Kms(kms_key) => {
+            let key = Zeroizing::new(random_bytes::<KEY_LEN>());
+            let blob = kms_key.wrap(key.as_slice())?;
+            let blob_len = u16::try_from(blob.len()).context("KMS ciphertext too long for a stanza")?;
+            let id = kms_key.key_id.as_bytes();
+            let body = [&[kms_key.provider.byte(), id.len() as u8][..], id, &blob_len.to_be_bytes(), &blob].concat();
+            (STANZA_KMS, body, key)
+        }
+    };
+    Ok([&[kind][..], &body, &encrypt_chacha20(&key, file_key)?].concat())
+}
+
+/// Header, stanzas and payload around an existing file key
+fn seal(
+    file_key: &[u8],
+    stanzas: &[Vec<u8>],
+    salt_label: &str,
+    plaintext: &[u8],
+    header: &Header,
+    kdf: &KdfParams,
+) -> Result<Vec<u8>> {
+    if header.suite != Suite::default() {
+        bail!("cipher suite {} is for passphrase (v5) files; v6 payloads always use their own", header.suite);
+    }
+    let header = header.stamped(plaintext, None).with_salt_label(salt_label);
+    let metadata = header.encode();
+    let packed = header.compression.compress(plaintext)?;
+    let stanzas_len: usize = stanzas.iter().map(Vec::len).sum();
+    let payload_len = 1 + stanzas_len + packed.len() + PAYLOAD_OVERHEAD;
+    let compressed = header.compression != Compression::None;
+    let name_bound = header.file_name.is_some();
+    let preamble = Preamble::new(VERSION_V6, kdf, compressed, name_bound, metadata.len(), payload_len);
+
+    let mut output = preamble.encode().to_vec();
+    output.extend_from_slice(&metadata);
+    output.push(stanzas.len() as u8);
+    for stanza in stanzas {
+        output.extend_from_slice(stanza);
+    }
+
+    let [inner_key, outer_key] = payload_keys(file_key, salt_label)?;
+    let inner = encrypt_chacha20(&inner_key, &packed)?;
+    let outer = encrypt_aes_gcm(&outer_key, &inner, &output)?;
+    output.extend_from_slice(&outer);
+    debug_assert_eq!(output.len(), preamble::LEN + preamble.metadata_len + preamble.payload_len);
+    let hmac_data = compute_hmac(&derive_embedded_key(), &output);
+    output.extend_from_slice(&hmac_data);
+    Ok(output)
+}
+
+/// Encrypt once; every recipient can open the result independently.
+/// `kdf` is only used for passphrase recipients
+pub fn encrypt(
+    recipients: &[Recipient],
+    salt_label: &str,
+    plaintext: &[u8],
+    header: &Header,
+    kdf: &KdfParams,
+) -> Result<Vec<u8>> {
+    if recipients.is_empty() || recipients.len() > MAX_RECIPIENTS {
+        bail!("need 1–{} recipients", MAX_RECIPIENTS);
+    }
+    let file_key = Zeroizing::new(random_bytes::<KEY_LEN>());
+    let stanzas = recipients
+        .iter()
+        .map(|r| stanza(r, file_key.as_slice(), salt_label, kdf))
+        .collect::<Result<Vec<_>>>()?;
+    seal(file_key.as_slice(), &stanzas, salt_label, plaintext, header, kdf)
+}
+
+/// One recipient's stanza, split at the kind's body length
+struct Stanza<'a> {
+    kind: u8,
+    body: &'a [u8],
+    wrapped: &'a [u8],
+}
+
+/// A structurally valid v6 blob, split into its parts
+struct Parsed<'a> {
+    /// Absent for files that start with a bare version byte
+    preamble: Option<Preamble>,
+    header: Header,
+    stanzas: Vec<Stanza<'a>>,
+    /// Everything before the outer layer
+    aad: &'a [u8],
+    outer: &'a [u8],
+}
+
+fn parse(data: &[u8]) -> Result<Parsed<'_>> {
+    if preamble::version(data) != Some(VERSION_V6) {
+        bail!("not v6 format");
+    }
+    let (preamble, prefix) = preamble::split(data)?;
+    let (header, header_len) = Header::parse(&data[prefix..])?;
+    if let Some(preamble) = &preamble {
+        preamble.check_metadata(&header, header_len)?;
+    }
+    let count_offset = prefix + header_len;
+    let count = *data.get(count_offset).context("v6 data too short")? as usize;
+    if count == 0 {
+        bail!("v6 file has no recipients");
+    }
+    if data.len() < count_offset + 1 + 32 {
+        bail!("v6 data too short");
+    }
+
+    let hmac_offset = data.len() - 32;
+    let computed_hmac = compute_hmac(&derive_embedded_key(), &data[..hmac_offset]);
+    if data[hmac_offset..] != computed_hmac[..] {
+        bail!("HMAC verification failed — data tampered or wrong binary");
+    }
+
+    // Stanza lengths depend on their kind, so walk them in order
+    let mut offset = count_offset + 1;
+    let mut stanzas = Vec::with_capacity(count);
+    for _ in 0..count {
+        let kind = *data[..hmac_offset].get(offset).context("v6 data too short")?;
+        let body_len = body_len(kind, &data[offset + 1..hmac_offset])
+            .with_context(|| format!("v6 stanza of unknown kind {:#04x} — upgrade violet-cipher", kind))?;
+        let body_end = offset + 1 + body_len;
+        let end = body_end + WRAPPED_LEN;
+        if end > hmac_offset {
+            bail!("v6 data too short");
+        }
+        stanzas.push(Stanza { kind, body: &data[offset + 1..body_end], wrapped: &data[body_end..end] });
+        offset = end;
+    }
+    let aad_len = offset;
+    if hmac_offset < aad_len + GCM_NONCE_LEN + 16 {
+        bail!("v6 data too short");
+    }
+    Ok(Parsed {
+        preamble,
+        header,
+        stanzas,
+        aad: &data[..aad_len],
+        outer: &data[aad_len..hmac_offset],
+    })
+}
+
+/// How many stanzas a v6 file has, by kind
+#[derive(Serialize)]
+pub struct Recipients {
+    pub x25519: usize,
+    pub hybrid: usize,
+    pub passphrase: usize,
+    pub kms: usize,
+}
+
+impl std::fmt::Display for Recipients {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} public-key + {} post-quantum + {} passphrase + {} KMS recipient(s)",
+            self.x25519, self.hybrid, self.passphrase, self.kms
+        )
+    }
+}
+
+/// Structural and HMAC check; needs no secret. Returns the header and recipients
+pub fn check_integrity(data: &[u8]) -> Result<(Header, Recipients)> {
+    let parsed = parse(data)?;
+    let count = |kind| parsed.stanzas.iter().filter(|s| s.kind == kind).count();
+    let recipients = Recipients {
+        x25519: count(STANZA_X25519),
+        hybrid: count(STANZA_HYBRID),
+        passphrase: count(STANZA_PASSPHRASE),
+        kms: count(STANZA_KMS),
+    };
+    Ok((parsed.header, recipients))
+}
+
+/// Nonces readable without a secret: each stanza's wrap, filed under its
+/// body (salt or ephemeral key), and the outer layer's
+pub fn nonces(data: &[u8]) -> Result<Vec<nonces::Found>> {
+    let parsed = parse(data)?;
+    let stanzas = parsed.stanzas.iter().map(|s| nonces::Found::new("stanza", Some(s.body), s.wrapped, GCM_NONCE_LEN));
+    Ok(stanzas.chain([nonces::Found::new("outer", None, parsed.outer, GCM_NONCE_LEN)]).flatten().collect())
+}
+
+/// Unwrap the file key with `secret`: an identity file's contents, or else a passphrase.
+/// Returns the file key and the index of the stanza that opened it
+fn unwrap_file_key(
+    parsed: &Parsed<'_>,
+    secret: &[u8],
+    salt_label: &str,
+    kdf: &KdfParams,
+) -> Result<(Zeroizing<Vec<u8>>, usize)> {
+    let kdf = parsed.kdf(kdf);
+    let opened = match Identity::parse(secret) {
+        Ok(identity) => {
+            let ours = PublicKey::from(identity.x25519());
+            let kem = match &identity {
+                Identity::Hybrid { kem_seed, .. } => Some(kem_keys(kem_seed).0),
+                Identity::X25519(_) => None,
+            };
+            parsed.stanzas.iter().enumerate().find_map(|(i, stanza)| {
+                if stanza.kind != STANZA_X25519 && stanza.kind != STANZA_HYBRID {
+                    return None;
+                }
+                let (ephemeral, kem_ciphertext) = stanza.body.split_at(32);
+                let ephemeral: [u8; 32] = ephemeral.try_into().ok()?;
+                let ephemeral = PublicKey::from(ephemeral);
+                let shared = identity.x25519().diffie_hellman(&ephemeral);
+                if !shared.was_contributory() {
+                    return None;
+                }
+                let key = match (stanza.kind, &kem) {
+                    (STANZA_X25519, None) => wrap_key(shared.as_bytes(), &ephemeral, &ours).ok()?,
+                    (STANZA_HYBRID, Some(kem)) => {
+                        let kem_shared = kem.decapsulate(kem_ciphertext.try_into().ok()?).ok()?;
+                        let kem_shared = Zeroizing::new(kem_shared.to_vec());
+                        hybrid_wrap_key(&kem_shared, shared.as_bytes(), &ephemeral, &ours, kem_ciphertext).ok()?
+                    }
+                    _ => return None,
+                };
+                let file_key = decrypt_chacha20(&key, stanza.wrapped).ok()?;
+                Some((Zeroizing::new(file_key), i))
+            })
+        }
+        Err(_) => {
+            if !parsed.stanzas.iter().any(|s| s.kind == STANZA_PASSPHRASE) {
+                bail!("file is encrypted to public-key or KMS recipients only — decrypt with --identity <file> or --kms");
+            }
+            let mut opened = None;
+            for (i, stanza) in parsed.stanzas.iter().enumerate() {
+                if stanza.kind != STANZA_PASSPHRASE {
+                    continue;
+                }
+                let key = passphrase_wrap_key(secret, stanza.body, salt_label, kdf)?;
+                if let Ok(file_key) = decrypt_chacha20(&key, stanza.wrapped) {
+                    opened = Some((Zeroizing::new(file_key), i));
+                    break;
+                }
+            }
+            opened
+        }
+    };
+    opened.context("this key is not among the file's recipients")
+}
+
+impl Parsed<'_> {
+    /// Argon2id costs of the passphrase stanzas: the preamble's, else the caller's
+    fn kdf<'k>(&'k self, fallback: &'k KdfParams) -> &'k KdfParams {
+        self.preamble.as_ref().map_or(fallback, |p| &p.kdf)
+    }
+}
+
+fn open_payload(parsed: &Parsed<'_>, file_key: &[u8], salt_label: &str) -> Result<Vec<u8>> {
+    let [inner_key, outer_key] = payload_keys(file_key, salt_label)?;
+    let inner = decrypt_aes_gcm(&outer_key, parsed.outer, parsed.aad)
+        .context("outer layer — wrong salt label or corrupted payload")?;
+    let packed = Zeroizing::new(decrypt_chacha20(&inner_key, &inner)?);
+    nonces::opened("inner", None, &inner, GCM_NONCE_LEN);
+    parsed.header.compression.decompress(&packed)
+}
+
+/// Decrypt with an identity file's contents or a passphrase (passed wherever a passphrase would go)
+pub fn decrypt(secret: &[u8], salt_label: &str, data: &[u8], kdf: &KdfParams) -> Result<(Header, Vec<u8>)> {
+    let parsed = parse(data)?;
+    let (file_key, _) = unwrap_file_key(&parsed, secret, salt_label, kdf)?;
+    let plaintext = open_payload(&parsed, &file_key, salt_label)?;
+    Ok((parsed.header, plaintext))
+}
+
+/// Decrypt through the file's KMS recipients, with the machine's cloud credentials
+#[cfg(feature = "kms")]
+pub fn decrypt_kms(salt_label: &str, data: &[u8]) -> Result<(Header, Vec<u8>)> {
+    let parsed = parse(data)?;
+    let mut failure = None;
+    for stanza in parsed.stanzas.iter().filter(|s| s.kind == STANZA_KMS) {
+        let (provider, key_id, blob) = kms_body(stanza.body)?;
+        let key = KmsKey { provider: Provider::from_byte(provider)?, key_id: key_id.to_string() };
+        match key.unwrap(blob) {
+            Ok(wrap) => {
+                let wrap: &[u8; KEY_LEN] = wrap.as_slice().try_into().context("KMS returned a key of the wrong length")?;
+                let file_key = Zeroizing::new(decrypt_chacha20(wrap, stanza.wrapped).context("KMS stanza does not open")?);
+                let plaintext = open_payload(&parsed, &file_key, salt_label)?;
+                return Ok((parsed.header, plaintext));
+            }
+            Err(e) => failure = Some(e.context(format!("unwrap with {}", key))),
+        }
+    }
+    Err(failure.unwrap_or_else(|| anyhow::anyhow!("file has no KMS recipient — decrypt with the passphrase or --identity")))
+}
+
+/// Swap the passphrase stanza `old` opens for one under `new`; every other
+/// recipient keeps access through the same file key
+pub fn rotate_passphrase(old: &[u8], new: &[u8], salt_label: &str, data: &[u8], kdf: &KdfParams) -> Result<Vec<u8>> {
+    let parsed = parse(data)?;
+    let (file_key, index) = unwrap_file_key(&parsed, old, salt_label, kdf)?;
+    if parsed.stanzas[index].kind != STANZA_PASSPHRASE {
+        bail!("the old key opens this file as an identity — nothing to rotate");
+    }
+    let plaintext = open_payload(&parsed, &file_key, salt_label)?;
+    let mut stanzas: Vec<Vec<u8>> =
+        parsed.stanzas.iter().map(|s| [&[s.kind][..], s.body, s.wrapped].concat()).collect();
+    let new_passphrase = Recipient::Passphrase(Zeroizing::new(new.to_vec()));
+    // Keep every passphrase stanza on the costs the preamble records
+    let kdf = parsed.kdf(kdf);
+    stanzas[index] = stanza(&new_passphrase, &file_key, salt_label, kdf)?;
+    seal(&file_key, &stanzas, salt_label, &plaintext, &parsed.header, kdf)
+}
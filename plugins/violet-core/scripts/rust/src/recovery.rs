@@ -0,0 +1,199 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Recovery key — an offline key that gives back a forgotten passphrase
+//
+// `key recovery-generate` draws an X25519 key pair, prints the private half
+// once as `VIOLET-RECOVERY-KEY-<64 hex>` for offline storage and keeps only
+// the public half, in recovery.pub in the data directory. From then on every
+// v5 file a command writes there carries the passphrase wrapped to that key
+// as a header entry (header.rs):
+//
+//   key ID (8) | ephemeral public key (32) | ChaCha20-Poly1305(wrap key, passphrase)
+//
+// with the wrap key of a v6 X25519 stanza (recipient.rs) and the key ID the
+// start of SHA-256 over the public key. The header is authenticated under the
+// passphrase, so the entry cannot be swapped without it. `key recover`
+// unwraps the passphrase from any such file, checks it against the file's
+// HMAC and writes it out; rotate to a new passphrase after. `encrypt-local`
+// rewrites any .enc without an entry for the current key, so its next run
+// brings files written before the key existed in.
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use tracing::info;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use crate::atomic::write_secret_new;
+use crate::recipient::{self, Recipient};
+use crate::{decrypt_chacha20, encrypt_chacha20, logging, random_bytes, read_header, v5_check_mac, write_atomic};
+use crate::{TARGET_FILES, VERSION_V5};
+
+pub const RECOVERY_FILE: &str = "recovery.pub";
+const SECRET_PREFIX: &str = "VIOLET-RECOVERY-KEY-";
+pub const KEY_ID_LEN: usize = 8;
+/// Key ID, ephemeral key, and the nonce and tag around the passphrase
+pub const MIN_ENTRY_LEN: usize = KEY_ID_LEN + 32 + 12 + 16;
+
+static CONFIGURED: OnceLock<PublicKey> = OnceLock::new();
+
+fn key_id(public: &PublicKey) -> [u8; KEY_ID_LEN] {
+    Sha256::digest(public.as_bytes())[..KEY_ID_LEN].try_into().expect("digest is longer than a key ID")
+}
+
+/// Hex ID of a recovery key, as headers and recovery.pub name it
+pub fn secret_id(secret: &StaticSecret) -> String {
+    hex::encode(key_id(&PublicKey::from(secret)))
+}
+
+/// The `VIOLET-RECOVERY-KEY-` line of a recovery key file
+pub fn encode_secret(secret: &StaticSecret) -> Zeroizing<String> {
+    Zeroizing::new(format!("{}{}\n", SECRET_PREFIX, hex::encode(secret.as_bytes())))
+}
+
+/// The recovery key in `text`; None when it has no `VIOLET-RECOVERY-KEY-` line
+pub fn parse_secret(text: &str) -> Option<Result<StaticSecret>> {
+    let hex_key = text.lines().find_map(|line| line.trim().strip_prefix(SECRET_PREFIX))?;
+    Some((|| {
+        let bytes = Zeroizing::new(hex::decode(hex_key).context("recovery key is not hex")?);
+        let bytes: [u8; 32] = bytes.as_slice().try_into().context("recovery key must be 32 bytes")?;
+        Ok(StaticSecret::from(bytes))
+    })())
+}
+
+/// Escrow to the recovery key of `data_dir` for the rest of the run, if it has one
+pub fn load(data_dir: &Path) -> Result<()> {
+    let path = data_dir.join(RECOVERY_FILE);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("read {:?}", path)),
+    };
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with('#'));
+    let Some(Ok(Recipient::X25519(public))) = line.map(Recipient::parse) else {
+        bail!("{:?} must hold the recovery key's {}… public key", path, recipient::PUBLIC_PREFIX);
+    };
+    // A second load in one run names the same directory
+    let _ = CONFIGURED.set(public);
+    Ok(())
+}
+
+/// Hex ID of the recovery key files are escrowed to this run
+pub fn configured_id() -> Option<String> {
+    CONFIGURED.get().map(|public| hex::encode(key_id(public)))
+}
+
+/// Hex ID of the recovery key a header entry is for
+pub fn entry_id(entry: &[u8]) -> String {
+    hex::encode(&entry[..KEY_ID_LEN])
+}
+
+/// Header entry wrapping `passphrase` to the configured recovery key; None without one
+pub fn escrow(passphrase: &[u8]) -> Result<Option<Vec<u8>>> {
+    let Some(public) = CONFIGURED.get() else {
+        return Ok(None);
+    };
+    let (ephemeral, shared) = recipient::ephemeral_agreement(public)?;
+    let wrap = recipient::wrap_key(&shared, &ephemeral, public)?;
+    let wrapped = encrypt_chacha20(&wrap, passphrase)?;
+    Ok(Some([key_id(public).as_slice(), ephemeral.as_bytes(), &wrapped].concat()))
+}
+
+fn unwrap(secret: &StaticSecret, entry: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    let public = PublicKey::from(secret);
+    if entry[..KEY_ID_LEN] != key_id(&public) {
+        bail!("escrowed to recovery key {}, not {}", entry_id(entry), hex::encode(key_id(&public)));
+    }
+    let ephemeral: [u8; 32] = entry[KEY_ID_LEN..KEY_ID_LEN + 32].try_into()?;
+    let ephemeral = PublicKey::from(ephemeral);
+    let shared = Zeroizing::new(secret.diffie_hellman(&ephemeral).to_bytes());
+    let wrap = recipient::wrap_key(&shared, &ephemeral, &public)?;
+    let passphrase = decrypt_chacha20(&wrap, &entry[KEY_ID_LEN + 32..]).context("recovery entry does not open")?;
+    Ok(Zeroizing::new(passphrase))
+}
+
+/// `key recovery-generate`: new key pair, the private half printed (or written to `output`) once
+pub fn generate(data_dir: &Path, output: Option<&Path>, replace: bool) -> Result<()> {
+    let path = data_dir.join(RECOVERY_FILE);
+    if path.exists() && !replace {
+        bail!("{:?} exists — pass --replace to retire its key (files keep their entries until rewritten)", path);
+    }
+    let secret = StaticSecret::from(random_bytes::<32>());
+    let public = PublicKey::from(&secret);
+    let encoded = encode_secret(&secret);
+    match output {
+        Some(output) => write_secret_new(output, encoded.as_bytes())?,
+        None => {
+            logging::claim_stdout();
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(encoded.as_bytes()).and_then(|()| stdout.flush()).context("write stdout")?;
+        }
+    }
+    let id = hex::encode(key_id(&public));
+    let contents = format!("# violet-cipher recovery key {}\n{}\n", id, Recipient::X25519(public));
+    write_atomic(&path, contents.as_bytes()).with_context(|| format!("write {:?}", path))?;
+    match output {
+        Some(output) => logging::note(format_args!("🛟 Recovery key {} written to {:?}", id, output)),
+        None => logging::note(format_args!("🛟 Recovery key {} printed above — it is not shown again", id)),
+    }
+    logging::note(format_args!("   Keep it offline (paper, a safe), never next to the data."));
+    logging::note(format_args!("   {:?} holds the public half; files written from now on escrow to it.", path));
+    logging::note(format_args!("   Run `encrypt-local` so the existing .enc files do too."));
+    Ok(())
+}
+
+/// `key recover`: the passphrase escrowed in `file` (default: the first target
+/// .enc that has an entry), written to `output` (mode 0600) or stdout
+pub fn recover(recovery_key_file: &Path, data_dir: &Path, file: Option<PathBuf>, output: Option<&Path>) -> Result<()> {
+    let text = Zeroizing::new(
+        fs::read_to_string(recovery_key_file).with_context(|| format!("read recovery key {:?}", recovery_key_file))?,
+    );
+    let secret = parse_secret(&text).with_context(|| format!("no {} line in {:?}", SECRET_PREFIX, recovery_key_file))??;
+
+    let candidates = match file {
+        Some(file) => vec![file],
+        None => TARGET_FILES.iter().map(|name| data_dir.join(format!("{}.enc", name))).filter(|p| p.exists()).collect(),
+    };
+    let mut last_error = None;
+    for path in candidates {
+        let data = fs::read(&path).with_context(|| format!("read {:?}", path))?;
+        let header = match read_header(&data) {
+            Ok(Some(header)) if crate::preamble::version(&data) == Some(VERSION_V5) => header,
+            Ok(_) => {
+                last_error = Some(anyhow::anyhow!("{:?} is not a v5 file", path));
+                continue;
+            }
+            Err(e) => {
+                last_error = Some(e.context(format!("{:?}", path)));
+                continue;
+            }
+        };
+        let Some(entry) = &header.recovery else {
+            last_error = Some(anyhow::anyhow!("{:?} carries no recovery entry — it predates the key", path));
+            continue;
+        };
+        let passphrase = match unwrap(&secret, entry) {
+            Ok(passphrase) => passphrase,
+            Err(e) => {
+                last_error = Some(e.context(format!("{:?}", path)));
+                continue;
+            }
+        };
+        v5_check_mac(&passphrase, &data)
+            .with_context(|| format!("{:?}: the escrowed passphrase does not match the file", path))?;
+        match output {
+            Some(output) => write_secret_new(output, &passphrase)?,
+            None => {
+                logging::claim_stdout();
+                std::io::stdout().write_all(&passphrase).context("write stdout")?;
+            }
+        }
+        info!("🛟 Passphrase recovered from {:?}{}", path, output.map_or(String::new(), |o| format!(" → {:?}", o)));
+        info!("   Choose a new one with `rotate-key`; the old one is only as safe as this recovery key.");
+        return Ok(());
+    }
+    Err(last_error.unwrap_or_else(|| anyhow::anyhow!("no .enc in {:?} to recover from — pass --file", data_dir)))
+}
@@ -0,0 +1,57 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Salt labels — the key domain a file is sealed under (`--salt-label`)
+//
+// A label picks the salt string that every layer passphrase and the binding
+// mix in: `violet-soul-salt-<label>-2026`. "local" and "git" are the domains
+// of the target files (.enc and .git.enc); any other label, e.g. "staging" or
+// "backup", is a domain of its own whose files open under no other label.
+// v5/v6 writers record the salt string in the header, so the single-file
+// decrypt commands pick it up when no label is given; v4 has no header and
+// needs the label every time.
+use anyhow::{bail, Result};
+use clap::Args;
+
+use crate::{read_header, LOCAL_SALT};
+
+const PREFIX: &str = "violet-soul-salt-";
+const SUFFIX: &str = "-2026";
+const MAX_LABEL_LEN: usize = 32;
+
+#[derive(Args)]
+pub struct SaltArgs {
+    /// Key domain: "local", "git" or any other label, e.g. "staging" (default: the one the file records, else local)
+    #[arg(long = "salt-label", visible_alias = "salt")]
+    salt_label: Option<String>,
+}
+
+impl SaltArgs {
+    /// Salt string to seal with: the given label's, else local's
+    pub fn for_writing(&self) -> Result<String> {
+        self.salt_label.as_deref().map_or(Ok(LOCAL_SALT.to_string()), salt_for)
+    }
+
+    /// Salt string to open `data` with: the given label's, else the one its
+    /// header records, else local's
+    pub fn for_reading(&self, data: &[u8]) -> Result<String> {
+        if let Some(label) = &self.salt_label {
+            return salt_for(label);
+        }
+        // A header that fails its check here fails again, with the real error, when opened
+        let recorded = read_header(data).ok().flatten().and_then(|header| header.salt_label);
+        Ok(recorded.unwrap_or_else(|| LOCAL_SALT.to_string()))
+    }
+}
+
+/// Salt string of a label: 1–32 of `a-z 0-9 . _ -`
+pub fn salt_for(label: &str) -> Result<String> {
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '_' | '-');
+    if label.is_empty() || label.len() > MAX_LABEL_LEN || !label.chars().all(allowed) {
+        bail!("salt label {:?} must be 1–{} characters of a-z, 0-9, '.', '_' and '-'", label, MAX_LABEL_LEN);
+    }
+    Ok(format!("{}{}{}", PREFIX, label, SUFFIX))
+}
+
+/// The label a salt string was made from, for messages; other strings as they are
+pub fn label_of(salt: &str) -> &str {
+    salt.strip_prefix(PREFIX).and_then(|rest| rest.strip_suffix(SUFFIX)).unwrap_or(salt)
+}
@@ -0,0 +1,246 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// SARIF — `verify` and `check-staged` findings for code scanning dashboards
+//
+// With `--sarif <path>` every issue the command counts is also written to a
+// SARIF 2.1.0 log: one result per finding, under one rule per kind of
+// finding (plaintext-leak, integrity, nonce-reuse, …) with a level and a
+// security-severity, located at the file it is about. Files inside a git
+// work tree get repository-relative URIs under %SRCROOT%, which is what
+// GitHub code scanning needs to pin an alert to a file; the findings are
+// about whole files, so each points at line 1. The log is written even when
+// nothing is found: uploading an empty run closes the alerts of earlier ones.
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+use crate::write_atomic;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SRCROOT: &str = "%SRCROOT%";
+
+/// Kinds of finding, one SARIF rule each
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    KeyLeak,
+    PlaintextLeak,
+    CommittedLocalKey,
+    PlaceholderData,
+    Integrity,
+    TokenMismatch,
+    MacMismatch,
+    NonceReuse,
+    SchemaViolation,
+    LegacyFormat,
+    MixedKeyFamilies,
+}
+
+impl Rule {
+    const ALL: [Rule; 11] = [
+        Rule::KeyLeak,
+        Rule::PlaintextLeak,
+        Rule::CommittedLocalKey,
+        Rule::PlaceholderData,
+        Rule::Integrity,
+        Rule::TokenMismatch,
+        Rule::MacMismatch,
+        Rule::NonceReuse,
+        Rule::SchemaViolation,
+        Rule::LegacyFormat,
+        Rule::MixedKeyFamilies,
+    ];
+
+    fn id(self) -> &'static str {
+        match self {
+            Rule::KeyLeak => "key-leak",
+            Rule::PlaintextLeak => "plaintext-leak",
+            Rule::CommittedLocalKey => "committed-local-key",
+            Rule::PlaceholderData => "placeholder-data",
+            Rule::Integrity => "integrity",
+            Rule::TokenMismatch => "token-mismatch",
+            Rule::MacMismatch => "mac-mismatch",
+            Rule::NonceReuse => "nonce-reuse",
+            Rule::SchemaViolation => "schema-violation",
+            Rule::LegacyFormat => "legacy-format",
+            Rule::MixedKeyFamilies => "mixed-key-families",
+        }
+    }
+
+    /// PascalCase name, as SARIF viewers show it
+    fn name(self) -> String {
+        self.id().split('-').map(|word| word[..1].to_uppercase() + &word[1..]).collect()
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Rule::KeyLeak => "A plaintext file contains the encryption passphrase",
+            Rule::PlaintextLeak => "Decrypted soul data is on disk or staged where ciphertext belongs",
+            Rule::CommittedLocalKey => "A file sealed with the local key is staged for commit",
+            Rule::PlaceholderData => "A .git.enc placeholder carries real data",
+            Rule::Integrity => "An encrypted file is damaged, truncated or fails to authenticate",
+            Rule::TokenMismatch => "A file no longer matches its verification token, or carries another key ID",
+            Rule::MacMismatch => "A file does not match its detached MAC",
+            Rule::NonceReuse => "The same nonce appears in more than one encrypted layer",
+            Rule::SchemaViolation => "A decrypted file does not match its bundled JSON Schema",
+            Rule::LegacyFormat => "A file still uses the legacy v2/v3 format",
+            Rule::MixedKeyFamilies => "The encrypted files were written under different keys",
+        }
+    }
+
+    fn level(self) -> &'static str {
+        match self {
+            Rule::LegacyFormat | Rule::MixedKeyFamilies | Rule::SchemaViolation => "warning",
+            _ => "error",
+        }
+    }
+
+    /// CVSS-style score GitHub sorts security alerts by
+    fn security_severity(self) -> &'static str {
+        match self {
+            Rule::KeyLeak | Rule::PlaintextLeak | Rule::NonceReuse => "9.0",
+            Rule::CommittedLocalKey | Rule::PlaceholderData => "7.5",
+            Rule::Integrity | Rule::TokenMismatch | Rule::MacMismatch => "7.0",
+            Rule::SchemaViolation => "4.0",
+            Rule::LegacyFormat | Rule::MixedKeyFamilies => "3.0",
+        }
+    }
+}
+
+struct Finding {
+    rule: Rule,
+    path: PathBuf,
+    message: String,
+}
+
+/// Findings of the running command; None unless `begin` was called
+static FINDINGS: Mutex<Option<Vec<Finding>>> = Mutex::new(None);
+
+/// Collect findings for the rest of the run
+pub fn begin() {
+    *FINDINGS.lock().unwrap_or_else(|e| e.into_inner()) = Some(Vec::new());
+}
+
+/// Note a finding about `path`, if findings are being collected
+pub fn record(rule: Rule, path: &Path, message: impl fmt::Display) {
+    if let Some(findings) = FINDINGS.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        findings.push(Finding { rule, path: path.to_path_buf(), message: message.to_string() });
+    }
+}
+
+/// Write the collected findings of `command` to `output` as a SARIF log
+pub fn write(output: &Path, command: &str) -> Result<()> {
+    let findings = FINDINGS.lock().unwrap_or_else(|e| e.into_inner()).take().unwrap_or_default();
+    let mut roots = Vec::new();
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let location = artifact_location(&finding.path, &mut roots);
+            json!({
+                "ruleId": finding.rule.id(),
+                "ruleIndex": Rule::ALL.iter().position(|rule| *rule == finding.rule),
+                "level": finding.rule.level(),
+                "message": { "text": finding.message },
+                "locations": [{
+                    "physicalLocation": { "artifactLocation": location, "region": { "startLine": 1 } }
+                }],
+            })
+        })
+        .collect();
+    let rules: Vec<Value> = Rule::ALL
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id(),
+                "name": rule.name(),
+                "shortDescription": { "text": rule.description() },
+                "defaultConfiguration": { "level": rule.level() },
+                "properties": { "tags": ["security"], "security-severity": rule.security_severity() },
+            })
+        })
+        .collect();
+    let mut run = json!({
+        "tool": {
+            "driver": {
+                "name": "violet-cipher",
+                "version": env!("CARGO_PKG_VERSION"),
+                "rules": rules,
+            }
+        },
+        "automationDetails": { "id": format!("violet-cipher/{}/", command) },
+        "results": results,
+    });
+    // One work tree per run in practice; a second root's files fall back to absolute URIs
+    if let Some(root) = roots.first() {
+        run["originalUriBaseIds"] = json!({ SRCROOT: { "uri": file_uri(root, true) } });
+    }
+    let log = json!({ "$schema": SARIF_SCHEMA, "version": "2.1.0", "runs": [run] });
+    write_atomic(output, serde_json::to_string_pretty(&log)?.as_bytes()).with_context(|| format!("write {:?}", output))
+}
+
+/// Repository-relative location of `path` when it is in a git work tree, else an absolute one
+fn artifact_location(path: &Path, roots: &mut Vec<PathBuf>) -> Value {
+    let path = resolve(path);
+    let root = path.parent().and_then(work_tree);
+    if let Some(root) = root {
+        if roots.is_empty() {
+            roots.push(root.clone());
+        }
+        if roots[0] == root {
+            if let Ok(relative) = path.strip_prefix(&root) {
+                let segments: Vec<String> =
+                    relative.components().map(|c| encode(&c.as_os_str().to_string_lossy())).collect();
+                return json!({ "uri": segments.join("/"), "uriBaseId": SRCROOT });
+            }
+        }
+    }
+    json!({ "uri": file_uri(&path, false) })
+}
+
+/// `path` made absolute, through its parent's real path where that exists
+fn resolve(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (absolute.parent().and_then(|parent| fs::canonicalize(parent).ok()), absolute.file_name()) {
+        (Some(parent), Some(name)) => parent.join(name),
+        _ => absolute,
+    }
+}
+
+/// Top of the git work tree `dir` is in
+fn work_tree(dir: &Path) -> Option<PathBuf> {
+    let output = Command::new("git").arg("-C").arg(dir).args(["rev-parse", "--show-toplevel"]).output().ok()?;
+    let top = String::from_utf8(output.stdout).ok().filter(|_| output.status.success())?;
+    fs::canonicalize(top.trim()).ok()
+}
+
+fn file_uri(path: &Path, directory: bool) -> String {
+    let segments: Vec<String> = path
+        .to_string_lossy()
+        .replace('\\', "/")
+        .split('/')
+        .map(encode)
+        .collect();
+    let uri = segments.join("/");
+    let uri = if uri.starts_with('/') { format!("file://{}", uri) } else { format!("file:///{}", uri) };
+    if directory && !uri.ends_with('/') {
+        uri + "/"
+    } else {
+        uri
+    }
+}
+
+/// Percent-encode one path segment for a URI
+fn encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' | b':' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -0,0 +1,147 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Bundled JSON Schemas for the target files, checked by `verify --deep`
+//
+// The schemas in ../schemas are compiled in, so a verify run needs nothing
+// next to the binary. Only the keywords they use are implemented: type,
+// enum, required, properties, additionalProperties, items, minItems,
+// minProperties and minLength; annotations ($schema, title, description)
+// are ignored and any other keyword is an error in the schema itself.
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Value};
+
+const SCHEMAS: &[(&str, &str)] = &[
+    ("rules-index.json", include_str!("../schemas/rules-index.schema.json")),
+    ("minds-index.json", include_str!("../schemas/minds-index.schema.json")),
+    ("vibe-library.json", include_str!("../schemas/vibe-library.schema.json")),
+];
+const ANNOTATIONS: &[&str] = &["$schema", "title", "description"];
+/// Violations reported per file before the rest are summarized
+pub const MAX_REPORTED: usize = 20;
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    let actual = type_name(value);
+    actual == expected || (expected == "number" && actual == "integer")
+}
+
+fn count(schema: &Map<String, Value>, keyword: &str) -> Result<Option<usize>> {
+    schema
+        .get(keyword)
+        .map(|n| n.as_u64().map(|n| n as usize).with_context(|| format!("{} must be a non-negative integer", keyword)))
+        .transpose()
+}
+
+fn pointer_token(pointer: &str, token: &str) -> String {
+    format!("{}/{}", pointer, token.replace('~', "~0").replace('/', "~1"))
+}
+
+/// Append a message for every way `value` breaks `schema`; errors only on a malformed schema
+fn check(schema: &Value, value: &Value, pointer: &str, violations: &mut Vec<String>) -> Result<()> {
+    let at = if pointer.is_empty() { "/" } else { pointer };
+    let schema = match schema {
+        Value::Bool(true) => return Ok(()),
+        Value::Bool(false) => {
+            violations.push(format!("{}: not allowed here", at));
+            return Ok(());
+        }
+        Value::Object(schema) => schema,
+        other => bail!("schema must be an object or boolean, got {}", type_name(other)),
+    };
+
+    for (keyword, rule) in schema {
+        match keyword.as_str() {
+            "type" => {
+                let expected: Vec<&str> = match rule {
+                    Value::String(t) => vec![t.as_str()],
+                    Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                    _ => bail!("type must be a string or array"),
+                };
+                if !expected.iter().any(|t| type_matches(t, value)) {
+                    violations.push(format!("{}: expected {}, found {}", at, expected.join(" or "), type_name(value)));
+                    // Nothing else in this schema applies to a value of the wrong type
+                    return Ok(());
+                }
+            }
+            "enum" => {
+                let allowed = rule.as_array().context("enum must be an array")?;
+                if !allowed.contains(value) {
+                    violations.push(format!("{}: {} is not one of {}", at, value, rule));
+                }
+            }
+            "required" => {
+                let names = rule.as_array().context("required must be an array")?;
+                if let Value::Object(map) = value {
+                    for name in names.iter().filter_map(Value::as_str).filter(|name| !map.contains_key(*name)) {
+                        violations.push(format!("{}: missing required {:?}", at, name));
+                    }
+                }
+            }
+            "properties" => {
+                let properties = rule.as_object().context("properties must be an object")?;
+                if let Value::Object(map) = value {
+                    for (name, child) in map {
+                        if let Some(child_schema) = properties.get(name) {
+                            check(child_schema, child, &pointer_token(pointer, name), violations)?;
+                        }
+                    }
+                }
+            }
+            "additionalProperties" => {
+                let declared = schema.get("properties").and_then(Value::as_object);
+                if let Value::Object(map) = value {
+                    for (name, child) in map.iter().filter(|(name, _)| !declared.is_some_and(|d| d.contains_key(*name))) {
+                        check(rule, child, &pointer_token(pointer, name), violations)?;
+                    }
+                }
+            }
+            "items" => {
+                if let Value::Array(items) = value {
+                    for (index, item) in items.iter().enumerate() {
+                        check(rule, item, &pointer_token(pointer, &index.to_string()), violations)?;
+                    }
+                }
+            }
+            "minItems" | "minProperties" | "minLength" => {
+                let min = count(schema, keyword)?.unwrap_or(0);
+                let (len, what) = match (keyword.as_str(), value) {
+                    ("minItems", Value::Array(items)) => (items.len(), "item(s)"),
+                    ("minProperties", Value::Object(map)) => (map.len(), "propert(ies)"),
+                    ("minLength", Value::String(s)) => (s.chars().count(), "character(s)"),
+                    _ => continue,
+                };
+                if len < min {
+                    violations.push(format!("{}: {} {}, at least {} required", at, len, what, min));
+                }
+            }
+            annotation if ANNOTATIONS.contains(&annotation) => {}
+            unsupported => bail!("schema keyword {:?} is not supported", unsupported),
+        }
+    }
+    Ok(())
+}
+
+/// Schema violations of a decrypted target file; None when no schema is bundled for `name`
+pub fn validate(name: &str, plaintext: &[u8]) -> Result<Option<Vec<String>>> {
+    let Some((_, text)) = SCHEMAS.iter().find(|(target, _)| *target == name) else {
+        return Ok(None);
+    };
+    let schema: Value = serde_json::from_str(text).with_context(|| format!("bundled schema for {}", name))?;
+    let document: Value = match serde_json::from_slice(plaintext) {
+        Ok(document) => document,
+        Err(e) => return Ok(Some(vec![format!("not JSON: {}", e)])),
+    };
+    let mut violations = Vec::new();
+    check(&schema, &document, "", &mut violations).with_context(|| format!("bundled schema for {}", name))?;
+    Ok(Some(violations))
+}
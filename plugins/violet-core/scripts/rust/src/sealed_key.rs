@@ -0,0 +1,157 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Hardware-sealed passphrase — `key seal`, opened again by --sealed-key
+//
+// The passphrase is sealed to this machine's TPM 2.0 as a keyed-hash data
+// object under the owner hierarchy's storage primary, which the TPM
+// re-derives from its internal seed on every use. The file keeps only the
+// sealed object's public and private blobs: another machine's TPM (or none)
+// cannot load them, so a copied disk or backup is no help. `--pcrs` also
+// binds the object to boot-state PCRs, so it stops opening after firmware or
+// boot-chain changes until it is sealed again.
+//
+// The TPM is driven through tpm2-tools (tpm2_createprimary, tpm2_create,
+// tpm2_load, tpm2_unseal), which honour TPM2TOOLS_TCTI; the passphrase only
+// crosses their stdin and stdout. macOS Secure Enclave keys are not offered:
+// they can only be kept by a signed binary with a keychain entitlement.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::atomic::write_secret_new;
+use crate::key_source::Passphrase;
+use crate::tool;
+use crate::{random_bytes, verify_token};
+
+pub const SEALED_KEY_FILE: &str = ".violet-sealed-key";
+const SEALED_KEY_VERSION: u32 = 1;
+const BACKEND_TPM2: &str = "tpm2";
+
+#[derive(Serialize, Deserialize)]
+struct SealedKey {
+    version: u32,
+    backend: String,
+    /// Key ID of the sealed passphrase, so `key seal` output can be matched to the keyring
+    /// (none while the data directory has no key-ID salt)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key_id: Option<String>,
+    /// PCR selection the object is bound to, e.g. `sha256:0,7`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pcrs: Option<String>,
+    /// Hex TPM2B_PUBLIC and TPM2B_PRIVATE of the sealed object
+    public: String,
+    private: String,
+}
+
+/// Scratch directory (0700) for TPM context files, removed on drop
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new() -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("violet-tpm-{}", hex::encode(random_bytes::<8>())));
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(&path).with_context(|| format!("create {:?}", path))?;
+        Ok(Self(path))
+    }
+
+    fn file(&self, name: &str) -> String {
+        self.0.join(name).display().to_string()
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Run a tpm2-tools program with `input` on stdin; returns its stdout
+fn tpm2(program: &str, args: &[&str], input: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    tool::run(program, args, input, "tpm2-tools")
+}
+
+/// The owner hierarchy's storage primary, recreated identically on every call
+fn create_primary(scratch: &Scratch) -> Result<String> {
+    let primary = scratch.file("primary.ctx");
+    tpm2("tpm2_createprimary", &["-Q", "-C", "o", "-g", "sha256", "-G", "ecc", "-c", &primary], b"")?;
+    Ok(primary)
+}
+
+fn check_platform() -> Result<()> {
+    if cfg!(target_os = "macos") {
+        bail!("Secure Enclave sealing needs a signed binary with a keychain entitlement; use --key-file on macOS");
+    }
+    Ok(())
+}
+
+/// Seal `passphrase` to this machine's TPM, optionally bound to `pcrs` (e.g. `sha256:0,7`)
+fn seal_tpm2(passphrase: &[u8], pcrs: Option<&str>) -> Result<(String, String)> {
+    let scratch = Scratch::new()?;
+    let primary = create_primary(&scratch)?;
+    let (public, private) = (scratch.file("seal.pub"), scratch.file("seal.priv"));
+    let mut args = vec!["-Q", "-C", &primary, "-g", "sha256", "-u", &public, "-r", &private, "-i", "-"];
+    let policy = scratch.file("pcr.policy");
+    if let Some(pcrs) = pcrs {
+        tpm2("tpm2_createpolicy", &["-Q", "--policy-pcr", "-l", pcrs, "-L", &policy], b"")?;
+        args.extend(["-L", &policy]);
+    }
+    tpm2("tpm2_create", &args, passphrase)?;
+    let read = |path: &str| fs::read(path).map(hex::encode).with_context(|| format!("read {}", path));
+    Ok((read(&public)?, read(&private)?))
+}
+
+fn unseal_tpm2(sealed: &SealedKey) -> Result<Passphrase> {
+    let scratch = Scratch::new()?;
+    let primary = create_primary(&scratch)?;
+    let (public, private, object) = (scratch.file("seal.pub"), scratch.file("seal.priv"), scratch.file("seal.ctx"));
+    fs::write(&public, hex::decode(&sealed.public).context("sealed public blob is not hex")?)?;
+    fs::write(&private, hex::decode(&sealed.private).context("sealed private blob is not hex")?)?;
+    tpm2("tpm2_load", &["-Q", "-C", &primary, "-u", &public, "-r", &private, "-c", &object], b"")
+        .context("the TPM will not load this sealed key — it was sealed on another machine")?;
+    let auth = sealed.pcrs.as_ref().map(|pcrs| format!("pcr:{}", pcrs));
+    let mut args = vec!["-c", object.as_str()];
+    if let Some(auth) = &auth {
+        args.extend(["-p", auth.as_str()]);
+    }
+    tpm2("tpm2_unseal", &args, b"").context("the TPM refused to unseal — boot state (PCRs) changed since `key seal`?")
+}
+
+/// Seal `passphrase` into a new file at `output`; returns its key ID, if it has one
+pub fn seal(passphrase: &[u8], output: &Path, pcrs: Option<&str>) -> Result<Option<String>> {
+    check_platform()?;
+    let key_id = verify_token::salted_key_id(passphrase)?;
+    let (public, private) = seal_tpm2(passphrase, pcrs)?;
+    let sealed = SealedKey {
+        version: SEALED_KEY_VERSION,
+        backend: BACKEND_TPM2.to_string(),
+        key_id: key_id.clone(),
+        pcrs: pcrs.map(str::to_string),
+        public,
+        private,
+    };
+    // Unseal once before writing, so a file that cannot be opened is never left behind
+    if *unseal_tpm2(&sealed)? != passphrase {
+        bail!("the TPM returned a different secret than was sealed");
+    }
+    write_secret_new(output, serde_json::to_string_pretty(&sealed)?.as_bytes())?;
+    Ok(key_id)
+}
+
+/// The passphrase sealed in `path`, unsealed by this machine's TPM
+pub fn unseal(path: &Path) -> Result<Passphrase> {
+    check_platform()?;
+    let json = fs::read_to_string(path).with_context(|| format!("read sealed key {:?}", path))?;
+    let sealed: SealedKey = serde_json::from_str(&json).with_context(|| format!("parse sealed key {:?}", path))?;
+    if sealed.version != SEALED_KEY_VERSION || sealed.backend != BACKEND_TPM2 {
+        bail!("sealed key {:?} is version {} for {} — upgrade violet-cipher", path, sealed.version, sealed.backend);
+    }
+    unseal_tpm2(&sealed).with_context(|| format!("unseal {:?}", path))
+}
+
+pub fn default_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(SEALED_KEY_FILE)
+}
@@ -0,0 +1,180 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Locked secrets — zeroized, page-locked heap buffers for key material
+//
+// Layer keys and the passphrase-plus-seed KDF input live in `Secret`
+// buffers. Each one sits in its own heap allocation, which is locked into RAM
+// (mlock / VirtualLock) so it is never written to swap, and is zeroized on
+// drop before its pages are unlocked. Locking is best effort: past
+// RLIMIT_MEMLOCK the buffer is still zeroized, just not pinned. Pages shared
+// by several secrets are reference-counted, so dropping one never unlocks
+// another. Buffers are created zeroed and filled in place, so no unlocked
+// copy is left behind on the stack.
+//
+// Debug builds also count live secrets; `assert_released` runs after every
+// command and fails if any key outlived the scope that derived it.
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+use zeroize::Zeroize;
+
+use crate::KEY_LEN;
+
+/// A derived 256-bit key
+pub type SecretKey = Secret<[u8; KEY_LEN]>;
+
+/// Locked page → how many live secrets touch it
+static LOCKED_PAGES: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+#[cfg(debug_assertions)]
+static LIVE: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+pub struct Secret<T: Zeroize + AsRef<[u8]>> {
+    inner: Box<T>,
+}
+
+impl<T: Zeroize + AsRef<[u8]>> Secret<T> {
+    fn locked(inner: Box<T>) -> Self {
+        let bytes: &[u8] = (*inner).as_ref();
+        lock(bytes.as_ptr() as usize, bytes.len());
+        #[cfg(debug_assertions)]
+        LIVE.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Self { inner }
+    }
+}
+
+impl<const N: usize> Secret<[u8; N]> {
+    pub fn zeroed() -> Self {
+        Self::locked(Box::new([0u8; N]))
+    }
+}
+
+impl Secret<Vec<u8>> {
+    /// A locked buffer of `len` zero bytes; its length never changes, so it is never reallocated
+    pub fn zeroed(len: usize) -> Self {
+        Self::locked(Box::new(vec![0u8; len]))
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.inner.as_mut_slice()
+    }
+}
+
+impl<T: Zeroize + AsRef<[u8]>> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<const N: usize> DerefMut for Secret<[u8; N]> {
+    fn deref_mut(&mut self) -> &mut [u8; N] {
+        &mut self.inner
+    }
+}
+
+impl<T: Zeroize + AsRef<[u8]>> Drop for Secret<T> {
+    fn drop(&mut self) {
+        // Zeroizing a Vec also empties it, so note where it was first
+        let bytes: &[u8] = (*self.inner).as_ref();
+        let (addr, len) = (bytes.as_ptr() as usize, bytes.len());
+        self.inner.zeroize();
+        let bytes: &[u8] = (*self.inner).as_ref();
+        debug_assert!(bytes.iter().all(|&b| b == 0), "secret not zeroized on drop");
+        unlock(addr, len);
+        #[cfg(debug_assertions)]
+        LIVE.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+/// Debug builds: fail if any secret is still alive (call once a command is done)
+pub fn assert_released() {
+    #[cfg(debug_assertions)]
+    {
+        let live = LIVE.load(std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(live, 0, "{} secret buffer(s) outlived their command", live);
+    }
+}
+
+fn pages(addr: usize, len: usize) -> impl Iterator<Item = usize> {
+    let size = os::page_size();
+    let first = addr / size;
+    let last = if len == 0 { first } else { (addr + len - 1) / size + 1 };
+    (first..last).map(move |page| page * size)
+}
+
+fn lock(addr: usize, len: usize) {
+    let mut locked = LOCKED_PAGES.lock().unwrap_or_else(|e| e.into_inner());
+    for page in pages(addr, len) {
+        let count = locked.entry(page).or_insert(0);
+        if *count == 0 {
+            os::lock(page);
+        }
+        *count += 1;
+    }
+}
+
+fn unlock(addr: usize, len: usize) {
+    let mut locked = LOCKED_PAGES.lock().unwrap_or_else(|e| e.into_inner());
+    for page in pages(addr, len) {
+        if let Some(count) = locked.get_mut(&page) {
+            *count -= 1;
+            if *count == 0 {
+                locked.remove(&page);
+                os::unlock(page);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+mod os {
+    pub fn page_size() -> usize {
+        // SAFETY: sysconf has no preconditions
+        match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+            size if size > 0 => size as usize,
+            _ => 4096,
+        }
+    }
+
+    pub fn lock(page: usize) {
+        // SAFETY: `page` is the start of a page of a live heap allocation; failure only leaves it unlocked
+        unsafe { libc::mlock(page as *const libc::c_void, page_size()) };
+    }
+
+    pub fn unlock(page: usize) {
+        // SAFETY: as for `lock`; the allocation is still live while its secret is dropped
+        unsafe { libc::munlock(page as *const libc::c_void, page_size()) };
+    }
+}
+
+#[cfg(windows)]
+mod os {
+    use windows_sys::Win32::System::Memory::{VirtualLock, VirtualUnlock};
+
+    pub fn page_size() -> usize {
+        4096
+    }
+
+    pub fn lock(page: usize) {
+        // SAFETY: `page` is the start of a page of a live heap allocation; failure only leaves it unlocked
+        unsafe { VirtualLock(page as *const core::ffi::c_void, page_size()) };
+    }
+
+    pub fn unlock(page: usize) {
+        // SAFETY: as for `lock`; the allocation is still live while its secret is dropped
+        unsafe { VirtualUnlock(page as *const core::ffi::c_void, page_size()) };
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod os {
+    pub fn page_size() -> usize {
+        4096
+    }
+
+    pub fn lock(_page: usize) {}
+
+    pub fn unlock(_page: usize) {}
+}
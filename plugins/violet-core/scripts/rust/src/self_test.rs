@@ -0,0 +1,267 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Self-test — known-answer checks of every primitive and legacy pipeline in this build
+//
+// `self-test` runs published vectors through the libraries the cipher is
+// built on (AES-256-GCM from the GCM specification, ChaCha20-Poly1305 from
+// RFC 8439, XChaCha20-Poly1305 from draft-irtf-cfrg-xchacha, AES-256-GCM-SIV
+// from RFC 8452, AES-256-CBC from SP 800-38A, Argon2id from RFC 9106, scrypt
+// from RFC 7914), then
+// decrypts fixed v2, v3 and v4 samples made by `test-vectors --seed 0` and
+// checks a fresh v4 round trip. A new build, compiler or platform that
+// passes decrypts existing files exactly as the old one did; one that fails
+// must not be trusted with real data.
+use aes::cipher::{block_padding::NoPadding, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes_gcm::{aead::{Aead, Payload}, Aes256Gcm, KeyInit, Nonce as GcmNonce};
+use aes_gcm_siv::Aes256GcmSiv;
+use anyhow::{bail, Context, Result};
+use argon2::{Algorithm, Argon2, AssociatedData, ParamsBuilder, Version};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce as ChaChaNonce, XChaCha20Poly1305, XNonce};
+use tracing::{error, info};
+
+use crate::{
+    decrypt_payload, v2_decrypt, v3_decrypt, v4_decrypt, v4_encrypt, Aes256CbcDec, Aes256CbcEnc, Binding, KdfParams,
+    LOCAL_SALT,
+};
+
+/// The `test-vectors` defaults the pipeline samples were made with
+const PASSPHRASE: &[u8] = b"violet-test-vector-passphrase";
+const PLAINTEXT: &[u8] = br#"{"violet":"test vector","layers":3}"#;
+const NAME: &str = "rules-index.json";
+/// Argon2id costs of the v4 sample (memory KiB, iterations, lanes)
+const V4_KDF: (u32, u32, u32) = (8192, 1, 1);
+
+const V2_SAMPLE: &str = "935562ca8611e1fbd96133b744b946c9e209f3221b186bb16338daa06199aec0\
+                         9def3942c81632bcd034a38178b89c63bf667846229918a46c01023262e7849d";
+const V3_SAMPLE: &str = "3c9be35c1125d22c0704c9380ce8fcfb65e84b4d98541d07c2dd5e50f818e2e0\
+                         87857674e71a138766275a1f189d63d40fd2600b4e7ef58580c14edc3ae84368\
+                         d205edcaf3c61b88466cd6a219445c8007810ae8c39aa20f6c15f7e1d3e187ff";
+const V4_SAMPLE: &str = "56534321040100020000200000000001000000010000000000000000000000d7\
+                         181d20d27dd8a20d32dc1e3b0b5c402cd813d96764c1b7cafe72431dc54348e6\
+                         d084cb2f68e4c01e760673d9ae1ddd1ed4c70e610ec094f438925438c387624b\
+                         4543a4cbc32809675799d08a909de25f16a59ea25b4f26f5bfb092a49c4ac332\
+                         1e76aef11c8c1c2b89945c7951c6457d39a335d9a69865af21cb278af07dabcb\
+                         2dd89000705afbed828ff3cf1229759430533263ce04fcf95a198858f9207374\
+                         0ea67df5902c32724c6ad34570f49dfe41567d43d69462f20133fa451f9ece6a\
+                         7e69b97ffff72c99937b9a9d956394cf20707c877bcaec0ca31bc3d5ad603d5b\
+                         a5e088468826cb05d2117498ae6d24d869609a56c311c9";
+
+/// One known-answer check; Err says what came out wrong
+type Check = fn() -> Result<()>;
+
+fn unhex(text: &str) -> Vec<u8> {
+    hex::decode(text).expect("self-test vector is hex")
+}
+
+/// `actual` must equal the hex `expected`
+fn expect(what: &str, actual: &[u8], expected: &str) -> Result<()> {
+    if actual != unhex(expected).as_slice() {
+        bail!("{} is {}, expected {}", what, hex::encode(actual), expected);
+    }
+    Ok(())
+}
+
+/// GCM specification test case 16: AES-256, 96-bit IV, with associated data
+fn aes_gcm() -> Result<()> {
+    let key = unhex("feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308");
+    let nonce = unhex("cafebabefacedbaddecaf888");
+    let aad = unhex("feedfacedeadbeeffeedfacedeadbeefabaddad2");
+    let plaintext = unhex(
+        "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+         1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+    );
+    let sealed = "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+                  8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662\
+                  76fc6ece0f4e1768cddf8853bb2d551b";
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| anyhow::anyhow!("AES-GCM init: {}", e))?;
+    let nonce = GcmNonce::from_slice(&nonce);
+    let ciphertext = cipher
+        .encrypt(nonce, Payload { msg: &plaintext, aad: &aad })
+        .map_err(|e| anyhow::anyhow!("AES-GCM encrypt: {}", e))?;
+    expect("ciphertext ‖ tag", &ciphertext, sealed)?;
+    let opened = cipher
+        .decrypt(nonce, Payload { msg: &ciphertext, aad: &aad })
+        .map_err(|e| anyhow::anyhow!("AES-GCM decrypt: {}", e))?;
+    expect("plaintext", &opened, &hex::encode(&plaintext))
+}
+
+/// RFC 8439 §2.8.2 AEAD example
+fn chacha20_poly1305() -> Result<()> {
+    let key: Vec<u8> = (0x80..=0x9f).collect();
+    let nonce = unhex("070000004041424344454647");
+    let aad = unhex("50515253c0c1c2c3c4c5c6c7");
+    let plaintext: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip \
+                             for the future, sunscreen would be it.";
+    let sealed = "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6\
+                  3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36\
+                  92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc\
+                  3ff4def08e4b7a9de576d26586cec64b6116\
+                  1ae10b594f09e26a7e902ecbd0600691";
+    let cipher = ChaCha20Poly1305::new_from_slice(&key).map_err(|e| anyhow::anyhow!("ChaCha20 init: {}", e))?;
+    let nonce = ChaChaNonce::from_slice(&nonce);
+    let ciphertext = cipher
+        .encrypt(nonce, Payload { msg: plaintext, aad: &aad })
+        .map_err(|e| anyhow::anyhow!("ChaCha20 encrypt: {}", e))?;
+    expect("ciphertext ‖ tag", &ciphertext, sealed)?;
+    let opened = cipher
+        .decrypt(nonce, Payload { msg: &ciphertext, aad: &aad })
+        .map_err(|e| anyhow::anyhow!("ChaCha20 decrypt: {}", e))?;
+    expect("plaintext", &opened, &hex::encode(plaintext))
+}
+
+/// draft-irtf-cfrg-xchacha-03 §A.3.1 AEAD example
+fn xchacha20_poly1305() -> Result<()> {
+    let key: Vec<u8> = (0x80..=0x9f).collect();
+    let nonce: Vec<u8> = (0x40..=0x57).collect();
+    let aad = unhex("50515253c0c1c2c3c4c5c6c7");
+    let plaintext: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip \
+                             for the future, sunscreen would be it.";
+    let sealed = "bd6d179d3e83d43b9576579493c0e939572a1700252bfaccbed2902c21396cbb\
+                  731c7f1b0b4aa6440bf3a82f4eda7e39ae64c6708c54c216cb96b72e1213b452\
+                  2f8c9ba40db5d945b11b69b982c1bb9e3f3fac2bc369488f76b2383565d3fff9\
+                  21f9664c97637da9768812f615c68b13b52e\
+                  c0875924c1c7987947deafd8780acf49";
+    let cipher = XChaCha20Poly1305::new_from_slice(&key).map_err(|e| anyhow::anyhow!("XChaCha20 init: {}", e))?;
+    let nonce = XNonce::from_slice(&nonce);
+    let ciphertext = cipher
+        .encrypt(nonce, Payload { msg: plaintext, aad: &aad })
+        .map_err(|e| anyhow::anyhow!("XChaCha20 encrypt: {}", e))?;
+    expect("ciphertext ‖ tag", &ciphertext, sealed)?;
+    let opened = cipher
+        .decrypt(nonce, Payload { msg: &ciphertext, aad: &aad })
+        .map_err(|e| anyhow::anyhow!("XChaCha20 decrypt: {}", e))?;
+    expect("plaintext", &opened, &hex::encode(plaintext))
+}
+
+/// RFC 8452 Appendix C.2: AEAD_AES_256_GCM_SIV, an 8-byte plaintext
+fn aes_gcm_siv() -> Result<()> {
+    let key = unhex("0100000000000000000000000000000000000000000000000000000000000000");
+    let nonce = unhex("030000000000000000000000");
+    let plaintext = unhex("0100000000000000");
+    let sealed = "c2ef328e5c71c83b843122130f7364b761e0b97427e3df28";
+    let cipher = Aes256GcmSiv::new_from_slice(&key).map_err(|e| anyhow::anyhow!("AES-GCM-SIV init: {}", e))?;
+    let nonce = GcmNonce::from_slice(&nonce);
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext.as_slice())
+        .map_err(|e| anyhow::anyhow!("AES-GCM-SIV encrypt: {}", e))?;
+    expect("ciphertext ‖ tag", &ciphertext, sealed)?;
+    let opened = cipher
+        .decrypt(nonce, ciphertext.as_slice())
+        .map_err(|e| anyhow::anyhow!("AES-GCM-SIV decrypt: {}", e))?;
+    expect("plaintext", &opened, &hex::encode(&plaintext))
+}
+
+/// SP 800-38A F.2.5/F.2.6: CBC-AES256, four blocks
+fn aes_cbc() -> Result<()> {
+    let key = unhex("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4");
+    let iv = unhex("000102030405060708090a0b0c0d0e0f");
+    let plaintext = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+                     30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+    let ciphertext = "f58c4c04d6e5f1ba779eabfb5f7bfbd69cfc4e967edb808d679f777bc6702c7d\
+                      39f23369a9d9bacfa530e26304231461b2eb05e2c39be9fcda6c19078c6a9d1b";
+    let mut buf = unhex(plaintext);
+    let len = buf.len();
+    let encrypted = Aes256CbcEnc::new_from_slices(&key, &iv)
+        .context("CBC init")?
+        .encrypt_padded_mut::<NoPadding>(&mut buf, len)
+        .map_err(|e| anyhow::anyhow!("AES-CBC encrypt: {}", e))?;
+    expect("ciphertext", encrypted, ciphertext)?;
+    let mut buf = unhex(ciphertext);
+    let decrypted = Aes256CbcDec::new_from_slices(&key, &iv)
+        .context("CBC init")?
+        .decrypt_padded_mut::<NoPadding>(&mut buf)
+        .map_err(|e| anyhow::anyhow!("AES-CBC decrypt: {}", e))?;
+    expect("plaintext", decrypted, plaintext)
+}
+
+/// RFC 9106 §5.3: Argon2id with secret and associated data
+fn argon2id() -> Result<()> {
+    let params = ParamsBuilder::new()
+        .m_cost(32)
+        .t_cost(3)
+        .p_cost(4)
+        .output_len(32)
+        .data(AssociatedData::new(&[0x04; 12]).map_err(|e| anyhow::anyhow!("Argon2 data: {}", e))?)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Argon2 params: {}", e))?;
+    let argon2 = Argon2::new_with_secret(&[0x03; 8], Algorithm::Argon2id, Version::V0x13, params)
+        .map_err(|e| anyhow::anyhow!("Argon2 init: {}", e))?;
+    let mut tag = [0u8; 32];
+    argon2
+        .hash_password_into(&[0x01; 32], &[0x02; 16], &mut tag)
+        .map_err(|e| anyhow::anyhow!("Argon2id: {}", e))?;
+    expect("tag", &tag, "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659")
+}
+
+/// RFC 7914 §12, second vector: N = 1024, r = 8, p = 16
+fn scrypt() -> Result<()> {
+    let params = scrypt::Params::new(10, 8, 16, 64).map_err(|e| anyhow::anyhow!("scrypt params: {}", e))?;
+    let mut out = [0u8; 64];
+    scrypt::scrypt(b"password", b"NaCl", &params, &mut out).map_err(|e| anyhow::anyhow!("scrypt: {}", e))?;
+    expect(
+        "derived key",
+        &out,
+        "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+         2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640",
+    )
+}
+
+fn v4_kdf() -> Result<KdfParams> {
+    let (memory, iterations, lanes) = V4_KDF;
+    KdfParams::new(memory, iterations, lanes, None).map_err(|e| anyhow::anyhow!("Argon2id params: {}", e))
+}
+
+fn v2_pipeline() -> Result<()> {
+    expect("plaintext", &v2_decrypt(PASSPHRASE, &unhex(V2_SAMPLE))?, &hex::encode(PLAINTEXT))
+}
+
+fn v3_pipeline() -> Result<()> {
+    expect("plaintext", &v3_decrypt(PASSPHRASE, LOCAL_SALT, &unhex(V3_SAMPLE))?, &hex::encode(PLAINTEXT))
+}
+
+fn v4_pipeline() -> Result<()> {
+    let opened = v4_decrypt(PASSPHRASE, Binding::new(LOCAL_SALT, NAME), &unhex(V4_SAMPLE), &v4_kdf()?)?;
+    expect("plaintext", &opened, &hex::encode(PLAINTEXT))
+}
+
+/// Encrypt under fresh salts and nonces, then open through the auto-detecting reader
+fn v4_round_trip() -> Result<()> {
+    let (binding, kdf) = (Binding::new(LOCAL_SALT, NAME), v4_kdf()?);
+    let sealed = v4_encrypt(PASSPHRASE, binding, PLAINTEXT, &kdf)?;
+    let (_, opened) = decrypt_payload(PASSPHRASE, binding, &sealed, &kdf)?;
+    expect("plaintext", &opened, &hex::encode(PLAINTEXT))
+}
+
+const CHECKS: &[(&str, Check)] = &[
+    ("AES-256-GCM (GCM spec test case 16)", aes_gcm),
+    ("ChaCha20-Poly1305 (RFC 8439 §2.8.2)", chacha20_poly1305),
+    ("XChaCha20-Poly1305 (draft-irtf-cfrg-xchacha §A.3.1)", xchacha20_poly1305),
+    ("AES-256-GCM-SIV (RFC 8452 C.2)", aes_gcm_siv),
+    ("AES-256-CBC (SP 800-38A F.2.5)", aes_cbc),
+    ("Argon2id (RFC 9106 §5.3)", argon2id),
+    ("scrypt (RFC 7914 §12)", scrypt),
+    ("v2 pipeline: scrypt → AES-CBC", v2_pipeline),
+    ("v3 pipeline: scrypt → AES-CBC × 2", v3_pipeline),
+    ("v4 pipeline: Argon2id → AES-GCM → ChaCha20-Poly1305 → AES-GCM", v4_pipeline),
+    ("v4 round trip under fresh salts", v4_round_trip),
+];
+
+/// Run every check; returns how many failed
+pub fn run() -> Result<u32> {
+    info!("🧪 violet-cipher {} self-test", env!("CARGO_PKG_VERSION"));
+    let mut failures = 0u32;
+    for (name, check) in CHECKS {
+        match check() {
+            Ok(()) => info!("  ✅ {}", name),
+            Err(e) => {
+                error!("  ❌ {} — {:#}", name, e);
+                failures += 1;
+            }
+        }
+    }
+    if failures > 0 {
+        info!("🧪 {} check(s) failed — do not trust this build with real data.", failures);
+    } else {
+        info!("🧪 All checks passed.");
+    }
+    Ok(failures)
+}
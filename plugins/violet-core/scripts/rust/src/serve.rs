@@ -0,0 +1,267 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Serve — JSON-RPC 2.0 over stdio for editor plugins, keys kept for the session
+//
+// `serve --stdio` reads one JSON-RPC request per line on stdin and writes one
+// response per line on stdout (notifications, requests without an id, get
+// none). It ends at EOF or on `shutdown`.
+//
+//   decrypt {target, profile?}        → {target, content_type, text} or {…, data: hex} for binary
+//   encrypt {target, text, profile?}  → {target, output, bytes}: the target's .enc rewritten
+//   status  {profile?}                → {data_dir, key_id, profile, targets: [{target, output, version}]}
+//
+// The passphrase comes from the usual flags or environment, never from the
+// protocol. Derived keys stay in the key cache for the whole session, so only
+// the first open of a file pays for Argon2id. Each encrypt draws fresh salts,
+// unless the session was started with --fast: then, like the unlock agent, it
+// seals every file with one salt per layer and salt label, so a file just
+// encrypted opens again without a derivation (see key_cache.rs for what
+// sharing salts costs). Each encrypt takes the data directory lock only for
+// its write, so other commands still run while an editor holds a session.
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use argon2::Params as KdfParams;
+use clap::Args;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::info;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::header::{Compression, ContentType, Header};
+use crate::kdf::KdfArgs;
+use crate::key_source::KeyArgs;
+use crate::preamble::{self, Suite};
+use crate::profile::{self, Profile};
+use crate::{
+    decrypt_payload, key_cache, keyring, lock, logging, recovery, resolve_data_dir, seal_local, token_key_id,
+    verify_token, write_local_enc, Binding, Sealer,
+};
+
+/// JSON-RPC error codes: the spec's, and one for a method that ran and failed
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_FAILED: i64 = -32000;
+
+#[derive(Args)]
+pub struct ServeArgs {
+    #[command(flatten)]
+    key: KeyArgs,
+    #[command(flatten)]
+    kdf: KdfArgs,
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+    /// Speak newline-delimited JSON-RPC on stdin/stdout (the only transport)
+    #[arg(long, required = true)]
+    stdio: bool,
+    /// zstd-compress what `encrypt` seals
+    #[arg(long)]
+    compress: bool,
+    /// Algorithms of the three layers `encrypt` seals with
+    #[arg(long, value_enum, default_value_t)]
+    suite: Suite,
+    /// Also write a .vtok verification token next to each .enc `encrypt` writes
+    #[arg(long)]
+    verify_token: bool,
+    /// Seal every file `encrypt` writes with the same layer salts, so reopening them derives no keys
+    #[arg(long)]
+    fast: bool,
+}
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    jsonrpc: Option<String>,
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TargetParams {
+    target: String,
+    #[serde(default)]
+    profile: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EncryptParams {
+    target: String,
+    text: String,
+    #[serde(default)]
+    profile: Option<String>,
+}
+
+impl Drop for EncryptParams {
+    fn drop(&mut self) {
+        self.text.zeroize();
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct StatusParams {
+    #[serde(default)]
+    profile: Option<String>,
+}
+
+/// A method's failure, with the JSON-RPC code to report it under
+struct Failure(i64, String);
+
+impl From<anyhow::Error> for Failure {
+    fn from(e: anyhow::Error) -> Self {
+        Failure(METHOD_FAILED, format!("{:#}", e))
+    }
+}
+
+fn params<T: for<'de> Deserialize<'de>>(value: Value) -> Result<T, Failure> {
+    let value = if value.is_null() { json!({}) } else { value };
+    serde_json::from_value(value).map_err(|e| Failure(INVALID_PARAMS, format!("invalid params: {}", e)))
+}
+
+/// Everything the session keeps between requests
+struct Session {
+    sealer: Sealer,
+    kdf: KdfParams,
+    data_dir: PathBuf,
+    header: Header,
+    key_id: String,
+    token_key_id: Option<String>,
+}
+
+impl Session {
+    fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        profile::load(&self.data_dir, name)
+    }
+
+    fn passphrase(&self) -> &[u8] {
+        match &self.sealer {
+            Sealer::Passphrase(key) => key,
+            _ => unreachable!("serve seals with its passphrase"),
+        }
+    }
+
+    fn decrypt(&self, params: TargetParams) -> Result<Value> {
+        let profile = self.profile(params.profile.as_deref())?;
+        let target = profile.select(std::slice::from_ref(&params.target))?[0];
+        let path = self.data_dir.join(profile.output(target));
+        let data = fs::read(&path).with_context(|| format!("read {:?}", path))?;
+        let binding = Binding::new(&profile.salt, target);
+        let (header, plain) = decrypt_payload(self.passphrase(), binding, &data, &self.kdf)?;
+        let plain = Zeroizing::new(plain);
+        let content_type = header.content_type.as_str();
+        if header.content_type.is_text() {
+            let text = std::str::from_utf8(&plain).context("text payload is not UTF-8")?;
+            Ok(json!({ "target": target, "content_type": content_type, "text": text }))
+        } else {
+            Ok(json!({ "target": target, "content_type": content_type, "data": hex::encode(&*plain) }))
+        }
+    }
+
+    fn encrypt(&self, params: EncryptParams) -> Result<Value> {
+        let profile = self.profile(params.profile.as_deref())?;
+        let target = profile.select(std::slice::from_ref(&params.target))?[0];
+        let plaintext = params.text.as_bytes();
+        let encrypted = seal_local(&self.sealer, &profile.salt, target, plaintext, &self.header, &self.kdf)?;
+        let output = profile.output(target);
+        let _lock = lock::acquire(&self.data_dir, "serve")?;
+        write_local_enc(&self.data_dir.join(&output), &encrypted, self.token_key_id.as_deref(), None)?;
+        keyring::record(&self.data_dir, &self.key_id, &self.kdf)?;
+        info!("  ✅ {} → {} ({} bytes)", target, output, encrypted.len());
+        Ok(json!({ "target": target, "output": output, "bytes": encrypted.len() }))
+    }
+
+    fn status(&self, params: StatusParams) -> Result<Value> {
+        let profile = self.profile(params.profile.as_deref())?;
+        let targets: Vec<Value> = profile
+            .targets
+            .iter()
+            .map(|target| {
+                let output = profile.output(target);
+                let version = fs::read(self.data_dir.join(&output)).ok().and_then(|data| preamble::version(&data));
+                json!({ "target": target, "output": output, "version": version })
+            })
+            .collect();
+        Ok(json!({
+            "data_dir": self.data_dir,
+            "key_id": self.key_id,
+            "profile": profile.name,
+            "targets": targets,
+        }))
+    }
+
+    /// The response to `line` (None for a notification) and whether it ends the session
+    fn respond(&self, line: &str) -> (Option<Value>, bool) {
+        let request: Request = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return (Some(error(Value::Null, PARSE_ERROR, format!("parse error: {}", e))), false),
+        };
+        let id = request.id.clone();
+        if request.jsonrpc.as_deref() != Some("2.0") {
+            return (Some(error(id.unwrap_or_default(), INVALID_REQUEST, "jsonrpc must be \"2.0\"".into())), false);
+        }
+        let shutdown = request.method == "shutdown";
+        let result = match request.method.as_str() {
+            "decrypt" => params(request.params).and_then(|p| Ok(self.decrypt(p)?)),
+            "encrypt" => params(request.params).and_then(|p| Ok(self.encrypt(p)?)),
+            "status" => params(request.params).and_then(|p| Ok(self.status(p)?)),
+            "shutdown" => Ok(Value::Null),
+            method => Err(Failure(METHOD_NOT_FOUND, format!("method not found: {}", method))),
+        };
+        let response = id.map(|id| match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(Failure(code, message)) => error(id, code, message),
+        });
+        (response, shutdown)
+    }
+}
+
+fn error(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Answer requests on stdin until EOF or `shutdown`
+pub fn run(args: ServeArgs) -> Result<()> {
+    logging::claim_stdout();
+    let data_dir = resolve_data_dir(args.data_dir);
+    recovery::load(&data_dir)?;
+    let key = args.key.resolve()?;
+    let kdf = args.kdf.params()?;
+    verify_token::begin(&data_dir, true);
+    verify_token::ensure_salt(&kdf)?;
+    let key_id = verify_token::derive_key_id(&key)?;
+    let token_key_id = token_key_id(&key, args.verify_token)?;
+    let header = Header::new(ContentType::json(), Compression::from_flag(args.compress)).with_suite(args.suite);
+    let session = Session { sealer: Sealer::Passphrase(key), kdf, data_dir, header, key_id, token_key_id };
+    if args.fast {
+        key_cache::share_salts();
+    }
+    info!("🔌 Serving JSON-RPC on stdio for {:?} (key ID {})", session.data_dir, session.key_id);
+
+    let stdin = io::stdin();
+    let mut stdout = io::stdout().lock();
+    for line in stdin.lock().lines() {
+        let line = Zeroizing::new(line.context("read stdin")?);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (response, shutdown) = session.respond(&line);
+        if let Some(response) = response {
+            let mut out = Zeroizing::new(serde_json::to_string(&response)?);
+            out.push('\n');
+            stdout.write_all(out.as_bytes()).and_then(|()| stdout.flush()).context("write stdout")?;
+        }
+        if shutdown {
+            break;
+        }
+    }
+    info!("🔌 Session over — wiping keys.");
+    Ok(())
+}
@@ -0,0 +1,320 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Shamir secret sharing for the soul key — `key split` / `key combine`
+// (the other `key` subcommands live in sealed_key.rs, fido2.rs, recovery.rs and paper.rs)
+//
+// Share line: violet-share-<split id, 8 hex>-<threshold>-<hex(x ‖ y)>
+// Recovery is `sharks` (GF(2^8), polynomial 0x11d) Lagrange interpolation.
+// Dealing is done here: sharks 0.5 draws coefficients from 1..=255, which
+// biases shares and leaks information about the secret (RUSTSEC-2024-0398).
+// Coefficients here are uniform over the whole field, and every split is
+// checked by recovering it through sharks before any share is written.
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use rand::RngCore;
+use sharks::{Share, Sharks};
+use tracing::info;
+use zeroize::Zeroizing;
+
+use crate::atomic::write_secret_new;
+use crate::key_source::KeyArgs;
+use crate::logging;
+use crate::paper::{self, PaperKind};
+use crate::{random_bytes, recovery, resolve_data_dir, sealed_key};
+
+const SHARE_PREFIX: &str = "violet-share-";
+
+#[derive(Subcommand)]
+pub enum KeyCommand {
+    /// Split the passphrase (or an identity file) into shares, any `threshold` of which rebuild it
+    Split {
+        #[command(flatten)]
+        key: KeyArgs,
+        /// Number of shares to hand out
+        #[arg(long, value_parser = clap::value_parser!(u8).range(2..))]
+        shares: u8,
+        /// Shares needed to reconstruct
+        #[arg(long, value_parser = clap::value_parser!(u8).range(2..))]
+        threshold: u8,
+        /// Write share-<n>.txt files (mode 0600) here instead of printing them
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+    },
+    /// Seal the passphrase to this machine's TPM, for --sealed-key (useless on any other machine)
+    Seal {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Sealed key file to create (default: .violet-sealed-key in the data directory)
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Also bind to these boot-state PCRs, e.g. sha256:0,7 (re-seal after firmware updates)
+        #[arg(long)]
+        pcrs: Option<String>,
+    },
+    /// Create a credential on a FIDO2 security key for --fido2 (use --fido2-device / --fido2-pin to choose how)
+    #[cfg(feature = "fido2")]
+    Fido2Enroll {
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Credential file to create (default: .violet-fido2 in the data directory)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// New recovery key: printed once for offline storage; files escrow the passphrase to it from now on
+    RecoveryGenerate {
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Write the recovery key here (mode 0600) instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Retire the data directory's current recovery key
+        #[arg(long)]
+        replace: bool,
+    },
+    /// Unwrap the passphrase a file escrowed to the recovery key
+    Recover {
+        /// File holding the VIOLET-RECOVERY-KEY- line
+        #[arg(long)]
+        recovery_key_file: PathBuf,
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// File to recover from (default: the first target .enc that has an entry)
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// Write the passphrase here (mode 0600) instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Print a recovery key or keyfile as BIP39 words and/or write it as a QR code, for an offline paper copy
+    ExportPaper {
+        /// Recovery key file or keyfile
+        file: PathBuf,
+        /// Also write a QR code here (.svg or .png, mode 0600)
+        #[arg(long)]
+        qr: Option<PathBuf>,
+        /// QR code only, for keyfiles too large for words
+        #[arg(long, requires = "qr")]
+        no_words: bool,
+    },
+    /// Restore a recovery key or keyfile from a paper backup's words or QR text
+    ImportPaper {
+        /// File holding the typed-in sheet or scanned QR text (default: stdin)
+        #[arg(long)]
+        from: Option<PathBuf>,
+        /// What the backup holds, when the words come without their heading
+        #[arg(long, value_enum)]
+        kind: Option<PaperKind>,
+        /// Secret file to create (mode 0600)
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Rebuild the secret from share files
+    Combine {
+        /// Files holding one share line each
+        #[arg(required = true)]
+        shares: Vec<PathBuf>,
+        /// Write the secret here (mode 0600) instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+pub fn run(command: KeyCommand) -> Result<()> {
+    match command {
+        KeyCommand::Split { key, shares, threshold, output_dir } => {
+            let secret = key.resolve()?;
+            split(&secret, shares, threshold, output_dir.as_deref())
+        }
+        KeyCommand::Seal { key, data_dir, output, pcrs } => {
+            let output = output.unwrap_or_else(|| sealed_key::default_path(&resolve_data_dir(data_dir)));
+            match sealed_key::seal(&key.resolve()?, &output, pcrs.as_deref())? {
+                Some(key_id) => info!("🔒 Passphrase (key ID {}) sealed to this machine's TPM → {:?}", key_id, output),
+                None => info!("🔒 Passphrase sealed to this machine's TPM → {:?}", output),
+            }
+            info!("   Use it with --sealed-key {:?} or VIOLET_SEALED_KEY.", output);
+            Ok(())
+        }
+        #[cfg(feature = "fido2")]
+        KeyCommand::Fido2Enroll { data_dir, output } => {
+            let output = output.unwrap_or_else(|| crate::fido2::default_path(&resolve_data_dir(data_dir)));
+            let pepper_id = crate::fido2::enroll(&output)?;
+            info!("🔑 Security key enrolled (pepper ID {}) → {:?}", pepper_id, output);
+            info!("   Pass --fido2 {:?} to seal and open v5 files with it.", output);
+            Ok(())
+        }
+        KeyCommand::RecoveryGenerate { data_dir, output, replace } => {
+            recovery::generate(&resolve_data_dir(data_dir), output.as_deref(), replace)
+        }
+        KeyCommand::Recover { recovery_key_file, data_dir, file, output } => {
+            recovery::recover(&recovery_key_file, &resolve_data_dir(data_dir), file, output.as_deref())
+        }
+        KeyCommand::ExportPaper { file, qr, no_words } => paper::export(&file, qr.as_deref(), !no_words),
+        KeyCommand::ImportPaper { from, kind, output } => paper::import(from.as_deref(), kind, &output),
+        KeyCommand::Combine { shares, output } => {
+            if output.is_none() {
+                logging::claim_stdout();
+            }
+            let secret = combine(&shares)?;
+            match output {
+                Some(path) => {
+                    write_secret_new(&path, &secret)?;
+                    logging::note(format_args!("🧩 Secret rebuilt from {} share(s) → {:?}", shares.len(), path));
+                }
+                None => std::io::stdout().write_all(&secret).context("write stdout")?,
+            }
+            Ok(())
+        }
+    }
+}
+
+/// GF(2^8) multiply modulo x^8 + x^4 + x^3 + x^2 + 1 — sharks' field, without table lookups
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (carry & 0x1d);
+        b >>= 1;
+    }
+    product
+}
+
+/// `count` shares as sharks' byte encoding, `x ‖ f_1(x) ‖ f_2(x) …` for x = 1..=count
+fn deal(secret: &[u8], count: u8, threshold: u8) -> Vec<Zeroizing<Vec<u8>>> {
+    let degree = threshold as usize - 1;
+    let mut coefficients = Zeroizing::new(vec![0u8; secret.len() * degree]);
+    rand::thread_rng().fill_bytes(&mut coefficients);
+
+    (1..=count)
+        .map(|x| {
+            let mut share = Zeroizing::new(Vec::with_capacity(secret.len() + 1));
+            share.push(x);
+            for (byte, coeffs) in secret.iter().zip(coefficients.chunks(degree)) {
+                // Horner, highest degree first; the constant term is the secret byte
+                let y = coeffs.iter().rev().fold(0u8, |acc, &c| gf_mul(acc, x) ^ c);
+                share.push(gf_mul(y, x) ^ byte);
+            }
+            share
+        })
+        .collect()
+}
+
+fn recover(shares: &[&[u8]], threshold: u8) -> Result<Zeroizing<Vec<u8>>> {
+    let shares = shares
+        .iter()
+        .map(|bytes| Share::try_from(*bytes).map_err(|e| anyhow::anyhow!("{}", e)))
+        .collect::<Result<Vec<_>>>()?;
+    let secret = Sharks(threshold).recover(&shares).map_err(|e| anyhow::anyhow!("{}", e))?;
+    Ok(Zeroizing::new(secret))
+}
+
+fn split(secret: &[u8], count: u8, threshold: u8, output_dir: Option<&Path>) -> Result<()> {
+    if threshold > count {
+        bail!("--threshold {} is more than --shares {}", threshold, count);
+    }
+    if output_dir.is_none() {
+        logging::claim_stdout();
+    }
+    let split_id = hex::encode(random_bytes::<4>());
+    let shares = deal(secret, count, threshold);
+
+    // Any `threshold` shares must rebuild the secret; check the first and the last window
+    let refs: Vec<&[u8]> = shares.iter().map(|s| s.as_slice()).collect();
+    let windows = [&refs[..threshold as usize], &refs[(count - threshold) as usize..]];
+    for window in windows {
+        if recover(window, threshold)?.as_slice() != secret {
+            bail!("share self-check failed — nothing written");
+        }
+    }
+
+    info!("🧩 Split the key into {} shares, any {} rebuild it (split {})", count, threshold, split_id);
+    for share in &shares {
+        let x = share[0];
+        let line = Zeroizing::new(format!(
+            "# share {} of {} — any {} rebuild the key with `violet-cipher key combine`\n{}{}-{}-{}\n",
+            x,
+            count,
+            threshold,
+            SHARE_PREFIX,
+            split_id,
+            threshold,
+            hex::encode(share.as_slice())
+        ));
+        match output_dir {
+            Some(dir) => {
+                std::fs::create_dir_all(dir).with_context(|| format!("create {:?}", dir))?;
+                let path = dir.join(format!("share-{}.txt", x));
+                write_secret_new(&path, line.as_bytes())?;
+                info!("  ✅ {:?}", path);
+            }
+            None => print!("{}", line.as_str()),
+        }
+    }
+    info!("🧩 Hand each share to a different holder; fewer than {} reveal nothing about the key.", threshold);
+    Ok(())
+}
+
+/// One parsed share line
+struct ParsedShare {
+    split_id: String,
+    threshold: u8,
+    bytes: Zeroizing<Vec<u8>>,
+}
+
+fn parse_share(path: &Path) -> Result<ParsedShare> {
+    let text = Zeroizing::new(std::fs::read_to_string(path).with_context(|| format!("read share {:?}", path))?);
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix(SHARE_PREFIX))
+        .with_context(|| format!("{:?} has no {} line", path, SHARE_PREFIX))?;
+    let mut parts = line.splitn(3, '-');
+    let (Some(split_id), Some(threshold), Some(data)) = (parts.next(), parts.next(), parts.next()) else {
+        bail!("{:?}: malformed share line", path);
+    };
+    let threshold = threshold.parse().with_context(|| format!("{:?}: bad threshold", path))?;
+    let bytes = Zeroizing::new(hex::decode(data).with_context(|| format!("{:?}: share is not hex", path))?);
+    if bytes.len() < 2 || bytes[0] == 0 {
+        bail!("{:?}: share is too short or has index 0", path);
+    }
+    Ok(ParsedShare { split_id: split_id.to_string(), threshold, bytes })
+}
+
+fn combine(paths: &[PathBuf]) -> Result<Zeroizing<Vec<u8>>> {
+    let shares = paths.iter().map(|p| parse_share(p)).collect::<Result<Vec<_>>>()?;
+    let first = &shares[0];
+    for (share, path) in shares.iter().zip(paths) {
+        if share.split_id != first.split_id || share.threshold != first.threshold {
+            bail!(
+                "{:?} belongs to split {}, not {} — shares from different splits cannot be mixed",
+                path,
+                share.split_id,
+                first.split_id
+            );
+        }
+        if share.bytes.len() != first.bytes.len() {
+            bail!("{:?} has a different length than the other shares — corrupted?", path);
+        }
+    }
+    let mut indexes: Vec<u8> = shares.iter().map(|s| s.bytes[0]).collect();
+    indexes.sort_unstable();
+    if let Some(pair) = indexes.windows(2).find(|pair| pair[0] == pair[1]) {
+        bail!("share {} was given twice", pair[0]);
+    }
+    let threshold = first.threshold;
+    if shares.len() < threshold as usize {
+        bail!("need {} shares to rebuild this key, got {}", threshold, shares.len());
+    }
+
+    let refs: Vec<&[u8]> = shares.iter().map(|s| s.bytes.as_slice()).collect();
+    let secret = recover(&refs[..threshold as usize], threshold)?;
+    // Extra shares let a corrupted one be noticed instead of yielding a wrong key
+    if refs.len() > threshold as usize && recover(&refs[refs.len() - threshold as usize..], threshold)? != secret {
+        bail!("the shares disagree — at least one is corrupted or from another split");
+    }
+    Ok(secret)
+}
@@ -0,0 +1,173 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Detached Ed25519 signatures over .enc ciphertext — who wrote it, independent of the passphrase
+//
+// <file>.sig is JSON: { "signer": "violet-sig-pub-<hex>", "signature": "<hex>" }
+// The signed message is SIGNATURE_CONTEXT ‖ ciphertext, so a signature made
+// here cannot be replayed as one over some other Ed25519 message format.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use ed25519_dalek::{Signature, Signer as _, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+use zeroize::Zeroizing;
+
+use crate::atomic::{write_atomic, write_secret_new};
+use crate::random_bytes;
+
+pub const PUBLIC_PREFIX: &str = "violet-sig-pub-";
+pub const SECRET_PREFIX: &str = "VIOLET-SIGNING-KEY-";
+const SIGNATURE_CONTEXT: &[u8] = b"violet-enc-signature-v1\0";
+const SIGNATURE_EXT: &str = "sig";
+
+/// An Ed25519 public key trusted to sign .enc files
+pub struct Signer(VerifyingKey);
+
+impl Signer {
+    /// Parse `violet-sig-pub-<64 hex>`
+    pub fn parse(text: &str) -> Result<Self> {
+        let hex_part = text
+            .trim()
+            .strip_prefix(PUBLIC_PREFIX)
+            .with_context(|| format!("signer must start with {}", PUBLIC_PREFIX))?;
+        let bytes: [u8; 32] = hex::decode(hex_part)
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .context("signer must be 64 hex characters after the prefix")?;
+        Ok(Self(VerifyingKey::from_bytes(&bytes).context("signer is not a valid Ed25519 public key")?))
+    }
+}
+
+impl std::fmt::Display for Signer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", PUBLIC_PREFIX, hex::encode(self.0.as_bytes()))
+    }
+}
+
+/// Contents of a detached `.sig` file
+#[derive(Serialize, Deserialize)]
+struct DetachedSignature {
+    signer: String,
+    signature: String,
+}
+
+/// Find the `VIOLET-SIGNING-KEY-` line; `#` comment lines are ignored
+fn parse_signing_key(text: &[u8]) -> Result<SigningKey> {
+    let text = std::str::from_utf8(text).ok();
+    let line = text
+        .into_iter()
+        .flat_map(str::lines)
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix(SECRET_PREFIX))
+        .context("no VIOLET-SIGNING-KEY- line — create one with `keygen --signing`")?;
+    let bytes = Zeroizing::new(hex::decode(line).context("signing key is not hex")?);
+    let bytes: [u8; 32] = bytes.as_slice().try_into().context("signing key must be 32 bytes")?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+fn signer_of(key: &SigningKey) -> Signer {
+    Signer(key.verifying_key())
+}
+
+/// Generate a signing key; writes it to `output` (0600, never overwritten)
+/// or stdout, and returns the public key
+pub fn keygen(output: Option<&Path>) -> Result<Signer> {
+    let key = SigningKey::from_bytes(&random_bytes::<32>());
+    let encoded = Zeroizing::new(format!(
+        "# public key: {}\n{}{}\n",
+        signer_of(&key),
+        SECRET_PREFIX,
+        hex::encode(key.to_bytes())
+    ));
+    match output {
+        Some(path) => write_secret_new(path, encoded.as_bytes())?,
+        None => print!("{}", encoded.as_str()),
+    }
+    Ok(signer_of(&key))
+}
+
+fn signature_path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".");
+    name.push(SIGNATURE_EXT);
+    PathBuf::from(name)
+}
+
+fn message(ciphertext: &[u8]) -> Vec<u8> {
+    [SIGNATURE_CONTEXT, ciphertext].concat()
+}
+
+/// Write `<file>.sig` next to each file
+pub fn sign(key_file: &Path, files: &[PathBuf], dry_run: bool) -> Result<()> {
+    let key_text = Zeroizing::new(fs::read(key_file).with_context(|| format!("read signing key {:?}", key_file))?);
+    let key = parse_signing_key(&key_text)?;
+    let signer = signer_of(&key);
+    info!("✍️  Signing as {}", signer);
+
+    for file in files {
+        let ciphertext = fs::read(file).with_context(|| format!("read {:?}", file))?;
+        let detached = DetachedSignature {
+            signer: signer.to_string(),
+            signature: hex::encode(key.sign(&message(&ciphertext)).to_bytes()),
+        };
+        let sig_path = signature_path(file);
+        if dry_run {
+            info!("  [dry-run] would write {:?}", sig_path);
+            continue;
+        }
+        write_atomic(&sig_path, format!("{}\n", serde_json::to_string_pretty(&detached)?).as_bytes())?;
+        info!("  ✅ {:?}", sig_path);
+    }
+    Ok(())
+}
+
+/// Check each file against its `.sig`; returns the number of files that fail
+///
+/// A file passes only when the signature is valid AND was made by one of
+/// `trusted` — a valid signature from anyone else is reported as untrusted.
+pub fn verify(files: &[PathBuf], trusted: &[Signer]) -> Result<u32> {
+    if trusted.is_empty() {
+        bail!("pass at least one --signer to trust");
+    }
+    info!("🔏 Verifying signatures...");
+    let mut failures = 0;
+    for file in files {
+        match verify_file(file, trusted) {
+            Ok(signer) => info!("  ✅ {:?} — signed by {}", file, signer),
+            Err(e) => {
+                error!("  ❌ {:?} — {:#}", file, e);
+                failures += 1;
+            }
+        }
+    }
+    if failures == 0 {
+        info!("🔏 All {} signature(s) valid.", files.len());
+    } else {
+        info!("🔏 {} of {} file(s) failed signature verification.", failures, files.len());
+    }
+    Ok(failures)
+}
+
+fn verify_file(file: &Path, trusted: &[Signer]) -> Result<Signer> {
+    let sig_path = signature_path(file);
+    let ciphertext = fs::read(file).with_context(|| format!("read {:?}", file))?;
+    let detached: DetachedSignature = serde_json::from_slice(
+        &fs::read(&sig_path).with_context(|| format!("no signature at {:?}", sig_path))?,
+    )
+    .with_context(|| format!("{:?} is not a signature file", sig_path))?;
+
+    let claimed = Signer::parse(&detached.signer)?;
+    let signature: [u8; 64] = hex::decode(&detached.signature)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .context("signature must be 128 hex characters")?;
+    claimed
+        .0
+        .verify_strict(&message(&ciphertext), &Signature::from_bytes(&signature))
+        .map_err(|_| anyhow::anyhow!("signature does not match the ciphertext"))?;
+    if !trusted.iter().any(|t| t.0 == claimed.0) {
+        bail!("valid signature from untrusted key {}", claimed);
+    }
+    Ok(claimed)
+}
@@ -0,0 +1,217 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Pre-commit scan — refuse plaintext soul data in the git index
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use tracing::{error, info};
+
+use crate::sarif::{self, Rule};
+use crate::{check_integrity, fields, preamble, TARGET_FILES};
+
+/// One index entry from `git ls-files --stage`
+struct IndexEntry {
+    path: String,
+    object: String,
+}
+
+/// Index paths reserved for the target files
+#[derive(Clone, Copy, PartialEq)]
+enum Protected {
+    Plaintext,
+    LocalCiphertext,
+    Placeholder,
+    /// Readable JSON whose sensitive values `encrypt-fields` sealed
+    SealedFields,
+}
+
+/// What a decrypted target file looks like, for spotting renamed copies
+struct Fingerprint {
+    name: &'static str,
+    content: Vec<u8>,
+    keys: Option<BTreeSet<String>>,
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .context("run git")?;
+    if !output.status.success() {
+        bail!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(output.stdout)
+}
+
+fn nul_separated(raw: &[u8]) -> impl Iterator<Item = String> + '_ {
+    raw.split(|&b| b == 0)
+        .filter(|s| !s.is_empty())
+        .map(|s| String::from_utf8_lossy(s).into_owned())
+}
+
+/// `<mode> <object> <stage>\t<path>` records of the whole index
+fn index_entries(repo: &Path) -> Result<Vec<IndexEntry>> {
+    let raw = git(repo, &["ls-files", "--stage", "-z"])?;
+    nul_separated(&raw)
+        .map(|record| {
+            let (meta, path) = record.split_once('\t').context("malformed ls-files record")?;
+            let object = meta.split(' ').nth(1).context("malformed ls-files record")?;
+            Ok(IndexEntry { path: path.to_string(), object: object.to_string() })
+        })
+        .collect()
+}
+
+/// Top-level keys of a JSON object, the "shape" of a decrypted target file
+fn json_keys(data: &[u8]) -> Option<BTreeSet<String>> {
+    match serde_json::from_slice::<serde_json::Value>(data).ok()? {
+        serde_json::Value::Object(map) if !map.is_empty() => Some(map.keys().cloned().collect()),
+        _ => None,
+    }
+}
+
+fn fingerprints(data_dir: &Path) -> Vec<Fingerprint> {
+    TARGET_FILES
+        .iter()
+        .filter_map(|&name| {
+            let content = fs::read(data_dir.join(name)).ok()?;
+            let keys = json_keys(&content);
+            Some(Fingerprint { name, content, keys })
+        })
+        .filter(|f| f.content.iter().any(|b| !b.is_ascii_whitespace()))
+        .collect()
+}
+
+/// Scan the index for plaintext target files, local-key ciphertext, malformed
+/// placeholders and copies of decrypted data under other names.
+///
+/// Only entries that differ from HEAD are read unless `all` is set. Returns the
+/// number of issues found.
+pub fn check_staged(data_dir: &Path, all: bool) -> Result<u32> {
+    info!("🔎 Scanning staged files for plaintext leaks...");
+    let data_dir = fs::canonicalize(data_dir).with_context(|| format!("data dir {:?}", data_dir))?;
+    let root = PathBuf::from(String::from_utf8(git(&data_dir, &["rev-parse", "--show-toplevel"])?)?.trim());
+    let data_rel = data_dir
+        .strip_prefix(fs::canonicalize(&root)?)
+        .context("data dir is outside the git repository")?
+        .to_string_lossy()
+        .replace('\\', "/");
+    let in_data = |name: &str| if data_rel.is_empty() { name.to_string() } else { format!("{}/{}", data_rel, name) };
+
+    let mut protected = HashMap::new();
+    for &name in TARGET_FILES {
+        protected.insert(in_data(name), Protected::Plaintext);
+        protected.insert(in_data(&format!("{}.enc", name)), Protected::LocalCiphertext);
+        protected.insert(in_data(&format!("{}.git.enc", name)), Protected::Placeholder);
+        protected.insert(in_data(&fields::fields_name(name)), Protected::SealedFields);
+    }
+
+    let entries = index_entries(&root)?;
+    let changed: HashSet<String> = if all {
+        entries.iter().map(|e| e.path.clone()).collect()
+    } else {
+        let raw = git(&root, &["diff", "--cached", "--name-only", "--no-renames", "--diff-filter=ACMRT", "-z"])?;
+        nul_separated(&raw).collect()
+    };
+
+    let known = fingerprints(&data_dir);
+    if known.is_empty() {
+        info!("  ℹ️  No decrypted target files in {:?}; checking names and placeholders only", data_dir);
+    }
+
+    let mut issues = 0u32;
+    let mut scanned = 0usize;
+    for entry in &entries {
+        let path = entry.path.as_str();
+        let kind = protected.get(path).copied();
+        match kind {
+            Some(Protected::Plaintext) => {
+                error!("  🚨 LEAK: {} is staged unencrypted", path);
+                sarif::record(Rule::PlaintextLeak, &root.join(path), format_args!("{} is staged unencrypted", path));
+                issues += 1;
+                continue;
+            }
+            Some(Protected::LocalCiphertext) => {
+                error!("  🚨 {} is staged — local-key files must never be committed, use .git.enc", path);
+                let message = format_args!("{} is staged — local-key files are never committed, use .git.enc", path);
+                sarif::record(Rule::CommittedLocalKey, &root.join(path), message);
+                issues += 1;
+                continue;
+            }
+            _ => {}
+        }
+        if !changed.contains(path) {
+            continue;
+        }
+
+        let blob = git(&root, &["cat-file", "blob", &entry.object])?;
+        scanned += 1;
+        if kind == Some(Protected::Placeholder) {
+            match preamble::version(&blob) {
+                Some(v) => match check_integrity(&blob) {
+                    Ok(()) => info!("  ✅ {} — v{}, well-formed, HMAC intact", path, v),
+                    Err(e) => {
+                        error!("  ❌ {} — {}", path, e);
+                        sarif::record(Rule::Integrity, &root.join(path), format_args!("staged {}: {}", path, e));
+                        issues += 1;
+                    }
+                },
+                _ if serde_json::from_slice::<serde_json::Value>(&blob).is_ok() => {
+                    error!("  🚨 LEAK: {} is plaintext JSON, not ciphertext", path);
+                    let message = format_args!("staged {} is plaintext JSON, not ciphertext", path);
+                    sarif::record(Rule::PlaintextLeak, &root.join(path), message);
+                    issues += 1;
+                }
+                _ => {
+                    error!("  ❌ {} — not a v4–v6 placeholder (run encrypt-git)", path);
+                    let message = format_args!("staged {} is not a v4–v6 placeholder (run encrypt-git)", path);
+                    sarif::record(Rule::Integrity, &root.join(path), message);
+                    issues += 1;
+                }
+            }
+            continue;
+        }
+
+        if kind == Some(Protected::SealedFields) {
+            // Shares the plaintext's structure by design; what matters is that something is sealed
+            let sealed = String::from_utf8_lossy(&blob).matches(fields::SEALED_PREFIX).count();
+            if sealed == 0 {
+                error!("  🚨 LEAK: {} has no sealed values — run encrypt-fields", path);
+                let message = format_args!("staged {} has no sealed values — run encrypt-fields", path);
+                sarif::record(Rule::PlaintextLeak, &root.join(path), message);
+                issues += 1;
+            } else {
+                info!("  ✅ {} — {} sealed value(s)", path, sealed);
+            }
+            continue;
+        }
+
+        let blob_keys = json_keys(&blob);
+        for fingerprint in &known {
+            if blob == fingerprint.content {
+                error!("  🚨 LEAK: {} is a copy of decrypted {}", path, fingerprint.name);
+                let message = format_args!("staged {} is a copy of decrypted {}", path, fingerprint.name);
+                sarif::record(Rule::PlaintextLeak, &root.join(path), message);
+                issues += 1;
+                break;
+            }
+            if fingerprint.keys.is_some() && blob_keys == fingerprint.keys {
+                error!("  🚨 LEAK: {} has the structure of decrypted {}", path, fingerprint.name);
+                let message = format_args!("staged {} has the structure of decrypted {}", path, fingerprint.name);
+                sarif::record(Rule::PlaintextLeak, &root.join(path), message);
+                issues += 1;
+                break;
+            }
+        }
+    }
+
+    if issues == 0 {
+        info!("🔎 No leaks in {} staged file(s).", scanned);
+    } else {
+        info!("🔎 Found {} issue(s) — unstage with `git restore --staged <file>`.", issues);
+    }
+    Ok(issues)
+}
@@ -0,0 +1,203 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Encryption status — per-target plaintext and .enc state, no key needed
+//
+// For each target: whether the plaintext exists, the .enc format version,
+// both sizes and modification times, the key generation from the header, and
+// whether the .enc is stale. A header's bare plaintext SHA-256, found in v5
+// files written before digests were keyed, decides staleness when there is
+// one; other files fall back to comparing modification times, since a keyed
+// digest needs the passphrase.
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::Serialize;
+use tracing::info;
+
+use crate::backup::utc_timestamp;
+use crate::keyring::Keyring;
+use crate::{logging, preamble, read_header, TARGET_FILES};
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum State {
+    /// .enc holds the current plaintext
+    InSync,
+    /// Plaintext changed after the .enc was written
+    Stale,
+    /// Plaintext only
+    NotEncrypted,
+    /// .enc only (plaintext not decrypted here)
+    EncryptedOnly,
+    /// The .enc fails its integrity check
+    Damaged,
+    Missing,
+}
+
+impl State {
+    fn label(self) -> &'static str {
+        match self {
+            Self::InSync => "✅ in sync",
+            Self::Stale => "⚠️  stale",
+            Self::NotEncrypted => "⚠️  not encrypted",
+            Self::EncryptedOnly => "🔒 encrypted only",
+            Self::Damaged => "❌ damaged",
+            Self::Missing => "⏭️  missing",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct FileInfo {
+    size: u64,
+    /// UTC, `20261017T040912Z`
+    modified: Option<String>,
+}
+
+#[derive(Serialize)]
+struct EncInfo {
+    #[serde(flatten)]
+    file: FileInfo,
+    /// `v5`, or `v2/v3` for legacy files without a preamble
+    version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encrypted_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key_generation: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TargetStatus {
+    name: &'static str,
+    plaintext: Option<FileInfo>,
+    enc: Option<EncInfo>,
+    state: State,
+    /// What staleness was judged by: `digest` or `mtime`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compared_by: Option<&'static str>,
+}
+
+fn file_info(path: &Path) -> Option<(FileInfo, Option<SystemTime>)> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime = meta.modified().ok();
+    Some((FileInfo { size: meta.len(), modified: mtime.map(utc_timestamp) }, mtime))
+}
+
+fn target_status(data_dir: &Path, name: &'static str, keyring: Option<&Keyring>) -> TargetStatus {
+    let plain_path = data_dir.join(name);
+    let enc_path = data_dir.join(format!("{}.enc", name));
+    let plaintext = file_info(&plain_path);
+    let Some((enc_file, enc_mtime)) = file_info(&enc_path) else {
+        let state = if plaintext.is_some() { State::NotEncrypted } else { State::Missing };
+        return TargetStatus { name, plaintext: plaintext.map(|(info, _)| info), enc: None, state, compared_by: None };
+    };
+
+    let data = fs::read(&enc_path).unwrap_or_default();
+    let mut enc = EncInfo {
+        file: enc_file,
+        version: preamble::version(&data).map_or("v2/v3".to_string(), |v| format!("v{}", v)),
+        encrypted_at: None,
+        key_id: None,
+        key_generation: None,
+        error: None,
+    };
+    let header = match preamble::version(&data).map(|_| read_header(&data)) {
+        Some(Ok(header)) => header,
+        Some(Err(e)) => {
+            enc.error = Some(e.to_string());
+            let plaintext = plaintext.map(|(info, _)| info);
+            return TargetStatus { name, plaintext, enc: Some(enc), state: State::Damaged, compared_by: None };
+        }
+        None => None,
+    };
+    if let Some(header) = &header {
+        enc.encrypted_at = header.encrypted_at.map(|at| utc_timestamp(UNIX_EPOCH + Duration::from_secs(at)));
+        enc.key_id = header.key_id.clone();
+        enc.key_generation = header.key_id.as_deref().and_then(|id| keyring?.find(id)).map(|entry| entry.generation);
+    }
+
+    let Some((plain_info, plain_mtime)) = plaintext else {
+        return TargetStatus { name, plaintext: None, enc: Some(enc), state: State::EncryptedOnly, compared_by: None };
+    };
+    let digest_match = header
+        .as_ref()
+        .and_then(|header| fs::read(&plain_path).ok().and_then(|current| header.matches_plaintext(&current, None)));
+    let (fresh, compared_by) = match (digest_match, plain_mtime, enc_mtime) {
+        (Some(matches), _, _) => (matches, "digest"),
+        (None, Some(plain), Some(enc)) => (plain <= enc, "mtime"),
+        (None, _, _) => (true, "mtime"),
+    };
+    let state = if fresh { State::InSync } else { State::Stale };
+    TargetStatus { name, plaintext: Some(plain_info), enc: Some(enc), state, compared_by: Some(compared_by) }
+}
+
+fn print_table(statuses: &[TargetStatus]) {
+    let rows: Vec<[String; 7]> = statuses
+        .iter()
+        .map(|s| {
+            let (plain_size, plain_modified) = match &s.plaintext {
+                Some(info) => (info.size.to_string(), info.modified.clone().unwrap_or_default()),
+                None => ("—".to_string(), "—".to_string()),
+            };
+            let (version, enc_size, enc_modified, key) = match &s.enc {
+                Some(enc) => (
+                    enc.version.clone(),
+                    enc.file.size.to_string(),
+                    enc.file.modified.clone().unwrap_or_default(),
+                    match (enc.key_generation, &enc.key_id) {
+                        (Some(generation), _) => format!("gen {}", generation),
+                        (None, Some(id)) => id.chars().take(12).collect(),
+                        (None, None) => "—".to_string(),
+                    },
+                ),
+                None => ("—".to_string(), "—".to_string(), "—".to_string(), "—".to_string()),
+            };
+            [s.name.to_string(), plain_size, plain_modified, version, enc_size, enc_modified, key]
+        })
+        .collect();
+    let header = ["FILE", "PLAIN BYTES", "PLAIN MODIFIED", "ENC", "ENC BYTES", "ENC MODIFIED", "KEY"];
+    let mut widths = header.map(|h| h.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells.iter().zip(widths).map(|(cell, width)| format!("{:<width$}", cell)).collect();
+        padded.join("  ")
+    };
+    logging::out(format_args!("  {}  STATE", line(&header.map(String::from))));
+    for (row, status) in rows.iter().zip(statuses) {
+        let by = status.compared_by.map(|by| format!(" (by {})", by)).unwrap_or_default();
+        logging::out(format_args!("  {}  {}{}", line(row), status.state.label(), by));
+        if let Some(error) = status.enc.as_ref().and_then(|enc| enc.error.as_deref()) {
+            logging::out(format_args!("      {}.enc: {}", status.name, error));
+        }
+    }
+}
+
+/// Print the state of every target as a table or JSON; returns how many are stale
+pub fn run(data_dir: &Path, json: bool) -> Result<usize> {
+    let keyring = Keyring::load(data_dir).ok();
+    let statuses: Vec<TargetStatus> =
+        TARGET_FILES.iter().map(|&name| target_status(data_dir, name, keyring.as_ref())).collect();
+    let stale = statuses.iter().filter(|s| s.state == State::Stale).count();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&statuses)?);
+        return Ok(stale);
+    }
+
+    info!("📋 Encryption status of {:?} (no key needed)...", data_dir);
+    print_table(&statuses);
+    if stale > 0 {
+        info!("📋 {} file(s) out of date — re-run encrypt-local.", stale);
+    } else {
+        info!("📋 Every .enc matches its plaintext (where both exist).");
+    }
+    Ok(stale)
+}
@@ -0,0 +1,183 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Encrypted datastore — every document is a v5 ciphertext row in one SQLite file
+//
+// Row-level encryption: document names, content types, sizes and timestamps
+// are visible to anyone holding the file; contents need the passphrase.
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::header::{Compression, ContentType, Header};
+use crate::kdf::KdfArgs;
+use crate::key_source::KeyArgs;
+
+pub const DEFAULT_STORE_FILE: &str = "violet-store.sqlite";
+const STORE_SALT: &str = "violet-soul-salt-store-2026";
+
+#[derive(Subcommand)]
+pub enum StoreCommand {
+    /// Encrypt a file (or stdin) into the store under a document name
+    Put {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        /// Store file (default: <data dir>/violet-store.sqlite)
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// Document name, e.g. rules-index.json
+        #[arg(long)]
+        name: String,
+        /// Read the document from this file instead of stdin
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// Content type tag (default: guessed from --name)
+        #[arg(long)]
+        content_type: Option<String>,
+    },
+    /// Decrypt a document to stdout or a file
+    Get {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[command(flatten)]
+        kdf: KdfArgs,
+        #[arg(long)]
+        db: Option<PathBuf>,
+        #[arg(long)]
+        name: String,
+        /// Write the plaintext here instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// List stored documents (needs no passphrase)
+    List {
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+}
+
+pub struct StoreEntry {
+    pub name: String,
+    pub content_type: String,
+    pub size: u64,
+    pub updated_at: i64,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path).with_context(|| format!("open store {:?}", path))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS documents (
+                name         TEXT PRIMARY KEY,
+                content_type TEXT NOT NULL,
+                ciphertext   BLOB NOT NULL,
+                updated_at   INTEGER NOT NULL
+            );",
+        )
+        .context("initialize store schema")?;
+        Ok(Self { conn })
+    }
+
+    /// Insert or replace a document; returns whether one was replaced
+    pub fn put(&self, name: &str, content_type: &ContentType, ciphertext: &[u8]) -> Result<bool> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+        let existed = self.get(name)?.is_some();
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO documents (name, content_type, ciphertext, updated_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![name, content_type.as_str(), ciphertext, now],
+            )
+            .with_context(|| format!("store {}", name))?;
+        Ok(existed)
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        self.conn
+            .query_row("SELECT ciphertext FROM documents WHERE name = ?1", params![name], |row| row.get(0))
+            .optional()
+            .with_context(|| format!("load {}", name))
+    }
+
+    pub fn list(&self) -> Result<Vec<StoreEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, content_type, length(ciphertext), updated_at FROM documents ORDER BY name",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(StoreEntry {
+                name: row.get(0)?,
+                content_type: row.get(1)?,
+                size: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        })?;
+        rows.collect::<rusqlite::Result<_>>().context("list documents")
+    }
+}
+
+fn resolve_db(db: Option<PathBuf>) -> PathBuf {
+    db.unwrap_or_else(|| crate::resolve_data_dir(None).join(DEFAULT_STORE_FILE))
+}
+
+pub fn run(command: StoreCommand) -> Result<()> {
+    match command {
+        StoreCommand::Put { key, kdf, db, name, file, content_type } => {
+            let key = key.resolve()?;
+            let content_type = match content_type {
+                Some(label) => ContentType::parse(&label)?,
+                None => ContentType::from_path(Path::new(&name)),
+            };
+            let plaintext = match &file {
+                Some(path) => std::fs::read(path).with_context(|| format!("read {:?}", path))?,
+                None => {
+                    let mut buf = Vec::new();
+                    std::io::stdin().read_to_end(&mut buf).context("read stdin")?;
+                    buf
+                }
+            };
+            let header = Header::new(content_type, Compression::None);
+            let binding = crate::Binding::new(STORE_SALT, &name);
+            let ciphertext = crate::v5_encrypt(&key, binding, &plaintext, &header, &kdf.params()?)?;
+            let store = Store::open(&resolve_db(db))?;
+            let replaced = store.put(&name, &header.content_type, &ciphertext)?;
+            let action = if replaced { "replaced" } else { "added" };
+            crate::logging::note(format_args!("🗄️  {} {} ({}, {} bytes encrypted)", action, name, header.content_type, ciphertext.len()));
+            Ok(())
+        }
+        StoreCommand::Get { key, kdf, db, name, output } => {
+            if output.is_none() {
+                crate::logging::claim_stdout();
+            }
+            let key = key.resolve()?;
+            let store = Store::open(&resolve_db(db))?;
+            let ciphertext = store.get(&name)?.with_context(|| format!("no document named {}", name))?;
+            let binding = crate::Binding::new(STORE_SALT, &name);
+            let (_, plain) = crate::decrypt_payload(&key, binding, &ciphertext, &kdf.params()?)?;
+            match output {
+                Some(path) => crate::atomic::write_atomic(&path, &plain)?,
+                None => std::io::stdout().write_all(&plain).context("write stdout")?,
+            }
+            Ok(())
+        }
+        StoreCommand::List { db } => {
+            let path = resolve_db(db);
+            let entries = Store::open(&path)?.list()?;
+            crate::logging::out(format_args!("🗄️  {} document(s) in {:?}", entries.len(), path));
+            for entry in entries {
+                crate::logging::out(format_args!(
+                    "  {}  {} ({} bytes encrypted, updated {})",
+                    entry.name, entry.content_type, entry.size, entry.updated_at
+                ));
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,155 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Strict decoding — a hardened gate in front of the decryptors for untrusted .enc files
+//
+// The ordinary readers are lenient on purpose: they take bare v4/v5 files,
+// try both associated-data forms on a bare v4 body and fall back to the
+// legacy v2/v3 CBC formats when nothing else fits. `--strict` runs `check`
+// first and refuses anything but a well-formed VSC!-framed v4–v6 file:
+//   - size capped before the file is read, Argon2id costs capped before any
+//     key is derived, so a hostile header cannot exhaust memory or time;
+//   - every length the preamble declares is checked with overflow-free
+//     arithmetic, and the file must end exactly where they say — no trailing
+//     bytes;
+//   - the v5/v6 metadata must fill its declared length with well-formed
+//     entries, each tag at most once;
+//   - the structure and HMAC must verify before the key is touched.
+// Each failure is a distinct `FormatError`, so callers can tell a truncated
+// download from tampering. Legacy and bare files must be re-encrypted first.
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use thiserror::Error;
+
+use crate::header::Header;
+use crate::kdf::{MAX_ITERATIONS, MAX_MEMORY_KIB, MAX_PARALLELISM};
+use crate::preamble::{self, Preamble, MAGIC};
+use crate::{check_integrity, LAYERS_OVERHEAD, VERSION_V4, VERSION_V5, VERSION_V6};
+
+/// Largest file `--strict` reads
+pub const MAX_FILE_LEN: u64 = 256 * 1024 * 1024;
+const HMAC_LEN: u64 = 32;
+/// Length prefix of the v5/v6 metadata
+const METADATA_PREFIX_LEN: usize = 2;
+/// Tag and length of one metadata entry
+const ENTRY_PREFIX_LEN: usize = 3;
+
+#[derive(Debug, Error)]
+pub enum FormatError {
+    #[error("file is {len} bytes, over the {max}-byte limit")]
+    TooLarge { len: u64, max: u64 },
+    #[error("no format marker — legacy v2/v3 or not a violet-cipher file (re-encrypt it before using --strict)")]
+    Legacy,
+    #[error("bare v{0} file without a VSC! preamble (re-encrypt it before using --strict)")]
+    Unframed(u8),
+    #[error("truncated: {what} needs {need} bytes, file has {have}")]
+    Truncated { what: &'static str, need: u64, have: u64 },
+    #[error("{extra} trailing bytes after the {declared} the preamble declares")]
+    TrailingBytes { declared: u64, extra: u64 },
+    #[error("preamble is malformed: {0}")]
+    Preamble(String),
+    #[error("Argon2id costs m={memory} KiB, t={iterations}, p={parallelism} exceed the limits \
+             ({MAX_MEMORY_KIB} KiB, {MAX_ITERATIONS}, {MAX_PARALLELISM})")]
+    KdfTooCostly { memory: u32, iterations: u32, parallelism: u32 },
+    #[error("v4 file declares {0} bytes of metadata; v4 has none")]
+    UnexpectedMetadata(usize),
+    #[error("payload is {len} bytes, shorter than the {min} its layers need")]
+    PayloadTooShort { len: usize, min: usize },
+    #[error("metadata is malformed: {0}")]
+    Metadata(String),
+    #[error("metadata entry {0:#04x} appears more than once")]
+    DuplicateTag(u8),
+    #[error("integrity check failed: {0}")]
+    Integrity(String),
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes(bytes[at..at + 4].try_into().expect("4 bytes"))
+}
+
+/// Read `path` for strict decoding: its size is checked before any byte is read
+pub fn read(path: &Path) -> Result<Vec<u8>> {
+    let len = fs::metadata(path).with_context(|| format!("stat {:?}", path))?.len();
+    if len > MAX_FILE_LEN {
+        return Err(FormatError::TooLarge { len, max: MAX_FILE_LEN }).with_context(|| format!("--strict: {:?}", path));
+    }
+    let data = fs::read(path).with_context(|| format!("read {:?}", path))?;
+    check(&data).with_context(|| format!("--strict: {:?}", path))?;
+    Ok(data)
+}
+
+/// Accept only a well-formed, intact, VSC!-framed v4–v6 blob within the limits
+pub fn check(data: &[u8]) -> Result<(), FormatError> {
+    let have = data.len() as u64;
+    if have > MAX_FILE_LEN {
+        return Err(FormatError::TooLarge { len: have, max: MAX_FILE_LEN });
+    }
+    let Some(version) = preamble::version(data) else {
+        return Err(FormatError::Legacy);
+    };
+    if !data.starts_with(MAGIC) {
+        return Err(FormatError::Unframed(version));
+    }
+    let need = (preamble::LEN as u64) + HMAC_LEN;
+    if have < need {
+        return Err(FormatError::Truncated { what: "preamble and HMAC", need, have });
+    }
+
+    // Costs first, as their own error; Preamble::parse refuses them as well
+    let bytes = &data[..preamble::LEN];
+    let (memory, iterations, parallelism) = (u32_at(bytes, 8), u32_at(bytes, 12), u32_at(bytes, 16));
+    if memory > MAX_MEMORY_KIB || iterations > MAX_ITERATIONS || parallelism > MAX_PARALLELISM {
+        return Err(FormatError::KdfTooCostly { memory, iterations, parallelism });
+    }
+    let metadata_len = u64::from(u32_at(bytes, 20));
+    let payload_len = u64::from_be_bytes(bytes[24..32].try_into().expect("8 bytes"));
+    let declared = [preamble::LEN as u64, metadata_len, payload_len, HMAC_LEN]
+        .into_iter()
+        .try_fold(0u64, u64::checked_add)
+        .ok_or_else(|| FormatError::Preamble("declared lengths overflow".to_string()))?;
+    if have < declared {
+        return Err(FormatError::Truncated { what: "declared metadata and payload", need: declared, have });
+    }
+    if have > declared {
+        return Err(FormatError::TrailingBytes { declared, extra: have - declared });
+    }
+    let preamble = Preamble::parse(data).map_err(|e| FormatError::Preamble(e.to_string()))?.ok_or(FormatError::Legacy)?;
+
+    let body = &data[preamble::LEN..preamble::LEN + preamble.metadata_len + preamble.payload_len];
+    let (metadata, payload) = body.split_at(preamble.metadata_len);
+    match version {
+        VERSION_V4 if !metadata.is_empty() => return Err(FormatError::UnexpectedMetadata(metadata.len())),
+        VERSION_V4 | VERSION_V5 if payload.len() < LAYERS_OVERHEAD => {
+            return Err(FormatError::PayloadTooShort { len: payload.len(), min: LAYERS_OVERHEAD });
+        }
+        VERSION_V5 | VERSION_V6 => check_metadata(metadata, &preamble)?,
+        _ => {}
+    }
+    check_integrity(data).map_err(|e| FormatError::Integrity(format!("{:#}", e)))
+}
+
+/// The v5/v6 TLV metadata fills exactly its declared length, once per tag, and parses
+fn check_metadata(metadata: &[u8], preamble: &Preamble) -> Result<(), FormatError> {
+    let malformed = |what: &str| FormatError::Metadata(what.to_string());
+    let prefix = metadata.get(..METADATA_PREFIX_LEN).ok_or_else(|| malformed("length prefix truncated"))?;
+    let body_len = usize::from(u16::from_be_bytes([prefix[0], prefix[1]]));
+    if METADATA_PREFIX_LEN + body_len != metadata.len() {
+        return Err(FormatError::Metadata(format!(
+            "entries declare {} bytes, the preamble {}",
+            body_len,
+            metadata.len() - METADATA_PREFIX_LEN
+        )));
+    }
+    let mut entries = &metadata[METADATA_PREFIX_LEN..];
+    let mut seen = [false; 256];
+    while !entries.is_empty() {
+        let entry = entries.get(..ENTRY_PREFIX_LEN).ok_or_else(|| malformed("entry header truncated"))?;
+        let (tag, len) = (entry[0], usize::from(u16::from_be_bytes([entry[1], entry[2]])));
+        if std::mem::replace(&mut seen[usize::from(tag)], true) {
+            return Err(FormatError::DuplicateTag(tag));
+        }
+        entries = entries.get(ENTRY_PREFIX_LEN + len..).ok_or_else(|| malformed("entry value runs past the end"))?;
+    }
+    let (header, header_len) = Header::parse(metadata).map_err(|e| FormatError::Metadata(e.to_string()))?;
+    preamble.check_metadata(&header, header_len).map_err(|e| FormatError::Metadata(e.to_string()))
+}
@@ -0,0 +1,5 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// violet-test-vectors — writes the deterministic test vectors (vectors.rs); built only with `--features test-vectors`
+fn main() -> anyhow::Result<()> {
+    violet_cipher::test_vectors_main()
+}
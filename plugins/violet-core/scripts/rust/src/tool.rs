@@ -0,0 +1,28 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// External tools — run a CLI with secrets over its stdin and stdout, never argv
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+use zeroize::Zeroizing;
+
+/// Run `program` with `input` on stdin and return its stdout; stderr becomes
+/// the error on failure. `package` names what provides `program` when it is missing
+pub fn run(program: &str, args: &[&str], input: &[u8], package: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("run `{}` — is {} installed and on PATH?", program, package))?;
+    let mut stdin = child.stdin.take().context("open stdin")?;
+    stdin.write_all(input).with_context(|| format!("write to `{}`", program))?;
+    drop(stdin);
+    let output = child.wait_with_output().with_context(|| format!("wait for `{}`", program))?;
+    let stdout = Zeroizing::new(output.stdout);
+    if !output.status.success() {
+        bail!("`{}` failed: {}", program, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(stdout)
+}
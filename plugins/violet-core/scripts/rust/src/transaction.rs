@@ -0,0 +1,87 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Staged multi-file writes — all destinations change together or not at all
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tracing::trace;
+
+use crate::atomic;
+
+pub const STAGE_SUFFIX: &str = ".vsc-staged";
+
+struct Staged {
+    dest: PathBuf,
+    tmp: PathBuf,
+    original: Option<Vec<u8>>,
+}
+
+/// Destinations are untouched until `commit`; dropping an uncommitted
+/// transaction removes its staged temp files.
+#[derive(Default)]
+pub struct Transaction {
+    staged: Vec<Staged>,
+    committed: bool,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write `contents` to a synced sibling temp file of `dest`
+    pub fn stage(&mut self, dest: &Path, contents: &[u8]) -> Result<()> {
+        let tmp = atomic::sibling_path(dest, STAGE_SUFFIX)?;
+        atomic::write_synced(&tmp, contents)?;
+
+        let original = if dest.exists() {
+            Some(fs::read(dest).with_context(|| format!("read {:?}", dest))?)
+        } else {
+            None
+        };
+        self.staged.push(Staged { dest: dest.to_path_buf(), tmp, original });
+        trace!(path = ?dest, bytes = contents.len(), "      💾 {:?} staged ({} bytes)", dest, contents.len());
+        Ok(())
+    }
+
+    /// Rename every staged file over its destination
+    ///
+    /// If any rename fails, destinations already swapped are restored from
+    /// their original contents before the error is returned.
+    pub fn commit(mut self) -> Result<()> {
+        for i in 0..self.staged.len() {
+            let entry = &self.staged[i];
+            if let Err(e) = fs::rename(&entry.tmp, &entry.dest) {
+                let failed = entry.dest.clone();
+                self.rollback(i);
+                return Err(e).with_context(|| format!("commit {:?} (rolled back)", failed));
+            }
+        }
+        self.committed = true;
+        for entry in &self.staged {
+            atomic::sync_parent_dir(&entry.dest)?;
+            trace!(path = ?entry.dest, "      💾 {:?} committed", entry.dest);
+        }
+        Ok(())
+    }
+
+    fn rollback(&self, swapped: usize) {
+        for entry in &self.staged[..swapped] {
+            let _ = match &entry.original {
+                Some(bytes) => atomic::write_atomic(&entry.dest, bytes),
+                None => fs::remove_file(&entry.dest).map_err(Into::into),
+            };
+        }
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        for entry in &self.staged {
+            let _ = fs::remove_file(&entry.tmp);
+        }
+    }
+}
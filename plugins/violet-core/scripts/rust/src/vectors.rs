@@ -0,0 +1,263 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Deterministic test vectors — fixed-salt, fixed-nonce v2/v3/v4 samples (feature `test-vectors`)
+//
+// The `violet-test-vectors` binary (and, in the same build, the
+// `test-vectors` subcommand, alias `gen-vectors`) writes the JSON the
+// Node/TypeScript implementation checks itself against byte for byte. The
+// binary has `required-features`, so `cargo build --release` skips it; the
+// checked-in fixture is regenerated from scripts/rust with
+//
+//   cargo run --features test-vectors --bin violet-test-vectors -- \
+//       --output ../../tests/fixtures/cipher-vectors.json
+//
+// and tests/test-cipher-vectors.cjs checks soul-cipher.js against it.
+//
+// Every salt, nonce and IV the cipher draws goes through `random_bytes`.
+// With this feature, `with_rng` swaps the source for the current thread, and
+// `test-vectors` runs the ordinary writers under a seeded one:
+//   draw = SHA-256("violet-test-vectors" ‖ seed u64 BE ‖ counter u64 BE) ‖ SHA-256(… counter + 1) ‖ …
+// truncated to the draw's length. Each sample starts the counter at 0; it
+// runs on across that sample's draws and every draw starts on a fresh block.
+// Vectors list their draws by name, in the order the writer takes them, so
+// another implementation can check its encryption byte for byte as well as
+// its decryption. Never build release binaries with this feature: the
+// override makes every salt predictable.
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use anyhow::{bail, Context, Result};
+use argon2::Params as KdfParams;
+use clap::{Args, Parser};
+use rand::RngCore;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::kdf::KdfArgs;
+use crate::keyring::KdfCosts;
+use crate::logging;
+use crate::{
+    decrypt_payload, derive_key_scrypt, encrypt_aes_cbc, v3_encrypt, v4_encrypt, write_atomic, Binding, LOCAL_SALT,
+    VERSION_V4,
+};
+
+const DOMAIN: &[u8] = b"violet-test-vectors";
+const FORMAT: u32 = 1;
+/// The fixed salt string v2 derives its scrypt key from
+const V2_SALT: &str = "violet-soul-salt";
+
+thread_local! {
+    static INJECTED: RefCell<Option<Box<dyn RngCore>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with every `random_bytes` draw on this thread taken from `rng`
+pub fn with_rng<T>(rng: Box<dyn RngCore>, f: impl FnOnce() -> T) -> T {
+    let previous = INJECTED.with(|slot| slot.borrow_mut().replace(rng));
+    let result = f();
+    INJECTED.with(|slot| *slot.borrow_mut() = previous);
+    result
+}
+
+/// Fill `buf` from the injected source; false when none is installed
+pub fn fill_injected(buf: &mut [u8]) -> bool {
+    INJECTED.with(|slot| match slot.borrow_mut().as_mut() {
+        Some(rng) => {
+            rng.fill_bytes(buf);
+            true
+        }
+        None => false,
+    })
+}
+
+/// SHA-256 counter-mode byte source; keeps a copy of each draw
+pub struct SeededRng {
+    seed: u64,
+    counter: u64,
+    draws: Rc<RefCell<Vec<Vec<u8>>>>,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, counter: 0, draws: Rc::default() }
+    }
+
+    /// Handle to the draws, readable after the RNG has been handed to `with_rng`
+    pub fn draws(&self) -> Rc<RefCell<Vec<Vec<u8>>>> {
+        Rc::clone(&self.draws)
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0u8; 4];
+        self.fill_bytes(&mut buf);
+        u32::from_be_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        self.fill_bytes(&mut buf);
+        u64::from_be_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(32) {
+            let block = Sha256::new()
+                .chain_update(DOMAIN)
+                .chain_update(self.seed.to_be_bytes())
+                .chain_update(self.counter.to_be_bytes())
+                .finalize();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+            self.counter += 1;
+        }
+        self.draws.borrow_mut().push(dest.to_vec());
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct VectorArgs {
+    /// Passphrase every sample is encrypted under
+    #[arg(long, default_value = "violet-test-vector-passphrase")]
+    passphrase: String,
+    /// Plaintext to encrypt (v2/v3 readers expect UTF-8 JSON)
+    #[arg(long, default_value = r#"{"violet":"test vector","layers":3}"#)]
+    plaintext: String,
+    /// Logical file name v4 binds the ciphertext to
+    #[arg(long, default_value = "rules-index.json")]
+    name: String,
+    /// Seed of the salt/nonce stream
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Formats to produce, comma-separated
+    #[arg(long, value_delimiter = ',', default_values_t = [2u8, 3, 4])]
+    versions: Vec<u8>,
+    #[command(flatten)]
+    kdf: KdfArgs,
+    /// Write the vectors here instead of stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+#[command(name = "violet-test-vectors", version, about = "Deterministic v2/v3/v4 test vectors for other implementations")]
+struct Cli {
+    #[command(flatten)]
+    args: VectorArgs,
+    #[command(flatten)]
+    log: logging::LogArgs,
+}
+
+#[derive(Serialize)]
+struct Draw {
+    name: &'static str,
+    hex: String,
+}
+
+#[derive(Serialize)]
+struct Vector {
+    version: u8,
+    passphrase: String,
+    /// scrypt salt string (v2/v3) or Argon2id salt label (v4)
+    salt_label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kdf: Option<KdfCosts>,
+    plaintext: String,
+    random_draws: Vec<Draw>,
+    ciphertext: String,
+}
+
+#[derive(Serialize)]
+struct VectorFile {
+    format: u32,
+    generator: String,
+    rng: &'static str,
+    seed: u64,
+    vectors: Vec<Vector>,
+}
+
+fn v2_encrypt(passphrase: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    Ok(encrypt_aes_cbc(&derive_key_scrypt(passphrase, V2_SALT)?, plaintext))
+}
+
+/// Encrypt one sample under a fresh seeded stream and prove it decrypts
+fn vector(args: &VectorArgs, version: u8, kdf: &KdfParams) -> Result<Vector> {
+    let passphrase = args.passphrase.as_bytes();
+    let plaintext = args.plaintext.as_bytes();
+    let binding = Binding::new(LOCAL_SALT, &args.name);
+    let (salt_label, draw_names): (&str, &[&'static str]) = match version {
+        2 => (V2_SALT, &["iv"]),
+        3 => (LOCAL_SALT, &["inner_iv", "outer_iv"]),
+        VERSION_V4 => (
+            LOCAL_SALT,
+            &["inner_salt", "inner_nonce", "middle_salt", "middle_nonce", "outer_salt", "outer_nonce"],
+        ),
+        other => bail!("no test vectors for v{} — choose from 2, 3, 4", other),
+    };
+
+    let rng = SeededRng::new(args.seed);
+    let draws = rng.draws();
+    let ciphertext = with_rng(Box::new(rng), || match version {
+        2 => v2_encrypt(passphrase, plaintext),
+        3 => v3_encrypt(passphrase, LOCAL_SALT, plaintext),
+        _ => v4_encrypt(passphrase, binding, plaintext, kdf),
+    })?;
+    let draws = draws.take();
+    if draws.len() != draw_names.len() {
+        bail!("v{} writer drew {} random values, expected {}", version, draws.len(), draw_names.len());
+    }
+
+    let (_, opened) = decrypt_payload(passphrase, binding, &ciphertext, kdf)
+        .with_context(|| format!("v{} vector does not decrypt", version))?;
+    if opened != plaintext {
+        bail!("v{} vector decrypts to different bytes", version);
+    }
+
+    Ok(Vector {
+        version,
+        passphrase: args.passphrase.clone(),
+        salt_label: salt_label.to_string(),
+        name: (version == VERSION_V4).then(|| args.name.clone()),
+        kdf: (version == VERSION_V4).then(|| KdfCosts::from(kdf)),
+        plaintext: args.plaintext.clone(),
+        random_draws: draw_names.iter().zip(draws).map(|(&name, bytes)| Draw { name, hex: hex::encode(bytes) }).collect(),
+        ciphertext: hex::encode(&ciphertext),
+    })
+}
+
+pub fn run(args: VectorArgs) -> Result<()> {
+    if args.output.is_none() {
+        logging::claim_stdout();
+    }
+    let kdf = args.kdf.params()?;
+    let vectors = args.versions.iter().map(|&version| vector(&args, version, &kdf)).collect::<Result<Vec<_>>>()?;
+    let file = VectorFile {
+        format: FORMAT,
+        generator: format!("violet-cipher {}", env!("CARGO_PKG_VERSION")),
+        rng: "SHA-256(\"violet-test-vectors\" ‖ seed u64 BE ‖ counter u64 BE), each draw on fresh blocks",
+        seed: args.seed,
+        vectors,
+    };
+    let json = serde_json::to_string_pretty(&file)?;
+    match &args.output {
+        Some(path) => {
+            write_atomic(path, format!("{}\n", json).as_bytes())?;
+            logging::note(format_args!("🧪 {} vector(s) written to {:?} — each decrypts back to its plaintext", file.vectors.len(), path));
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+/// `violet-test-vectors`: parse its own command line, then `run`
+pub fn main() -> Result<()> {
+    let cli = Cli::parse();
+    logging::init(&cli.log);
+    run(cli.args)
+}
@@ -0,0 +1,181 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Verification tokens — key-family fingerprint + ciphertext digest sidecars
+//
+// A `.vtok` sidecar lets `verify --no-key` confirm a ciphertext is intact and
+// was produced under the expected passphrase without holding that passphrase.
+// The key ID comes from its own Argon2id derivation, salted per data
+// directory: keyring.json keeps a random salt and the costs IDs are derived
+// with, drawn by the first command that records a key there (`ensure_salt`,
+// at that command's --kdf-* costs). No table computed in advance applies to
+// an ID, and guessing the passphrase from one costs what guessing it from a
+// file does; IDs only compare within one data directory. Outside a data
+// directory (the C API, WebAssembly) there is no salt and no key ID. The
+// same derivation, expanded with HKDF, keys the plaintext digest v5 headers
+// record (`digest_key`), so a header confirms a guessed plaintext only to
+// someone holding the passphrase.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{bail, Context, Result};
+use argon2::Params as KdfParams;
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::keyring::{KdfCosts, KeyIdSalt, Keyring};
+use crate::random_bytes;
+use crate::secret::SecretKey;
+
+pub const TOKEN_SUFFIX: &str = ".vtok";
+const TOKEN_VERSION: u32 = 1;
+pub const KEY_ID_LEN: usize = 8;
+const KEY_ID_SALT_LEN: usize = 16;
+const DIGEST_KEY_INFO: &[u8] = b"violet-plaintext-digest-2026";
+
+#[derive(Serialize, Deserialize)]
+pub struct VerifyToken {
+    pub version: u32,
+    /// Hex fingerprint of the passphrase family (not usable for decryption)
+    pub key_id: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// Where key IDs are salted this run
+struct SaltContext {
+    data_dir: PathBuf,
+    /// Whether a salt drawn here may be saved to the keyring (dry runs and readers keep it in memory)
+    writes: bool,
+    /// Salt and costs, once read from the keyring or drawn
+    salt: Option<(Vec<u8>, KdfParams)>,
+}
+
+static CONTEXT: Mutex<Option<SaltContext>> = Mutex::new(None);
+
+/// Salt key IDs with the keyring of `data_dir` for the rest of the run
+pub fn begin(data_dir: &Path, writes: bool) {
+    let context = SaltContext { data_dir: data_dir.to_path_buf(), writes, salt: None };
+    *CONTEXT.lock().unwrap_or_else(|e| e.into_inner()) = Some(context);
+}
+
+/// The run's key-ID salt, read from the keyring while it has none yet (another
+/// process, such as the agent's client, may draw it meanwhile)
+fn salt(context: &mut SaltContext) -> Result<Option<(Vec<u8>, KdfParams)>> {
+    if context.salt.is_none() {
+        if let Some(recorded) = Keyring::load(&context.data_dir)?.key_id_salt {
+            let salt = hex::decode(&recorded.salt).context("keyring key-ID salt is not hex")?;
+            context.salt = Some((salt, recorded.kdf.params()?));
+        }
+    }
+    Ok(context.salt.clone())
+}
+
+/// Give the data directory a key-ID salt, derived with `kdf`, unless its keyring has one
+///
+/// A writing run saves it; a dry run or a command that only reads keeps it for the run.
+pub fn ensure_salt(kdf: &KdfParams) -> Result<()> {
+    let mut guard = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(context) = guard.as_mut() else {
+        return Ok(());
+    };
+    if salt(context)?.is_some() {
+        return Ok(());
+    }
+    let salt = random_bytes::<KEY_ID_SALT_LEN>().to_vec();
+    if context.writes {
+        let mut keyring = Keyring::load(&context.data_dir)?;
+        if !keyring.keys.is_empty() {
+            warn!(
+                "  ⚠️  {:?} lists key IDs from before salting; they no longer match any passphrase and are dropped",
+                Keyring::path(&context.data_dir)
+            );
+            keyring.keys.clear();
+        }
+        keyring.key_id_salt = Some(KeyIdSalt { salt: hex::encode(&salt), kdf: KdfCosts::from(kdf) });
+        keyring.save(&context.data_dir)?;
+    }
+    context.salt = Some((salt, kdf.clone()));
+    Ok(())
+}
+
+/// Argon2id of `passphrase` under the run's key-ID salt; cached, so key ID and
+/// digest key cost one derivation
+fn salted_key(passphrase: &[u8]) -> Result<Option<SecretKey>> {
+    let salt = {
+        let mut guard = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+        match guard.as_mut() {
+            Some(context) => salt(context)?,
+            None => None,
+        }
+    };
+    salt.map(|(salt, kdf)| crate::derive_key_argon2(passphrase, &salt, &kdf)).transpose()
+}
+
+/// Fingerprint a passphrase; identical for every file encrypted with it in the
+/// data directory. None outside one, or before its first key is recorded
+pub fn salted_key_id(passphrase: &[u8]) -> Result<Option<String>> {
+    Ok(salted_key(passphrase)?.map(|full| hex::encode(&full[..KEY_ID_LEN])))
+}
+
+/// `salted_key_id`, for callers that need one
+pub fn derive_key_id(passphrase: &[u8]) -> Result<String> {
+    salted_key_id(passphrase)?.context(
+        "no key-ID salt here yet: key IDs are salted per data directory, by its first encrypt-local or keyring add",
+    )
+}
+
+/// Key of the plaintext HMAC in v5 headers; None where there is no key ID
+pub fn digest_key(passphrase: &[u8]) -> Result<Option<SecretKey>> {
+    let Some(full) = salted_key(passphrase)? else {
+        return Ok(None);
+    };
+    let mut key = SecretKey::zeroed();
+    Hkdf::<Sha256>::new(None, &*full)
+        .expand(DIGEST_KEY_INFO, &mut *key)
+        .map_err(|e| anyhow::anyhow!("HKDF expand: {}", e))?;
+    Ok(Some(key))
+}
+
+pub fn token_path(enc_path: &Path) -> PathBuf {
+    let mut name = enc_path.as_os_str().to_os_string();
+    name.push(TOKEN_SUFFIX);
+    PathBuf::from(name)
+}
+
+impl VerifyToken {
+    pub fn for_ciphertext(key_id: &str, ciphertext: &[u8]) -> Self {
+        Self {
+            version: TOKEN_VERSION,
+            key_id: key_id.to_string(),
+            sha256: hex::encode(Sha256::digest(ciphertext)),
+            size: ciphertext.len() as u64,
+        }
+    }
+
+    pub fn write(&self, enc_path: &Path) -> Result<()> {
+        let path = token_path(enc_path);
+        let json = serde_json::to_string_pretty(self)?;
+        crate::atomic::write_atomic(&path, json.as_bytes())
+    }
+
+    /// Load the sidecar for `enc_path`, if there is one
+    pub fn read(enc_path: &Path) -> Result<Option<Self>> {
+        let path = token_path(enc_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&path).with_context(|| format!("read {:?}", path))?;
+        let token: Self = serde_json::from_str(&json).with_context(|| format!("parse {:?}", path))?;
+        if token.version != TOKEN_VERSION {
+            bail!("unsupported verification token version {}", token.version);
+        }
+        Ok(Some(token))
+    }
+
+    /// Whether `ciphertext` is byte-identical to what the token recorded
+    pub fn matches(&self, ciphertext: &[u8]) -> bool {
+        self.size == ciphertext.len() as u64 && self.sha256 == hex::encode(Sha256::digest(ciphertext))
+    }
+}
@@ -0,0 +1,86 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Browser bindings — `auto_decrypt` (bytes or text) and `v4_encrypt` over wasm-bindgen (`--features wasm`)
+//
+// The plugin UI's viewer loads this build to open `.enc` files client-side
+// instead of spawning the native binary. Everything goes in and out as
+// bytes and strings: the page reads the file, nothing here touches the
+// filesystem, the agent socket or the terminal. Build with
+//   cargo build --release --lib --target wasm32-unknown-unknown --no-default-features --features wasm
+//   wasm-bindgen --target web target/wasm32-unknown-unknown/release/violet_cipher.wasm --out-dir <ui dir>
+//
+// No pepper, `--fido2` or `--keyfile` is available to the browser, so v5
+// files sealed with one of those fail the HMAC check like a wrong binary would.
+use argon2::Params as KdfParams;
+use wasm_bindgen::prelude::*;
+
+use crate::{salt, Binding};
+
+/// Argon2id costs per layer, each defaulting like the CLI's `--kdf-*` flags.
+/// Files with a VSC! preamble carry their own and ignore these
+fn kdf_params(memory: Option<u32>, iterations: Option<u32>, parallelism: Option<u32>) -> Result<KdfParams, JsError> {
+    crate::kdf::bounded_params(
+        memory.unwrap_or(KdfParams::DEFAULT_M_COST),
+        iterations.unwrap_or(KdfParams::DEFAULT_T_COST),
+        parallelism.unwrap_or(KdfParams::DEFAULT_P_COST),
+    )
+    .map_err(js_error)
+}
+
+fn js_error(err: anyhow::Error) -> JsError {
+    JsError::new(&format!("{:#}", err))
+}
+
+/// Decrypt any supported version to its plaintext bytes (a Uint8Array), e.g.
+/// `autoDecrypt(passphrase, "git", "rules-index.json", bytes)`; the salt
+/// label is "local" (`<name>.enc`), "git" (`<name>.git.enc`) or any other domain
+#[wasm_bindgen(js_name = autoDecrypt)]
+pub fn auto_decrypt(
+    passphrase: &str,
+    salt_label: &str,
+    name: &str,
+    data: &[u8],
+    kdf_memory: Option<u32>,
+    kdf_iterations: Option<u32>,
+    kdf_parallelism: Option<u32>,
+) -> Result<Vec<u8>, JsError> {
+    let kdf = kdf_params(kdf_memory, kdf_iterations, kdf_parallelism)?;
+    let salt = salt::salt_for(salt_label).map_err(js_error)?;
+    let binding = Binding::new(&salt, name);
+    let plain = crate::auto_decrypt(passphrase.as_bytes(), binding, data, &kdf).map_err(js_error)?;
+    Ok(plain.to_vec())
+}
+
+/// `autoDecrypt` as a string; binary content types are refused
+#[wasm_bindgen(js_name = autoDecryptText)]
+pub fn auto_decrypt_text(
+    passphrase: &str,
+    salt_label: &str,
+    name: &str,
+    data: &[u8],
+    kdf_memory: Option<u32>,
+    kdf_iterations: Option<u32>,
+    kdf_parallelism: Option<u32>,
+) -> Result<String, JsError> {
+    let kdf = kdf_params(kdf_memory, kdf_iterations, kdf_parallelism)?;
+    let salt = salt::salt_for(salt_label).map_err(js_error)?;
+    let binding = Binding::new(&salt, name);
+    let plain = crate::auto_decrypt_text(passphrase.as_bytes(), binding, data, &kdf).map_err(js_error)?;
+    Ok(plain.as_str().to_owned())
+}
+
+/// Encrypt to a framed v4 file, which every build of the CLI opens
+#[wasm_bindgen(js_name = v4Encrypt)]
+pub fn v4_encrypt(
+    passphrase: &str,
+    salt_label: &str,
+    name: &str,
+    plaintext: &[u8],
+    kdf_memory: Option<u32>,
+    kdf_iterations: Option<u32>,
+    kdf_parallelism: Option<u32>,
+) -> Result<Vec<u8>, JsError> {
+    let kdf = kdf_params(kdf_memory, kdf_iterations, kdf_parallelism)?;
+    let salt = salt::salt_for(salt_label).map_err(js_error)?;
+    let binding = Binding::new(&salt, name);
+    crate::v4_encrypt(passphrase.as_bytes(), binding, plaintext, &kdf).map_err(js_error)
+}
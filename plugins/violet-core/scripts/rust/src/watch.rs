@@ -0,0 +1,215 @@
+// Authors: Joysusy & Violet Klaudia 💖
+// Watch mode — re-encrypt target JSON files to .enc whenever they are saved
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Context, Result};
+use argon2::Params as KdfParams;
+use notify::{EventKind, RecursiveMode, Watcher};
+use sha2::{Digest, Sha256};
+use tracing::{error, info, warn};
+
+use crate::header::Header;
+use crate::logging;
+use crate::monitor::{self, Monitor, Outcome};
+use crate::{file_mtime, seal_local, write_local_enc, Sealer, LOCAL_SALT, TARGET_FILES};
+
+/// Longest wait on the watcher before checking pending saves again
+const IDLE_TICK: Duration = Duration::from_secs(1);
+
+/// Everything needed to turn a target JSON into its .enc
+struct Encryptor<'a> {
+    sealer: &'a Sealer,
+    kdf: &'a KdfParams,
+    data_dir: &'a Path,
+    /// Content type, compression and suite every file is sealed with
+    header: &'a Header,
+    key_id: Option<&'a str>,
+    backup: Option<usize>,
+    monitor: Monitor,
+}
+
+impl Encryptor<'_> {
+    fn encrypt(&self, name: &str, plaintext: &[u8], status: &mut Status) -> Result<()> {
+        let header = self.header.with_plaintext_mtime(file_mtime(&self.data_dir.join(name)));
+        let result = seal_local(self.sealer, LOCAL_SALT, name, plaintext, &header, self.kdf).and_then(|encrypted| {
+            write_local_enc(&self.data_dir.join(format!("{}.enc", name)), &encrypted, self.key_id, self.backup)?;
+            Ok(encrypted.len())
+        });
+        match &result {
+            Ok(len) => self.monitor.record(name, Outcome::Encrypted, format!("{} bytes", len)),
+            Err(e) => self.monitor.record(name, Outcome::Failed, format!("{:#}", e)),
+        }
+        let len = result?;
+        status.encrypted += 1;
+        status.last = Some(format!("{} at {}", name, clock()));
+        status.above();
+        info!("  🔐 {} → {}.enc ({} bytes)", name, name, len);
+        Ok(())
+    }
+}
+
+struct Status {
+    encrypted: usize,
+    last: Option<String>,
+    dirty: bool,
+}
+
+impl Status {
+    /// Redraw the single status line in place, if anything changed since the
+    /// last draw; only among log lines for a terminal, never in JSON, `--quiet` or `--plain` output
+    fn draw(&mut self, data_dir: &Path) {
+        if !self.dirty || !logging::status_line() {
+            return;
+        }
+        self.dirty = false;
+        let last = self.last.as_deref().unwrap_or("nothing yet");
+        print!("\r\x1b[2K👀 Watching {:?} — {} encrypted, last: {}", data_dir, self.encrypted, last);
+        let _ = std::io::stdout().flush();
+    }
+
+    /// Clear the status line so a log line can take its place; the next draw puts it back below
+    fn above(&mut self) {
+        if logging::status_line() {
+            print!("\r\x1b[2K");
+        }
+        self.dirty = true;
+    }
+}
+
+/// Watch `data_dir` and encrypt each target JSON once it has been quiet for `debounce`
+///
+/// Runs until interrupted. Saves that leave the file unchanged or not valid
+/// JSON (e.g. caught mid-write) are skipped. With a `socket`, `status --daemon`
+/// can ask how it is doing.
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    sealer: &Sealer,
+    kdf: &KdfParams,
+    data_dir: &Path,
+    header: &Header,
+    key_id: Option<&str>,
+    backup: Option<usize>,
+    debounce: Duration,
+    socket: Option<&Path>,
+) -> Result<()> {
+    let monitor = Monitor::new(data_dir, monitor::fingerprints(sealer)?, TARGET_FILES);
+    if let Some(socket) = socket {
+        monitor.serve(socket)?;
+        info!("📡 Status on {:?} (violet-cipher status --daemon)", socket);
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("start filesystem watcher")?;
+    watcher
+        .watch(data_dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("watch {:?}", data_dir))?;
+
+    let encryptor = Encryptor { sealer, kdf, data_dir, header, key_id, backup, monitor: monitor.clone() };
+    let mut status = Status { encrypted: 0, last: None, dirty: true };
+    let mut digests: HashMap<&str, [u8; 32]> = HashMap::new();
+
+    // Catch up on edits made while nothing was watching
+    for &name in TARGET_FILES {
+        let json_path = data_dir.join(name);
+        let Ok(plaintext) = fs::read(&json_path) else {
+            continue;
+        };
+        if is_stale(&json_path, &data_dir.join(format!("{}.enc", name))) {
+            encryptor.encrypt(name, &plaintext, &mut status)?;
+        } else {
+            status.above();
+            info!("  ✅ {}.enc is up to date", name);
+        }
+        digests.insert(name, Sha256::digest(&plaintext).into());
+    }
+
+    let mut pending: HashMap<&str, Instant> = HashMap::new();
+    status.draw(data_dir);
+    loop {
+        let wait = pending
+            .values()
+            .map(|changed| debounce.saturating_sub(changed.elapsed()))
+            .min()
+            .unwrap_or(IDLE_TICK);
+
+        match rx.recv_timeout(wait) {
+            Ok(Ok(event)) => {
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    for path in &event.paths {
+                        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                        if let Some(&name) = TARGET_FILES.iter().find(|&&n| n == file_name) {
+                            pending.insert(name, Instant::now());
+                        }
+                    }
+                }
+            }
+            Ok(Err(e)) => {
+                monitor.record("(watcher)", Outcome::Failed, e.to_string());
+                status.above();
+                warn!("  ⚠️  Watcher error: {}", e);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        let ready: Vec<&str> = pending
+            .iter()
+            .filter(|(_, changed)| changed.elapsed() >= debounce)
+            .map(|(&name, _)| name)
+            .collect();
+        for name in ready {
+            pending.remove(name);
+            let Ok(plaintext) = fs::read(data_dir.join(name)) else {
+                continue;
+            };
+            let digest: [u8; 32] = Sha256::digest(&plaintext).into();
+            if digests.get(name) == Some(&digest) {
+                continue;
+            }
+            if serde_json::from_slice::<serde_json::Value>(&plaintext).is_err() {
+                monitor.record(name, Outcome::Skipped, "not valid JSON yet".to_string());
+                status.above();
+                info!("  ⏳ {} is not valid JSON yet — waiting for the next save", name);
+                continue;
+            }
+            match encryptor.encrypt(name, &plaintext, &mut status) {
+                Ok(()) => {
+                    digests.insert(name, digest);
+                }
+                Err(e) => {
+                    status.above();
+                    error!("  ❌ {}: {:#}", name, e);
+                }
+            }
+        }
+        status.draw(data_dir);
+    }
+    if logging::status_line() {
+        println!();
+    }
+    if let Some(socket) = socket {
+        let _ = fs::remove_file(socket);
+    }
+    Ok(())
+}
+
+/// The JSON was modified after its .enc was written (or there is no .enc)
+fn is_stale(json_path: &Path, enc_path: &Path) -> bool {
+    let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
+    !enc_path.exists() || modified(json_path) > modified(enc_path)
+}
+
+/// `HH:MM:SS` UTC, for the status line
+fn clock() -> String {
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+        % 86_400;
+    format!("{:02}:{:02}:{:02}Z", secs / 3_600, secs % 3_600 / 60, secs % 60)
+}
@@ -0,0 +1,103 @@
+#!/usr/bin/env node
+// Authors: Joysusy & Violet Klaudia 💖
+// VioletCore — Cipher Test Vectors v0.1.0
+//
+// Checks soul-cipher.js against fixtures/cipher-vectors.json, written by the
+// Rust cipher's `violet-test-vectors` binary (see scripts/rust/src/vectors.rs
+// for how to regenerate it). v2/v3 must decrypt, and re-encrypt byte for byte
+// from the listed IVs; v4 needs Argon2id, which soul-cipher.js does not
+// implement, so only its presence is checked here.
+
+const fs = require('fs');
+const path = require('path');
+const crypto = require('crypto');
+const { multiLayerDecrypt, LOCAL_SALT } = require('../scripts/soul-cipher');
+
+const OUTER_SALT = 'violet-outer-shell-2026';
+const fixture = JSON.parse(fs.readFileSync(path.join(__dirname, 'fixtures', 'cipher-vectors.json'), 'utf-8'));
+
+let passed = 0;
+let failed = 0;
+
+function test(name, fn) {
+  try {
+    fn();
+    console.log(`✅ ${name}`);
+    passed++;
+  } catch (err) {
+    console.error(`❌ ${name}`);
+    console.error(`   ${err.message}`);
+    failed++;
+  }
+}
+
+function assert(condition, message) {
+  if (!condition) {
+    throw new Error(message || 'Assertion failed');
+  }
+}
+
+function cbc(key, iv, plaintext) {
+  const cipher = crypto.createCipheriv('aes-256-cbc', key, iv);
+  return Buffer.concat([iv, cipher.update(plaintext), cipher.final()]);
+}
+
+function draw(vector, name) {
+  const found = vector.random_draws.find((d) => d.name === name);
+  assert(found, `v${vector.version} vector lists no ${name} draw`);
+  return Buffer.from(found.hex, 'hex');
+}
+
+function vectorsOf(version) {
+  const vectors = fixture.vectors.filter((v) => v.version === version);
+  assert(vectors.length > 0, `fixture holds no v${version} vector`);
+  return vectors;
+}
+
+console.log('\n🧪 VioletCore Cipher Test Vectors\n');
+
+test('fixture format is understood', () => {
+  assert(fixture.format === 1, `unknown fixture format ${fixture.format}`);
+});
+
+test('v2 vectors decrypt and re-encrypt byte for byte', () => {
+  for (const v of vectorsOf(2)) {
+    const key = crypto.scryptSync(v.passphrase, v.salt_label, 32);
+    const ciphertext = Buffer.from(v.ciphertext, 'hex');
+    const decipher = crypto.createDecipheriv('aes-256-cbc', key, ciphertext.subarray(0, 16));
+    const plain = Buffer.concat([decipher.update(ciphertext.subarray(16)), decipher.final()]).toString('utf-8');
+    assert(plain === v.plaintext, 'v2 plaintext differs');
+    assert(cbc(key, draw(v, 'iv'), Buffer.from(v.plaintext, 'utf-8')).equals(ciphertext), 'v2 ciphertext differs');
+  }
+});
+
+test('v3 vectors decrypt with soul-cipher.js', () => {
+  for (const v of vectorsOf(3)) {
+    assert(v.salt_label === LOCAL_SALT, `v3 vector uses salt ${v.salt_label}`);
+    const plain = multiLayerDecrypt(v.passphrase, v.salt_label, Buffer.from(v.ciphertext, 'hex'));
+    assert(plain === v.plaintext, 'v3 plaintext differs');
+  }
+});
+
+test('v3 vectors re-encrypt byte for byte', () => {
+  for (const v of vectorsOf(3)) {
+    const innerKey = crypto.scryptSync(v.passphrase, v.salt_label, 32);
+    const outerKey = crypto.scryptSync(v.passphrase + '-outer', OUTER_SALT, 32);
+    const inner = cbc(innerKey, draw(v, 'inner_iv'), Buffer.from(v.plaintext, 'utf-8'));
+    const outer = cbc(outerKey, draw(v, 'outer_iv'), inner);
+    assert(outer.equals(Buffer.from(v.ciphertext, 'hex')), 'v3 ciphertext differs');
+  }
+});
+
+test('v4 vectors are present for the TypeScript port', () => {
+  for (const v of vectorsOf(4)) {
+    assert(v.kdf && v.name, 'v4 vector lacks its KDF costs or bound name');
+    assert(v.random_draws.length === 6, `v4 vector lists ${v.random_draws.length} draws, expected 6`);
+  }
+});
+
+console.log(`\n📊 Results: ${passed} passed, ${failed} failed\n`);
+
+if (failed > 0) {
+  process.exit(1);
+}